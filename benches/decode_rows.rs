@@ -0,0 +1,132 @@
+//! Compares decoding many small textures row by row with decoding them block by block.
+//!
+//! Run with `cargo bench --bench decode_rows`. Without the `--bench` argument, for example under
+//! `cargo test --all-targets`, every case only runs once.
+
+use std::{hint::black_box, time::Instant};
+
+use block_compression::decode::{
+    decode_block_bc1, decode_block_bc3, decode_block_bc4, decode_block_bc5, decode_block_bc7,
+    decode_row_bc1, decode_row_bc3, decode_row_bc4, decode_row_bc5, decode_row_bc7,
+};
+
+/// The textures are 16x16 pixels large, like the icons and glyphs of an atlas.
+const TEXTURE_SIZE: usize = 16;
+const TEXTURE_COUNT: usize = 4096;
+
+type DecodeFn = fn(&[u8], &mut [u8], usize);
+
+struct Case {
+    name: &'static str,
+    block_size: usize,
+    block_width: usize,
+    decode_row: DecodeFn,
+    decode_block: DecodeFn,
+}
+
+const CASES: [Case; 5] = [
+    Case {
+        name: "BC1",
+        block_size: 8,
+        block_width: 16,
+        decode_row: decode_row_bc1,
+        decode_block: decode_block_bc1,
+    },
+    Case {
+        name: "BC3",
+        block_size: 16,
+        block_width: 16,
+        decode_row: decode_row_bc3,
+        decode_block: decode_block_bc3,
+    },
+    Case {
+        name: "BC4",
+        block_size: 8,
+        block_width: 4,
+        decode_row: decode_row_bc4,
+        decode_block: decode_block_bc4,
+    },
+    Case {
+        name: "BC5",
+        block_size: 16,
+        block_width: 8,
+        decode_row: decode_row_bc5,
+        decode_block: decode_block_bc5,
+    },
+    Case {
+        name: "BC7",
+        block_size: 16,
+        block_width: 16,
+        decode_row: decode_row_bc7,
+        decode_block: decode_block_bc7,
+    },
+];
+
+/// Returns the fastest of `runs` runs in milliseconds.
+fn measure(runs: usize, mut run: impl FnMut()) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed().as_secs_f64() * 1000.0
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let runs = if std::env::args().any(|arg| arg == "--bench") {
+        20
+    } else {
+        1
+    };
+
+    let blocks_x = TEXTURE_SIZE / 4;
+    println!("Decoding {TEXTURE_COUNT} textures of {TEXTURE_SIZE}x{TEXTURE_SIZE} pixels");
+
+    for case in CASES {
+        let row_size = blocks_x * case.block_size;
+        let texture_size = row_size * blocks_x;
+        let compressed: Vec<u8> = (0..TEXTURE_COUNT * texture_size)
+            .map(|index| (index as u32).wrapping_mul(2654435761).rotate_right(13) as u8)
+            .collect();
+
+        let pitch = blocks_x * case.block_width;
+        let mut decoded = vec![0; pitch * TEXTURE_SIZE];
+
+        let blocks_ms = measure(runs, || {
+            for texture in compressed.chunks_exact(texture_size) {
+                for (row, out_rows) in texture
+                    .chunks_exact(row_size)
+                    .zip(decoded.chunks_exact_mut(pitch * 4))
+                {
+                    for (index, block) in row.chunks_exact(case.block_size).enumerate() {
+                        (case.decode_block)(
+                            black_box(block),
+                            &mut out_rows[index * case.block_width..],
+                            pitch,
+                        );
+                    }
+                }
+                black_box(&mut decoded);
+            }
+        });
+
+        let rows_ms = measure(runs, || {
+            for texture in compressed.chunks_exact(texture_size) {
+                for (row, out_rows) in texture
+                    .chunks_exact(row_size)
+                    .zip(decoded.chunks_exact_mut(pitch * 4))
+                {
+                    (case.decode_row)(black_box(row), out_rows, pitch);
+                }
+                black_box(&mut decoded);
+            }
+        });
+
+        println!(
+            "{}: blocks {blocks_ms:.3} ms, rows {rows_ms:.3} ms, speedup {:.2}x",
+            case.name,
+            blocks_ms / rows_ms
+        );
+    }
+}
@@ -1,24 +1,73 @@
-use std::{fs::File, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
 
 use block_compression::{
-    half::f16, BC6HSettings, BC7Settings, CompressionVariant, GpuBlockCompressor,
+    color::rgba8_srgb_to_linear_f16,
+    dds::{write_dds, write_dds_legacy, DdsDescriptor},
+    half::f16,
+    BC6HSettings, BC7Settings, CompressionVariant, GpuBlockCompressor, TaskOptions,
 };
 use bytemuck::cast_slice;
-use ddsfile::{AlphaMode, D3D10ResourceDimension, Dds, DxgiFormat, NewDxgiParams};
 use image::ImageReader;
 use pollster::block_on;
 use wgpu::{
     util::{DeviceExt, TextureDataOrder},
     BackendOptions, Backends, Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
     ComputePassDescriptor, ComputePassTimestampWrites, Device, DeviceDescriptor,
-    Dx12BackendOptions, Dx12Compiler, Error, Extent3d, Features, GlBackendOptions,
-    Gles3MinorVersion, Instance, InstanceDescriptor, InstanceFlags, Maintain, MapMode, MemoryHints,
-    PowerPreference, QueryType, Queue, Texture, TextureDescriptor, TextureDimension, TextureFormat,
-    TextureUsages, TextureViewDescriptor,
+    Dx12BackendOptions, Dx12Compiler, Extent3d, Features, GlBackendOptions, Gles3MinorVersion,
+    Instance, InstanceDescriptor, InstanceFlags, Maintain, MapMode, MemoryHints, Origin3d,
+    PowerPreference, QueryType, Queue, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor,
 };
 
+/// Where the compressor reads its input from.
+enum Input {
+    /// A single image file, which is written as a DDS file next to it.
+    File(String),
+    /// Raw frames from stdin, which are written to stdout.
+    Stdin(StreamOptions),
+}
+
+/// The layout of the raw frames read from stdin.
+struct StreamOptions {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    dds: bool,
+    legacy_dds: bool,
+}
+
+/// The pixel format of the raw frames read from stdin.
+#[derive(Copy, Clone)]
+enum PixelFormat {
+    Rgba8,
+    Rgba16Float,
+}
+
+impl PixelFormat {
+    fn bytes_per_pixel(self) -> u32 {
+        match self {
+            PixelFormat::Rgba8 => 4,
+            PixelFormat::Rgba16Float => 8,
+        }
+    }
+
+    fn texture_format(self) -> TextureFormat {
+        match self {
+            PixelFormat::Rgba8 => TextureFormat::Rgba8Unorm,
+            PixelFormat::Rgba16Float => TextureFormat::Rgba16Float,
+        }
+    }
+}
+
 fn main() {
-    let (variant, file_name) = match parse_args() {
+    let (variant, legacy_dds, input) = match parse_args() {
         Some(args) => args,
         None => return,
     };
@@ -26,6 +75,14 @@ fn main() {
     let (device, queue) = create_resources();
     let mut compressor: GpuBlockCompressor = GpuBlockCompressor::new(device.clone(), queue.clone());
 
+    let file_name = match input {
+        Input::File(file_name) => file_name,
+        Input::Stdin(options) => {
+            stream_frames(&mut compressor, &device, &queue, variant, &options);
+            return;
+        }
+    };
+
     let start = Instant::now();
 
     let texture = read_image_and_create_texture(&device, &queue, &file_name, variant);
@@ -54,6 +111,7 @@ fn main() {
         &blocks_buffer,
         None,
         None,
+        TaskOptions::default(),
     );
 
     compress(&mut compressor, &device, &queue);
@@ -70,7 +128,7 @@ fn main() {
 
     let start = Instant::now();
 
-    write_dds_file(&file_name, variant, width, height, block_data);
+    write_dds_file(&file_name, variant, legacy_dds, width, height, block_data);
 
     let duration = start.elapsed();
     println!(
@@ -111,10 +169,10 @@ fn create_resources() -> (Arc<Device>, Arc<Queue>) {
         None,
     ))
     .expect("Failed to create device");
-    device.on_uncaptured_error(Box::new(error_handler));
 
+    // Status messages go to stderr, since stdout may carry the compressed frames.
     let info = adapter.get_info();
-    println!("Using backend: {:?}", info.backend);
+    eprintln!("Using backend: {:?}", info.backend);
 
     (Arc::new(device), Arc::new(queue))
 }
@@ -135,10 +193,8 @@ fn read_image_and_create_texture(
     let height = rgba_image.height();
 
     if matches!(variant, CompressionVariant::BC6H(..)) {
-        let rgba_f16_data: Vec<u8> = rgba_image
-            .iter()
-            .flat_map(|color| f16::from_f64(srgb_to_linear(*color)).to_le_bytes())
-            .collect();
+        let mut rgba_f16_data = vec![f16::ZERO; rgba_image.len()];
+        rgba8_srgb_to_linear_f16(&rgba_image, &mut rgba_f16_data);
 
         device.create_texture_with_data(
             queue,
@@ -157,7 +213,7 @@ fn read_image_and_create_texture(
                 view_formats: &[],
             },
             TextureDataOrder::LayerMajor,
-            rgba_f16_data.as_slice(),
+            cast_slice(&rgba_f16_data),
         )
     } else {
         device.create_texture_with_data(
@@ -182,16 +238,6 @@ fn read_image_and_create_texture(
     }
 }
 
-#[inline]
-pub fn srgb_to_linear(srgb: u8) -> f64 {
-    let v = (srgb as f64) / 255.0;
-    if v <= 0.04045 {
-        v / 12.92
-    } else {
-        ((v + 0.055) / 1.055).powf(2.4)
-    }
-}
-
 fn compress(compressor: &mut GpuBlockCompressor, device: &Device, queue: &Queue) {
     let timestamp_query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
         label: Some("timestamp query set"),
@@ -217,18 +263,20 @@ fn compress(compressor: &mut GpuBlockCompressor, device: &Device, queue: &Queue)
         label: Some("command encoder"),
     });
 
-    {
-        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-            label: Some("compute pass"),
-            timestamp_writes: Some(ComputePassTimestampWrites {
-                query_set: &timestamp_query_set,
-                beginning_of_pass_write_index: Some(0),
-                end_of_pass_write_index: Some(1),
-            }),
-        });
-
-        compressor.compress(&mut pass);
-    }
+    compressor
+        .compress_validated(|compressor| {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("compute pass"),
+                timestamp_writes: Some(ComputePassTimestampWrites {
+                    query_set: &timestamp_query_set,
+                    beginning_of_pass_write_index: Some(0),
+                    end_of_pass_write_index: Some(1),
+                }),
+            });
+
+            compressor.compress(&mut pass)
+        })
+        .unwrap_or_else(|error| panic!("couldn't compress: {error}"));
 
     encoder.resolve_query_set(&timestamp_query_set, 0..2, &timestamp_resolve_buffer, 0);
 
@@ -269,6 +317,139 @@ fn compress(compressor: &mut GpuBlockCompressor, device: &Device, queue: &Queue)
     }
 }
 
+/// Compresses raw frames from stdin until it is closed and writes the blocks of every frame to
+/// stdout, either raw or as a DDS file per frame.
+fn stream_frames(
+    compressor: &mut GpuBlockCompressor,
+    device: &Device,
+    queue: &Queue,
+    variant: CompressionVariant,
+    options: &StreamOptions,
+) {
+    let StreamOptions {
+        width,
+        height,
+        format,
+        dds,
+        legacy_dds,
+    } = *options;
+
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("stdin frame"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: format.texture_format(),
+        usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+    let blocks_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("blocks buffer"),
+        size: variant.blocks_byte_size(width, height) as _,
+        usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
+    let bytes_per_row = width * format.bytes_per_pixel();
+    let mut frame = vec![0; (bytes_per_row * height) as usize];
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let mut frame_count = 0;
+
+    let start = Instant::now();
+
+    while read_frame(&mut stdin, &mut frame).expect("failed to read frame from stdin") {
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &frame,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        compressor.add_compression_task(
+            variant,
+            &texture_view,
+            width,
+            height,
+            &blocks_buffer,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("frame command encoder"),
+        });
+        compressor
+            .compress_validated(|compressor| compressor.compress_split(&mut encoder))
+            .unwrap_or_else(|error| panic!("couldn't compress: {error}"));
+        queue.submit([encoder.finish()]);
+
+        let block_data = download_blocks_data(device, queue, blocks_buffer.clone());
+
+        if dds {
+            let descriptor = DdsDescriptor {
+                srgb: true,
+                ..DdsDescriptor::new(variant, width, height)
+            };
+            write_dds_with_header(&mut stdout, &descriptor, &block_data, legacy_dds)
+        } else {
+            stdout.write_all(&block_data)
+        }
+        .and_then(|_| stdout.flush())
+        .expect("failed to write blocks to stdout");
+
+        frame_count += 1;
+    }
+
+    let duration = start.elapsed();
+    eprintln!(
+        "Compressed {frame_count} frames in {:.3} ms",
+        duration.as_secs_f64() * 1000.0
+    );
+}
+
+/// Fills `frame` from `reader`. Returns `false` if the input ended before the frame started.
+fn read_frame(reader: &mut impl Read, frame: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+
+    while filled < frame.len() {
+        match reader.read(&mut frame[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("input ended after {filled} of {} frame bytes", frame.len()),
+                ))
+            }
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(true)
+}
+
 fn download_blocks_data(device: &Device, queue: &Queue, block_buffer: Buffer) -> Vec<u8> {
     let size = block_buffer.size();
 
@@ -310,50 +491,56 @@ fn download_blocks_data(device: &Device, queue: &Queue, block_buffer: Buffer) ->
     result
 }
 
+/// Writes a DX9 style DDS file for the legacy DXT names of the variants, and a file with the
+/// DX10 header extension otherwise.
+fn write_dds_with_header(
+    writer: &mut impl Write,
+    descriptor: &DdsDescriptor,
+    block_data: &[u8],
+    legacy_dds: bool,
+) -> io::Result<()> {
+    if legacy_dds {
+        write_dds_legacy(writer, descriptor, block_data)
+    } else {
+        write_dds(writer, descriptor, block_data)
+    }
+}
+
 fn write_dds_file(
     file_name: &str,
     variant: CompressionVariant,
+    legacy_dds: bool,
     width: u32,
     height: u32,
     block_data: Vec<u8>,
 ) {
-    let mut dds = Dds::new_dxgi(NewDxgiParams {
-        height,
-        width,
-        depth: None,
-        format: dxgi_format(variant),
-        mipmap_levels: Some(1),
-        array_layers: None,
-        caps2: None,
-        is_cubemap: false,
-        resource_dimension: D3D10ResourceDimension::Texture2D,
-        alpha_mode: AlphaMode::Straight,
-    })
-    .expect("failed to create DDS header");
-
-    dds.data = block_data;
+    let descriptor = DdsDescriptor {
+        srgb: true,
+        ..DdsDescriptor::new(variant, width, height)
+    };
 
     let mut dds_name = PathBuf::from(file_name);
     dds_name.set_extension("dds");
 
-    let mut file = File::create(dds_name).expect("failed to create output file");
-    dds.write(&mut file).expect("failed to write DDS file");
-}
-
-fn dxgi_format(variant: CompressionVariant) -> DxgiFormat {
-    match variant {
-        CompressionVariant::BC1 => DxgiFormat::BC1_UNorm_sRGB,
-        CompressionVariant::BC2 => DxgiFormat::BC2_UNorm_sRGB,
-        CompressionVariant::BC3 => DxgiFormat::BC3_UNorm_sRGB,
-        CompressionVariant::BC4 => DxgiFormat::BC4_UNorm,
-        CompressionVariant::BC5 => DxgiFormat::BC5_UNorm,
-        CompressionVariant::BC6H(..) => DxgiFormat::BC6H_UF16,
-        CompressionVariant::BC7(..) => DxgiFormat::BC7_UNorm_sRGB,
-    }
+    let file = File::create(dds_name).expect("failed to create output file");
+    let mut writer = BufWriter::new(file);
+    write_dds_with_header(&mut writer, &descriptor, &block_data, legacy_dds)
+        .and_then(|_| writer.flush())
+        .expect("failed to write DDS file");
 }
 
 fn print_help() {
     println!("Usage: compressor <compression_variant> <input_file>");
+    println!(
+        "       compressor <compression_variant> - --width <pixels> --height <pixels> [options]"
+    );
+    println!("\nPassing - as the input file reads raw frames from stdin until it is closed and");
+    println!("writes the compressed blocks of every frame to stdout.");
+    println!("\nStreaming options:");
+    println!("  --width <pixels>   Width of the frames");
+    println!("  --height <pixels>  Height of the frames");
+    println!("  --format <format>  Pixel format of the frames: rgba8 (default) or rgba16f");
+    println!("  --dds              Write every frame as a DDS file instead of raw blocks");
     println!("\nCompression variants:");
     println!("  bc1  - BC1 compression (RGB)");
     println!("  bc2  - BC2 compression with sharp alpha (RGBA)");
@@ -362,20 +549,23 @@ fn print_help() {
     println!("  bc5  - BC5 compression (RG)");
     println!("  bc6h - BC6H compression (RGB HDR)");
     println!("  bc7  - BC7 compression with smooth alpha (RGBA)");
+    println!("\nThe legacy names dxt1, dxt3 and dxt5 select BC1, BC2 and BC3 and write DDS files");
+    println!("without the DX10 header extension, for engines that only read DX9 style files.");
 }
 
-fn parse_args() -> Option<(CompressionVariant, String)> {
+fn parse_args() -> Option<(CompressionVariant, bool, Input)> {
     let args: Vec<String> = std::env::args().collect();
 
-    if args.len() != 3 || args.contains(&"--help".to_string()) {
+    if args.len() < 3 || args.contains(&"--help".to_string()) {
         print_help();
         return None;
     }
 
-    let variant = match args[1].to_lowercase().as_str() {
-        "bc1" => CompressionVariant::BC1,
-        "bc2" => CompressionVariant::BC2,
-        "bc3" => CompressionVariant::BC3,
+    let name = args[1].to_lowercase();
+    let variant = match name.as_str() {
+        "bc1" | "dxt1" => CompressionVariant::BC1,
+        "bc2" | "dxt3" => CompressionVariant::BC2,
+        "bc3" | "dxt5" => CompressionVariant::BC3,
         "bc4" => CompressionVariant::BC4,
         "bc5" => CompressionVariant::BC5,
         "bc6h" => CompressionVariant::BC6H(BC6HSettings::very_slow()),
@@ -386,24 +576,73 @@ fn parse_args() -> Option<(CompressionVariant, String)> {
             return None;
         }
     };
+    let legacy_dds = name.starts_with("dxt");
 
-    let file_name = args[2].clone();
+    if args[2] != "-" {
+        if args.len() != 3 {
+            println!("Error: Options are only supported when reading from stdin");
+            print_help();
+            return None;
+        }
 
-    Some((variant, file_name))
-}
+        return Some((variant, legacy_dds, Input::File(args[2].clone())));
+    }
+
+    let mut width = None;
+    let mut height = None;
+    let mut format = PixelFormat::Rgba8;
+    let mut dds = false;
+
+    let mut options = args[3..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--width" => {
+                width = options
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .filter(|&value| value > 0)
+            }
+            "--height" => {
+                height = options
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .filter(|&value| value > 0)
+            }
+            "--format" => {
+                format = match options.next().map(|value| value.to_lowercase()).as_deref() {
+                    Some("rgba8") => PixelFormat::Rgba8,
+                    Some("rgba16f") => PixelFormat::Rgba16Float,
+                    _ => {
+                        println!("Error: Invalid pixel format");
+                        print_help();
+                        return None;
+                    }
+                }
+            }
+            "--dds" => dds = true,
+            _ => {
+                println!("Error: Unknown option {option}");
+                print_help();
+                return None;
+            }
+        }
+    }
 
-pub fn error_handler(error: Error) {
-    let (message_type, message) = match error {
-        Error::OutOfMemory { source } => ("OutOfMemory", source.to_string()),
-        Error::Validation {
-            source,
-            description,
-        } => ("Validation", format!("{source}: {description}")),
-        Error::Internal {
-            source,
-            description,
-        } => ("Internal", format!("{source}: {description}")),
+    let (Some(width), Some(height)) = (width, height) else {
+        println!("Error: Reading from stdin needs a valid --width and --height");
+        print_help();
+        return None;
     };
 
-    panic!("wgpu [{message_type}] [error]: {message}");
+    Some((
+        variant,
+        legacy_dds,
+        Input::Stdin(StreamOptions {
+            width,
+            height,
+            format,
+            dds,
+            legacy_dds,
+        }),
+    ))
 }
@@ -0,0 +1,43 @@
+use wgpu::{Buffer, BufferDescriptor, Device};
+
+/// Supplies the buffers a [`GpuBlockCompressor`] allocates internally.
+///
+/// The compressor allocates a few buffers of its own: the uniforms and settings of the tasks,
+/// which grow with the number of tasks, and the staging buffers of readbacks. Engines that
+/// manage their GPU memory in pools can implement this trait to keep these allocations inside
+/// their pools. Use [`GpuBlockCompressor::with_allocator()`] to create a compressor with a
+/// custom allocator. The destination buffers of the compression tasks are always provided by
+/// the caller and never go through the allocator.
+///
+/// [`GpuBlockCompressor`]: crate::GpuBlockCompressor
+/// [`GpuBlockCompressor::with_allocator()`]: crate::GpuBlockCompressor::with_allocator
+pub trait BufferAllocator: Send + Sync {
+    /// Returns a buffer for the given descriptor.
+    ///
+    /// The buffer needs to be at least `descriptor.size` bytes large and support all usages of
+    /// the descriptor. It may be larger and may be a recycled buffer, but it must not be used by
+    /// anything else until it is released. Buffers with `mapped_at_creation` set are never
+    /// requested.
+    fn create_buffer(&self, device: &Device, descriptor: &BufferDescriptor<'_>) -> Buffer;
+
+    /// Hands back a buffer that was created by [`BufferAllocator::create_buffer()`] and is no
+    /// longer used by the compressor.
+    ///
+    /// GPU work that was already submitted may still use the buffer. Buffers that are still
+    /// referenced when the device is lost or a mapping fails are dropped without being released.
+    /// The default implementation drops the buffer.
+    fn release_buffer(&self, buffer: Buffer) {
+        drop(buffer);
+    }
+}
+
+/// A [`BufferAllocator`] that creates every buffer directly on the device. Used by
+/// [`GpuBlockCompressor::new()`](crate::GpuBlockCompressor::new).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DeviceAllocator;
+
+impl BufferAllocator for DeviceAllocator {
+    fn create_buffer(&self, device: &Device, descriptor: &BufferDescriptor<'_>) -> Buffer {
+        device.create_buffer(descriptor)
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use wgpu::{AdapterInfo, Backends, Device, DeviceDescriptor, Instance, Queue};
+
+/// A device on one of the adapters enumerated by [`for_each_backend()`].
+pub struct BackendDevice {
+    /// Information about the adapter of the device, including its backend.
+    pub info: AdapterInfo,
+    /// The device that was created on the adapter.
+    pub device: Arc<Device>,
+    /// The queue of the device.
+    pub queue: Arc<Queue>,
+}
+
+/// Creates a device on every adapter of `instance` that uses one of the given `backends` and
+/// calls `callback` with it.
+///
+/// Shader compilers differ between Vulkan, DX12, Metal and GL, so a bug in the compression
+/// shaders often only shows up on a single backend. Running the same compression on every
+/// adapter and comparing the decoded results against each other or against the CPU encoders
+/// catches these bugs before they reach users. The devices are created with the default
+/// features and limits, adapters that can't provide them are skipped.
+///
+/// Only available on native platforms, since the web can't enumerate adapters.
+///
+/// Returns the number of adapters `callback` was called with.
+///
+/// # Example
+///
+/// ```ignore
+/// let instance = Instance::new(&InstanceDescriptor::default());
+///
+/// pollster::block_on(for_each_backend(&instance, Backends::all(), |backend| {
+///     let mut compressor = GpuBlockCompressor::new(backend.device, backend.queue);
+///     // Compress a reference texture and compare the blocks of the backends.
+/// }));
+/// ```
+pub async fn for_each_backend<F>(instance: &Instance, backends: Backends, mut callback: F) -> usize
+where
+    F: FnMut(BackendDevice),
+{
+    let mut count = 0;
+
+    for adapter in instance.enumerate_adapters(backends) {
+        let info = adapter.get_info();
+
+        let Ok((device, queue)) = adapter
+            .request_device(
+                &DeviceDescriptor {
+                    label: Some(&format!("{} ({:?}) device", info.name, info.backend)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+        else {
+            continue;
+        };
+
+        callback(BackendDevice {
+            info,
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+        });
+        count += 1;
+    }
+
+    count
+}
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::CompressionVariant;
+
+/// Identifies the blocks of a task in a [`BlockCache`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct CacheKey {
+    pub(crate) content_hash: u64,
+    pub(crate) variant: CompressionVariant,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+struct CacheEntry {
+    blocks: Arc<[u8]>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<CacheKey, CacheEntry>,
+    size: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// Compressed blocks of earlier tasks, keyed by a content hash the caller provides.
+///
+/// Editor hot-reload loops recompress all textures of an asset, although most of them did not
+/// change. A [`GpuBlockCompressor`] with a cache skips the dispatch of every task whose
+/// [content hash] was compressed before and writes the cached blocks into the destination
+/// buffer instead. The blocks of the other tasks are read back and added to the cache. See
+/// [`GpuBlockCompressor::set_block_cache()`] for the requirements of the tasks.
+///
+/// Entries are keyed by the content hash, the variant with its settings, and the size of the
+/// task. Once the cached blocks exceed the maximal size, the least recently used entries are
+/// evicted. The cache can be cloned to share it between several compressors.
+///
+/// [`GpuBlockCompressor`]: crate::GpuBlockCompressor
+/// [`GpuBlockCompressor::set_block_cache()`]: crate::GpuBlockCompressor::set_block_cache
+/// [content hash]: crate::TaskOptions::content_hash
+#[derive(Clone)]
+pub struct BlockCache {
+    entries: Arc<Mutex<Entries>>,
+    max_size: usize,
+}
+
+impl BlockCache {
+    /// Creates an empty cache that holds at most `max_size` bytes of blocks.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            entries: Arc::default(),
+            max_size,
+        }
+    }
+
+    /// Returns the maximal number of bytes of blocks the cache holds.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Returns the number of bytes of all cached blocks.
+    pub fn size(&self) -> usize {
+        self.lock().size
+    }
+
+    /// Returns the number of cached tasks.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if no blocks are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tasks that were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Returns the number of tasks with a content hash that were not found in the cache.
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    /// Returns the cached blocks of a texture, without counting a hit or a miss.
+    pub fn get(
+        &self,
+        content_hash: u64,
+        variant: CompressionVariant,
+        width: u32,
+        height: u32,
+    ) -> Option<Arc<[u8]>> {
+        let key = CacheKey {
+            content_hash,
+            variant,
+            width,
+            height,
+        };
+        self.lock()
+            .entries
+            .get(&key)
+            .map(|entry| entry.blocks.clone())
+    }
+
+    /// Removes all cached blocks. The hit and miss counters keep their values.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.entries.clear();
+        entries.size = 0;
+    }
+
+    /// Looks up the blocks of a task and counts the hit or miss.
+    pub(crate) fn lookup(&self, key: &CacheKey) -> Option<Arc<[u8]>> {
+        let mut entries = self.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let blocks = entries.entries.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            entry.blocks.clone()
+        });
+
+        match blocks {
+            Some(_) => entries.hits += 1,
+            None => entries.misses += 1,
+        }
+
+        blocks
+    }
+
+    /// Adds the blocks of a task and evicts the least recently used entries until the cache
+    /// fits its maximal size. Blocks that are larger than the whole cache are not added.
+    pub(crate) fn insert(&self, key: CacheKey, blocks: &[u8]) {
+        if blocks.len() > self.max_size {
+            return;
+        }
+
+        let mut entries = self.lock();
+        entries.clock += 1;
+        let entry = CacheEntry {
+            blocks: blocks.into(),
+            last_used: entries.clock,
+        };
+
+        entries.size += blocks.len();
+        if let Some(replaced) = entries.entries.insert(key, entry) {
+            entries.size -= replaced.blocks.len();
+        }
+
+        while entries.size > self.max_size {
+            let oldest = entries
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(&key, _)| key)
+                .expect("cache is larger than its entries");
+            let evicted = entries.entries.remove(&oldest).unwrap();
+            entries.size -= evicted.blocks.len();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    fn key(content_hash: u64) -> CacheKey {
+        CacheKey {
+            content_hash,
+            variant: CompressionVariant::BC1,
+            width: 8,
+            height: 8,
+        }
+    }
+
+    #[test]
+    fn test_lookup_counts_hits_and_misses() {
+        let cache = BlockCache::new(1024);
+
+        assert!(cache.lookup(&key(1)).is_none());
+        cache.insert(key(1), &[1; 32]);
+        assert_eq!(cache.lookup(&key(1)).as_deref(), Some(&[1; 32][..]));
+
+        // The variant and size are part of the key.
+        let bc4 = CacheKey {
+            variant: CompressionVariant::BC4,
+            ..key(1)
+        };
+        assert!(cache.lookup(&bc4).is_none());
+
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(
+            cache.get(1, CompressionVariant::BC1, 8, 8).unwrap().len(),
+            32
+        );
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_insert_evicts_least_recently_used() {
+        let cache = BlockCache::new(96);
+
+        cache.insert(key(1), &[1; 32]);
+        cache.insert(key(2), &[2; 32]);
+        cache.insert(key(3), &[3; 32]);
+        assert!(cache.lookup(&key(1)).is_some());
+
+        cache.insert(key(4), &[4; 32]);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.size(), 96);
+        assert!(cache.lookup(&key(2)).is_none());
+        assert!(cache.lookup(&key(1)).is_some());
+
+        // Replacing an entry keeps the size in sync.
+        cache.insert(key(4), &[5; 16]);
+        assert_eq!(cache.size(), 80);
+
+        cache.insert(key(5), &[0; 128]);
+        assert!(cache.lookup(&key(5)).is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+    }
+}
@@ -1,19 +1,143 @@
-use std::{collections::HashMap, num::NonZeroU64, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    num::{NonZeroU32, NonZeroU64},
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use bytemuck::{cast_slice, Pod, Zeroable};
 use wgpu::{
     self, include_wgsl, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
-    BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages, ComputePass, ComputePipeline,
-    ComputePipelineDescriptor, Device, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue,
-    ShaderModule, ShaderStages, TextureSampleType, TextureView, TextureViewDimension,
+    BufferBinding, BufferBindingType, BufferDescriptor, BufferSlice, BufferUsages, BufferView,
+    CommandEncoder, ComputePass, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor,
+    Device, DeviceLostReason, Features, LoadOp, MapMode, Operations, PipelineCompilationOptions,
+    PipelineLayout, PipelineLayoutDescriptor, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+    StoreOp, Texture, TextureDescriptor, TextureDimension, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension, MAP_ALIGNMENT,
 };
+#[cfg(feature = "bounds_checks")]
+use wgpu::{CommandEncoderDescriptor, Maintain};
 
-#[cfg(feature = "bc6h")]
-use crate::BC6HSettings;
 #[cfg(feature = "bc7")]
 use crate::BC7Settings;
-use crate::CompressionVariant;
+use crate::{
+    block_cache::{BlockCache, CacheKey},
+    diagnostics::workgroup_count,
+    mip_dimension, BufferAllocator, ByteOffset, CompressionVariant, DeviceAllocator, EdgeFill,
+    FormatKey,
+};
+#[cfg(feature = "bc6h")]
+use crate::{BC6HSettings, HdrRemap};
+
+/// The default number of tasks that [`GpuBlockCompressor::compress_split()`] records into a
+/// single compute pass. Some drivers handle very long compute passes poorly.
+const DEFAULT_MAX_TASKS_PER_PASS: usize = 256;
+
+/// The number of tasks per compute pass on software adapters. See
+/// [`GpuBlockCompressor::set_software_compatibility()`].
+const SOFTWARE_MAX_TASKS_PER_PASS: usize = 16;
+
+/// The number of blocks per dispatch on software adapters, which is a 512x512 texture. See
+/// [`GpuBlockCompressor::set_software_compatibility()`].
+const SOFTWARE_MAX_BLOCKS_PER_DISPATCH: u32 = 128 * 128;
+
+/// The upper limit of the source textures that are bound as a single texture array. See
+/// [`GpuBlockCompressor::texture_array_len()`].
+const MAX_TEXTURE_ARRAY_LEN: u32 = 256;
+
+/// The declaration of the source texture every shader contains.
+const SOURCE_TEXTURE_DECLARATION: &str = "var source_texture: texture_2d<f32>;";
+
+/// Panics if the settings of the variant can't be encoded, before a shader runs with them.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn assert_valid_settings(variant: CompressionVariant) {
+    let result = match variant {
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(settings) => settings.validate(),
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(settings) => settings.validate(),
+        _ => Ok(()),
+    };
+
+    if let Err(error) = result {
+        panic!("invalid settings of {variant:?}: {error}");
+    }
+}
+
+/// The declarations every shader contains for the bounds checks, which are disabled.
+#[cfg(feature = "bounds_checks")]
+const RELEASE_HOOKS: &str = "const BOUNDS_CHECKS: bool = false;\nfn report_bounds_violation() {}\n";
+
+/// Replaces [`RELEASE_HOOKS`] to enable the bounds checks and count the violations.
+#[cfg(feature = "bounds_checks")]
+const BOUNDS_CHECK_HOOKS: &str = "const BOUNDS_CHECKS: bool = true;
+@group(1) @binding(0) var<storage, read_write> bounds_violations: atomic<u32>;
+fn report_bounds_violation() {
+    atomicAdd(&bounds_violations, 1u);
+}
+";
+
+/// Binds the bind group of a dispatch with the dynamic offsets the variant needs.
+#[allow(unused_variables)]
+fn set_dispatch_bind_group(
+    pass: &mut ComputePass,
+    variant: CompressionVariant,
+    bind_group: &BindGroup,
+    uniform_offset: u32,
+    settings_offset: u32,
+) {
+    match variant {
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(..) => {
+            pass.set_bind_group(0, bind_group, &[uniform_offset, settings_offset]);
+        }
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(..) => {
+            pass.set_bind_group(0, bind_group, &[uniform_offset, settings_offset]);
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            pass.set_bind_group(0, bind_group, &[uniform_offset]);
+        }
+    }
+}
+
+/// Rewrites a shader to read its source from an array of `len` textures, at the texture index
+/// of the uniforms.
+fn texture_array_shader(
+    descriptor: ShaderModuleDescriptor,
+    len: NonZeroU32,
+) -> ShaderModuleDescriptor {
+    let ShaderSource::Wgsl(source) = descriptor.source else {
+        unreachable!("all shaders are written in WGSL");
+    };
+    assert!(
+        source.contains(SOURCE_TEXTURE_DECLARATION),
+        "shader has no source texture"
+    );
+
+    let source = source
+        .replacen(
+            SOURCE_TEXTURE_DECLARATION,
+            &format!("var source_textures: binding_array<texture_2d<f32>, {len}>;"),
+            1,
+        )
+        .replace(
+            "textureLoad(source_texture,",
+            "textureLoad(source_textures[uniforms.texture_index],",
+        );
+
+    ShaderModuleDescriptor {
+        label: descriptor.label,
+        source: ShaderSource::Wgsl(source.into()),
+    }
+}
 
 #[derive(Copy, Clone, Zeroable, Pod)]
 #[repr(C)]
@@ -24,11 +148,42 @@ struct Uniforms {
     height: u32,
     /// Start row of the texture data we want to convert.
     texture_y_offset: u32,
-    /// Start of the blocks data in u32 elements.
+    /// Start of the blocks data in u32 elements, relative to the bound buffer offset.
     blocks_offset: u32,
+    /// Start of the block errors in f32 elements, relative to the bound buffer offset.
+    errors_offset: u32,
+    /// Set to 1 if the block errors should be written.
+    write_errors: u32,
+    /// How pixels outside of the image are filled.
+    edge_fill: u32,
+    /// The packed RGBA8 color for the solid color edge fill.
+    fill_color: u32,
+    /// The gradient filter for normals generated from a height map.
+    normal_filter: u32,
+    /// The strength of normals generated from a height map.
+    normal_strength: f32,
+    /// Set to 1 if the Y axis of generated normals points down.
+    normal_flip_y: u32,
+    /// Set to 1 if the 4-bit alpha of BC2 is dithered.
+    dither_alpha: u32,
+    /// Set to 1 if BC4 is compressed as a signed distance field.
+    sdf: u32,
+    /// The value of the signed distance field in 8-bit levels at which the edge lies.
+    sdf_pivot: f32,
+    /// The factor the HDR values are multiplied with before the BC6H encoding.
+    hdr_scale: f32,
+    /// The offset that is added to the scaled HDR values.
+    hdr_bias: f32,
+    /// Set to 1 if the loaded colors are encoded back to sRGB.
+    source_srgb: u32,
+    /// Set to 1 if BC1 selects the punch-through mode for blocks with transparent pixels.
+    punch_through_alpha: u32,
+    /// The index of the source texture, if the source textures are bound as an array.
+    texture_index: u32,
 }
 
 struct Task {
+    id: TaskId,
     variant: CompressionVariant,
     width: u32,
     height: u32,
@@ -36,183 +191,1228 @@ struct Task {
     #[cfg(any(feature = "bc6h", feature = "bc7"))]
     setting_offset: u32,
     texture_y_offset: u32,
-    buffer_offset: u32,
+    buffer_binding_offset: u64,
+    buffer_binding_size: u64,
+    blocks_offset: u32,
     texture_view: TextureView,
+    secondary_texture_view: Option<TextureView>,
     buffer: Buffer,
+    errors: Option<TaskErrors>,
+    encoding: EncodingOptions,
+    normal_map: Option<NormalMapSettings>,
+    precision_16bit: bool,
+    label: Option<String>,
+    completion: Option<Completion>,
+    texture_slot: Option<TextureSlot>,
+    content_hash: Option<u64>,
+    /// The key the blocks are added to the block cache with, once they were read back.
+    cache_fill: Option<CacheKey>,
 }
 
-/// Compresses texture data with a block compression algorithm using WGPU compute shader.
-pub struct GpuBlockCompressor {
-    scratch_buffer: Vec<u8>,
-    task: Vec<Task>,
-    uniforms_buffer: Buffer,
+/// The place of a task in a bind group that binds the source textures of several tasks as an
+/// array.
+#[derive(Copy, Clone, Default)]
+struct TextureSlot {
+    texture_index: u32,
+    /// The u32 elements between the start of the shared blocks binding and the task binding.
+    blocks_rebase: u32,
+    /// The f32 elements between the start of the shared errors binding and the task binding.
+    errors_rebase: u32,
+}
+
+/// The tasks that share a texture array bind group, and the ranges of their shared bindings.
+struct TextureArrayGroup {
+    tasks: Vec<usize>,
+    blocks: Range<u64>,
+    errors: Option<Range<u64>>,
+}
+
+struct TaskErrors {
+    buffer: Buffer,
+    binding_offset: u64,
+    binding_size: u64,
+    errors_offset: u32,
+}
+
+/// The counter of the writes the kernels skipped, because they were out of bounds.
+#[cfg(feature = "bounds_checks")]
+struct BoundsChecks {
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+#[cfg(feature = "bounds_checks")]
+impl BoundsChecks {
+    fn new(device: &Device) -> Self {
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("bounds violations"),
+            size: 4,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("bounds violations bind group"),
+            layout: &Self::create_layout(device),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self { buffer, bind_group }
+    }
+
+    fn create_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("bounds violations bind group layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(4),
+                },
+                count: None,
+            }],
+        })
+    }
+}
+
+type CompletionCallback = Box<dyn FnOnce(CompletedTask) + Send>;
+
+struct Completion {
+    callback: CompletionCallback,
+    readback: bool,
+}
+
+/// A dispatched task that waits for its command buffer to be submitted.
+struct PendingCompletion {
+    id: TaskId,
+    buffer: Buffer,
+    offset: u64,
+    size: u64,
+    callback: CompletionCallback,
+    readback: bool,
+    staging: Option<(Arc<ReadbackStaging>, u64)>,
+    cache_fill: Option<(CacheKey, BlockCache)>,
     #[cfg(feature = "bc6h")]
-    bc6h_settings_buffer: Buffer,
-    #[cfg(feature = "bc7")]
-    bc7_settings_buffer: Buffer,
-    bind_group_layouts: HashMap<CompressionVariant, BindGroupLayout>,
-    pipelines: HashMap<CompressionVariant, ComputePipeline>,
-    device: Arc<Device>,
-    queue: Arc<Queue>,
-    uniforms_aligned_size: usize,
+    hdr_remap: HdrRemap,
+}
+
+/// A readback that waits for the mapping of its staging buffer.
+struct PendingReadback {
+    task: CompletedTask,
+    staging_offset: u64,
+    callback: CompletionCallback,
+    cache_fill: Option<(CacheKey, BlockCache)>,
+}
+
+/// The staging buffer that holds the readbacks of all tasks recorded by one call of
+/// [`GpuBlockCompressor::record_readbacks()`].
+///
+/// It is mapped once for all of its tasks, and unmapped and released to the allocator when the
+/// last of them is dropped. Buffers that were never mapped are dropped without being released.
+struct ReadbackStaging {
+    buffer: Buffer,
+    allocator: Arc<dyn BufferAllocator>,
+    mapped: AtomicBool,
+}
+
+impl Drop for ReadbackStaging {
+    fn drop(&mut self) {
+        if self.mapped.load(Ordering::Acquire) {
+            self.buffer.unmap();
+            self.allocator.release_buffer(self.buffer.clone());
+        }
+    }
+}
+
+/// Identifies a compression task added to a [`GpuBlockCompressor`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TaskId(u64);
+
+/// A compression task whose compressed blocks were written by the GPU.
+///
+/// Handed to the callbacks registered with [`GpuBlockCompressor::on_complete()`] and
+/// [`GpuBlockCompressor::on_complete_with_readback()`].
+pub struct CompletedTask {
+    id: TaskId,
+    buffer: Buffer,
+    offset: u64,
+    size: u64,
+    staging: Option<(Arc<ReadbackStaging>, u64)>,
     #[cfg(feature = "bc6h")]
-    bc6h_aligned_size: usize,
-    #[cfg(feature = "bc7")]
-    bc7_aligned_size: usize,
+    hdr_remap: HdrRemap,
 }
 
-impl GpuBlockCompressor {
-    /// Creates a new block compressor instance.
-    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
-        let limits = device.limits();
+impl CompletedTask {
+    /// The ID of the task.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
 
-        let alignment = limits.min_uniform_buffer_offset_alignment as usize;
-        let size = size_of::<Uniforms>();
-        let uniforms_aligned_size = size.div_ceil(alignment) * alignment;
+    /// The slice of the destination buffer that holds the compressed blocks of the task.
+    ///
+    /// The blocks are safe to be copied or sampled by any work submitted from now on.
+    pub fn blocks(&self) -> BufferSlice<'_> {
+        self.buffer.slice(self.offset..self.offset + self.size)
+    }
+
+    /// The compressed blocks of the task copied to the CPU.
+    ///
+    /// Returns `None` if the task was not registered with
+    /// [`GpuBlockCompressor::on_complete_with_readback()`] or if mapping the staging buffer
+    /// failed. The view needs to be dropped before the task is dropped.
+    pub fn mapped_blocks(&self) -> Option<BufferView<'_>> {
+        self.staging.as_ref().map(|(staging, offset)| {
+            staging
+                .buffer
+                .slice(*offset..*offset + self.size)
+                .get_mapped_range()
+        })
+    }
+
+    /// The CRC-32 checksum of the compressed blocks copied to the CPU.
+    ///
+    /// Allows storing the checksum next to the blocks, so streaming systems can validate them
+    /// after a transfer with [`crc32()`](crate::crc32). Returns `None` under the same conditions
+    /// as [`CompletedTask::mapped_blocks()`].
+    pub fn checksum(&self) -> Option<u32> {
+        self.mapped_blocks().map(|blocks| crate::crc32(&blocks))
+    }
+
+    /// The remapping that restores the original HDR values from the decoded blocks, which is
+    /// the inverse of the [`EncodingOptions::hdr_remap`] of the task.
+    ///
+    /// Returns [`HdrRemap::IDENTITY`] for tasks without a remapping.
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    pub fn hdr_restore(&self) -> HdrRemap {
+        self.hdr_remap.inverse()
+    }
+}
+
+/// Errors of the [`GpuBlockCompressor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompressorError {
+    /// The device of the compressor was lost, for example because of a driver reset or GPU
+    /// removal. All resources of the compressor are invalid and need to be rebuilt with
+    /// [`GpuBlockCompressor::recreate()`] on a new device.
+    DeviceLost {
+        /// The reason the device was lost.
+        reason: DeviceLostReason,
+        /// The message the device was lost with.
+        message: String,
+    },
+}
+
+impl fmt::Display for CompressorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeviceLost { reason, message } => {
+                write!(f, "device was lost ({reason:?}): {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompressorError {}
+
+/// Counters of the allocations and uploads of a [`GpuBlockCompressor`].
+///
+/// Returned by [`GpuBlockCompressor::memory_stats()`]. The counters add up since the compressor
+/// was created or [`GpuBlockCompressor::reset_memory_stats()`] was called. Once a compressor
+/// has seen its largest batch of tasks, it should only create bind groups and upload bytes:
+/// resetting the counters every frame and checking that `buffers_reallocated` stays zero
+/// verifies that it doesn't allocate during steady-state frames.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct MemoryStats {
+    /// The number of calls of [`GpuBlockCompressor::compress()`],
+    /// [`GpuBlockCompressor::compress_chunk()`] and [`GpuBlockCompressor::compress_split()`].
+    pub compress_calls: u64,
+    /// The number of bind groups created, one per dispatched task or per texture array of tasks
+    /// that share a bind group.
+    pub bind_groups_created: u64,
+    /// The number of uniforms and settings buffers that were replaced by larger ones, because a
+    /// batch had more tasks than all batches before it.
+    pub buffers_reallocated: u64,
+    /// The number of staging buffers requested from the [`BufferAllocator`] for readbacks. All
+    /// readbacks recorded by one call of [`GpuBlockCompressor::record_readbacks()`] share a
+    /// single staging buffer.
+    pub staging_buffers_requested: u64,
+    /// The number of bytes of uniforms and settings written to the queue.
+    pub bytes_uploaded: u64,
+}
+
+/// A resource that a task accesses when it is dispatched.
+///
+/// Returned by [`GpuBlockCompressor::resource_usages()`]. Render graphs use them to place the
+/// barriers and transitions around the compute pass of the compressor.
+#[derive(Clone, Debug)]
+pub enum ResourceUsage {
+    /// The task reads the texture view in a compute shader.
+    ReadTexture(TextureView),
+    /// The task writes a byte range of a storage buffer in a compute shader.
+    WriteBuffer {
+        /// The written buffer.
+        buffer: Buffer,
+        /// The bound byte range of the buffer.
+        range: Range<u64>,
+    },
+}
+
+/// The color space of the texels a source texture view returns.
+///
+/// Set with [`EncodingOptions::source_color_space`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SourceColorSpace {
+    /// The view returns the stored values, like `Rgba8Unorm` views do. The values are compressed
+    /// as they are loaded.
+    #[default]
+    Raw,
+    /// The view decodes sRGB colors to linear ones, like `Rgba8UnormSrgb` views do. The loaded
+    /// colors are encoded back to sRGB, so the blocks store the same values as for a `Raw` view
+    /// of the texture and are meant for an sRGB block format. The alpha channel is not affected.
+    Srgb,
+}
+
+/// The gradient filter used to derive normals from a height map.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum NormalFilter {
+    /// The 3x3 Sobel operator.
+    #[default]
+    Sobel,
+    /// The 3x3 Scharr operator, which is more rotationally symmetric than Sobel and keeps
+    /// diagonal details sharper.
+    Scharr,
+}
+
+/// Settings to generate a tangent space normal map from a height map.
+///
+/// Used by [`GpuBlockCompressor::add_normal_map_compression_task()`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NormalMapSettings {
+    /// The gradient filter.
+    pub filter: NormalFilter,
+    /// Scales the height differences. At a strength of 1.0, a height difference of 1.0 between
+    /// neighboring pixels tilts the normal by 45 degrees.
+    pub strength: f32,
+    /// Lets the Y axis of the normals point down, like DirectX expects. By default the Y axis
+    /// points up, like OpenGL expects.
+    pub flip_y: bool,
+}
+
+impl Default for NormalMapSettings {
+    fn default() -> Self {
+        Self {
+            filter: NormalFilter::Sobel,
+            strength: 1.0,
+            flip_y: false,
+        }
+    }
+}
+
+/// Options that control how the blocks of a compression task are encoded.
+///
+/// Part of the [`TaskOptions`] of a task and of the [`DispatchUniforms`] of a single dispatch.
+/// The default options encode the texels as they are loaded.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EncodingOptions {
+    /// How the pixels outside the image are filled. Only has an effect if the width or height
+    /// of the task is not a multiple of 4.
+    pub edge_fill: EdgeFill,
+    /// Applies an ordered dither to the 4-bit alpha of BC2.
+    ///
+    /// Reduces the banding of smooth alpha gradients like smoke or fog, which BC2 can only
+    /// store with 16 levels. The alpha blocks are identical to the ones of
+    /// [`compress_rgba8_bc2_dithered()`](crate::encode::compress_rgba8_bc2_dithered). Only has
+    /// an effect for `CompressionVariant::BC2`.
+    pub dither_alpha: bool,
+    /// Lets BC1 use the punch-through alpha mode.
+    ///
+    /// Every block with pixels whose alpha is below 128 is compressed with both the opaque and
+    /// the punch-through mode, and the one with the lower error is kept. The error of the color
+    /// is weighted by the alpha of the source, and the written block errors then include the
+    /// alpha channel. The mode is selected like by
+    /// [`compress_rgba8_bc1_punch_through()`](crate::encode::compress_rgba8_bc1_punch_through).
+    /// Only has an effect for `CompressionVariant::BC1`.
+    pub punch_through_alpha: bool,
+    /// Compresses BC4 as a signed distance field with the edge at the given pivot, or as a
+    /// plain BC4 texture if `None`.
+    ///
+    /// The error of values close to the pivot is weighted higher, so the edges of fonts and
+    /// shapes stay crisp. The pivot is given in 8-bit levels and is usually 128. The encoder is
+    /// the same as the one of [`compress_rgba8_bc4_sdf()`](crate::encode::compress_rgba8_bc4_sdf),
+    /// 16-bit textures keep their full precision during the fit. Only has an effect for
+    /// `CompressionVariant::BC4`.
+    pub sdf_pivot: Option<u8>,
+    /// Remaps the HDR values before they are encoded.
+    ///
+    /// Every color channel of the source texture is encoded as `value * scale + bias`, which
+    /// moves content with an extreme dynamic range into the range where half floats are
+    /// precise. `Rgba32Float` sources with values beyond the half float range need the
+    /// `FLOAT32_FILTERABLE` feature of the device. Non-finite values are handled after the
+    /// remapping as configured in the settings. The encoder is the same as the one of
+    /// [`compress_rgba32f_remapped()`](crate::encode::compress_rgba32f_remapped), and
+    /// [`CompletedTask::hdr_restore()`] returns the inverse that restores the original values.
+    /// Only has an effect for `CompressionVariant::BC6H`.
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    pub hdr_remap: HdrRemap,
+    /// The color space of the texels the source texture view returns.
+    ///
+    /// Textures with an sRGB format can be compressed through a view of the same format, which
+    /// saves creating the texture with a non-sRGB entry in its `view_formats`. Use
+    /// [`SourceColorSpace::Srgb`] for these views, so the colors are converted back to the
+    /// stored sRGB values before they are encoded. BC6H stores linear colors and always encodes
+    /// the values as they are loaded. Height maps of normal map tasks and the secondary
+    /// textures of interleaved tasks are not converted either.
+    pub source_color_space: SourceColorSpace,
+}
 
+impl EncodingOptions {
+    /// Returns uniforms with the fields of the options set and all other fields zeroed.
+    fn uniforms(&self) -> Uniforms {
         #[cfg(feature = "bc6h")]
-        let bc6h_aligned_size = {
-            let alignment = limits.min_storage_buffer_offset_alignment as usize;
-            let size = size_of::<BC6HSettings>();
-            size.div_ceil(alignment) * alignment
-        };
+        let (hdr_scale, hdr_bias) = (self.hdr_remap.scale, self.hdr_remap.bias);
+        #[cfg(not(feature = "bc6h"))]
+        let (hdr_scale, hdr_bias) = (1.0, 0.0);
 
-        #[cfg(feature = "bc7")]
-        let bc7_aligned_size = {
-            let alignment = limits.min_storage_buffer_offset_alignment as usize;
-            let size = size_of::<BC7Settings>();
-            size.div_ceil(alignment) * alignment
+        Uniforms {
+            edge_fill: self.edge_fill.raw_mode(),
+            fill_color: self.edge_fill.raw_color(),
+            dither_alpha: self.dither_alpha as u32,
+            sdf: self.sdf_pivot.is_some() as u32,
+            sdf_pivot: self.sdf_pivot.unwrap_or(0) as f32,
+            hdr_scale,
+            hdr_bias,
+            source_srgb: (self.source_color_space == SourceColorSpace::Srgb) as u32,
+            punch_through_alpha: self.punch_through_alpha as u32,
+            ..Zeroable::zeroed()
+        }
+    }
+}
+
+/// Options of a single compression task, passed to
+/// [`GpuBlockCompressor::add_compression_task()`] and the other functions that add tasks.
+///
+/// The default options encode the texels as they are loaded, don't label the task, don't cache
+/// its blocks and don't write block errors.
+///
+/// # Example
+///
+/// ```ignore
+/// let options = TaskOptions {
+///     encoding: EncodingOptions {
+///         edge_fill: EdgeFill::Mirror,
+///         ..Default::default()
+///     },
+///     label: Some("terrain tile"),
+///     ..Default::default()
+/// };
+/// compressor.add_compression_task(variant, &view, width, height, &blocks, None, None, options);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TaskOptions<'a> {
+    /// How the blocks of the task are encoded.
+    pub encoding: EncodingOptions,
+    /// A debug label of the task.
+    ///
+    /// The label is used for the bind group of the task and its dispatch is wrapped in a debug
+    /// group with the same name, so the dispatches are easy to find in graphics debuggers like
+    /// RenderDoc or PIX.
+    pub label: Option<&'a str>,
+    /// The content hash that identifies the blocks of the task in the
+    /// [block cache](GpuBlockCompressor::set_block_cache).
+    ///
+    /// The hash needs to cover everything that influences the blocks besides the variant, its
+    /// settings and the size of the task, which are part of the key anyway. That is the texels
+    /// of the source region and the other options of the task, like the edge fill. Tasks
+    /// without a content hash are always dispatched.
+    pub content_hash: Option<u64>,
+    /// The storage buffer the per block errors of the task are written into, or `None` to not
+    /// write any errors.
+    ///
+    /// For every block a single `f32` is written in the same row-major block order as the
+    /// compressed blocks. This allows callers to build their own refinement, to stream the
+    /// worst blocks first or to debug the encoder. The error is the sum of the squared errors of
+    /// all pixels of the block:
+    ///
+    /// * BC1 to BC5: Measured on the 8-bit values of the channels the variant stores.
+    /// * BC6H and BC7: The error metric of the encoder for the selected block encoding. BC6H
+    ///   blocks with non-finite values rejected by
+    ///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject) have an infinite
+    ///   error.
+    pub errors_buffer: Option<&'a Buffer>,
+    /// The offset in bytes into the `errors_buffer`. Ignored without an `errors_buffer`.
+    pub errors_offset: Option<u64>,
+}
+
+/// The uniforms of a single dispatch recorded with [`GpuBlockCompressor::record_single()`].
+///
+/// Integrators that manage their own uniforms buffer write [`DispatchUniforms::to_bytes()`] at
+/// an offset that is a multiple of the `min_uniform_buffer_offset_alignment` of the device.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DispatchUniforms {
+    /// The width of the texture region in pixels.
+    pub width: u32,
+    /// The height of the texture region in pixels.
+    pub height: u32,
+    /// The first row of the texture region.
+    pub texture_y_offset: u32,
+    /// The start of the blocks in u32 elements, relative to the offset of the blocks binding.
+    pub blocks_offset: u32,
+    /// The start of the block errors in f32 elements, relative to the offset of the errors
+    /// binding, or `None` if no block errors are written.
+    pub errors_offset: Option<u32>,
+    /// How the blocks are encoded.
+    pub encoding: EncodingOptions,
+}
+
+impl DispatchUniforms {
+    /// The size of the uniforms in bytes.
+    pub const SIZE: usize = size_of::<Uniforms>();
+
+    /// Creates the uniforms of a texture of the given size, with the defaults of
+    /// [`GpuBlockCompressor::add_compression_task()`].
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            texture_y_offset: 0,
+            blocks_offset: 0,
+            errors_offset: None,
+            encoding: EncodingOptions::default(),
+        }
+    }
+
+    /// Returns the [`DispatchUniforms::SIZE`] bytes the shaders read.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let uniforms = Uniforms {
+            width: self.width,
+            height: self.height,
+            texture_y_offset: self.texture_y_offset,
+            blocks_offset: self.blocks_offset,
+            errors_offset: self.errors_offset.unwrap_or(0),
+            write_errors: self.errors_offset.is_some() as u32,
+            ..self.encoding.uniforms()
         };
 
-        #[cfg(feature = "bc15")]
-        let shader_module_bc1_to_5 =
-            device.create_shader_module(include_wgsl!("shader/bc1_to_5.wgsl"));
-        #[cfg(feature = "bc6h")]
-        let shader_module_bc6h = device.create_shader_module(include_wgsl!("shader/bc6h.wgsl"));
-        #[cfg(feature = "bc7")]
-        let shader_module_bc7 = device.create_shader_module(include_wgsl!("shader/bc7.wgsl"));
+        cast_slice(&[uniforms]).to_vec()
+    }
+}
 
-        let uniforms_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("uniforms"),
-            size: (uniforms_aligned_size * 16) as _,
-            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
-            mapped_at_creation: false,
-        });
+/// The compiled pipelines and bind group layouts of the [`GpuBlockCompressor`], which can be
+/// shared between several compressors of the same device.
+///
+/// Creating a compressor compiles the shaders of all enabled variants, which takes a noticeable
+/// amount of time. The pipelines are immutable, so engines that record compression work on
+/// several threads can create them once and build a cheap compressor per thread with
+/// [`GpuBlockCompressor::with_shared_pipelines()`]. Every compressor keeps its own tasks and
+/// uniforms buffers, so the compressors don't need any synchronization between them.
+///
+/// The pipelines also track whether their device was lost, which all compressors that share
+/// them report with [`GpuBlockCompressor::device_lost()`].
+///
+/// # Example
+/// ```ignore
+/// let shared = Arc::new(SharedPipelines::new(device.clone()));
+///
+/// std::thread::scope(|scope| {
+///     for textures in texture_batches {
+///         let shared = shared.clone();
+///         let queue = queue.clone();
+///         scope.spawn(move || {
+///             let mut compressor = GpuBlockCompressor::with_shared_pipelines(shared, queue);
+///             // Add the tasks of the batch and record them into an encoder of this thread.
+///         });
+///     }
+/// });
+/// ```
+pub struct SharedPipelines {
+    device: Arc<Device>,
+    device_lost: Arc<Mutex<Option<CompressorError>>>,
+    bind_group_layouts: HashMap<FormatKey, BindGroupLayout>,
+    pipelines: HashMap<FormatKey, ComputePipeline>,
+    interleaved_bind_group_layouts: HashMap<FormatKey, BindGroupLayout>,
+    interleaved_pipelines: HashMap<FormatKey, ComputePipeline>,
+    normal_map_pipeline: Option<ComputePipeline>,
+    pipelines_16bit: HashMap<FormatKey, ComputePipeline>,
+    texture_array_len: u32,
+    texture_array_bind_group_layouts: HashMap<FormatKey, BindGroupLayout>,
+    texture_array_pipelines: HashMap<FormatKey, ComputePipeline>,
+}
 
-        #[cfg(feature = "bc6h")]
-        let bc6h_settings_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("bc6h settings"),
-            size: (bc6h_aligned_size * 16) as _,
-            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
+impl SharedPipelines {
+    /// Compiles the shaders and creates the pipelines of all enabled variants.
+    ///
+    /// On devices that support texture arrays, the shaders are compiled a second time for the
+    /// pipelines that read their source from a texture array. See
+    /// [`GpuBlockCompressor::texture_array_len()`].
+    ///
+    /// Registers a device lost callback on `device` to detect device loss, which replaces any
+    /// callback that was set before.
+    pub fn new(device: Arc<Device>) -> Self {
+        let device_lost = Arc::new(Mutex::new(None));
+        {
+            let device_lost = device_lost.clone();
+            device.set_device_lost_callback(move |reason, message| {
+                if let Ok(mut device_lost) = device_lost.lock() {
+                    *device_lost = Some(CompressorError::DeviceLost { reason, message });
+                }
+            });
+        }
 
+        #[cfg(feature = "bc15")]
+        let shader_module_bc1_to_5 = GpuBlockCompressor::create_shader_module(
+            &device,
+            include_wgsl!("shader/bc1_to_5.wgsl"),
+        );
+        #[cfg(feature = "bc6h")]
+        let shader_module_bc6h =
+            GpuBlockCompressor::create_shader_module(&device, include_wgsl!("shader/bc6h.wgsl"));
         #[cfg(feature = "bc7")]
-        let bc7_settings_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("bc7 settings"),
-            size: (bc7_aligned_size * 16) as _,
-            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
+        let shader_module_bc7 =
+            GpuBlockCompressor::create_shader_module(&device, include_wgsl!("shader/bc7.wgsl"));
+        let shader_module_rgba8 =
+            GpuBlockCompressor::create_shader_module(&device, include_wgsl!("shader/rgba8.wgsl"));
 
         let mut bind_group_layouts = HashMap::new();
         let mut pipelines = HashMap::new();
+        #[allow(unused_mut)]
+        let mut interleaved_bind_group_layouts = HashMap::new();
+        #[allow(unused_mut)]
+        let mut interleaved_pipelines = HashMap::new();
 
         #[cfg(feature = "bc15")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc1_to_5,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC1,
         );
         #[cfg(feature = "bc15")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc1_to_5,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC2,
         );
         #[cfg(feature = "bc15")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc1_to_5,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC3,
         );
         #[cfg(feature = "bc15")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc1_to_5,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC4,
         );
         #[cfg(feature = "bc15")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc1_to_5,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC5,
         );
         #[cfg(feature = "bc6h")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc6h,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC6H(BC6HSettings::basic()),
         );
         #[cfg(feature = "bc7")]
-        Self::create_pipeline(
+        GpuBlockCompressor::create_pipeline(
             &device,
             &shader_module_bc7,
             &mut bind_group_layouts,
             &mut pipelines,
+            None,
             CompressionVariant::BC7(BC7Settings::alpha_basic()),
         );
+        GpuBlockCompressor::create_pipeline(
+            &device,
+            &shader_module_rgba8,
+            &mut bind_group_layouts,
+            &mut pipelines,
+            None,
+            CompressionVariant::RGBA8,
+        );
+        #[cfg(feature = "bc15")]
+        GpuBlockCompressor::create_interleaved_pipeline(
+            &device,
+            &shader_module_bc1_to_5,
+            &mut interleaved_bind_group_layouts,
+            &mut interleaved_pipelines,
+            CompressionVariant::BC3,
+        );
+        #[cfg(feature = "bc15")]
+        GpuBlockCompressor::create_interleaved_pipeline(
+            &device,
+            &shader_module_bc1_to_5,
+            &mut interleaved_bind_group_layouts,
+            &mut interleaved_pipelines,
+            CompressionVariant::BC5,
+        );
+
+        #[cfg(feature = "bc15")]
+        let normal_map_pipeline = Some(GpuBlockCompressor::create_normal_map_pipeline(
+            &device,
+            &shader_module_bc1_to_5,
+            &bind_group_layouts[&FormatKey::BC5],
+        ));
+        #[cfg(not(feature = "bc15"))]
+        let normal_map_pipeline = None;
+
+        #[allow(unused_mut)]
+        let mut pipelines_16bit = HashMap::new();
+        #[cfg(feature = "bc15")]
+        for variant in [CompressionVariant::BC4, CompressionVariant::BC5] {
+            let pipeline = GpuBlockCompressor::create_16bit_pipeline(
+                &device,
+                &shader_module_bc1_to_5,
+                &bind_group_layouts[&variant.format_key()],
+                variant,
+            );
+            pipelines_16bit.insert(variant.format_key(), pipeline);
+        }
+
+        let texture_array_len = GpuBlockCompressor::device_texture_array_len(&device);
+        let mut texture_array_bind_group_layouts = HashMap::new();
+        let mut texture_array_pipelines = HashMap::new();
+        if let Some(len) = NonZeroU32::new(texture_array_len) {
+            GpuBlockCompressor::create_texture_array_pipelines(
+                &device,
+                len,
+                &mut texture_array_bind_group_layouts,
+                &mut texture_array_pipelines,
+            );
+        }
 
         Self {
-            scratch_buffer: Vec::default(),
-            task: Vec::default(),
-            uniforms_buffer,
-            #[cfg(feature = "bc6h")]
-            bc6h_settings_buffer,
-            #[cfg(feature = "bc7")]
-            bc7_settings_buffer,
+            device,
+            device_lost,
             bind_group_layouts,
             pipelines,
-            device,
-            queue,
-            uniforms_aligned_size,
-            #[cfg(feature = "bc6h")]
-            bc6h_aligned_size,
-            #[cfg(feature = "bc7")]
-            bc7_aligned_size,
+            interleaved_bind_group_layouts,
+            interleaved_pipelines,
+            normal_map_pipeline,
+            pipelines_16bit,
+            texture_array_len,
+            texture_array_bind_group_layouts,
+            texture_array_pipelines,
         }
     }
 
-    #[allow(unused_mut)]
-    fn create_pipeline(
-        device: &Device,
-        shader_module: &ShaderModule,
-        bind_group_layouts: &mut HashMap<CompressionVariant, BindGroupLayout>,
-        pipelines: &mut HashMap<CompressionVariant, ComputePipeline>,
-        variant: CompressionVariant,
-    ) {
-        let mut layout_entries = vec![
-            BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: BindingType::Texture {
-                    sample_type: TextureSampleType::Float { filterable: true },
+    /// Returns the device the pipelines were created with.
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the layout of the bind group that [`GpuBlockCompressor::record_single()`] binds
+    /// for the variant.
+    ///
+    /// The bind group has these entries:
+    ///
+    /// * `0`: The source texture view.
+    /// * `1`: The storage buffer the blocks are written to.
+    /// * `2`: The uniforms buffer with a dynamic offset, bound with a size of at least
+    ///   [`DispatchUniforms::SIZE`] bytes.
+    /// * `3`: For BC6H and BC7, the storage buffer of the [`BC6HSettings`] or [`BC7Settings`]
+    ///   with a dynamic offset. The settings are written with [`bytemuck::bytes_of()`].
+    /// * `4`: The storage buffer the block errors are written to. Needs to be bound even if no
+    ///   errors are written.
+    ///
+    /// [`BC6HSettings`]: crate::BC6HSettings
+    /// [`BC7Settings`]: crate::BC7Settings
+    pub fn bind_group_layout(&self, variant: CompressionVariant) -> &BindGroupLayout {
+        self.bind_group_layouts
+            .get(&variant.format_key())
+            .expect("can't find bind group layout for variant")
+    }
+}
+
+/// Compresses texture data with a block compression algorithm using WGPU compute shader.
+pub struct GpuBlockCompressor {
+    scratch_buffer: Vec<u8>,
+    task: Vec<Task>,
+    next_task_id: u64,
+    completions: Vec<PendingCompletion>,
+    uniforms_buffer: Buffer,
+    dummy_errors_buffer: Buffer,
+    #[cfg(feature = "bc6h")]
+    bc6h_settings_buffer: Buffer,
+    #[cfg(feature = "bc7")]
+    bc7_settings_buffer: Buffer,
+    shared: Arc<SharedPipelines>,
+    #[cfg(feature = "bounds_checks")]
+    bounds_checks: BoundsChecks,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    allocator: Arc<dyn BufferAllocator>,
+    uniforms_aligned_size: usize,
+    storage_offset_alignment: u64,
+    max_tasks_per_pass: usize,
+    max_blocks_per_dispatch: u32,
+    memory_stats: MemoryStats,
+    block_cache: Option<BlockCache>,
+    #[cfg(feature = "bc6h")]
+    bc6h_aligned_size: usize,
+    #[cfg(feature = "bc7")]
+    bc7_aligned_size: usize,
+}
+
+impl GpuBlockCompressor {
+    /// Creates a new block compressor instance.
+    ///
+    /// Only the pipelines of the variants whose features are enabled (`bc15`, `bc6h` and `bc7`)
+    /// are created, and only their shaders are compiled into the binary. The RGBA8 pipeline is
+    /// always created.
+    ///
+    /// The compressor registers a device lost callback on `device` to detect device loss, which
+    /// replaces any callback that was set before. Use
+    /// [`GpuBlockCompressor::with_shared_pipelines()`] to create several compressors for the
+    /// same device.
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self::with_allocator(device, queue, Arc::new(DeviceAllocator))
+    }
+
+    /// Creates a new block compressor instance that allocates its internal buffers with the
+    /// given allocator.
+    ///
+    /// The allocator supplies the uniforms and settings buffers of the tasks and the staging
+    /// buffers of readbacks, see [`BufferAllocator`]. Otherwise the same as
+    /// [`GpuBlockCompressor::new()`].
+    pub fn with_allocator(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        allocator: Arc<dyn BufferAllocator>,
+    ) -> Self {
+        Self::from_shared(Arc::new(SharedPipelines::new(device)), queue, allocator)
+    }
+
+    /// Creates a new block compressor instance that uses the given pipelines instead of
+    /// compiling its own.
+    ///
+    /// The device of the compressor is the device the pipelines were created with. The
+    /// compressor only allocates its own uniforms and settings buffers, which makes it cheap to
+    /// create one compressor per thread, see [`SharedPipelines`].
+    pub fn with_shared_pipelines(shared: Arc<SharedPipelines>, queue: Arc<Queue>) -> Self {
+        Self::from_shared(shared, queue, Arc::new(DeviceAllocator))
+    }
+
+    fn from_shared(
+        shared: Arc<SharedPipelines>,
+        queue: Arc<Queue>,
+        allocator: Arc<dyn BufferAllocator>,
+    ) -> Self {
+        let device = shared.device.clone();
+        let limits = device.limits();
+
+        let alignment = limits.min_uniform_buffer_offset_alignment as usize;
+        let size = size_of::<Uniforms>();
+        let uniforms_aligned_size = size.div_ceil(alignment) * alignment;
+        let storage_offset_alignment = limits.min_storage_buffer_offset_alignment as u64;
+
+        #[cfg(feature = "bc6h")]
+        let bc6h_aligned_size = {
+            let alignment = limits.min_storage_buffer_offset_alignment as usize;
+            let size = size_of::<BC6HSettings>();
+            size.div_ceil(alignment) * alignment
+        };
+
+        #[cfg(feature = "bc7")]
+        let bc7_aligned_size = {
+            let alignment = limits.min_storage_buffer_offset_alignment as usize;
+            let size = size_of::<BC7Settings>();
+            size.div_ceil(alignment) * alignment
+        };
+
+        let uniforms_buffer = allocator.create_buffer(
+            &device,
+            &BufferDescriptor {
+                label: Some("uniforms"),
+                size: (uniforms_aligned_size * 16) as _,
+                usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            },
+        );
+
+        let dummy_errors_buffer = allocator.create_buffer(
+            &device,
+            &BufferDescriptor {
+                label: Some("dummy block errors"),
+                size: 4,
+                usage: BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            },
+        );
+
+        #[cfg(feature = "bc6h")]
+        let bc6h_settings_buffer = allocator.create_buffer(
+            &device,
+            &BufferDescriptor {
+                label: Some("bc6h settings"),
+                size: (bc6h_aligned_size * 16) as _,
+                usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            },
+        );
+
+        #[cfg(feature = "bc7")]
+        let bc7_settings_buffer = allocator.create_buffer(
+            &device,
+            &BufferDescriptor {
+                label: Some("bc7 settings"),
+                size: (bc7_aligned_size * 16) as _,
+                usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            },
+        );
+
+        Self {
+            scratch_buffer: Vec::default(),
+            task: Vec::default(),
+            next_task_id: 0,
+            completions: Vec::default(),
+            uniforms_buffer,
+            dummy_errors_buffer,
+            #[cfg(feature = "bc6h")]
+            bc6h_settings_buffer,
+            #[cfg(feature = "bc7")]
+            bc7_settings_buffer,
+            shared,
+            #[cfg(feature = "bounds_checks")]
+            bounds_checks: BoundsChecks::new(&device),
+            device,
+            queue,
+            allocator,
+            uniforms_aligned_size,
+            storage_offset_alignment,
+            max_tasks_per_pass: DEFAULT_MAX_TASKS_PER_PASS,
+            max_blocks_per_dispatch: u32::MAX,
+            memory_stats: MemoryStats::default(),
+            block_cache: None,
+            #[cfg(feature = "bc6h")]
+            bc6h_aligned_size,
+            #[cfg(feature = "bc7")]
+            bc7_aligned_size,
+        }
+    }
+
+    /// Rebuilds all pipelines and buffers of the compressor on a new device.
+    ///
+    /// Needs to be called after the device of the compressor was lost. All added tasks and all
+    /// callbacks that were not called yet are dropped. Task IDs, the limits of tasks per pass and
+    /// blocks per dispatch, the memory statistics, the block cache and the buffer allocator stay
+    /// the same across the recreation. The compressor creates its own pipelines on the new device,
+    /// so it no longer shares the pipelines of other compressors.
+    pub fn recreate(&mut self, device: Arc<Device>, queue: Arc<Queue>) {
+        let next_task_id = self.next_task_id;
+        let max_tasks_per_pass = self.max_tasks_per_pass;
+        let max_blocks_per_dispatch = self.max_blocks_per_dispatch;
+        let memory_stats = self.memory_stats;
+        let block_cache = self.block_cache.take();
+        *self = Self::with_allocator(device, queue, self.allocator.clone());
+        self.next_task_id = next_task_id;
+        self.max_tasks_per_pass = max_tasks_per_pass;
+        self.max_blocks_per_dispatch = max_blocks_per_dispatch;
+        self.memory_stats = memory_stats;
+        self.block_cache = block_cache;
+    }
+
+    /// Returns the pipelines of the compressor, to create further compressors for the same
+    /// device with [`GpuBlockCompressor::with_shared_pipelines()`].
+    pub fn shared_pipelines(&self) -> &Arc<SharedPipelines> {
+        &self.shared
+    }
+
+    pub(crate) fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    pub(crate) fn queue(&self) -> &Arc<Queue> {
+        &self.queue
+    }
+
+    pub(crate) fn has_pending_tasks(&self) -> bool {
+        !self.task.is_empty()
+    }
+
+    pub(crate) fn pending_completions(&self) -> usize {
+        self.completions.len()
+    }
+
+    /// Drops the completions of the tasks that were dispatched after the first `len` ones.
+    pub(crate) fn truncate_completions(&mut self, len: usize) {
+        self.completions.truncate(len);
+    }
+
+    /// Returns the error if the device of the compressor was lost.
+    pub fn device_lost(&self) -> Option<CompressorError> {
+        self.shared
+            .device_lost
+            .lock()
+            .ok()
+            .and_then(|device_lost| device_lost.clone())
+    }
+
+    /// The maximum number of tasks that [`GpuBlockCompressor::compress_split()`] records into a
+    /// single compute pass.
+    ///
+    /// This is the limit set with [`GpuBlockCompressor::set_max_tasks_per_pass()`], which
+    /// defaults to 256, capped by the number of tasks whose uniforms and settings the device can
+    /// address with dynamic offsets in a single buffer.
+    pub fn max_tasks_per_pass(&self) -> usize {
+        self.max_tasks_per_pass.min(self.device_max_tasks())
+    }
+
+    /// The number of source textures that are bound as a single texture array, or zero if the
+    /// device doesn't support texture arrays.
+    ///
+    /// Devices with the [`Features::TEXTURE_BINDING_ARRAY`] and
+    /// [`Features::PARTIALLY_BOUND_BINDING_ARRAY`] features bind the source textures of up to
+    /// this many tasks in a single bind group, instead of creating a bind group for every task.
+    /// This saves most of the bind groups of batches with thousands of textures. The length is
+    /// limited by `max_sampled_textures_per_shader_stage` of the device limits and 256.
+    ///
+    /// Tasks share a bind group if they have the same format, write into the same blocks buffer
+    /// and errors buffer, and their ranges of these buffers fit into a single binding. Tasks
+    /// with a secondary texture, a height map or 16-bit precision always get their own bind
+    /// group. [`MemoryStats::bind_groups_created`] counts the bind groups that were created.
+    pub fn texture_array_len(&self) -> usize {
+        self.shared.texture_array_len as usize
+    }
+
+    /// Sets the maximum number of tasks that [`GpuBlockCompressor::compress_split()`] records
+    /// into a single compute pass.
+    ///
+    /// # Panics
+    /// - If `max_tasks_per_pass` is zero
+    pub fn set_max_tasks_per_pass(&mut self, max_tasks_per_pass: usize) {
+        assert_ne!(
+            max_tasks_per_pass, 0,
+            "at least one task per pass is needed"
+        );
+        self.max_tasks_per_pass = max_tasks_per_pass;
+    }
+
+    /// The maximum number of blocks a single dispatch compresses.
+    ///
+    /// Tasks with more blocks are split into bands of whole block rows, which are dispatched one
+    /// after another. Defaults to `u32::MAX`, so tasks are only split by
+    /// [`GpuBlockCompressor::set_max_blocks_per_dispatch()`] or
+    /// [`GpuBlockCompressor::set_software_compatibility()`].
+    pub fn max_blocks_per_dispatch(&self) -> u32 {
+        self.max_blocks_per_dispatch
+    }
+
+    /// Sets the maximum number of blocks a single dispatch compresses.
+    ///
+    /// Long running dispatches can trigger the timeout detection of drivers, which resets the
+    /// device. Splitting large tasks into several shorter dispatches avoids this on slow GPUs,
+    /// at the cost of a few more commands. A band holds at least one row of blocks. Tasks
+    /// created with [`GpuBlockCompressor::add_normal_map_compression_task()`] are never split,
+    /// since their height map is filtered across the block rows.
+    ///
+    /// # Panics
+    /// - If `max_blocks_per_dispatch` is zero
+    pub fn set_max_blocks_per_dispatch(&mut self, max_blocks_per_dispatch: u32) {
+        assert_ne!(
+            max_blocks_per_dispatch, 0,
+            "at least one block per dispatch is needed"
+        );
+        self.max_blocks_per_dispatch = max_blocks_per_dispatch;
+    }
+
+    /// Adjusts the limits of the compressor for software adapters like lavapipe, llvmpipe,
+    /// SwiftShader or WARP.
+    ///
+    /// Software adapters run the kernels on the CPU, which is orders of magnitude slower than a
+    /// GPU, so a single large task can run into the timeouts of the driver or of the test
+    /// harness. Enabling the compatibility limits records at most 16 tasks per compute pass and
+    /// splits every dispatch into bands of at most 16384 blocks. The workgroup size stays at 8x8,
+    /// which all adapters support. Disabling it restores the default limits.
+    ///
+    /// Use [`is_software_adapter()`] to detect software adapters and
+    /// [`GpuBlockCompressor::self_check()`] to verify the output on them.
+    ///
+    /// [`is_software_adapter()`]: crate::is_software_adapter
+    pub fn set_software_compatibility(&mut self, enabled: bool) {
+        if enabled {
+            self.max_tasks_per_pass = SOFTWARE_MAX_TASKS_PER_PASS;
+            self.max_blocks_per_dispatch = SOFTWARE_MAX_BLOCKS_PER_DISPATCH;
+        } else {
+            self.max_tasks_per_pass = DEFAULT_MAX_TASKS_PER_PASS;
+            self.max_blocks_per_dispatch = u32::MAX;
+        }
+    }
+
+    /// Returns the counters of the allocations and uploads of the compressor.
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.memory_stats
+    }
+
+    /// Resets all counters of [`GpuBlockCompressor::memory_stats()`] to zero.
+    pub fn reset_memory_stats(&mut self) {
+        self.memory_stats = MemoryStats::default();
+    }
+
+    /// Returns the number of block and error writes the kernels skipped since the compressor
+    /// was created or the counter was reset, because they were outside of the task or the bound
+    /// buffer.
+    ///
+    /// Without the checks, such writes corrupt the output of other tasks that share the buffer.
+    /// A value other than zero is a bug in the compressor. Waits until the GPU has finished all
+    /// submitted work.
+    #[cfg(feature = "bounds_checks")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bounds_checks")))]
+    pub fn bounds_violations(&self) -> u32 {
+        let staging = self.device.create_buffer(&BufferDescriptor {
+            label: Some("bounds violations staging"),
+            size: 4,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&self.bounds_checks.buffer, 0, &staging, 0, 4);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        staging.slice(..).map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+
+        // Mapping only fails if the device was lost, which leaves nothing to count.
+        if !matches!(receiver.try_recv(), Ok(Ok(()))) {
+            return 0;
+        }
+
+        let data = staging.slice(..).get_mapped_range();
+        u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+    }
+
+    /// Resets the counter of [`GpuBlockCompressor::bounds_violations()`] for all work submitted
+    /// afterward.
+    #[cfg(feature = "bounds_checks")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bounds_checks")))]
+    pub fn reset_bounds_violations(&self) {
+        self.queue
+            .write_buffer(&self.bounds_checks.buffer, 0, &0u32.to_le_bytes());
+    }
+
+    /// The number of tasks whose uniforms and settings can be addressed with the `u32` dynamic
+    /// offsets into buffers of the maximal size of the device.
+    fn device_max_tasks(&self) -> usize {
+        #[allow(unused_mut)]
+        let mut aligned_size = self.uniforms_aligned_size;
+        #[cfg(feature = "bc6h")]
+        {
+            aligned_size = aligned_size.max(self.bc6h_aligned_size);
+        }
+        #[cfg(feature = "bc7")]
+        {
+            aligned_size = aligned_size.max(self.bc7_aligned_size);
+        }
+
+        let max_buffer_size = self
+            .device
+            .limits()
+            .max_buffer_size
+            .min(u32::MAX as u64 + 1);
+        (max_buffer_size / aligned_size as u64).max(1) as usize
+    }
+
+    /// Creates a shader module. With the `bounds_checks` feature, the bounds checking variant of
+    /// the shader is created instead.
+    fn create_shader_module(device: &Device, descriptor: ShaderModuleDescriptor) -> ShaderModule {
+        #[cfg(feature = "bounds_checks")]
+        let descriptor = {
+            let ShaderSource::Wgsl(source) = descriptor.source else {
+                unreachable!("all shaders are written in WGSL");
+            };
+            assert!(
+                source.contains(RELEASE_HOOKS),
+                "shader has no bounds check hooks"
+            );
+
+            ShaderModuleDescriptor {
+                label: descriptor.label,
+                source: ShaderSource::Wgsl(
+                    source.replacen(RELEASE_HOOKS, BOUNDS_CHECK_HOOKS, 1).into(),
+                ),
+            }
+        };
+
+        device.create_shader_module(descriptor)
+    }
+
+    /// Creates the layout of a pipeline whose bindings are described by `bind_group_layout`.
+    /// With the `bounds_checks` feature, the violation counter is bound as the second group.
+    fn create_pipeline_layout(
+        device: &Device,
+        label: &str,
+        bind_group_layout: &BindGroupLayout,
+    ) -> PipelineLayout {
+        // Bind group layouts with the same entries are compatible, so every pipeline can use its
+        // own copy of the layout.
+        #[cfg(feature = "bounds_checks")]
+        let bounds_checks_layout = BoundsChecks::create_layout(device);
+
+        device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(label),
+            #[cfg(not(feature = "bounds_checks"))]
+            bind_group_layouts: &[bind_group_layout],
+            #[cfg(feature = "bounds_checks")]
+            bind_group_layouts: &[bind_group_layout, &bounds_checks_layout],
+            push_constant_ranges: &[],
+        })
+    }
+
+    /// Creates the pipeline of a variant. With a `texture_count`, the source is read from a
+    /// texture array of that length, which needs a shader created by [`texture_array_shader()`].
+    #[allow(unused_mut)]
+    fn create_pipeline(
+        device: &Device,
+        shader_module: &ShaderModule,
+        bind_group_layouts: &mut HashMap<FormatKey, BindGroupLayout>,
+        pipelines: &mut HashMap<FormatKey, ComputePipeline>,
+        texture_count: Option<NonZeroU32>,
+        variant: CompressionVariant,
+    ) {
+        let mut layout_entries = vec![
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
                     view_dimension: TextureViewDimension::D2,
                     multisampled: false,
                 },
-                count: None,
+                count: texture_count,
             },
             BindGroupLayoutEntry {
                 binding: 1,
@@ -234,6 +1434,16 @@ impl GpuBlockCompressor {
                 },
                 count: None,
             },
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ];
 
         match variant {
@@ -267,36 +1477,272 @@ impl GpuBlockCompressor {
             _ => {}
         }
 
+        let name = match texture_count {
+            Some(..) => format!("{} texture array", variant.name()),
+            None => variant.name().to_string(),
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some(&format!("{name} bind group layout")),
+            entries: &layout_entries,
+        });
+
+        let pipeline_layout = Self::create_pipeline_layout(
+            device,
+            &format!("{name} block compression pipeline layout"),
+            &bind_group_layout,
+        );
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(&format!("{name} block compression pipeline")),
+            layout: Some(&pipeline_layout),
+            module: shader_module,
+            entry_point: Some(variant.entry_point()),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        bind_group_layouts.insert(variant.format_key(), bind_group_layout);
+        pipelines.insert(variant.format_key(), pipeline);
+    }
+
+    /// Returns the number of source textures the device can bind as a single texture array, or
+    /// zero if it doesn't support partially bound texture arrays.
+    fn device_texture_array_len(device: &Device) -> u32 {
+        let required = Features::TEXTURE_BINDING_ARRAY | Features::PARTIALLY_BOUND_BINDING_ARRAY;
+        if !device.features().contains(required) {
+            return 0;
+        }
+
+        let len = device
+            .limits()
+            .max_sampled_textures_per_shader_stage
+            .min(MAX_TEXTURE_ARRAY_LEN);
+
+        // An array with a single texture doesn't save any bind groups.
+        if len < 2 {
+            0
+        } else {
+            len
+        }
+    }
+
+    /// Compiles the shaders of all enabled variants a second time to read their source from an
+    /// array of `len` textures, and creates their pipelines.
+    fn create_texture_array_pipelines(
+        device: &Device,
+        len: NonZeroU32,
+        bind_group_layouts: &mut HashMap<FormatKey, BindGroupLayout>,
+        pipelines: &mut HashMap<FormatKey, ComputePipeline>,
+    ) {
+        #[cfg(feature = "bc15")]
+        {
+            let shader_module = Self::create_shader_module(
+                device,
+                texture_array_shader(include_wgsl!("shader/bc1_to_5.wgsl"), len),
+            );
+            for variant in [
+                CompressionVariant::BC1,
+                CompressionVariant::BC2,
+                CompressionVariant::BC3,
+                CompressionVariant::BC4,
+                CompressionVariant::BC5,
+            ] {
+                Self::create_pipeline(
+                    device,
+                    &shader_module,
+                    bind_group_layouts,
+                    pipelines,
+                    Some(len),
+                    variant,
+                );
+            }
+        }
+        #[cfg(feature = "bc6h")]
+        Self::create_pipeline(
+            device,
+            &Self::create_shader_module(
+                device,
+                texture_array_shader(include_wgsl!("shader/bc6h.wgsl"), len),
+            ),
+            bind_group_layouts,
+            pipelines,
+            Some(len),
+            CompressionVariant::BC6H(BC6HSettings::basic()),
+        );
+        #[cfg(feature = "bc7")]
+        Self::create_pipeline(
+            device,
+            &Self::create_shader_module(
+                device,
+                texture_array_shader(include_wgsl!("shader/bc7.wgsl"), len),
+            ),
+            bind_group_layouts,
+            pipelines,
+            Some(len),
+            CompressionVariant::BC7(BC7Settings::alpha_basic()),
+        );
+        Self::create_pipeline(
+            device,
+            &Self::create_shader_module(
+                device,
+                texture_array_shader(include_wgsl!("shader/rgba8.wgsl"), len),
+            ),
+            bind_group_layouts,
+            pipelines,
+            Some(len),
+            CompressionVariant::RGBA8,
+        );
+    }
+
+    #[cfg(feature = "bc15")]
+    fn create_interleaved_pipeline(
+        device: &Device,
+        shader_module: &ShaderModule,
+        bind_group_layouts: &mut HashMap<FormatKey, BindGroupLayout>,
+        pipelines: &mut HashMap<FormatKey, ComputePipeline>,
+        variant: CompressionVariant,
+    ) {
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let layout_entries = [
+            texture_entry(0),
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture_entry(3),
+        ];
+
         let name = variant.name();
+        let entry_point = match variant {
+            CompressionVariant::BC3 => "compress_bc3_separate_alpha",
+            CompressionVariant::BC5 => "compress_bc5_interleaved",
+            _ => unreachable!("variant has no interleaved entry point"),
+        };
 
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some(&format!("{name} bind group layout")),
+            label: Some(&format!("{name} interleaved bind group layout")),
             entries: &layout_entries,
         });
 
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some(&format!("{name} block compression pipeline layout")),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
+        let pipeline_layout = Self::create_pipeline_layout(
+            device,
+            &format!("{name} interleaved block compression pipeline layout"),
+            &bind_group_layout,
+        );
 
         let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-            label: Some(&format!("{name} block compression pipeline")),
+            label: Some(&format!("{name} interleaved block compression pipeline")),
             layout: Some(&pipeline_layout),
             module: shader_module,
-            entry_point: Some(variant.entry_point()),
+            entry_point: Some(entry_point),
             compilation_options: PipelineCompilationOptions::default(),
             cache: None,
         });
 
-        bind_group_layouts.insert(variant, bind_group_layout);
-        pipelines.insert(variant, pipeline);
+        bind_group_layouts.insert(variant.format_key(), bind_group_layout);
+        pipelines.insert(variant.format_key(), pipeline);
+    }
+
+    /// Creates the pipeline that generates normals from a height map and compresses them to BC5.
+    /// It uses the same bindings as the BC5 pipeline.
+    #[cfg(feature = "bc15")]
+    fn create_normal_map_pipeline(
+        device: &Device,
+        shader_module: &ShaderModule,
+        bind_group_layout: &BindGroupLayout,
+    ) -> ComputePipeline {
+        let pipeline_layout = Self::create_pipeline_layout(
+            device,
+            "bc5 normal map block compression pipeline layout",
+            bind_group_layout,
+        );
+
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("bc5 normal map block compression pipeline"),
+            layout: Some(&pipeline_layout),
+            module: shader_module,
+            entry_point: Some("compress_bc5_from_height"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        })
+    }
+
+    /// Creates the pipeline of a BC4 or BC5 variant that keeps the 16-bit precision of the
+    /// source. It uses the same bindings as the regular pipeline of the variant.
+    #[cfg(feature = "bc15")]
+    fn create_16bit_pipeline(
+        device: &Device,
+        shader_module: &ShaderModule,
+        bind_group_layout: &BindGroupLayout,
+        variant: CompressionVariant,
+    ) -> ComputePipeline {
+        let name = variant.name();
+
+        let pipeline_layout = Self::create_pipeline_layout(
+            device,
+            &format!("{name} 16-bit block compression pipeline layout"),
+            bind_group_layout,
+        );
+
+        let entry_point = match variant {
+            CompressionVariant::BC4 => "compress_bc4_16bit",
+            CompressionVariant::BC5 => "compress_bc5_16bit",
+            _ => unreachable!("only BC4 and BC5 have a 16-bit pipeline"),
+        };
+
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(&format!("{name} 16-bit block compression pipeline")),
+            layout: Some(&pipeline_layout),
+            module: shader_module,
+            entry_point: Some(entry_point),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        })
     }
 
     /// Adds a texture compression task to the queue.
     ///
     /// This API is designed to be very flexible. For example, it is possible to fill the mip map
     /// levels of a texture with multiple calls to this function.
+    /// [`GpuBlockCompressor::add_mip_compression_task()`] computes the size of a mip level
+    /// instead.
     ///
     /// # Texture View Requirements
     /// The source texture should provide enough channels for the texture compression. If only a
@@ -304,7 +1750,8 @@ impl GpuBlockCompressor {
     /// encoded. All texture compression need to work on the raw texture data. The texture can
     /// use a sRGB texture format, but it needs to provide a view with a non-sRGB texture format.
     /// For example for a texture with a `Rgba8UnormSrgb` texture format, you will need to provide
-    /// a texture view with the `Rgba8Unorm` format.
+    /// a texture view with the `Rgba8Unorm` format, or an `Rgba8UnormSrgb` view together with
+    /// the [`EncodingOptions::source_color_space`] of [`SourceColorSpace::Srgb`].
     ///
     /// BC1, 2, 3, 4, 5 and 7 expect to work on an `unorm` format. `Rgba8Unorm` should be correct
     /// for 99.9% of cases.
@@ -312,6 +1759,10 @@ impl GpuBlockCompressor {
     /// BC6H needs an `unorm` or `float` format. `Rgba16Float` is optimal for HDR textures.
     /// Colors should be in linear space and not in sRGBA space.
     ///
+    /// [`CompressionVariant::RGBA8`] copies the texels without compressing them, so the same task
+    /// can produce uncompressed outputs. Its rows are padded to a multiple of 4 texels, and
+    /// [`CompressionVariant::bytes_per_row()`] returns their size for texture copies.
+    ///
     /// # Buffer Requirements
     /// The destination buffer must have sufficient capacity to store the compressed blocks at the
     /// specified offset. The required size can be calculated using
@@ -332,12 +1783,38 @@ impl GpuBlockCompressor {
     /// * `height` - Height of the texture view in pixels
     /// * `buffer` - Destination storage buffer for the compressed data
     /// * `texture_y_offset` - Optional offset in pixel rows into the source texture
-    /// * `blocks_offset` - Optional offset in bytes into the destination buffer
+    /// * `blocks_offset` - Optional offset in bytes into the destination buffer.
+    ///   [`BlockCoord::byte_offset()`](crate::BlockCoord::byte_offset) returns the offset of a
+    ///   block of a larger texture
+    /// * `options` - The encoding, label, content hash and block errors of the task
+    ///
+    /// # Large Buffers
+    /// The `blocks_offset` is a 64-bit value. The destination buffer is bound at the closest
+    /// aligned offset below `blocks_offset` and only covers the blocks of the task, so only the
+    /// blocks of a single task need to be addressable by the shader. This allows tasks to write
+    /// well beyond the first 4 GiB of a destination buffer.
+    ///
+    /// # Partial Blocks
+    /// The `width` and `height` don't need to be a multiple of 4. The blocks at the right and
+    /// bottom edge then extend past the image and the missing pixels are filled by clamping to
+    /// the edge. Use [`EncodingOptions::edge_fill`] to choose a different strategy.
+    ///
+    /// Returns the ID of the task, which can be used to get notified once the task has finished
+    /// with [`GpuBlockCompressor::on_complete()`].
     ///
     /// # Panics
-    /// - If `width` or `height` or `texture_y_offset`, if set, is not a multiple of 4
+    /// - If `texture_y_offset`, if set, is not a multiple of 4
+    /// - If `blocks_offset`, if set, is not a multiple of 4
     /// - If the destination `buffer` is not a storage buffer
-    /// - If the destination `buffer` is too small to hold the compressed blocks at the specified offset
+    /// - If the destination `buffer` is too small to hold the compressed blocks at the specified
+    ///   offset
+    /// - If the compressed blocks of the task are too large to be addressed by the shader
+    /// - If the settings of BC6H or BC7 fail their `validate()` check
+    /// - If the scale of the [`EncodingOptions::hdr_remap`] is zero or not finite, or its bias
+    ///   is not finite
+    /// - If the `errors_offset` of the options, if set, is not a multiple of 4
+    /// - If the `errors_buffer` of the options is not a storage buffer or too small to hold the
+    ///   errors at the specified offset
     #[allow(clippy::too_many_arguments)]
     pub fn add_compression_task(
         &mut self,
@@ -347,32 +1824,55 @@ impl GpuBlockCompressor {
         height: u32,
         buffer: &Buffer,
         texture_y_offset: Option<u32>,
-        blocks_offset: Option<u32>,
-    ) {
-        assert_eq!(height % 4, 0);
-        assert_eq!(width % 4, 0);
-
+        blocks_offset: Option<ByteOffset>,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
         if let Some(texture_y_offset) = texture_y_offset {
             assert_eq!(texture_y_offset % 4, 0);
         }
 
+        #[cfg(any(feature = "bc6h", feature = "bc7"))]
+        assert_valid_settings(variant);
+        #[cfg(feature = "bc6h")]
+        options.encoding.hdr_remap.validate();
+
+        let blocks_offset = blocks_offset.map_or(0, u64::from);
+        assert_eq!(
+            blocks_offset % 4,
+            0,
+            "blocks offset must be a multiple of 4"
+        );
+
         assert!(
             buffer.usage().contains(BufferUsages::STORAGE),
             "buffer needs to be a storage buffer"
         );
 
-        let required_size = variant.blocks_byte_size(width, height);
-        let total_size = blocks_offset.unwrap_or(0) as usize + required_size;
+        let required_size = variant.blocks_byte_size(width, height) as u64;
+        let total_size = blocks_offset
+            .checked_add(required_size)
+            .expect("blocks offset overflows the addressable buffer range");
 
         assert!(
-            buffer.size() as usize >= total_size,
+            buffer.size() >= total_size,
             "buffer size ({}) is too small to hold compressed blocks at offset {}. Required size: {}",
             buffer.size(),
-            blocks_offset.unwrap_or(0),
+            blocks_offset,
             total_size
         );
 
+        let (buffer_binding_offset, buffer_binding_size, blocks_word_offset) =
+            self.aligned_binding(blocks_offset, required_size);
+
+        let errors = options.errors_buffer.map(|errors_buffer| {
+            self.task_errors(width, height, errors_buffer, options.errors_offset)
+        });
+
+        let id = TaskId(self.next_task_id);
+        self.next_task_id += 1;
+
         self.task.push(Task {
+            id,
             variant,
             width,
             height,
@@ -380,21 +1880,605 @@ impl GpuBlockCompressor {
             #[cfg(any(feature = "bc6h", feature = "bc7"))]
             setting_offset: 0,
             texture_y_offset: texture_y_offset.unwrap_or(0),
-            buffer_offset: blocks_offset.unwrap_or(0),
+            buffer_binding_offset,
+            buffer_binding_size,
+            blocks_offset: blocks_word_offset,
             texture_view: texture_view.clone(),
+            secondary_texture_view: None,
             buffer: buffer.clone(),
+            errors,
+            encoding: options.encoding,
+            normal_map: None,
+            precision_16bit: false,
+            label: options.label.map(str::to_owned),
+            completion: None,
+            texture_slot: None,
+            content_hash: options.content_hash,
+            cache_fill: None,
+        });
+
+        id
+    }
+
+    /// Adds a task that compresses a single mip level of a texture.
+    ///
+    /// Creates a view of the mip level and computes its size from the size of the texture, so
+    /// the width and height can't get out of sync with the level. Levels that are smaller than
+    /// a block are padded like all partial blocks. The view uses the format of the texture and
+    /// its first array layer. All other requirements and arguments are the same as for
+    /// [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut offset = 0;
+    /// for mip_level in 0..texture.mip_level_count() {
+    ///     compressor.add_mip_compression_task(
+    ///         variant,
+    ///         &texture,
+    ///         mip_level,
+    ///         &blocks,
+    ///         Some(ByteOffset(offset)),
+    ///         TaskOptions::default(),
+    ///     );
+    ///
+    ///     let size = texture.size().mip_level_size(mip_level, texture.dimension());
+    ///     offset += variant.blocks_byte_size(size.width, size.height) as u64;
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// - If `mip_level` is not a mip level of `texture`
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    pub fn add_mip_compression_task(
+        &mut self,
+        variant: CompressionVariant,
+        texture: &Texture,
+        mip_level: u32,
+        buffer: &Buffer,
+        blocks_offset: Option<ByteOffset>,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
+        assert!(
+            mip_level < texture.mip_level_count(),
+            "mip level {mip_level} is out of range, the texture has {} levels",
+            texture.mip_level_count()
+        );
+
+        let texture_view = texture.create_view(&TextureViewDescriptor {
+            label: Some("mip level compression view"),
+            dimension: Some(TextureViewDimension::D2),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            array_layer_count: Some(1),
+            ..Default::default()
+        });
+
+        self.add_compression_task(
+            variant,
+            &texture_view,
+            mip_dimension(texture.width(), mip_level),
+            mip_dimension(texture.height(), mip_level),
+            buffer,
+            None,
+            blocks_offset,
+            options,
+        )
+    }
+
+    /// Sets the cache of the blocks of earlier tasks, or removes it with `None`.
+    ///
+    /// Tasks with a [content hash] that is found in the cache are not dispatched. Their cached
+    /// blocks are written into the destination buffer with [`Queue::write_buffer()`], which
+    /// takes effect with the next submission of the queue, and their completion callbacks are
+    /// called like for dispatched tasks. The blocks of the other tasks with a content hash are
+    /// read back and added to the cache, so [`GpuBlockCompressor::record_readbacks()`] needs to
+    /// be called after compressing, like for
+    /// [`GpuBlockCompressor::on_complete_with_readback()`].
+    ///
+    /// Only tasks whose destination buffer can be used as copy source and destination and that
+    /// don't [write block errors] use the cache.
+    ///
+    /// [content hash]: TaskOptions::content_hash
+    /// [write block errors]: TaskOptions::errors_buffer
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// compressor.set_block_cache(Some(BlockCache::new(256 * 1024 * 1024)));
+    ///
+    /// for texture in &textures {
+    ///     let options = TaskOptions {
+    ///         content_hash: Some(texture.content_hash),
+    ///         ..Default::default()
+    ///     };
+    ///     compressor.add_compression_task(
+    ///         variant,
+    ///         &texture.view,
+    ///         width,
+    ///         height,
+    ///         &blocks,
+    ///         None,
+    ///         None,
+    ///         options,
+    ///     );
+    /// }
+    /// ```
+    pub fn set_block_cache(&mut self, cache: Option<BlockCache>) {
+        self.block_cache = cache;
+    }
+
+    /// Returns the block cache of the compressor, if one was set.
+    pub fn block_cache(&self) -> Option<&BlockCache> {
+        self.block_cache.as_ref()
+    }
+
+    /// Returns the binding of the block errors of a task with the given size.
+    fn task_errors(
+        &self,
+        width: u32,
+        height: u32,
+        errors_buffer: &Buffer,
+        errors_offset: Option<u64>,
+    ) -> TaskErrors {
+        let errors_offset = errors_offset.unwrap_or(0);
+        assert_eq!(
+            errors_offset % 4,
+            0,
+            "errors offset must be a multiple of 4"
+        );
+
+        assert!(
+            errors_buffer.usage().contains(BufferUsages::STORAGE),
+            "errors buffer needs to be a storage buffer"
+        );
+
+        let block_count = width.div_ceil(4) as u64 * height.div_ceil(4) as u64;
+        let required_size = block_count * size_of::<f32>() as u64;
+        let total_size = errors_offset
+            .checked_add(required_size)
+            .expect("errors offset overflows the addressable buffer range");
+
+        assert!(
+            errors_buffer.size() >= total_size,
+            "errors buffer size ({}) is too small to hold block errors at offset {}. Required size: {}",
+            errors_buffer.size(),
+            errors_offset,
+            total_size
+        );
+
+        let (binding_offset, binding_size, errors_word_offset) =
+            self.aligned_binding(errors_offset, required_size);
+
+        TaskErrors {
+            buffer: errors_buffer.clone(),
+            binding_offset,
+            binding_size,
+            errors_offset: errors_word_offset,
+        }
+    }
+
+    /// Returns the resources the given task reads and writes once it is dispatched, or `None` if
+    /// the task was already dispatched or does not belong to this compressor.
+    ///
+    /// Render graphs can declare these usages for the pass that records the compressor, instead
+    /// of deriving them from the arguments of the tasks. The list contains:
+    ///
+    /// * The source texture views, which are read. Interleaved tasks read two views and MSAA
+    ///   resolve tasks read their resolved texture.
+    /// * The destination buffer of the blocks and the [`TaskOptions::errors_buffer`], which are
+    ///   written. The range is the range the buffer is bound with. It starts at the closest
+    ///   aligned offset below the offset of the blocks and ends after the last block.
+    ///
+    /// The uniforms and settings buffers of the compressor are owned by the compressor and are
+    /// not listed. The copies of [`GpuBlockCompressor::record_readbacks()`] are recorded
+    /// separately, after the compute pass.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let task = compressor.add_compression_task(
+    ///     variant,
+    ///     &view,
+    ///     width,
+    ///     height,
+    ///     &blocks,
+    ///     None,
+    ///     None,
+    ///     TaskOptions::default(),
+    /// );
+    ///
+    /// for usage in compressor.resource_usages(task).unwrap() {
+    ///     match usage {
+    ///         ResourceUsage::ReadTexture(view) => graph_pass.read_texture(view),
+    ///         ResourceUsage::WriteBuffer { buffer, range } => {
+    ///             graph_pass.write_buffer(buffer, range)
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn resource_usages(&self, task_id: TaskId) -> Option<Vec<ResourceUsage>> {
+        let task = self.task.iter().find(|task| task.id == task_id)?;
+
+        let mut usages = vec![ResourceUsage::ReadTexture(task.texture_view.clone())];
+        if let Some(secondary_texture_view) = &task.secondary_texture_view {
+            usages.push(ResourceUsage::ReadTexture(secondary_texture_view.clone()));
+        }
+
+        usages.push(ResourceUsage::WriteBuffer {
+            buffer: task.buffer.clone(),
+            range: task.buffer_binding_offset
+                ..task.buffer_binding_offset + task.buffer_binding_size,
         });
+        if let Some(errors) = &task.errors {
+            usages.push(ResourceUsage::WriteBuffer {
+                buffer: errors.buffer.clone(),
+                range: errors.binding_offset..errors.binding_offset + errors.binding_size,
+            });
+        }
+
+        Some(usages)
+    }
+
+    /// Registers a callback that is called once the given task has finished on the GPU.
+    ///
+    /// The callback receives the slice of the destination buffer holding the compressed blocks
+    /// of the task, so engine code can start using or copying them per task, instead of waiting
+    /// on a global fence. The notification is built on [`Queue::on_submitted_work_done()`]:
+    ///
+    /// 1. Register the callback before calling [`GpuBlockCompressor::compress()`].
+    /// 2. Submit the command buffer containing the compute pass.
+    /// 3. Call [`GpuBlockCompressor::after_submit()`].
+    ///
+    /// The callback is called from within `Device::poll()` once all work submitted up to that
+    /// point has finished.
+    ///
+    /// # Panics
+    /// - If the task was already dispatched or does not belong to this compressor
+    pub fn on_complete(
+        &mut self,
+        task_id: TaskId,
+        callback: impl FnOnce(CompletedTask) + Send + 'static,
+    ) {
+        self.set_completion(task_id, Box::new(callback), false);
+    }
+
+    /// Registers a callback that is called once the compressed blocks of the given task were
+    /// copied to the CPU.
+    ///
+    /// Works like [`GpuBlockCompressor::on_complete()`], but additionally copies the blocks of
+    /// the task into a staging buffer, which is mapped once the GPU has finished. The data can
+    /// be accessed with [`CompletedTask::mapped_blocks()`]. The copy needs to be recorded with
+    /// [`GpuBlockCompressor::record_readbacks()`] after the compute pass has ended and before
+    /// the command buffer is submitted.
+    ///
+    /// # Panics
+    /// - If the task was already dispatched or does not belong to this compressor
+    /// - If the destination buffer of the task can't be used as a copy source
+    pub fn on_complete_with_readback(
+        &mut self,
+        task_id: TaskId,
+        callback: impl FnOnce(CompletedTask) + Send + 'static,
+    ) {
+        self.set_completion(task_id, Box::new(callback), true);
+    }
+
+    fn set_completion(&mut self, task_id: TaskId, callback: CompletionCallback, readback: bool) {
+        let task = self
+            .task
+            .iter_mut()
+            .find(|task| task.id == task_id)
+            .expect("task was already dispatched or does not exist");
+
+        if readback {
+            assert!(
+                task.buffer.usage().contains(BufferUsages::COPY_SRC),
+                "buffer needs to be a copy source for readbacks"
+            );
+        }
+
+        task.completion = Some(Completion { callback, readback });
+    }
+
+    /// Returns the aligned binding offset, the binding size and the remaining offset in u32
+    /// elements for a range of a storage buffer.
+    fn aligned_binding(&self, offset: u64, size: u64) -> (u64, u64, u32) {
+        let binding_offset = offset - (offset % self.storage_offset_alignment);
+        let word_offset = (offset - binding_offset) / 4;
+
+        assert!(
+            word_offset + size / 4 <= u32::MAX as u64,
+            "output of a single task can't be addressed by the shader"
+        );
+
+        (binding_offset, word_offset * 4 + size, word_offset as u32)
+    }
+
+    /// Adds a texture compression task that reads its channels from two separate textures.
+    ///
+    /// This avoids a packing pass for material pipelines that store their channels in separate
+    /// textures. The following variants are supported:
+    ///
+    /// * BC3: The color is read from the RGB channels of `texture_view` and the alpha from the red
+    ///   channel of `secondary_texture_view`.
+    /// * BC5: The red channel is read from the red channel of `texture_view` and the green channel
+    ///   from the red channel of `secondary_texture_view`.
+    ///
+    /// Both views need to be at least `width` x `height` (plus `texture_y_offset`) in size. All
+    /// other requirements and arguments are the same as for
+    /// [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Panics
+    /// - If `variant` is not BC3 or BC5
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_interleaved_compression_task(
+        &mut self,
+        variant: CompressionVariant,
+        texture_view: &TextureView,
+        secondary_texture_view: &TextureView,
+        width: u32,
+        height: u32,
+        buffer: &Buffer,
+        texture_y_offset: Option<u32>,
+        blocks_offset: Option<ByteOffset>,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
+        assert!(
+            self.shared
+                .interleaved_pipelines
+                .contains_key(&variant.format_key()),
+            "only BC3 and BC5 support interleaved compression tasks"
+        );
+
+        let id = self.add_compression_task(
+            variant,
+            texture_view,
+            width,
+            height,
+            buffer,
+            texture_y_offset,
+            blocks_offset,
+            options,
+        );
+
+        if let Some(task) = self.task.last_mut() {
+            task.secondary_texture_view = Some(secondary_texture_view.clone());
+        }
+
+        id
+    }
+
+    /// Adds a BC4 or BC5 compression task that keeps the full precision of a 16-bit source.
+    ///
+    /// Height maps and other single channel data are often stored as `R16Unorm` or `Rg16Unorm`.
+    /// The regular kernels truncate the minimum and maximum of a block to 8-bit endpoints and
+    /// pick the indices for the unquantized endpoints, which costs up to a full 8-bit step on
+    /// smooth data. This task reads the 16-bit values of every texel, tries the 8-bit endpoints
+    /// around the minimum, the maximum and a least squares fit, and picks the indices for the
+    /// quantized endpoints.
+    ///
+    /// The texture view should use a 16-bit `unorm` format. Views with less precision work as
+    /// well, but don't gain anything. All other requirements and arguments are the same as for
+    /// [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Panics
+    /// - If `variant` is not `CompressionVariant::BC4` or `CompressionVariant::BC5`
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_16bit_compression_task(
+        &mut self,
+        variant: CompressionVariant,
+        texture_view: &TextureView,
+        width: u32,
+        height: u32,
+        buffer: &Buffer,
+        texture_y_offset: Option<u32>,
+        blocks_offset: Option<ByteOffset>,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
+        assert!(
+            matches!(variant, CompressionVariant::BC4 | CompressionVariant::BC5),
+            "only BC4 and BC5 support 16-bit source precision"
+        );
+
+        let id = self.add_compression_task(
+            variant,
+            texture_view,
+            width,
+            height,
+            buffer,
+            texture_y_offset,
+            blocks_offset,
+            options,
+        );
+
+        if let Some(task) = self.task.last_mut() {
+            task.precision_16bit = true;
+        }
+
+        id
+    }
+
+    /// Adds a task that generates a normal map from a height map and compresses it to BC5.
+    ///
+    /// The height is read from the red channel of `texture_view`. The X and Y components of the
+    /// normals are written into the red and green channel of the BC5 blocks, mapped from
+    /// `[-1, 1]` to `[0, 1]`. The Z component can be reconstructed in the shader. This saves the
+    /// separate pass to generate the normal map, which terrain pipelines need otherwise.
+    ///
+    /// The gradient at the image border is computed by repeating the border pixels. All other
+    /// requirements and arguments are the same as for
+    /// [`GpuBlockCompressor::add_compression_task()`] with `CompressionVariant::BC5`.
+    ///
+    /// # Panics
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_normal_map_compression_task(
+        &mut self,
+        texture_view: &TextureView,
+        width: u32,
+        height: u32,
+        buffer: &Buffer,
+        texture_y_offset: Option<u32>,
+        blocks_offset: Option<ByteOffset>,
+        settings: NormalMapSettings,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
+        let id = self.add_compression_task(
+            CompressionVariant::BC5,
+            texture_view,
+            width,
+            height,
+            buffer,
+            texture_y_offset,
+            blocks_offset,
+            options,
+        );
+
+        if let Some(task) = self.task.last_mut() {
+            task.normal_map = Some(settings);
+        }
+
+        id
+    }
+
+    /// Resolves a multisampled render target and adds a task to compress the resolved image.
+    ///
+    /// The resolve is recorded into `encoder` as a render pass without draw calls, which creates
+    /// a single sampled texture of the same size and format. Record the compression afterward
+    /// into the same encoder, for example with [`GpuBlockCompressor::compress_split()`], so
+    /// anti-aliased frames like photo mode screenshots can be captured directly into compressed
+    /// blocks with a single submission. The multisampled texture keeps its content.
+    ///
+    /// The resolved image has the size of `multisampled_texture`. All other requirements and
+    /// arguments are the same as for [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Panics
+    /// - If `multisampled_texture` is not a multisampled 2D texture with a single layer
+    /// - If `multisampled_texture` can't be used as a render attachment
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    pub fn add_msaa_resolve_compression_task(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        variant: CompressionVariant,
+        multisampled_texture: &Texture,
+        buffer: &Buffer,
+        blocks_offset: Option<ByteOffset>,
+        options: TaskOptions<'_>,
+    ) -> TaskId {
+        assert!(
+            multisampled_texture.sample_count() > 1,
+            "texture needs to be multisampled"
+        );
+        assert!(
+            multisampled_texture.dimension() == TextureDimension::D2
+                && multisampled_texture.depth_or_array_layers() == 1,
+            "texture needs to be a 2D texture with a single layer"
+        );
+        assert!(
+            multisampled_texture
+                .usage()
+                .contains(TextureUsages::RENDER_ATTACHMENT),
+            "texture needs to be a render attachment"
+        );
+
+        let resolve_texture = self.device.create_texture(&TextureDescriptor {
+            label: Some("msaa resolve texture"),
+            size: multisampled_texture.size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: multisampled_texture.format(),
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let resolve_view = resolve_texture.create_view(&TextureViewDescriptor::default());
+
+        let multisampled_view = multisampled_texture.create_view(&TextureViewDescriptor {
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("msaa resolve"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &multisampled_view,
+                resolve_target: Some(&resolve_view),
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.add_compression_task(
+            variant,
+            &resolve_view,
+            multisampled_texture.width(),
+            multisampled_texture.height(),
+            buffer,
+            None,
+            blocks_offset,
+            options,
+        )
+    }
+
+    /// Returns the block rows of the dispatches of a task. Tasks with more blocks than the
+    /// maximum per dispatch are split into bands of whole block rows.
+    fn bands(&self, task: &Task) -> Vec<Range<u32>> {
+        let block_width = task.width.div_ceil(4);
+        let block_height = task.height.div_ceil(4);
+
+        let band_rows = if task.normal_map.is_some()
+            || block_width * block_height <= self.max_blocks_per_dispatch
+        {
+            block_height
+        } else {
+            (self.max_blocks_per_dispatch / block_width).max(1)
+        };
+
+        let mut bands = Vec::new();
+        let mut start = 0;
+        while start < block_height {
+            let mut end = (start + band_rows).min(block_height);
+
+            // The edge fill of a partial last block row can read the rows above it, so it is
+            // never dispatched as a band of its own.
+            if end + 1 == block_height && task.height % 4 != 0 {
+                end += 1;
+            }
+
+            bands.push(start..end);
+            start = end;
+        }
+
+        bands
     }
 
     fn update_buffer_sizes(&mut self) {
-        let total_uniforms_size = self.uniforms_aligned_size * self.task.len();
+        let uniforms_count: usize = self.task.iter().map(|task| self.bands(task).len()).sum();
+        let total_uniforms_size = self.uniforms_aligned_size * uniforms_count;
         if total_uniforms_size > self.uniforms_buffer.size() as usize {
-            self.uniforms_buffer = self.device.create_buffer(&BufferDescriptor {
-                label: Some("uniforms buffer"),
-                size: total_uniforms_size as u64,
-                usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
-                mapped_at_creation: false,
-            });
+            let uniforms_buffer = self.allocator.create_buffer(
+                &self.device,
+                &BufferDescriptor {
+                    label: Some("uniforms buffer"),
+                    size: total_uniforms_size as u64,
+                    usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+                    mapped_at_creation: false,
+                },
+            );
+            let previous = std::mem::replace(&mut self.uniforms_buffer, uniforms_buffer);
+            self.allocator.release_buffer(previous);
+            self.memory_stats.buffers_reallocated += 1;
         }
 
         #[cfg(feature = "bc6h")]
@@ -407,12 +2491,18 @@ impl GpuBlockCompressor {
 
             let total_bc6h_size = self.bc6h_aligned_size * bc6_setting_count;
             if total_bc6h_size > self.bc6h_settings_buffer.size() as usize {
-                self.bc6h_settings_buffer = self.device.create_buffer(&BufferDescriptor {
-                    label: Some("bc6h settings buffer"),
-                    size: total_bc6h_size as u64,
-                    usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-                    mapped_at_creation: false,
-                });
+                let settings_buffer = self.allocator.create_buffer(
+                    &self.device,
+                    &BufferDescriptor {
+                        label: Some("bc6h settings buffer"),
+                        size: total_bc6h_size as u64,
+                        usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                        mapped_at_creation: false,
+                    },
+                );
+                let previous = std::mem::replace(&mut self.bc6h_settings_buffer, settings_buffer);
+                self.allocator.release_buffer(previous);
+                self.memory_stats.buffers_reallocated += 1;
             }
         }
 
@@ -426,33 +2516,64 @@ impl GpuBlockCompressor {
 
             let total_bc7_size = self.bc7_aligned_size * bc7_setting_count;
             if total_bc7_size > self.bc7_settings_buffer.size() as usize {
-                self.bc7_settings_buffer = self.device.create_buffer(&BufferDescriptor {
-                    label: Some("bc7 settings buffer"),
-                    size: total_bc7_size as u64,
-                    usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-                    mapped_at_creation: false,
-                });
+                let settings_buffer = self.allocator.create_buffer(
+                    &self.device,
+                    &BufferDescriptor {
+                        label: Some("bc7 settings buffer"),
+                        size: total_bc7_size as u64,
+                        usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                        mapped_at_creation: false,
+                    },
+                );
+                let previous = std::mem::replace(&mut self.bc7_settings_buffer, settings_buffer);
+                self.allocator.release_buffer(previous);
+                self.memory_stats.buffers_reallocated += 1;
             }
         }
     }
 
     fn upload(&mut self) {
         self.scratch_buffer.clear();
-        for (index, task) in self.task.iter_mut().enumerate() {
-            let offset = index * self.uniforms_aligned_size;
+        let mut offset = 0;
+        for index in 0..self.task.len() {
+            let bands = self.bands(&self.task[index]);
+            let task = &mut self.task[index];
             task.uniform_offset = offset as u32;
 
-            let uniforms = Uniforms {
-                width: task.width,
-                height: task.height,
-                texture_y_offset: task.texture_y_offset,
-                blocks_offset: task.buffer_offset / 4,
-            };
+            let block_width = task.width.div_ceil(4);
+            let block_words = task.variant.block_byte_size() / 4;
+            let slot = task.texture_slot.unwrap_or_default();
+
+            for band in bands {
+                let uniforms = Uniforms {
+                    width: task.width,
+                    height: task.height.min(band.end * 4) - band.start * 4,
+                    texture_y_offset: task.texture_y_offset + band.start * 4,
+                    blocks_offset: slot.blocks_rebase
+                        + task.blocks_offset
+                        + band.start * block_width * block_words,
+                    errors_offset: task.errors.as_ref().map_or(0, |errors| {
+                        slot.errors_rebase + errors.errors_offset + band.start * block_width
+                    }),
+                    write_errors: task.errors.is_some() as u32,
+                    normal_filter: task
+                        .normal_map
+                        .map_or(0, |normal_map| normal_map.filter as u32),
+                    normal_strength: task
+                        .normal_map
+                        .map_or(0.0, |normal_map| normal_map.strength),
+                    normal_flip_y: task.normal_map.is_some_and(|normal_map| normal_map.flip_y)
+                        as u32,
+                    texture_index: slot.texture_index,
+                    ..task.encoding.uniforms()
+                };
 
-            self.scratch_buffer
-                .resize(offset + self.uniforms_aligned_size, 0);
-            self.scratch_buffer[offset..offset + size_of::<Uniforms>()]
-                .copy_from_slice(cast_slice(&[uniforms]));
+                self.scratch_buffer
+                    .resize(offset + self.uniforms_aligned_size, 0);
+                self.scratch_buffer[offset..offset + size_of::<Uniforms>()]
+                    .copy_from_slice(cast_slice(&[uniforms]));
+                offset += self.uniforms_aligned_size;
+            }
         }
         if !self.scratch_buffer.is_empty() {
             if let Some(mut data) = self.queue.write_buffer_with(
@@ -462,6 +2583,7 @@ impl GpuBlockCompressor {
             ) {
                 data.copy_from_slice(&self.scratch_buffer);
             }
+            self.memory_stats.bytes_uploaded += self.scratch_buffer.len() as u64;
         }
 
         #[cfg(feature = "bc6h")]
@@ -495,6 +2617,7 @@ impl GpuBlockCompressor {
                 ) {
                     data.copy_from_slice(&self.scratch_buffer);
                 }
+                self.memory_stats.bytes_uploaded += self.scratch_buffer.len() as u64;
             }
         }
 
@@ -529,80 +2652,550 @@ impl GpuBlockCompressor {
                 ) {
                     data.copy_from_slice(&self.scratch_buffer);
                 }
+                self.memory_stats.bytes_uploaded += self.scratch_buffer.len() as u64;
             }
         }
     }
 
     /// Will upload all dispatch data and then dispatches all compression tasks to the GPU.
     ///
+    /// All tasks are recorded into `pass`, no matter how many were added. Use
+    /// [`GpuBlockCompressor::compress_split()`] to spread a large number of tasks over several
+    /// compute passes.
+    ///
+    /// # Arguments
+    /// * `pass` - The compute pass to record commands into
+    ///
+    /// # Errors
+    /// - [`CompressorError::DeviceLost`] if the device was lost. All added tasks are dropped
+    ///   without recording any commands. Use [`GpuBlockCompressor::recreate()`] to continue on a
+    ///   new device.
+    pub fn compress(&mut self, pass: &mut ComputePass) -> Result<(), CompressorError> {
+        let bind_groups = self.prepare()?;
+
+        let mut tasks = std::mem::take(&mut self.task);
+        for (task, bind_group) in tasks.drain(..).zip(bind_groups) {
+            self.dispatch(pass, task, &bind_group);
+        }
+        self.task = tasks;
+
+        Ok(())
+    }
+
+    /// Will upload the dispatch data of the oldest `max_tasks` tasks and then dispatches only
+    /// those to the GPU. Returns the number of tasks that are left for later calls.
+    ///
+    /// Allows spreading a very large batch over several frames by calling this once per frame
+    /// until it returns zero. The remaining tasks keep their order, destination buffers and
+    /// completion callbacks, and new tasks can be added in between. Every chunk needs to be
+    /// submitted before the next call, since all chunks share the buffers of the dispatch data.
+    ///
     /// # Arguments
     /// * `pass` - The compute pass to record commands into
-    pub fn compress(&mut self, pass: &mut ComputePass) {
+    /// * `max_tasks` - The maximal number of tasks to record
+    ///
+    /// # Errors
+    /// - [`CompressorError::DeviceLost`] if the device was lost. All added tasks are dropped
+    ///   without recording any commands. Use [`GpuBlockCompressor::recreate()`] to continue on a
+    ///   new device.
+    ///
+    /// # Panics
+    /// - If `max_tasks` is zero
+    pub fn compress_chunk(
+        &mut self,
+        pass: &mut ComputePass,
+        max_tasks: usize,
+    ) -> Result<usize, CompressorError> {
+        assert_ne!(max_tasks, 0, "at least one task per chunk is needed");
+
+        let remaining = self.task.split_off(max_tasks.min(self.task.len()));
+        let bind_groups = self.prepare()?;
+
+        let tasks = std::mem::replace(&mut self.task, remaining);
+        for (task, bind_group) in tasks.into_iter().zip(bind_groups) {
+            self.dispatch(pass, task, &bind_group);
+        }
+
+        Ok(self.task.len())
+    }
+
+    /// Will upload all dispatch data and then dispatches all compression tasks to the GPU,
+    /// beginning a new compute pass after every [`GpuBlockCompressor::max_tasks_per_pass()`]
+    /// tasks.
+    ///
+    /// # Arguments
+    /// * `encoder` - The command encoder to begin the compute passes on
+    ///
+    /// # Errors
+    /// - [`CompressorError::DeviceLost`] if the device was lost. All added tasks are dropped
+    ///   without recording any commands. Use [`GpuBlockCompressor::recreate()`] to continue on a
+    ///   new device.
+    pub fn compress_split(&mut self, encoder: &mut CommandEncoder) -> Result<(), CompressorError> {
+        let bind_groups = self.prepare()?;
+        let max_tasks_per_pass = self.max_tasks_per_pass();
+
+        let mut tasks = std::mem::take(&mut self.task);
+        let mut dispatches = tasks.drain(..).zip(bind_groups).peekable();
+        while dispatches.peek().is_some() {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("block compression"),
+                timestamp_writes: None,
+            });
+
+            for (task, bind_group) in dispatches.by_ref().take(max_tasks_per_pass) {
+                self.dispatch(&mut pass, task, &bind_group);
+            }
+        }
+        drop(dispatches);
+        self.task = tasks;
+
+        Ok(())
+    }
+
+    /// Uploads the dispatch data of all tasks and creates their bind groups.
+    fn prepare(&mut self) -> Result<Vec<BindGroup>, CompressorError> {
+        if let Some(error) = self.device_lost() {
+            self.task.clear();
+            self.completions.clear();
+            return Err(error);
+        }
+
+        self.serve_cached_tasks();
         self.update_buffer_sizes();
+        let groups = self.group_texture_arrays();
         self.upload();
 
-        let mut bind_groups: Vec<BindGroup> = self
+        let mut bind_groups = vec![None; self.task.len()];
+        for group in &groups {
+            let bind_group = self.create_texture_array_bind_group(group);
+            for &index in &group.tasks {
+                bind_groups[index] = Some(bind_group.clone());
+            }
+        }
+
+        let grouped_tasks: usize = groups.iter().map(|group| group.tasks.len()).sum();
+        self.memory_stats.compress_calls += 1;
+        self.memory_stats.bind_groups_created +=
+            (self.task.len() - grouped_tasks + groups.len()) as u64;
+
+        Ok(self
             .task
             .iter()
-            .map(|task| self.create_bind_group(task))
-            .collect();
+            .zip(bind_groups)
+            .map(|(task, bind_group)| bind_group.unwrap_or_else(|| self.create_bind_group(task)))
+            .collect())
+    }
 
-        for (task, bind_group) in self.task.drain(..).zip(bind_groups.drain(..)) {
-            let pipeline = self
-                .pipelines
-                .get(&task.variant)
-                .expect("can't find pipeline for variant");
+    /// Writes the cached blocks of the tasks that are found in the block cache and removes
+    /// these tasks from the batch. The other tasks with a content hash read their blocks back
+    /// to fill the cache.
+    fn serve_cached_tasks(&mut self) {
+        let Some(cache) = self.block_cache.clone() else {
+            return;
+        };
 
-            pass.set_pipeline(pipeline);
+        for mut task in std::mem::take(&mut self.task) {
+            let cacheable = task.errors.is_none()
+                && task
+                    .buffer
+                    .usage()
+                    .contains(BufferUsages::COPY_SRC | BufferUsages::COPY_DST);
+            let Some(content_hash) = task.content_hash.filter(|_| cacheable) else {
+                self.task.push(task);
+                continue;
+            };
 
-            match task.variant {
-                #[cfg(feature = "bc6h")]
-                CompressionVariant::BC6H(..) => {
-                    pass.set_bind_group(
-                        0,
-                        &bind_group,
-                        &[task.uniform_offset, task.setting_offset],
-                    );
-                }
-                #[cfg(feature = "bc7")]
-                CompressionVariant::BC7(..) => {
-                    pass.set_bind_group(
-                        0,
-                        &bind_group,
-                        &[task.uniform_offset, task.setting_offset],
-                    );
-                }
-                #[allow(irrefutable_let_patterns)]
-                #[allow(unreachable_patterns)]
-                _ => {
-                    pass.set_bind_group(0, &bind_group, &[task.uniform_offset]);
+            let key = CacheKey {
+                content_hash,
+                variant: task.variant,
+                width: task.width,
+                height: task.height,
+            };
+
+            if let Some(blocks) = cache.lookup(&key) {
+                let offset = task.buffer_binding_offset + task.blocks_offset as u64 * 4;
+                self.queue.write_buffer(&task.buffer, offset, &blocks);
+                self.push_completion(task);
+            } else {
+                task.completion
+                    .get_or_insert_with(|| Completion {
+                        callback: Box::new(|_| {}),
+                        readback: true,
+                    })
+                    .readback = true;
+                task.cache_fill = Some(key);
+                self.task.push(task);
+            }
+        }
+    }
+
+    /// Groups the tasks that can share a texture array bind group and assigns them their slots.
+    /// Tasks that can't share a bind group with another task keep their own bind group.
+    fn group_texture_arrays(&mut self) -> Vec<TextureArrayGroup> {
+        for task in &mut self.task {
+            task.texture_slot = None;
+        }
+
+        let len = self.texture_array_len();
+        if len == 0 {
+            return Vec::new();
+        }
+
+        let max_binding_size = self.device.limits().max_storage_buffer_binding_size as u64;
+        let merge = |a: &Range<u64>, b: &Range<u64>| {
+            let merged = a.start.min(b.start)..a.end.max(b.end);
+            (merged.end - merged.start <= max_binding_size).then_some(merged)
+        };
+
+        let mut groups: Vec<TextureArrayGroup> = Vec::new();
+        for (index, task) in self.task.iter().enumerate() {
+            if task.secondary_texture_view.is_some()
+                || task.normal_map.is_some()
+                || task.precision_16bit
+            {
+                continue;
+            }
+
+            let blocks =
+                task.buffer_binding_offset..task.buffer_binding_offset + task.buffer_binding_size;
+            let errors = task
+                .errors
+                .as_ref()
+                .map(|errors| errors.binding_offset..errors.binding_offset + errors.binding_size);
+
+            let joined = groups.iter_mut().any(|group| {
+                let first = &self.task[group.tasks[0]];
+                if group.tasks.len() == len
+                    || first.variant.format_key() != task.variant.format_key()
+                    || first.buffer != task.buffer
+                    || first.errors.as_ref().map(|errors| &errors.buffer)
+                        != task.errors.as_ref().map(|errors| &errors.buffer)
+                {
+                    return false;
                 }
+
+                let Some(merged_blocks) = merge(&group.blocks, &blocks) else {
+                    return false;
+                };
+                let merged_errors = match (&group.errors, &errors) {
+                    (Some(group_errors), Some(errors)) => match merge(group_errors, errors) {
+                        Some(merged) => Some(merged),
+                        None => return false,
+                    },
+                    _ => None,
+                };
+
+                group.tasks.push(index);
+                group.blocks = merged_blocks;
+                group.errors = merged_errors;
+                true
+            });
+
+            if !joined {
+                groups.push(TextureArrayGroup {
+                    tasks: vec![index],
+                    blocks,
+                    errors,
+                });
+            }
+        }
+
+        groups.retain(|group| group.tasks.len() > 1);
+
+        for group in &groups {
+            for (texture_index, &index) in group.tasks.iter().enumerate() {
+                let task = &mut self.task[index];
+                let errors_rebase = match (&task.errors, &group.errors) {
+                    (Some(errors), Some(group_errors)) => {
+                        ((errors.binding_offset - group_errors.start) / 4) as u32
+                    }
+                    _ => 0,
+                };
+
+                task.texture_slot = Some(TextureSlot {
+                    texture_index: texture_index as u32,
+                    blocks_rebase: ((task.buffer_binding_offset - group.blocks.start) / 4) as u32,
+                    errors_rebase,
+                });
             }
+        }
+
+        groups
+    }
 
-            let block_width = (task.width + 3) / 4;
-            let block_height = (task.height + 3) / 4;
+    fn dispatch(&mut self, pass: &mut ComputePass, task: Task, bind_group: &BindGroup) {
+        let pipeline = if task.normal_map.is_some() {
+            self.shared
+                .normal_map_pipeline
+                .as_ref()
+                .expect("can't find normal map pipeline")
+        } else if task.precision_16bit {
+            self.shared
+                .pipelines_16bit
+                .get(&task.variant.format_key())
+                .expect("can't find 16-bit pipeline for variant")
+        } else if task.secondary_texture_view.is_some() {
+            self.shared
+                .interleaved_pipelines
+                .get(&task.variant.format_key())
+                .expect("can't find interleaved pipeline for variant")
+        } else if task.texture_slot.is_some() {
+            self.shared
+                .texture_array_pipelines
+                .get(&task.variant.format_key())
+                .expect("can't find texture array pipeline for variant")
+        } else {
+            self.shared
+                .pipelines
+                .get(&task.variant.format_key())
+                .expect("can't find pipeline for variant")
+        };
+
+        pass.set_pipeline(pipeline);
+        #[cfg(feature = "bounds_checks")]
+        pass.set_bind_group(1, &self.bounds_checks.bind_group, &[]);
+
+        if let Some(label) = task.label.as_deref() {
+            pass.push_debug_group(label);
+        }
 
-            let workgroup_width = (block_width + 7) / 8;
-            let workgroup_height = (block_height + 7) / 8;
+        for (index, band) in self.bands(&task).into_iter().enumerate() {
+            let uniform_offset = task.uniform_offset + (index * self.uniforms_aligned_size) as u32;
+            set_dispatch_bind_group(
+                pass,
+                task.variant,
+                bind_group,
+                uniform_offset,
+                #[cfg(any(feature = "bc6h", feature = "bc7"))]
+                task.setting_offset,
+                #[cfg(not(any(feature = "bc6h", feature = "bc7")))]
+                0,
+            );
 
+            let band_height = task.height.min(band.end * 4) - band.start * 4;
+            let [workgroup_width, workgroup_height] = workgroup_count(task.width, band_height);
             pass.dispatch_workgroups(workgroup_width, workgroup_height, 1);
         }
+
+        if task.label.is_some() {
+            pass.pop_debug_group();
+        }
+
+        self.push_completion(task);
+    }
+
+    /// Queues the completion callback of a task that was dispatched or served from the block
+    /// cache.
+    fn push_completion(&mut self, task: Task) {
+        if let Some(completion) = task.completion {
+            let offset = task.buffer_binding_offset + task.blocks_offset as u64 * 4;
+            let size = task.buffer_binding_size - task.blocks_offset as u64 * 4;
+
+            self.completions.push(PendingCompletion {
+                id: task.id,
+                buffer: task.buffer,
+                offset,
+                size,
+                callback: completion.callback,
+                readback: completion.readback,
+                staging: None,
+                cache_fill: task.cache_fill.zip(self.block_cache.clone()),
+                #[cfg(feature = "bc6h")]
+                hdr_remap: task.encoding.hdr_remap,
+            });
+        }
+    }
+
+    /// Records a single dispatch of the pipeline of the variant, without adding a task.
+    ///
+    /// A building block for integrators that manage their own uniforms and settings buffers and
+    /// bind groups, for example to pack them into the buffers of their renderer, while reusing
+    /// the pipelines and shaders of the compressor. The bind group needs to be created with
+    /// [`SharedPipelines::bind_group_layout()`] of the variant, and the uniforms at
+    /// `uniform_offset` with [`DispatchUniforms::to_bytes()`].
+    ///
+    /// None of the state of the compressor is used, besides the bounds checks of the
+    /// `bounds_checks` feature. The dispatch isn't split, so large textures can exceed the
+    /// limits of the device on workgroups per dimension.
+    ///
+    /// # Arguments
+    /// * `pass` - The compute pass to record the dispatch into
+    /// * `variant` - The variant whose pipeline is dispatched
+    /// * `bind_group` - The bind group of the dispatch
+    /// * `uniform_offset` - The dynamic offset of the uniforms binding
+    /// * `settings_offset` - The dynamic offset of the settings binding of BC6H and BC7. Ignored
+    ///   for all other variants
+    /// * `dispatch_dims` - The number of workgroups in X and Y, as returned in the
+    ///   [`DispatchReport::workgroup_count`](crate::diagnostics::DispatchReport::workgroup_count)
+    ///   of [`dispatch_report()`](crate::diagnostics::dispatch_report)
+    pub fn record_single(
+        &self,
+        pass: &mut ComputePass,
+        variant: CompressionVariant,
+        bind_group: &BindGroup,
+        uniform_offset: u32,
+        settings_offset: u32,
+        dispatch_dims: [u32; 2],
+    ) {
+        let pipeline = self
+            .shared
+            .pipelines
+            .get(&variant.format_key())
+            .expect("can't find pipeline for variant");
+
+        pass.set_pipeline(pipeline);
+        #[cfg(feature = "bounds_checks")]
+        pass.set_bind_group(1, &self.bounds_checks.bind_group, &[]);
+
+        set_dispatch_bind_group(pass, variant, bind_group, uniform_offset, settings_offset);
+        pass.dispatch_workgroups(dispatch_dims[0], dispatch_dims[1], 1);
+    }
+
+    /// Records the copies of all dispatched tasks registered with
+    /// [`GpuBlockCompressor::on_complete_with_readback()`], and of the tasks that fill the
+    /// [block cache](GpuBlockCompressor::set_block_cache), into their staging buffers.
+    ///
+    /// # Arguments
+    /// * `encoder` - The command encoder to record the copies into. Needs to be the encoder of
+    ///   the compute pass or one that is submitted after it.
+    pub fn record_readbacks(&mut self, encoder: &mut CommandEncoder) {
+        let mut staging_offsets = Vec::new();
+        let mut staging_size = 0;
+        for (index, completion) in self.completions.iter().enumerate() {
+            if completion.readback && completion.staging.is_none() {
+                staging_offsets.push((index, staging_size));
+                staging_size = wgpu::util::align_to(staging_size + completion.size, MAP_ALIGNMENT);
+            }
+        }
+
+        if staging_offsets.is_empty() {
+            return;
+        }
+
+        let staging = Arc::new(ReadbackStaging {
+            buffer: self.allocator.create_buffer(
+                &self.device,
+                &BufferDescriptor {
+                    label: Some("readback staging buffer"),
+                    size: staging_size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                },
+            ),
+            allocator: self.allocator.clone(),
+            mapped: AtomicBool::new(false),
+        });
+        self.memory_stats.staging_buffers_requested += 1;
+
+        for (index, staging_offset) in staging_offsets {
+            let completion = &mut self.completions[index];
+            encoder.copy_buffer_to_buffer(
+                &completion.buffer,
+                completion.offset,
+                &staging.buffer,
+                staging_offset,
+                completion.size,
+            );
+            completion.staging = Some((staging.clone(), staging_offset));
+        }
+    }
+
+    /// Hands the callbacks of all dispatched tasks over to the queue.
+    ///
+    /// Needs to be called after the command buffer containing the compute pass, and the
+    /// readbacks if any, was submitted. Otherwise the callbacks are called too early.
+    ///
+    /// # Panics
+    /// - If a task registered with [`GpuBlockCompressor::on_complete_with_readback()`] or a task
+    ///   that fills the block cache has no recorded readback. No callback is handed over in
+    ///   this case, so the readbacks can still be recorded and submitted.
+    pub fn after_submit(&mut self) {
+        assert!(
+            self.completions
+                .iter()
+                .all(|completion| !completion.readback || completion.staging.is_some()),
+            "record_readbacks() needs to be called before submitting"
+        );
+
+        let mut completed = Vec::new();
+        let mut readbacks: Vec<(Arc<ReadbackStaging>, Vec<PendingReadback>)> = Vec::new();
+
+        for completion in self.completions.drain(..) {
+            let task = CompletedTask {
+                id: completion.id,
+                buffer: completion.buffer,
+                offset: completion.offset,
+                size: completion.size,
+                staging: None,
+                #[cfg(feature = "bc6h")]
+                hdr_remap: completion.hdr_remap,
+            };
+
+            let Some((staging, staging_offset)) = completion.staging else {
+                completed.push((task, completion.callback));
+                continue;
+            };
+
+            let readback = PendingReadback {
+                task,
+                staging_offset,
+                callback: completion.callback,
+                cache_fill: completion.cache_fill,
+            };
+            match readbacks
+                .iter_mut()
+                .find(|(other, _)| Arc::ptr_eq(other, &staging))
+            {
+                Some((_, pending)) => pending.push(readback),
+                None => readbacks.push((staging, vec![readback])),
+            }
+        }
+
+        for (staging, pending) in readbacks {
+            let buffer = staging.buffer.clone();
+            buffer.slice(..).map_async(MapMode::Read, move |result| {
+                let mapped = result.is_ok();
+                staging.mapped.store(mapped, Ordering::Release);
+
+                for readback in pending {
+                    let mut task = readback.task;
+                    if mapped {
+                        let offset = readback.staging_offset;
+                        if let Some((key, cache)) = readback.cache_fill {
+                            let blocks = staging.buffer.slice(offset..offset + task.size);
+                            cache.insert(key, &blocks.get_mapped_range());
+                        }
+                        task.staging = Some((staging.clone(), offset));
+                    }
+                    (readback.callback)(task);
+                }
+            });
+        }
+
+        if !completed.is_empty() {
+            self.queue.on_submitted_work_done(move || {
+                for (task, callback) in completed {
+                    callback(task);
+                }
+            });
+        }
     }
 
     fn create_bind_group(&self, task: &Task) -> BindGroup {
+        if let Some(secondary_texture_view) = task.secondary_texture_view.as_ref() {
+            return self.create_interleaved_bind_group(task, secondary_texture_view);
+        }
+
         let bind_group_layout = self
+            .shared
             .bind_group_layouts
-            .get(&task.variant)
+            .get(&task.variant.format_key())
             .expect("Can't find bind group layout for variant");
 
         match task.variant {
-            #[cfg(feature = "bc15")]
-            CompressionVariant::BC1
-            | CompressionVariant::BC2
-            | CompressionVariant::BC3
-            | CompressionVariant::BC4
-            | CompressionVariant::BC5 => self.device.create_bind_group(&BindGroupDescriptor {
-                label: Some("bind group"),
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => self.device.create_bind_group(&BindGroupDescriptor {
+                label: Some(task.label.as_deref().unwrap_or("bind group")),
                 layout: bind_group_layout,
                 entries: &[
                     BindGroupEntry {
@@ -611,7 +3204,11 @@ impl GpuBlockCompressor {
                     },
                     BindGroupEntry {
                         binding: 1,
-                        resource: task.buffer.as_entire_binding(),
+                        resource: BindingResource::Buffer(BufferBinding {
+                            buffer: &task.buffer,
+                            offset: task.buffer_binding_offset,
+                            size: NonZeroU64::new(task.buffer_binding_size),
+                        }),
                     },
                     BindGroupEntry {
                         binding: 2,
@@ -621,11 +3218,23 @@ impl GpuBlockCompressor {
                             size: Some(NonZeroU64::new(self.uniforms_aligned_size as u64).unwrap()),
                         }),
                     },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: self.errors_binding(task),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: BindingResource::Buffer(BufferBinding {
+                            buffer: &self.bc6h_settings_buffer,
+                            offset: 0,
+                            size: Some(NonZeroU64::new(self.bc6h_aligned_size as u64).unwrap()),
+                        }),
+                    },
                 ],
             }),
-            #[cfg(feature = "bc6h")]
-            CompressionVariant::BC6H(..) => self.device.create_bind_group(&BindGroupDescriptor {
-                label: Some("bind group"),
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(..) => self.device.create_bind_group(&BindGroupDescriptor {
+                label: Some(task.label.as_deref().unwrap_or("bind group")),
                 layout: bind_group_layout,
                 entries: &[
                     BindGroupEntry {
@@ -634,7 +3243,11 @@ impl GpuBlockCompressor {
                     },
                     BindGroupEntry {
                         binding: 1,
-                        resource: task.buffer.as_entire_binding(),
+                        resource: BindingResource::Buffer(BufferBinding {
+                            buffer: &task.buffer,
+                            offset: task.buffer_binding_offset,
+                            size: NonZeroU64::new(task.buffer_binding_size),
+                        }),
                     },
                     BindGroupEntry {
                         binding: 2,
@@ -644,19 +3257,22 @@ impl GpuBlockCompressor {
                             size: Some(NonZeroU64::new(self.uniforms_aligned_size as u64).unwrap()),
                         }),
                     },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: self.errors_binding(task),
+                    },
                     BindGroupEntry {
                         binding: 3,
                         resource: BindingResource::Buffer(BufferBinding {
-                            buffer: &self.bc6h_settings_buffer,
+                            buffer: &self.bc7_settings_buffer,
                             offset: 0,
-                            size: Some(NonZeroU64::new(self.bc6h_aligned_size as u64).unwrap()),
+                            size: Some(NonZeroU64::new(self.bc7_aligned_size as u64).unwrap()),
                         }),
                     },
                 ],
             }),
-            #[cfg(feature = "bc7")]
-            CompressionVariant::BC7(..) => self.device.create_bind_group(&BindGroupDescriptor {
-                label: Some("bind group"),
+            _ => self.device.create_bind_group(&BindGroupDescriptor {
+                label: Some(task.label.as_deref().unwrap_or("bind group")),
                 layout: bind_group_layout,
                 entries: &[
                     BindGroupEntry {
@@ -665,7 +3281,11 @@ impl GpuBlockCompressor {
                     },
                     BindGroupEntry {
                         binding: 1,
-                        resource: task.buffer.as_entire_binding(),
+                        resource: BindingResource::Buffer(BufferBinding {
+                            buffer: &task.buffer,
+                            offset: task.buffer_binding_offset,
+                            size: NonZeroU64::new(task.buffer_binding_size),
+                        }),
                     },
                     BindGroupEntry {
                         binding: 2,
@@ -676,15 +3296,162 @@ impl GpuBlockCompressor {
                         }),
                     },
                     BindGroupEntry {
-                        binding: 3,
-                        resource: BindingResource::Buffer(BufferBinding {
-                            buffer: &self.bc7_settings_buffer,
-                            offset: 0,
-                            size: Some(NonZeroU64::new(self.bc7_aligned_size as u64).unwrap()),
-                        }),
+                        binding: 4,
+                        resource: self.errors_binding(task),
                     },
                 ],
             }),
         }
     }
+
+    fn create_interleaved_bind_group(
+        &self,
+        task: &Task,
+        secondary_texture_view: &TextureView,
+    ) -> BindGroup {
+        let bind_group_layout = self
+            .shared
+            .interleaved_bind_group_layouts
+            .get(&task.variant.format_key())
+            .expect("Can't find interleaved bind group layout for variant");
+
+        self.device.create_bind_group(&BindGroupDescriptor {
+            label: Some(task.label.as_deref().unwrap_or("interleaved bind group")),
+            layout: bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&task.texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: &task.buffer,
+                        offset: task.buffer_binding_offset,
+                        size: NonZeroU64::new(task.buffer_binding_size),
+                    }),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: &self.uniforms_buffer,
+                        offset: 0,
+                        size: Some(NonZeroU64::new(self.uniforms_aligned_size as u64).unwrap()),
+                    }),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: self.errors_binding(task),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(secondary_texture_view),
+                },
+            ],
+        })
+    }
+
+    #[allow(unused_mut)]
+    fn create_texture_array_bind_group(&self, group: &TextureArrayGroup) -> BindGroup {
+        let first = &self.task[group.tasks[0]];
+        let bind_group_layout = self
+            .shared
+            .texture_array_bind_group_layouts
+            .get(&first.variant.format_key())
+            .expect("Can't find texture array bind group layout for variant");
+
+        let texture_views: Vec<&TextureView> = group
+            .tasks
+            .iter()
+            .map(|&index| &self.task[index].texture_view)
+            .collect();
+
+        let mut entries = vec![
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureViewArray(&texture_views),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &first.buffer,
+                    offset: group.blocks.start,
+                    size: NonZeroU64::new(group.blocks.end - group.blocks.start),
+                }),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &self.uniforms_buffer,
+                    offset: 0,
+                    size: Some(NonZeroU64::new(self.uniforms_aligned_size as u64).unwrap()),
+                }),
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: match (&first.errors, &group.errors) {
+                    (Some(errors), Some(range)) => BindingResource::Buffer(BufferBinding {
+                        buffer: &errors.buffer,
+                        offset: range.start,
+                        size: NonZeroU64::new(range.end - range.start),
+                    }),
+                    _ => self.dummy_errors_buffer.as_entire_binding(),
+                },
+            },
+        ];
+
+        match first.variant {
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => entries.push(BindGroupEntry {
+                binding: 3,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &self.bc6h_settings_buffer,
+                    offset: 0,
+                    size: Some(NonZeroU64::new(self.bc6h_aligned_size as u64).unwrap()),
+                }),
+            }),
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(..) => entries.push(BindGroupEntry {
+                binding: 3,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &self.bc7_settings_buffer,
+                    offset: 0,
+                    size: Some(NonZeroU64::new(self.bc7_aligned_size as u64).unwrap()),
+                }),
+            }),
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+
+        self.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("texture array bind group"),
+            layout: bind_group_layout,
+            entries: &entries,
+        })
+    }
+
+    fn errors_binding<'a>(&'a self, task: &'a Task) -> BindingResource<'a> {
+        match task.errors.as_ref() {
+            Some(errors) => BindingResource::Buffer(BufferBinding {
+                buffer: &errors.buffer,
+                offset: errors.binding_offset,
+                size: NonZeroU64::new(errors.binding_size),
+            }),
+            None => self.dummy_errors_buffer.as_entire_binding(),
+        }
+    }
+}
+
+impl Drop for GpuBlockCompressor {
+    fn drop(&mut self) {
+        self.allocator.release_buffer(self.uniforms_buffer.clone());
+        self.allocator
+            .release_buffer(self.dummy_errors_buffer.clone());
+        #[cfg(feature = "bc6h")]
+        self.allocator
+            .release_buffer(self.bc6h_settings_buffer.clone());
+        #[cfg(feature = "bc7")]
+        self.allocator
+            .release_buffer(self.bc7_settings_buffer.clone());
+    }
 }
@@ -0,0 +1,166 @@
+//! Estimates of the memory that compressed textures need, before they are compressed.
+//!
+//! All sizes only depend on the dimensions, variants and mip levels of the textures, so build
+//! systems can check a texture budget without running the bake. The sizes on the GPU are the
+//! sizes of the blocks, drivers can add padding on top of them.
+//!
+//! # Example
+//! ```
+//! use block_compression::{
+//!     budget::{estimate_budget, sampled_compression_ratio, BudgetEntry},
+//!     mip::MipTail,
+//!     BC7Settings, CompressionVariant,
+//! };
+//!
+//! let entries = [
+//!     BudgetEntry {
+//!         mip_tail: Some(MipTail::Full),
+//!         ..BudgetEntry::new(CompressionVariant::BC7(BC7Settings::alpha_basic()), 2048, 2048)
+//!     },
+//!     BudgetEntry {
+//!         array_layers: 16,
+//!         ..BudgetEntry::new(CompressionVariant::BC1, 512, 512)
+//!     },
+//! ];
+//!
+//! // Compressed sizes of a few representative baked textures with zstd.
+//! let ratio = sampled_compression_ratio([(4_194_304, 3_355_443), (1_048_576, 734_003)]);
+//!
+//! let estimate = estimate_budget(&entries, ratio);
+//! assert_eq!(estimate.gpu_bytes, 5_592_432 + 16 * 131_072);
+//! assert!(estimate.compressed_disk_bytes.unwrap() < estimate.disk_bytes);
+//! ```
+
+use crate::{dds, mip::MipTail, CompressionVariant};
+
+/// A texture that is part of a budget.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BudgetEntry {
+    /// The block compression variant of the texture.
+    pub variant: CompressionVariant,
+    /// The width of the base mip level in pixels.
+    pub width: u32,
+    /// The height of the base mip level in pixels.
+    pub height: u32,
+    /// The number of array layers. Count every face of a cubemap as a layer.
+    pub array_layers: u32,
+    /// How the mip chain of the texture ends, or `None` if only the base level is stored.
+    pub mip_tail: Option<MipTail>,
+}
+
+impl BudgetEntry {
+    /// Creates an entry of a single texture without mip levels.
+    pub const fn new(variant: CompressionVariant, width: u32, height: u32) -> Self {
+        Self {
+            variant,
+            width,
+            height,
+            array_layers: 1,
+            mip_tail: None,
+        }
+    }
+
+    /// Returns the number of mip levels of the texture, including the base level.
+    pub const fn mip_level_count(&self) -> u32 {
+        match self.mip_tail {
+            Some(mip_tail) => mip_tail.level_count(self.width, self.height),
+            None => 1,
+        }
+    }
+
+    /// Returns the size in bytes of the blocks of all mip levels and array layers.
+    pub const fn byte_size(&self) -> u64 {
+        let layer_size =
+            self.variant
+                .mip_blocks_offset(self.width, self.height, self.mip_level_count());
+        layer_size as u64 * self.array_layers as u64
+    }
+}
+
+/// The result of [`estimate_budget()`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BudgetEstimate {
+    /// The size in bytes of the blocks of all textures.
+    pub gpu_bytes: u64,
+    /// The size in bytes of all textures stored as DDS files, including the headers.
+    pub disk_bytes: u64,
+    /// The estimated size in bytes of all DDS files after a general purpose compressor like zstd,
+    /// or `None` if no compression ratio was given.
+    pub compressed_disk_bytes: Option<u64>,
+}
+
+/// Estimates the memory the textures need on the GPU and on disk.
+///
+/// # Arguments
+/// * `entries` - The textures of the budget
+/// * `compression_ratio` - The ratio of the compressed to the uncompressed size of blocks with
+///   a general purpose compressor, usually taken from [`sampled_compression_ratio()`]. Block
+///   compressed data only shrinks moderately, so a ratio measured on similar content is far more
+///   reliable than a guess.
+pub fn estimate_budget(entries: &[BudgetEntry], compression_ratio: Option<f64>) -> BudgetEstimate {
+    let gpu_bytes: u64 = entries.iter().map(BudgetEntry::byte_size).sum();
+    let disk_bytes = gpu_bytes + entries.len() as u64 * dds::HEADER_BYTE_SIZE as u64;
+
+    BudgetEstimate {
+        gpu_bytes,
+        disk_bytes,
+        compressed_disk_bytes: compression_ratio
+            .map(|ratio| (disk_bytes as f64 * ratio).ceil() as u64),
+    }
+}
+
+/// Returns the compression ratio of sampled blocks, or `None` if there are no samples.
+///
+/// Every sample is the `(uncompressed, compressed)` size in bytes of the blocks of a baked
+/// texture. The ratio is weighted by the size of the samples.
+pub fn sampled_compression_ratio(samples: impl IntoIterator<Item = (usize, usize)>) -> Option<f64> {
+    let (uncompressed, compressed) =
+        samples
+            .into_iter()
+            .fold((0u64, 0u64), |(uncompressed, compressed), sample| {
+                (uncompressed + sample.0 as u64, compressed + sample.1 as u64)
+            });
+
+    (uncompressed != 0).then(|| compressed as f64 / uncompressed as f64)
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+    use crate::dds::{DdsDescriptor, DdsDimension};
+
+    #[test]
+    fn test_budget_matches_dds_layout() {
+        let entry = BudgetEntry {
+            array_layers: 6,
+            mip_tail: Some(MipTail::StopAt4x4),
+            ..BudgetEntry::new(CompressionVariant::BC3, 64, 64)
+        };
+        assert_eq!(entry.mip_level_count(), 5);
+
+        let descriptor = DdsDescriptor {
+            mip_level_count: entry.mip_level_count(),
+            dimension: DdsDimension::Cube,
+            ..DdsDescriptor::new(entry.variant, 64, 64)
+        };
+        assert_eq!(entry.byte_size(), descriptor.data_byte_size() as u64);
+
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        let mut file = Vec::new();
+        dds::write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+
+        let estimate = estimate_budget(&[entry, entry], Some(0.5));
+        assert_eq!(estimate.gpu_bytes, 2 * entry.byte_size());
+        assert_eq!(estimate.disk_bytes, 2 * file.len() as u64);
+        assert_eq!(estimate.compressed_disk_bytes, Some(file.len() as u64));
+    }
+
+    #[test]
+    fn test_sampled_compression_ratio() {
+        assert_eq!(sampled_compression_ratio([]), None);
+        assert_eq!(
+            sampled_compression_ratio([(100, 80), (300, 160)]),
+            Some(0.6)
+        );
+    }
+}
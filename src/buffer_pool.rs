@@ -0,0 +1,300 @@
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+
+use wgpu::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder, Device, MapMode,
+};
+
+const STATE_FREE: u8 = 0;
+const STATE_IN_FLIGHT: u8 = 1;
+const STATE_MAPPING: u8 = 2;
+const STATE_READY: u8 = 3;
+const STATE_FAILED: u8 = 4;
+
+/// The lifecycle state of a slot inside a [`BlockBufferPool`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SlotState {
+    /// The slot can be acquired.
+    Free,
+    /// The slot was acquired and is used by compression tasks that are not yet read back.
+    InFlight,
+    /// The staging buffer of the slot is waiting for the mapping to finish.
+    Mapping,
+    /// The staging buffer of the slot is mapped and can be read.
+    Ready,
+    /// Mapping the staging buffer of the slot failed. [`BlockBufferPool::read()`] returns the
+    /// error and frees the slot.
+    Failed,
+}
+
+impl SlotState {
+    const fn from_raw(raw: u8) -> Self {
+        match raw {
+            STATE_FREE => Self::Free,
+            STATE_IN_FLIGHT => Self::InFlight,
+            STATE_MAPPING => Self::Mapping,
+            STATE_READY => Self::Ready,
+            _ => Self::Failed,
+        }
+    }
+}
+
+struct Slot {
+    destination: Buffer,
+    staging: Buffer,
+    state: Arc<AtomicU8>,
+}
+
+/// Manages a ring of destination and staging buffers for continuous compression.
+///
+/// Compressing every frame and reading the result back to the CPU normally either stalls on
+/// `map_async()` or allocates new buffers every frame. The pool owns `N` destination storage
+/// buffers with a matching staging buffer each and tracks the mapping lifecycle of every slot,
+/// so that a slot is only handed out again once its data was read.
+///
+/// The lifecycle of a slot is:
+///
+/// 1. [`BlockBufferPool::acquire()`] returns a free slot.
+/// 2. Compression tasks write into [`BlockBufferPool::destination()`].
+/// 3. [`BlockBufferPool::record_readback()`] copies the destination into the staging buffer.
+/// 4. After the command buffer was submitted, [`BlockBufferPool::request_map()`] starts mapping.
+/// 5. Once the device was polled and the slot is ready, [`BlockBufferPool::read()`] hands out
+///    the data and frees the slot. If the mapping failed, it returns the error instead.
+///
+/// # Example
+///
+/// Triple-buffered readback of a texture that is compressed every frame:
+///
+/// ```ignore
+/// let size = variant.blocks_byte_size(width, height) as u64;
+/// let mut pool = BlockBufferPool::new(device.clone(), size, 3);
+///
+/// loop {
+///     // Consume all frames that finished their readback.
+///     for slot in 0..pool.len() {
+///         if let Some(Err(error)) = pool.read(slot, |blocks| upload_somewhere(blocks)) {
+///             eprintln!("readback of slot {slot} failed: {error}");
+///         }
+///     }
+///
+///     // All three slots are still in flight, so we skip compression this frame.
+///     let Some(slot) = pool.acquire() else {
+///         device.poll(Maintain::Poll);
+///         continue;
+///     };
+///
+///     compressor.add_compression_task(
+///         variant,
+///         &texture_view,
+///         width,
+///         height,
+///         pool.destination(slot),
+///         None,
+///         None,
+///         TaskOptions::default(),
+///     );
+///
+///     let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+///     {
+///         let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+///         compressor.compress(&mut pass).unwrap();
+///     }
+///     pool.record_readback(slot, &mut encoder);
+///     queue.submit([encoder.finish()]);
+///
+///     pool.request_map(slot);
+///     device.poll(Maintain::Poll);
+/// }
+/// ```
+pub struct BlockBufferPool {
+    slots: Vec<Slot>,
+    size: u64,
+    next: usize,
+}
+
+impl BlockBufferPool {
+    /// Creates a new pool with `count` slots, each able to hold `size` bytes of compressed blocks.
+    ///
+    /// # Panics
+    /// - If `count` is zero
+    /// - If `size` is not a multiple of 4
+    pub fn new(device: Arc<Device>, size: u64, count: usize) -> Self {
+        assert_ne!(count, 0, "pool needs at least one slot");
+        assert_eq!(size % 4, 0, "size must be a multiple of 4");
+
+        let slots = (0..count)
+            .map(|index| Slot {
+                destination: device.create_buffer(&BufferDescriptor {
+                    label: Some(&format!("pool blocks buffer {index}")),
+                    size,
+                    usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                }),
+                staging: device.create_buffer(&BufferDescriptor {
+                    label: Some(&format!("pool staging buffer {index}")),
+                    size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                state: Arc::new(AtomicU8::new(STATE_FREE)),
+            })
+            .collect();
+
+        Self {
+            slots,
+            size,
+            next: 0,
+        }
+    }
+
+    /// Returns the number of slots in the pool.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if the pool has no slots. A pool always has at least one slot.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the size in bytes of every destination buffer.
+    pub fn buffer_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the current state of the given slot.
+    pub fn state(&self, slot: usize) -> SlotState {
+        SlotState::from_raw(self.slots[slot].state.load(Ordering::Acquire))
+    }
+
+    /// Acquires the next free slot in rotating order and marks it as in flight.
+    ///
+    /// Returns `None` if all slots are still in use.
+    pub fn acquire(&mut self) -> Option<usize> {
+        let count = self.slots.len();
+
+        for step in 0..count {
+            let index = (self.next + step) % count;
+            let acquired = self.slots[index]
+                .state
+                .compare_exchange(
+                    STATE_FREE,
+                    STATE_IN_FLIGHT,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok();
+
+            if acquired {
+                self.next = (index + 1) % count;
+                return Some(index);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the destination storage buffer of the given slot.
+    pub fn destination(&self, slot: usize) -> &Buffer {
+        &self.slots[slot].destination
+    }
+
+    /// Records a copy of the destination buffer into the staging buffer of the given slot.
+    ///
+    /// # Panics
+    /// - If the slot is not in flight
+    pub fn record_readback(&self, slot: usize, encoder: &mut CommandEncoder) {
+        assert_eq!(
+            self.state(slot),
+            SlotState::InFlight,
+            "slot {slot} needs to be acquired before recording a readback"
+        );
+
+        let slot = &self.slots[slot];
+        encoder.copy_buffer_to_buffer(&slot.destination, 0, &slot.staging, 0, self.size);
+    }
+
+    /// Starts mapping the staging buffer of the given slot.
+    ///
+    /// Must be called after the command buffer containing the readback was submitted. The device
+    /// needs to be polled for the mapping to finish.
+    ///
+    /// # Panics
+    /// - If the slot is not in flight
+    pub fn request_map(&self, slot: usize) {
+        let slot = &self.slots[slot];
+
+        let transitioned = slot
+            .state
+            .compare_exchange(
+                STATE_IN_FLIGHT,
+                STATE_MAPPING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+        assert!(transitioned, "slot needs to be in flight to be mapped");
+
+        let state = slot.state.clone();
+        slot.staging
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let next = match result {
+                    Ok(()) => STATE_READY,
+                    Err(_) => STATE_FAILED,
+                };
+                state.store(next, Ordering::Release);
+            });
+    }
+
+    /// Reads the compressed blocks of the given slot, if the mapping has finished.
+    ///
+    /// The staging buffer is unmapped afterward and the slot becomes free again. Returns `None`
+    /// if the slot is not ready yet, and the error of `map_async()` if the mapping failed, which
+    /// also frees the slot.
+    pub fn read<R>(
+        &self,
+        slot: usize,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Option<Result<R, BufferAsyncError>> {
+        let slot = &self.slots[slot];
+
+        match slot.state.load(Ordering::Acquire) {
+            STATE_READY => {}
+            STATE_FAILED => {
+                slot.state.store(STATE_FREE, Ordering::Release);
+                return Some(Err(BufferAsyncError));
+            }
+            _ => return None,
+        }
+
+        let result = {
+            let data = slot.staging.slice(..).get_mapped_range();
+            f(&data)
+        };
+
+        slot.staging.unmap();
+        slot.state.store(STATE_FREE, Ordering::Release);
+
+        Some(Ok(result))
+    }
+
+    /// Releases a slot that was acquired but never submitted, so it can be acquired again.
+    ///
+    /// # Panics
+    /// - If the slot is not in flight
+    pub fn release(&self, slot: usize) {
+        let released = self.slots[slot]
+            .state
+            .compare_exchange(
+                STATE_IN_FLIGHT,
+                STATE_FREE,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+        assert!(released, "only in flight slots can be released");
+    }
+}
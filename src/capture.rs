@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+};
+
+use wgpu::{BufferDescriptor, BufferUsages, Device, Maintain, TextureView};
+
+use crate::{CompressionVariant, GpuBlockCompressor, TaskId, TaskOptions};
+
+type CaptureCallback = Box<dyn FnOnce(CapturedFrame)>;
+
+struct PendingCapture {
+    variant: CompressionVariant,
+    width: u32,
+    height: u32,
+    callback: CaptureCallback,
+}
+
+/// A texture captured by a [`FrameCapture`].
+pub struct CapturedFrame {
+    id: TaskId,
+    variant: CompressionVariant,
+    width: u32,
+    height: u32,
+    blocks: Option<Vec<u8>>,
+}
+
+impl CapturedFrame {
+    /// Returns the ID of the compression task of the capture.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Returns the block compression variant of the blocks.
+    pub fn variant(&self) -> CompressionVariant {
+        self.variant
+    }
+
+    /// Returns the width of the captured texture in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the captured texture in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the compressed blocks, or `None` if the staging buffer couldn't be mapped.
+    pub fn blocks(&self) -> Option<&[u8]> {
+        self.blocks.as_deref()
+    }
+
+    /// Returns the compressed blocks, or `None` if the staging buffer couldn't be mapped.
+    pub fn into_blocks(self) -> Option<Vec<u8>> {
+        self.blocks
+    }
+}
+
+/// Captures textures into compressed blocks on the CPU without stalling the frame.
+///
+/// Reading back a screenshot in the frame it was taken needs to wait for the GPU, which causes a
+/// visible hitch. A capture instead only adds a compression task with a readback to the
+/// compressor. The blocks are mapped once the GPU has finished and delivered on a later frame:
+///
+/// 1. [`FrameCapture::capture()`] adds the compression task during the frame.
+/// 2. The frame runs [`GpuBlockCompressor::compress()`],
+///    [`GpuBlockCompressor::record_readbacks()`] and [`GpuBlockCompressor::after_submit()`] as
+///    usual.
+/// 3. [`FrameCapture::deliver()`] is called once per frame. It polls the device without
+///    blocking and calls the callbacks of all finished captures on the calling thread.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut capture = FrameCapture::new(device.clone());
+///
+/// loop {
+///     if photo_mode_requested() {
+///         capture.capture(
+///             &mut compressor,
+///             &frame_view,
+///             width,
+///             height,
+///             CompressionVariant::BC7(BC7Settings::opaque_basic()),
+///             |frame| save_photo(frame.width(), frame.height(), frame.blocks().unwrap()),
+///         );
+///     }
+///
+///     let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+///     compressor.compress_split(&mut encoder).unwrap();
+///     compressor.record_readbacks(&mut encoder);
+///     queue.submit([encoder.finish()]);
+///     compressor.after_submit();
+///
+///     capture.deliver();
+/// }
+/// ```
+pub struct FrameCapture {
+    device: Arc<Device>,
+    sender: Sender<(TaskId, Option<Vec<u8>>)>,
+    receiver: Receiver<(TaskId, Option<Vec<u8>>)>,
+    pending: HashMap<TaskId, PendingCapture>,
+}
+
+impl FrameCapture {
+    /// Creates a new capture utility for textures of the given device.
+    pub fn new(device: Arc<Device>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            device,
+            sender,
+            receiver,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Adds a task to `compressor` that compresses the texture and reads the blocks back.
+    ///
+    /// The destination buffer is allocated by the capture. `callback` is called from within
+    /// [`FrameCapture::deliver()`] once the blocks were copied to the CPU. All other requirements
+    /// and arguments are the same as for [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Panics
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    pub fn capture(
+        &mut self,
+        compressor: &mut GpuBlockCompressor,
+        texture_view: &TextureView,
+        width: u32,
+        height: u32,
+        variant: CompressionVariant,
+        callback: impl FnOnce(CapturedFrame) + 'static,
+    ) -> TaskId {
+        let buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("capture blocks buffer"),
+            size: variant.blocks_byte_size(width, height) as u64,
+            usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let id = compressor.add_compression_task(
+            variant,
+            texture_view,
+            width,
+            height,
+            &buffer,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+
+        let sender = self.sender.clone();
+        compressor.on_complete_with_readback(id, move |task| {
+            let blocks = task.mapped_blocks().map(|blocks| blocks.to_vec());
+            // The receiver is gone if the capture was dropped in the meantime.
+            let _ = sender.send((task.id(), blocks));
+        });
+
+        self.pending.insert(
+            id,
+            PendingCapture {
+                variant,
+                width,
+                height,
+                callback: Box::new(callback),
+            },
+        );
+
+        id
+    }
+
+    /// Returns the number of captures that were not delivered yet.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Polls the device without blocking and calls the callbacks of all finished captures.
+    ///
+    /// Returns the number of delivered captures.
+    pub fn deliver(&mut self) -> usize {
+        self.device.poll(Maintain::Poll);
+
+        let mut delivered = 0;
+        while let Ok((id, blocks)) = self.receiver.try_recv() {
+            let Some(capture) = self.pending.remove(&id) else {
+                continue;
+            };
+
+            (capture.callback)(CapturedFrame {
+                id,
+                variant: capture.variant,
+                width: capture.width,
+                height: capture.height,
+                blocks,
+            });
+            delivered += 1;
+        }
+
+        delivered
+    }
+}
@@ -0,0 +1,131 @@
+//! Conversions between sRGB encoded and linear color values.
+//!
+//! These are the conversions the encoders, decoders and the mip generation of this crate use,
+//! for example when BC6H compresses 8-bit sRGB data or when HDR blocks are decoded to RGBA8.
+//! Inputs that are prepared with the same math compress to the same blocks as the RGBA8 paths.
+//!
+//! The conversions only apply to the color channels. Alpha is always linear.
+//!
+//! # Example
+//! ```
+//! use block_compression::color::{linear_to_srgb8, srgb8_to_linear, srgb8_to_linear_slice};
+//!
+//! assert_eq!(srgb8_to_linear(255), 1.0);
+//! assert_eq!(linear_to_srgb8(srgb8_to_linear(128)), 128);
+//!
+//! let mut linear = [0.0; 3];
+//! srgb8_to_linear_slice(&[0, 128, 255], &mut linear);
+//! assert_eq!(linear[1], srgb8_to_linear(128));
+//! ```
+
+use std::sync::OnceLock;
+
+/// Converts an sRGB encoded value in `[0, 1]` to linear.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear value in `[0, 1]` to sRGB encoded.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts an 8-bit sRGB encoded value to linear.
+///
+/// Looks the value up in a table, which holds the results of [`srgb_to_linear()`].
+#[inline]
+pub fn srgb8_to_linear(value: u8) -> f32 {
+    srgb8_table()[value as usize]
+}
+
+/// Converts a linear value to an 8-bit sRGB encoded value. Values outside of `[0, 1]` are
+/// clamped and NaN is converted to 0.
+pub fn linear_to_srgb8(value: f32) -> u8 {
+    (linear_to_srgb(value).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts 8-bit sRGB encoded values to linear with [`srgb8_to_linear()`].
+///
+/// # Panics
+/// - If `srgb` and `linear` have different lengths
+pub fn srgb8_to_linear_slice(srgb: &[u8], linear: &mut [f32]) {
+    assert_eq!(srgb.len(), linear.len(), "slices differ in length");
+
+    let table = srgb8_table();
+    for (linear, &srgb) in linear.iter_mut().zip(srgb) {
+        *linear = table[srgb as usize];
+    }
+}
+
+/// Converts linear values to 8-bit sRGB encoded values with [`linear_to_srgb8()`].
+///
+/// # Panics
+/// - If `linear` and `srgb` have different lengths
+pub fn linear_to_srgb8_slice(linear: &[f32], srgb: &mut [u8]) {
+    assert_eq!(linear.len(), srgb.len(), "slices differ in length");
+
+    for (srgb, &linear) in srgb.iter_mut().zip(linear) {
+        *srgb = linear_to_srgb8(linear);
+    }
+}
+
+/// Converts an 8-bit sRGB encoded value to a linear half float, the way BC6H reads 8-bit data.
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[inline]
+pub fn srgb8_to_linear_f16(value: u8) -> half::f16 {
+    half::f16::from_f32(srgb8_to_linear(value))
+}
+
+/// Converts sRGB encoded RGBA8 data to linear RGBA16F data, for example to upload an 8-bit image
+/// as the source texture of BC6H. The alpha channel is only normalized.
+///
+/// # Panics
+/// - If `rgba` and `linear` have different lengths
+/// - If the length is not a multiple of 4
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn rgba8_srgb_to_linear_f16(rgba: &[u8], linear: &mut [half::f16]) {
+    assert_eq!(rgba.len(), linear.len(), "slices differ in length");
+    assert_eq!(rgba.len() % 4, 0, "slices don't hold whole RGBA texels");
+
+    let table = srgb8_table();
+    for (linear, rgba) in linear.chunks_exact_mut(4).zip(rgba.chunks_exact(4)) {
+        for channel in 0..3 {
+            linear[channel] = half::f16::from_f32(table[rgba[channel] as usize]);
+        }
+        linear[3] = half::f16::from_f32(rgba[3] as f32 / 255.0);
+    }
+}
+
+fn srgb8_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|value| srgb_to_linear(value as f32 / 255.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_matches_formula() {
+        for value in 0..=255u8 {
+            let linear = srgb8_to_linear(value);
+
+            assert_eq!(linear, srgb_to_linear(value as f32 / 255.0));
+            assert_eq!(linear_to_srgb8(linear), value);
+        }
+
+        assert_eq!(linear_to_srgb8(-1.0), 0);
+        assert_eq!(linear_to_srgb8(4.0), 255);
+        assert_eq!(linear_to_srgb8(f32::NAN), 0);
+    }
+}
@@ -0,0 +1,849 @@
+use std::{sync::mpsc, time::Instant};
+
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+use wgpu::{
+    util::BufferInitDescriptor, BindGroupDescriptor, BindGroupEntry, ComputePassDescriptor,
+    ComputePipelineDescriptor, MapMode, PipelineCompilationOptions, ShaderModuleDescriptor,
+    ShaderSource,
+};
+use wgpu::{
+    util::{DeviceExt, TextureDataOrder},
+    AdapterInfo, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, DeviceType, Extent3d,
+    Limits, Maintain, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor,
+};
+
+#[cfg(feature = "bc6h")]
+use crate::color::rgba8_srgb_to_linear_f16;
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+use crate::settings::FieldLayout;
+use crate::{
+    decode::decompress_blocks_as_rgba8,
+    encode::{compress_rgba8, packed_rgba8, preset_report, preset_variants, PresetReport},
+    testgen::TestPattern,
+    CompressionVariant, CompressorError, GpuBlockCompressor, ImageView, ImageViewMut, TaskOptions,
+};
+#[cfg(feature = "bc6h")]
+use crate::{settings::BC6H_SETTINGS_LAYOUT, BC6HSettings};
+#[cfg(feature = "bc7")]
+use crate::{settings::BC7_SETTINGS_LAYOUT, BC7Settings};
+
+/// The names of software adapters that don't report themselves as CPU devices on all backends.
+const SOFTWARE_ADAPTER_NAMES: [&str; 4] = [
+    "llvmpipe",
+    "lavapipe",
+    "swiftshader",
+    "microsoft basic render driver",
+];
+
+/// The size of the test image of the self-check. It is not a multiple of the workgroup size, so
+/// the edge workgroups are only partially used.
+const SELF_CHECK_WIDTH: u32 = 200;
+const SELF_CHECK_HEIGHT: u32 = 120;
+
+/// The minimal PSNR in dB between the decoded GPU and CPU blocks of a variant to pass the
+/// self-check. Both sides run the same algorithms, so only small differences of the floating
+/// point math are expected.
+pub const MIN_SELF_CHECK_PSNR: f64 = 40.0;
+
+/// Returns `true` if the adapter runs on the CPU, like lavapipe, llvmpipe, SwiftShader or WARP.
+///
+/// Continuous integration machines without a GPU often only provide these adapters. Pass the
+/// result to [`GpuBlockCompressor::set_software_compatibility()`] to adjust the limits of the
+/// compressor.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut compressor = GpuBlockCompressor::new(device, queue);
+/// compressor.set_software_compatibility(is_software_adapter(&adapter.get_info()));
+/// ```
+pub fn is_software_adapter(info: &AdapterInfo) -> bool {
+    if info.device_type == DeviceType::Cpu {
+        return true;
+    }
+
+    let name = info.name.to_lowercase();
+    SOFTWARE_ADAPTER_NAMES
+        .iter()
+        .any(|software_name| name.contains(software_name))
+}
+
+/// The size of the workgroups of all compression kernels.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// The resources the kernel of a variant binds, which need to fit the limits of a device.
+struct KernelRequirements {
+    sampled_textures: u32,
+    storage_buffers: u32,
+    dynamic_storage_buffers: u32,
+    min_storage_buffer_binding_size: u64,
+    workgroup_storage_size: u32,
+}
+
+impl KernelRequirements {
+    fn of(variant: CompressionVariant) -> Self {
+        let mut requirements = Self {
+            sampled_textures: 1,
+            // The blocks and the block errors.
+            storage_buffers: 2,
+            dynamic_storage_buffers: 0,
+            min_storage_buffer_binding_size: 16,
+            workgroup_storage_size: 0,
+        };
+
+        if cfg!(feature = "bounds_checks") {
+            requirements.storage_buffers += 1;
+        }
+
+        match variant {
+            // The compressor also creates the pipelines that read from two textures.
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC3 | CompressionVariant::BC5 => {
+                requirements.sampled_textures = 2;
+            }
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => {
+                requirements.storage_buffers += 1;
+                requirements.dynamic_storage_buffers = 1;
+                requirements.min_storage_buffer_binding_size = size_of::<BC6HSettings>() as u64;
+            }
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(..) => {
+                requirements.storage_buffers += 1;
+                requirements.dynamic_storage_buffers = 1;
+                requirements.min_storage_buffer_binding_size = size_of::<BC7Settings>() as u64;
+                // The seeds of the neighbor seeding.
+                requirements.workgroup_storage_size = WORKGROUP_SIZE * WORKGROUP_SIZE * 4;
+            }
+            _ => {}
+        }
+
+        requirements
+    }
+
+    fn fits(&self, limits: &Limits) -> bool {
+        let bind_groups = if cfg!(feature = "bounds_checks") {
+            2
+        } else {
+            1
+        };
+
+        limits.max_bind_groups >= bind_groups
+            && limits.max_sampled_textures_per_shader_stage >= self.sampled_textures
+            && limits.max_storage_buffers_per_shader_stage >= self.storage_buffers
+            && limits.max_dynamic_storage_buffers_per_pipeline_layout
+                >= self.dynamic_storage_buffers
+            && limits.max_storage_buffer_binding_size as u64 >= self.min_storage_buffer_binding_size
+            && limits.max_uniform_buffers_per_shader_stage >= 1
+            && limits.max_dynamic_uniform_buffers_per_pipeline_layout >= 1
+            && limits.max_compute_workgroup_storage_size >= self.workgroup_storage_size
+            && limits.max_compute_invocations_per_workgroup >= WORKGROUP_SIZE * WORKGROUP_SIZE
+            && limits.max_compute_workgroup_size_x >= WORKGROUP_SIZE
+            && limits.max_compute_workgroup_size_y >= WORKGROUP_SIZE
+            && limits.max_compute_workgroups_per_dimension >= 1
+    }
+}
+
+/// The result of a single variant of [`GpuBlockCompressor::self_check()`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SelfCheckResult {
+    /// The variant that was checked.
+    pub variant: CompressionVariant,
+    /// The PSNR in dB between the decoded GPU and CPU blocks, or `None` if the GPU blocks
+    /// couldn't be read back.
+    pub psnr: Option<f64>,
+}
+
+impl SelfCheckResult {
+    /// Returns `true` if the GPU blocks match the CPU blocks closely enough.
+    pub fn passed(&self) -> bool {
+        self.psnr.is_some_and(|psnr| psnr >= MIN_SELF_CHECK_PSNR)
+    }
+}
+
+/// The report of [`GpuBlockCompressor::self_check()`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelfCheckReport {
+    /// The results of all checked variants.
+    pub results: Vec<SelfCheckResult>,
+    /// The settings fields that the shaders read differently than they are uploaded, like
+    /// `"BC7Settings::alpha_weight"`.
+    pub settings_mismatches: Vec<String>,
+}
+
+impl SelfCheckReport {
+    /// Returns `true` if all variants passed and the shaders read all settings correctly.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(SelfCheckResult::passed) && self.settings_mismatches.is_empty()
+    }
+
+    /// Returns the results of the variants that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &SelfCheckResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+}
+
+impl GpuBlockCompressor {
+    /// Returns the enabled variants whose kernels fit the limits of a device or adapter.
+    ///
+    /// Applications can call this before a compressor is created, for example to grey out the
+    /// formats a browser or an old mobile GPU can't compress. Pass [`Device::limits()`] for an
+    /// existing device or [`Adapter::limits()`] for the best device an adapter can create. The
+    /// limits that are checked are the number of textures, storage and uniform buffers the
+    /// kernels bind, the size of their workgroups and workgroup memory, and the binding size of
+    /// the settings of BC6H and BC7. WebGL2 has no compute shaders, so its limits support no
+    /// variant. The kernels only use 32-bit floats and don't need any optional device features.
+    ///
+    /// BC6H and BC7 are returned with their basic presets, since all settings bind the same
+    /// resources. [`GpuBlockCompressor::new()`] creates the pipelines of all enabled variants,
+    /// so it needs a device that supports all of them. Unsupported variants can be excluded by
+    /// disabling their features. The maximal storage buffer binding size also limits the size
+    /// of the blocks a single task writes.
+    ///
+    /// [`Device::limits()`]: wgpu::Device::limits
+    /// [`Adapter::limits()`]: wgpu::Adapter::limits
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let supported = GpuBlockCompressor::supported_variants(&adapter.limits());
+    ///
+    /// for variant in all_variants {
+    ///     let enabled = supported
+    ///         .iter()
+    ///         .any(|supported| supported.format_key() == variant.format_key());
+    ///     ui.add_enabled(enabled, Button::new(variant.name()));
+    /// }
+    /// ```
+    pub fn supported_variants(limits: &Limits) -> Vec<CompressionVariant> {
+        [
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC1,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC2,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC3,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC4,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC5,
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(BC6HSettings::basic()),
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(BC7Settings::alpha_basic()),
+            CompressionVariant::RGBA8,
+        ]
+        .into_iter()
+        .filter(|&variant| KernelRequirements::of(variant).fits(limits))
+        .collect()
+    }
+
+    /// Verifies that the compressor produces correct blocks on its device.
+    ///
+    /// Compresses a synthetic test image with BC1 to BC5 and BC7, as far as their features are
+    /// enabled, and copies it as RGBA8, using the current limits of the compressor. The blocks
+    /// are read back and compared against the CPU encoders, which run the same algorithms. BC6H
+    /// is not checked, since its CPU encoder treats 8-bit data as sRGB. Blocks until the GPU has
+    /// finished, so it is meant for startup checks and test suites, for example to validate a
+    /// software adapter in continuous integration before running the real tests on it.
+    ///
+    /// The BC6H and BC7 settings are also uploaded to a shader that declares the `Settings`
+    /// struct of their compression shader and copies every field back, which catches settings
+    /// that the shaders read from the wrong offsets.
+    ///
+    /// # Errors
+    /// - [`CompressorError::DeviceLost`] if the device was lost
+    ///
+    /// # Panics
+    /// - If tasks were added that were not dispatched yet
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut compressor = GpuBlockCompressor::new(device, queue);
+    /// compressor.set_software_compatibility(is_software_adapter(&adapter.get_info()));
+    ///
+    /// let report = compressor.self_check().unwrap();
+    /// for failure in report.failures() {
+    ///     eprintln!("{:?} differs from the CPU encoder: {:?} dB", failure.variant, failure.psnr);
+    /// }
+    /// assert!(report.passed());
+    /// ```
+    pub fn self_check(&mut self) -> Result<SelfCheckReport, CompressorError> {
+        assert!(
+            !self.has_pending_tasks(),
+            "the self-check needs a compressor without pending tasks"
+        );
+
+        let variants: &[CompressionVariant] = &[
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC1,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC2,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC3,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC4,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC5,
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(BC7Settings::alpha_ultrafast()),
+            CompressionVariant::RGBA8,
+        ];
+
+        let (width, height) = (SELF_CHECK_WIDTH, SELF_CHECK_HEIGHT);
+        let rgba_data = TestPattern::AlphaRamp.generate_rgba8(width, height);
+
+        let device = self.device().clone();
+        let queue = self.queue().clone();
+
+        let texture = device.create_texture_with_data(
+            &queue,
+            &TextureDescriptor {
+                label: Some("self-check texture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            &rgba_data,
+        );
+        let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+        let (sender, receiver) = mpsc::channel();
+        for (index, variant) in variants.iter().enumerate() {
+            let buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("self-check blocks buffer"),
+                size: variant.blocks_byte_size(width, height) as u64,
+                usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+
+            let id = self.add_compression_task(
+                *variant,
+                &texture_view,
+                width,
+                height,
+                &buffer,
+                None,
+                None,
+                TaskOptions::default(),
+            );
+
+            let sender = sender.clone();
+            self.on_complete_with_readback(id, move |task| {
+                let blocks = task.mapped_blocks().map(|blocks| blocks.to_vec());
+                let _ = sender.send((index, blocks));
+            });
+        }
+        drop(sender);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("self-check"),
+        });
+        self.compress_split(&mut encoder)?;
+        self.record_readbacks(&mut encoder);
+        queue.submit([encoder.finish()]);
+        self.after_submit();
+        device.poll(Maintain::Wait);
+
+        if let Some(error) = self.device_lost() {
+            return Err(error);
+        }
+
+        let mut gpu_blocks = vec![None; variants.len()];
+        for (index, blocks) in receiver.try_iter() {
+            gpu_blocks[index] = blocks;
+        }
+
+        let image = ImageView::packed(&rgba_data, width, height);
+        let results = variants
+            .iter()
+            .copied()
+            .zip(gpu_blocks)
+            .map(|(variant, gpu_blocks)| {
+                let mut cpu_blocks = vec![0; variant.blocks_byte_size(width, height)];
+                compress_rgba8(variant, image, &mut cpu_blocks);
+
+                let psnr = gpu_blocks.map(|gpu_blocks| {
+                    psnr(
+                        &decode(variant, &gpu_blocks, width, height),
+                        &decode(variant, &cpu_blocks, width, height),
+                    )
+                });
+
+                SelfCheckResult { variant, psnr }
+            })
+            .collect();
+
+        #[cfg(not(any(feature = "bc6h", feature = "bc7")))]
+        let settings_mismatches = Vec::new();
+        #[cfg(any(feature = "bc6h", feature = "bc7"))]
+        let settings_mismatches = [
+            #[cfg(feature = "bc6h")]
+            (
+                "BC6HSettings",
+                include_str!("shader/bc6h.wgsl"),
+                BC6H_SETTINGS_LAYOUT,
+                size_of::<BC6HSettings>(),
+            ),
+            #[cfg(feature = "bc7")]
+            (
+                "BC7Settings",
+                include_str!("shader/bc7.wgsl"),
+                BC7_SETTINGS_LAYOUT,
+                size_of::<BC7Settings>(),
+            ),
+        ]
+        .into_iter()
+        .flat_map(|(name, source, layout, size)| {
+            settings_mismatches(&device, &queue, name, source, layout, size)
+        })
+        .collect();
+
+        Ok(SelfCheckReport {
+            results,
+            settings_mismatches,
+        })
+    }
+
+    /// Compresses the image with every preset of the variant on the GPU and reports how long
+    /// each preset took and the quality it reached.
+    ///
+    /// The GPU counterpart of [`evaluate_presets()`](crate::encode::evaluate_presets), with the
+    /// same presets and quality metrics. Every preset is submitted on its own and timed from the
+    /// submission until the GPU has finished, which includes the copy of the blocks into a
+    /// staging buffer but not the upload of the image. For BC6H the image is converted from
+    /// sRGB to linear half floats, like the CPU encoder reads 8-bit data. Blocks until the GPU
+    /// has finished, so it is meant for tools and not for frames of an application.
+    ///
+    /// Presets whose blocks couldn't be read back are left out of the reports.
+    ///
+    /// # Errors
+    /// - [`CompressorError::DeviceLost`] if the device was lost
+    ///
+    /// # Panics
+    /// - If tasks were added that were not dispatched yet
+    /// - If the width or height of the `image` is not a multiple of 4
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+    ///
+    /// let reports = compressor.evaluate_presets(image, variant).unwrap();
+    /// for report in &reports {
+    ///     println!("{}: {:.2} ms, {:.2} dB", report.name, report.encode_ms, report.psnr);
+    /// }
+    /// ```
+    pub fn evaluate_presets(
+        &mut self,
+        image: ImageView<'_, u8>,
+        variant: CompressionVariant,
+    ) -> Result<Vec<PresetReport>, CompressorError> {
+        assert!(
+            !self.has_pending_tasks(),
+            "evaluating presets needs a compressor without pending tasks"
+        );
+        assert_eq!(image.height() % 4, 0);
+        assert_eq!(image.width() % 4, 0);
+
+        let (width, height) = (image.width(), image.height());
+        let source = packed_rgba8(image);
+
+        let device = self.device().clone();
+        let queue = self.queue().clone();
+
+        let (format, data) = match variant {
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => {
+                let mut linear = vec![half::f16::ZERO; source.len()];
+                rgba8_srgb_to_linear_f16(&source, &mut linear);
+                (
+                    TextureFormat::Rgba16Float,
+                    bytemuck::cast_slice(&linear).to_vec(),
+                )
+            }
+            _ => (TextureFormat::Rgba8Unorm, source.clone()),
+        };
+
+        let texture = device.create_texture_with_data(
+            &queue,
+            &TextureDescriptor {
+                label: Some("preset evaluation texture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            &data,
+        );
+        let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+        let mut reports = Vec::new();
+        for (name, variant) in preset_variants(variant) {
+            let buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("preset evaluation blocks buffer"),
+                size: variant.blocks_byte_size(width, height) as u64,
+                usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+
+            let id = self.add_compression_task(
+                variant,
+                &texture_view,
+                width,
+                height,
+                &buffer,
+                None,
+                None,
+                TaskOptions::default(),
+            );
+
+            let (sender, receiver) = mpsc::channel();
+            self.on_complete_with_readback(id, move |task| {
+                let _ = sender.send(task.mapped_blocks().map(|blocks| blocks.to_vec()));
+            });
+
+            let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("preset evaluation"),
+            });
+            self.compress_split(&mut encoder)?;
+            self.record_readbacks(&mut encoder);
+
+            let start = Instant::now();
+            queue.submit([encoder.finish()]);
+            self.after_submit();
+            device.poll(Maintain::Wait);
+            let encode_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+            if let Some(error) = self.device_lost() {
+                return Err(error);
+            }
+
+            if let Ok(Some(blocks)) = receiver.try_recv() {
+                reports.push(preset_report(
+                    name, variant, encode_ms, &source, &blocks, width, height,
+                ));
+            }
+        }
+
+        Ok(reports)
+    }
+}
+
+/// A field of the `Settings` struct of a shader, parsed from the source of the shader.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+struct ShaderField<'a> {
+    name: &'a str,
+    /// The statements that copy the words of the field into the read back words.
+    copies: Vec<String>,
+}
+
+/// Parses the fields of the `Settings` struct of a shader. Only the 4-byte types that the
+/// settings use are supported, other fields don't copy any words.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn shader_fields(source: &str) -> (&str, Vec<ShaderField<'_>>) {
+    let start = source
+        .find("struct Settings {")
+        .expect("shader has no settings struct");
+    let end = start
+        + source[start..]
+            .find('}')
+            .expect("settings struct isn't closed")
+        + 1;
+    let declaration = &source[start..end];
+
+    let mut word = 0;
+    let fields = declaration
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("//"))
+        .filter_map(|line| line.trim_end_matches(',').split_once(':'))
+        .map(|(name, ty)| {
+            let (name, ty) = (name.trim(), ty.trim());
+            let elements = ty
+                .strip_prefix("array<")
+                .and_then(|ty| ty.strip_suffix('>'))
+                .and_then(|ty| ty.split_once(','))
+                .map(|(element, count)| (element.trim(), count.trim().parse().ok()));
+
+            let copy = |word: u32, element: &str, value: String| match element {
+                "u32" => Some(format!("words[{word}] = {value};")),
+                "f32" => Some(format!("words[{word}] = bitcast<u32>({value});")),
+                _ => None,
+            };
+
+            let copies: Vec<String> = match elements {
+                Some((element, Some(count))) => (0..count)
+                    .filter_map(|index| {
+                        copy(word + index, element, format!("settings.{name}[{index}]"))
+                    })
+                    .collect(),
+                Some((_, None)) => Vec::new(),
+                None => copy(word, ty, format!("settings.{name}"))
+                    .into_iter()
+                    .collect(),
+            };
+            word += copies.len() as u32;
+
+            ShaderField { name, copies }
+        })
+        .collect();
+
+    (declaration, fields)
+}
+
+/// Returns a shader with the `read_settings` kernel, which copies the words of all fields of the
+/// settings into the read back words.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn read_settings_shader(declaration: &str, fields: &[ShaderField]) -> String {
+    let copies: Vec<&str> = fields
+        .iter()
+        .flat_map(|field| field.copies.iter().map(String::as_str))
+        .collect();
+
+    format!(
+        "{declaration}
+
+@group(0) @binding(0) var<storage, read> settings: Settings;
+@group(0) @binding(1) var<storage, read_write> words: array<u32>;
+
+@compute
+@workgroup_size(1)
+fn read_settings() {{
+    {}
+}}
+",
+        copies.join("\n    ")
+    )
+}
+
+/// Uploads settings whose words all differ to a shader that copies every field of the
+/// `Settings` struct of `source` back, and returns the fields that were read from the wrong
+/// words.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn settings_mismatches(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    name: &str,
+    source: &str,
+    layout: &[FieldLayout],
+    size: usize,
+) -> Vec<String> {
+    let (declaration, fields) = shader_fields(source);
+    let word_count: usize = fields.iter().map(|field| field.copies.len()).sum();
+
+    let uploaded: Vec<u32> = (1..=(size / 4) as u32).collect();
+    let settings_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("self-check settings buffer"),
+        contents: bytemuck::cast_slice(&uploaded),
+        usage: BufferUsages::STORAGE,
+    });
+    let words_size = (word_count.max(1) * 4) as u64;
+    let words_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("self-check settings words buffer"),
+        size: words_size,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let staging = device.create_buffer(&BufferDescriptor {
+        label: Some("self-check settings staging buffer"),
+        size: words_size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("self-check settings shader"),
+        source: ShaderSource::Wgsl(read_settings_shader(declaration, &fields).into()),
+    });
+    let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: Some("self-check settings pipeline"),
+        layout: None,
+        module: &module,
+        entry_point: Some("read_settings"),
+        compilation_options: PipelineCompilationOptions::default(),
+        cache: None,
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("self-check settings bind group"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: settings_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: words_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("self-check settings"),
+    });
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("self-check settings"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(&words_buffer, 0, &staging, 0, words_size);
+    queue.submit([encoder.finish()]);
+
+    let (sender, receiver) = mpsc::channel();
+    staging.slice(..).map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(Maintain::Wait);
+
+    if !matches!(receiver.try_recv(), Ok(Ok(()))) {
+        return vec![name.to_string()];
+    }
+    let data = staging.slice(..).get_mapped_range();
+    let words: &[u32] = bytemuck::cast_slice(&data);
+
+    let mut mismatches = Vec::new();
+    let mut word = 0;
+    for field in &fields {
+        let read = &words[word..word + field.copies.len()];
+        word += field.copies.len();
+
+        let expected = layout
+            .iter()
+            .find(|layout| layout.name == field.name)
+            .map(|layout| &uploaded[layout.offset / 4..(layout.offset + layout.size) / 4]);
+        if expected != Some(read) {
+            mismatches.push(format!("{name}::{}", field.name));
+        }
+    }
+    mismatches.extend(
+        layout
+            .iter()
+            .filter(|layout| !fields.iter().any(|field| field.name == layout.name))
+            .map(|layout| format!("{name}::{}", layout.name)),
+    );
+
+    mismatches
+}
+
+fn decode(variant: CompressionVariant, blocks_data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut rgba_data = vec![0; (width * height * 4) as usize];
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks_data,
+        ImageViewMut::packed(&mut rgba_data, width, height),
+    );
+    rgba_data
+}
+
+fn psnr(a: &[u8], b: &[u8]) -> f64 {
+    let mse = a
+        .iter()
+        .zip(b)
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum::<f64>()
+        / a.len() as f64;
+
+    10.0 * (255.0 * 255.0 / mse.max(1e-9)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_software_adapter() {
+        let info = |name: &str, device_type| AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+
+        assert!(is_software_adapter(&info(
+            "llvmpipe (LLVM 15.0.7, 256 bits)",
+            DeviceType::Cpu
+        )));
+        assert!(is_software_adapter(&info(
+            "Microsoft Basic Render Driver",
+            DeviceType::Other
+        )));
+        assert!(is_software_adapter(&info(
+            "SwiftShader Device (Subzero)",
+            DeviceType::Other
+        )));
+        assert!(!is_software_adapter(&info(
+            "NVIDIA GeForce RTX 4070",
+            DeviceType::DiscreteGpu
+        )));
+    }
+
+    #[cfg(feature = "bc7")]
+    #[test]
+    fn test_supported_variants() {
+        let all = GpuBlockCompressor::supported_variants(&Limits::default());
+        assert_eq!(all.last(), Some(&CompressionVariant::RGBA8));
+        assert_eq!(
+            GpuBlockCompressor::supported_variants(&Limits::downlevel_defaults()),
+            all
+        );
+        assert!(
+            GpuBlockCompressor::supported_variants(&Limits::downlevel_webgl2_defaults()).is_empty()
+        );
+
+        // Only BC7 needs workgroup memory.
+        let limits = Limits {
+            max_compute_workgroup_storage_size: 0,
+            ..Limits::default()
+        };
+        let expected: Vec<_> = all
+            .iter()
+            .copied()
+            .filter(|variant| !matches!(variant, CompressionVariant::BC7(..)))
+            .collect();
+        assert_eq!(GpuBlockCompressor::supported_variants(&limits), expected);
+    }
+
+    #[cfg(feature = "bc7")]
+    #[test]
+    fn test_shader_fields() {
+        let (declaration, fields) = shader_fields(include_str!("shader/bc7.wgsl"));
+        assert!(declaration.starts_with("struct Settings {") && declaration.ends_with('}'));
+
+        for (field, layout) in fields.iter().zip(BC7_SETTINGS_LAYOUT) {
+            assert_eq!(field.name, layout.name);
+            assert_eq!(field.copies.len() * 4, layout.size, "{}", layout.name);
+        }
+        assert_eq!(fields.len(), BC7_SETTINGS_LAYOUT.len());
+
+        let shader = read_settings_shader(declaration, &fields);
+        assert!(shader.contains("words[0] = settings.refine_iterations[0];"));
+        assert!(shader.contains("words[20] = bitcast<u32>(settings.alpha_weight);"));
+    }
+}
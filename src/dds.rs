@@ -0,0 +1,843 @@
+//! Writing of compressed blocks into DDS files.
+//!
+//! [`decode_all()`] decodes every subresource of such a texture back into images, for example
+//! to build thumbnails or previews of all mip levels and array layers.
+//!
+//! [`write_dds()`] writes the files with the DX10 header extension, which supports all block
+//! compression variants, sRGB formats, texture arrays, cubemaps and volume textures. Old engines
+//! that only read DX9 style files get them from [`write_dds_legacy()`], which stores the FourCC
+//! code of the variant instead and is limited to BC1 to BC5 and RGBA8. The blocks
+//! of all subresources are expected in one contiguous buffer in the order DDS stores them, so the
+//! output of several compression tasks can be written directly. Use
+//! [`DdsDescriptor::subresource_offset()`] to get the blocks offset of every task.
+//!
+//! # Example
+//! ```
+//! use block_compression::{
+//!     dds::{write_dds, DdsDescriptor, DdsDimension},
+//!     encode::compress_rgba8,
+//!     CompressionVariant, ImageView,
+//! };
+//!
+//! // A cubemap with a full mip chain.
+//! let variant = CompressionVariant::BC1;
+//! let descriptor = DdsDescriptor {
+//!     mip_level_count: 7,
+//!     dimension: DdsDimension::Cube,
+//!     ..DdsDescriptor::new(variant, 64, 64)
+//! };
+//!
+//! let mut blocks_data = vec![0u8; descriptor.data_byte_size()];
+//!
+//! // Compress the base mip level of the second face of the first cube.
+//! let rgba_data = vec![0u8; 64 * 64 * 4]; // Your RGBA data
+//! let offset = descriptor.subresource_offset(0, 1, 0);
+//! let size = descriptor.mip_byte_size(0);
+//! compress_rgba8(
+//!     variant,
+//!     ImageView::packed(&rgba_data, 64, 64),
+//!     &mut blocks_data[offset..offset + size],
+//! );
+//!
+//! let mut file = Vec::new();
+//! write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{decode::decompress_blocks_as_rgba8, mip_dimension, CompressionVariant, ImageViewMut};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: u32 = 124;
+const PIXEL_FORMAT_SIZE: u32 = 32;
+
+/// The size in bytes of everything [`write_dds()`] writes in front of the blocks, which is the
+/// magic number, the header and the DX10 header extension.
+pub const HEADER_BYTE_SIZE: usize = 4 + HEADER_SIZE as usize + 20;
+
+/// The size in bytes of everything [`write_dds_legacy()`] writes in front of the blocks, which
+/// is the magic number and the header.
+pub const LEGACY_HEADER_BYTE_SIZE: usize = 4 + HEADER_SIZE as usize;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDSD_DEPTH: u32 = 0x800000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+#[cfg(feature = "bc15")]
+const DDS_ALPHA_MODE_UNKNOWN: u32 = 0;
+const DDS_ALPHA_MODE_STRAIGHT: u32 = 1;
+const DDS_ALPHA_MODE_OPAQUE: u32 = 3;
+
+/// The dimension of a texture stored in a DDS file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum DdsDimension {
+    /// A 2D texture or a 2D texture array.
+    #[default]
+    Texture2D,
+    /// A cubemap or a cubemap array. Every array layer consists of six faces in the order +X, -X,
+    /// +Y, -Y, +Z and -Z.
+    Cube,
+    /// A volume texture. Every mip level stores all of its depth slices.
+    Volume,
+}
+
+/// Describes the layout of a texture stored in a DDS file.
+#[derive(Copy, Clone, Debug)]
+pub struct DdsDescriptor {
+    /// The block compression variant of the blocks.
+    pub variant: CompressionVariant,
+    /// The width of the base mip level in pixels.
+    pub width: u32,
+    /// The height of the base mip level in pixels.
+    pub height: u32,
+    /// The depth of the base mip level in pixels. Must be 1 for everything but volume textures.
+    pub depth: u32,
+    /// The number of mip levels, including the base level.
+    pub mip_level_count: u32,
+    /// The number of array layers. Counts whole cubes for cubemaps. Must be 1 for volume textures.
+    pub array_layers: u32,
+    /// The dimension of the texture.
+    pub dimension: DdsDimension,
+    /// Whether the color data is stored in sRGB space. Only used by BC1, BC2, BC3, BC7 and
+    /// RGBA8.
+    pub srgb: bool,
+}
+
+impl DdsDescriptor {
+    /// Creates a descriptor of a single 2D texture without mip levels.
+    pub const fn new(variant: CompressionVariant, width: u32, height: u32) -> Self {
+        Self {
+            variant,
+            width,
+            height,
+            depth: 1,
+            mip_level_count: 1,
+            array_layers: 1,
+            dimension: DdsDimension::Texture2D,
+            srgb: false,
+        }
+    }
+
+    /// Returns the number of faces of every array layer.
+    pub const fn face_count(&self) -> u32 {
+        match self.dimension {
+            DdsDimension::Cube => 6,
+            DdsDimension::Texture2D | DdsDimension::Volume => 1,
+        }
+    }
+
+    /// Returns the size in bytes of a single face of the given mip level. For volume textures
+    /// the size includes all depth slices of the level.
+    pub const fn mip_byte_size(&self, mip_level: u32) -> usize {
+        let width = mip_dimension(self.width, mip_level);
+        let height = mip_dimension(self.height, mip_level);
+        let depth = match self.dimension {
+            DdsDimension::Volume => mip_dimension(self.depth, mip_level),
+            DdsDimension::Texture2D | DdsDimension::Cube => 1,
+        };
+        self.variant.blocks_byte_size(width, height) * depth as usize
+    }
+
+    /// Returns the size in bytes of a single face with all of its mip levels.
+    fn face_byte_size(&self) -> usize {
+        (0..self.mip_level_count)
+            .map(|mip_level| self.mip_byte_size(mip_level))
+            .sum()
+    }
+
+    /// Returns the size in bytes of the blocks of all subresources.
+    pub fn data_byte_size(&self) -> usize {
+        self.face_byte_size() * self.face_count() as usize * self.array_layers as usize
+    }
+
+    /// Returns the offset in bytes of the blocks of a subresource.
+    ///
+    /// # Arguments
+    /// * `array_layer` - The array layer. For cubemaps, the index of the cube
+    /// * `face` - The face of the cube. Must be 0 for everything but cubemaps
+    /// * `mip_level` - The mip level
+    ///
+    /// # Panics
+    /// - If the subresource is not part of the texture
+    pub fn subresource_offset(&self, array_layer: u32, face: u32, mip_level: u32) -> usize {
+        assert!(
+            array_layer < self.array_layers
+                && face < self.face_count()
+                && mip_level < self.mip_level_count,
+            "subresource is not part of the texture"
+        );
+
+        let face_index = (array_layer * self.face_count() + face) as usize;
+        let mip_offset: usize = (0..mip_level)
+            .map(|mip_level| self.mip_byte_size(mip_level))
+            .sum();
+
+        face_index * self.face_byte_size() + mip_offset
+    }
+
+    fn dxgi_format(&self) -> u32 {
+        match self.variant {
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC1 => 71 + self.srgb as u32,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC2 => 74 + self.srgb as u32,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC3 => 77 + self.srgb as u32,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC4 => 80,
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC5 => 83,
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => 95,
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(..) => 98 + self.srgb as u32,
+            CompressionVariant::RGBA8 => 28 + self.srgb as u32,
+        }
+    }
+
+    fn validate(&self) {
+        assert!(
+            self.width != 0 && self.height != 0 && self.depth != 0,
+            "texture dimensions must not be zero"
+        );
+        assert!(
+            self.mip_level_count != 0 && self.array_layers != 0,
+            "mip level count and array layers must not be zero"
+        );
+
+        match self.dimension {
+            DdsDimension::Texture2D => {
+                assert_eq!(self.depth, 1, "only volume textures can have a depth");
+            }
+            DdsDimension::Cube => {
+                assert_eq!(self.depth, 1, "only volume textures can have a depth");
+                assert_eq!(self.width, self.height, "cubemap faces must be square");
+            }
+            DdsDimension::Volume => {
+                assert_eq!(
+                    self.array_layers, 1,
+                    "volume textures can't have array layers"
+                );
+            }
+        }
+
+        // DDS files store the rows of uncompressed texels without the padding of RGBA8 outputs.
+        if self.variant == CompressionVariant::RGBA8 {
+            let last_level = self.mip_level_count - 1;
+            assert!(
+                mip_dimension(self.width, last_level) % 4 == 0
+                    && mip_dimension(self.height, last_level) % 4 == 0,
+                "all mip levels of RGBA8 textures need a width and height that are multiples of 4"
+            );
+        }
+    }
+}
+
+/// Writes a DDS file with the DX10 header extension.
+///
+/// # Arguments
+/// * `writer` - The destination of the file
+/// * `descriptor` - The layout of the texture
+/// * `blocks_data` - The blocks of all subresources in the order described by
+///   [`DdsDescriptor::subresource_offset()`]
+///
+/// # Panics
+/// - If the descriptor is invalid, for example a cubemap with non-square faces, a volume
+///   texture with array layers or an RGBA8 texture with mip levels whose size is not a
+///   multiple of 4
+/// - If `blocks_data` has not the expected size ([`DdsDescriptor::data_byte_size()`])
+pub fn write_dds(
+    writer: &mut impl Write,
+    descriptor: &DdsDescriptor,
+    blocks_data: &[u8],
+) -> io::Result<()> {
+    write_file(writer, descriptor, blocks_data, false)
+}
+
+/// Writes a DX9 style DDS file without the DX10 header extension, for engines that only read
+/// the FourCC code of the pixel format.
+///
+/// BC1 to BC5 are stored with the code of [`CompressionVariant::fourcc()`], RGBA8 with the bit
+/// masks of its channels. The format of a legacy file has no sRGB variant, so
+/// [`DdsDescriptor::srgb`] is ignored. Cubemaps, volume textures and mip levels are stored like
+/// [`write_dds()`] stores them.
+///
+/// # Arguments
+/// * `writer` - The destination of the file
+/// * `descriptor` - The layout of the texture
+/// * `blocks_data` - The blocks of all subresources in the order described by
+///   [`DdsDescriptor::subresource_offset()`]
+///
+/// # Panics
+/// - If the variant is BC6H or BC7, which can only be stored with the DX10 header extension
+/// - If the texture has more than one array layer
+/// - Under the same conditions as [`write_dds()`]
+pub fn write_dds_legacy(
+    writer: &mut impl Write,
+    descriptor: &DdsDescriptor,
+    blocks_data: &[u8],
+) -> io::Result<()> {
+    write_file(writer, descriptor, blocks_data, true)
+}
+
+fn write_file(
+    writer: &mut impl Write,
+    descriptor: &DdsDescriptor,
+    blocks_data: &[u8],
+    legacy: bool,
+) -> io::Result<()> {
+    descriptor.validate();
+
+    if legacy {
+        assert!(
+            descriptor.variant.fourcc().is_some()
+                || descriptor.variant == CompressionVariant::RGBA8,
+            "the variant can only be stored with the DX10 header extension"
+        );
+        assert_eq!(
+            descriptor.array_layers, 1,
+            "legacy DDS files can't store array layers"
+        );
+    }
+
+    assert_eq!(
+        blocks_data.len(),
+        descriptor.data_byte_size(),
+        "the blocks data has not the expected size"
+    );
+
+    let has_mips = descriptor.mip_level_count > 1;
+
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT;
+    let mut caps = DDSCAPS_TEXTURE;
+    let mut caps2 = 0;
+    let mut depth = 0;
+    let mut resource_dimension = D3D10_RESOURCE_DIMENSION_TEXTURE2D;
+    let mut misc_flag = 0;
+
+    let pitch_or_linear_size = if descriptor.variant == CompressionVariant::RGBA8 {
+        flags |= DDSD_PITCH;
+        descriptor.variant.bytes_per_row(descriptor.width)
+    } else {
+        flags |= DDSD_LINEARSIZE;
+        descriptor.mip_byte_size(0) as u32
+    };
+
+    if has_mips {
+        flags |= DDSD_MIPMAPCOUNT;
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+
+    if descriptor.array_layers > 1 {
+        caps |= DDSCAPS_COMPLEX;
+    }
+
+    match descriptor.dimension {
+        DdsDimension::Texture2D => {}
+        DdsDimension::Cube => {
+            caps |= DDSCAPS_COMPLEX;
+            caps2 |= DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES;
+            misc_flag |= D3D10_RESOURCE_MISC_TEXTURECUBE;
+        }
+        DdsDimension::Volume => {
+            flags |= DDSD_DEPTH;
+            caps |= DDSCAPS_COMPLEX;
+            caps2 |= DDSCAPS2_VOLUME;
+            depth = descriptor.depth;
+            resource_dimension = D3D10_RESOURCE_DIMENSION_TEXTURE3D;
+        }
+    }
+
+    let alpha_mode = match descriptor.variant {
+        // BC1 blocks can hold punch-through alpha, which can't be told from the variant.
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC1 => DDS_ALPHA_MODE_UNKNOWN,
+        variant if variant.has_alpha() => DDS_ALPHA_MODE_STRAIGHT,
+        _ => DDS_ALPHA_MODE_OPAQUE,
+    };
+
+    let mut header = Vec::with_capacity(HEADER_BYTE_SIZE);
+    header.extend_from_slice(MAGIC);
+
+    let mut push = |value: u32| header.extend_from_slice(&value.to_le_bytes());
+
+    push(HEADER_SIZE);
+    push(flags);
+    push(descriptor.height);
+    push(descriptor.width);
+    push(pitch_or_linear_size);
+    push(depth);
+    push(descriptor.mip_level_count);
+    (0..11).for_each(|_| push(0));
+
+    // Pixel format
+    push(PIXEL_FORMAT_SIZE);
+    match descriptor.variant.fourcc() {
+        _ if !legacy => {
+            push(DDPF_FOURCC);
+            push(u32::from_le_bytes(*b"DX10"));
+            (0..5).for_each(|_| push(0));
+        }
+        Some(fourcc) => {
+            push(DDPF_FOURCC);
+            push(u32::from_le_bytes(fourcc));
+            (0..5).for_each(|_| push(0));
+        }
+        None => {
+            push(DDPF_RGB | DDPF_ALPHAPIXELS);
+            push(0);
+            push(32);
+            push(0x0000_00FF);
+            push(0x0000_FF00);
+            push(0x00FF_0000);
+            push(0xFF00_0000);
+        }
+    }
+
+    push(caps);
+    push(caps2);
+    (0..3).for_each(|_| push(0));
+
+    if !legacy {
+        // DX10 header extension
+        push(descriptor.dxgi_format());
+        push(resource_dimension);
+        push(misc_flag);
+        push(descriptor.array_layers);
+        push(alpha_mode);
+    }
+
+    writer.write_all(&header)?;
+    writer.write_all(blocks_data)
+}
+
+/// The decoded texels of a [`LevelImage`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelData {
+    /// Tightly packed RGBA8 texels.
+    Rgba8(Vec<u8>),
+    /// Tightly packed RGBA16F texels of HDR variants.
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    Rgba16F(Vec<half::f16>),
+}
+
+/// A single decoded subresource of a texture, returned by [`decode_all()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelImage {
+    /// The array layer. For cubemaps, the index of the cube.
+    pub array_layer: u32,
+    /// The face of the cube. Always 0 for everything but cubemaps.
+    pub face: u32,
+    /// The mip level.
+    pub mip_level: u32,
+    /// The depth slice of the mip level. Always 0 for everything but volume textures.
+    pub depth_slice: u32,
+    /// The width of the mip level in pixels.
+    pub width: u32,
+    /// The height of the mip level in pixels.
+    pub height: u32,
+    /// The decoded texels.
+    pub data: LevelData,
+}
+
+/// Decodes every subresource of a texture.
+///
+/// Returns one image for every mip level of every face and array layer, and for every depth
+/// slice of volume textures, in the order DDS stores them. BC6H is decoded as unsigned RGBA16F,
+/// all other variants as RGBA8. The blocks of a file written by [`write_dds()`] start at
+/// [`HEADER_BYTE_SIZE`].
+///
+/// # Panics
+/// - If the descriptor is invalid, for the same reasons as [`write_dds()`]
+/// - If `blocks_data` has not the expected size ([`DdsDescriptor::data_byte_size()`])
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     dds::{decode_all, DdsDescriptor, LevelData},
+///     CompressionVariant,
+/// };
+///
+/// let descriptor = DdsDescriptor {
+///     mip_level_count: 3,
+///     ..DdsDescriptor::new(CompressionVariant::BC1, 16, 8)
+/// };
+/// let blocks_data = vec![0u8; descriptor.data_byte_size()];
+///
+/// let images = decode_all(&descriptor, &blocks_data);
+/// assert_eq!(images.len(), 3);
+/// assert_eq!((images[2].width, images[2].height), (4, 2));
+/// assert!(matches!(&images[2].data, LevelData::Rgba8(data) if data.len() == 4 * 2 * 4));
+/// ```
+pub fn decode_all(descriptor: &DdsDescriptor, blocks_data: &[u8]) -> Vec<LevelImage> {
+    descriptor.validate();
+
+    assert_eq!(
+        blocks_data.len(),
+        descriptor.data_byte_size(),
+        "the blocks data has not the expected size"
+    );
+
+    let variant = descriptor.variant;
+    let mut images = Vec::new();
+
+    for array_layer in 0..descriptor.array_layers {
+        for face in 0..descriptor.face_count() {
+            for mip_level in 0..descriptor.mip_level_count {
+                let width = mip_dimension(descriptor.width, mip_level);
+                let height = mip_dimension(descriptor.height, mip_level);
+                let depth = match descriptor.dimension {
+                    DdsDimension::Volume => mip_dimension(descriptor.depth, mip_level),
+                    DdsDimension::Texture2D | DdsDimension::Cube => 1,
+                };
+
+                let offset = descriptor.subresource_offset(array_layer, face, mip_level);
+                let slice_size = variant.blocks_byte_size(width, height);
+
+                for depth_slice in 0..depth {
+                    let start = offset + depth_slice as usize * slice_size;
+                    let blocks = &blocks_data[start..start + slice_size];
+
+                    images.push(LevelImage {
+                        array_layer,
+                        face,
+                        mip_level,
+                        depth_slice,
+                        width,
+                        height,
+                        data: decode_level(variant, blocks, width, height),
+                    });
+                }
+            }
+        }
+    }
+
+    images
+}
+
+fn decode_level(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> LevelData {
+    // The blocks at the right and bottom edge are decoded completely and then cropped.
+    let padded_width = width.next_multiple_of(4);
+    let padded_height = height.next_multiple_of(4);
+    let texel_count = padded_width as usize * padded_height as usize * 4;
+
+    #[cfg(feature = "bc6h")]
+    if variant.is_hdr() {
+        let mut rgba_data = vec![half::f16::ZERO; texel_count];
+        crate::decode::decompress_blocks_as_rgba16f(
+            variant,
+            blocks_data,
+            ImageViewMut::packed(&mut rgba_data, padded_width, padded_height),
+        );
+        return LevelData::Rgba16F(crop(&rgba_data, padded_width, width, height));
+    }
+
+    let mut rgba_data = vec![0; texel_count];
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks_data,
+        ImageViewMut::packed(&mut rgba_data, padded_width, padded_height),
+    );
+    LevelData::Rgba8(crop(&rgba_data, padded_width, width, height))
+}
+
+fn crop<T: Copy>(rgba_data: &[T], padded_width: u32, width: u32, height: u32) -> Vec<T> {
+    rgba_data
+        .chunks_exact(padded_width as usize * 4)
+        .take(height as usize)
+        .flat_map(|row| &row[..width as usize * 4])
+        .copied()
+        .collect()
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    fn read_u32(data: &[u8], index: usize) -> u32 {
+        u32::from_le_bytes(data[index * 4..index * 4 + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_subresource_layout() {
+        let descriptor = DdsDescriptor {
+            mip_level_count: 3,
+            array_layers: 2,
+            dimension: DdsDimension::Cube,
+            ..DdsDescriptor::new(CompressionVariant::BC1, 8, 8)
+        };
+
+        // Mips of 8x8, 4x4 and 2x2 pixels with 4, 1 and 1 blocks of 8 bytes.
+        assert_eq!(descriptor.data_byte_size(), 2 * 6 * (32 + 8 + 8));
+        assert_eq!(descriptor.subresource_offset(0, 1, 0), 48);
+        assert_eq!(descriptor.subresource_offset(1, 0, 2), 6 * 48 + 40);
+    }
+
+    #[test]
+    fn test_write_cubemap_header() {
+        let descriptor = DdsDescriptor {
+            dimension: DdsDimension::Cube,
+            srgb: true,
+            ..DdsDescriptor::new(CompressionVariant::BC3, 4, 4)
+        };
+
+        let mut file = Vec::new();
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+
+        assert_eq!(&file[..4], MAGIC);
+        assert_eq!(file.len(), 4 + 124 + 20 + 6 * 16);
+        assert_eq!(read_u32(&file, 21), u32::from_le_bytes(*b"DX10"));
+        assert_eq!(
+            read_u32(&file, 28),
+            DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES
+        );
+        assert_eq!(read_u32(&file, 32), 78);
+        assert_eq!(read_u32(&file, 33), D3D10_RESOURCE_DIMENSION_TEXTURE2D);
+        assert_eq!(read_u32(&file, 34), D3D10_RESOURCE_MISC_TEXTURECUBE);
+        assert_eq!(read_u32(&file, 35), 1);
+    }
+
+    #[test]
+    fn test_bc1_alpha_mode_is_not_opaque() {
+        use crate::{encode::compress_rgba8_bc1_punch_through, ImageView};
+
+        // The left half of the block is transparent.
+        let rgba_data: Vec<u8> = (0..16)
+            .flat_map(|index| {
+                if index % 4 < 2 {
+                    [0; 4]
+                } else {
+                    [200, 40, 40, 255]
+                }
+            })
+            .collect();
+        let mut blocks_data = [0; 8];
+        compress_rgba8_bc1_punch_through(ImageView::packed(&rgba_data, 4, 4), &mut blocks_data);
+        let decoded = crate::test_util::decode(CompressionVariant::BC1, &blocks_data, 4, 4);
+        assert!(decoded.chunks_exact(4).any(|texel| texel[3] == 0));
+
+        let mut file = Vec::new();
+        let descriptor = DdsDescriptor::new(CompressionVariant::BC1, 4, 4);
+        write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+        assert_eq!(read_u32(&file, 36), DDS_ALPHA_MODE_UNKNOWN);
+
+        for (variant, alpha_mode) in [
+            (CompressionVariant::BC3, DDS_ALPHA_MODE_STRAIGHT),
+            (CompressionVariant::BC4, DDS_ALPHA_MODE_OPAQUE),
+        ] {
+            let mut file = Vec::new();
+            let descriptor = DdsDescriptor::new(variant, 4, 4);
+            let blocks_data = vec![0; descriptor.data_byte_size()];
+            write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+            assert_eq!(read_u32(&file, 36), alpha_mode, "{variant:?}");
+        }
+    }
+
+    #[test]
+    fn test_write_legacy_header() {
+        let descriptor = DdsDescriptor {
+            mip_level_count: 2,
+            srgb: true,
+            ..DdsDescriptor::new(CompressionVariant::BC3, 8, 8)
+        };
+
+        let mut file = Vec::new();
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        write_dds_legacy(&mut file, &descriptor, &blocks_data).unwrap();
+
+        assert_eq!(file.len(), LEGACY_HEADER_BYTE_SIZE + 4 * 16 + 16);
+        assert_eq!(read_u32(&file, 20), DDPF_FOURCC);
+        assert_eq!(read_u32(&file, 21), u32::from_le_bytes(*b"DXT5"));
+        assert_eq!(read_u32(&file, 27) & DDSCAPS_MIPMAP, DDSCAPS_MIPMAP);
+        assert_eq!(&file[LEGACY_HEADER_BYTE_SIZE..], &blocks_data[..]);
+
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC2,
+            CompressionVariant::BC3,
+            CompressionVariant::BC4,
+            CompressionVariant::BC5,
+        ] {
+            let fourcc = variant.fourcc().unwrap();
+            assert_eq!(CompressionVariant::from_fourcc(fourcc), Some(variant));
+        }
+
+        let descriptor = DdsDescriptor::new(CompressionVariant::RGBA8, 4, 4);
+        let mut file = Vec::new();
+        write_dds_legacy(&mut file, &descriptor, &[0; 64]).unwrap();
+
+        assert_eq!(read_u32(&file, 20), DDPF_RGB | DDPF_ALPHAPIXELS);
+        assert_eq!(read_u32(&file, 22), 32);
+        assert_eq!(read_u32(&file, 26), 0xFF00_0000);
+    }
+
+    #[test]
+    #[should_panic(expected = "array layers")]
+    fn test_legacy_rejects_arrays() {
+        let descriptor = DdsDescriptor {
+            array_layers: 2,
+            ..DdsDescriptor::new(CompressionVariant::BC1, 4, 4)
+        };
+
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        write_dds_legacy(&mut Vec::new(), &descriptor, &blocks_data).unwrap();
+    }
+
+    #[test]
+    fn test_volume_mip_sizes() {
+        let descriptor = DdsDescriptor {
+            depth: 4,
+            mip_level_count: 3,
+            dimension: DdsDimension::Volume,
+            ..DdsDescriptor::new(CompressionVariant::BC4, 8, 8)
+        };
+
+        assert_eq!(descriptor.mip_byte_size(0), 4 * 4 * 8);
+        assert_eq!(descriptor.mip_byte_size(1), 2 * 8);
+        assert_eq!(descriptor.mip_byte_size(2), 8);
+    }
+
+    #[test]
+    fn test_write_rgba8_header() {
+        let descriptor = DdsDescriptor {
+            mip_level_count: 2,
+            srgb: true,
+            ..DdsDescriptor::new(CompressionVariant::RGBA8, 8, 8)
+        };
+
+        let mut file = Vec::new();
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        write_dds(&mut file, &descriptor, &blocks_data).unwrap();
+
+        assert_eq!(file.len(), 4 + 124 + 20 + (8 * 8 + 4 * 4) * 4);
+        assert_eq!(
+            read_u32(&file, 2) & (DDSD_PITCH | DDSD_LINEARSIZE),
+            DDSD_PITCH
+        );
+        assert_eq!(read_u32(&file, 5), 8 * 4);
+        assert_eq!(read_u32(&file, 32), 29);
+    }
+
+    #[test]
+    #[should_panic(expected = "multiples of 4")]
+    fn test_rgba8_needs_unpadded_mip_levels() {
+        let descriptor = DdsDescriptor {
+            mip_level_count: 3,
+            ..DdsDescriptor::new(CompressionVariant::RGBA8, 8, 8)
+        };
+
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+        write_dds(&mut Vec::new(), &descriptor, &blocks_data).unwrap();
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let descriptor = DdsDescriptor {
+            mip_level_count: 2,
+            array_layers: 2,
+            ..DdsDescriptor::new(CompressionVariant::RGBA8, 8, 8)
+        };
+
+        // Every subresource is filled with its own value.
+        let mut blocks_data = vec![0; descriptor.data_byte_size()];
+        for array_layer in 0..2 {
+            for mip_level in 0..2 {
+                let offset = descriptor.subresource_offset(array_layer, 0, mip_level);
+                let size = descriptor.mip_byte_size(mip_level);
+                blocks_data[offset..offset + size].fill((array_layer * 2 + mip_level) as u8);
+            }
+        }
+
+        let images = decode_all(&descriptor, &blocks_data);
+        assert_eq!(images.len(), 4);
+
+        for (index, image) in images.iter().enumerate() {
+            assert_eq!(
+                (image.array_layer, image.mip_level),
+                (index as u32 / 2, index as u32 % 2)
+            );
+            assert_eq!(image.width, 8 >> image.mip_level);
+
+            let data = match &image.data {
+                LevelData::Rgba8(data) => data,
+                #[cfg(feature = "bc6h")]
+                LevelData::Rgba16F(..) => panic!("RGBA8 texture decoded as HDR"),
+            };
+            assert_eq!(data.len(), (image.width * image.height * 4) as usize);
+            assert!(data.iter().all(|&value| value == index as u8));
+        }
+    }
+
+    #[test]
+    fn test_decode_all_volume_slices() {
+        let descriptor = DdsDescriptor {
+            depth: 4,
+            mip_level_count: 2,
+            dimension: DdsDimension::Volume,
+            ..DdsDescriptor::new(CompressionVariant::BC4, 6, 6)
+        };
+        let blocks_data = vec![0; descriptor.data_byte_size()];
+
+        // The 6x6 and 3x3 levels only partially cover their blocks.
+        let images = decode_all(&descriptor, &blocks_data);
+        assert!(images.iter().all(|image| match &image.data {
+            LevelData::Rgba8(data) => data.len() == (image.width * image.height * 4) as usize,
+            #[cfg(feature = "bc6h")]
+            LevelData::Rgba16F(..) => false,
+        }));
+
+        let slices: Vec<_> = images
+            .iter()
+            .map(|image| (image.mip_level, image.depth_slice))
+            .collect();
+        assert_eq!(slices, [(0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_dxgi_format_round_trip() {
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC2,
+            CompressionVariant::BC3,
+            CompressionVariant::BC4,
+            CompressionVariant::BC5,
+        ] {
+            for srgb in [false, true] {
+                let descriptor = DdsDescriptor {
+                    srgb,
+                    ..DdsDescriptor::new(variant, 4, 4)
+                };
+                let format = descriptor.dxgi_format();
+                assert_eq!(CompressionVariant::from_dxgi(format), Some(variant));
+            }
+        }
+    }
+}
@@ -2,6 +2,10 @@
 
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
 mod block;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod compare;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod row;
 
 #[cfg(feature = "bc7")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
@@ -9,17 +13,42 @@ pub use self::block::decode_block_bc7;
 #[cfg(feature = "bc15")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
 pub use self::block::{
-    decode_block_bc1, decode_block_bc2, decode_block_bc3, decode_block_bc4, decode_block_bc5,
+    decode_block_bc1, decode_block_bc2, decode_block_bc3, decode_block_bc4,
+    decode_block_bc4_signed, decode_block_bc5, decode_block_bc5_signed,
+};
+#[cfg(feature = "bc15")]
+use self::block::{
+    decode_block_bc3_with, decode_block_bc4_signed_with, decode_block_bc4_with,
+    decode_block_bc5_signed_with, decode_block_bc5_with,
 };
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
 pub use self::block::{decode_block_bc6h, decode_block_bc6h_float};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+pub(crate) use self::compare::compare_rgba8;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::compare::{compare_compressed, DiffStats};
 #[cfg(feature = "bc6h")]
-use crate::BC6HSettings;
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub use self::row::decode_row_bc6h;
+#[cfg(feature = "bc7")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
+pub use self::row::decode_row_bc7;
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub use self::row::{
+    decode_row_bc1, decode_row_bc2, decode_row_bc3, decode_row_bc4, decode_row_bc5,
+};
 #[cfg(feature = "bc7")]
 use crate::BC7Settings;
+#[cfg(feature = "bc6h")]
+use crate::{color::linear_to_srgb8, BC6HSettings};
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-use crate::CompressionVariant;
+use crate::{CompressionVariant, ImageViewMut};
 
 /// Trait to decode a BC variant into RGBA8 data.
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
@@ -47,16 +76,82 @@ struct BC1Decoder;
 #[cfg(feature = "bc15")]
 struct BC2Decoder;
 #[cfg(feature = "bc15")]
-struct BC3Decoder;
+struct BC3Decoder<const EXACT: bool>;
 #[cfg(feature = "bc15")]
-struct BC4Decoder;
+struct BC4Decoder<const SIGNED: bool, const EXACT: bool>;
 #[cfg(feature = "bc15")]
-struct BC5Decoder;
+struct BC5Decoder<const SIGNED: bool, const EXACT: bool>;
 #[cfg(feature = "bc6h")]
-struct BC6HDecoder;
+struct BC6HDecoder<const SIGNED: bool>;
 #[cfg(feature = "bc7")]
 struct BC7Decoder;
 
+/// How the HDR values of BC6H blocks are mapped into the displayable range when they are
+/// converted to RGBA8.
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ToneMapping {
+    /// Values are clamped into the `[0, 1]` range. Everything brighter than `1.0` is clipped.
+    #[default]
+    Clamp,
+    /// The simple Reinhard operator `x / (1 + x)`, which compresses the whole positive range
+    /// into `[0, 1)` and keeps highlights distinguishable.
+    Reinhard,
+}
+
+#[cfg(feature = "bc6h")]
+impl ToneMapping {
+    fn apply(self, linear: f32) -> f32 {
+        // Negative values of signed blocks and NaNs can't be displayed.
+        let linear = if linear > 0.0 { linear } else { 0.0 };
+
+        match self {
+            ToneMapping::Clamp => linear.min(1.0),
+            ToneMapping::Reinhard => linear / (1.0 + linear),
+        }
+    }
+}
+
+/// Options that control how BC6H blocks are decoded.
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BC6HDecodeOptions {
+    /// Decodes the blocks as the signed `BC6H_SF16` format instead of the unsigned
+    /// `BC6H_UF16` format.
+    pub signed: bool,
+    /// The tone mapping that is used when converting to RGBA8. Ignored by the float outputs.
+    pub tone_mapping: ToneMapping,
+}
+
+/// Options that control how blocks are decoded into RGBA8 images.
+///
+/// The default options keep the plain behavior of [`decompress_blocks_as_rgba8()`].
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct DecodeOptions {
+    /// The RGBA8 value that transparent BC1 texels are decoded to. Defaults to transparent
+    /// black.
+    pub bc1_transparent_color: [u8; 4],
+    /// Fills the channels the variant doesn't store instead of leaving them zero. BC4 is
+    /// decoded as opaque grayscale, BC5 and BC6H get an opaque alpha channel.
+    pub expand_channels: bool,
+    /// Decodes BC4, BC5 and BC6H blocks as their signed formats. The signed BC4 and BC5 values
+    /// are mapped from `[-1, 1]` into `[0, 255]` and negative BC6H values are clamped to zero.
+    /// Ignored by all other variants.
+    pub signed: bool,
+    /// Interpolates BC3 alpha, BC4 and BC5 exactly like the D3D11 functional spec, which
+    /// rounds to the nearest value, instead of truncating like the faster default. The decoded
+    /// values then match reference decoders bit for bit, which validation tools need. BC1 and
+    /// BC2 colors, BC6H and BC7 always follow the spec exactly and are not affected.
+    pub d3d11_exact: bool,
+}
+
 #[cfg(feature = "bc15")]
 impl BlockRgba8Decoder for BC1Decoder {
     #[inline(always)]
@@ -82,10 +177,10 @@ impl BlockRgba8Decoder for BC2Decoder {
 }
 
 #[cfg(feature = "bc15")]
-impl BlockRgba8Decoder for BC3Decoder {
+impl<const EXACT: bool> BlockRgba8Decoder for BC3Decoder<EXACT> {
     #[inline(always)]
     fn decode_block_rgba8(compressed: &[u8], decompressed: &mut [u8], pitch: usize) {
-        decode_block_bc3(compressed, decompressed, pitch)
+        decode_block_bc3_with::<EXACT>(compressed, decompressed, pitch)
     }
 
     fn block_byte_size() -> u32 {
@@ -93,27 +188,87 @@ impl BlockRgba8Decoder for BC3Decoder {
     }
 }
 
+/// Maps a SNORM8 value from `[-1, 1]` into `[0, 255]`.
 #[cfg(feature = "bc15")]
-impl BlockRgba8Decoder for BC4Decoder {
+fn snorm8_to_unorm8(value: i8) -> u8 {
+    let value = (value as i32).max(-127) + 127;
+    ((value * 255 + 127) / 254) as u8
+}
+
+/// Trait to decode the channels of BC4 and BC5 blocks into pixels of any size.
+#[cfg(feature = "bc15")]
+trait BlockChannelDecoder {
+    /// The number of channels the blocks store.
+    const CHANNELS: usize;
+    /// The variant of the blocks.
+    const VARIANT: CompressionVariant;
+
+    /// Writes the channels into the first bytes of pixels that are `PIXEL_SIZE` bytes large,
+    /// without touching the remaining bytes.
+    fn decode_block_channels<const PIXEL_SIZE: usize>(
+        compressed: &[u8],
+        decompressed: &mut [u8],
+        pitch: usize,
+    );
+}
+
+#[cfg(feature = "bc15")]
+impl<const SIGNED: bool, const EXACT: bool> BlockChannelDecoder for BC4Decoder<SIGNED, EXACT> {
+    const CHANNELS: usize = 1;
+    const VARIANT: CompressionVariant = CompressionVariant::BC4;
+
     #[inline(always)]
-    fn decode_block_rgba8(compressed: &[u8], decompressed: &mut [u8], pitch: usize) {
-        const PITCH: usize = 4;
-        let mut buffer = [0u8; 16];
-        decode_block_bc4(compressed, &mut buffer, 4);
+    fn decode_block_channels<const PIXEL_SIZE: usize>(
+        compressed: &[u8],
+        decompressed: &mut [u8],
+        pitch: usize,
+    ) {
+        if SIGNED {
+            let mut signed_buffer = [0i8; 16];
+            decode_block_bc4_signed_with::<EXACT>(compressed, &mut signed_buffer, 4);
 
-        // Convert R8 to RGBA8
-        for y in 0..4 {
-            for x in 0..4 {
-                let out_pos = y * pitch + x * 4;
-                let in_pos = y * PITCH + x;
+            for (index, value) in signed_buffer.into_iter().enumerate() {
+                let out_pos = index / 4 * pitch + index % 4 * PIXEL_SIZE;
+                decompressed[out_pos] = snorm8_to_unorm8(value);
+            }
+        } else {
+            decode_block_bc4_with::<EXACT, PIXEL_SIZE>(compressed, decompressed, pitch);
+        }
+    }
+}
 
-                decompressed[out_pos] = buffer[in_pos];
-                decompressed[out_pos + 1] = 0;
-                decompressed[out_pos + 2] = 0;
-                decompressed[out_pos + 3] = 0;
+#[cfg(feature = "bc15")]
+impl<const SIGNED: bool, const EXACT: bool> BlockChannelDecoder for BC5Decoder<SIGNED, EXACT> {
+    const CHANNELS: usize = 2;
+    const VARIANT: CompressionVariant = CompressionVariant::BC5;
+
+    #[inline(always)]
+    fn decode_block_channels<const PIXEL_SIZE: usize>(
+        compressed: &[u8],
+        decompressed: &mut [u8],
+        pitch: usize,
+    ) {
+        if SIGNED {
+            let mut signed_buffer = [0i8; 32];
+            decode_block_bc5_signed_with::<EXACT>(compressed, &mut signed_buffer, 8);
+
+            for (index, values) in signed_buffer.chunks_exact(2).enumerate() {
+                let out_pos = index / 4 * pitch + index % 4 * PIXEL_SIZE;
+                decompressed[out_pos] = snorm8_to_unorm8(values[0]);
+                decompressed[out_pos + 1] = snorm8_to_unorm8(values[1]);
             }
+        } else {
+            decode_block_bc5_with::<EXACT, PIXEL_SIZE>(compressed, decompressed, pitch);
         }
     }
+}
+
+#[cfg(feature = "bc15")]
+impl<const SIGNED: bool, const EXACT: bool> BlockRgba8Decoder for BC4Decoder<SIGNED, EXACT> {
+    #[inline(always)]
+    fn decode_block_rgba8(compressed: &[u8], decompressed: &mut [u8], pitch: usize) {
+        decode_block_zero_filled::<Self, 4>(compressed, decompressed, pitch)
+    }
 
     fn block_byte_size() -> u32 {
         CompressionVariant::BC4.block_byte_size()
@@ -121,50 +276,87 @@ impl BlockRgba8Decoder for BC4Decoder {
 }
 
 #[cfg(feature = "bc15")]
-impl BlockRgba8Decoder for BC5Decoder {
+impl<const SIGNED: bool, const EXACT: bool> BlockRgba8Decoder for BC5Decoder<SIGNED, EXACT> {
     #[inline(always)]
     fn decode_block_rgba8(compressed: &[u8], decompressed: &mut [u8], pitch: usize) {
-        const PITCH: usize = 8;
-        let mut buffer = [0u8; 32];
-        decode_block_bc5(compressed, &mut buffer, PITCH);
+        decode_block_zero_filled::<Self, 4>(compressed, decompressed, pitch)
+    }
 
-        // Convert RG8 to RGBA8
+    fn block_byte_size() -> u32 {
+        CompressionVariant::BC5.block_byte_size()
+    }
+}
+
+/// Decodes the channels of a block into pixels of `PIXEL_SIZE` bytes and zeroes the bytes of
+/// the channels the block doesn't store.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+fn decode_block_zero_filled<D: BlockChannelDecoder, const PIXEL_SIZE: usize>(
+    compressed: &[u8],
+    decompressed: &mut [u8],
+    pitch: usize,
+) {
+    if PIXEL_SIZE > D::CHANNELS {
         for y in 0..4 {
             for x in 0..4 {
-                let out_pos = y * pitch + x * 4;
-                let in_pos = y * PITCH + x * 2;
-
-                decompressed[out_pos] = buffer[in_pos];
-                decompressed[out_pos + 1] = buffer[in_pos + 1];
-                decompressed[out_pos + 2] = 0;
-                decompressed[out_pos + 3] = 0;
+                let out_pos = y * pitch + x * PIXEL_SIZE;
+                decompressed[out_pos + D::CHANNELS..out_pos + PIXEL_SIZE].fill(0);
             }
         }
     }
 
-    fn block_byte_size() -> u32 {
-        CompressionVariant::BC5.block_byte_size()
-    }
+    D::decode_block_channels::<PIXEL_SIZE>(compressed, decompressed, pitch);
 }
 
-#[cfg(feature = "bc6h")]
-fn linear_to_srgb(linear: f32) -> u8 {
-    let v = if linear <= 0.0031308 {
-        linear * 12.92
-    } else {
-        1.055 * linear.powf(1.0 / 2.4) - 0.055
-    };
+/// Decodes BC4 or BC5 blocks into tightly packed rows of pixels that only hold the channels of
+/// the blocks. `PIXEL_SIZE` needs to match the channel count of the decoder.
+#[cfg(feature = "bc15")]
+fn decompress_channels<D: BlockChannelDecoder, const PIXEL_SIZE: usize>(
+    blocks_data: &[u8],
+    mut image: ImageViewMut<'_, u8>,
+) {
+    debug_assert_eq!(PIXEL_SIZE, D::CHANNELS);
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let blocks_x = width.div_ceil(4);
+    let block_byte_size = D::VARIANT.block_byte_size() as usize;
+    let row_pitch = image.stride() as usize;
+    let data = image.data_mut();
+    // Holds the blocks at the right and bottom edge, which are decoded completely and then
+    // cropped.
+    let mut edge_block = [0u8; 32];
 
-    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+    for (index, block) in blocks_data.chunks_exact(block_byte_size).enumerate() {
+        let x = index as u32 % blocks_x * 4;
+        let y = index as u32 / blocks_x * 4;
+        let out_pos = y as usize * row_pitch + x as usize * PIXEL_SIZE;
+
+        if x + 4 <= width && y + 4 <= height {
+            D::decode_block_channels::<PIXEL_SIZE>(block, &mut data[out_pos..], row_pitch);
+        } else {
+            D::decode_block_channels::<PIXEL_SIZE>(block, &mut edge_block, 4 * PIXEL_SIZE);
+
+            let columns = u32::min(width - x, 4) as usize * PIXEL_SIZE;
+            for row in 0..u32::min(height - y, 4) as usize {
+                let row_pos = out_pos + row * row_pitch;
+                data[row_pos..row_pos + columns]
+                    .copy_from_slice(&edge_block[row * 4 * PIXEL_SIZE..][..columns]);
+            }
+        }
+    }
 }
 
 #[cfg(feature = "bc6h")]
-impl BlockRgba8Decoder for BC6HDecoder {
+impl<const SIGNED: bool> BlockRgba8Decoder for BC6HDecoder<SIGNED> {
     #[inline(always)]
     fn decode_block_rgba8(compressed: &[u8], decompressed: &mut [u8], pitch: usize) {
         const PITCH: usize = 12;
         let mut buffer = [0.0_f32; 48];
-        decode_block_bc6h_float(compressed, &mut buffer, PITCH, false);
+        decode_block_bc6h_float(compressed, &mut buffer, PITCH, SIGNED);
 
         // Convert RGB16F to RGBA8
         for y in 0..4 {
@@ -172,9 +364,10 @@ impl BlockRgba8Decoder for BC6HDecoder {
                 let out_pos = y * pitch + x * 4;
                 let in_pos = y * PITCH + x * 3;
 
-                decompressed[out_pos] = linear_to_srgb(buffer[in_pos]) as _;
-                decompressed[out_pos + 1] = linear_to_srgb(buffer[in_pos + 1]) as _;
-                decompressed[out_pos + 2] = linear_to_srgb(buffer[in_pos + 2]) as _;
+                for channel in 0..3 {
+                    decompressed[out_pos + channel] =
+                        linear_to_srgb8(ToneMapping::Clamp.apply(buffer[in_pos + channel]));
+                }
                 decompressed[out_pos + 3] = 0;
             }
         }
@@ -198,16 +391,12 @@ impl BlockRgba8Decoder for BC7Decoder {
 }
 
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-fn decompress_rgba8<D: BlockRgba8Decoder>(
-    width: u32,
-    height: u32,
-    blocks_data: &[u8],
-    rgba_data: &mut [u8],
-) {
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
+fn decompress_rgba8<D: BlockRgba8Decoder>(blocks_data: &[u8], image: &mut ImageViewMut<'_, u8>) {
+    let blocks_x = (image.width() + 3) / 4;
+    let blocks_y = (image.height() + 3) / 4;
     let block_byte_size = D::block_byte_size() as usize;
-    let output_row_pitch = width as usize * 4; // Always RGBA
+    let output_row_pitch = image.stride() as usize;
+    let rgba_data = image.data_mut();
 
     for by in 0..blocks_y {
         for bx in 0..blocks_x {
@@ -231,11 +420,36 @@ fn decompress_rgba8<D: BlockRgba8Decoder>(
     }
 }
 
+/// Copies uncompressed RGBA8 texels, whose rows are padded to a multiple of 4 texels.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+fn copy_rgba8(texels: &[u8], image: &mut ImageViewMut<'_, u8>) {
+    let row_pitch = CompressionVariant::RGBA8.bytes_per_row(image.width()) as usize;
+
+    for y in 0..image.height() {
+        let row = image.row_mut(y);
+        let offset = y as usize * row_pitch;
+        row.copy_from_slice(&texels[offset..offset + row.len()]);
+    }
+}
+
 #[cfg(feature = "bc6h")]
-impl BlockRgba16fDecoder for BC6HDecoder {
+impl<const SIGNED: bool> BlockRgba16fDecoder for BC6HDecoder<SIGNED> {
     #[inline(always)]
     fn decode_block_rgba16f(compressed: &[u8], decompressed: &mut [half::f16], pitch: usize) {
-        decode_block_bc6h(compressed, decompressed, pitch, false);
+        const PITCH: usize = 12;
+        let mut buffer = [half::f16::ZERO; 48];
+        decode_block_bc6h(compressed, &mut buffer, PITCH, SIGNED);
+
+        // Convert RGB16F to RGBA16F
+        for y in 0..4 {
+            for x in 0..4 {
+                let out_pos = y * pitch + x * 4;
+                let in_pos = y * PITCH + x * 3;
+
+                decompressed[out_pos..out_pos + 3].copy_from_slice(&buffer[in_pos..in_pos + 3]);
+                decompressed[out_pos + 3] = half::f16::ONE;
+            }
+        }
     }
 
     fn block_byte_size() -> u32 {
@@ -245,15 +459,14 @@ impl BlockRgba16fDecoder for BC6HDecoder {
 
 #[cfg(feature = "bc6h")]
 fn decompress_rgba16f<D: BlockRgba16fDecoder>(
-    width: u32,
-    height: u32,
     blocks_data: &[u8],
-    rgba_data: &mut [half::f16],
+    mut image: ImageViewMut<'_, half::f16>,
 ) {
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
+    let blocks_x = (image.width() + 3) / 4;
+    let blocks_y = (image.height() + 3) / 4;
     let block_byte_size = D::block_byte_size() as usize;
-    let output_row_pitch = width as usize * 4; // Always RGBA16f
+    let output_row_pitch = image.stride() as usize;
+    let rgba_data = image.data_mut();
 
     for by in 0..blocks_y {
         for bx in 0..blocks_x {
@@ -278,10 +491,23 @@ fn decompress_rgba16f<D: BlockRgba16fDecoder>(
 }
 
 #[cfg(feature = "bc6h")]
-impl BlockRgba32fDecoder for BC6HDecoder {
+impl<const SIGNED: bool> BlockRgba32fDecoder for BC6HDecoder<SIGNED> {
     #[inline(always)]
     fn decode_block_rgba32f(compressed: &[u8], decompressed: &mut [f32], pitch: usize) {
-        decode_block_bc6h_float(compressed, decompressed, pitch, false);
+        const PITCH: usize = 12;
+        let mut buffer = [0.0_f32; 48];
+        decode_block_bc6h_float(compressed, &mut buffer, PITCH, SIGNED);
+
+        // Convert RGB32F to RGBA32F
+        for y in 0..4 {
+            for x in 0..4 {
+                let out_pos = y * pitch + x * 4;
+                let in_pos = y * PITCH + x * 3;
+
+                decompressed[out_pos..out_pos + 3].copy_from_slice(&buffer[in_pos..in_pos + 3]);
+                decompressed[out_pos + 3] = 1.0;
+            }
+        }
     }
 
     fn block_byte_size() -> u32 {
@@ -291,15 +517,14 @@ impl BlockRgba32fDecoder for BC6HDecoder {
 
 #[cfg(feature = "bc6h")]
 fn decompress_rgba32f<D: BlockRgba32fDecoder>(
-    width: u32,
-    height: u32,
     blocks_data: &[u8],
-    rgba_data: &mut [f32],
+    mut image: ImageViewMut<'_, f32>,
 ) {
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
+    let blocks_x = (image.width() + 3) / 4;
+    let blocks_y = (image.height() + 3) / 4;
     let block_byte_size = D::block_byte_size() as usize;
-    let output_row_pitch = width as usize * 4; // Always RGBA32f
+    let output_row_pitch = image.stride() as usize;
+    let rgba_data = image.data_mut();
 
     for by in 0..blocks_y {
         for bx in 0..blocks_x {
@@ -325,9 +550,11 @@ fn decompress_rgba32f<D: BlockRgba32fDecoder>(
 
 /// Helper function to easily decompress block data into RGBA8 data.
 ///
+/// BC6H blocks are decoded as unsigned and clamped into the displayable range. Use
+/// [`decompress_bc6h_blocks_as_rgba8()`] to decode signed blocks or to select a tone mapping.
+///
 /// # Panics
 /// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
-/// - The `rgba_data` has not the expected size (`width * height * 4`)
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
 #[cfg_attr(
     docsrs,
@@ -335,73 +562,527 @@ fn decompress_rgba32f<D: BlockRgba32fDecoder>(
 )]
 pub fn decompress_blocks_as_rgba8(
     variant: CompressionVariant,
-    width: u32,
-    height: u32,
     blocks_data: &[u8],
-    rgba_data: &mut [u8],
+    image: ImageViewMut<'_, u8>,
 ) {
-    let expected_input_size = variant.blocks_byte_size(width, height);
+    image.assert_rgba();
+    decompress_blocks_as_rgba8_with_options(variant, blocks_data, image, &DecodeOptions::default());
+}
+
+/// Decompresses block data into RGBA8 data with the given [`DecodeOptions`].
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     decode::{decompress_blocks_as_rgba8_with_options, DecodeOptions},
+///     CompressionVariant, ImageViewMut,
+/// };
+///
+/// let variant = CompressionVariant::BC4;
+/// let blocks_data = vec![0u8; variant.blocks_byte_size(64, 64)];
+/// let mut rgba_data = vec![0u8; 64 * 64 * 4];
+///
+/// let options = DecodeOptions {
+///     expand_channels: true,
+///     ..Default::default()
+/// };
+/// decompress_blocks_as_rgba8_with_options(
+///     variant,
+///     &blocks_data,
+///     ImageViewMut::packed(&mut rgba_data, 64, 64),
+///     &options,
+/// );
+/// assert!(rgba_data.chunks_exact(4).all(|texel| texel[3] == 255));
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn decompress_blocks_as_rgba8_with_options(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    mut image: ImageViewMut<'_, u8>,
+    options: &DecodeOptions,
+) {
+    image.assert_rgba();
+    let expected_input_size = variant.blocks_byte_size(image.width(), image.height());
     assert_eq!(
         blocks_data.len(),
         expected_input_size,
         "the input bitstream slice has not the expected size"
     );
 
-    let expected_output_size = width as usize * height as usize * 4;
-    assert_eq!(
-        rgba_data.len(),
-        expected_output_size,
-        "the output slice has not the expected size"
-    );
-
-    match variant {
+    let image = &mut image;
+    match (variant, options.signed) {
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC1 => {
-            decompress_rgba8::<BC1Decoder>(width, height, blocks_data, rgba_data)
-        }
+        (CompressionVariant::BC1, _) => decompress_rgba8::<BC1Decoder>(blocks_data, image),
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC2 => {
-            decompress_rgba8::<BC2Decoder>(width, height, blocks_data, rgba_data)
-        }
+        (CompressionVariant::BC2, _) => decompress_rgba8::<BC2Decoder>(blocks_data, image),
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC3 => {
-            decompress_rgba8::<BC3Decoder>(width, height, blocks_data, rgba_data)
-        }
+        (CompressionVariant::BC3, _) => match options.d3d11_exact {
+            false => decompress_rgba8::<BC3Decoder<false>>(blocks_data, image),
+            true => decompress_rgba8::<BC3Decoder<true>>(blocks_data, image),
+        },
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC4 => {
-            decompress_rgba8::<BC4Decoder>(width, height, blocks_data, rgba_data)
-        }
+        (CompressionVariant::BC4, false) => match options.d3d11_exact {
+            false => decompress_rgba8::<BC4Decoder<false, false>>(blocks_data, image),
+            true => decompress_rgba8::<BC4Decoder<false, true>>(blocks_data, image),
+        },
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC5 => {
-            decompress_rgba8::<BC5Decoder>(width, height, blocks_data, rgba_data)
+        (CompressionVariant::BC4, true) => match options.d3d11_exact {
+            false => decompress_rgba8::<BC4Decoder<true, false>>(blocks_data, image),
+            true => decompress_rgba8::<BC4Decoder<true, true>>(blocks_data, image),
+        },
+        #[cfg(feature = "bc15")]
+        (CompressionVariant::BC5, false) => match options.d3d11_exact {
+            false => decompress_rgba8::<BC5Decoder<false, false>>(blocks_data, image),
+            true => decompress_rgba8::<BC5Decoder<false, true>>(blocks_data, image),
+        },
+        #[cfg(feature = "bc15")]
+        (CompressionVariant::BC5, true) => match options.d3d11_exact {
+            false => decompress_rgba8::<BC5Decoder<true, false>>(blocks_data, image),
+            true => decompress_rgba8::<BC5Decoder<true, true>>(blocks_data, image),
+        },
+        #[cfg(feature = "bc6h")]
+        (CompressionVariant::BC6H(..), false) => {
+            decompress_rgba8::<BC6HDecoder<false>>(blocks_data, image)
         }
         #[cfg(feature = "bc6h")]
-        CompressionVariant::BC6H(..) => {
-            decompress_rgba8::<BC6HDecoder>(width, height, blocks_data, rgba_data)
+        (CompressionVariant::BC6H(..), true) => {
+            decompress_rgba8::<BC6HDecoder<true>>(blocks_data, image)
         }
         #[cfg(feature = "bc7")]
-        CompressionVariant::BC7(..) => {
-            decompress_rgba8::<BC7Decoder>(width, height, blocks_data, rgba_data)
+        (CompressionVariant::BC7(..), _) => decompress_rgba8::<BC7Decoder>(blocks_data, image),
+        (CompressionVariant::RGBA8, _) => copy_rgba8(blocks_data, image),
+    }
+
+    #[cfg(any(feature = "bc15", feature = "bc6h"))]
+    apply_decode_options(variant, image, options);
+}
+
+/// Decompresses BC5 blocks into grayscale and alpha (LA8) data.
+///
+/// The counterpart of [`compress_la8_bc5()`](crate::encode::compress_la8_bc5), which stores the
+/// luminance in the red channel and the alpha in the green channel. `image` receives the
+/// luminance and the alpha of every pixel and needs two channels.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`CompressionVariant::BC5.blocks_byte_size()`)
+/// - If `image` doesn't have exactly two channels
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     decode::decompress_bc5_as_la8, encode::compress_la8_bc5, CompressionVariant, ImageView,
+///     ImageViewMut,
+/// };
+///
+/// let (width, height) = (6, 5);
+/// let la_data: Vec<u8> = (0..width * height).flat_map(|index| [index as u8 * 8, 255]).collect();
+///
+/// let mut blocks_data = vec![0u8; CompressionVariant::BC5.blocks_byte_size(width, height)];
+/// let image = ImageView::packed_with_channels(&la_data, width, height, 2);
+/// compress_la8_bc5(image, &mut blocks_data);
+///
+/// let mut decoded = vec![0u8; la_data.len()];
+/// let image = ImageViewMut::packed_with_channels(&mut decoded, width, height, 2);
+/// decompress_bc5_as_la8(&blocks_data, image);
+/// assert!(decoded.chunks_exact(2).all(|texel| texel[1] == 255));
+/// ```
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decompress_bc5_as_la8(blocks_data: &[u8], image: ImageViewMut<'_, u8>) {
+    assert_eq!(
+        image.channels(),
+        2,
+        "the image needs to hold LA8 data with 2 channels"
+    );
+
+    decompress_bc5_as_rg8(blocks_data, image, &DecodeOptions::default());
+}
+
+/// Decompresses two BC4 buffers of the luminance and the alpha into grayscale and alpha (LA8)
+/// data.
+///
+/// The counterpart of [`compress_la8_bc4()`](crate::encode::compress_la8_bc4). Works like
+/// [`decompress_bc5_as_la8()`].
+///
+/// # Panics
+/// - The `luminance_blocks` or `alpha_blocks` have not the expected size
+///   (`CompressionVariant::BC4.blocks_byte_size()`)
+/// - If `image` doesn't have exactly two channels
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decompress_bc4_as_la8(
+    luminance_blocks: &[u8],
+    alpha_blocks: &[u8],
+    image: ImageViewMut<'_, u8>,
+) {
+    let expected_input_size =
+        CompressionVariant::BC4.blocks_byte_size(image.width(), image.height());
+    assert_eq!(
+        luminance_blocks.len(),
+        expected_input_size,
+        "the luminance bitstream slice has not the expected size"
+    );
+    assert_eq!(
+        alpha_blocks.len(),
+        expected_input_size,
+        "the alpha bitstream slice has not the expected size"
+    );
+
+    // A BC5 block is a BC4 block of the red channel followed by one of the green channel.
+    let bc5_blocks: Vec<u8> = luminance_blocks
+        .chunks_exact(8)
+        .zip(alpha_blocks.chunks_exact(8))
+        .flat_map(|(luminance, alpha)| luminance.iter().chain(alpha).copied())
+        .collect();
+
+    decompress_bc5_as_la8(&bc5_blocks, image);
+}
+
+/// Decompresses BC4 blocks into single channel (R8) data.
+///
+/// Decodes straight into `image`, a view with one channel, instead of expanding every texel to
+/// RGBA8 like [`decompress_blocks_as_rgba8()`]. The `signed` and `d3d11_exact` fields of the
+/// `options` are applied like by [`decompress_blocks_as_rgba8_with_options()`], all other fields
+/// are ignored.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`CompressionVariant::BC4.blocks_byte_size()`)
+/// - If `image` doesn't have exactly one channel
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     decode::{decompress_bc4_as_r8, DecodeOptions},
+///     ImageViewMut,
+/// };
+///
+/// let (width, height) = (6, 5);
+/// // Both endpoints are 200.
+/// let blocks_data = [200, 200, 0, 0, 0, 0, 0, 0].repeat(4);
+///
+/// let mut r_data = vec![0u8; (width * height) as usize];
+/// let image = ImageViewMut::packed_with_channels(&mut r_data, width, height, 1);
+/// decompress_bc4_as_r8(&blocks_data, image, &DecodeOptions::default());
+/// assert!(r_data.iter().all(|&value| value == 200));
+/// ```
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decompress_bc4_as_r8(
+    blocks_data: &[u8],
+    image: ImageViewMut<'_, u8>,
+    options: &DecodeOptions,
+) {
+    assert_eq!(
+        blocks_data.len(),
+        CompressionVariant::BC4.blocks_byte_size(image.width(), image.height()),
+        "the input bitstream slice has not the expected size"
+    );
+    assert_eq!(
+        image.channels(),
+        1,
+        "the image needs to hold R8 data with 1 channel"
+    );
+
+    let decompress = match (options.signed, options.d3d11_exact) {
+        (false, false) => decompress_channels::<BC4Decoder<false, false>, 1>,
+        (false, true) => decompress_channels::<BC4Decoder<false, true>, 1>,
+        (true, false) => decompress_channels::<BC4Decoder<true, false>, 1>,
+        (true, true) => decompress_channels::<BC4Decoder<true, true>, 1>,
+    };
+    decompress(blocks_data, image);
+}
+
+/// Decompresses BC5 blocks into two channel (RG8) data.
+///
+/// Works like [`decompress_bc4_as_r8()`], with a view of two channels.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`CompressionVariant::BC5.blocks_byte_size()`)
+/// - If `image` doesn't have exactly two channels
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decompress_bc5_as_rg8(
+    blocks_data: &[u8],
+    image: ImageViewMut<'_, u8>,
+    options: &DecodeOptions,
+) {
+    assert_eq!(
+        blocks_data.len(),
+        CompressionVariant::BC5.blocks_byte_size(image.width(), image.height()),
+        "the input bitstream slice has not the expected size"
+    );
+    assert_eq!(
+        image.channels(),
+        2,
+        "the image needs to hold RG8 data with 2 channels"
+    );
+
+    let decompress = match (options.signed, options.d3d11_exact) {
+        (false, false) => decompress_channels::<BC5Decoder<false, false>, 2>,
+        (false, true) => decompress_channels::<BC5Decoder<false, true>, 2>,
+        (true, false) => decompress_channels::<BC5Decoder<true, false>, 2>,
+        (true, true) => decompress_channels::<BC5Decoder<true, true>, 2>,
+    };
+    decompress(blocks_data, image);
+}
+
+/// Fills the transparent BC1 texels and expands the missing channels.
+#[cfg(any(feature = "bc15", feature = "bc6h"))]
+fn apply_decode_options(
+    variant: CompressionVariant,
+    image: &mut ImageViewMut<'_, u8>,
+    options: &DecodeOptions,
+) {
+    let fill_transparent = options.bc1_transparent_color;
+    let apply: fn(&mut [u8], [u8; 4]) = match variant {
+        // Only the transparent texels of BC1 have a zero alpha.
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC1 if fill_transparent != [0; 4] => |texel, color| {
+            if texel[3] == 0 {
+                texel.copy_from_slice(&color);
+            }
+        },
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC4 if options.expand_channels => |texel, _| {
+            texel[1] = texel[0];
+            texel[2] = texel[0];
+            texel[3] = 255;
+        },
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC5 if options.expand_channels => |texel, _| texel[3] = 255,
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(..) if options.expand_channels => |texel, _| texel[3] = 255,
+        _ => return,
+    };
+
+    for y in 0..image.height() {
+        for texel in image.row_mut(y).chunks_exact_mut(4) {
+            apply(texel, fill_transparent);
+        }
+    }
+}
+
+/// The content of a single plane of a planar RGBA8 output.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PlaneChannel {
+    /// The red channel.
+    Red,
+    /// The green channel.
+    Green,
+    /// The blue channel.
+    Blue,
+    /// The alpha channel.
+    Alpha,
+    /// The full range BT.709 luma of the RGB channels.
+    Luma,
+}
+
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+impl PlaneChannel {
+    fn extract(self, texel: &[u8]) -> u8 {
+        match self {
+            Self::Red => texel[0],
+            Self::Green => texel[1],
+            Self::Blue => texel[2],
+            Self::Alpha => texel[3],
+            Self::Luma => {
+                let luma =
+                    0.2126 * texel[0] as f32 + 0.7152 * texel[1] as f32 + 0.0722 * texel[2] as f32;
+                luma.round() as u8
+            }
+        }
+    }
+}
+
+/// A single 8-bit plane of a planar output.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+#[derive(Debug)]
+pub struct Plane<'a> {
+    /// The channel that is written into the plane.
+    pub channel: PlaneChannel,
+    /// The data of the plane, starting with the first pixel of the first row.
+    pub data: &'a mut [u8],
+    /// The number of bytes between the start of two rows.
+    pub pitch: usize,
+}
+
+/// The destination of decoded RGBA8 data.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+#[derive(Debug)]
+pub enum DecodeOutput<'a, 'b> {
+    /// Interleaved RGBA8 pixels.
+    Interleaved(ImageViewMut<'a, u8>),
+    /// Separate planes with their own pitch, each holding a single channel. Media pipelines can
+    /// feed them to encoders that take planar input, or extract only the luma or alpha.
+    Planar {
+        /// The width of the image in pixels.
+        width: u32,
+        /// The height of the image in pixels.
+        height: u32,
+        /// The planes to write.
+        planes: &'b mut [Plane<'a>],
+    },
+}
+
+/// Decompresses block data into interleaved or planar RGBA8 data with the given
+/// [`DecodeOptions`].
+///
+/// Planar output is decoded one row of blocks at a time, so it only needs a small temporary
+/// buffer.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+/// - If the pitch of a plane is smaller than the width, or its data is too small to hold
+///   `height` rows
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     decode::{decompress_blocks_into, DecodeOptions, DecodeOutput, Plane, PlaneChannel},
+///     CompressionVariant,
+/// };
+///
+/// let variant = CompressionVariant::BC3;
+/// let blocks_data = vec![0u8; variant.blocks_byte_size(64, 64)];
+///
+/// // The luma plane has a padded pitch.
+/// let mut luma = vec![0u8; 128 * 64];
+/// let mut alpha = vec![0u8; 64 * 64];
+/// let mut planes = [
+///     Plane { channel: PlaneChannel::Luma, data: &mut luma, pitch: 128 },
+///     Plane { channel: PlaneChannel::Alpha, data: &mut alpha, pitch: 64 },
+/// ];
+///
+/// decompress_blocks_into(
+///     variant,
+///     &blocks_data,
+///     DecodeOutput::Planar { width: 64, height: 64, planes: &mut planes },
+///     &DecodeOptions::default(),
+/// );
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn decompress_blocks_into(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    output: DecodeOutput<'_, '_>,
+    options: &DecodeOptions,
+) {
+    let (width, height, planes) = match output {
+        DecodeOutput::Interleaved(image) => {
+            decompress_blocks_as_rgba8_with_options(variant, blocks_data, image, options);
+            return;
+        }
+        DecodeOutput::Planar {
+            width,
+            height,
+            planes,
+        } => (width, height, planes),
+    };
+
+    let expected_input_size = variant.blocks_byte_size(width, height);
+    assert_eq!(
+        blocks_data.len(),
+        expected_input_size,
+        "the input bitstream slice has not the expected size"
+    );
+
+    for plane in planes.iter() {
+        assert!(
+            plane.pitch >= width as usize,
+            "pitch ({}) of the {:?} plane is smaller than the width ({})",
+            plane.pitch,
+            plane.channel,
+            width
+        );
+
+        let required_len = if height == 0 {
+            0
+        } else {
+            (height as usize - 1) * plane.pitch + width as usize
+        };
+        assert!(
+            plane.data.len() >= required_len,
+            "data size ({}) of the {:?} plane is too small. Required size: {}",
+            plane.data.len(),
+            plane.channel,
+            required_len
+        );
+    }
+
+    // The strip covers whole blocks, the pixels outside the image are skipped when copying.
+    let strip_width = width.next_multiple_of(4);
+    let row_byte_size = variant.blocks_byte_size(strip_width, 4);
+    let mut strip = vec![0; strip_width as usize * 4 * 4];
+
+    for (block_y, row_blocks) in blocks_data.chunks(row_byte_size.max(1)).enumerate() {
+        let y = block_y as u32 * 4;
+        let strip_height = u32::min(height - y, 4) as usize;
+
+        decompress_blocks_as_rgba8_with_options(
+            variant,
+            row_blocks,
+            ImageViewMut::packed(&mut strip, strip_width, 4),
+            options,
+        );
+
+        for (row, rgba_row) in strip
+            .chunks_exact(strip_width as usize * 4)
+            .take(strip_height)
+            .enumerate()
+        {
+            for plane in planes.iter_mut() {
+                let offset = (y as usize + row) * plane.pitch;
+                let plane_row = &mut plane.data[offset..offset + width as usize];
+
+                for (value, texel) in plane_row.iter_mut().zip(rgba_row.chunks_exact(4)) {
+                    *value = plane.channel.extract(texel);
+                }
+            }
         }
     }
 }
 
 /// Helper function to easily decompress block data into RGBA16F data. Only BCH6 is currently supported.
 ///
+/// The blocks are decoded as unsigned. Use [`decompress_bc6h_blocks_as_rgba16f()`] to decode
+/// signed blocks.
+///
 /// # Panics
 /// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
-/// - The `rgba_data` has not the expected size (`width * height * 4`)
 /// - If `variant` is any other value than BC6H.
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
 pub fn decompress_blocks_as_rgba16f(
     variant: CompressionVariant,
-    width: u32,
-    height: u32,
     blocks_data: &[u8],
-    rgba_data: &mut [half::f16],
+    image: ImageViewMut<'_, half::f16>,
 ) {
-    let expected_input_size = variant.blocks_byte_size(width, height);
+    image.assert_rgba();
+    let expected_input_size = variant.blocks_byte_size(image.width(), image.height());
 
     assert_eq!(
         blocks_data.len(),
@@ -409,16 +1090,9 @@ pub fn decompress_blocks_as_rgba16f(
         "the input bitstream slice has not the expected size"
     );
 
-    let expected_output_size = width as usize * height as usize * 4;
-    assert_eq!(
-        rgba_data.len(),
-        expected_output_size,
-        "the output slice has not the expected size"
-    );
-
     match variant {
         CompressionVariant::BC6H(..) => {
-            decompress_rgba16f::<BC6HDecoder>(width, height, blocks_data, rgba_data)
+            decompress_rgba16f::<BC6HDecoder<false>>(blocks_data, image)
         }
         #[allow(unreachable_patterns)]
         _ => {
@@ -429,36 +1103,30 @@ pub fn decompress_blocks_as_rgba16f(
 
 /// Helper function to easily decompress block data into RGBA32F data. Only BCH6 is currently supported.
 ///
+/// The blocks are decoded as unsigned. Use [`decompress_bc6h_blocks_as_rgba32f()`] to decode
+/// signed blocks.
+///
 /// # Panics
 /// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
-/// - The `rgba_data` has not the expected size (`width * height * 4`)
 /// - If `variant` is any other value than BC6H.
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
 pub fn decompress_blocks_as_rgba32f(
     variant: CompressionVariant,
-    width: u32,
-    height: u32,
     blocks_data: &[u8],
-    rgba_data: &mut [f32],
+    image: ImageViewMut<'_, f32>,
 ) {
-    let expected_input_size = variant.blocks_byte_size(width, height);
+    image.assert_rgba();
+    let expected_input_size = variant.blocks_byte_size(image.width(), image.height());
     assert_eq!(
         blocks_data.len(),
         expected_input_size,
         "the input bitstream slice has not the expected size"
     );
 
-    let expected_output_size = width as usize * height as usize * 4;
-    assert_eq!(
-        rgba_data.len(),
-        expected_output_size,
-        "the output slice has not the expected size"
-    );
-
     match variant {
         CompressionVariant::BC6H(..) => {
-            decompress_rgba32f::<BC6HDecoder>(width, height, blocks_data, rgba_data)
+            decompress_rgba32f::<BC6HDecoder<false>>(blocks_data, image)
         }
         #[allow(unreachable_patterns)]
         _ => {
@@ -466,3 +1134,377 @@ pub fn decompress_blocks_as_rgba32f(
         }
     }
 }
+
+/// Decompresses BC6H block data into RGBA8 data for previewing HDR content.
+///
+/// The blocks are decoded as signed or unsigned as selected by the `options`. The linear HDR
+/// values are tone mapped, converted to sRGB and written with an opaque alpha channel.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn decompress_bc6h_blocks_as_rgba8(
+    blocks_data: &[u8],
+    mut image: ImageViewMut<'_, u8>,
+    options: &BC6HDecodeOptions,
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    let mut hdr_data = vec![0.0; width as usize * height as usize * 4];
+    decompress_bc6h_blocks_as_rgba32f(
+        blocks_data,
+        ImageViewMut::packed(&mut hdr_data, width, height),
+        options.signed,
+    );
+
+    for (y, hdr_row) in (0..height).zip(hdr_data.chunks_exact(width as usize * 4)) {
+        let row = image.row_mut(y);
+
+        for (texel, hdr_texel) in row.chunks_exact_mut(4).zip(hdr_row.chunks_exact(4)) {
+            for channel in 0..3 {
+                texel[channel] = linear_to_srgb8(options.tone_mapping.apply(hdr_texel[channel]));
+            }
+            texel[3] = 255;
+        }
+    }
+}
+
+/// Decompresses BC6H block data into RGBA16F data as either the signed or unsigned format.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn decompress_bc6h_blocks_as_rgba16f(
+    blocks_data: &[u8],
+    image: ImageViewMut<'_, half::f16>,
+    signed: bool,
+) {
+    image.assert_rgba();
+    let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+    let expected_input_size = variant.blocks_byte_size(image.width(), image.height());
+    assert_eq!(
+        blocks_data.len(),
+        expected_input_size,
+        "the input bitstream slice has not the expected size"
+    );
+
+    if signed {
+        decompress_rgba16f::<BC6HDecoder<true>>(blocks_data, image)
+    } else {
+        decompress_rgba16f::<BC6HDecoder<false>>(blocks_data, image)
+    }
+}
+
+/// Decompresses BC6H block data into RGBA32F data as either the signed or unsigned format.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn decompress_bc6h_blocks_as_rgba32f(
+    blocks_data: &[u8],
+    image: ImageViewMut<'_, f32>,
+    signed: bool,
+) {
+    image.assert_rgba();
+    let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+    let expected_input_size = variant.blocks_byte_size(image.width(), image.height());
+    assert_eq!(
+        blocks_data.len(),
+        expected_input_size,
+        "the input bitstream slice has not the expected size"
+    );
+
+    if signed {
+        decompress_rgba32f::<BC6HDecoder<true>>(blocks_data, image)
+    } else {
+        decompress_rgba32f::<BC6HDecoder<false>>(blocks_data, image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_bc1_transparent_color() {
+        // Endpoints with c0 <= c1 select the BC1A mode, all indices select the transparent texel.
+        let blocks = [0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut rgba_data = [0xAA; 4 * 4 * 4];
+
+        decompress_blocks_as_rgba8(
+            CompressionVariant::BC1,
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 4, 4),
+        );
+        assert!(rgba_data.chunks_exact(4).all(|texel| texel == [0; 4]));
+
+        let options = DecodeOptions {
+            bc1_transparent_color: [255, 0, 255, 0],
+            ..Default::default()
+        };
+        decompress_blocks_as_rgba8_with_options(
+            CompressionVariant::BC1,
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 4, 4),
+            &options,
+        );
+        assert!(rgba_data
+            .chunks_exact(4)
+            .all(|texel| texel == [255, 0, 255, 0]));
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_d3d11_exact_interpolation() {
+        // Endpoints 10 and 0, index 2 selects 6/7 * 10 = 8.57.
+        let blocks = [10, 0, 0b010, 0, 0, 0, 0, 0];
+        let mut rgba_data = [0; 4 * 4 * 4];
+
+        decompress_blocks_as_rgba8(
+            CompressionVariant::BC4,
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 4, 4),
+        );
+        assert_eq!(rgba_data[0], 8);
+
+        let options = DecodeOptions {
+            d3d11_exact: true,
+            ..Default::default()
+        };
+        decompress_blocks_as_rgba8_with_options(
+            CompressionVariant::BC4,
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 4, 4),
+            &options,
+        );
+        assert_eq!(rgba_data[0], 9);
+
+        // Endpoints 0 and -10, index 3 selects 2/7 * -10 = -2.86.
+        let blocks = [0, 0xF6, 0b011, 0, 0, 0, 0, 0];
+        let mut signed = [0; 16];
+        decode_block_bc4_signed_with::<false>(&blocks, &mut signed, 4);
+        assert_eq!(signed[0], -2);
+        decode_block_bc4_signed_with::<true>(&blocks, &mut signed, 4);
+        assert_eq!(signed[0], -3);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_channel_outputs_match_rgba8() {
+        // The blocks of the 12x8 source only partially cover the 10x7 channel outputs.
+        let (width, height) = (10, 7);
+        let rgba_data: Vec<u8> = (0..12 * 8 * 4).map(|i| (i * 59 % 253) as u8).collect();
+
+        for variant in [CompressionVariant::BC4, CompressionVariant::BC5] {
+            let blocks = crate::test_util::compress(variant, &rgba_data, 12, 8);
+
+            let channels = variant.channel_count() as usize;
+            for (signed, d3d11_exact) in
+                [(false, false), (false, true), (true, false), (true, true)]
+            {
+                let options = DecodeOptions {
+                    signed,
+                    d3d11_exact,
+                    ..Default::default()
+                };
+
+                let mut expected = vec![0; 12 * 8 * 4];
+                decompress_blocks_as_rgba8_with_options(
+                    variant,
+                    &blocks,
+                    ImageViewMut::packed(&mut expected, 12, 8),
+                    &options,
+                );
+
+                let mut decoded = vec![0; (width * height) as usize * channels];
+                let image = ImageViewMut::packed_with_channels(
+                    &mut decoded,
+                    width,
+                    height,
+                    channels as u32,
+                );
+                match variant {
+                    CompressionVariant::BC4 => decompress_bc4_as_r8(&blocks, image, &options),
+                    _ => decompress_bc5_as_rg8(&blocks, image, &options),
+                }
+
+                let rows = decoded.chunks_exact(width as usize * channels);
+                for (row, expected_row) in rows.zip(expected.chunks_exact(12 * 4)) {
+                    for (texel, expected) in row.chunks_exact(channels).zip(expected_row.chunks(4))
+                    {
+                        assert_eq!(texel, &expected[..channels], "{variant:?} {options:?}");
+                    }
+                }
+                assert!(expected
+                    .chunks(4)
+                    .all(|texel| texel[channels..].iter().all(|&value| value == 0)));
+            }
+        }
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_bc4_signed_and_expanded() {
+        // Endpoints -127 and 127, index 0 selects -1.0 and index 1 selects 1.0.
+        let blocks = [0x81, 0x7F, 0b1000, 0, 0, 0, 0, 0];
+        let mut rgba_data = [0; 4 * 4 * 4];
+
+        let options = DecodeOptions {
+            expand_channels: true,
+            signed: true,
+            ..Default::default()
+        };
+        decompress_blocks_as_rgba8_with_options(
+            CompressionVariant::BC4,
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 4, 4),
+            &options,
+        );
+        assert_eq!(&rgba_data[..8], &[0, 0, 0, 255, 255, 255, 255, 255]);
+
+        let mut signed = [0; 16];
+        decode_block_bc4_signed(&blocks, &mut signed, 4);
+        assert_eq!(signed[..3], [-127, 127, -127]);
+        assert_eq!(snorm8_to_unorm8(0), 128);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_planar_output() {
+        // A BC3 block with an alpha of 255 and opaque white color.
+        let block = [
+            0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0,
+        ];
+        let variant = CompressionVariant::BC3;
+        let blocks = block.repeat(2 * 2);
+
+        let mut rgba_data = vec![0; 8 * 8 * 4];
+        decompress_blocks_into(
+            variant,
+            &blocks,
+            DecodeOutput::Interleaved(ImageViewMut::packed(&mut rgba_data, 8, 8)),
+            &DecodeOptions::default(),
+        );
+        assert!(rgba_data.iter().all(|&value| value == 255));
+
+        let mut luma = vec![0; 8 * 6];
+        let mut red = vec![0; 6 * 6];
+        let mut planes = [
+            Plane {
+                channel: PlaneChannel::Luma,
+                data: &mut luma,
+                pitch: 8,
+            },
+            Plane {
+                channel: PlaneChannel::Red,
+                data: &mut red,
+                pitch: 6,
+            },
+        ];
+        decompress_blocks_into(
+            variant,
+            &blocks,
+            DecodeOutput::Planar {
+                width: 6,
+                height: 6,
+                planes: &mut planes,
+            },
+            &DecodeOptions::default(),
+        );
+
+        for row in luma.chunks_exact(8) {
+            assert_eq!(row, [255, 255, 255, 255, 255, 255, 0, 0]);
+        }
+        assert!(red.iter().all(|&value| value == 255));
+    }
+
+    #[cfg(feature = "bc6h")]
+    fn compress_uniform_bc6h(value: f32) -> Vec<u8> {
+        let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+        let rgba_data = vec![half::f16::from_f32(value); 8 * 4 * 4];
+        crate::test_util::compress_hdr(variant, &rgba_data, 8, 4)
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_bc6h_rgba32f_is_rgba() {
+        let blocks = compress_uniform_bc6h(2.0);
+
+        let mut rgba_data = vec![0.0; 8 * 4 * 4];
+        decompress_bc6h_blocks_as_rgba32f(
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 8, 4),
+            false,
+        );
+
+        for texel in rgba_data.chunks_exact(4) {
+            for &value in &texel[..3] {
+                assert!((value - 2.0).abs() < 0.01, "unexpected value {value}");
+            }
+            assert_eq!(texel[3], 1.0);
+        }
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_bc6h_rgba8_tone_mapping() {
+        let blocks = compress_uniform_bc6h(2.0);
+        let mut rgba_data = vec![0; 8 * 4 * 4];
+
+        let options = BC6HDecodeOptions::default();
+        decompress_bc6h_blocks_as_rgba8(
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 8, 4),
+            &options,
+        );
+        assert!(rgba_data.chunks_exact(4).all(|t| t == [255, 255, 255, 255]));
+
+        let options = BC6HDecodeOptions {
+            tone_mapping: ToneMapping::Reinhard,
+            ..Default::default()
+        };
+        decompress_bc6h_blocks_as_rgba8(
+            &blocks,
+            ImageViewMut::packed(&mut rgba_data, 8, 4),
+            &options,
+        );
+        let expected = linear_to_srgb8(2.0 / 3.0);
+        for texel in rgba_data.chunks_exact(4) {
+            for &value in &texel[..3] {
+                assert!(value.abs_diff(expected) <= 1, "unexpected value {value}");
+            }
+            assert_eq!(texel[3], 255);
+        }
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_compare_compressed() {
+        let (width, height) = (12, 8);
+        let rgba_data: Vec<u8> = (0..width * height * 4).map(|i| (i * 7) as u8).collect();
+        let variant = CompressionVariant::BC1;
+        let blocks = crate::test_util::compress(variant, &rgba_data, width, height);
+
+        let stats = compare_compressed(variant, &blocks, &blocks, width, height);
+        assert!(stats.is_identical());
+        assert_eq!(stats.psnr, f64::INFINITY);
+        assert_eq!(stats.ssim, 1.0);
+        assert_eq!(stats.max_error, 0);
+
+        // Flips the indices of the last block.
+        let mut corrupted = blocks.clone();
+        for byte in &mut corrupted[5 * 8 + 4..6 * 8] {
+            *byte = !*byte;
+        }
+
+        let stats = compare_compressed(variant, &blocks, &corrupted, width, height);
+        assert_eq!(stats.differing_blocks, [(2, 1)]);
+        assert!(stats.psnr.is_finite());
+        assert!(stats.ssim < 1.0);
+        assert!(stats.max_error > 0);
+    }
+}
@@ -78,13 +78,25 @@ pub fn decode_block_bc3(
     compressed_block: &[u8],
     decompressed_block: &mut [u8],
     destination_pitch: usize,
+) {
+    decode_block_bc3_with::<false>(compressed_block, decompressed_block, destination_pitch);
+}
+
+/// Decodes a BC3 block like [`decode_block_bc3()`], with the alpha interpolation of the D3D11
+/// functional spec if `EXACT` is set.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn decode_block_bc3_with<const EXACT: bool>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [u8],
+    destination_pitch: usize,
 ) {
     decode_color_block::<true>(
         &compressed_block[8..],
         decompressed_block,
         destination_pitch,
     );
-    decode_smooth_alpha_block::<4>(
+    decode_smooth_alpha_block::<4, EXACT>(
         compressed_block,
         &mut decompressed_block[3..],
         destination_pitch,
@@ -99,7 +111,24 @@ pub fn decode_block_bc4(
     decompressed_block: &mut [u8],
     destination_pitch: usize,
 ) {
-    decode_smooth_alpha_block::<1>(compressed_block, decompressed_block, destination_pitch);
+    decode_block_bc4_with::<false, 1>(compressed_block, decompressed_block, destination_pitch);
+}
+
+/// Decodes a BC4 block like [`decode_block_bc4()`], with the interpolation of the D3D11
+/// functional spec if `EXACT` is set. The values are written into the first byte of pixels
+/// that are `PIXEL_SIZE` bytes large.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn decode_block_bc4_with<const EXACT: bool, const PIXEL_SIZE: usize>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [u8],
+    destination_pitch: usize,
+) {
+    decode_smooth_alpha_block::<PIXEL_SIZE, EXACT>(
+        compressed_block,
+        decompressed_block,
+        destination_pitch,
+    );
 }
 
 /// Decodes a BC5 block by reading 16 bytes from `compressed_block` and writing the RG8 data into `decompressed_block` with `destination_pitch` many bytes per output row.
@@ -110,8 +139,84 @@ pub fn decode_block_bc5(
     decompressed_block: &mut [u8],
     destination_pitch: usize,
 ) {
-    decode_smooth_alpha_block::<2>(compressed_block, decompressed_block, destination_pitch);
-    decode_smooth_alpha_block::<2>(
+    decode_block_bc5_with::<false, 2>(compressed_block, decompressed_block, destination_pitch);
+}
+
+/// Decodes a BC5 block like [`decode_block_bc5()`], with the interpolation of the D3D11
+/// functional spec if `EXACT` is set. The values are written into the first two bytes of pixels
+/// that are `PIXEL_SIZE` bytes large.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn decode_block_bc5_with<const EXACT: bool, const PIXEL_SIZE: usize>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [u8],
+    destination_pitch: usize,
+) {
+    decode_smooth_alpha_block::<PIXEL_SIZE, EXACT>(
+        compressed_block,
+        decompressed_block,
+        destination_pitch,
+    );
+    decode_smooth_alpha_block::<PIXEL_SIZE, EXACT>(
+        &compressed_block[8..],
+        &mut decompressed_block[1..],
+        destination_pitch,
+    );
+}
+
+/// Decodes a signed BC4 block by reading 8 bytes from `compressed_block` and writing the R8 SNORM data into `decompressed_block` with `destination_pitch` many elements per output row.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub fn decode_block_bc4_signed(
+    compressed_block: &[u8],
+    decompressed_block: &mut [i8],
+    destination_pitch: usize,
+) {
+    decode_block_bc4_signed_with::<false>(compressed_block, decompressed_block, destination_pitch);
+}
+
+/// Decodes a signed BC4 block like [`decode_block_bc4_signed()`], with the interpolation of the
+/// D3D11 functional spec if `EXACT` is set.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn decode_block_bc4_signed_with<const EXACT: bool>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [i8],
+    destination_pitch: usize,
+) {
+    decode_signed_smooth_alpha_block::<1, EXACT>(
+        compressed_block,
+        decompressed_block,
+        destination_pitch,
+    );
+}
+
+/// Decodes a signed BC5 block by reading 16 bytes from `compressed_block` and writing the RG8 SNORM data into `decompressed_block` with `destination_pitch` many elements per output row.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub fn decode_block_bc5_signed(
+    compressed_block: &[u8],
+    decompressed_block: &mut [i8],
+    destination_pitch: usize,
+) {
+    decode_block_bc5_signed_with::<false>(compressed_block, decompressed_block, destination_pitch);
+}
+
+/// Decodes a signed BC5 block like [`decode_block_bc5_signed()`], with the interpolation of the
+/// D3D11 functional spec if `EXACT` is set.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn decode_block_bc5_signed_with<const EXACT: bool>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [i8],
+    destination_pitch: usize,
+) {
+    decode_signed_smooth_alpha_block::<2, EXACT>(
+        compressed_block,
+        decompressed_block,
+        destination_pitch,
+    );
+    decode_signed_smooth_alpha_block::<2, EXACT>(
         &compressed_block[8..],
         &mut decompressed_block[1..],
         destination_pitch,
@@ -126,6 +231,35 @@ fn decode_color_block<const OPAQUE_MODE: bool>(
     decompressed_block: &mut [u8],
     destination_pitch: usize,
 ) {
+    let ref_colors = color_palette::<OPAQUE_MODE>(compressed_block);
+
+    let mut color_indices = u32::from_le_bytes([
+        compressed_block[4],
+        compressed_block[5],
+        compressed_block[6],
+        compressed_block[7],
+    ]);
+
+    // Fill out the decompressed color block
+    for i in 0..4 {
+        for j in 0..4 {
+            let idx = color_indices & 0x03;
+            let offset = j * 4;
+            let color = ref_colors[idx as usize];
+
+            decompressed_block[i * destination_pitch + offset..][..4]
+                .copy_from_slice(&color.to_le_bytes());
+
+            color_indices >>= 2;
+        }
+    }
+}
+
+/// Computes the four RGBA8 colors of a BC1/DXT1 color block, which are stored in little-endian
+/// order.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+pub(crate) fn color_palette<const OPAQUE_MODE: bool>(compressed_block: &[u8]) -> [u32; 4] {
     let mut ref_colors = [0u32; 4];
     let c0 = u16::from_le_bytes([compressed_block[0], compressed_block[1]]);
     let c1 = u16::from_le_bytes([compressed_block[2], compressed_block[3]]);
@@ -174,26 +308,7 @@ fn decode_color_block<const OPAQUE_MODE: bool>(
         ref_colors[3] = 0x00000000;
     }
 
-    let mut color_indices = u32::from_le_bytes([
-        compressed_block[4],
-        compressed_block[5],
-        compressed_block[6],
-        compressed_block[7],
-    ]);
-
-    // Fill out the decompressed color block
-    for i in 0..4 {
-        for j in 0..4 {
-            let idx = color_indices & 0x03;
-            let offset = j * 4;
-            let color = ref_colors[idx as usize];
-
-            decompressed_block[i * destination_pitch + offset..][..4]
-                .copy_from_slice(&color.to_le_bytes());
-
-            color_indices >>= 2;
-        }
-    }
+    ref_colors
 }
 
 /// Decodes a BC2/DXT3 alpha block (sharp transitions)
@@ -214,44 +329,119 @@ fn decode_sharp_alpha_block(
     }
 }
 
+/// Divides the weighted sum of two endpoints by the sum of the weights. The D3D11 functional
+/// spec interpolates in floating point and rounds to the nearest value, while the faster integer
+/// division truncates towards zero.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+fn interpolate_smooth<const EXACT: bool>(sum: i16, divisor: i16) -> i16 {
+    if !EXACT {
+        sum / divisor
+    } else if sum >= 0 {
+        (sum + divisor / 2) / divisor
+    } else {
+        (sum - divisor / 2) / divisor
+    }
+}
+
 /// Decodes a BC2/DXT3 alpha block (smooth transitions)
 #[cfg(feature = "bc15")]
 #[inline(always)]
 #[rustfmt::skip]
-fn decode_smooth_alpha_block<const PIXEL_SIZE: usize>(
+fn decode_smooth_alpha_block<const PIXEL_SIZE: usize, const EXACT: bool>(
     compressed_block: &[u8],
     decompressed_block: &mut [u8],
     destination_pitch: usize,
 ) {
     let block = u64::from_le_bytes(compressed_block[0..8].try_into().unwrap());
+    let alpha = smooth_alpha_palette::<EXACT>(block);
+
+    let mut indices = block >> 16;
+
+    for i in 0..4 {
+        for j in 0..4 {
+            decompressed_block[i * destination_pitch + j * PIXEL_SIZE] = alpha[(indices & 0x07) as usize];
+            indices >>= 3;
+        }
+    }
+}
+
+/// Computes the eight values of a BC3 alpha or a BC4/BC5 channel block, which is read as a
+/// little-endian `u64`.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+#[rustfmt::skip]
+pub(crate) fn smooth_alpha_palette<const EXACT: bool>(block: u64) -> [u8; 8] {
+    let a0 = (block & 0xFF) as i16;
+    let a1 = ((block >> 8) & 0xFF) as i16;
+    let lerp = interpolate_smooth::<EXACT>;
 
     let mut alpha = [0u8; 8];
-    alpha[0] = (block & 0xFF) as u8;
-    alpha[1] = ((block >> 8) & 0xFF) as u8;
+    alpha[0] = a0 as u8;
+    alpha[1] = a1 as u8;
 
-    if alpha[0] > alpha[1] {
+    if a0 > a1 {
         // 6 interpolated alpha values
-        alpha[2] = ((6 * alpha[0] as u16 +     alpha[1] as u16) / 7) as u8;   // 6/7*alpha_0 + 1/7*alpha_1
-        alpha[3] = ((5 * alpha[0] as u16 + 2 * alpha[1] as u16) / 7) as u8;   // 5/7*alpha_0 + 2/7*alpha_1
-        alpha[4] = ((4 * alpha[0] as u16 + 3 * alpha[1] as u16) / 7) as u8;   // 4/7*alpha_0 + 3/7*alpha_1
-        alpha[5] = ((3 * alpha[0] as u16 + 4 * alpha[1] as u16) / 7) as u8;   // 3/7*alpha_0 + 4/7*alpha_1
-        alpha[6] = ((2 * alpha[0] as u16 + 5 * alpha[1] as u16) / 7) as u8;   // 2/7*alpha_0 + 5/7*alpha_1
-        alpha[7] = ((    alpha[0] as u16 + 6 * alpha[1] as u16) / 7) as u8;   // 1/7*alpha_0 + 6/7*alpha_1
+        alpha[2] = lerp(6 * a0 +     a1, 7) as u8;   // 6/7*alpha_0 + 1/7*alpha_1
+        alpha[3] = lerp(5 * a0 + 2 * a1, 7) as u8;   // 5/7*alpha_0 + 2/7*alpha_1
+        alpha[4] = lerp(4 * a0 + 3 * a1, 7) as u8;   // 4/7*alpha_0 + 3/7*alpha_1
+        alpha[5] = lerp(3 * a0 + 4 * a1, 7) as u8;   // 3/7*alpha_0 + 4/7*alpha_1
+        alpha[6] = lerp(2 * a0 + 5 * a1, 7) as u8;   // 2/7*alpha_0 + 5/7*alpha_1
+        alpha[7] = lerp(    a0 + 6 * a1, 7) as u8;   // 1/7*alpha_0 + 6/7*alpha_1
     } else {
         // 4 interpolated alpha values
-        alpha[2] = ((4 * alpha[0] as u16 +     alpha[1] as u16) / 5) as u8;   // 4/5*alpha_0 + 1/5*alpha_1
-        alpha[3] = ((3 * alpha[0] as u16 + 2 * alpha[1] as u16) / 5) as u8;   // 3/5*alpha_0 + 2/5*alpha_1
-        alpha[4] = ((2 * alpha[0] as u16 + 3 * alpha[1] as u16) / 5) as u8;   // 2/5*alpha_0 + 3/5*alpha_1
-        alpha[5] = ((    alpha[0] as u16 + 4 * alpha[1] as u16) / 5) as u8;   // 1/5*alpha_0 + 4/5*alpha_1
+        alpha[2] = lerp(4 * a0 +     a1, 5) as u8;   // 4/5*alpha_0 + 1/5*alpha_1
+        alpha[3] = lerp(3 * a0 + 2 * a1, 5) as u8;   // 3/5*alpha_0 + 2/5*alpha_1
+        alpha[4] = lerp(2 * a0 + 3 * a1, 5) as u8;   // 2/5*alpha_0 + 3/5*alpha_1
+        alpha[5] = lerp(    a0 + 4 * a1, 5) as u8;   // 1/5*alpha_0 + 4/5*alpha_1
         alpha[6] = 0x00;
         alpha[7] = 0xFF;
     }
 
+    alpha
+}
+
+/// Decodes a signed BC4/BC5 channel block (smooth transitions)
+#[cfg(feature = "bc15")]
+#[inline(always)]
+#[rustfmt::skip]
+fn decode_signed_smooth_alpha_block<const PIXEL_SIZE: usize, const EXACT: bool>(
+    compressed_block: &[u8],
+    decompressed_block: &mut [i8],
+    destination_pitch: usize,
+) {
+    let block = u64::from_le_bytes(compressed_block[0..8].try_into().unwrap());
+
+    // -128 and -127 both represent -1.0
+    let mut alpha = [0i16; 8];
+    alpha[0] = ((block & 0xFF) as u8 as i8).max(-127) as i16;
+    alpha[1] = (((block >> 8) & 0xFF) as u8 as i8).max(-127) as i16;
+
+    let lerp = interpolate_smooth::<EXACT>;
+
+    if alpha[0] > alpha[1] {
+        // 6 interpolated values
+        alpha[2] = lerp(6 * alpha[0] +     alpha[1], 7);   // 6/7*alpha_0 + 1/7*alpha_1
+        alpha[3] = lerp(5 * alpha[0] + 2 * alpha[1], 7);   // 5/7*alpha_0 + 2/7*alpha_1
+        alpha[4] = lerp(4 * alpha[0] + 3 * alpha[1], 7);   // 4/7*alpha_0 + 3/7*alpha_1
+        alpha[5] = lerp(3 * alpha[0] + 4 * alpha[1], 7);   // 3/7*alpha_0 + 4/7*alpha_1
+        alpha[6] = lerp(2 * alpha[0] + 5 * alpha[1], 7);   // 2/7*alpha_0 + 5/7*alpha_1
+        alpha[7] = lerp(    alpha[0] + 6 * alpha[1], 7);   // 1/7*alpha_0 + 6/7*alpha_1
+    } else {
+        // 4 interpolated values
+        alpha[2] = lerp(4 * alpha[0] +     alpha[1], 5);   // 4/5*alpha_0 + 1/5*alpha_1
+        alpha[3] = lerp(3 * alpha[0] + 2 * alpha[1], 5);   // 3/5*alpha_0 + 2/5*alpha_1
+        alpha[4] = lerp(2 * alpha[0] + 3 * alpha[1], 5);   // 2/5*alpha_0 + 3/5*alpha_1
+        alpha[5] = lerp(    alpha[0] + 4 * alpha[1], 5);   // 1/5*alpha_0 + 4/5*alpha_1
+        alpha[6] = -127;
+        alpha[7] = 127;
+    }
+
     let mut indices = block >> 16;
 
     for i in 0..4 {
         for j in 0..4 {
-            decompressed_block[i * destination_pitch + j * PIXEL_SIZE] = alpha[(indices & 0x07) as usize];
+            decompressed_block[i * destination_pitch + j * PIXEL_SIZE] = alpha[(indices & 0x07) as usize] as i8;
             indices >>= 3;
         }
     }
@@ -1775,6 +1965,7 @@ mod tests {
         assert_eq!(&decoded[..], &expected_output[..], "BC6H block mismatch");
     }
 
+    #[cfg(feature = "bc6h")]
     #[test]
     #[rustfmt::skip]
     fn test_bc6h_block_0_float() {
@@ -0,0 +1,179 @@
+//! Comparison of two compressed buffers of the same variant.
+
+use super::decompress_blocks_as_rgba8;
+use crate::{CompressionVariant, ImageViewMut};
+
+/// Size of the square windows the SSIM is computed over.
+const SSIM_WINDOW: u32 = 8;
+
+/// The result of [`compare_compressed()`].
+///
+/// All metrics are computed over the decoded RGBA8 data and only include the channels the
+/// variant stores (see [`CompressionVariant::channel_count()`]).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffStats {
+    /// Peak signal-to-noise ratio in dB. Is infinite if both buffers decode to the same image.
+    pub psnr: f64,
+    /// Mean structural similarity over windows of 8x8 pixels. Is `1.0` if both buffers decode to
+    /// the same image.
+    pub ssim: f64,
+    /// Largest absolute difference of any channel of any pixel.
+    pub max_error: u8,
+    /// The `(x, y)` coordinates of all blocks whose bytes differ, in row-major order. Blocks can
+    /// differ even if they decode to the same pixels.
+    pub differing_blocks: Vec<(u32, u32)>,
+}
+
+impl DiffStats {
+    /// Returns `true` if both buffers contain the exact same blocks.
+    pub fn is_identical(&self) -> bool {
+        self.differing_blocks.is_empty()
+    }
+}
+
+/// Decodes two compressed buffers of the same variant and size and compares them.
+///
+/// Useful to validate encoder changes against a known good output or to detect corruption of
+/// stored blocks. `a` is treated as the reference, but all metrics are symmetric.
+///
+/// # Panics
+/// - Any of the buffers has not the expected size (`variant.blocks_byte_size()`)
+///
+/// # Example
+/// ```
+/// use block_compression::{decode::compare_compressed, CompressionVariant};
+///
+/// let variant = CompressionVariant::BC4;
+/// let a = vec![0u8; variant.blocks_byte_size(16, 16)];
+/// let mut b = a.clone();
+/// // Sets the first endpoint of the block at (2, 1) to white.
+/// b[(4 + 2) * 8] = 255;
+///
+/// let stats = compare_compressed(variant, &a, &b, 16, 16);
+/// assert_eq!(stats.differing_blocks, [(2, 1)]);
+/// assert_eq!(stats.max_error, 255);
+/// assert!(stats.psnr.is_finite());
+/// ```
+pub fn compare_compressed(
+    variant: CompressionVariant,
+    a: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+) -> DiffStats {
+    let expected_size = variant.blocks_byte_size(width, height);
+    assert_eq!(a.len(), expected_size, "`a` has not the expected size");
+    assert_eq!(b.len(), expected_size, "`b` has not the expected size");
+
+    let blocks_x = width.div_ceil(4);
+    let block_size = variant.block_byte_size() as usize;
+    let differing_blocks = a
+        .chunks_exact(block_size)
+        .zip(b.chunks_exact(block_size))
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(index, _)| (index as u32 % blocks_x, index as u32 / blocks_x))
+        .collect();
+
+    let decode = |blocks: &[u8]| {
+        let mut rgba = vec![0u8; width as usize * height as usize * 4];
+        decompress_blocks_as_rgba8(
+            variant,
+            blocks,
+            ImageViewMut::packed(&mut rgba, width, height),
+        );
+        rgba
+    };
+    DiffStats {
+        differing_blocks,
+        ..compare_rgba8(variant, &decode(a), &decode(b), width, height)
+    }
+}
+
+/// Compares two packed RGBA8 images over the channels the variant stores. The returned stats
+/// don't list any differing blocks.
+pub(crate) fn compare_rgba8(
+    variant: CompressionVariant,
+    a: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+) -> DiffStats {
+    let channels = variant.channel_count() as usize;
+
+    let mut squared_error = 0.0;
+    let mut max_error = 0;
+    for (a, b) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+        for channel in 0..channels {
+            let diff = a[channel].abs_diff(b[channel]);
+            squared_error += (diff as f64) * (diff as f64);
+            max_error = max_error.max(diff);
+        }
+    }
+
+    let samples = (a.len() / 4 * channels) as f64;
+    let psnr = if squared_error == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 * samples / squared_error).log10()
+    };
+
+    DiffStats {
+        psnr,
+        ssim: mean_ssim(a, b, width, height, channels),
+        max_error,
+        differing_blocks: Vec::new(),
+    }
+}
+
+/// Averages the SSIM of all channels over non-overlapping windows. Windows at the right and
+/// bottom edge are smaller if the size is not a multiple of the window size.
+fn mean_ssim(a: &[u8], b: &[u8], width: u32, height: u32, channels: usize) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let mut total = 0.0;
+    let mut count = 0;
+
+    for window_y in (0..height).step_by(SSIM_WINDOW as usize) {
+        for window_x in (0..width).step_by(SSIM_WINDOW as usize) {
+            let window_width = SSIM_WINDOW.min(width - window_x);
+            let window_height = SSIM_WINDOW.min(height - window_y);
+            let pixels = (window_width * window_height) as f64;
+
+            for channel in 0..channels {
+                let mut sum = [0.0; 2];
+                let mut sum_sq = [0.0; 2];
+                let mut sum_ab = 0.0;
+
+                for y in window_y..window_y + window_height {
+                    for x in window_x..window_x + window_width {
+                        let index = (y * width + x) as usize * 4 + channel;
+                        let (a, b) = (a[index] as f64, b[index] as f64);
+                        sum[0] += a;
+                        sum[1] += b;
+                        sum_sq[0] += a * a;
+                        sum_sq[1] += b * b;
+                        sum_ab += a * b;
+                    }
+                }
+
+                let mean_a = sum[0] / pixels;
+                let mean_b = sum[1] / pixels;
+                let var_a = sum_sq[0] / pixels - mean_a * mean_a;
+                let var_b = sum_sq[1] / pixels - mean_b * mean_b;
+                let covar = sum_ab / pixels - mean_a * mean_b;
+
+                total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covar + C2))
+                    / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+                count += 1;
+            }
+        }
+    }
+
+    if count == 0 {
+        1.0
+    } else {
+        total / count as f64
+    }
+}
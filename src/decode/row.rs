@@ -0,0 +1,304 @@
+//! Decoding of whole rows of blocks with a single call.
+//!
+//! The BC1 to BC5 row functions split the 4 output rows once and decode every block straight
+//! into fixed-size arrays of these rows, which avoids the pitch arithmetic and the bounds
+//! checks of every texel that the block functions do. The `decode_rows` benchmark compares
+//! them with the block functions on many small textures. BC6H and BC7 call their block decoder
+//! for every block, since decoding their modes takes far longer than the call itself.
+
+#[cfg(feature = "bc6h")]
+use super::block::decode_block_bc6h;
+#[cfg(feature = "bc7")]
+use super::block::decode_block_bc7;
+#[cfg(feature = "bc15")]
+use super::block::{color_palette, smooth_alpha_palette};
+
+/// Checks the sizes of a row once and returns the number of elements of a decoded row.
+fn check_row<T, const BLOCK_SIZE: usize, const BLOCK_WIDTH: usize>(
+    compressed_row: &[u8],
+    out_rows: &[T],
+    pitch: usize,
+) -> usize {
+    assert_eq!(
+        compressed_row.len() % BLOCK_SIZE,
+        0,
+        "the compressed row doesn't hold whole blocks"
+    );
+
+    let row_len = compressed_row.len() / BLOCK_SIZE * BLOCK_WIDTH;
+    if row_len > 0 {
+        assert!(pitch >= row_len, "the pitch is smaller than a decoded row");
+        assert!(
+            out_rows.len() >= 3 * pitch + row_len,
+            "out_rows is too small to hold 4 decoded rows"
+        );
+    }
+
+    row_len
+}
+
+/// Decodes the blocks of a row with `decode_block`, which writes `BLOCK_WIDTH` elements of
+/// every row of a block with the given pitch.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[inline(always)]
+fn decode_row<T, const BLOCK_SIZE: usize, const BLOCK_WIDTH: usize>(
+    compressed_row: &[u8],
+    out_rows: &mut [T],
+    pitch: usize,
+    decode_block: impl Fn(&[u8], &mut [T], usize),
+) {
+    let row_len = check_row::<T, BLOCK_SIZE, BLOCK_WIDTH>(compressed_row, out_rows, pitch);
+    if row_len == 0 {
+        return;
+    }
+
+    let out_rows = &mut out_rows[..3 * pitch + row_len];
+    for (index, block) in compressed_row.chunks_exact(BLOCK_SIZE).enumerate() {
+        decode_block(block, &mut out_rows[index * BLOCK_WIDTH..], pitch);
+    }
+}
+
+/// Decodes the blocks of a row with `decode_block`, which receives a block and the
+/// `BLOCK_WIDTH` bytes it covers in each of the 4 rows.
+///
+/// The rows are split once, so the loop walks the blocks and the rows in lockstep with arrays
+/// of a fixed size and the block decoders write them without any bounds checks.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+fn decode_split_rows<const BLOCK_SIZE: usize, const BLOCK_WIDTH: usize>(
+    compressed_row: &[u8],
+    out_rows: &mut [u8],
+    pitch: usize,
+    decode_block: impl Fn(&[u8; BLOCK_SIZE], [&mut [u8; BLOCK_WIDTH]; 4]),
+) {
+    let row_len = check_row::<u8, BLOCK_SIZE, BLOCK_WIDTH>(compressed_row, out_rows, pitch);
+    if row_len == 0 {
+        return;
+    }
+
+    let (row0, rest) = out_rows.split_at_mut(pitch);
+    let (row1, rest) = rest.split_at_mut(pitch);
+    let (row2, row3) = rest.split_at_mut(pitch);
+
+    let blocks = compressed_row
+        .chunks_exact(BLOCK_SIZE)
+        .zip(row0[..row_len].chunks_exact_mut(BLOCK_WIDTH))
+        .zip(row1[..row_len].chunks_exact_mut(BLOCK_WIDTH))
+        .zip(row2[..row_len].chunks_exact_mut(BLOCK_WIDTH))
+        .zip(row3[..row_len].chunks_exact_mut(BLOCK_WIDTH));
+    for ((((block, out0), out1), out2), out3) in blocks {
+        decode_block(
+            block.try_into().unwrap(),
+            [
+                out0.try_into().unwrap(),
+                out1.try_into().unwrap(),
+                out2.try_into().unwrap(),
+                out3.try_into().unwrap(),
+            ],
+        );
+    }
+}
+
+/// Writes the colors of a BC1 color block into the 4 rows of RGBA8 texels.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+fn write_color_rows<const OPAQUE_MODE: bool>(color_block: &[u8], rows: &mut [&mut [u8; 16]; 4]) {
+    let colors = color_palette::<OPAQUE_MODE>(color_block).map(u32::to_le_bytes);
+
+    for (row, &indices) in rows.iter_mut().zip(&color_block[4..8]) {
+        for (texel, shift) in row.chunks_exact_mut(4).zip([0, 2, 4, 6]) {
+            texel.copy_from_slice(&colors[(indices >> shift) as usize & 0x03]);
+        }
+    }
+}
+
+/// Writes the values of a BC3 alpha or a BC4/BC5 channel block into every `PIXEL_SIZE`th byte
+/// of the 4 rows, starting at `channel`.
+#[cfg(feature = "bc15")]
+#[inline(always)]
+fn write_smooth_rows<const PIXEL_SIZE: usize, const WIDTH: usize>(
+    block: &[u8],
+    channel: usize,
+    rows: &mut [&mut [u8; WIDTH]; 4],
+) {
+    let block = u64::from_le_bytes(block[..8].try_into().unwrap());
+    let values = smooth_alpha_palette::<false>(block);
+
+    let mut indices = block >> 16;
+    for row in rows.iter_mut() {
+        for texel in row.chunks_exact_mut(PIXEL_SIZE) {
+            texel[channel] = values[(indices & 0x07) as usize];
+            indices >>= 3;
+        }
+    }
+}
+
+/// Decodes a row of BC1 blocks into RGBA8 data with a single call.
+///
+/// Checks the sizes of the whole row once and then writes every block into the 4 output rows
+/// without further bounds checks. The decoded texels are identical to the ones of
+/// [`decode_block_bc1()`](crate::decode::decode_block_bc1).
+///
+/// # Arguments
+/// * `compressed_row` - The blocks of a single row of blocks
+/// * `out_rows` - Receives the 4 rows of texels of the blocks, with 16 bytes per block
+/// * `pitch` - The distance between the rows in `out_rows` in bytes
+///
+/// # Panics
+/// - If `compressed_row` doesn't hold whole blocks
+/// - If `pitch` is smaller than the size of a decoded row
+/// - If `out_rows` is too small to hold the 4 decoded rows
+///
+/// # Example
+/// ```
+/// use block_compression::decode::decode_row_bc1;
+///
+/// // A row of 8 blocks, which decodes to 32x4 texels.
+/// let compressed_row = [0u8; 8 * 8];
+/// let mut out_rows = vec![0u8; 32 * 4 * 4];
+/// decode_row_bc1(&compressed_row, &mut out_rows, 32 * 4);
+/// ```
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decode_row_bc1(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_split_rows::<8, 16>(compressed_row, out_rows, pitch, |block, mut rows| {
+        write_color_rows::<false>(block, &mut rows);
+    });
+}
+
+/// Decodes a row of BC2 blocks into RGBA8 data, like [`decode_row_bc1()`].
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decode_row_bc2(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_split_rows::<16, 16>(compressed_row, out_rows, pitch, |block, mut rows| {
+        write_color_rows::<true>(&block[8..], &mut rows);
+
+        for (row, alpha) in rows.iter_mut().zip(block[..8].chunks_exact(2)) {
+            let alpha = u16::from_le_bytes([alpha[0], alpha[1]]);
+            for (texel, shift) in row.chunks_exact_mut(4).zip([0, 4, 8, 12]) {
+                texel[3] = ((alpha >> shift) & 0x0F) as u8 * 17;
+            }
+        }
+    });
+}
+
+/// Decodes a row of BC3 blocks into RGBA8 data, like [`decode_row_bc1()`].
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decode_row_bc3(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_split_rows::<16, 16>(compressed_row, out_rows, pitch, |block, mut rows| {
+        write_color_rows::<true>(&block[8..], &mut rows);
+        write_smooth_rows::<4, 16>(block, 3, &mut rows);
+    });
+}
+
+/// Decodes a row of BC4 blocks into R8 data, like [`decode_row_bc1()`]. Every block writes 4
+/// bytes of each row.
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decode_row_bc4(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_split_rows::<8, 4>(compressed_row, out_rows, pitch, |block, mut rows| {
+        write_smooth_rows::<1, 4>(block, 0, &mut rows);
+    });
+}
+
+/// Decodes a row of BC5 blocks into RG8 data, like [`decode_row_bc1()`]. Every block writes 8
+/// bytes of each row.
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn decode_row_bc5(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_split_rows::<16, 8>(compressed_row, out_rows, pitch, |block, mut rows| {
+        write_smooth_rows::<2, 8>(block, 0, &mut rows);
+        write_smooth_rows::<2, 8>(&block[8..], 1, &mut rows);
+    });
+}
+
+/// Decodes a row of BC6H blocks into RGB16F data, like [`decode_row_bc1()`]. Every block writes
+/// 12 half floats of each row and `pitch` is counted in half floats.
+///
+/// Calls [`decode_block_bc6h()`](crate::decode::decode_block_bc6h) for every block after
+/// checking the sizes once.
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn decode_row_bc6h(
+    compressed_row: &[u8],
+    out_rows: &mut [half::f16],
+    pitch: usize,
+    is_signed: bool,
+) {
+    decode_row::<half::f16, 16, 12>(compressed_row, out_rows, pitch, |block, out, pitch| {
+        decode_block_bc6h(block, out, pitch, is_signed)
+    });
+}
+
+/// Decodes a row of BC7 blocks into RGBA8 data, like [`decode_row_bc1()`].
+///
+/// Calls [`decode_block_bc7()`](crate::decode::decode_block_bc7) for every block after checking
+/// the sizes once.
+#[cfg(feature = "bc7")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
+pub fn decode_row_bc7(compressed_row: &[u8], out_rows: &mut [u8], pitch: usize) {
+    decode_row::<u8, 16, 16>(compressed_row, out_rows, pitch, decode_block_bc7);
+}
+
+#[cfg(all(test, feature = "bc15", feature = "bc7"))]
+mod tests {
+    use super::*;
+    use crate::decode::{
+        decode_block_bc1, decode_block_bc2, decode_block_bc3, decode_block_bc4, decode_block_bc5,
+    };
+
+    type DecodeFn = fn(&[u8], &mut [u8], usize);
+
+    /// Decodes the row block by block, with 3 bytes of padding after every row.
+    fn decode_blocks(
+        compressed_row: &[u8],
+        block_size: usize,
+        block_width: usize,
+        decode_block: DecodeFn,
+    ) -> (Vec<u8>, usize) {
+        let block_count = compressed_row.len() / block_size;
+        let pitch = block_count * block_width + 3;
+        let mut out_rows = vec![0xAA; 4 * pitch];
+
+        for (index, block) in compressed_row.chunks_exact(block_size).enumerate() {
+            decode_block(block, &mut out_rows[index * block_width..], pitch);
+        }
+
+        (out_rows, pitch)
+    }
+
+    #[test]
+    fn test_rows_match_blocks() {
+        let compressed_row: Vec<u8> = (0..7 * 16u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+
+        let rows: [(DecodeFn, DecodeFn, usize, usize); 6] = [
+            (decode_row_bc1, decode_block_bc1, 8, 16),
+            (decode_row_bc2, decode_block_bc2, 16, 16),
+            (decode_row_bc3, decode_block_bc3, 16, 16),
+            (decode_row_bc4, decode_block_bc4, 8, 4),
+            (decode_row_bc5, decode_block_bc5, 16, 8),
+            (decode_row_bc7, decode_block_bc7, 16, 16),
+        ];
+
+        for (index, (decode_row, decode_block, block_size, block_width)) in
+            rows.into_iter().enumerate()
+        {
+            let (expected, pitch) =
+                decode_blocks(&compressed_row, block_size, block_width, decode_block);
+
+            // The padding after every row keeps its value.
+            let mut out_rows = vec![0xAA; 4 * pitch];
+            decode_row(&compressed_row, &mut out_rows, pitch);
+            assert_eq!(out_rows, expected, "row function {index}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "too small")]
+    fn test_row_needs_four_rows() {
+        decode_row_bc1(&[0; 16], &mut [0; 3 * 32 + 31], 32);
+    }
+}
@@ -0,0 +1,253 @@
+//! Diagnostics of the compute kernels of the [`GpuBlockCompressor`].
+//!
+//! The speed of the GPU compressor differs a lot between GPUs. Besides the raw compute power, it
+//! mostly depends on how many invocations of a kernel the GPU can keep in flight, which is limited
+//! by the registers and the shared memory every invocation needs. The reports of this module show
+//! how a texture is dispatched and, with the `reflection` feature, estimate the resources the
+//! kernel of a variant needs, so performance differences can be matched with the limits of the
+//! GPUs.
+//!
+//! # Example
+//! ```
+//! use block_compression::{diagnostics::dispatch_report, BC7Settings, CompressionVariant};
+//!
+//! let report = dispatch_report(CompressionVariant::BC7(BC7Settings::alpha_basic()), 1000, 600);
+//!
+//! assert_eq!(report.workgroup_size, [8, 8, 1]);
+//! assert_eq!(report.workgroup_count, [32, 19, 1]);
+//! assert_eq!(report.block_count, 250 * 150);
+//! assert!(report.utilization() > 0.95);
+//! ```
+//!
+//! [`GpuBlockCompressor`]: crate::GpuBlockCompressor
+
+use crate::CompressionVariant;
+
+/// The workgroup size of all compression kernels. Every invocation compresses a single block.
+pub const WORKGROUP_SIZE: [u32; 3] = [8, 8, 1];
+
+/// How a compression task is dispatched, returned by [`dispatch_report()`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DispatchReport {
+    /// The block compression variant of the task.
+    pub variant: CompressionVariant,
+    /// The entry point of the kernel in its shader.
+    pub entry_point: &'static str,
+    /// The number of invocations of a workgroup in every dimension.
+    pub workgroup_size: [u32; 3],
+    /// The number of workgroups that are dispatched in every dimension.
+    pub workgroup_count: [u32; 3],
+    /// The number of blocks of the texture, which is the number of invocations that do work.
+    pub block_count: u32,
+    /// The resources an invocation of the kernel needs, or `None` without the `reflection`
+    /// feature.
+    pub kernel: Option<KernelStats>,
+}
+
+impl DispatchReport {
+    /// Returns the number of dispatched invocations, including the ones outside of the texture
+    /// that return right away.
+    pub const fn invocation_count(&self) -> u64 {
+        let [x, y, z] = self.workgroup_count;
+        let [size_x, size_y, size_z] = self.workgroup_size;
+        (x * size_x) as u64 * (y * size_y) as u64 * (z * size_z) as u64
+    }
+
+    /// Returns the fraction of dispatched invocations that compress a block. Textures whose
+    /// block counts are not a multiple of the workgroup size leave parts of the edge workgroups
+    /// idle.
+    pub fn utilization(&self) -> f64 {
+        match self.invocation_count() {
+            0 => 0.0,
+            invocations => self.block_count as f64 / invocations as f64,
+        }
+    }
+}
+
+/// The resources an invocation of a kernel needs, estimated from the reflection of its shader.
+///
+/// The estimates are taken from the WGSL source, so they are upper bounds of what a driver
+/// needs. Compilers keep most variables in registers, reuse the registers of variables that are
+/// no longer needed and spill large arrays into memory.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct KernelStats {
+    /// The size in bytes of all function variables along the deepest call chain of the kernel.
+    pub private_memory_bytes: u32,
+    /// The number of 32-bit registers that hold the function variables, if none are spilled.
+    pub estimated_registers: u32,
+    /// The size in bytes of the workgroup memory the kernel uses, which is shared by all
+    /// invocations of a workgroup.
+    pub workgroup_memory_bytes: u32,
+}
+
+/// Reports how a texture of the given size is dispatched for the variant.
+///
+/// The dispatch is the same as the one of [`GpuBlockCompressor::add_compression_task()`] for a
+/// texture of `width` and `height` pixels, unless the compressor splits it into several
+/// dispatches because of [`GpuBlockCompressor::set_max_blocks_per_dispatch()`]. With the
+/// `reflection` feature, the shader of the variant is parsed to estimate the resources of the
+/// kernel, which takes a few milliseconds.
+///
+/// [`GpuBlockCompressor::add_compression_task()`]: crate::GpuBlockCompressor::add_compression_task
+/// [`GpuBlockCompressor::set_max_blocks_per_dispatch()`]: crate::GpuBlockCompressor::set_max_blocks_per_dispatch
+pub fn dispatch_report(variant: CompressionVariant, width: u32, height: u32) -> DispatchReport {
+    let [workgroup_width, workgroup_height] = workgroup_count(width, height);
+
+    DispatchReport {
+        variant,
+        entry_point: variant.entry_point(),
+        workgroup_size: WORKGROUP_SIZE,
+        workgroup_count: [workgroup_width, workgroup_height, 1],
+        block_count: width.div_ceil(4) * height.div_ceil(4),
+        #[cfg(feature = "reflection")]
+        kernel: reflection::kernel_stats(variant),
+        #[cfg(not(feature = "reflection"))]
+        kernel: None,
+    }
+}
+
+/// Returns the number of workgroups in X and Y to compress a texture of the given size.
+pub(crate) const fn workgroup_count(width: u32, height: u32) -> [u32; 2] {
+    let block_width = width.div_ceil(4);
+    let block_height = height.div_ceil(4);
+
+    [
+        block_width.div_ceil(WORKGROUP_SIZE[0]),
+        block_height.div_ceil(WORKGROUP_SIZE[1]),
+    ]
+}
+
+#[cfg(feature = "reflection")]
+mod reflection {
+    use std::collections::HashMap;
+
+    use naga::{
+        front::wgsl, proc::Layouter, AddressSpace, Block, Function, Handle, Module, Statement,
+    };
+
+    use super::KernelStats;
+    use crate::CompressionVariant;
+
+    pub(super) fn kernel_stats(variant: CompressionVariant) -> Option<KernelStats> {
+        let source = match variant {
+            #[cfg(feature = "bc15")]
+            CompressionVariant::BC1
+            | CompressionVariant::BC2
+            | CompressionVariant::BC3
+            | CompressionVariant::BC4
+            | CompressionVariant::BC5 => include_str!("shader/bc1_to_5.wgsl"),
+            #[cfg(feature = "bc6h")]
+            CompressionVariant::BC6H(..) => include_str!("shader/bc6h.wgsl"),
+            #[cfg(feature = "bc7")]
+            CompressionVariant::BC7(..) => include_str!("shader/bc7.wgsl"),
+            CompressionVariant::RGBA8 => include_str!("shader/rgba8.wgsl"),
+        };
+
+        let module = wgsl::parse_str(source).ok()?;
+        let mut layouter = Layouter::default();
+        layouter.update(module.to_ctx()).ok()?;
+
+        let entry_point = module
+            .entry_points
+            .iter()
+            .find(|entry_point| entry_point.name == variant.entry_point())?;
+
+        let mut stack_sizes = HashMap::new();
+        let private_memory_bytes =
+            stack_size(&module, &layouter, &entry_point.function, &mut stack_sizes);
+
+        // None of the kernels uses workgroup memory at the moment, but it is the limit that
+        // matters most for the occupancy if a kernel does.
+        let workgroup_memory_bytes = module
+            .global_variables
+            .iter()
+            .filter(|(_, global)| global.space == AddressSpace::WorkGroup)
+            .map(|(_, global)| layouter[global.ty].size)
+            .sum();
+
+        Some(KernelStats {
+            private_memory_bytes,
+            estimated_registers: private_memory_bytes.div_ceil(4),
+            workgroup_memory_bytes,
+        })
+    }
+
+    /// Returns the size of the variables of the function and of the largest chain of functions it
+    /// calls.
+    fn stack_size(
+        module: &Module,
+        layouter: &Layouter,
+        function: &Function,
+        stack_sizes: &mut HashMap<Handle<Function>, u32>,
+    ) -> u32 {
+        let locals: u32 = function
+            .local_variables
+            .iter()
+            .map(|(_, local)| layouter[local.ty].size)
+            .sum();
+
+        let mut callees = Vec::new();
+        collect_callees(&function.body, &mut callees);
+
+        let deepest_callee = callees
+            .into_iter()
+            .map(|callee| match stack_sizes.get(&callee) {
+                Some(&size) => size,
+                None => {
+                    let size = stack_size(module, layouter, &module.functions[callee], stack_sizes);
+                    stack_sizes.insert(callee, size);
+                    size
+                }
+            })
+            .max()
+            .unwrap_or(0);
+
+        locals + deepest_callee
+    }
+
+    fn collect_callees(block: &Block, callees: &mut Vec<Handle<Function>>) {
+        for statement in block.iter() {
+            match statement {
+                Statement::Block(block) => collect_callees(block, callees),
+                Statement::If { accept, reject, .. } => {
+                    collect_callees(accept, callees);
+                    collect_callees(reject, callees);
+                }
+                Statement::Switch { cases, .. } => {
+                    for case in cases {
+                        collect_callees(&case.body, callees);
+                    }
+                }
+                Statement::Loop {
+                    body, continuing, ..
+                } => {
+                    collect_callees(body, callees);
+                    collect_callees(continuing, callees);
+                }
+                Statement::Call { function, .. } => callees.push(*function),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "reflection", feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_stats() {
+        let bc1 = dispatch_report(CompressionVariant::BC1, 64, 64);
+        let bc4 = dispatch_report(CompressionVariant::BC4, 64, 64);
+        let (bc1, bc4) = (bc1.kernel.unwrap(), bc4.kernel.unwrap());
+
+        // Both kernels load the whole block of 64 floats.
+        assert!(bc1.private_memory_bytes >= 256);
+        assert!(bc4.private_memory_bytes >= 256);
+        assert_eq!(
+            bc1.estimated_registers,
+            bc1.private_memory_bytes.div_ceil(4)
+        );
+        assert_eq!(bc1.workgroup_memory_bytes, 0);
+    }
+}
@@ -1,26 +1,110 @@
 //! CPU based encoding.
 
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+use std::ops::Range;
+
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod batch;
 #[cfg(feature = "bc15")]
 mod bc1_to_5;
 #[cfg(feature = "bc6h")]
 mod bc6h;
 #[cfg(feature = "bc7")]
 mod bc7;
+#[cfg(all(feature = "unstable", any(feature = "bc6h", feature = "bc7")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "unstable", any(feature = "bc6h", feature = "bc7"))))
+)]
+pub mod bitstream;
+#[cfg(all(not(feature = "unstable"), any(feature = "bc6h", feature = "bc7")))]
+mod bitstream;
 #[cfg(any(feature = "bc6h", feature = "bc7"))]
 mod common;
+#[cfg(all(
+    feature = "image",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod encoded_image;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod presets;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod rows;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod solid_color;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod target_quality;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod virtual_texture;
 
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::batch::{compress_batch, compress_batch_with_callback, BatchItem, BatchOrder};
 #[cfg(feature = "bc15")]
-use self::bc1_to_5::BlockCompressorBC15;
+use self::bc1_to_5::{BlockCompressorBC15, ColorBlockFn};
 #[cfg(feature = "bc6h")]
 use self::bc6h::BlockCompressorBC6H;
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub use self::bc6h::BC6H_NO_HINT;
 #[cfg(feature = "bc7")]
 use self::bc7::BlockCompressorBC7;
-#[cfg(feature = "bc6h")]
-use crate::BC6HSettings;
+#[cfg(all(
+    feature = "image",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "image",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use self::encoded_image::{compress_encoded_image, CompressedImage};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::presets::{evaluate_presets, PresetReport};
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+pub(crate) use self::presets::{packed_rgba8, preset_report, preset_variants};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::rows::compress_rgba8_rows;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::target_quality::{compress_rgba8_target_quality, QualityTarget};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use self::virtual_texture::{
+    compress_pages, PageAtlas, PageAtlasSettings, VirtualPage, PAGE_SIZE,
+};
+#[cfg(feature = "bc15")]
+use crate::decode::{
+    decode_block_bc1, decode_block_bc2, decode_block_bc3, decode_block_bc4, decode_block_bc5,
+};
 #[cfg(feature = "bc7")]
 use crate::BC7Settings;
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-use crate::CompressionVariant;
+use crate::{mip_dimension, CompressionVariant, EdgeFill, ImageView};
+#[cfg(feature = "bc6h")]
+use crate::{BC6HSettings, HdrRemap};
 
 /// Compresses raw RGBA8 data into using a texture block compression format.
 ///
@@ -29,7 +113,8 @@ use crate::CompressionVariant;
 ///
 /// # Data Layout Requirements
 /// The input data must be in RGBA8 format (8 bits per channel, 32 bits per pixel). The data is
-/// expected to be in row-major order, with optional stride for padding between rows.
+/// described by an [`ImageView`], which allows padding between rows and cropped regions of a
+/// larger image.
 ///
 /// # Buffer Requirements
 /// The destination buffer must have sufficient capacity to store the compressed blocks.
@@ -43,37 +128,27 @@ use crate::CompressionVariant;
 ///
 /// # Arguments
 /// * `variation` - The block compression format to use
-/// * `rgba_data` - Source RGBA8 pixel data
+/// * `image` - View into the source RGBA8 pixel data
 /// * `blocks_buffer` - Destination buffer for the compressed blocks
-/// * `width` - Width of the image in pixels
-/// * `height` - Height of the image in pixels
-/// * `stride` - Number of bytes per row in the source data (for padding).
-///              Must be `width * 4` for tightly packed RGBA data.
 ///
 /// # Panics
-/// * If `width` or `height` is not a multiple of 4
+/// * If the width or height of the `image` is not a multiple of 4. Use
+///   [`compress_rgba8_padded()`] for images of any size.
 /// * If the destination `blocks_buffer` is too small to hold the compressed data
 ///
 /// # Example
 /// ```
-/// use block_compression::{encode::compress_rgba8, CompressionVariant};
+/// use block_compression::{encode::compress_rgba8, CompressionVariant, ImageView};
 ///
 /// let rgba_data = vec![0u8; 256 * 256 * 4]; // Your RGBA data
 /// let width = 256;
 /// let height = 256;
-/// let stride = width * 4; // Tightly packed rows
+/// let image = ImageView::packed(&rgba_data, width, height); // Tightly packed rows
 /// let variant = CompressionVariant::BC1;
 ///
 /// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(width, height)];
 ///
-/// compress_rgba8(
-///     variant,
-///     &rgba_data,
-///     &mut blocks_buffer,
-///     width,
-///     height,
-///     stride,
-/// );
+/// compress_rgba8(variant, image, &mut blocks_buffer);
 /// ```
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
 #[cfg_attr(
@@ -82,12 +157,76 @@ use crate::CompressionVariant;
 )]
 pub fn compress_rgba8(
     variation: CompressionVariant,
-    rgba_data: &[u8],
+    image: ImageView<'_, u8>,
     blocks_buffer: &mut [u8],
-    width: u32,
-    height: u32,
-    stride: u32,
 ) {
+    image.assert_rgba();
+    compress_rgba8_with(
+        variation,
+        image,
+        blocks_buffer,
+        #[cfg(feature = "bc15")]
+        BlockCompressorBC15::compress_block_bc1_core,
+    );
+}
+
+/// Compresses RGBA8 data for the fast preview tier, which trades quality for latency.
+///
+/// Meant for live previews, like the viewport of an editor, where a texture is recompressed
+/// on every change and the latency matters more than the PSNR. The final asset should be
+/// compressed again with [`compress_rgba8()`]. The tier per variant is:
+///
+/// * BC1, BC2 and BC3: The color endpoints are the minimum and maximum of the color channels,
+///   without any refinement.
+/// * BC4 and BC5: The endpoints are the minimum and maximum of the channels, like in
+///   [`compress_rgba8()`].
+/// * BC6H and BC7: The settings of the variant are replaced by [`BC6HSettings::preview()`] or
+///   [`BC7Settings::preview()`], see [`CompressionVariant::preview()`].
+///
+/// All arguments behave like in [`compress_rgba8()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compress_rgba8_preview, CompressionVariant, ImageView};
+///
+/// let rgba_data = vec![0u8; 64 * 64 * 4];
+/// let variant = CompressionVariant::BC1;
+/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(64, 64)];
+///
+/// compress_rgba8_preview(variant, ImageView::packed(&rgba_data, 64, 64), &mut blocks_buffer);
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compress_rgba8_preview(
+    variation: CompressionVariant,
+    image: ImageView<'_, u8>,
+    blocks_buffer: &mut [u8],
+) {
+    image.assert_rgba();
+    compress_rgba8_with(
+        variation.preview(),
+        image,
+        blocks_buffer,
+        #[cfg(feature = "bc15")]
+        BlockCompressorBC15::compress_block_bc1_preview,
+    );
+}
+
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+fn compress_rgba8_with(
+    variation: CompressionVariant,
+    image: ImageView<'_, u8>,
+    blocks_buffer: &mut [u8],
+    #[cfg(feature = "bc15")] compress_color_block: ColorBlockFn,
+) {
+    let (width, height) = (image.width(), image.height());
     assert_eq!(height % 4, 0);
     assert_eq!(width % 4, 0);
 
@@ -100,122 +239,1366 @@ pub fn compress_rgba8(
         required_size
     );
 
-    let stride = stride as usize;
+    let rgba_data = image.data();
+    let stride = image.stride() as usize;
     let block_width = (width as usize + 3) / 4;
     let block_height = (height as usize + 3) / 4;
 
     match variation {
         #[cfg(feature = "bc15")]
         CompressionVariant::BC1 => {
-            compress_bc1(rgba_data, blocks_buffer, block_width, block_height, stride);
+            compress_bc1(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                compress_color_block,
+            );
         }
         #[cfg(feature = "bc15")]
         CompressionVariant::BC2 => {
-            compress_bc2(rgba_data, blocks_buffer, block_width, block_height, stride);
+            compress_bc2(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                compress_color_block,
+                false,
+            );
         }
         #[cfg(feature = "bc15")]
         CompressionVariant::BC3 => {
-            compress_bc3(rgba_data, blocks_buffer, block_width, block_height, stride);
+            compress_bc3(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                compress_color_block,
+            );
         }
         #[cfg(feature = "bc15")]
         CompressionVariant::BC4 => {
-            compress_bc4(rgba_data, blocks_buffer, block_width, block_height, stride);
+            compress_bc4(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                None,
+            );
+        }
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC5 => {
+            compress_bc5(rgba_data, blocks_buffer, block_width, block_height, stride);
+        }
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(settings) => {
+            compress_bc6h_8bit(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                &settings,
+            );
+        }
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(settings) => {
+            compress_bc7(
+                rgba_data,
+                blocks_buffer,
+                block_width,
+                block_height,
+                stride,
+                &settings,
+            );
+        }
+        CompressionVariant::RGBA8 => {
+            copy_rgba8(rgba_data, blocks_buffer, block_width, block_height, stride);
+        }
+    }
+}
+
+/// Compresses raw RGBA16 (half-float) data using the BC6H texture block compression format.
+///
+/// It supports only BC6H compression format and provides CPU-based texture compression
+/// for RGBA16 (half-float) data. BC6H has no alpha channel, so the alpha is dropped. Use
+/// [`find_discarded_alpha()`] to detect images whose alpha would get lost.
+///
+/// # Data Layout Requirements
+/// The input data must be in RGBA16 format (16 bits per channel using half-float). The data is
+/// described by an [`ImageView`], which allows padding between rows and cropped regions of a
+/// larger image.
+///
+/// # Buffer Requirements
+/// The destination buffer must have sufficient capacity to store the compressed blocks.
+/// The required size can be calculated using [`CompressionVariant::blocks_byte_size()`].
+///
+/// For example:
+/// ```ignore
+/// let required_size = variant.blocks_byte_size(width, height);
+/// assert!(blocks_buffer.len() &gt;= required_size);
+/// ```
+///
+/// # Arguments
+/// * `variation` - The block compression format to use (must be BC6H)
+/// * `image` - View into the source RGBA16 pixel data in half-float format
+/// * `blocks_buffer` - Destination buffer for the compressed blocks
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+/// * If `variation` is not `CompressionVariant::BC6H`
+/// * If a color channel of the `image` is NaN or infinite and the settings use
+///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject)
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compress_rgba16, BC6HSettings, CompressionVariant, ImageView};
+/// use half::f16;
+///
+/// let rgba_data = vec![f16::ZERO; 256 * 256 * 4]; // Your RGBA16 data
+/// let width = 256;
+/// let height = 256;
+/// let image = ImageView::packed(&rgba_data, width, height); // Tightly packed rows
+/// let settings = BC6HSettings::very_slow();
+/// let variant = CompressionVariant::BC6H(settings);
+///
+/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(width, height)];
+///
+/// compress_rgba16(variant, image, &mut blocks_buffer);
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn compress_rgba16(
+    variation: CompressionVariant,
+    image: ImageView<'_, half::f16>,
+    blocks_buffer: &mut [u8],
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    let block_width = (width as usize + 3) / 4;
+    let block_height = (height as usize + 3) / 4;
+
+    match variation {
+        CompressionVariant::BC6H(settings) => {
+            compress_bc6h_16bit(image, blocks_buffer, block_width, block_height, &settings);
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            panic!("only BC6H is supported for calling compress_rgba16");
+        }
+    }
+}
+
+/// Compresses RGBA32F data into BC6H after remapping the color channels.
+///
+/// Every color channel is converted to a half float as `value * scale + bias` of the `remap`,
+/// so float images with values beyond the half float range can be stored. Finite values outside
+/// of the half float range after the remapping are clamped to it, and NaN and infinite values
+/// are handled as configured in the settings. Returns the inverse of `remap`, which restores the
+/// original values from the decoded blocks and needs to be stored next to the texture. All other
+/// arguments behave like in [`compress_rgba16()`].
+///
+/// # Panics
+/// * For the same reasons as [`compress_rgba16()`]
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::compress_rgba32f_remapped, BC6HSettings, CompressionVariant, HdrRemap, ImageView,
+/// };
+///
+/// // Luminance in candela per square meter, far beyond the half float range.
+/// let rgba_data = vec![1.0e6_f32; 8 * 8 * 4];
+/// let image = ImageView::packed(&rgba_data, 8, 8);
+/// let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+///
+/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(8, 8)];
+/// let restore = compress_rgba32f_remapped(
+///     variant,
+///     image,
+///     HdrRemap::from_range(0.0, 2.0e6),
+///     &mut blocks_buffer,
+/// );
+/// assert_eq!(restore.apply(0.5), 1.0e6);
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn compress_rgba32f_remapped(
+    variation: CompressionVariant,
+    image: ImageView<'_, f32>,
+    remap: HdrRemap,
+    blocks_buffer: &mut [u8],
+) -> HdrRemap {
+    image.assert_rgba();
+    remap.validate();
+
+    let half_max = half::f16::MAX.to_f32();
+    let remap_channel = |value: f32| {
+        let remapped = remap.apply(value);
+        if value.is_nan() {
+            half::f16::NAN
+        } else if value.is_infinite() {
+            // The sign of infinite values is the one after the remapping.
+            half::f16::INFINITY.copysign(half::f16::from_f32(remapped))
+        } else {
+            half::f16::from_f32(remapped.clamp(-half_max, half_max))
         }
+    };
+
+    let (width, height) = (image.width(), image.height());
+    let remapped: Vec<half::f16> = image
+        .rows()
+        .flat_map(|row| row[..width as usize * 4].chunks_exact(4))
+        .flat_map(|texel| {
+            [
+                remap_channel(texel[0]),
+                remap_channel(texel[1]),
+                remap_channel(texel[2]),
+                half::f16::from_f32(texel[3]),
+            ]
+        })
+        .collect();
+
+    compress_rgba16(
+        variation,
+        ImageView::packed(&remapped, width, height),
+        blocks_buffer,
+    );
+
+    remap.inverse()
+}
+
+/// The alpha channel of an image that is discarded by BC6H, as found by
+/// [`find_discarded_alpha()`].
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiscardedAlpha {
+    /// The number of pixels that are not opaque.
+    pub translucent_pixels: usize,
+    /// The smallest alpha value of the image in the range `[0, 1]`.
+    pub min_alpha: f32,
+}
+
+/// Checks if the alpha channel of an RGBA16 (half-float) image carries information.
+///
+/// BC6H only stores the color channels and silently drops the alpha channel. Running this
+/// analysis before [`compress_rgba16()`] allows tools to warn artists that their alpha is
+/// discarded, instead of them finding out in-game. Alpha values that round to an opaque 8-bit
+/// alpha count as opaque, so that small conversion errors are not reported.
+///
+/// Returns `None` if every pixel is opaque.
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::find_discarded_alpha, ImageView};
+/// use half::f16;
+///
+/// let mut rgba_data = vec![f16::ONE; 4 * 4 * 4];
+/// assert_eq!(find_discarded_alpha(ImageView::packed(&rgba_data, 4, 4)), None);
+///
+/// rgba_data[3] = f16::from_f32(0.5);
+/// let discarded = find_discarded_alpha(ImageView::packed(&rgba_data, 4, 4)).unwrap();
+/// assert_eq!(discarded.translucent_pixels, 1);
+/// assert_eq!(discarded.min_alpha, 0.5);
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn find_discarded_alpha(image: ImageView<'_, half::f16>) -> Option<DiscardedAlpha> {
+    image.assert_rgba();
+    discarded_alpha(
+        image
+            .rows()
+            .flat_map(|row| row.chunks_exact(4).map(|pixel| pixel[3].to_f32())),
+    )
+}
+
+/// Checks if the alpha channel of an RGBA8 image carries information.
+///
+/// Works like [`find_discarded_alpha()`] for images that are compressed to BC6H with
+/// [`compress_rgba8()`].
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn find_discarded_alpha_rgba8(image: ImageView<'_, u8>) -> Option<DiscardedAlpha> {
+    image.assert_rgba();
+    discarded_alpha(
+        image
+            .rows()
+            .flat_map(|row| row.chunks_exact(4).map(|pixel| pixel[3] as f32 / 255.0)),
+    )
+}
+
+/// Returns the `(x, y)` coordinates of the first pixel of an RGBA16 (half-float) image with a
+/// color channel that is NaN or infinite, in row-major order.
+///
+/// Allows tools to report broken HDR sources before they are compressed, see
+/// [`NonFiniteHandling`](crate::NonFiniteHandling). The alpha channel is ignored, since BC6H
+/// discards it. Returns `None` if all color channels are finite.
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::find_non_finite, ImageView};
+/// use half::f16;
+///
+/// let mut rgba_data = vec![f16::ONE; 8 * 4 * 4];
+/// assert_eq!(find_non_finite(ImageView::packed(&rgba_data, 8, 4)), None);
+///
+/// rgba_data[(2 * 8 + 5) * 4 + 1] = f16::NAN;
+/// assert_eq!(find_non_finite(ImageView::packed(&rgba_data, 8, 4)), Some((5, 2)));
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn find_non_finite(image: ImageView<'_, half::f16>) -> Option<(u32, u32)> {
+    image.assert_rgba();
+    image.rows().zip(0..).find_map(|(row, y)| {
+        row.chunks_exact(4)
+            .position(|pixel| pixel[..3].iter().any(|value| !value.is_finite()))
+            .map(|x| (x as u32, y))
+    })
+}
+
+#[cfg(feature = "bc6h")]
+fn discarded_alpha(alpha: impl Iterator<Item = f32>) -> Option<DiscardedAlpha> {
+    // Everything that rounds to an 8-bit alpha of 255 is opaque.
+    const OPAQUE: f32 = 254.5 / 255.0;
+
+    let mut discarded = DiscardedAlpha {
+        translucent_pixels: 0,
+        min_alpha: 1.0,
+    };
+
+    for alpha in alpha {
+        if alpha < OPAQUE || alpha.is_nan() {
+            discarded.translucent_pixels += 1;
+            discarded.min_alpha = f32::min(discarded.min_alpha, alpha);
+        }
+    }
+
+    (discarded.translucent_pixels > 0).then_some(discarded)
+}
+
+/// Compresses raw RGBA8 data of any size.
+///
+/// If the width or height of the `image` is not a multiple of 4, the blocks at the right and
+/// bottom edge extend past the image and the missing pixels are filled as described by
+/// `edge_fill`. All other arguments behave like in [`compress_rgba8()`].
+///
+/// # Panics
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compress_rgba8_padded, CompressionVariant, EdgeFill, ImageView};
+///
+/// let rgba_data = vec![0u8; 30 * 30 * 4]; // Your RGBA data
+/// let image = ImageView::packed(&rgba_data, 30, 30);
+/// let variant = CompressionVariant::BC1;
+///
+/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(30, 30)];
+///
+/// compress_rgba8_padded(variant, image, EdgeFill::ClampToEdge, &mut blocks_buffer);
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compress_rgba8_padded(
+    variation: CompressionVariant,
+    image: ImageView<'_, u8>,
+    edge_fill: EdgeFill,
+    blocks_buffer: &mut [u8],
+) {
+    image.assert_rgba();
+    if image.width() % 4 == 0 && image.height() % 4 == 0 {
+        compress_rgba8(variation, image, blocks_buffer);
+        return;
+    }
+
+    let (rgba_data, width, height) = image.padded_to_blocks(edge_fill);
+    compress_rgba8(
+        variation,
+        ImageView::packed(&rgba_data, width, height),
+        blocks_buffer,
+    );
+}
+
+/// Compresses the mip levels in `mip_range` into an existing buffer that holds the compressed
+/// blocks of a full mip chain.
+///
+/// Allows re-encoding single mip levels with a different variant setting, for example to
+/// re-bake the highest-resolution levels of a streamed texture with a slower preset, without
+/// touching the blocks of all other levels. The blocks of every level are written at the offset
+/// returned by [`CompressionVariant::mip_blocks_offset()`]. Levels whose size is not a multiple
+/// of 4 are padded by clamping to the edge, use [`compress_rgba8_mip_range_with_edge_fill()`] to
+/// select a different [`EdgeFill`].
+///
+/// The chain can end with the 1x1 level or earlier, see [`MipTail`](crate::mip::MipTail).
+/// `mip_levels` only needs to contain the levels of the chain.
+///
+/// # Arguments
+/// * `variation` - The compression format to use. Needs to be the variant of the mip chain.
+/// * `mip_levels` - The source images of the mip levels, starting with the base level. Only the
+///   levels in `mip_range` are read.
+/// * `mip_range` - The mip levels that are compressed
+/// * `blocks_buffer` - The blocks of the full mip chain
+///
+/// # Panics
+/// * If `mip_range` is not inside `mip_levels`
+/// * If a source image in `mip_range` has not the size of its mip level
+/// * If the `blocks_buffer` is too small to hold the blocks of the levels in `mip_range`
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::compress_rgba8_mip_range, mip::generate_mip_chain_rgba8, BC7Settings,
+///     CompressionVariant, ImageView,
+/// };
+///
+/// let rgba_data = vec![0u8; 64 * 64 * 4];
+/// let mips = generate_mip_chain_rgba8(&rgba_data, 64, 64, &Default::default());
+///
+/// let mut mip_levels = vec![ImageView::packed(&rgba_data, 64, 64)];
+/// mip_levels.extend(mips.iter().map(|mip| mip.view()));
+///
+/// let fast = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+/// let size = fast.mip_blocks_offset(64, 64, mip_levels.len() as u32);
+/// let mut blocks_buffer = vec![0u8; size];
+/// compress_rgba8_mip_range(fast, &mip_levels, 0..mip_levels.len() as u32, &mut blocks_buffer);
+///
+/// // Re-bake the two largest levels later with a slower preset.
+/// let slow = CompressionVariant::BC7(BC7Settings::opaque_slow());
+/// compress_rgba8_mip_range(slow, &mip_levels, 0..2, &mut blocks_buffer);
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compress_rgba8_mip_range(
+    variation: CompressionVariant,
+    mip_levels: &[ImageView<'_, u8>],
+    mip_range: Range<u32>,
+    blocks_buffer: &mut [u8],
+) {
+    compress_rgba8_mip_range_with_edge_fill(
+        variation,
+        mip_levels,
+        mip_range,
+        EdgeFill::ClampToEdge,
+        blocks_buffer,
+    );
+}
+
+/// Compresses the mip levels in `mip_range` like [`compress_rgba8_mip_range()`], but fills the
+/// padding of levels whose size is not a multiple of 4 as described by `edge_fill`.
+///
+/// The last levels of a full chain are smaller than a block, so most of their block is
+/// padding. The padding is never sampled, but it is part of the endpoint fit.
+/// [`EdgeFill::ClampToEdge`] and [`EdgeFill::Mirror`] only repeat the pixels of the level and
+/// keep these levels exact, while [`EdgeFill::Zero`] and [`EdgeFill::SolidColor`] spend
+/// precision on the fill color.
+///
+/// # Panics
+/// * For the same reasons as [`compress_rgba8_mip_range()`]
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::compress_rgba8_mip_range_with_edge_fill,
+///     mip::{generate_mip_chain_rgba8, BakeOptions, MipTail},
+///     CompressionVariant, EdgeFill, ImageView,
+/// };
+///
+/// let rgba_data = vec![0u8; 64 * 64 * 4];
+/// let options = BakeOptions {
+///     mip_tail: MipTail::StopAt4x4,
+///     ..Default::default()
+/// };
+/// let mips = generate_mip_chain_rgba8(&rgba_data, 64, 64, &options);
+///
+/// let mut mip_levels = vec![ImageView::packed(&rgba_data, 64, 64)];
+/// mip_levels.extend(mips.iter().map(|mip| mip.view()));
+///
+/// let variant = CompressionVariant::BC1;
+/// let mut blocks_buffer = vec![0u8; variant.mip_chain_byte_size(64, 64, options.mip_tail)];
+/// compress_rgba8_mip_range_with_edge_fill(
+///     variant,
+///     &mip_levels,
+///     0..mip_levels.len() as u32,
+///     EdgeFill::Mirror,
+///     &mut blocks_buffer,
+/// );
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compress_rgba8_mip_range_with_edge_fill(
+    variation: CompressionVariant,
+    mip_levels: &[ImageView<'_, u8>],
+    mip_range: Range<u32>,
+    edge_fill: EdgeFill,
+    blocks_buffer: &mut [u8],
+) {
+    assert!(
+        mip_range.end as usize <= mip_levels.len(),
+        "mip range {mip_range:?} is not inside the {} mip levels",
+        mip_levels.len()
+    );
+
+    let (width, height) = (mip_levels[0].width(), mip_levels[0].height());
+
+    for mip_level in mip_range {
+        let image = mip_levels[mip_level as usize];
+        let mip_width = mip_dimension(width, mip_level);
+        let mip_height = mip_dimension(height, mip_level);
+        assert_eq!(
+            (image.width(), image.height()),
+            (mip_width, mip_height),
+            "mip level {mip_level} has not the expected size"
+        );
+
+        let offset = variation.mip_blocks_offset(width, height, mip_level);
+        let size = variation.blocks_byte_size(mip_width, mip_height);
+        assert!(
+            blocks_buffer.len() >= offset + size,
+            "blocks_buffer size ({}) is too small to hold mip level {mip_level}. Required size: {}",
+            blocks_buffer.len(),
+            offset + size
+        );
+
+        compress_rgba8_padded(
+            variation,
+            image,
+            edge_fill,
+            &mut blocks_buffer[offset..offset + size],
+        );
+    }
+}
+
+/// Compresses grayscale RGBA8 data using BC1 with gray endpoints.
+///
+/// Meant for grayscale images like masks, which are commonly stored as BC1. Instead of fitting
+/// the RGB colors, only the luminance of the pixels is fitted and both endpoints are forced to
+/// the 64 gray levels of RGB565. This gives a better quality for grayscale images and the
+/// repeating endpoints compress better with general purpose compressors like zstd. The alpha
+/// channel is ignored.
+///
+/// All other arguments behave like in [`compress_rgba8()`] with `CompressionVariant::BC1`.
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_rgba8_bc1_grayscale(image: ImageView<'_, u8>, blocks_buffer: &mut [u8]) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = CompressionVariant::BC1.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    compress_bc1(
+        image.data(),
+        blocks_buffer,
+        (width as usize).div_ceil(4),
+        (height as usize).div_ceil(4),
+        image.stride() as usize,
+        BlockCompressorBC15::compress_block_bc1_grayscale,
+    );
+}
+
+/// Compresses RGBA8 data using BC1 with punch-through alpha where it lowers the error.
+///
+/// BC1 has a second mode with only three colors, in which the fourth index is transparent
+/// black. It's meant for images with binary alpha, like foliage or fences, which would otherwise
+/// need BC2 or BC3 at twice the size. For every block with pixels whose alpha is below 128, both
+/// modes are compressed and the one with the lower error is kept, like good DXT1 encoders do.
+/// The error of the color is weighted by the alpha of the source, since the color of invisible
+/// pixels doesn't matter. Blocks without transparent pixels are compressed like in
+/// [`compress_rgba8()`]. The mode is selected like by
+/// the `EncodingOptions::punch_through_alpha` of the GPU compressor.
+///
+/// The transparent pixels are decoded as black. Renderers that blend need premultiplied alpha
+/// or an alpha test to hide them.
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_rgba8_bc1_punch_through(image: ImageView<'_, u8>, blocks_buffer: &mut [u8]) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = CompressionVariant::BC1.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    compress_bc1(
+        image.data(),
+        blocks_buffer,
+        width.div_ceil(4) as usize,
+        height.div_ceil(4) as usize,
+        image.stride() as usize,
+        BlockCompressorBC15::compress_block_bc1_punch_through,
+    );
+}
+
+/// Compresses RGBA8 data using BC2 with an ordered dither of the 4-bit alpha.
+///
+/// BC2 stores alpha with only 16 levels, which shows as visible banding in smooth gradients
+/// like smoke or fog. This adds a fixed 4x4 Bayer pattern to the alpha before quantizing it, so
+/// that the average alpha of every block matches the source more closely at the cost of a fine
+/// noise pattern. The pattern is the same as the one of
+/// the `EncodingOptions::dither_alpha` of the GPU compressor, so both produce the same alpha
+/// blocks. The color is compressed like in [`compress_rgba8()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_rgba8_bc2_dithered(image: ImageView<'_, u8>, blocks_buffer: &mut [u8]) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = CompressionVariant::BC2.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    compress_bc2(
+        image.data(),
+        blocks_buffer,
+        width.div_ceil(4) as usize,
+        height.div_ceil(4) as usize,
+        image.stride() as usize,
+        BlockCompressorBC15::compress_block_bc1_core,
+        true,
+    );
+}
+
+/// Compresses the red channel of RGBA8 data as a signed distance field using BC4.
+///
+/// Signed distance fields of fonts and shapes are rendered by thresholding the distance at the
+/// `pivot`, usually 128, which is the 0.5 crossing of a normalized distance field. Instead of
+/// minimizing the error of all values alike, the error of values close to the pivot is weighted
+/// up to 32 times higher, so the endpoints keep the crossing in place. This preserves the edges
+/// far better at the same size, while values far away from the edge get slightly less accurate.
+/// The weights are the same as the ones of the `EncodingOptions::sdf_pivot` of the GPU compressor.
+///
+/// All other arguments behave like in [`compress_rgba8()`] with `CompressionVariant::BC4`.
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_rgba8_bc4_sdf(image: ImageView<'_, u8>, pivot: u8, blocks_buffer: &mut [u8]) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = CompressionVariant::BC4.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    compress_bc4(
+        image.data(),
+        blocks_buffer,
+        width.div_ceil(4) as usize,
+        height.div_ceil(4) as usize,
+        image.stride() as usize,
+        Some(pivot),
+    );
+}
+
+/// Compresses grayscale and alpha (LA8) data into a single BC5 buffer.
+///
+/// Meant for font and SDF atlases, which store a coverage or distance value together with an
+/// alpha value. The luminance is stored in the red channel and the alpha in the green channel.
+/// `image` holds the luminance and the alpha of every pixel and needs two channels. Images of any
+/// size are supported. The blocks at the right and bottom edge repeat the edge pixels. Use
+/// [`decompress_bc5_as_la8()`](crate::decode::decompress_bc5_as_la8) to decode the blocks.
+///
+/// # Panics
+/// * If `image` doesn't have exactly two channels
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compress_la8_bc5, CompressionVariant, ImageView};
+///
+/// let (width, height) = (30, 18);
+/// let la_data = vec![0u8; width as usize * height as usize * 2];
+/// let image = ImageView::packed_with_channels(&la_data, width, height, 2);
+///
+/// let mut blocks_buffer = vec![0u8; CompressionVariant::BC5.blocks_byte_size(width, height)];
+/// compress_la8_bc5(image, &mut blocks_buffer);
+/// ```
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_la8_bc5(image: ImageView<'_, u8>, blocks_buffer: &mut [u8]) {
+    let required_size = CompressionVariant::BC5.blocks_byte_size(image.width(), image.height());
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    let (rgba_data, padded_width, padded_height) = la8_to_padded_rgba8(image);
+
+    compress_bc5(
+        &rgba_data,
+        blocks_buffer,
+        padded_width as usize / 4,
+        padded_height as usize / 4,
+        padded_width as usize * 4,
+    );
+}
+
+/// Compresses grayscale and alpha (LA8) data into two separate BC4 buffers.
+///
+/// Works like [`compress_la8_bc5()`], but stores the luminance and the alpha as separate
+/// single channel textures, for example if the alpha is only needed by some passes. Both
+/// buffers hold the same blocks as the red and green half of the BC5 blocks. Use
+/// [`decompress_bc4_as_la8()`](crate::decode::decompress_bc4_as_la8) to decode the blocks.
+///
+/// # Panics
+/// * If `image` doesn't have exactly two channels
+/// * If `luminance_blocks` or `alpha_blocks` are too small to hold the compressed data
+#[cfg(feature = "bc15")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+pub fn compress_la8_bc4(
+    image: ImageView<'_, u8>,
+    luminance_blocks: &mut [u8],
+    alpha_blocks: &mut [u8],
+) {
+    let (width, height) = (image.width(), image.height());
+    let required_size = CompressionVariant::BC4.blocks_byte_size(width, height);
+
+    assert!(
+        luminance_blocks.len() >= required_size,
+        "luminance_blocks size ({}) is too small to hold compressed blocks. Required size: {}",
+        luminance_blocks.len(),
+        required_size
+    );
+    assert!(
+        alpha_blocks.len() >= required_size,
+        "alpha_blocks size ({}) is too small to hold compressed blocks. Required size: {}",
+        alpha_blocks.len(),
+        required_size
+    );
+
+    let mut bc5_blocks = vec![0; CompressionVariant::BC5.blocks_byte_size(width, height)];
+    compress_la8_bc5(image, &mut bc5_blocks);
+
+    for ((block, luminance), alpha) in bc5_blocks
+        .chunks_exact(16)
+        .zip(luminance_blocks.chunks_exact_mut(8))
+        .zip(alpha_blocks.chunks_exact_mut(8))
+    {
+        luminance.copy_from_slice(&block[..8]);
+        alpha.copy_from_slice(&block[8..]);
+    }
+}
+
+/// Expands LA8 data into RGBA8 data with the luminance in red and the alpha in green, which is
+/// extended to the next multiple of 4 in both dimensions.
+#[cfg(feature = "bc15")]
+fn la8_to_padded_rgba8(image: ImageView<'_, u8>) -> (Vec<u8>, u32, u32) {
+    assert_eq!(
+        image.channels(),
+        2,
+        "the image needs to hold LA8 data with 2 channels"
+    );
+
+    let rgba_data: Vec<u8> = image
+        .rows()
+        .flat_map(|row| row.chunks_exact(2))
+        .flat_map(|texel| [texel[0], texel[1], 0, 255])
+        .collect();
+
+    ImageView::packed(&rgba_data, image.width(), image.height())
+        .padded_to_blocks(EdgeFill::ClampToEdge)
+}
+
+/// Compresses raw RGBA8 data into a BC1 and a BC7 tier in a single pass.
+///
+/// Streaming systems often ship a low quality BC1 and a high quality BC7 version of the same
+/// texture. Every block of the source is read and converted only once and then encoded into
+/// both variants, which saves the second pass over the image. The blocks are identical to
+/// compressing the image with [`compress_rgba8()`] once for each variant.
+///
+/// # Arguments
+/// * `image` - View into the source RGBA8 pixel data
+/// * `bc7_settings` - The settings of the BC7 tier
+/// * `bc1_blocks` - Destination buffer for the compressed BC1 blocks
+/// * `bc7_blocks` - Destination buffer for the compressed BC7 blocks
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If one of the destination buffers is too small to hold the compressed data
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::compress_rgba8_bc1_bc7, BC7Settings, CompressionVariant, ImageView,
+/// };
+///
+/// let rgba_data = vec![0u8; 64 * 64 * 4];
+/// let image = ImageView::packed(&rgba_data, 64, 64);
+///
+/// let mut bc1_blocks = vec![0u8; CompressionVariant::BC1.blocks_byte_size(64, 64)];
+/// let mut bc7_blocks = vec![0u8; 16 * 16 * 16];
+///
+/// compress_rgba8_bc1_bc7(
+///     image,
+///     &BC7Settings::opaque_basic(),
+///     &mut bc1_blocks,
+///     &mut bc7_blocks,
+/// );
+/// ```
+#[cfg(all(feature = "bc15", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "bc15", feature = "bc7"))))]
+pub fn compress_rgba8_bc1_bc7(
+    image: ImageView<'_, u8>,
+    bc7_settings: &BC7Settings,
+    bc1_blocks: &mut [u8],
+    bc7_blocks: &mut [u8],
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    for (variant, blocks_buffer) in [
+        (CompressionVariant::BC1, &*bc1_blocks),
+        (CompressionVariant::BC7(*bc7_settings), &*bc7_blocks),
+    ] {
+        let required_size = variant.blocks_byte_size(width, height);
+
+        assert!(
+            blocks_buffer.len() >= required_size,
+            "blocks_buffer size ({}) is too small to hold compressed {:?} blocks. Required size: {}",
+            blocks_buffer.len(),
+            variant,
+            required_size
+        );
+    }
+
+    compress_bc1_bc7(
+        image.data(),
+        bc1_blocks,
+        bc7_blocks,
+        width as usize / 4,
+        height as usize / 4,
+        image.stride() as usize,
+        bc7_settings,
+    );
+}
+
+/// Returns the compressed blocks of a texture that is filled with a single RGBA8 color.
+///
+/// The bit pattern of the block is derived analytically from the color and repeated for every
+/// block, without running the encoder. This makes it cheap to create placeholder textures or
+/// fallbacks for textures that are still streaming in.
+///
+/// BC1 ignores the alpha channel, BC4 only uses the red channel and BC5 the red and green
+/// channel. For BC6H the color channels are mapped to `[0, 1]`. BC4, BC5 and BC7 reproduce the
+/// color exactly.
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compressed_solid_color, CompressionVariant};
+///
+/// let variant = CompressionVariant::BC3;
+/// let blocks = compressed_solid_color(variant, [255, 0, 255, 255], 64, 64);
+/// assert_eq!(blocks.len(), variant.blocks_byte_size(64, 64));
+/// ```
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compressed_solid_color(
+    variant: CompressionVariant,
+    rgba: [u8; 4],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    if variant == CompressionVariant::RGBA8 {
+        return rgba.repeat(variant.blocks_byte_size(width, height) / rgba.len());
+    }
+
+    let block = solid_color::solid_color_block(variant, rgba);
+    let block = &block[..variant.block_byte_size() as usize];
+    block.repeat(variant.blocks_byte_size(width, height) / block.len())
+}
+
+/// Compresses raw RGBA16 (half-float) data of any size using BC6H.
+///
+/// The edge of images whose width or height is not a multiple of 4 is filled as described by
+/// `edge_fill`. All other arguments behave like in [`compress_rgba16()`].
+///
+/// # Panics
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+/// * If `variation` is not `CompressionVariant::BC6H`
+/// * If a color channel of the `image` is NaN or infinite and the settings use
+///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject)
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn compress_rgba16_padded(
+    variation: CompressionVariant,
+    image: ImageView<'_, half::f16>,
+    edge_fill: EdgeFill,
+    blocks_buffer: &mut [u8],
+) {
+    image.assert_rgba();
+    if image.width() % 4 == 0 && image.height() % 4 == 0 {
+        compress_rgba16(variation, image, blocks_buffer);
+        return;
+    }
+
+    let (rgba_data, width, height) = image.padded_to_blocks(edge_fill);
+    compress_rgba16(
+        variation,
+        ImageView::packed(&rgba_data, width, height),
+        blocks_buffer,
+    );
+}
+
+/// Compresses raw RGBA8 data while reusing the blocks of a previously compressed frame.
+///
+/// This is meant for slowly changing content, like dynamic lightmaps. For every block the block
+/// at the same position in `previous_blocks` is evaluated first. If its error is not larger than
+/// `max_error`, it is reused as is and the expensive mode search is skipped. Otherwise the
+/// regular search is run, which can only improve upon the previous block.
+///
+/// The error is the sum of the squared errors over all pixels of a block, measured in the
+/// error metric of the encoder: 8-bit channel values for BC7 and the bit patterns of the half
+/// floats (scaled by 64 / 31) for BC6H. A `max_error` of `0.0` only reuses perfect blocks.
+///
+/// Only BC6H and BC7 are supported. All other arguments behave like in [`compress_rgba8()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If `previous_blocks` or `blocks_buffer` are too small to hold the compressed data
+/// * If `variation` is not BC6H or BC7
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bc6h", feature = "bc7"))))]
+pub fn compress_rgba8_with_previous(
+    variation: CompressionVariant,
+    image: ImageView<'_, u8>,
+    previous_blocks: &[u8],
+    blocks_buffer: &mut [u8],
+    max_error: f32,
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+    assert!(
+        previous_blocks.len() >= required_size,
+        "previous_blocks size ({}) is too small to hold compressed blocks. Required size: {}",
+        previous_blocks.len(),
+        required_size
+    );
+
+    let rgba_data = image.data();
+    let stride = image.stride() as usize;
+    let block_width = (width as usize).div_ceil(4);
+    let block_height = (height as usize).div_ceil(4);
+
+    match variation {
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(settings) => {
+            compress_blocks_16(blocks_buffer, block_width, block_height, |xx, yy, block| {
+                let offset = (yy * block_width + xx) * 16;
+                let mut block_compressor = BlockCompressorBC6H::new(&settings);
+                block_compressor.load_block_interleaved_8bit(rgba_data, xx, yy, stride);
+                block_compressor
+                    .compress_bc6h_core_warm(&previous_blocks[offset..offset + 16], max_error);
+                block_compressor.store_data1(block);
+            });
+        }
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(settings) => {
+            compress_blocks_16(blocks_buffer, block_width, block_height, |xx, yy, block| {
+                let offset = (yy * block_width + xx) * 16;
+                let mut block_compressor = BlockCompressorBC7::new(&settings);
+                block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
+                block_compressor.compute_opaque_err();
+                block_compressor
+                    .compress_block_bc7_core_warm(&previous_blocks[offset..offset + 16], max_error);
+                block_compressor.store_data1(block);
+            });
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            panic!("only BC6H and BC7 are supported for calling compress_rgba8_with_previous");
+        }
+    }
+}
+
+/// Compresses raw RGBA16 (half-float) data with BC6H while reusing the blocks of a previously
+/// compressed frame.
+///
+/// See [`compress_rgba8_with_previous()`] for how the previous blocks are reused and how
+/// `max_error` is measured. All other arguments behave like in [`compress_rgba16()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If `previous_blocks` or `blocks_buffer` are too small to hold the compressed data
+/// * If `variation` is not `CompressionVariant::BC6H`
+/// * If a color channel of the `image` is NaN or infinite and the settings use
+///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject)
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn compress_rgba16_with_previous(
+    variation: CompressionVariant,
+    image: ImageView<'_, half::f16>,
+    previous_blocks: &[u8],
+    blocks_buffer: &mut [u8],
+    max_error: f32,
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+    assert!(
+        previous_blocks.len() >= required_size,
+        "previous_blocks size ({}) is too small to hold compressed blocks. Required size: {}",
+        previous_blocks.len(),
+        required_size
+    );
+
+    let block_width = (width as usize).div_ceil(4);
+    let block_height = (height as usize).div_ceil(4);
+
+    match variation {
+        CompressionVariant::BC6H(settings) => {
+            compress_blocks_16(blocks_buffer, block_width, block_height, |xx, yy, block| {
+                let offset = (yy * block_width + xx) * 16;
+                let mut block_compressor = BlockCompressorBC6H::new(&settings);
+                block_compressor.load_block_interleaved_16bit(image, xx, yy);
+                block_compressor
+                    .compress_bc6h_core_warm(&previous_blocks[offset..offset + 16], max_error);
+                block_compressor.store_data1(block);
+            });
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            panic!("only BC6H is supported for calling compress_rgba16_with_previous");
+        }
+    }
+}
+
+/// Compresses raw RGBA16 (half-float) data with BC6H, starting the search of every block with
+/// the mode and partition a previous compression selected for it.
+///
+/// This is meant for consecutive frames of similar content, like HDR video or captured light
+/// probes. `hints` holds a single byte per block, in the same row-major block order as the
+/// compressed blocks, which names the mode and the partition of the block. For every block with
+/// a hint, the block is first encoded with the hinted mode and partition, fitted to the new
+/// texels. If the error of that encoding is not larger than `max_error`, the mode search is
+/// skipped. Otherwise the regular search is run, which can only improve upon the hinted
+/// encoding. Afterward the hint of the selected encoding is written back into `hints`, so the
+/// same buffer can be passed to the compression of the next frame.
+///
+/// Fill `hints` with [`BC6H_NO_HINT`] for the first frame, which runs the regular search for
+/// every block and only exports the hints. Other values than the ones written by this function
+/// are treated like [`BC6H_NO_HINT`]. Unlike [`compress_rgba16_with_previous()`], the endpoints
+/// are always fitted to the new frame, so blocks with a changing color but a stable structure
+/// benefit as well. `max_error` is measured like for [`compress_rgba8_with_previous()`]. All
+/// other arguments behave like in [`compress_rgba16()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If `hints` is smaller than the number of blocks or `blocks_buffer` is too small to hold
+///   the compressed data
+/// * If `variation` is not `CompressionVariant::BC6H`
+/// * If a color channel of the `image` is NaN or infinite and the settings use
+///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject)
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::{compress_rgba16_with_hints, BC6H_NO_HINT},
+///     BC6HSettings, CompressionVariant, ImageView,
+/// };
+/// use half::f16;
+///
+/// let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+/// let (width, height) = (16, 16);
+/// let mut hints = vec![BC6H_NO_HINT; variant.blocks_byte_size(width, height) / 16];
+/// let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+///
+/// for frame in 0..3 {
+///     let texels = vec![f16::from_f32(frame as f32 + 0.5); (width * height * 4) as usize];
+///     let image = ImageView::packed(&texels, width, height);
+///     compress_rgba16_with_hints(variant, image, &mut hints, &mut blocks, 1.0);
+/// }
+/// assert!(hints.iter().all(|&hint| hint != BC6H_NO_HINT));
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+pub fn compress_rgba16_with_hints(
+    variation: CompressionVariant,
+    image: ImageView<'_, half::f16>,
+    hints: &mut [u8],
+    blocks_buffer: &mut [u8],
+    max_error: f32,
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    let block_width = (width as usize).div_ceil(4);
+    let block_height = (height as usize).div_ceil(4);
+
+    assert!(
+        hints.len() >= block_width * block_height,
+        "hints size ({}) is too small to hold the hints of all blocks. Required size: {}",
+        hints.len(),
+        block_width * block_height
+    );
+
+    match variation {
+        CompressionVariant::BC6H(settings) => {
+            compress_blocks_16_with_side(
+                blocks_buffer,
+                hints,
+                block_width,
+                block_height,
+                |xx, yy, block, hint| {
+                    let mut block_compressor = BlockCompressorBC6H::new(&settings);
+                    block_compressor.load_block_interleaved_16bit(image, xx, yy);
+                    block_compressor.compress_bc6h_core_hinted(*hint, max_error);
+                    block_compressor.store_data1(block);
+                    *hint = block_compressor.hint();
+                },
+            );
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            panic!("only BC6H is supported for calling compress_rgba16_with_hints");
+        }
+    }
+}
+
+/// Compresses raw RGBA8 data and writes the error of every compressed block into `errors_buffer`.
+///
+/// For every block a single `f32` is written in the same row-major block order as the
+/// compressed blocks. This allows callers to build their own refinement, to stream the worst
+/// blocks first or to debug the encoder. The error is the sum of the squared errors of all
+/// pixels of the block:
+///
+/// * BC1 to BC5: Measured on the 8-bit values of the channels the variant stores.
+/// * BC6H and BC7: The error metric of the encoder for the selected block encoding.
+///
+/// All other arguments behave like in [`compress_rgba8()`].
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+/// * If `errors_buffer` is too small to hold one error per block
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub fn compress_rgba8_with_errors(
+    variation: CompressionVariant,
+    image: ImageView<'_, u8>,
+    blocks_buffer: &mut [u8],
+    errors_buffer: &mut [f32],
+) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
+    assert_eq!(height % 4, 0);
+    assert_eq!(width % 4, 0);
+
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    let block_width = (width as usize).div_ceil(4);
+    let block_height = (height as usize).div_ceil(4);
+
+    assert!(
+        errors_buffer.len() >= block_width * block_height,
+        "errors_buffer size ({}) is too small to hold block errors. Required size: {}",
+        errors_buffer.len(),
+        block_width * block_height
+    );
+
+    let rgba_data = image.data();
+    let stride = image.stride() as usize;
+
+    match variation {
         #[cfg(feature = "bc15")]
-        CompressionVariant::BC5 => {
-            compress_bc5(rgba_data, blocks_buffer, block_width, block_height, stride);
+        CompressionVariant::BC1
+        | CompressionVariant::BC2
+        | CompressionVariant::BC3
+        | CompressionVariant::BC4
+        | CompressionVariant::BC5 => {
+            compress_rgba8(variation, image, blocks_buffer);
+            block_errors_bc15(
+                variation,
+                rgba_data,
+                blocks_buffer,
+                errors_buffer,
+                block_width,
+                block_height,
+                stride,
+            );
         }
         #[cfg(feature = "bc6h")]
         CompressionVariant::BC6H(settings) => {
-            compress_bc6h_8bit(
-                rgba_data,
+            compress_blocks_16_with_errors(
                 blocks_buffer,
+                errors_buffer,
                 block_width,
                 block_height,
-                stride,
-                &settings,
+                |xx, yy, block| {
+                    let mut block_compressor = BlockCompressorBC6H::new(&settings);
+                    block_compressor.load_block_interleaved_8bit(rgba_data, xx, yy, stride);
+                    block_compressor.compress_bc6h_core();
+                    block_compressor.store_data1(block);
+                    block_compressor.best_err()
+                },
             );
         }
         #[cfg(feature = "bc7")]
         CompressionVariant::BC7(settings) => {
-            compress_bc7(
-                rgba_data,
+            compress_blocks_16_with_errors(
                 blocks_buffer,
+                errors_buffer,
                 block_width,
                 block_height,
-                stride,
-                &settings,
+                |xx, yy, block| {
+                    let mut block_compressor = BlockCompressorBC7::new(&settings);
+                    block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
+                    block_compressor.compute_opaque_err();
+                    block_compressor.compress_block_bc7_core();
+                    block_compressor.store_data1(block);
+                    block_compressor.best_err()
+                },
             );
         }
+        CompressionVariant::RGBA8 => {
+            compress_rgba8(variation, image, blocks_buffer);
+            errors_buffer[..block_width * block_height].fill(0.0);
+        }
     }
 }
 
-/// Compresses raw RGBA16 (half-float) data using the BC6H texture block compression format.
-///
-/// It supports only BC6H compression format and provides CPU-based texture compression
-/// for RGBA16 (half-float) data.
-///
-/// # Data Layout Requirements
-/// The input data must be in RGBA16 format (16 bits per channel using half-float). The data is
-/// expected to be in row-major order, with optional stride for padding between rows.
-///
-/// # Buffer Requirements
-/// The destination buffer must have sufficient capacity to store the compressed blocks.
-/// The required size can be calculated using [`CompressionVariant::blocks_byte_size()`].
-///
-/// For example:
-/// ```ignore
-/// let required_size = variant.blocks_byte_size(width, height);
-/// assert!(blocks_buffer.len() &gt;= required_size);
-/// ```
+/// Compresses raw RGBA16 (half-float) data with BC6H and writes the error of every compressed
+/// block into `errors_buffer`.
 ///
-/// # Arguments
-/// * `variation` - The block compression format to use (must be BC6H)
-/// * `rgb_data` - Source RGBA16 pixel data in half-float format
-/// * `blocks_buffer` - Destination buffer for the compressed blocks
-/// * `width` - Width of the image in pixels
-/// * `height` - Height of the image in pixels
-/// * `stride` - Number of half-float elements per row in the source data (for padding).
-///              Must be `width * 4` for tightly packed RGBA data.
+/// See [`compress_rgba8_with_errors()`] for the layout of the errors. All other arguments behave
+/// like in [`compress_rgba16()`].
 ///
 /// # Panics
-/// * If `width` or `height` is not a multiple of 4
+/// * If the width or height of the `image` is not a multiple of 4
 /// * If the destination `blocks_buffer` is too small to hold the compressed data
+/// * If `errors_buffer` is too small to hold one error per block
 /// * If `variation` is not `CompressionVariant::BC6H`
-///
-/// # Example
-/// ```
-/// use block_compression::{encode::compress_rgba16, BC6HSettings, CompressionVariant};
-/// use half::f16;
-///
-/// let rgba_data = vec![f16::ZERO; 256 * 256 * 4]; // Your RGBA16 data
-/// let width = 256;
-/// let height = 256;
-/// let stride = width * 4; // Tightly packed rows
-/// let settings = BC6HSettings::very_slow();
-/// let variant = CompressionVariant::BC6H(settings);
-///
-/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(width, height)];
-///
-/// compress_rgba16(
-///     variant,
-///     &rgba_data,
-///     &mut blocks_buffer,
-///     width,
-///     height,
-///     stride,
-/// );
-/// ```
+/// * If a color channel of the `image` is NaN or infinite and the settings use
+///   [`NonFiniteHandling::Reject`](crate::NonFiniteHandling::Reject)
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
-pub fn compress_rgba16(
+pub fn compress_rgba16_with_errors(
     variation: CompressionVariant,
-    rgba_data: &[half::f16],
+    image: ImageView<'_, half::f16>,
     blocks_buffer: &mut [u8],
-    width: u32,
-    height: u32,
-    stride: u32,
+    errors_buffer: &mut [f32],
 ) {
+    image.assert_rgba();
+    let (width, height) = (image.width(), image.height());
     assert_eq!(height % 4, 0);
     assert_eq!(width % 4, 0);
 
@@ -228,24 +1611,35 @@ pub fn compress_rgba16(
         required_size
     );
 
-    let stride = stride as usize;
-    let block_width = (width as usize + 3) / 4;
-    let block_height = (height as usize + 3) / 4;
+    let block_width = (width as usize).div_ceil(4);
+    let block_height = (height as usize).div_ceil(4);
+
+    assert!(
+        errors_buffer.len() >= block_width * block_height,
+        "errors_buffer size ({}) is too small to hold block errors. Required size: {}",
+        errors_buffer.len(),
+        block_width * block_height
+    );
 
     match variation {
         CompressionVariant::BC6H(settings) => {
-            compress_bc6h_16bit(
-                rgba_data,
+            compress_blocks_16_with_errors(
                 blocks_buffer,
+                errors_buffer,
                 block_width,
                 block_height,
-                stride,
-                &settings,
+                |xx, yy, block| {
+                    let mut block_compressor = BlockCompressorBC6H::new(&settings);
+                    block_compressor.load_block_interleaved_16bit(image, xx, yy);
+                    block_compressor.compress_bc6h_core();
+                    block_compressor.store_data1(block);
+                    block_compressor.best_err()
+                },
             );
         }
         #[allow(unreachable_patterns)]
         _ => {
-            panic!("only BC6H is supported for calling compress_rgba16");
+            panic!("only BC6H is supported for calling compress_rgba16_with_errors");
         }
     }
 }
@@ -256,6 +1650,25 @@ use rayon::prelude::*;
 #[cfg(feature = "rayon")]
 use strength_reduce::StrengthReducedUsize;
 
+/// Copies the texels of whole blocks into rows of uncompressed RGBA8 texels.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+fn copy_rgba8(
+    rgba_data: &[u8],
+    blocks_buffer: &mut [u8],
+    block_width: usize,
+    block_height: usize,
+    stride: usize,
+) {
+    let row_size = block_width * 16;
+
+    for (y, row) in blocks_buffer[..row_size * block_height * 4]
+        .chunks_exact_mut(row_size)
+        .enumerate()
+    {
+        row.copy_from_slice(&rgba_data[y * stride..y * stride + row_size]);
+    }
+}
+
 #[cfg(feature = "bc15")]
 fn compress_bc1(
     rgba_data: &[u8],
@@ -263,6 +1676,7 @@ fn compress_bc1(
     block_width: usize,
     block_height: usize,
     stride: usize,
+    compress_block: ColorBlockFn,
 ) {
     #[cfg(feature = "rayon")]
     {
@@ -281,7 +1695,7 @@ fn compress_bc1(
                 let mut block_compressor = BlockCompressorBC15::default();
                 block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
 
-                let color_result = block_compressor.compress_block_bc1_core();
+                let color_result = compress_block(&block_compressor, &block_compressor.stats(0..3));
                 BlockCompressorBC15::store_data1(block, &color_result);
             });
     }
@@ -292,8 +1706,8 @@ fn compress_bc1(
             let mut block_compressor = BlockCompressorBC15::default();
 
             block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
-            let color_result = block_compressor.compress_block_bc1_core();
-            block_compressor.store_data(blocks_buffer, block_width, xx, yy, &color_result);
+            let color_result = compress_block(&block_compressor, &block_compressor.stats(0..3));
+            BlockCompressorBC15::store_data(blocks_buffer, block_width, xx, yy, &color_result);
         }
     }
 }
@@ -305,6 +1719,8 @@ fn compress_bc2(
     block_width: usize,
     block_height: usize,
     stride: usize,
+    compress_color_block: ColorBlockFn,
+    dither_alpha: bool,
 ) {
     #[cfg(feature = "rayon")]
     {
@@ -323,14 +1739,15 @@ fn compress_bc2(
                 let mut compressed_data: [u32; BLOCK_WORDS] = [0; BLOCK_WORDS];
 
                 let alpha_result =
-                    block_compressor.load_block_alpha_4bit(rgba_data, xx, yy, stride);
+                    block_compressor.load_block_alpha_4bit(rgba_data, xx, yy, stride, dither_alpha);
 
                 compressed_data[0] = alpha_result[0];
                 compressed_data[1] = alpha_result[1];
 
                 block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
 
-                let color_result = block_compressor.compress_block_bc1_core();
+                let color_result =
+                    compress_color_block(&block_compressor, &block_compressor.stats(0..3));
                 compressed_data[2] = color_result[0];
                 compressed_data[3] = color_result[1];
 
@@ -344,14 +1761,16 @@ fn compress_bc2(
             let mut block_compressor = BlockCompressorBC15::default();
             let mut compressed_data = [0; 4];
 
-            let alpha_result = block_compressor.load_block_alpha_4bit(rgba_data, xx, yy, stride);
+            let alpha_result =
+                block_compressor.load_block_alpha_4bit(rgba_data, xx, yy, stride, dither_alpha);
 
             compressed_data[0] = alpha_result[0];
             compressed_data[1] = alpha_result[1];
 
             block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
 
-            let color_result = block_compressor.compress_block_bc1_core();
+            let color_result =
+                compress_color_block(&block_compressor, &block_compressor.stats(0..3));
             compressed_data[2] = color_result[0];
             compressed_data[3] = color_result[1];
 
@@ -367,6 +1786,7 @@ fn compress_bc3(
     block_width: usize,
     block_height: usize,
     stride: usize,
+    compress_color_block: ColorBlockFn,
 ) {
     #[cfg(feature = "rayon")]
     {
@@ -387,11 +1807,13 @@ fn compress_bc3(
 
                 block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
 
-                let alpha_result = block_compressor.compress_block_bc3_alpha();
+                let stats = block_compressor.stats(0..4);
+
+                let alpha_result = block_compressor.compress_block_bc3_alpha(&stats);
                 compressed_data[0] = alpha_result[0];
                 compressed_data[1] = alpha_result[1];
 
-                let color_result = block_compressor.compress_block_bc1_core();
+                let color_result = compress_color_block(&block_compressor, &stats);
                 compressed_data[2] = color_result[0];
                 compressed_data[3] = color_result[1];
 
@@ -408,11 +1830,13 @@ fn compress_bc3(
 
             block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
 
-            let alpha_result = block_compressor.compress_block_bc3_alpha();
+            let stats = block_compressor.stats(0..4);
+
+            let alpha_result = block_compressor.compress_block_bc3_alpha(&stats);
             compressed_data[0] = alpha_result[0];
             compressed_data[1] = alpha_result[1];
 
-            let color_result = block_compressor.compress_block_bc1_core();
+            let color_result = compress_color_block(&block_compressor, &stats);
             compressed_data[2] = color_result[0];
             compressed_data[3] = color_result[1];
 
@@ -428,6 +1852,7 @@ fn compress_bc4(
     block_width: usize,
     block_height: usize,
     stride: usize,
+    sdf_pivot: Option<u8>,
 ) {
     #[cfg(feature = "rayon")]
     {
@@ -448,7 +1873,12 @@ fn compress_bc4(
 
                 block_compressor.load_block_r_8bit(rgba_data, xx, yy, stride);
 
-                let color_result = block_compressor.compress_block_bc3_alpha();
+                let color_result = match sdf_pivot {
+                    Some(pivot) => block_compressor.compress_block_bc4_sdf(pivot as f32),
+                    None => {
+                        block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4))
+                    }
+                };
                 compressed_data[0] = color_result[0];
                 compressed_data[1] = color_result[1];
 
@@ -465,7 +1895,10 @@ fn compress_bc4(
 
             block_compressor.load_block_r_8bit(rgba_data, xx, yy, stride);
 
-            let color_result = block_compressor.compress_block_bc3_alpha();
+            let color_result = match sdf_pivot {
+                Some(pivot) => block_compressor.compress_block_bc4_sdf(pivot as f32),
+                None => block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4)),
+            };
             compressed_data[0] = color_result[0];
             compressed_data[1] = color_result[1];
 
@@ -500,13 +1933,15 @@ fn compress_bc5(
 
                 block_compressor.load_block_r_8bit(rgba_data, xx, yy, stride);
 
-                let red_result = block_compressor.compress_block_bc3_alpha();
+                let red_result =
+                    block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4));
                 compressed_data[0] = red_result[0];
                 compressed_data[1] = red_result[1];
 
                 block_compressor.load_block_g_8bit(rgba_data, xx, yy, stride);
 
-                let green_result = block_compressor.compress_block_bc3_alpha();
+                let green_result =
+                    block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4));
                 compressed_data[2] = green_result[0];
                 compressed_data[3] = green_result[1];
 
@@ -523,13 +1958,15 @@ fn compress_bc5(
 
             block_compressor.load_block_r_8bit(rgba_data, xx, yy, stride);
 
-            let red_result = block_compressor.compress_block_bc3_alpha();
+            let red_result =
+                block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4));
             compressed_data[0] = red_result[0];
             compressed_data[1] = red_result[1];
 
             block_compressor.load_block_g_8bit(rgba_data, xx, yy, stride);
 
-            let green_result = block_compressor.compress_block_bc3_alpha();
+            let green_result =
+                block_compressor.compress_block_bc3_alpha(&block_compressor.stats(3..4));
             compressed_data[2] = green_result[0];
             compressed_data[3] = green_result[1];
 
@@ -579,11 +2016,10 @@ fn compress_bc6h_8bit(
 
 #[cfg(feature = "bc6h")]
 fn compress_bc6h_16bit(
-    rgba_data: &[half::f16],
+    image: ImageView<'_, half::f16>,
     blocks_buffer: &mut [u8],
     block_width: usize,
     block_height: usize,
-    stride: usize,
     settings: &BC6HSettings,
 ) {
     #[cfg(feature = "rayon")]
@@ -599,7 +2035,7 @@ fn compress_bc6h_16bit(
                 let (yy, xx) = StrengthReducedUsize::div_rem(idx, bw);
 
                 let mut block_compressor = BlockCompressorBC6H::new(settings);
-                block_compressor.load_block_interleaved_16bit(rgba_data, xx, yy, stride);
+                block_compressor.load_block_interleaved_16bit(image, xx, yy);
                 block_compressor.compress_bc6h_core();
                 block_compressor.store_data1(block);
             });
@@ -609,7 +2045,7 @@ fn compress_bc6h_16bit(
     for yy in 0..block_height {
         for xx in 0..block_width {
             let mut block_compressor = BlockCompressorBC6H::new(settings);
-            block_compressor.load_block_interleaved_16bit(rgba_data, xx, yy, stride);
+            block_compressor.load_block_interleaved_16bit(image, xx, yy);
             block_compressor.compress_bc6h_core();
             block_compressor.store_data(blocks_buffer, block_width, xx, yy);
         }
@@ -625,6 +2061,18 @@ fn compress_bc7(
     stride: usize,
     settings: &BC7Settings,
 ) {
+    if settings.neighbor_seeding().is_some() {
+        compress_bc7_seeded(
+            rgba_data,
+            blocks_buffer,
+            block_width,
+            block_height,
+            stride,
+            settings,
+        );
+        return;
+    }
+
     #[cfg(feature = "rayon")]
     {
         const BLOCK_BYTES: usize = 16;
@@ -657,3 +2105,699 @@ fn compress_bc7(
         }
     }
 }
+
+/// Compresses the blocks of every row from left to right, so that every block can start its
+/// search with the mode of the block to its left. Rows are compressed in parallel.
+#[cfg(feature = "bc7")]
+fn compress_bc7_seeded(
+    rgba_data: &[u8],
+    blocks_buffer: &mut [u8],
+    block_width: usize,
+    block_height: usize,
+    stride: usize,
+    settings: &BC7Settings,
+) {
+    const BLOCK_BYTES: usize = 16;
+
+    if block_width == 0 {
+        return;
+    }
+
+    let compress_row = |yy: usize, row: &mut [u8]| {
+        let mut seed = None;
+        for (xx, block) in row.chunks_exact_mut(BLOCK_BYTES).enumerate() {
+            let mut block_compressor = BlockCompressorBC7::new(settings);
+            block_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
+            block_compressor.compute_opaque_err();
+            block_compressor.compress_block_bc7_core_seeded(seed);
+            block_compressor.store_data1(block);
+            seed = Some(block_compressor.data());
+        }
+    };
+
+    let rows = &mut blocks_buffer[..block_width * block_height * BLOCK_BYTES];
+
+    #[cfg(feature = "rayon")]
+    rows.par_chunks_exact_mut(block_width * BLOCK_BYTES)
+        .enumerate()
+        .for_each(|(yy, row)| compress_row(yy, row));
+
+    #[cfg(not(feature = "rayon"))]
+    for (yy, row) in rows.chunks_exact_mut(block_width * BLOCK_BYTES).enumerate() {
+        compress_row(yy, row);
+    }
+}
+
+#[cfg(all(feature = "bc15", feature = "bc7"))]
+fn compress_bc1_bc7(
+    rgba_data: &[u8],
+    bc1_blocks: &mut [u8],
+    bc7_blocks: &mut [u8],
+    block_width: usize,
+    block_height: usize,
+    stride: usize,
+    settings: &BC7Settings,
+) {
+    const BC1_BYTES: usize = CompressionVariant::BC1.block_byte_size() as usize;
+    const BC7_BYTES: usize = 16;
+
+    let compress_block = |xx: usize, yy: usize, bc1_block: &mut [u8], bc7_block: &mut [u8]| {
+        let mut bc1_compressor = BlockCompressorBC15::default();
+        bc1_compressor.load_block_interleaved_rgba(rgba_data, xx, yy, stride);
+
+        let color_result = bc1_compressor.compress_block_bc1_core(&bc1_compressor.stats(0..3));
+        BlockCompressorBC15::store_data1(bc1_block, &color_result);
+
+        let mut bc7_compressor = BlockCompressorBC7::new(settings);
+        bc7_compressor.load_block(bc1_compressor.block());
+        bc7_compressor.compute_opaque_err();
+        bc7_compressor.compress_block_bc7_core();
+        bc7_compressor.store_data1(bc7_block);
+    };
+
+    let block_count = block_width * block_height;
+
+    #[cfg(feature = "rayon")]
+    {
+        let bw = StrengthReducedUsize::new(block_width);
+
+        bc1_blocks[..(block_count * BC1_BYTES)]
+            .par_chunks_exact_mut(BC1_BYTES)
+            .zip(bc7_blocks[..(block_count * BC7_BYTES)].par_chunks_exact_mut(BC7_BYTES))
+            .enumerate()
+            .for_each(|(idx, (bc1_block, bc7_block))| {
+                let (yy, xx) = StrengthReducedUsize::div_rem(idx, bw);
+                compress_block(xx, yy, bc1_block, bc7_block);
+            });
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    for (idx, (bc1_block, bc7_block)) in bc1_blocks[..(block_count * BC1_BYTES)]
+        .chunks_exact_mut(BC1_BYTES)
+        .zip(bc7_blocks[..(block_count * BC7_BYTES)].chunks_exact_mut(BC7_BYTES))
+        .enumerate()
+    {
+        let (yy, xx) = (idx / block_width, idx % block_width);
+        compress_block(xx, yy, bc1_block, bc7_block);
+    }
+}
+
+/// Calls `compress_block` for every 16 byte block of the buffer with the block coordinates.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn compress_blocks_16<F>(
+    blocks_buffer: &mut [u8],
+    block_width: usize,
+    block_height: usize,
+    compress_block: F,
+) where
+    F: Fn(usize, usize, &mut [u8]) + Send + Sync,
+{
+    const BLOCK_BYTES: usize = 16;
+
+    #[cfg(feature = "rayon")]
+    {
+        let bw = StrengthReducedUsize::new(block_width);
+
+        blocks_buffer[..(block_width * block_height * BLOCK_BYTES)]
+            .par_chunks_exact_mut(BLOCK_BYTES)
+            .enumerate()
+            .for_each(|(idx, block)| {
+                let (yy, xx) = StrengthReducedUsize::div_rem(idx, bw);
+                compress_block(xx, yy, block);
+            });
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    for (idx, block) in blocks_buffer[..(block_width * block_height * BLOCK_BYTES)]
+        .chunks_exact_mut(BLOCK_BYTES)
+        .enumerate()
+    {
+        let (yy, xx) = (idx / block_width, idx % block_width);
+        compress_block(xx, yy, block);
+    }
+}
+
+/// Calls `compress_block` for every 16 byte block of the buffer with the block coordinates and
+/// stores the returned block error.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn compress_blocks_16_with_errors<F>(
+    blocks_buffer: &mut [u8],
+    errors_buffer: &mut [f32],
+    block_width: usize,
+    block_height: usize,
+    compress_block: F,
+) where
+    F: Fn(usize, usize, &mut [u8]) -> f32 + Send + Sync,
+{
+    compress_blocks_16_with_side(
+        blocks_buffer,
+        errors_buffer,
+        block_width,
+        block_height,
+        |xx, yy, block, error| *error = compress_block(xx, yy, block),
+    );
+}
+
+/// Calls `compress_block` for every 16 byte block of the buffer with the block coordinates and
+/// the value of the block in a side buffer, which holds one value per block.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn compress_blocks_16_with_side<T, F>(
+    blocks_buffer: &mut [u8],
+    side_buffer: &mut [T],
+    block_width: usize,
+    block_height: usize,
+    compress_block: F,
+) where
+    T: Send,
+    F: Fn(usize, usize, &mut [u8], &mut T) + Send + Sync,
+{
+    const BLOCK_BYTES: usize = 16;
+
+    let block_count = block_width * block_height;
+
+    #[cfg(feature = "rayon")]
+    {
+        let bw = StrengthReducedUsize::new(block_width);
+
+        blocks_buffer[..(block_count * BLOCK_BYTES)]
+            .par_chunks_exact_mut(BLOCK_BYTES)
+            .zip(side_buffer[..block_count].par_iter_mut())
+            .enumerate()
+            .for_each(|(idx, (block, side))| {
+                let (yy, xx) = StrengthReducedUsize::div_rem(idx, bw);
+                compress_block(xx, yy, block, side);
+            });
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    for (idx, (block, side)) in blocks_buffer[..(block_count * BLOCK_BYTES)]
+        .chunks_exact_mut(BLOCK_BYTES)
+        .zip(side_buffer[..block_count].iter_mut())
+        .enumerate()
+    {
+        let (yy, xx) = (idx / block_width, idx % block_width);
+        compress_block(xx, yy, block, side);
+    }
+}
+
+/// Decodes the compressed BC1 to BC5 blocks and stores the squared error of every block,
+/// measured on the channels the variant stores.
+#[cfg(feature = "bc15")]
+fn block_errors_bc15(
+    variation: CompressionVariant,
+    rgba_data: &[u8],
+    blocks_buffer: &[u8],
+    errors_buffer: &mut [f32],
+    block_width: usize,
+    block_height: usize,
+    stride: usize,
+) {
+    let block_bytes = variation.block_byte_size() as usize;
+    let channels = variation.channel_count() as usize;
+
+    for yy in 0..block_height {
+        for xx in 0..block_width {
+            let offset = (yy * block_width + xx) * block_bytes;
+            let block = &blocks_buffer[offset..offset + block_bytes];
+
+            let mut decoded = [0u8; 64];
+            match variation {
+                CompressionVariant::BC1 => decode_block_bc1(block, &mut decoded, 16),
+                CompressionVariant::BC2 => decode_block_bc2(block, &mut decoded, 16),
+                CompressionVariant::BC3 => decode_block_bc3(block, &mut decoded, 16),
+                CompressionVariant::BC4 => decode_block_bc4(block, &mut decoded, 16),
+                CompressionVariant::BC5 => decode_block_bc5(block, &mut decoded, 16),
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            }
+
+            let pixel_size = match variation {
+                CompressionVariant::BC4 => 1,
+                CompressionVariant::BC5 => 2,
+                _ => 4,
+            };
+
+            let mut err = 0.0;
+            for y in 0..4 {
+                for x in 0..4 {
+                    let source_offset = (yy * 4 + y) * stride + (xx * 4 + x) * 4;
+                    let decoded_offset = y * 16 + x * pixel_size;
+
+                    for channel in 0..channels {
+                        let diff = rgba_data[source_offset + channel] as f32
+                            - decoded[decoded_offset + channel] as f32;
+                        err += diff * diff;
+                    }
+                }
+            }
+
+            errors_buffer[yy * block_width + xx] = err;
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "bc15", feature = "bc6h", feature = "bc7")))]
+mod tests {
+    use super::*;
+    use crate::testgen::TestPattern;
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_find_discarded_alpha() {
+        let (width, height) = (16, 8);
+
+        let opaque = TestPattern::Gradient.generate_rgba16f(width, height, 4.0);
+        assert_eq!(
+            find_discarded_alpha(ImageView::packed(&opaque, width, height)),
+            None
+        );
+
+        let ramp = TestPattern::AlphaRamp.generate_rgba16f(width, height, 4.0);
+        let discarded = find_discarded_alpha(ImageView::packed(&ramp, width, height)).unwrap();
+        assert_eq!(
+            discarded.translucent_pixels,
+            ((width - 1) * height) as usize
+        );
+        assert_eq!(discarded.min_alpha, 0.0);
+
+        let ramp = TestPattern::AlphaRamp.generate_rgba8(width, height);
+        let image = ImageView::packed(&ramp, width, height);
+        assert_eq!(find_discarded_alpha_rgba8(image), Some(discarded));
+
+        // Only the rightmost column of the ramp is opaque.
+        assert_eq!(find_discarded_alpha_rgba8(image.crop(15, 0, 1, 8)), None);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    #[should_panic(expected = "RGBA data with 4 channels")]
+    fn test_rgba_needs_four_channels() {
+        let la_data = [0; 4 * 4 * 2];
+        let image = ImageView::packed_with_channels(&la_data, 4, 4, 2);
+
+        let mut blocks = [0; 8];
+        compress_rgba8(CompressionVariant::BC1, image, &mut blocks);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_la8_packing() {
+        use crate::decode::{decompress_bc4_as_la8, decompress_bc5_as_la8};
+        use crate::ImageViewMut;
+
+        // Not a multiple of 4, so the edge blocks are padded.
+        let (width, height) = (22, 13);
+        let rgba_data = TestPattern::Gradient.generate_rgba8(width, height);
+        let la_data: Vec<u8> = rgba_data
+            .chunks_exact(4)
+            .flat_map(|texel| [texel[0], texel[1]])
+            .collect();
+
+        let image = ImageView::packed_with_channels(&la_data, width, height, 2);
+
+        let mut bc5_blocks = vec![0; CompressionVariant::BC5.blocks_byte_size(width, height)];
+        compress_la8_bc5(image, &mut bc5_blocks);
+
+        let bc4_size = CompressionVariant::BC4.blocks_byte_size(width, height);
+        let (mut luminance_blocks, mut alpha_blocks) = (vec![0; bc4_size], vec![0; bc4_size]);
+        compress_la8_bc4(image, &mut luminance_blocks, &mut alpha_blocks);
+
+        // The alpha blocks are the same as BC4 blocks of the alpha stored in the red channel.
+        let (padded_width, padded_height) = (24, 16);
+        let alpha_as_red: Vec<u8> = (0..padded_height)
+            .flat_map(|y: u32| {
+                (0..padded_width).map(move |x: u32| (x.min(width - 1), y.min(height - 1)))
+            })
+            .flat_map(|(x, y)| [la_data[(y * width + x) as usize * 2 + 1], 0, 0, 255])
+            .collect();
+        let expected_alpha = crate::test_util::compress(
+            CompressionVariant::BC4,
+            &alpha_as_red,
+            padded_width,
+            padded_height,
+        );
+        assert_eq!(alpha_blocks, expected_alpha);
+
+        let mut from_bc5 = vec![0; la_data.len()];
+        decompress_bc5_as_la8(
+            &bc5_blocks,
+            ImageViewMut::packed_with_channels(&mut from_bc5, width, height, 2),
+        );
+        let mut from_bc4 = vec![0; la_data.len()];
+        decompress_bc4_as_la8(
+            &luminance_blocks,
+            &alpha_blocks,
+            ImageViewMut::packed_with_channels(&mut from_bc4, width, height, 2),
+        );
+        assert_eq!(from_bc5, from_bc4);
+
+        // A padded view of the same pixels compresses to the same blocks.
+        let stride = width * 2 + 6;
+        let padded_data: Vec<u8> = la_data
+            .chunks_exact(width as usize * 2)
+            .flat_map(|row| row.iter().copied().chain([0xAA; 6]))
+            .collect();
+        let mut from_padded = vec![0; bc5_blocks.len()];
+        compress_la8_bc5(
+            ImageView::with_channels(&padded_data, width, height, 2, stride),
+            &mut from_padded,
+        );
+        assert_eq!(from_padded, bc5_blocks);
+
+        let max_error = la_data
+            .iter()
+            .zip(&from_bc5)
+            .map(|(&a, &b)| a.abs_diff(b))
+            .max()
+            .unwrap();
+        assert!(max_error <= 8, "LA8 round trip error is {max_error}");
+    }
+
+    #[cfg(all(feature = "bc15", feature = "bc7"))]
+    #[test]
+    fn test_preview_tier() {
+        use crate::test_util::{decode, psnr, round_trip_psnr};
+
+        let (width, height) = (32, 32);
+        let rgba_data = TestPattern::Gradient.generate_rgba8(width, height);
+
+        assert_eq!(
+            CompressionVariant::BC7(BC7Settings::alpha_slow()).preview(),
+            CompressionVariant::BC7(BC7Settings::preview())
+        );
+
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC3,
+            CompressionVariant::BC7(BC7Settings::alpha_basic()),
+        ] {
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8_preview(
+                variant,
+                ImageView::packed(&rgba_data, width, height),
+                &mut blocks,
+            );
+
+            let preview = psnr(&rgba_data, &decode(variant, &blocks, width, height));
+            let full = round_trip_psnr(variant, &rgba_data, width, height);
+            assert!(
+                preview >= 30.0,
+                "{variant:?}: preview has a PSNR of {preview:.2} dB, expected at least 30 dB"
+            );
+            assert!(
+                preview <= full + 0.01,
+                "{variant:?}: preview ({preview:.2} dB) is better than full ({full:.2} dB)"
+            );
+        }
+    }
+
+    #[cfg(all(feature = "rayon", feature = "bc15", feature = "bc7"))]
+    #[test]
+    fn test_identical_output_across_thread_counts() {
+        let (width, height) = (64, 32);
+
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC3,
+            CompressionVariant::BC5,
+            CompressionVariant::BC7(BC7Settings::alpha_basic()),
+        ] {
+            for pattern in TestPattern::all(3) {
+                let rgba_data = pattern.generate_rgba8(width, height);
+
+                let compress = |threads: usize| {
+                    let pool = rayon::ThreadPoolBuilder::new()
+                        .num_threads(threads)
+                        .build()
+                        .unwrap();
+
+                    pool.install(|| crate::test_util::compress(variant, &rgba_data, width, height))
+                };
+
+                let expected = compress(1);
+                for threads in [1, 2, 4, 7] {
+                    assert_eq!(
+                        compress(threads),
+                        expected,
+                        "{pattern:?}: {variant:?} differs with {threads} threads"
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "bc15", feature = "bc7"))]
+    #[test]
+    fn test_bc1_bc7_tiers() {
+        let (width, height) = (32, 16);
+        let settings = BC7Settings::alpha_ultrafast();
+
+        for pattern in TestPattern::all(11) {
+            let rgba_data = pattern.generate_rgba8(width, height);
+            let image = ImageView::packed(&rgba_data, width, height);
+
+            let mut bc1_blocks = vec![0; CompressionVariant::BC1.blocks_byte_size(width, height)];
+            let mut bc7_blocks =
+                vec![0; CompressionVariant::BC7(settings).blocks_byte_size(width, height)];
+            compress_rgba8_bc1_bc7(image, &settings, &mut bc1_blocks, &mut bc7_blocks);
+
+            for (variant, tier) in [
+                (CompressionVariant::BC1, &bc1_blocks),
+                (CompressionVariant::BC7(settings), &bc7_blocks),
+            ] {
+                let expected = crate::test_util::compress(variant, &rgba_data, width, height);
+                assert_eq!(tier, &expected, "{pattern:?}: {variant:?}");
+            }
+        }
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_bc6h_hdr_remap_restores_range() {
+        use crate::{decode::decompress_blocks_as_rgba32f, ImageViewMut};
+
+        // A gradient from 1e3 to 1e7, which overflows half floats without the remapping.
+        let (width, height) = (16, 16);
+        let rgba_data: Vec<f32> = (0..width * height)
+            .flat_map(|index| {
+                let value = 1.0e3 * 10f32.powf(4.0 * index as f32 / (width * height) as f32);
+                [value, value * 0.5, value * 0.25, 1.0]
+            })
+            .collect();
+
+        let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+        let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+        let restore = compress_rgba32f_remapped(
+            variant,
+            ImageView::packed(&rgba_data, width, height),
+            HdrRemap::from_range(0.0, 1.0e7),
+            &mut blocks,
+        );
+
+        let mut decoded = vec![0.0; rgba_data.len()];
+        decompress_blocks_as_rgba32f(
+            variant,
+            &blocks,
+            ImageViewMut::packed(&mut decoded, width, height),
+        );
+
+        for (texel, original) in decoded.chunks_exact(4).zip(rgba_data.chunks_exact(4)) {
+            let restored = restore.apply(texel[0]);
+            assert!(
+                (restored - original[0]).abs() <= original[0] * 0.1 + 1.0e3,
+                "{restored} differs from {}",
+                original[0]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rgba8_passthrough() {
+        let (width, height) = (13, 7);
+        let variant = CompressionVariant::RGBA8;
+        assert_eq!(variant.bytes_per_row(width), 16 * 4);
+        assert_eq!(variant.blocks_byte_size(width, height), 16 * 8 * 4);
+
+        for pattern in TestPattern::all(3) {
+            let rgba_data = pattern.generate_rgba8(width, height);
+
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8_padded(
+                variant,
+                ImageView::packed(&rgba_data, width, height),
+                EdgeFill::SolidColor([1, 2, 3, 4]),
+                &mut blocks,
+            );
+
+            // The rows are padded to 16 texels, the missing texels are filled.
+            let row_size = width as usize * 4;
+            let row_pitch = variant.bytes_per_row(width) as usize;
+            for (y, row) in rgba_data.chunks_exact(row_size).enumerate() {
+                assert_eq!(&blocks[y * row_pitch..y * row_pitch + row_size], row);
+                assert_eq!(
+                    &blocks[y * row_pitch + row_size..(y + 1) * row_pitch],
+                    [1, 2, 3, 4].repeat(3)
+                );
+            }
+
+            let decoded = crate::test_util::decode(variant, &blocks, width, height);
+            assert_eq!(decoded, rgba_data, "{pattern:?}");
+        }
+
+        let solid = compressed_solid_color(variant, [10, 20, 30, 40], width, height);
+        assert_eq!(solid, [10, 20, 30, 40].repeat(16 * 8));
+    }
+
+    #[cfg(feature = "bc7")]
+    #[test]
+    fn test_warm_start_never_loses_quality() {
+        use crate::test_util::{compress, decode, psnr};
+
+        let (width, height) = (16, 16);
+        let variant = CompressionVariant::BC7(BC7Settings::opaque_basic());
+        let rgba_data = TestPattern::Gradient.generate_rgba8(width, height);
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        let cold = compress(variant, &rgba_data, width, height);
+        let cold_psnr = psnr(&rgba_data, &decode(variant, &cold, width, height));
+
+        // The blocks of the same frame and of an unrelated frame.
+        let unrelated = TestPattern::Noise { seed: 1 }.generate_rgba8(width, height);
+        let unrelated = compress(variant, &unrelated, width, height);
+
+        for previous_blocks in [&cold, &unrelated] {
+            let mut warm = vec![0; cold.len()];
+            compress_rgba8_with_previous(variant, image, previous_blocks, &mut warm, 0.0);
+
+            let warm_psnr = psnr(&rgba_data, &decode(variant, &warm, width, height));
+            assert!(
+                warm_psnr >= cold_psnr - 0.01,
+                "warm start loses quality: {warm_psnr} dB < {cold_psnr} dB"
+            );
+        }
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_warm_start_never_loses_quality_hdr() {
+        use crate::test_util::{compress_hdr, decode_hdr, hdr_squared_error};
+
+        let (width, height) = (16, 16);
+        let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+        let rgba_data = TestPattern::Gradient.generate_rgba16f(width, height, 4.0);
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        let cold = compress_hdr(variant, &rgba_data, width, height);
+        let cold_error = hdr_squared_error(&rgba_data, &decode_hdr(variant, &cold, width, height));
+
+        let unrelated = TestPattern::Noise { seed: 1 }.generate_rgba16f(width, height, 4.0);
+        let unrelated = compress_hdr(variant, &unrelated, width, height);
+
+        for previous_blocks in [&cold, &unrelated] {
+            let mut warm = vec![0; cold.len()];
+            compress_rgba16_with_previous(variant, image, previous_blocks, &mut warm, 0.0);
+
+            let warm_error =
+                hdr_squared_error(&rgba_data, &decode_hdr(variant, &warm, width, height));
+            assert!(
+                warm_error <= cold_error * 1.001,
+                "warm start loses quality: {warm_error} > {cold_error}"
+            );
+        }
+    }
+
+    #[cfg(all(feature = "bc15", feature = "bc7"))]
+    #[test]
+    fn test_block_errors_match_decoded_blocks() {
+        use crate::test_util::{block_squared_errors, decode};
+
+        let (width, height) = (16, 8);
+        let rgba_data = TestPattern::Gradient.generate_rgba8(width, height);
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC3,
+            CompressionVariant::BC7(BC7Settings::opaque_basic()),
+        ] {
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+            let mut errors = vec![0.0; 8];
+            compress_rgba8_with_errors(variant, image, &mut blocks, &mut errors);
+
+            let decoded = decode(variant, &blocks, width, height);
+            let expected = block_squared_errors(&rgba_data, &decoded, width, height);
+
+            for (index, (&error, &expected)) in errors.iter().zip(&expected).enumerate() {
+                assert!(
+                    (error as f64 - expected).abs() <= 1e-3 * expected.max(1.0),
+                    "{variant:?}: block {index} reports {error}, decoding gives {expected}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "bc7")]
+    #[test]
+    fn test_padded_mip_tails() {
+        use crate::mip::{generate_mip_chain_rgba8, BakeOptions, MipTail};
+
+        let (width, height) = (16, 8);
+        let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+
+        for (mip_tail, level_count) in [(MipTail::Full, 5), (MipTail::StopAt4x4, 2)] {
+            let options = BakeOptions {
+                mip_tail,
+                ..Default::default()
+            };
+
+            for pattern in TestPattern::all(3) {
+                let rgba_data = pattern.generate_rgba8(width, height);
+                let mips = generate_mip_chain_rgba8(&rgba_data, width, height, &options);
+
+                let mut mip_levels = vec![ImageView::packed(&rgba_data, width, height)];
+                mip_levels.extend(mips.iter().map(|mip| mip.view()));
+                assert_eq!(mip_levels.len(), level_count);
+
+                let size = variant.mip_chain_byte_size(width, height, mip_tail);
+                assert_eq!(
+                    size,
+                    variant.mip_blocks_offset(width, height, level_count as u32)
+                );
+
+                for edge_fill in [EdgeFill::ClampToEdge, EdgeFill::Mirror] {
+                    let mut blocks = vec![0; size];
+                    compress_rgba8_mip_range_with_edge_fill(
+                        variant,
+                        &mip_levels,
+                        0..level_count as u32,
+                        edge_fill,
+                        &mut blocks,
+                    );
+
+                    // Levels with at most 4 pixels only repeat their own pixels in the padding,
+                    // which BC7 reproduces almost exactly.
+                    for (mip_level, image) in mip_levels.iter().enumerate() {
+                        if image.width() * image.height() > 4 {
+                            continue;
+                        }
+
+                        let offset = variant.mip_blocks_offset(width, height, mip_level as u32);
+                        let decoded =
+                            crate::test_util::decode(variant, &blocks[offset..offset + 16], 4, 4);
+
+                        let row_size = image.width() as usize * 4;
+                        for (y, row) in image.data().chunks_exact(row_size).enumerate() {
+                            let decoded = &decoded[y * 16..y * 16 + row_size];
+                            for (decoded, expected) in decoded.iter().zip(row) {
+                                assert!(
+                                    decoded.abs_diff(*expected) <= 2,
+                                    "{pattern:?} {edge_fill:?}: level {mip_level} is not exact"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
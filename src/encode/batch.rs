@@ -0,0 +1,204 @@
+#[cfg(feature = "rayon")]
+use std::{collections::BTreeMap, sync::Mutex};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::compress_rgba8_padded;
+use crate::{CompressionVariant, EdgeFill, ImageView};
+
+/// A single image of a batch compressed by [`compress_batch()`].
+#[derive(Copy, Clone, Debug)]
+pub struct BatchItem<'a> {
+    /// The block compression format to use.
+    pub variant: CompressionVariant,
+    /// View into the source RGBA8 pixel data.
+    pub image: ImageView<'a, u8>,
+    /// How the blocks are filled if the size of the image is not a multiple of 4.
+    pub edge_fill: EdgeFill,
+}
+
+impl<'a> BatchItem<'a> {
+    /// Creates a batch item that fills partial blocks with [`EdgeFill::ClampToEdge`].
+    pub fn new(variant: CompressionVariant, image: ImageView<'a, u8>) -> Self {
+        Self {
+            variant,
+            image,
+            edge_fill: EdgeFill::default(),
+        }
+    }
+
+    fn compress(&self) -> Vec<u8> {
+        let mut blocks = vec![
+            0;
+            self.variant
+                .blocks_byte_size(self.image.width(), self.image.height())
+        ];
+        compress_rgba8_padded(self.variant, self.image, self.edge_fill, &mut blocks);
+        blocks
+    }
+}
+
+/// The order in which [`compress_batch_with_callback()`] delivers the compressed images.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum BatchOrder {
+    /// The images are delivered in the order of the items. Finished images are held back until
+    /// all previous images are delivered.
+    #[default]
+    Input,
+    /// The images are delivered as soon as they are finished.
+    Completion,
+}
+
+/// Compresses a batch of RGBA8 images and returns their blocks in the order of the items.
+///
+/// Asset cookers that spawn a thread per image oversubscribe the CPU, since the compression of
+/// every single image is already parallel. With the `rayon` feature, the images and their
+/// blocks are instead scheduled as tasks of a single rayon thread pool. Large images are started
+/// first, so that small images fill the gaps at the end of the batch. The pool is the one the
+/// function is called from, use `rayon::ThreadPool::install()` to run the batch on a custom
+/// pool. Without the `rayon` feature, the images are compressed one after another.
+///
+/// The images can have any size, partial blocks are filled as described by
+/// [`BatchItem::edge_fill`].
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::{compress_batch, BatchItem},
+///     BC7Settings, CompressionVariant, ImageView,
+/// };
+///
+/// let albedo = vec![0u8; 256 * 256 * 4];
+/// let icon = vec![0u8; 30 * 30 * 4];
+///
+/// let blocks = compress_batch(&[
+///     BatchItem::new(
+///         CompressionVariant::BC7(BC7Settings::alpha_ultrafast()),
+///         ImageView::packed(&albedo, 256, 256),
+///     ),
+///     BatchItem::new(CompressionVariant::BC1, ImageView::packed(&icon, 30, 30)),
+/// ]);
+///
+/// assert_eq!(blocks[1].len(), CompressionVariant::BC1.blocks_byte_size(30, 30));
+/// ```
+pub fn compress_batch(items: &[BatchItem<'_>]) -> Vec<Vec<u8>> {
+    #[cfg(feature = "rayon")]
+    {
+        let mut results = vec![Vec::new(); items.len()];
+        compress_batch_with_callback(items, BatchOrder::Completion, |index, blocks| {
+            results[index] = blocks;
+        });
+        results
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    items.iter().map(BatchItem::compress).collect()
+}
+
+/// Compresses a batch of RGBA8 images and calls `callback` with the index of the item and its
+/// blocks for every finished image.
+///
+/// This allows writing finished images to disk while the rest of the batch is still being
+/// compressed. `callback` is never called concurrently, but possibly from different threads of
+/// the thread pool. See [`compress_batch()`] for how the batch is scheduled.
+pub fn compress_batch_with_callback<F>(items: &[BatchItem<'_>], order: BatchOrder, callback: F)
+where
+    F: FnMut(usize, Vec<u8>) + Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        struct Delivery<F> {
+            next: usize,
+            finished: BTreeMap<usize, Vec<u8>>,
+            callback: F,
+        }
+
+        let mut indices: Vec<usize> = (0..items.len()).collect();
+        indices.sort_by_key(|&index| {
+            let image = &items[index].image;
+            std::cmp::Reverse(image.width() as u64 * image.height() as u64)
+        });
+
+        let delivery = Mutex::new(Delivery {
+            next: 0,
+            finished: BTreeMap::new(),
+            callback,
+        });
+
+        indices.into_par_iter().for_each(|index| {
+            let blocks = items[index].compress();
+
+            let mut delivery = delivery.lock().unwrap();
+            let delivery = &mut *delivery;
+            match order {
+                BatchOrder::Completion => (delivery.callback)(index, blocks),
+                BatchOrder::Input => {
+                    delivery.finished.insert(index, blocks);
+                    while let Some(blocks) = delivery.finished.remove(&delivery.next) {
+                        (delivery.callback)(delivery.next, blocks);
+                        delivery.next += 1;
+                    }
+                }
+            }
+        });
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        // Without a thread pool, both orders are the same.
+        let _ = order;
+        let mut callback = callback;
+        for (index, item) in items.iter().enumerate() {
+            callback(index, item.compress());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_order() {
+        let sizes = [(8, 8), (64, 64), (30, 30), (4, 4), (128, 20)];
+        let images: Vec<Vec<u8>> = sizes
+            .iter()
+            .map(|&(width, height)| {
+                (0..width * height * 4)
+                    .map(|index| (index * 7 % 251) as u8)
+                    .collect()
+            })
+            .collect();
+
+        let variant = CompressionVariant::BC3;
+
+        let items: Vec<BatchItem> = images
+            .iter()
+            .zip(sizes)
+            .map(|(image, (width, height))| {
+                BatchItem::new(variant, ImageView::packed(image, width, height))
+            })
+            .collect();
+
+        let blocks = compress_batch(&items);
+        assert_eq!(blocks.len(), items.len());
+        for (item, blocks) in items.iter().zip(&blocks) {
+            assert_eq!(blocks, &item.compress());
+        }
+
+        let mut delivered = Vec::new();
+        compress_batch_with_callback(&items, BatchOrder::Input, |index, result| {
+            assert_eq!(result, blocks[index]);
+            delivered.push(index);
+        });
+        assert_eq!(delivered, [0, 1, 2, 3, 4]);
+
+        let mut delivered = Vec::new();
+        compress_batch_with_callback(&items, BatchOrder::Completion, |index, _| {
+            delivered.push(index);
+        });
+        delivered.sort();
+        assert_eq!(delivered, [0, 1, 2, 3, 4]);
+    }
+}
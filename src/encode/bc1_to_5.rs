@@ -1,9 +1,57 @@
+use core::ops::Range;
+
+use crate::{decode::decode_block_bc1, endian::write_words_le};
+
 #[repr(transparent)]
 #[derive(Clone, Copy)]
 pub(crate) struct BlockCompressorBC15 {
     block: [f32; 64],
 }
 
+/// Statistics of a loaded block. They are computed once per block and shared by the color and
+/// the alpha pass of BC3. Channels the statistics weren't computed for keep an empty range.
+#[derive(Clone, Copy)]
+pub(crate) struct BlockStats {
+    /// Mean of the red, green and blue channel.
+    dc: [f32; 3],
+    /// Minimum of every channel.
+    min: [f32; 4],
+    /// Maximum of every channel.
+    max: [f32; 4],
+}
+
+/// Thresholds of an ordered 4x4 dither in 1/16 steps. Must match `BAYER_4X4` in the BC1 to BC5
+/// shader.
+const BAYER_4X4: [u32; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// Pixels with a lower alpha are transparent in the punch-through mode of BC1. Must match
+/// `PUNCH_THROUGH_THRESHOLD` in the BC1 to BC5 shader.
+const PUNCH_THROUGH_THRESHOLD: f32 = 128.0;
+
+/// The weight of the error of values at the pivot of a signed distance field, relative to the
+/// values far away from it. Must match `SDF_PIVOT_WEIGHT` in the BC1 to BC5 shader.
+const SDF_PIVOT_WEIGHT: f32 = 32.0;
+
+/// The distance to the pivot in 8-bit levels, at which the weight of the error has fallen off to
+/// the weight of all other values. Must match `SDF_EDGE_WIDTH` in the BC1 to BC5 shader.
+const SDF_EDGE_WIDTH: f32 = 24.0;
+
+/// The number of 8-bit levels around the fitted endpoints that are searched for the best
+/// endpoints of a signed distance field. Must match `SDF_SEARCH_RADIUS` in the BC1 to BC5 shader.
+const SDF_SEARCH_RADIUS: i32 = 4;
+
+/// A candidate of BC4 endpoints with the indices of the values and their weighted error.
+#[derive(Clone, Copy)]
+struct Bc4Fit {
+    error: f32,
+    e0: u32,
+    e1: u32,
+    qblock: [u32; 2],
+}
+
+/// Compresses the color of a block into BC1 endpoints and indices.
+pub(crate) type ColorBlockFn = fn(&BlockCompressorBC15, &BlockStats) -> [u32; 2];
+
 impl Default for BlockCompressorBC15 {
     fn default() -> Self {
         Self { block: [0.0; 64] }
@@ -39,6 +87,12 @@ impl BlockCompressorBC15 {
         }
     }
 
+    /// Returns the loaded block, with the 16 values of red, green, blue and alpha after another.
+    #[cfg(feature = "bc7")]
+    pub(crate) fn block(&self) -> &[f32; 64] {
+        &self.block
+    }
+
     pub(crate) fn load_block_r_8bit(
         &mut self,
         rgba_data: &[u8],
@@ -87,6 +141,7 @@ impl BlockCompressorBC15 {
         xx: usize,
         yy: usize,
         stride: usize,
+        dither: bool,
     ) -> [u32; 2] {
         let mut alpha_bits = [0; 2];
 
@@ -100,7 +155,12 @@ impl BlockCompressorBC15 {
                 let alpha = rgba_data[offset + 3] as f32 / 255.0;
 
                 // Convert alpha to 4 bits (0-15)
-                let alpha4 = (alpha * 15.0) as u32;
+                let alpha4 = if dither {
+                    let threshold = (BAYER_4X4[y * 4 + x] as f32 + 0.5) / 16.0;
+                    ((alpha * 15.0 + threshold) as u32).min(15)
+                } else {
+                    (alpha * 15.0) as u32
+                };
                 let bit_position = y * 16 + x * 4;
 
                 if bit_position < 32 {
@@ -124,45 +184,43 @@ impl BlockCompressorBC15 {
         data: &[u32],
     ) {
         let offset = (yy * block_width + xx) * (data.len() * 4);
-
-        // removes some bounds checks to slice it here
-        let blocks_buffer = &mut blocks_buffer[offset..];
-
-        for (index, &value) in data.iter().enumerate() {
-            let byte_offset = index * 4;
-            blocks_buffer[byte_offset..(byte_offset + 4)].copy_from_slice(&value.to_le_bytes());
-
-            // blocks_buffer[byte_offset] = value as u8;
-            // blocks_buffer[byte_offset + 1] = (value >> 8) as u8;
-            // blocks_buffer[byte_offset + 2] = (value >> 16) as u8;
-            // blocks_buffer[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(data, &mut blocks_buffer[offset..]);
     }
 
     #[allow(dead_code)]
     #[inline]
     pub(crate) fn store_data1(block: &mut [u8], data: &[u32]) {
-        for (index, &value) in data.iter().enumerate() {
-            let byte_offset = index * 4;
-
-            block[byte_offset..(byte_offset + 4)].copy_from_slice(&value.to_le_bytes());
-
-            // block[byte_offset] = value as u8;
-            // block[byte_offset + 1] = (value >> 8) as u8;
-            // block[byte_offset + 2] = (value >> 16) as u8;
-            // block[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(data, block);
     }
 
-    fn compute_covar_dc(&self, covar: &mut [f32; 6], dc: &mut [f32; 3]) {
-        for (p, value) in dc.iter_mut().enumerate() {
+    /// Computes the statistics of the given channels in a single pass over the block. BC1 and BC2
+    /// only read the color channels `0..3`, BC3 reads all four and BC4 and BC5 only the channel
+    /// `3` that their loaders fill.
+    pub(crate) fn stats(&self, channels: Range<usize>) -> BlockStats {
+        let mut stats = BlockStats {
+            dc: [0.0; 3],
+            min: [255.0; 4],
+            max: [0.0; 4],
+        };
+
+        for p in channels {
             let mut acc = 0.0;
             for k in 0..16 {
-                acc += self.block[k + p * 16];
+                let value = self.block[k + p * 16];
+                acc += value;
+                stats.min[p] = f32::min(stats.min[p], value);
+                stats.max[p] = f32::max(stats.max[p], value);
+            }
+
+            if p < 3 {
+                stats.dc[p] = acc / 16.0;
             }
-            *value = acc / 16.0;
         }
 
+        stats
+    }
+
+    fn compute_covar(&self, covar: &mut [f32; 6], dc: &[f32; 3]) {
         let mut covar0 = 0.0;
         let mut covar1 = 0.0;
         let mut covar2 = 0.0;
@@ -383,13 +441,13 @@ impl BlockCompressorBC15 {
         (qbits1 >> 1) + (qbits1 ^ (qbits0 << 1))
     }
 
-    pub(crate) fn compress_block_bc1_core(&self) -> [u32; 2] {
+    pub(crate) fn compress_block_bc1_core(&self, stats: &BlockStats) -> [u32; 2] {
         let power_iterations = 4;
         let refine_iterations = 1;
 
+        let dc = stats.dc;
         let mut covar = [0.0; 6];
-        let mut dc = [0.0; 3];
-        self.compute_covar_dc(&mut covar, &mut dc);
+        self.compute_covar(&mut covar, &dc);
 
         const EPS: f32 = f32::EPSILON;
         covar[0] += EPS;
@@ -428,15 +486,349 @@ impl BlockCompressorBC15 {
         data
     }
 
-    pub(crate) fn compress_block_bc3_alpha(&self) -> [u32; 2] {
-        let mut ep = [255.0, 0.0];
+    /// Uses the minimum and maximum of the color channels as endpoints, without refinement.
+    pub(crate) fn compress_block_bc1_preview(&self, stats: &BlockStats) -> [u32; 2] {
+        let c0 = [stats.max[0], stats.max[1], stats.max[2]];
+        let c1 = [stats.min[0], stats.min[1], stats.min[2]];
+
+        let mut p = [Self::enc_rgb565(&c0), Self::enc_rgb565(&c1)];
+        if p[0] < p[1] {
+            p.swap(0, 1);
+        }
+
+        let bits = self.fast_quant(p[0], p[1]);
+
+        [((p[1] as u32) << 16) | p[0] as u32, Self::fix_qbits(bits)]
+    }
+
+    /// Returns the RGB565 color of a gray level, which uses the full 6 bits of green.
+    const fn gray_rgb565(level: i32) -> i32 {
+        ((level >> 1) << 11) | (level << 5) | (level >> 1)
+    }
+
+    fn luminance(c: &[f32; 3]) -> f32 {
+        0.299 * c[0] + 0.587 * c[1] + 0.114 * c[2]
+    }
+
+    /// Returns the squared luminance error and the indices of the block for two gray levels.
+    fn gray_quant(luminance: &[f32; 16], level0: i32, level1: i32) -> (f32, u32) {
+        let mut c0 = [0.0; 3];
+        let mut c1 = [0.0; 3];
+        Self::dec_rgb565(&mut c0, Self::gray_rgb565(level0));
+        Self::dec_rgb565(&mut c1, Self::gray_rgb565(level1));
+
+        let l0 = Self::luminance(&c0);
+        let l1 = Self::luminance(&c1);
+        let palette = [l0, l1, (2.0 * l0 + l1) / 3.0, (l0 + 2.0 * l1) / 3.0];
+
+        let mut error = 0.0;
+        let mut bits = 0;
+        for (k, &value) in luminance.iter().enumerate() {
+            let mut best_error = f32::MAX;
+            let mut best_index = 0;
+            for (index, &entry) in palette.iter().enumerate() {
+                let entry_error = (value - entry).powi(2);
+                if entry_error < best_error {
+                    best_error = entry_error;
+                    best_index = index as u32;
+                }
+            }
+
+            error += best_error;
+            bits |= best_index << (k * 2);
+        }
+
+        (error, bits)
+    }
+
+    /// Returns the luminance of the two endpoints that fit the indices best in the least squares
+    /// sense, or `None` if the indices don't span a line.
+    fn gray_refine(luminance: &[f32; 16], bits: u32) -> Option<(f32, f32)> {
+        const WEIGHTS: [(f32, f32); 4] = [
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (2.0 / 3.0, 1.0 / 3.0),
+            (1.0 / 3.0, 2.0 / 3.0),
+        ];
+
+        let (mut aa, mut ab, mut bb, mut al, mut bl) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (k, &value) in luminance.iter().enumerate() {
+            let (a, b) = WEIGHTS[((bits >> (k * 2)) & 3) as usize];
+            aa += a * a;
+            ab += a * b;
+            bb += b * b;
+            al += a * value;
+            bl += b * value;
+        }
+
+        let det = aa * bb - ab * ab;
+        if det.abs() < 1e-6 {
+            return None;
+        }
+
+        Some(((al * bb - bl * ab) / det, (bl * aa - al * ab) / det))
+    }
+
+    /// Compresses the block with gray endpoints, only fitting the luminance of the pixels.
+    pub(crate) fn compress_block_bc1_grayscale(&self, _stats: &BlockStats) -> [u32; 2] {
+        const REFINE_ITERATIONS: usize = 2;
+
+        let mut luminance = [0.0; 16];
+        for (k, value) in luminance.iter_mut().enumerate() {
+            *value = Self::luminance(&[self.block[k], self.block[16 + k], self.block[32 + k]]);
+        }
+
+        let level = |value: f32| i32::clamp((value * 63.0 / 255.0).round() as i32, 0, 63);
+
+        // Searches the neighborhood of the two gray levels for the endpoints with the smallest
+        // error.
+        let search = |best: &mut (f32, i32, i32, u32), high: i32, low: i32, radius: i32| {
+            for level0 in (high - radius).max(0)..=(high + radius).min(63) {
+                for level1 in (low - radius).max(0)..=(low + radius).min(level0) {
+                    let (error, bits) = Self::gray_quant(&luminance, level0, level1);
+                    if error < best.0 {
+                        *best = (error, level0, level1, bits);
+                    }
+                }
+            }
+        };
+
+        let min = luminance.iter().copied().fold(255.0, f32::min);
+        let max = luminance.iter().copied().fold(0.0, f32::max);
+
+        let mut best = (f32::MAX, 0, 0, 0);
+        search(&mut best, level(max), level(min), 2);
+
+        for _ in 0..REFINE_ITERATIONS {
+            let Some((l0, l1)) = Self::gray_refine(&luminance, best.3) else {
+                break;
+            };
+            let (high, low) = (level(l0.max(l1)), level(l0.min(l1)));
+            search(&mut best, high, low, 1);
+        }
+
+        let (_, level0, level1, bits) = best;
+        let p0 = Self::gray_rgb565(level0) as u32;
+        let p1 = Self::gray_rgb565(level1) as u32;
+
+        [(p1 << 16) | p0, bits]
+    }
+
+    /// Compresses the block with the opaque or the punch-through mode of BC1, whichever has the
+    /// lower error. Blocks without transparent pixels always use the opaque mode.
+    pub(crate) fn compress_block_bc1_punch_through(&self, stats: &BlockStats) -> [u32; 2] {
+        let opaque = self.compress_block_bc1_core(stats);
+        if self.block[48..64]
+            .iter()
+            .all(|&alpha| alpha >= PUNCH_THROUGH_THRESHOLD)
+        {
+            return opaque;
+        }
+
+        let punch_through = self.compress_block_bc1_three_color();
+        if self.bc1_alpha_weighted_error(punch_through) < self.bc1_alpha_weighted_error(opaque) {
+            punch_through
+        } else {
+            opaque
+        }
+    }
+
+    /// Fits the opaque pixels to the three colors of the punch-through mode and marks all other
+    /// pixels as transparent.
+    fn compress_block_bc1_three_color(&self) -> [u32; 2] {
+        let mut mask = 0u32;
+        let mut count = 0.0;
+        let mut dc = [0.0; 3];
+        for k in 0..16 {
+            if self.block[48 + k] >= PUNCH_THROUGH_THRESHOLD {
+                mask |= 1 << k;
+                count += 1.0;
+                for (p, value) in dc.iter_mut().enumerate() {
+                    *value += self.block[k + p * 16];
+                }
+            }
+        }
+
+        if mask == 0 {
+            // Equal endpoints select the punch-through mode and every pixel is transparent.
+            return [0, u32::MAX];
+        }
+
+        for value in dc.iter_mut() {
+            *value /= count;
+        }
+
+        let mut covar = [0.0; 6];
+        for k in (0..16).filter(|k| mask & (1 << k) != 0) {
+            let rgb0 = self.block[k] - dc[0];
+            let rgb1 = self.block[k + 16] - dc[1];
+            let rgb2 = self.block[k + 32] - dc[2];
+
+            covar[0] += rgb0 * rgb0;
+            covar[1] += rgb0 * rgb1;
+            covar[2] += rgb0 * rgb2;
+            covar[3] += rgb1 * rgb1;
+            covar[4] += rgb1 * rgb2;
+            covar[5] += rgb2 * rgb2;
+        }
+
+        const EPS: f32 = f32::EPSILON;
+        covar[0] += EPS;
+        covar[3] += EPS;
+        covar[5] += EPS;
+
+        let mut axis = [0.0; 3];
+        Self::compute_axis3(&mut axis, &covar, 4);
+
+        let mut min_dot = f32::MAX;
+        let mut max_dot = f32::MIN;
+        for k in (0..16).filter(|k| mask & (1 << k) != 0) {
+            let mut dot = 0.0;
+            for p in 0..3 {
+                dot += (self.block[k + p * 16] - dc[p]) * axis[p];
+            }
+
+            min_dot = f32::min(min_dot, dot);
+            max_dot = f32::max(max_dot, dot);
+        }
+
+        if max_dot - min_dot < 1.0 {
+            min_dot -= 0.5;
+            max_dot += 0.5;
+        }
+
+        let norm_sq: f32 = axis.iter().map(|value| value * value).sum();
+        let rnorm_sq = norm_sq.recip();
+
+        let mut c0 = [0.0; 3];
+        let mut c1 = [0.0; 3];
+        for p in 0..3 {
+            c0[p] = f32::clamp(dc[p] + min_dot * rnorm_sq * axis[p], 0.0, 255.0);
+            c1[p] = f32::clamp(dc[p] + max_dot * rnorm_sq * axis[p], 0.0, 255.0);
+        }
+
+        let mut p = Self::three_color_endpoints(&c0, &c1);
+        let mut bits = self.three_color_quant(p, mask);
+
+        let (c0, c1) = self.three_color_refine(bits, mask, &dc);
+        p = Self::three_color_endpoints(&c0, &c1);
+        bits = self.three_color_quant(p, mask);
+
+        [((p[1] as u32) << 16) | p[0] as u32, bits]
+    }
+
+    /// Encodes the endpoints in the order that selects the punch-through mode.
+    fn three_color_endpoints(c0: &[f32; 3], c1: &[f32; 3]) -> [i32; 2] {
+        let p = [Self::enc_rgb565(c0), Self::enc_rgb565(c1)];
+        [p[0].min(p[1]), p[0].max(p[1])]
+    }
+
+    /// Returns the indices of the pixels in the punch-through mode. Opaque pixels are projected
+    /// on the line between the endpoints, all other pixels get the transparent index 3.
+    fn three_color_quant(&self, p: [i32; 2], mask: u32) -> u32 {
+        let mut c0 = [0.0; 3];
+        let mut c1 = [0.0; 3];
+        Self::dec_rgb565(&mut c0, p[0]);
+        Self::dec_rgb565(&mut c1, p[1]);
+
+        let mut dir = [0.0; 3];
+        for p in 0..3 {
+            dir[p] = c1[p] - c0[p];
+        }
+
+        let sq_norm: f32 = dir.iter().map(|value| value * value).sum();
+        let scale = if sq_norm > 0.0 { 2.0 / sq_norm } else { 0.0 };
+
+        // The levels 0, 1 and 2 lie at the first endpoint, the middle and the second endpoint.
+        const LEVEL_INDEX: [u32; 3] = [0, 2, 1];
 
-        // Find min/max endpoints using block[48] to block[63] for alpha
+        let mut bits = 0;
         for k in 0..16 {
-            ep[0] = f32::min(ep[0], self.block[48 + k]);
-            ep[1] = f32::max(ep[1], self.block[48 + k]);
+            let index = if mask & (1 << k) != 0 {
+                let mut dot = 0.0;
+                for p in 0..3 {
+                    dot += (self.block[k + p * 16] - c0[p]) * dir[p];
+                }
+
+                LEVEL_INDEX[i32::clamp((dot * scale + 0.5) as i32, 0, 2) as usize]
+            } else {
+                3
+            };
+
+            bits |= index << (k * 2);
         }
 
+        bits
+    }
+
+    /// Fits the endpoints to the opaque pixels with a least squares solve for the given indices.
+    fn three_color_refine(&self, bits: u32, mask: u32, dc: &[f32; 3]) -> ([f32; 3], [f32; 3]) {
+        let mut aa = 0.0;
+        let mut ab = 0.0;
+        let mut bb = 0.0;
+        let mut ax = [0.0; 3];
+        let mut bx = [0.0; 3];
+
+        for k in (0..16).filter(|k| mask & (1 << k) != 0) {
+            let weight = match (bits >> (k * 2)) & 3 {
+                0 => 0.0,
+                1 => 1.0,
+                _ => 0.5,
+            };
+
+            aa += (1.0 - weight) * (1.0 - weight);
+            ab += (1.0 - weight) * weight;
+            bb += weight * weight;
+
+            for p in 0..3 {
+                ax[p] += (1.0 - weight) * self.block[k + p * 16];
+                bx[p] += weight * self.block[k + p * 16];
+            }
+        }
+
+        let det = aa * bb - ab * ab;
+        if det.abs() < 1e-3 {
+            return (*dc, *dc);
+        }
+
+        let mut c0 = [0.0; 3];
+        let mut c1 = [0.0; 3];
+        for p in 0..3 {
+            c0[p] = f32::clamp((ax[p] * bb - bx[p] * ab) / det, 0.0, 255.0);
+            c1[p] = f32::clamp((bx[p] * aa - ax[p] * ab) / det, 0.0, 255.0);
+        }
+
+        (c0, c1)
+    }
+
+    /// Returns the squared error of the decoded BC1 block. The error of the color is weighted by
+    /// the alpha of the source, since the color of invisible pixels doesn't matter.
+    fn bc1_alpha_weighted_error(&self, data: [u32; 2]) -> f32 {
+        let mut compressed = [0; 8];
+        write_words_le(&data, &mut compressed);
+
+        let mut decoded = [0; 64];
+        decode_block_bc1(&compressed, &mut decoded, 16);
+
+        let mut error = 0.0;
+        for k in 0..16 {
+            let alpha = self.block[48 + k];
+
+            let mut color_error = 0.0;
+            for p in 0..3 {
+                color_error += (self.block[k + p * 16] - decoded[k * 4 + p] as f32).powi(2);
+            }
+
+            error += color_error * alpha / 255.0 + (alpha - decoded[k * 4 + 3] as f32).powi(2);
+        }
+
+        error
+    }
+
+    pub(crate) fn compress_block_bc3_alpha(&self, stats: &BlockStats) -> [u32; 2] {
+        // The endpoints are the min/max of block[48] to block[63], which holds the alpha
+        let mut ep = [stats.min[3], stats.max[3]];
+
         // Prevent division by zero
         if ep[0] == ep[1] {
             ep[1] = ep[0] + 0.1;
@@ -470,4 +862,345 @@ impl BlockCompressorBC15 {
 
         data
     }
+
+    /// Compresses the values of block[48] to block[63] as BC4, with the error of values close to
+    /// the `pivot` weighted higher than the error of all other values.
+    ///
+    /// The crossing of a signed distance field is where the edge of a glyph or shape ends up, so
+    /// it needs to stay in place, while the values far away from it are only used for effects
+    /// like outlines and glows.
+    pub(crate) fn compress_block_bc4_sdf(&self, pivot: f32) -> [u32; 2] {
+        let mut values = [0.0; 16];
+        values.copy_from_slice(&self.block[48..64]);
+
+        let mut weights = [0.0; 16];
+        for (weight, value) in weights.iter_mut().zip(values) {
+            *weight = Self::sdf_weight(value, pivot);
+        }
+
+        let fit = Self::compress_block_bc4_weighted(&values, &weights, SDF_SEARCH_RADIUS);
+
+        let mut data = [0; 2];
+        data[0] = fit.e0 | (fit.e1 << 8) | (fit.qblock[0] << 16);
+        data[1] = (fit.qblock[0] >> 16) | (fit.qblock[1] << 8);
+
+        data
+    }
+
+    fn sdf_weight(value: f32, pivot: f32) -> f32 {
+        let falloff = f32::max(1.0 - (value - pivot).abs() / SDF_EDGE_WIDTH, 0.0);
+        1.0 + (SDF_PIVOT_WEIGHT - 1.0) * falloff
+    }
+
+    /// Returns the weights of the first and second endpoint for an index of the eight value mode.
+    fn bc4_weights(index: u32) -> [f32; 2] {
+        match index {
+            0 => [1.0, 0.0],
+            1 => [0.0, 1.0],
+            _ => {
+                let weight = (index - 1) as f32 / 7.0;
+                [1.0 - weight, weight]
+            }
+        }
+    }
+
+    /// Assigns every value to the closest palette entry of the two 8-bit endpoints.
+    fn bc4_fit(values: &[f32; 16], weights: &[f32; 16], e0: u32, e1: u32) -> Bc4Fit {
+        let mut fit = Bc4Fit {
+            error: 0.0,
+            e0,
+            e1,
+            qblock: [0; 2],
+        };
+
+        for k in 0..16 {
+            let mut best_error = f32::MAX;
+            let mut best_index = 0;
+
+            for index in 0..8 {
+                let [w0, w1] = Self::bc4_weights(index);
+                let entry = w0 * e0 as f32 + w1 * e1 as f32;
+                let error = (values[k] - entry) * (values[k] - entry);
+
+                if error < best_error {
+                    best_error = error;
+                    best_index = index;
+                }
+            }
+
+            fit.error += weights[k] * best_error;
+            fit.qblock[k / 8] |= best_index << ((k % 8) * 3);
+        }
+
+        fit
+    }
+
+    /// Tries the 8-bit endpoints up to `radius` levels around the unquantized endpoints. The
+    /// first endpoint needs to be the larger one, or the block would be decoded in the six value
+    /// mode.
+    fn bc4_try_endpoints(
+        values: &[f32; 16],
+        weights: &[f32; 16],
+        best: &mut Bc4Fit,
+        high: f32,
+        low: f32,
+        radius: i32,
+    ) {
+        for i in 1 - radius..=radius {
+            for j in 1 - radius..=radius {
+                let e0 = f32::clamp(high.floor() + i as f32, 0.0, 255.0) as u32;
+                let e1 = f32::clamp(low.floor() + j as f32, 0.0, 255.0) as u32;
+
+                if e0 < e1 {
+                    continue;
+                }
+
+                let fit = Self::bc4_fit(values, weights, e0, e1);
+                if fit.error < best.error {
+                    *best = fit;
+                }
+            }
+        }
+    }
+
+    /// Fits the endpoints to minimize the weighted squared error of the values.
+    fn compress_block_bc4_weighted(values: &[f32; 16], weights: &[f32; 16], radius: i32) -> Bc4Fit {
+        let high = values.iter().copied().fold(0.0, f32::max);
+        let low = values.iter().copied().fold(255.0, f32::min);
+
+        let mut best = Bc4Fit {
+            error: f32::MAX,
+            e0: 0,
+            e1: 0,
+            qblock: [0; 2],
+        };
+        Self::bc4_try_endpoints(values, weights, &mut best, high, low, radius);
+
+        // Weighted least squares fit of the endpoints for the indices of the best candidate.
+        let (mut aa, mut ab, mut bb, mut av, mut bv) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for k in 0..16 {
+            let index = (best.qblock[k / 8] >> ((k % 8) * 3)) & 7;
+            let [w0, w1] = Self::bc4_weights(index);
+            let (weight, value) = (weights[k], values[k]);
+
+            aa += weight * w0 * w0;
+            ab += weight * w0 * w1;
+            bb += weight * w1 * w1;
+            av += weight * w0 * value;
+            bv += weight * w1 * value;
+        }
+
+        let det = aa * bb - ab * ab;
+        if det.abs() > 1e-6 {
+            let e0 = (av * bb - bv * ab) / det;
+            let e1 = (bv * aa - av * ab) / det;
+            Self::bc4_try_endpoints(values, weights, &mut best, e0.max(e1), e0.min(e1), radius);
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_util::{compress, decode, squared_error},
+        testgen::TestPattern,
+        CompressionVariant, ImageView,
+    };
+
+    #[test]
+    fn test_bc1_grayscale() {
+        use crate::encode::compress_rgba8_bc1_grayscale;
+
+        let (width, height) = (32, 32);
+        let variant = CompressionVariant::BC1;
+
+        for pattern in TestPattern::all(9) {
+            let rgba_data: Vec<u8> = pattern
+                .generate_rgba8(width, height)
+                .chunks_exact(4)
+                .flat_map(|texel| [texel[0], texel[0], texel[0], 255])
+                .collect();
+
+            let rgb = compress(variant, &rgba_data, width, height);
+            let mut gray = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8_bc1_grayscale(ImageView::packed(&rgba_data, width, height), &mut gray);
+
+            for block in gray.chunks_exact(8) {
+                for endpoint in [&block[0..2], &block[2..4]] {
+                    let color = u16::from_le_bytes([endpoint[0], endpoint[1]]);
+                    let (red, green, blue) = (color >> 11, (color >> 5) & 63, color & 31);
+                    assert!(red == blue && green >> 1 == red, "{color:04x} is not gray");
+                }
+            }
+
+            let error =
+                |blocks: &[u8]| squared_error(&rgba_data, &decode(variant, blocks, width, height));
+            let (rgb, gray) = (error(&rgb), error(&gray));
+            assert!(
+                gray <= rgb,
+                "{pattern:?}: grayscale error ({gray}) is worse than RGB error ({rgb})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bc4_sdf_preserves_edges() {
+        use crate::encode::compress_rgba8_bc4_sdf;
+
+        // The signed distance field of a circle, with the edge at 128.
+        let (width, height) = (64, 64);
+        let variant = CompressionVariant::BC4;
+        let rgba_data: Vec<u8> = (0..width * height)
+            .flat_map(|index| {
+                let (x, y) = ((index % width) as f32 - 31.5, (index / width) as f32 - 31.5);
+                let distance = 20.0 - (x * x + y * y).sqrt();
+                [(128.0 + distance * 12.0).clamp(0.0, 255.0) as u8, 0, 0, 255]
+            })
+            .collect();
+
+        let plain_blocks = compress(variant, &rgba_data, width, height);
+        let mut sdf_blocks = vec![0; variant.blocks_byte_size(width, height)];
+        compress_rgba8_bc4_sdf(
+            ImageView::packed(&rgba_data, width, height),
+            128,
+            &mut sdf_blocks,
+        );
+
+        // Returns the squared error close to the edge and the number of pixels on the wrong side.
+        let edge_error = |blocks: &[u8]| {
+            let decoded = decode(variant, blocks, width, height);
+
+            let mut error = 0;
+            let mut flipped = 0;
+            for (source, decoded) in rgba_data.iter().step_by(4).zip(decoded.iter().step_by(4)) {
+                if source.abs_diff(128) <= 16 {
+                    error += (source.abs_diff(*decoded) as u32).pow(2);
+                }
+                if (*source >= 128) != (*decoded >= 128) {
+                    flipped += 1;
+                }
+            }
+            (error, flipped)
+        };
+
+        let (plain_error, plain_flipped) = edge_error(&plain_blocks);
+        let (sdf_error, sdf_flipped) = edge_error(&sdf_blocks);
+        assert!(
+            sdf_error * 2 < plain_error,
+            "edge error of {sdf_error} is not below {plain_error}"
+        );
+        assert!(sdf_flipped <= plain_flipped);
+    }
+
+    #[test]
+    fn test_bc2_dithered_alpha() {
+        use crate::encode::compress_rgba8_bc2_dithered;
+
+        // A shallow alpha ramp, which shows wide bands with 16 alpha levels.
+        let (width, height) = (64, 16);
+        let variant = CompressionVariant::BC2;
+        let rgba_data: Vec<u8> = (0..width * height)
+            .flat_map(|index| [128, 64, 32, (index % width * 2 + index / width) as u8])
+            .collect();
+
+        // Error of the mean alpha of every block, which is what remains after filtering.
+        let error = |blocks: &[u8]| {
+            let decoded = decode(variant, blocks, width, height);
+
+            let mean_alpha = |data: &[u8], block_x: u32, block_y: u32| {
+                let mut sum = 0.0;
+                for y in block_y * 4..block_y * 4 + 4 {
+                    for x in block_x * 4..block_x * 4 + 4 {
+                        sum += data[((y * width + x) * 4 + 3) as usize] as f64;
+                    }
+                }
+                sum / 16.0
+            };
+
+            let mut error = 0.0;
+            for block_y in 0..height / 4 {
+                for block_x in 0..width / 4 {
+                    let diff = mean_alpha(&rgba_data, block_x, block_y)
+                        - mean_alpha(&decoded, block_x, block_y);
+                    error += diff * diff;
+                }
+            }
+            error
+        };
+
+        let truncated = compress(variant, &rgba_data, width, height);
+        let mut dithered = vec![0; variant.blocks_byte_size(width, height)];
+        compress_rgba8_bc2_dithered(ImageView::packed(&rgba_data, width, height), &mut dithered);
+
+        // Only the alpha half of the blocks is affected.
+        for (truncated, dithered) in truncated.chunks_exact(16).zip(dithered.chunks_exact(16)) {
+            assert_eq!(truncated[8..], dithered[8..]);
+        }
+
+        let (truncated, dithered) = (error(&truncated), error(&dithered));
+        assert!(
+            dithered < truncated,
+            "dithered error ({dithered}) is not lower than truncated error ({truncated})"
+        );
+    }
+
+    #[test]
+    fn test_bc1_punch_through_alpha() {
+        use crate::encode::compress_rgba8_bc1_punch_through;
+
+        // Leaves of a color gradient on a transparent background, with opaque, transparent and
+        // mixed blocks.
+        let (width, height) = (32, 32);
+        let variant = CompressionVariant::BC1;
+        let rgba_data: Vec<u8> = (0..width * height)
+            .flat_map(|index| {
+                let (x, y) = (index % width, index / width);
+                let alpha = if x < 8 || (x + y) % 3 == 0 { 255 } else { 0 };
+                [(x * 8) as u8, (y * 8) as u8, 96, alpha]
+            })
+            .collect();
+
+        // The error of the color of invisible pixels doesn't matter.
+        let error = |decoded: &[u8]| -> f64 {
+            let mut error = 0.0;
+            for (source, decoded) in rgba_data.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+                let alpha = source[3] as f64 / 255.0;
+                for channel in 0..4 {
+                    let weight = if channel < 3 { alpha } else { 1.0 };
+                    error += weight * (source[channel] as f64 - decoded[channel] as f64).powi(2);
+                }
+            }
+            error
+        };
+
+        let opaque = compress(variant, &rgba_data, width, height);
+        let mut punch_through = vec![0; variant.blocks_byte_size(width, height)];
+        compress_rgba8_bc1_punch_through(
+            ImageView::packed(&rgba_data, width, height),
+            &mut punch_through,
+        );
+
+        // The two opaque block columns are left untouched.
+        for (row, (opaque, punch_through)) in opaque
+            .chunks_exact(8 * 8)
+            .zip(punch_through.chunks_exact(8 * 8))
+            .enumerate()
+        {
+            assert_eq!(opaque[..16], punch_through[..16], "block row {row}");
+        }
+
+        let decoded = decode(variant, &punch_through, width, height);
+        for (source, decoded) in rgba_data.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+            assert_eq!(source[3], decoded[3]);
+        }
+
+        let opaque = error(&decode(variant, &opaque, width, height));
+        let punch_through = error(&decoded);
+        assert!(
+            punch_through < opaque,
+            "punch-through error ({punch_through}) is not lower than opaque error ({opaque})"
+        );
+    }
 }
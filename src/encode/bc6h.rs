@@ -1,5 +1,41 @@
 use super::common::*;
-use crate::BC6HSettings;
+use crate::{
+    color::srgb8_to_linear_f16,
+    decode::decode_block_bc6h,
+    endian::{read_words_le, write_words_le},
+    BC6HSettings, ImageView, NonFiniteHandling,
+};
+
+/// The hint of a block that is not known, for example before the first compression with
+/// [`compress_rgba16_with_hints()`](super::compress_rgba16_with_hints).
+pub const BC6H_NO_HINT: u8 = 0xFF;
+
+/// The two-region modes the search tests, in the order of their hints. Modes 2 and 6 stand for
+/// the modes of their family that favor the channel with the largest span.
+const HINT_MODES_2P: [usize; 6] = [0, 1, 2, 5, 6, 9];
+
+/// Encodes a mode of the search and the partition of two-region modes into a single byte.
+fn encode_hint(mode: usize, part_id: i32) -> u8 {
+    match HINT_MODES_2P
+        .iter()
+        .position(|&hint_mode| hint_mode == mode)
+    {
+        Some(index) => (index * 32) as u8 + part_id as u8,
+        None => (HINT_MODES_2P.len() * 32 + mode - 10) as u8,
+    }
+}
+
+/// Returns the mode of the search and the partition of a hint, or `None` for unknown hints.
+fn decode_hint(hint: u8) -> Option<(usize, i32)> {
+    let hint = hint as usize;
+    let one_region = HINT_MODES_2P.len() * 32;
+
+    match hint {
+        _ if hint < one_region => Some((HINT_MODES_2P[hint / 32], (hint % 32) as i32)),
+        _ if hint < one_region + 4 => Some((hint - one_region + 10, 0)),
+        _ => None,
+    }
+}
 
 pub(crate) struct BlockCompressorBC6H<'a> {
     block: [f32; 64],
@@ -11,21 +47,14 @@ pub(crate) struct BlockCompressorBC6H<'a> {
     max_span_idx: usize,
 
     mode: usize,
+    /// The mode that is tested, before the modes 2 and 6 are adjusted to the largest span.
+    test_mode: usize,
+    hint: u8,
     epb: u32,
     qbounds: [i32; 8],
     settings: &'a BC6HSettings,
 }
 
-#[inline(always)]
-pub fn srgb_to_linear(srgb: u8) -> f32 {
-    let v = (srgb as f32) / 255.0;
-    if v <= 0.04045 {
-        v / 12.92
-    } else {
-        ((v + 0.055) / 1.055).powf(2.4)
-    }
-}
-
 impl<'a> BlockCompressorBC6H<'a> {
     pub(crate) fn new(settings: &'a BC6HSettings) -> Self {
         Self {
@@ -36,6 +65,8 @@ impl<'a> BlockCompressorBC6H<'a> {
             max_span: 0.0,
             max_span_idx: 0,
             mode: 0,
+            test_mode: 0,
+            hint: BC6H_NO_HINT,
             epb: 0,
             qbounds: [0; 8],
             settings,
@@ -56,11 +87,9 @@ impl<'a> BlockCompressorBC6H<'a> {
 
                 let offset = pixel_y * stride + pixel_x * 4;
 
-                let red = half::f16::from_f32(srgb_to_linear(rgba_data[offset])).to_bits() as f32;
-                let green =
-                    half::f16::from_f32(srgb_to_linear(rgba_data[offset + 1])).to_bits() as f32;
-                let blue =
-                    half::f16::from_f32(srgb_to_linear(rgba_data[offset + 2])).to_bits() as f32;
+                let red = srgb8_to_linear_f16(rgba_data[offset]).to_bits() as f32;
+                let green = srgb8_to_linear_f16(rgba_data[offset + 1]).to_bits() as f32;
+                let blue = srgb8_to_linear_f16(rgba_data[offset + 2]).to_bits() as f32;
 
                 self.block[y * 4 + x] = red;
                 self.block[16 + y * 4 + x] = green;
@@ -72,11 +101,13 @@ impl<'a> BlockCompressorBC6H<'a> {
 
     pub(crate) fn load_block_interleaved_16bit(
         &mut self,
-        rgba_data: &[half::f16],
+        image: ImageView<'_, half::f16>,
         xx: usize,
         yy: usize,
-        stride: usize,
     ) {
+        let rgba_data = image.data();
+        let stride = image.stride() as usize;
+
         for y in 0..4 {
             for x in 0..4 {
                 let pixel_x = xx * 4 + x;
@@ -84,18 +115,60 @@ impl<'a> BlockCompressorBC6H<'a> {
 
                 let offset = pixel_y * stride + pixel_x * 4;
 
-                let red = rgba_data[offset].to_bits() as f32;
-                let green = rgba_data[offset + 1].to_bits() as f32;
-                let blue = rgba_data[offset + 2].to_bits() as f32;
-
-                self.block[y * 4 + x] = red;
-                self.block[16 + y * 4 + x] = green;
-                self.block[32 + y * 4 + x] = blue;
+                for channel in 0..3 {
+                    let mut value = rgba_data[offset + channel];
+                    if !value.is_finite() {
+                        value = self.replace_non_finite(image, pixel_x, pixel_y, channel);
+                    }
+                    self.block[channel * 16 + y * 4 + x] = value.to_bits() as f32;
+                }
                 self.block[48 + y * 4 + x] = 0.0;
             }
         }
     }
 
+    /// Returns the replacement of a NaN or infinite channel of the given pixel.
+    fn replace_non_finite(
+        &self,
+        image: ImageView<'_, half::f16>,
+        x: usize,
+        y: usize,
+        channel: usize,
+    ) -> half::f16 {
+        let value = image.row(y as u32)[x * 4 + channel];
+
+        match self.settings.non_finite_handling() {
+            NonFiniteHandling::Clamp if value.is_nan() => half::f16::ZERO,
+            NonFiniteHandling::Clamp if value.is_sign_negative() => half::f16::MIN,
+            NonFiniteHandling::Clamp => half::f16::MAX,
+            NonFiniteHandling::NeighborAverage => {
+                let neighbors = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                let (sum, count) = neighbors
+                    .into_iter()
+                    .filter(|&(x, y)| x < image.width() as usize && y < image.height() as usize)
+                    .map(|(x, y)| image.row(y as u32)[x * 4 + channel])
+                    .filter(|value| value.is_finite())
+                    .fold((0.0, 0), |(sum, count), value| {
+                        (sum + value.to_f32(), count + 1)
+                    });
+
+                if count == 0 {
+                    half::f16::ZERO
+                } else {
+                    half::f16::from_f32(sum / count as f32)
+                }
+            }
+            NonFiniteHandling::Reject => {
+                panic!("channel {channel} of pixel ({x}, {y}) is not finite")
+            }
+        }
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub(crate) fn store_data(
@@ -106,26 +179,22 @@ impl<'a> BlockCompressorBC6H<'a> {
         yy: usize,
     ) {
         let offset = (yy * block_width + xx) * 16;
-
-        for (index, &value) in self.data[..4].iter().enumerate() {
-            let byte_offset = offset + index * 4;
-            blocks_buffer[byte_offset] = value as u8;
-            blocks_buffer[byte_offset + 1] = (value >> 8) as u8;
-            blocks_buffer[byte_offset + 2] = (value >> 16) as u8;
-            blocks_buffer[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(&self.data[..4], &mut blocks_buffer[offset..]);
     }
 
     #[allow(dead_code)]
     #[inline]
     pub(crate) fn store_data1(&self, block: &mut [u8]) {
-        for (index, &value) in self.data[..4].iter().enumerate() {
-            let byte_offset = index * 4;
-            block[byte_offset] = value as u8;
-            block[byte_offset + 1] = (value >> 8) as u8;
-            block[byte_offset + 2] = (value >> 16) as u8;
-            block[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(&self.data[..4], block);
+    }
+
+    pub(crate) fn best_err(&self) -> f32 {
+        self.best_err
+    }
+
+    /// Returns the hint of the mode and partition of the best encoding.
+    pub(crate) fn hint(&self) -> u8 {
+        self.hint
     }
 
     fn get_mode_prefix(mode: usize) -> u32 {
@@ -315,7 +384,13 @@ impl<'a> BlockCompressorBC6H<'a> {
         let mut ep = [0.0; 24];
         for j in 0..pairs as usize {
             let mask = get_pattern_mask(part_id, j as u32);
-            block_segment_core(&mut ep[j * 8..], &self.block, mask, channels);
+            block_segment_core(
+                &mut ep[j * 8..],
+                &self.block,
+                mask,
+                channels,
+                self.settings.precise_pca(),
+            );
         }
 
         self.ep_quant_dequant_bc6h(qep, &mut ep, 2);
@@ -383,13 +458,20 @@ impl<'a> BlockCompressorBC6H<'a> {
 
         if best_err < self.best_err {
             self.best_err = best_err;
+            self.hint = encode_hint(self.test_mode, best_part_id);
             self.bc6h_code_2p(&mut best_qep, best_qblock, best_part_id, self.mode);
         }
     }
 
     fn bc6h_enc_1p(&mut self) {
         let mut ep = [0.0; 24];
-        block_segment_core(&mut ep, &self.block, 0xFFFFFFFF, 3);
+        block_segment_core(
+            &mut ep,
+            &self.block,
+            0xFFFFFFFF,
+            3,
+            self.settings.precise_pca(),
+        );
 
         let mut qep = [0; 24];
         self.ep_quant_dequant_bc6h(&mut qep, &mut ep, 1);
@@ -407,6 +489,7 @@ impl<'a> BlockCompressorBC6H<'a> {
 
         if err < self.best_err {
             self.best_err = err;
+            self.hint = encode_hint(self.test_mode, 0);
             self.bc6h_code_1p(&mut qep, &mut qblock, self.mode);
         }
     }
@@ -421,6 +504,8 @@ impl<'a> BlockCompressorBC6H<'a> {
             return;
         }
 
+        self.test_mode = mode;
+
         if mode >= 10 {
             self.epb = mode_bits;
             self.mode = mode;
@@ -820,7 +905,59 @@ impl<'a> BlockCompressorBC6H<'a> {
 
     pub(crate) fn compress_bc6h_core(&mut self) {
         self.bc6h_setup();
+        self.bc6h_search();
+    }
+
+    /// Uses a previously encoded block as the first candidate. The mode search is skipped if the
+    /// error of the previous block is not larger than `max_error`. Otherwise the search can only
+    /// improve upon the previous block.
+    pub(crate) fn compress_bc6h_core_warm(&mut self, previous: &[u8], max_error: f32) {
+        self.bc6h_setup();
 
+        let mut decoded = [half::f16::ZERO; 48];
+        decode_block_bc6h(previous, &mut decoded, 12, false);
+
+        let mut err = 0.0;
+        for k in 0..16 {
+            for p in 0..3 {
+                let value = (decoded[k * 3 + p].to_bits() as f32 / 31.0) * 64.0;
+                err += sq(value - self.block[p * 16 + k]);
+            }
+        }
+
+        read_words_le(&previous[..16], &mut self.data[..4]);
+        self.data[4] = 0;
+        self.best_err = err;
+
+        if err > max_error {
+            self.bc6h_search();
+        }
+    }
+
+    /// Encodes the block with the mode and partition of a hint first. The mode search is skipped
+    /// if the error of the hinted encoding is not larger than `max_error`. Otherwise the search
+    /// can only improve upon the hinted encoding.
+    pub(crate) fn compress_bc6h_core_hinted(&mut self, hint: u8, max_error: f32) {
+        self.bc6h_setup();
+
+        if let Some((mode, part_id)) = decode_hint(hint) {
+            self.bc6h_test_mode(mode, false, 0.0);
+
+            if mode >= 10 {
+                self.bc6h_enc_1p();
+            } else {
+                let mut part_list = [0; 32];
+                part_list[0] = part_id;
+                self.bc6h_enc_2p_list(&part_list, 1);
+            }
+        }
+
+        if self.best_err > max_error {
+            self.bc6h_search();
+        }
+    }
+
+    fn bc6h_search(&mut self) {
         if self.settings.slow_mode != 0 {
             self.bc6h_test_mode(0, true, 0.0);
             self.bc6h_test_mode(1, true, 0.0);
@@ -859,3 +996,194 @@ impl<'a> BlockCompressorBC6H<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x4 block with a diagonal edge between two colors, whose brightness grows by `frame`.
+    fn edge_block(frame: f32) -> Vec<half::f16> {
+        (0..16)
+            .flat_map(|i| {
+                let (x, y) = (i % 4, i / 4);
+                let color = if x > y {
+                    [2.0, 0.5, 0.25]
+                } else {
+                    [0.1, 0.3, 1.5]
+                };
+                let gradient = 1.0 + (x + y) as f32 * 0.05 + frame * 0.1;
+                [color[0], color[1], color[2], 1.0].map(|value| {
+                    half::f16::from_f32(if value == 1.0 { 1.0 } else { value * gradient })
+                })
+            })
+            .collect()
+    }
+
+    fn compress_block(texels: &[half::f16], hint: u8, max_error: f32) -> (f32, u8) {
+        let settings = BC6HSettings::basic();
+        let mut block_compressor = BlockCompressorBC6H::new(&settings);
+        block_compressor.load_block_interleaved_16bit(ImageView::packed(texels, 4, 4), 0, 0);
+        block_compressor.compress_bc6h_core_hinted(hint, max_error);
+        (block_compressor.best_err(), block_compressor.hint())
+    }
+
+    #[test]
+    fn test_hint_round_trip() {
+        for hint in 0..=u8::MAX {
+            match decode_hint(hint) {
+                Some((mode, part_id)) => assert_eq!(encode_hint(mode, part_id), hint),
+                None => assert!(hint >= 196),
+            }
+        }
+        assert_eq!(decode_hint(BC6H_NO_HINT), None);
+    }
+
+    #[test]
+    fn test_hinted_search() {
+        let (searched_err, hint) = compress_block(&edge_block(0.0), BC6H_NO_HINT, 0.0);
+        assert_ne!(hint, BC6H_NO_HINT);
+
+        // Without a hint the regular search runs.
+        let settings = BC6HSettings::basic();
+        let mut block_compressor = BlockCompressorBC6H::new(&settings);
+        block_compressor.load_block_interleaved_16bit(
+            ImageView::packed(&edge_block(0.0), 4, 4),
+            0,
+            0,
+        );
+        block_compressor.compress_bc6h_core();
+        assert_eq!(block_compressor.best_err(), searched_err);
+
+        // A kept hint is fitted to the next frame, and a search can only improve upon it.
+        let next_frame = edge_block(1.0);
+        let (kept_err, kept_hint) = compress_block(&next_frame, hint, f32::MAX);
+        assert_eq!(kept_hint, hint);
+        let (improved_err, _) = compress_block(&next_frame, hint, 0.0);
+        let (unhinted_err, _) = compress_block(&next_frame, BC6H_NO_HINT, 0.0);
+        assert!(improved_err <= kept_err.min(unhinted_err));
+    }
+
+    #[test]
+    fn test_non_finite_handling() {
+        use half::f16;
+
+        use crate::{
+            encode::find_non_finite, test_util::compress_hdr, testgen::TestPattern,
+            CompressionVariant,
+        };
+
+        let (width, height) = (16, 8);
+        let source = TestPattern::Gradient.generate_rgba16f(width, height, 4.0);
+        let index = |x: u32, y: u32, channel: usize| (y * width + x) as usize * 4 + channel;
+
+        let compress = |data: &[f16], handling: NonFiniteHandling| {
+            let settings = BC6HSettings::very_fast().with_non_finite_handling(handling);
+            compress_hdr(CompressionVariant::BC6H(settings), data, width, height)
+        };
+
+        let broken_channels = [(5, 2, 0), (9, 6, 1), (0, 7, 2)];
+        let mut broken = source.clone();
+        broken[index(5, 2, 0)] = f16::NAN;
+        broken[index(9, 6, 1)] = f16::INFINITY;
+        broken[index(0, 7, 2)] = f16::NEG_INFINITY;
+        assert_eq!(
+            find_non_finite(ImageView::packed(&broken, width, height)),
+            Some((5, 2))
+        );
+
+        let mut clamped = source.clone();
+        for ((x, y, channel), value) in
+            broken_channels
+                .into_iter()
+                .zip([f16::ZERO, f16::MAX, f16::MIN])
+        {
+            clamped[index(x, y, channel)] = value;
+        }
+        assert_eq!(
+            compress(&broken, NonFiniteHandling::Clamp),
+            compress(&clamped, NonFiniteHandling::Clamp)
+        );
+
+        // Neighbors outside of the image are skipped.
+        let mut averaged = source.clone();
+        for (x, y, channel) in broken_channels {
+            let neighbors: Vec<f32> = [
+                (x as i32 - 1, y as i32),
+                (x as i32 + 1, y as i32),
+                (x as i32, y as i32 - 1),
+                (x as i32, y as i32 + 1),
+            ]
+            .into_iter()
+            .filter(|&(x, y)| (0..width as i32).contains(&x) && (0..height as i32).contains(&y))
+            .map(|(x, y)| source[index(x as u32, y as u32, channel)].to_f32())
+            .collect();
+            averaged[index(x, y, channel)] =
+                f16::from_f32(neighbors.iter().sum::<f32>() / neighbors.len() as f32);
+        }
+        assert_eq!(
+            compress(&broken, NonFiniteHandling::NeighborAverage),
+            compress(&averaged, NonFiniteHandling::NeighborAverage)
+        );
+
+        assert_eq!(
+            compress(&source, NonFiniteHandling::Reject),
+            compress(&source, NonFiniteHandling::Clamp)
+        );
+        let rejected = std::panic::catch_unwind(|| compress(&broken, NonFiniteHandling::Reject));
+        assert!(rejected.is_err());
+    }
+
+    #[test]
+    fn test_precise_pca() {
+        use half::f16;
+
+        use crate::{
+            test_util::{compress_hdr, decode_hdr, hdr_squared_error},
+            testgen::SplitMix64,
+            CompressionVariant,
+        };
+
+        // Bright blocks whose values only differ by a few half float steps. The sums of their
+        // squares cancel out in single precision.
+        let (width, height) = (16, 16);
+        let mut rng = SplitMix64::new(7);
+        for base in [8000.0, 30000.0] {
+            let step = base / 1024.0;
+            let rgba_data: Vec<f16> = (0..width * height)
+                .flat_map(|index| {
+                    let (x, y) = (index % width, index / width);
+                    let t = (x % 4 + (y % 4) * 4) as f32 / 15.0;
+                    let noise = (rng.next_u64() % 3) as f32 * step;
+                    [
+                        f16::from_f32(base + t * 6.0 * step),
+                        f16::from_f32(base - t * 4.0 * step + noise),
+                        f16::from_f32(base + noise),
+                        f16::ONE,
+                    ]
+                })
+                .collect();
+
+            let error = |settings: BC6HSettings| {
+                let variant = CompressionVariant::BC6H(settings);
+                let blocks = compress_hdr(variant, &rgba_data, width, height);
+                hdr_squared_error(&rgba_data, &decode_hdr(variant, &blocks, width, height))
+            };
+
+            for settings in [BC6HSettings::very_fast(), BC6HSettings::basic()] {
+                let imprecise = error(settings);
+                let precise = error(settings.with_precise_pca(true));
+                assert!(
+                    precise <= imprecise,
+                    "{base}: {precise} is worse than {imprecise}"
+                );
+            }
+
+            // Without refinement, the endpoints follow the axis.
+            let settings = BC6HSettings::very_fast();
+            assert!(error(settings.with_precise_pca(true)) < error(settings) * 0.6);
+        }
+
+        assert!(BC6HSettings::very_slow().precise_pca());
+        assert!(!BC6HSettings::slow().precise_pca());
+    }
+}
@@ -1,5 +1,12 @@
 use super::common::*;
-use crate::BC7Settings;
+use crate::{
+    decode::decode_block_bc7,
+    endian::{read_words_le, write_words_le},
+    BC7Settings, Mode2Search,
+};
+
+/// The maximum number of mode 2 partitions that are searched with [`Mode2Search::Estimate`].
+const MODE2_ESTIMATE_PARTITIONS: usize = 8;
 
 #[derive(Default)]
 struct Mode45Parameters {
@@ -16,6 +23,11 @@ pub(crate) struct BlockCompressorBC7<'a> {
     data: [u32; 5],
     best_err: f32,
     opaque_err: f32,
+    /// The alpha of all texels of the block is 255.
+    opaque: bool,
+    weights: [f32; 4],
+    /// The color channels whose error is measured in linear space.
+    linear_channels: u32,
     settings: &'a BC7Settings,
 }
 
@@ -31,6 +43,9 @@ impl<'a> BlockCompressorBC7<'a> {
             data: [0; 5],
             best_err: f32::INFINITY,
             opaque_err: 0.0,
+            opaque: false,
+            weights: [1.0, 1.0, 1.0, settings.alpha_weight()],
+            linear_channels: if settings.linear_error() { 0b111 } else { 0 },
             settings,
         }
     }
@@ -62,6 +77,12 @@ impl<'a> BlockCompressorBC7<'a> {
         }
     }
 
+    /// Loads a block that was already converted by another compressor with the same layout.
+    #[cfg(feature = "bc15")]
+    pub(crate) fn load_block(&mut self, block: &[f32; 64]) {
+        self.block = *block;
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub(crate) fn store_data(
@@ -72,26 +93,22 @@ impl<'a> BlockCompressorBC7<'a> {
         yy: usize,
     ) {
         let offset = (yy * block_width + xx) * 16;
-
-        for (index, &value) in self.data[..4].iter().enumerate() {
-            let byte_offset = offset + index * 4;
-            blocks_buffer[byte_offset] = value as u8;
-            blocks_buffer[byte_offset + 1] = (value >> 8) as u8;
-            blocks_buffer[byte_offset + 2] = (value >> 16) as u8;
-            blocks_buffer[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(&self.data[..4], &mut blocks_buffer[offset..]);
     }
 
     #[allow(dead_code)]
     #[inline]
     pub(crate) fn store_data1(&self, block: &mut [u8]) {
-        for (index, &value) in self.data[..4].iter().enumerate() {
-            let byte_offset = index * 4;
-            block[byte_offset] = value as u8;
-            block[byte_offset + 1] = (value >> 8) as u8;
-            block[byte_offset + 2] = (value >> 16) as u8;
-            block[byte_offset + 3] = (value >> 24) as u8;
-        }
+        write_words_le(&self.data[..4], block);
+    }
+
+    pub(crate) fn best_err(&self) -> f32 {
+        self.best_err
+    }
+
+    /// Returns the words of the encoded block.
+    pub(crate) fn data(&self) -> [u32; 4] {
+        [self.data[0], self.data[1], self.data[2], self.data[3]]
     }
 
     fn unpack_to_byte(v: i32, bits: u32) -> i32 {
@@ -243,6 +260,7 @@ impl<'a> BlockCompressorBC7<'a> {
         channel_block: &[f32; 16],
         bits: u32,
         epbits: u32,
+        linear: bool,
     ) -> f32 {
         let mut ep = [255.0, 0.0];
 
@@ -252,14 +270,14 @@ impl<'a> BlockCompressorBC7<'a> {
         }
 
         Self::channel_quant_dequant(qep, &mut ep, epbits);
-        let mut err = Self::channel_opt_quant(qblock, channel_block, bits, &ep);
+        let mut err = Self::channel_opt_quant(qblock, channel_block, bits, &ep, linear);
 
         // Refine
         let refine_iterations = self.settings.refine_iterations_channel;
         for _ in 0..refine_iterations {
             Self::channel_opt_endpoints(&mut ep, channel_block, bits, *qblock);
             Self::channel_quant_dequant(qep, &mut ep, epbits);
-            err = Self::channel_opt_quant(qblock, channel_block, bits, &ep);
+            err = Self::channel_opt_quant(qblock, channel_block, bits, &ep, linear);
         }
 
         err
@@ -280,6 +298,7 @@ impl<'a> BlockCompressorBC7<'a> {
         channel_block: &[f32; 16],
         bits: u32,
         ep: &[f32; 2],
+        linear: bool,
     ) -> f32 {
         let levels = 1 << bits;
 
@@ -301,8 +320,13 @@ impl<'a> BlockCompressorBC7<'a> {
 
             let dec_v0 = (((64 - w0) * ep[0] as i32 + w0 * ep[1] as i32 + 32) / 64) as f32;
             let dec_v1 = (((64 - w1) * ep[0] as i32 + w1 * ep[1] as i32 + 32) / 64) as f32;
-            err0 += sq(dec_v0 - channel_block[k]);
-            err1 += sq(dec_v1 - channel_block[k]);
+            let weight = if linear {
+                linear_error_weight(channel_block[k])
+            } else {
+                1.0
+            };
+            err0 += weight * sq(dec_v0 - channel_block[k]);
+            err1 += weight * sq(dec_v1 - channel_block[k]);
 
             let best_err = if err0 < err1 { err0 } else { err1 };
 
@@ -368,8 +392,14 @@ impl<'a> BlockCompressorBC7<'a> {
         }
     }
 
-    pub(crate) fn block_segment(ep: &mut [f32], block: &[f32; 64], mask: u32, channels: usize) {
-        block_segment_core(ep, block, mask, channels);
+    pub(crate) fn block_segment(
+        ep: &mut [f32],
+        block: &[f32; 64],
+        mask: u32,
+        channels: usize,
+        precise: bool,
+    ) {
+        block_segment_core(ep, block, mask, channels, precise);
 
         for i in 0..2 {
             for p in 0..channels {
@@ -529,12 +559,27 @@ impl<'a> BlockCompressorBC7<'a> {
         let mut ep = [0.0; 24];
         for j in 0..pairs {
             let mask = get_pattern_mask(part_id, j as u32);
-            Self::block_segment(&mut ep[j * 8..], &self.block, mask, channels);
+            Self::block_segment(
+                &mut ep[j * 8..],
+                &self.block,
+                mask,
+                channels,
+                self.settings.precise_pca(),
+            );
         }
 
         Self::ep_quant_dequant(qep, &mut ep, mode, channels);
 
-        block_quant(qblock, &self.block, bits, &ep, pattern, channels)
+        block_quant_weighted(
+            qblock,
+            &self.block,
+            bits,
+            &ep,
+            pattern,
+            channels,
+            &self.weights,
+            self.linear_channels,
+        )
     }
 
     fn bc7_enc_mode01237(&mut self, mode: usize, part_list: &[i32; 64], part_count: usize) {
@@ -589,7 +634,16 @@ impl<'a> BlockCompressorBC7<'a> {
             Self::ep_quant_dequant(&mut qep, &mut ep, mode, channels);
 
             let pattern = get_pattern(best_part_id);
-            let err = block_quant(&mut qblock, &self.block, bits, &ep, pattern, channels);
+            let err = block_quant_weighted(
+                &mut qblock,
+                &self.block,
+                bits,
+                &ep,
+                pattern,
+                channels,
+                &self.weights,
+                self.linear_channels,
+            );
 
             if err < best_err {
                 best_qep[..(8 * pairs)].copy_from_slice(&qep[..(8 * pairs)]);
@@ -614,11 +668,34 @@ impl<'a> BlockCompressorBC7<'a> {
 
         self.bc7_enc_mode01237(0, &part_list, 16);
 
-        if self.settings.skip_mode2 == 0 {
+        if self.settings.skip_mode2 == Mode2Search::FULL {
             self.bc7_enc_mode01237(2, &part_list, 64);
         }
     }
 
+    /// Only searches the mode 2 partitions whose estimated error can beat the best error.
+    fn bc7_enc_mode2_estimate(&mut self) {
+        let mut part_list = [0; 64];
+        let mut part_count = 0;
+
+        for part in 0..64 {
+            let mut bound = self.opaque_err;
+            for j in 0..3 {
+                let mask = get_pattern_mask(part + 64, j);
+                bound += block_pca_residual(&self.block, mask, 3);
+            }
+
+            if bound < self.best_err {
+                part_list[part_count] = part + bound as i32 * 64;
+                part_count += 1;
+            }
+        }
+
+        let search_count = usize::min(part_count, MODE2_ESTIMATE_PARTITIONS);
+        partial_sort_list(&mut part_list, part_count, search_count as u32);
+        self.bc7_enc_mode01237(2, &part_list, search_count);
+    }
+
     fn bc7_enc_mode13(&mut self) {
         if self.settings.fast_skip_threshold_mode1 == 0
             && self.settings.fast_skip_threshold_mode3 == 0
@@ -696,20 +773,54 @@ impl<'a> BlockCompressorBC7<'a> {
         }
 
         let mut ep = [0.0; 8];
-        Self::block_segment(&mut ep, &candidate_block, 0xFFFFFFFF, 3);
+        Self::block_segment(
+            &mut ep,
+            &candidate_block,
+            0xFFFFFFFF,
+            3,
+            self.settings.precise_pca(),
+        );
 
         let mut qep = [0; 8];
         Self::ep_quant_dequant(&mut qep, &mut ep, mode, 3);
 
+        // The rotated channel takes the place of alpha, so it also takes its weight. Alpha is
+        // not sRGB encoded, so its error is never measured in linear space.
+        let mut weights = self.weights;
+        let mut linear_channels = self.linear_channels;
+        if rotation < 3 {
+            weights.swap(rotation as usize, 3);
+            linear_channels &= !(1 << rotation);
+        }
+        let channel_linear = linear_channels != self.linear_channels;
+
         let mut qblock = [0; 2];
-        let mut err = block_quant(&mut qblock, &candidate_block, bits, &ep, 0, 3);
+        let mut err = block_quant_weighted(
+            &mut qblock,
+            &candidate_block,
+            bits,
+            &ep,
+            0,
+            3,
+            &weights,
+            linear_channels,
+        );
 
         // Refine
         let refine_iterations = self.settings.refine_iterations[mode];
         for _ in 0..refine_iterations {
             opt_endpoints(&mut ep, &candidate_block, bits, qblock, 0xFFFFFFFF, 3);
             Self::ep_quant_dequant(&mut qep, &mut ep, mode, 3);
-            err = block_quant(&mut qblock, &candidate_block, bits, &ep, 0, 3);
+            err = block_quant_weighted(
+                &mut qblock,
+                &candidate_block,
+                bits,
+                &ep,
+                0,
+                3,
+                &weights,
+                linear_channels,
+            );
         }
 
         let channel_data: [f32; 16] =
@@ -719,7 +830,15 @@ impl<'a> BlockCompressorBC7<'a> {
         let mut aqep = [0; 2];
         let mut aqblock = [0; 2];
 
-        err += self.opt_channel(&mut aqblock, &mut aqep, &channel_data, abits, aepbits);
+        err += self.weights[rotation as usize]
+            * self.opt_channel(
+                &mut aqblock,
+                &mut aqep,
+                &channel_data,
+                abits,
+                aepbits,
+                channel_linear,
+            );
 
         if err < *best_err {
             best_candidate.qep.copy_from_slice(&qep[..8]);
@@ -736,8 +855,8 @@ impl<'a> BlockCompressorBC7<'a> {
         let mut best_candidate = Mode45Parameters::default();
         let mut best_err = self.best_err;
 
-        let channel0 = self.settings.mode45_channel0;
-        for p in channel0..self.settings.channels {
+        let channels = self.settings.mode45_channel0..self.settings.mode45_channel1;
+        for p in channels.clone() {
             self.bc7_enc_mode45_candidate(&mut best_candidate, &mut best_err, 4, p, 0);
             self.bc7_enc_mode45_candidate(&mut best_candidate, &mut best_err, 4, p, 1);
         }
@@ -748,7 +867,7 @@ impl<'a> BlockCompressorBC7<'a> {
             self.bc7_code_mode45(&best_candidate, 4);
         }
 
-        for p in channel0..self.settings.channels {
+        for p in channels {
             self.bc7_enc_mode45_candidate(&mut best_candidate, &mut best_err, 5, p, 0);
         }
 
@@ -769,6 +888,7 @@ impl<'a> BlockCompressorBC7<'a> {
             &self.block,
             0xFFFFFFFF,
             self.settings.channels as usize,
+            self.settings.precise_pca(),
         );
 
         if self.settings.channels == 3 {
@@ -780,13 +900,15 @@ impl<'a> BlockCompressorBC7<'a> {
         Self::ep_quant_dequant(&mut qep, &mut ep, MODE, self.settings.channels as usize);
 
         let mut qblock = [0; 2];
-        let mut err = block_quant(
+        let mut err = block_quant_weighted(
             &mut qblock,
             &self.block,
             BITS,
             &ep,
             0,
             self.settings.channels as usize,
+            &self.weights,
+            self.linear_channels,
         );
 
         let refine_iterations = self.settings.refine_iterations[MODE];
@@ -800,13 +922,15 @@ impl<'a> BlockCompressorBC7<'a> {
                 self.settings.channels as usize,
             );
             Self::ep_quant_dequant(&mut qep, &mut ep, MODE, self.settings.channels as usize);
-            err = block_quant(
+            err = block_quant_weighted(
                 &mut qblock,
                 &self.block,
                 BITS,
                 &ep,
                 0,
                 self.settings.channels as usize,
+                &self.weights,
+                self.linear_channels,
             );
         }
 
@@ -850,20 +974,105 @@ impl<'a> BlockCompressorBC7<'a> {
         );
     }
 
+    /// Fits the block with the mode and partition or rotation of the block that starts with the
+    /// word `seed`.
+    fn bc7_enc_seed(&mut self, seed: u32, alpha_modes: bool) {
+        let mode = (seed & 0xFF).trailing_zeros() as usize;
+
+        match mode {
+            0..=3 | 7 if mode != 7 || alpha_modes => {
+                let (shift, bits) = match mode {
+                    0 => (1, 4),
+                    7 => (8, 6),
+                    _ => (mode + 1, 6),
+                };
+                let mut part_list = [0; 64];
+                part_list[0] = ((seed >> shift) & ((1 << bits) - 1)) as i32;
+                self.bc7_enc_mode01237(mode, &part_list, 1);
+            }
+            4 | 5 if alpha_modes => {
+                // The rotation is stored as the channel that is swapped with alpha plus one.
+                let rotation = ((seed >> (mode + 1)) + 3) & 3;
+                let swap = if mode == 4 { (seed >> 7) & 1 } else { 0 };
+
+                let mut candidate = Mode45Parameters::default();
+                let mut best_err = self.best_err;
+                self.bc7_enc_mode45_candidate(&mut candidate, &mut best_err, mode, rotation, swap);
+
+                if best_err < self.best_err {
+                    self.best_err = best_err;
+                    self.bc7_code_mode45(&candidate, mode);
+                }
+            }
+            6 => self.bc7_enc_mode6(),
+            _ => {}
+        }
+    }
+
+    /// Starts the search with the mode of `seed`, the block to the left, if the settings enable
+    /// the neighbor seeding. The full search is skipped if the seeded block is good enough.
+    pub(crate) fn compress_block_bc7_core_seeded(&mut self, seed: Option<[u32; 4]>) {
+        if let (Some(seed), Some(max_error)) = (seed, self.settings.neighbor_seeding()) {
+            self.bc7_enc_seed(seed[0], self.settings.channels == 3 || !self.opaque);
+            if self.best_err <= max_error {
+                return;
+            }
+        }
+
+        self.compress_block_bc7_core();
+    }
+
     pub(crate) fn compress_block_bc7_core(&mut self) {
+        // Opaque blocks of an alpha preset skip the modes that spend bits on separate alpha.
+        let alpha_modes = self.settings.channels == 3 || !self.opaque;
+
         if self.settings.mode_selection[0] != 0 {
             self.bc7_enc_mode02();
         }
         if self.settings.mode_selection[1] != 0 {
             self.bc7_enc_mode13();
-            self.bc7_enc_mode7();
+            if alpha_modes {
+                self.bc7_enc_mode7();
+            }
         }
-        if self.settings.mode_selection[2] != 0 {
+        if self.settings.mode_selection[2] != 0 && alpha_modes {
             self.bc7_enc_mode45();
         }
         if self.settings.mode_selection[3] != 0 {
             self.bc7_enc_mode6();
         }
+        if self.settings.mode_selection[0] != 0 && self.settings.skip_mode2 == Mode2Search::ESTIMATE
+        {
+            self.bc7_enc_mode2_estimate();
+        }
+    }
+
+    /// Uses a previously encoded block as the first candidate. The mode search is skipped if the
+    /// error of the previous block is not larger than `max_error`. Otherwise the search can only
+    /// improve upon the previous block.
+    pub(crate) fn compress_block_bc7_core_warm(&mut self, previous: &[u8], max_error: f32) {
+        let mut decoded = [0u8; 64];
+        decode_block_bc7(previous, &mut decoded, 16);
+
+        let channels = self.settings.channels as usize;
+        let mut err = 0.0;
+        for k in 0..16 {
+            for p in 0..channels {
+                let mut weight = self.weights[p];
+                if self.linear_channels & (1 << p) != 0 {
+                    weight *= linear_error_weight(self.block[p * 16 + k]);
+                }
+                err += weight * sq(decoded[k * 4 + p] as f32 - self.block[p * 16 + k]);
+            }
+        }
+
+        read_words_le(&previous[..16], &mut self.data[..4]);
+        self.data[4] = 0;
+        self.best_err = err;
+
+        if err > max_error {
+            self.compress_block_bc7_core();
+        }
     }
 
     pub(crate) fn compute_opaque_err(&mut self) {
@@ -874,7 +1083,286 @@ impl<'a> BlockCompressorBC7<'a> {
             for k in 0..16 {
                 err += sq(self.block[48 + k] - 255.0);
             }
-            err
+            self.opaque = err == 0.0;
+            self.weights[3] * err
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compress_block(settings: &BC7Settings, alpha: u8) -> [u8; 16] {
+        let rgba_data: Vec<u8> = (0..16u32)
+            .flat_map(|i| [(i * 17) as u8, (255 - i * 13) as u8, (i * i) as u8, alpha])
+            .collect();
+
+        let mut block_compressor = BlockCompressorBC7::new(settings);
+        block_compressor.load_block_interleaved_rgba(&rgba_data, 0, 0, 16);
+        block_compressor.compute_opaque_err();
+        block_compressor.compress_block_bc7_core();
+
+        let mut block = [0; 16];
+        block_compressor.store_data1(&mut block);
+        block
+    }
+
+    #[test]
+    fn test_neighbor_seeding() {
+        use crate::{
+            test_util::{compress, decode, squared_error},
+            CompressionVariant,
+        };
+
+        let (width, height) = (32, 16);
+        let rgba_data = crate::testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+        let round_trip_error = |settings: BC7Settings| {
+            let variant = CompressionVariant::BC7(settings);
+            let blocks = compress(variant, &rgba_data, width, height);
+            squared_error(&rgba_data, &decode(variant, &blocks, width, height))
+        };
+
+        // Every seeded block is kept, so all blocks of a row share the mode of the first one.
+        let settings = BC7Settings::alpha_basic().with_neighbor_seeding(Some(f32::MAX));
+        let blocks = compress(CompressionVariant::BC7(settings), &rgba_data, width, height);
+        for row in blocks.chunks_exact(16 * width as usize / 4) {
+            let mode = row[0].trailing_zeros();
+            assert!(row
+                .chunks_exact(16)
+                .all(|block| block[0].trailing_zeros() == mode));
+        }
+
+        // Only perfect seeds skip the search, which can only improve upon them.
+        let unseeded = round_trip_error(BC7Settings::alpha_basic());
+        let seeded = round_trip_error(BC7Settings::alpha_basic().with_neighbor_seeding(Some(0.0)));
+        assert!(seeded <= unseeded * 1.05, "{seeded} > {unseeded}");
+    }
+
+    #[test]
+    fn test_opaque_blocks_skip_alpha_modes() {
+        let settings = BC7Settings::alpha_slow();
+        let mode = |block: [u8; 16]| block[0].trailing_zeros();
+
+        let opaque = compress_block(&settings, 255);
+        assert!(![4, 5, 7].contains(&mode(opaque)), "mode {}", mode(opaque));
+
+        let mut decoded = [0; 64];
+        decode_block_bc7(&opaque, &mut decoded, 16);
+        assert!(decoded.chunks_exact(4).all(|texel| texel[3] == 255));
+
+        // A single texel that isn't fully opaque allows all modes again.
+        let mut settings = settings;
+        settings.mode_selection = [0, 0, 1, 0];
+        assert!([4, 5].contains(&mode(compress_block(&settings, 254))));
+    }
+
+    #[test]
+    fn test_psnr_statistics() {
+        use crate::{test_util::round_trip_psnr, testgen::TestPattern, CompressionVariant};
+
+        let psnr = |pattern: TestPattern, settings: BC7Settings| {
+            let rgba_data = pattern.generate_rgba8(32, 32);
+            round_trip_psnr(CompressionVariant::BC7(settings), &rgba_data, 32, 32)
+        };
+
+        let expectations = [
+            (TestPattern::Gradient, BC7Settings::opaque_basic(), 40.0),
+            (
+                TestPattern::Checkerboard { cell_size: 4 },
+                BC7Settings::opaque_ultra_fast(),
+                50.0,
+            ),
+            (TestPattern::AlphaRamp, BC7Settings::alpha_basic(), 40.0),
+            (
+                TestPattern::Noise { seed: 3 },
+                BC7Settings::alpha_basic(),
+                15.0,
+            ),
+        ];
+
+        for (pattern, settings, min_psnr) in expectations {
+            let psnr = psnr(pattern, settings);
+            assert!(
+                psnr >= min_psnr,
+                "{pattern:?} has a PSNR of {psnr:.2} dB, expected at least {min_psnr} dB"
+            );
+        }
+
+        let fast = psnr(
+            TestPattern::Noise { seed: 3 },
+            BC7Settings::alpha_ultrafast(),
+        );
+        let slow = psnr(TestPattern::Noise { seed: 3 }, BC7Settings::alpha_slow());
+        assert!(
+            slow >= fast,
+            "slow ({slow:.2} dB) is worse than fast ({fast:.2} dB)"
+        );
+    }
+
+    #[test]
+    fn test_alpha_weight() {
+        use crate::{
+            test_util::{compress, decode},
+            testgen::TestPattern,
+            CompressionVariant,
+        };
+
+        let alpha_error = |pattern: TestPattern, alpha_weight: f32| -> f64 {
+            let (width, height) = (32, 32);
+            let settings = BC7Settings::alpha_basic().with_alpha_weight(alpha_weight);
+            let variant = CompressionVariant::BC7(settings);
+            let rgba_data = pattern.generate_rgba8(width, height);
+
+            let blocks = compress(variant, &rgba_data, width, height);
+            let decoded = decode(variant, &blocks, width, height);
+
+            rgba_data
+                .chunks_exact(4)
+                .zip(decoded.chunks_exact(4))
+                .map(|(a, b)| (a[3] as f64 - b[3] as f64).powi(2))
+                .sum()
+        };
+
+        for pattern in [TestPattern::Noise { seed: 11 }, TestPattern::AlphaRamp] {
+            let neutral = alpha_error(pattern, 1.0);
+            let weighted = alpha_error(pattern, 8.0);
+            assert!(
+                weighted <= neutral,
+                "{pattern:?}: weighted alpha error ({weighted}) is worse than neutral ({neutral})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_linear_error() {
+        use crate::{
+            test_util::{compress, decode},
+            testgen::TestPattern,
+            CompressionVariant,
+        };
+
+        fn to_linear(value: u8) -> f64 {
+            let value = value as f64 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        }
+
+        // Returns the squared error of the color channels in linear and in sRGB space.
+        let errors = |rgba_data: &[u8], width: u32, height: u32, linear_error: bool| {
+            let settings = BC7Settings::opaque_basic().with_linear_error(linear_error);
+            let variant = CompressionVariant::BC7(settings);
+
+            let blocks = compress(variant, rgba_data, width, height);
+            let decoded = decode(variant, &blocks, width, height);
+
+            rgba_data
+                .chunks_exact(4)
+                .zip(decoded.chunks_exact(4))
+                .flat_map(|(a, b)| (0..3).map(move |channel| (a[channel], b[channel])))
+                .fold((0.0, 0.0), |(linear, srgb), (a, b)| {
+                    (
+                        linear + (to_linear(a) - to_linear(b)).powi(2),
+                        srgb + (a as f64 - b as f64).powi(2),
+                    )
+                })
+        };
+
+        // Noise with dark and bright blocks, which need to share the precision.
+        let (width, height) = (64, 64);
+        let rgba_data: Vec<u8> = TestPattern::Noise { seed: 5 }
+            .generate_rgba8(width, height)
+            .chunks_exact(4)
+            .enumerate()
+            .flat_map(|(index, texel)| {
+                let bright = (index as u32 % width / 4 + index as u32 / width / 4) % 2 == 1;
+                let scale = |value: u8| if bright { 192 + value / 4 } else { value / 4 };
+                [scale(texel[0]), scale(texel[1]), scale(texel[2]), 255]
+            })
+            .collect();
+
+        let (default_linear, default_srgb) = errors(&rgba_data, width, height, false);
+        let (linear_linear, linear_srgb) = errors(&rgba_data, width, height, true);
+
+        assert!(
+            linear_linear < default_linear,
+            "linear error ({linear_linear}) is not lower than the default ({default_linear})"
+        );
+        assert!(
+            default_srgb < linear_srgb,
+            "sRGB error ({default_srgb}) is not lower than with linear error ({linear_srgb})"
+        );
+    }
+
+    #[test]
+    fn test_mode2_estimate() {
+        use crate::{
+            encode::compress_rgba8_with_errors, testgen::TestPattern, CompressionVariant, ImageView,
+        };
+
+        let total_error = |pattern: TestPattern, search: Mode2Search| -> f32 {
+            let (width, height) = (32, 32);
+            let settings = BC7Settings::opaque_slow().with_mode2_search(search);
+            let variant = CompressionVariant::BC7(settings);
+            let rgba_data = pattern.generate_rgba8(width, height);
+
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+            let mut errors = vec![0.0; (width * height / 16) as usize];
+            compress_rgba8_with_errors(
+                variant,
+                ImageView::packed(&rgba_data, width, height),
+                &mut blocks,
+                &mut errors,
+            );
+            errors.iter().sum()
+        };
+
+        for pattern in TestPattern::all(5) {
+            let full = total_error(pattern, Mode2Search::Full);
+            let estimate = total_error(pattern, Mode2Search::Estimate);
+            let skip = total_error(pattern, Mode2Search::Skip);
+
+            assert!(
+                estimate <= skip,
+                "{pattern:?}: estimate ({estimate}) is worse than skip ({skip})"
+            );
+            assert!(
+                estimate <= full * 1.01,
+                "{pattern:?}: estimate ({estimate}) is worse than full ({full})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_mode45_scalar_channel() {
+        use crate::{
+            test_util::compress, testgen::TestPattern, CompressionVariant, Mode45ScalarChannel,
         };
+
+        let (width, height) = (32, 32);
+        let settings =
+            BC7Settings::alpha_basic().with_mode45_scalar_channel(Mode45ScalarChannel::Green);
+        let variant = CompressionVariant::BC7(settings);
+
+        let mut mode45_blocks = 0;
+        for pattern in TestPattern::all(7) {
+            let rgba_data = pattern.generate_rgba8(width, height);
+            let blocks = compress(variant, &rgba_data, width, height);
+
+            for block in blocks.chunks_exact(16) {
+                let mode = block[0].trailing_zeros();
+                if mode == 4 || mode == 5 {
+                    // Rotation 2 swaps alpha and green.
+                    let rotation = (block[0] >> (mode + 1)) & 3;
+                    assert_eq!(rotation, 2, "{pattern:?}");
+                    mode45_blocks += 1;
+                }
+            }
+        }
+
+        assert!(mode45_blocks > 0);
     }
 }
@@ -0,0 +1,150 @@
+//! Low-level bit packing of BC6H and BC7 blocks.
+//!
+//! The encoders assemble a block in five little-endian 32-bit words. The first four words hold
+//! the 128 bits of the block, the fifth word receives the bits that overflow while the indices
+//! are written with their full width, before the implicit most significant bit of the anchor
+//! indices is removed again with [`data_shl_1bit_from()`].
+//!
+//! This module is meant for prototyping custom packing strategies. It is only public with the
+//! `unstable` feature and is not covered by semver guarantees.
+//!
+//! # Example
+//!
+#![cfg_attr(all(feature = "unstable", feature = "bc7"), doc = "```")]
+#![cfg_attr(not(all(feature = "unstable", feature = "bc7")), doc = "```ignore")]
+//! use block_compression::{decode::decode_block_bc7, encode::bitstream::BlockWriter};
+//!
+//! // An opaque white BC7 mode 6 block.
+//! let mut writer = BlockWriter::new();
+//! writer.put_bits(7, 1 << 6);
+//! for _ in 0..8 {
+//!     writer.put_bits(7, 0x7F);
+//! }
+//! writer.put_bits(1, 1);
+//! writer.put_bits(1, 1);
+//! writer.put_bits(3, 0);
+//! for _ in 1..16 {
+//!     writer.put_bits(4, 0);
+//! }
+//! assert_eq!(writer.position(), 128);
+//!
+//! let mut pixels = [0; 64];
+//! decode_block_bc7(&writer.to_bytes(), &mut pixels, 16);
+//! assert!(pixels.iter().all(|&value| value == 255));
+//! ```
+
+use crate::endian::write_words_le;
+
+/// Writes the lowest `bits` bits of `value` at the bit position `pos` and advances `pos`.
+///
+/// `value` must not have any bits set above `bits`, since it is combined with the existing data
+/// without masking.
+///
+/// # Panics
+/// - If the bits don't fit into the five words of `data`
+#[inline]
+pub fn put_bits(data: &mut [u32; 5], pos: &mut u32, bits: u32, value: u32) {
+    data[(*pos / 32) as usize] |= value << (*pos % 32);
+    if *pos % 32 + bits > 32 {
+        data[(*pos / 32 + 1) as usize] |= value >> (32 - *pos % 32);
+    }
+    *pos += bits;
+}
+
+/// Removes the bit at position `from_bits - 1` by shifting all following bits one position
+/// towards the start of the block.
+///
+/// Only bits in the third and fourth word can be removed, which is where the anchor indices of
+/// BC6H and BC7 blocks are located. Nothing happens for `from_bits` of 128 or more.
+///
+/// `from_bits` must be at least 64. Smaller values overflow the mask computation, which panics
+/// in debug builds and corrupts the block in release builds.
+#[inline]
+pub fn data_shl_1bit_from(data: &mut [u32; 5], from_bits: usize) {
+    if from_bits < 96 {
+        let shifted = (data[2] >> 1) | (data[3] << 31);
+        let mask = ((1 << (from_bits - 64)) - 1) >> 1;
+        data[2] = (mask & data[2]) | (!mask & shifted);
+        data[3] = (data[3] >> 1) | (data[4] << 31);
+        data[4] >>= 1;
+    } else if from_bits < 128 {
+        let shifted = (data[3] >> 1) | (data[4] << 31);
+        let mask = ((1 << (from_bits - 96)) - 1) >> 1;
+        data[3] = (mask & data[3]) | (!mask & shifted);
+        data[4] >>= 1;
+    }
+}
+
+/// Assembles a single block with [`put_bits()`] and [`data_shl_1bit_from()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockWriter {
+    data: [u32; 5],
+    position: u32,
+}
+
+// Without the `unstable` feature only the block encoders of this crate use the writer.
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+impl BlockWriter {
+    /// Creates a writer for an empty block.
+    pub const fn new() -> Self {
+        Self {
+            data: [0; 5],
+            position: 0,
+        }
+    }
+
+    /// Appends the lowest `bits` bits of `value`. See [`put_bits()`].
+    #[inline]
+    pub fn put_bits(&mut self, bits: u32, value: u32) {
+        put_bits(&mut self.data, &mut self.position, bits, value);
+    }
+
+    /// Removes the bit at position `from_bits - 1` and moves the write position back by one.
+    /// See [`data_shl_1bit_from()`].
+    pub fn shl_1bit_from(&mut self, from_bits: usize) {
+        data_shl_1bit_from(&mut self.data, from_bits);
+        self.position -= 1;
+    }
+
+    /// Returns the number of bits written so far.
+    pub const fn position(&self) -> u32 {
+        self.position
+    }
+
+    /// Returns the words of the block, including the overflow word.
+    pub const fn data(&self) -> &[u32; 5] {
+        &self.data
+    }
+
+    /// Returns the 16 bytes of the block. Bits written past the first 128 bits are dropped.
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        write_words_le(&self.data[..4], &mut bytes);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_writer() {
+        let mut writer = BlockWriter::new();
+        writer.put_bits(30, 0x2AAA_AAAA);
+        writer.put_bits(4, 0b1111);
+        assert_eq!(writer.position(), 34);
+        assert_eq!(writer.data()[..2], [0xEAAA_AAAA, 0b11]);
+
+        // Removing the implicit bit of an anchor index that was written with its full width.
+        let mut writer = BlockWriter::new();
+        writer.put_bits(32, 0);
+        writer.put_bits(32, 0);
+        writer.put_bits(3, 0b011);
+        writer.put_bits(2, 0b11);
+        writer.shl_1bit_from(67);
+        assert_eq!(writer.position(), 68);
+        assert_eq!(writer.data()[2], 0b1111);
+        assert_eq!(writer.to_bytes()[8], 0b1111);
+    }
+}
@@ -1,8 +1,49 @@
+pub(crate) use super::bitstream::{data_shl_1bit_from, put_bits};
+
 #[inline(always)]
 pub(crate) const fn sq(x: f32) -> f32 {
     x * x
 }
 
+/// The squared slope of the sRGB transfer function at every 8-bit value. Scales the squared error
+/// of an sRGB encoded value to the squared error in linear space. Must match
+/// `LINEAR_ERROR_WEIGHTS` in the BC7 shader.
+#[rustfmt::skip]
+pub(crate) const LINEAR_ERROR_WEIGHTS: [f32; 256] = [
+    0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664,
+    0.005990664, 0.005990664, 0.005990664, 0.006698007, 0.007474674, 0.008306961, 0.009196571, 0.010145195,
+    0.011154513, 0.012226191, 0.013361886, 0.014563243, 0.015831897, 0.017169474, 0.018577589, 0.02005785,
+    0.021611853, 0.023241188, 0.024947437, 0.026732171, 0.028596954, 0.030543348, 0.0325729, 0.034687147,
+    0.036887635, 0.039175887, 0.041553423, 0.044021767, 0.04658242, 0.04923689, 0.051986672, 0.05483326,
+    0.05777814, 0.06082279, 0.06396868, 0.06721729, 0.070570074, 0.0740285, 0.07759402, 0.08126808,
+    0.08505212, 0.08894759, 0.09295591, 0.097078525, 0.10131686, 0.10567233, 0.11014635, 0.11474034,
+    0.11945571, 0.12429386, 0.12925617, 0.13434409, 0.13955897, 0.1449022, 0.15037519, 0.15597929,
+    0.16171591, 0.1675864, 0.17359215, 0.17973451, 0.18601486, 0.19243455, 0.19899493, 0.20569739,
+    0.21254323, 0.21953383, 0.22667053, 0.23395467, 0.24138759, 0.24897061, 0.2567051, 0.26459235,
+    0.2726337, 0.28083047, 0.289184, 0.29769558, 0.30636656, 0.3151982, 0.3241919, 0.33334887,
+    0.34267044, 0.35215798, 0.36181268, 0.37163594, 0.381629, 0.39179313, 0.40212968, 0.41263992,
+    0.42332512, 0.43418655, 0.4452255, 0.45644328, 0.46784112, 0.47942033, 0.49118218, 0.5031279,
+    0.5152588, 0.5275761, 0.5400811, 0.552775, 0.5656592, 0.5787348, 0.59200317, 0.6054655,
+    0.61912304, 0.632977, 0.64702874, 0.66127944, 0.6757303, 0.6903826, 0.7052376, 0.7202965,
+    0.7355606, 0.75103104, 0.76670915, 0.78259605, 0.798693, 0.8150013, 0.8315221, 0.84825665,
+    0.8652062, 0.88237184, 0.89975494, 0.91735667, 0.9351782, 0.9532208, 0.9714856, 0.9899739,
+    1.0086868, 1.0276257, 1.0467916, 1.0661857, 1.0858094, 1.1056637, 1.1257498, 1.1460692,
+    1.1666226, 1.1874117, 1.2084373, 1.2297007, 1.2512032, 1.2729459, 1.29493, 1.3171567,
+    1.3396271, 1.3623426, 1.3853041, 1.4085128, 1.4319701, 1.4556772, 1.4796349, 1.5038446,
+    1.5283076, 1.5530249, 1.5779977, 1.6032271, 1.6287144, 1.6544608, 1.6804672, 1.7067351,
+    1.7332655, 1.7600595, 1.7871182, 1.814443, 1.8420349, 1.869895, 1.8980247, 1.9264249,
+    1.9550967, 1.9840416, 2.0132604, 2.0427544, 2.0725248, 2.1025724, 2.1328988, 2.163505,
+    2.1943922, 2.2255611, 2.2570136, 2.2887502, 2.3207722, 2.3530807, 2.385677, 2.4185622,
+    2.4517374, 2.4852037, 2.5189621, 2.553014, 2.5873606, 2.6220026, 2.6569414, 2.692178,
+    2.7277138, 2.7635496, 2.7996864, 2.8361259, 2.8728688, 2.9099164, 2.9472694, 2.9849296,
+    3.0228975, 3.0611746, 3.099762, 3.1386604, 3.1778712, 3.2173958, 3.2572348, 3.2973897,
+    3.3378613, 3.378651, 3.4197595, 3.4611883, 3.5029383, 3.5450106, 3.5874064, 3.630127,
+    3.673173, 3.7165458, 3.7602465, 3.804276, 3.8486357, 3.8933265, 3.9383497, 3.983706,
+    4.029397, 4.0754232, 4.121786, 4.1684866, 4.215526, 4.2629056, 4.3106256, 4.358688,
+    4.4070935, 4.455843, 4.504938, 4.5543795, 4.604168, 4.6543055, 4.7047925, 4.75563,
+    4.8068194, 4.8583617, 4.910258, 4.962509, 5.0151167, 5.068081, 5.1214037, 5.175086,
+];
+
 pub(crate) fn get_unquant_value(bits: u32, index: i32) -> i32 {
     match bits {
         2 => {
@@ -100,29 +141,6 @@ pub(crate) fn get_skips(part_id: i32) -> [u32; 3] {
     [0, skip_packed >> 4, skip_packed & 15]
 }
 
-pub(crate) fn put_bits(data: &mut [u32; 5], pos: &mut u32, bits: u32, v: u32) {
-    data[(*pos / 32) as usize] |= v << (*pos % 32);
-    if *pos % 32 + bits > 32 {
-        data[(*pos / 32 + 1) as usize] |= v >> (32 - *pos % 32);
-    }
-    *pos += bits;
-}
-
-pub(crate) fn data_shl_1bit_from(data: &mut [u32; 5], from_bits: usize) {
-    if from_bits < 96 {
-        let shifted = (data[2] >> 1) | (data[3] << 31);
-        let mask = ((1 << (from_bits - 64)) - 1) >> 1;
-        data[2] = (mask & data[2]) | (!mask & shifted);
-        data[3] = (data[3] >> 1) | (data[4] << 31);
-        data[4] >>= 1;
-    } else if from_bits < 128 {
-        let shifted = (data[3] >> 1) | (data[4] << 31);
-        let mask = ((1 << (from_bits - 96)) - 1) >> 1;
-        data[3] = (mask & data[3]) | (!mask & shifted);
-        data[4] >>= 1;
-    }
-}
-
 pub(crate) fn partial_sort_list(list: &mut [i32], length: usize, partial_count: u32) {
     for k in 0..partial_count as usize {
         let mut best_idx = k;
@@ -367,17 +385,73 @@ pub(crate) fn compute_covar_dc_masked(
     covar_from_stats(covar, stats, channels);
 }
 
+/// The index into the covariance of every pair of channels.
+const COVAR_INDEX: [[usize; 4]; 4] = [[0, 1, 2, 3], [1, 4, 5, 6], [2, 5, 7, 8], [3, 6, 8, 9]];
+
+/// Computes the covariance like [`compute_covar_dc_masked()`], but from the differences to the
+/// mean in double precision, which doesn't cancel out for large values with small differences.
+/// The sums of the differences compensate for the rounding error of the mean.
+pub(crate) fn compute_covar_dc_masked_precise(
+    covar: &mut [f32; 10],
+    dc: &mut [f32; 4],
+    block: &[f32; 64],
+    mask: u32,
+    channels: usize,
+) {
+    let texels = (0..16).filter(|k| (mask >> k) & 1 != 0);
+
+    let mut count = 0.0;
+    let mut mean = [0.0f64; 4];
+    for k in texels.clone() {
+        count += 1.0;
+        for p in 0..channels {
+            mean[p] += block[16 * p + k] as f64;
+        }
+    }
+    for p in 0..channels {
+        mean[p] /= count;
+        dc[p] = mean[p] as f32;
+    }
+
+    let mut sums = [0.0f64; 4];
+    let mut products = [0.0f64; 10];
+    for k in texels {
+        let mut diff = [0.0f64; 4];
+        for p in 0..channels {
+            diff[p] = block[16 * p + k] as f64 - mean[p];
+            sums[p] += diff[p];
+        }
+        for p in 0..channels {
+            for q in p..channels {
+                products[COVAR_INDEX[p][q]] += diff[p] * diff[q];
+            }
+        }
+    }
+
+    for p in 0..channels {
+        for q in p..channels {
+            let index = COVAR_INDEX[p][q];
+            covar[index] = (products[index] - sums[p] * sums[q] / count) as f32;
+        }
+    }
+}
+
 pub(crate) fn block_pca_axis(
     axis: &mut [f32; 4],
     dc: &mut [f32; 4],
     block: &[f32; 64],
     mask: u32,
     channels: usize,
+    precise: bool,
 ) {
     const POWER_ITERATIONS: u32 = 8; // 4 not enough for HQ
 
     let mut covar = [0.0; 10];
-    compute_covar_dc_masked(&mut covar, dc, block, mask, channels);
+    if precise {
+        compute_covar_dc_masked_precise(&mut covar, dc, block, mask, channels);
+    } else {
+        compute_covar_dc_masked(&mut covar, dc, block, mask, channels);
+    }
 
     const INV_VAR: f32 = 1.0 / (256.0 * 256.0);
     for covar in covar.iter_mut() {
@@ -419,6 +493,19 @@ pub(crate) fn block_pca_bound_split(
     bound.sqrt() * 256.0
 }
 
+/// Estimates the smallest squared error of fitting a line segment through the masked pixels.
+#[cfg(feature = "bc7")]
+pub(crate) fn block_pca_residual(block: &[f32; 64], mask: u32, channels: usize) -> f32 {
+    let mut stats = [0.0; 15];
+    compute_stats_masked(&mut stats, block, mask, channels);
+
+    let mut covar = [0.0; 10];
+    covar_from_stats(&mut covar, stats, channels);
+
+    get_pca_bound(&covar, channels) * 256.0 * 256.0
+}
+
+#[cfg(feature = "bc6h")]
 pub(crate) fn block_quant(
     qblock: &mut [u32; 2],
     block: &[f32; 64],
@@ -426,6 +513,22 @@ pub(crate) fn block_quant(
     ep: &[f32],
     pattern: u32,
     channels: usize,
+) -> f32 {
+    block_quant_weighted(qblock, block, bits, ep, pattern, channels, &[1.0; 4], 0)
+}
+
+/// Like [`block_quant()`], but scales the squared error of every channel by its weight. The
+/// error of the channels whose bit is set in `linear_channels` is measured in linear space.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn block_quant_weighted(
+    qblock: &mut [u32; 2],
+    block: &[f32; 64],
+    bits: u32,
+    ep: &[f32],
+    pattern: u32,
+    channels: usize,
+    weights: &[f32; 4],
+    linear_channels: u32,
 ) -> f32 {
     let mut total_err = 0.0;
     let levels = 1 << bits;
@@ -462,8 +565,13 @@ pub(crate) fn block_quant(
             let ep_b = ep[8 * j + 4 + p];
             let dec_v0 = (((64 - w0) * ep_a as i32 + w0 * ep_b as i32 + 32) / 64) as f32;
             let dec_v1 = (((64 - w1) * ep_a as i32 + w1 * ep_b as i32 + 32) / 64) as f32;
-            err0 += sq(dec_v0 - block[k + p * 16]);
-            err1 += sq(dec_v1 - block[k + p * 16]);
+            let weight = if linear_channels & (1 << p) != 0 {
+                weights[p] * linear_error_weight(block[k + p * 16])
+            } else {
+                weights[p]
+            };
+            err0 += weight * sq(dec_v0 - block[k + p * 16]);
+            err1 += weight * sq(dec_v1 - block[k + p * 16]);
         }
 
         let mut best_err = err1;
@@ -480,10 +588,22 @@ pub(crate) fn block_quant(
     total_err
 }
 
-pub(crate) fn block_segment_core(ep: &mut [f32], block: &[f32; 64], mask: u32, channels: usize) {
+/// Returns the factor that converts the squared error of an 8-bit sRGB value into linear space.
+#[inline(always)]
+pub(crate) fn linear_error_weight(value: f32) -> f32 {
+    LINEAR_ERROR_WEIGHTS[value.clamp(0.0, 255.0) as usize]
+}
+
+pub(crate) fn block_segment_core(
+    ep: &mut [f32],
+    block: &[f32; 64],
+    mask: u32,
+    channels: usize,
+    precise: bool,
+) {
     let mut axis = [0.0; 4];
     let mut dc = [0.0; 4];
-    block_pca_axis(&mut axis, &mut dc, block, mask, channels);
+    block_pca_axis(&mut axis, &mut dc, block, mask, channels, precise);
 
     let mut ext = [f32::INFINITY, f32::NEG_INFINITY];
 
@@ -611,3 +731,39 @@ pub(crate) fn bc7_code_apply_swap_mode01237(
 
     flips
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_error_weights() {
+        for (value, &weight) in LINEAR_ERROR_WEIGHTS.iter().enumerate() {
+            let srgb = value as f64 / 255.0;
+            let slope = if srgb <= 0.04045 {
+                1.0 / 12.92
+            } else {
+                2.4 / 1.055 * ((srgb + 0.055) / 1.055).powf(1.4)
+            };
+            assert!((weight as f64 - slope * slope).abs() <= slope * slope * 1e-6);
+        }
+
+        #[cfg(feature = "bc7")]
+        {
+            let shader = include_str!("../shader/bc7.wgsl");
+            let start = shader
+                .find("const LINEAR_ERROR_WEIGHTS = array<f32, 256>(")
+                .expect("the shader has no linear error weights");
+            let table = &shader[start..];
+            let table = &table[table.find('(').unwrap() + 1..table.find(')').unwrap()];
+
+            let shader_weights: Vec<f32> = table
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse().unwrap())
+                .collect();
+            assert_eq!(shader_weights, LINEAR_ERROR_WEIGHTS);
+        }
+    }
+}
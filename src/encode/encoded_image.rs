@@ -0,0 +1,216 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageError, ImageReader};
+
+#[cfg(feature = "bc6h")]
+use super::{compress_rgba16_padded, rows::compress_rows};
+use super::{compress_rgba8_padded, compress_rgba8_rows};
+#[cfg(feature = "bc6h")]
+use crate::color::srgb_to_linear;
+use crate::{CompressionVariant, EdgeFill, ImageView};
+
+/// An image that was decoded and compressed by [`compress_encoded_image()`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CompressedImage {
+    /// The variant the blocks are compressed with.
+    pub variant: CompressionVariant,
+    /// The width of the decoded image in pixels.
+    pub width: u32,
+    /// The height of the decoded image in pixels.
+    pub height: u32,
+    /// The compressed blocks, with the size returned by
+    /// [`CompressionVariant::blocks_byte_size()`].
+    pub blocks: Vec<u8>,
+}
+
+/// Decodes an encoded image file and compresses it in one call.
+///
+/// Meant for asset ingestion on servers, where uploads arrive as PNG or JPEG bytes. The format
+/// is detected from the content of `bytes`. PNG and JPEG are always supported, other formats
+/// once the matching feature of the `image` crate is enabled. The decoder applies the default
+/// [`image::Limits`], so a malicious file can't exhaust the memory of the server.
+///
+/// Decoded RGBA8 images are compressed straight from the buffer of the decoder. All other pixel
+/// formats are converted row by row while they are compressed with
+/// [`compress_rgba8_rows()`](super::compress_rgba8_rows), so only a few rows of converted texels
+/// are kept besides the decoded image. Grayscale images are expanded to RGB and
+/// images without alpha are opaque.
+///
+/// BC6H compresses images with more than 8 bits per channel from their full precision. Integer
+/// channels are sRGB encoded and converted to linear, like the 8-bit channels of
+/// [`compress_rgba8()`](super::compress_rgba8), while float channels are already linear.
+///
+/// # Arguments
+/// * `bytes` - The encoded image file
+/// * `variant` - The block compression format to use
+/// * `edge_fill` - How the blocks at the right and bottom edge are filled, if the width or
+///   height of the image is not a multiple of 4
+///
+/// # Errors
+/// - [`ImageError`] if the format is unknown or not enabled, or if the file can't be decoded
+///
+/// # Example
+/// ```no_run
+/// use block_compression::{
+///     encode::compress_encoded_image, BC7Settings, CompressionVariant, EdgeFill,
+/// };
+///
+/// let bytes = std::fs::read("upload.png").unwrap();
+/// let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+///
+/// let image = compress_encoded_image(&bytes, variant, EdgeFill::ClampToEdge).unwrap();
+/// assert_eq!(image.blocks.len(), variant.blocks_byte_size(image.width, image.height));
+/// ```
+pub fn compress_encoded_image(
+    bytes: &[u8],
+    variant: CompressionVariant,
+    edge_fill: EdgeFill,
+) -> Result<CompressedImage, ImageError> {
+    let image = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .decode()?;
+
+    let width = image.width();
+    let height = image.height();
+    let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+
+    if width != 0 && height != 0 {
+        match &image {
+            DynamicImage::ImageRgba8(rgba) => {
+                let view = ImageView::packed(rgba.as_raw(), width, height);
+                compress_rgba8_padded(variant, view, edge_fill, &mut blocks);
+            }
+            #[cfg(feature = "bc6h")]
+            _ if variant.is_hdr()
+                && image.color().bytes_per_pixel() > image.color().channel_count() =>
+            {
+                let linear = matches!(
+                    image,
+                    DynamicImage::ImageRgb32F(..) | DynamicImage::ImageRgba32F(..)
+                );
+                compress_rows(
+                    variant,
+                    width,
+                    height,
+                    edge_fill,
+                    |y, row| {
+                        let source = image.crop_imm(0, y, width, 1).into_rgba32f();
+                        for (index, (texel, &value)) in
+                            row.iter_mut().zip(source.as_raw()).enumerate()
+                        {
+                            let value = match index % 4 {
+                                3 => value,
+                                _ if linear => value,
+                                _ => srgb_to_linear(value),
+                            };
+                            *texel = half::f16::from_f32(value);
+                        }
+                    },
+                    compress_rgba16_padded,
+                    &mut blocks,
+                );
+            }
+            _ => {
+                compress_rgba8_rows(
+                    variant,
+                    width,
+                    height,
+                    edge_fill,
+                    |y, row| {
+                        let source = image.crop_imm(0, y, width, 1).into_rgba8();
+                        row.copy_from_slice(source.as_raw());
+                    },
+                    &mut blocks,
+                );
+            }
+        }
+    }
+
+    Ok(CompressedImage {
+        variant,
+        width,
+        height,
+        blocks,
+    })
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use image::{ImageFormat, RgbImage};
+
+    use super::*;
+    use crate::encode::compress_rgba8;
+
+    fn encode_png(image: DynamicImage) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_compress_encoded_image() {
+        let variant = CompressionVariant::BC1;
+        let (width, height) = (13, 10);
+        let rgb = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 19) as u8, (y * 23) as u8, ((x + y) * 7) as u8])
+        });
+
+        // The RGBA8 data the band conversion needs to match.
+        let rgba = DynamicImage::ImageRgb8(rgb.clone()).into_rgba8();
+
+        for edge_fill in [
+            EdgeFill::ClampToEdge,
+            EdgeFill::Mirror,
+            EdgeFill::Zero,
+            EdgeFill::SolidColor([255, 0, 255, 255]),
+        ] {
+            let (padded, padded_width, padded_height) =
+                ImageView::packed(rgba.as_raw(), width, height).padded_to_blocks(edge_fill);
+            let mut expected = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8(
+                variant,
+                ImageView::packed(&padded, padded_width, padded_height),
+                &mut expected,
+            );
+
+            for image in [
+                DynamicImage::ImageRgb8(rgb.clone()),
+                DynamicImage::ImageRgba8(rgba.clone()),
+            ] {
+                let compressed =
+                    compress_encoded_image(&encode_png(image), variant, edge_fill).unwrap();
+
+                assert_eq!(compressed.width, width);
+                assert_eq!(compressed.height, height);
+                assert_eq!(compressed.blocks, expected, "{edge_fill:?}");
+            }
+        }
+
+        assert!(compress_encoded_image(b"not an image", variant, EdgeFill::Zero).is_err());
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_bc6h_matches_for_8_and_16_bit_images() {
+        let variant = CompressionVariant::BC6H(crate::BC6HSettings::very_fast());
+        let rgb = RgbImage::from_fn(8, 8, |x, y| {
+            image::Rgb([(x * 31) as u8, (y * 29) as u8, ((x ^ y) * 17) as u8])
+        });
+        let rgb16 = DynamicImage::ImageRgb8(rgb.clone()).into_rgb16();
+
+        let blocks_8 = compress_encoded_image(
+            &encode_png(DynamicImage::ImageRgb8(rgb)),
+            variant,
+            EdgeFill::ClampToEdge,
+        )
+        .unwrap();
+        let blocks_16 = compress_encoded_image(
+            &encode_png(DynamicImage::ImageRgb16(rgb16)),
+            variant,
+            EdgeFill::ClampToEdge,
+        )
+        .unwrap();
+
+        assert_eq!(blocks_8, blocks_16);
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Instant;
+
+use super::compress_rgba8;
+#[cfg(feature = "bc6h")]
+use crate::BC6HSettings;
+#[cfg(feature = "bc7")]
+use crate::BC7Settings;
+use crate::{
+    decode::{compare_rgba8, decompress_blocks_as_rgba8},
+    CompressionVariant, ImageView, ImageViewMut,
+};
+
+/// The encode time and quality of a preset, returned by [`evaluate_presets()`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PresetReport {
+    /// The name of the constructor of the preset settings, like `"alpha_basic"`. Variants
+    /// without settings are named `"default"`.
+    pub name: &'static str,
+    /// The variant with the settings of the preset.
+    pub variant: CompressionVariant,
+    /// The time it took to compress the image, in milliseconds.
+    pub encode_ms: f64,
+    /// Peak signal-to-noise ratio between the source and the decoded blocks, in dB.
+    pub psnr: f64,
+    /// Mean structural similarity between the source and the decoded blocks.
+    pub ssim: f64,
+}
+
+impl PresetReport {
+    /// Returns `true` if the preset is at least as fast and as good as `other` and better in
+    /// one of them. The presets that no other preset dominates form the Pareto front of a
+    /// texture.
+    pub fn dominates(&self, other: &PresetReport) -> bool {
+        self.encode_ms <= other.encode_ms
+            && self.psnr >= other.psnr
+            && (self.encode_ms < other.encode_ms || self.psnr > other.psnr)
+    }
+}
+
+/// Compresses the image with every preset of the variant and reports how long each preset took
+/// and the quality it reached.
+///
+/// Meant for tools that show artists the trade-off between encode time and quality of a
+/// texture class, for example as a Pareto chart of representative textures. BC6H and BC7 are
+/// evaluated with their preview and ISPC presets from the fastest to the slowest, BC7 with the
+/// alpha presets if `variant` encodes alpha. The settings of `variant` itself are not used. All
+/// other variants have a single preset.
+///
+/// The quality is measured on the 8-bit values of the channels the variant stores, like
+/// [`compare_compressed()`](crate::decode::compare_compressed) does. The encode times are
+/// measured on the calling thread and include the parallelism of the `rayon` feature.
+///
+/// # Panics
+/// * If the width or height of the `image` is not a multiple of 4
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::evaluate_presets, BC7Settings, CompressionVariant, ImageView};
+///
+/// let rgba_data = vec![128u8; 16 * 16 * 4];
+/// let variant = CompressionVariant::BC7(BC7Settings::opaque_basic());
+///
+/// let reports = evaluate_presets(ImageView::packed(&rgba_data, 16, 16), variant);
+/// for report in &reports {
+///     let optimal = !reports.iter().any(|other| other.dominates(report));
+///     println!(
+///         "{}: {:.2} ms, {:.2} dB, optimal: {optimal}",
+///         report.name, report.encode_ms, report.psnr
+///     );
+/// }
+/// assert_eq!(reports[0].name, "preview");
+/// ```
+pub fn evaluate_presets(
+    image: ImageView<'_, u8>,
+    variant: CompressionVariant,
+) -> Vec<PresetReport> {
+    image.assert_rgba();
+    assert_eq!(image.height() % 4, 0);
+    assert_eq!(image.width() % 4, 0);
+
+    let (width, height) = (image.width(), image.height());
+    let source = packed_rgba8(image);
+
+    preset_variants(variant)
+        .into_iter()
+        .map(|(name, variant)| {
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+
+            let start = Instant::now();
+            compress_rgba8(variant, image, &mut blocks);
+            let encode_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+            preset_report(name, variant, encode_ms, &source, &blocks, width, height)
+        })
+        .collect()
+}
+
+/// Returns the presets of the variant, ordered from the fastest to the slowest.
+pub(crate) fn preset_variants(
+    variant: CompressionVariant,
+) -> Vec<(&'static str, CompressionVariant)> {
+    match variant {
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(..) => [
+            ("preview", BC6HSettings::preview()),
+            ("very_fast", BC6HSettings::very_fast()),
+            ("fast", BC6HSettings::fast()),
+            ("basic", BC6HSettings::basic()),
+            ("slow", BC6HSettings::slow()),
+            ("very_slow", BC6HSettings::very_slow()),
+        ]
+        .into_iter()
+        .map(|(name, settings)| (name, CompressionVariant::BC6H(settings)))
+        .collect(),
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(settings) => {
+            let presets = if settings.channels == 4 {
+                [
+                    ("preview", BC7Settings::preview()),
+                    ("alpha_ultrafast", BC7Settings::alpha_ultrafast()),
+                    ("alpha_very_fast", BC7Settings::alpha_very_fast()),
+                    ("alpha_fast", BC7Settings::alpha_fast()),
+                    ("alpha_basic", BC7Settings::alpha_basic()),
+                    ("alpha_slow", BC7Settings::alpha_slow()),
+                ]
+            } else {
+                [
+                    ("preview", BC7Settings::preview()),
+                    ("opaque_ultra_fast", BC7Settings::opaque_ultra_fast()),
+                    ("opaque_very_fast", BC7Settings::opaque_very_fast()),
+                    ("opaque_fast", BC7Settings::opaque_fast()),
+                    ("opaque_basic", BC7Settings::opaque_basic()),
+                    ("opaque_slow", BC7Settings::opaque_slow()),
+                ]
+            };
+
+            presets
+                .into_iter()
+                .map(|(name, settings)| (name, CompressionVariant::BC7(settings)))
+                .collect()
+        }
+        #[allow(unreachable_patterns)]
+        variant => vec![("default", variant)],
+    }
+}
+
+/// Decodes the blocks of a preset and compares them with the packed source.
+pub(crate) fn preset_report(
+    name: &'static str,
+    variant: CompressionVariant,
+    encode_ms: f64,
+    source: &[u8],
+    blocks: &[u8],
+    width: u32,
+    height: u32,
+) -> PresetReport {
+    let mut decoded = vec![0; source.len()];
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks,
+        ImageViewMut::packed(&mut decoded, width, height),
+    );
+    let stats = compare_rgba8(variant, source, &decoded, width, height);
+
+    PresetReport {
+        name,
+        variant,
+        encode_ms,
+        psnr: stats.psnr,
+        ssim: stats.ssim,
+    }
+}
+
+/// Copies the rows of the image into a buffer without padding between the rows.
+pub(crate) fn packed_rgba8(image: ImageView<'_, u8>) -> Vec<u8> {
+    (0..image.height())
+        .flat_map(|y| image.row(y))
+        .copied()
+        .collect()
+}
+
+#[cfg(all(test, feature = "bc7"))]
+mod tests {
+    use super::*;
+    use crate::testgen::TestPattern;
+
+    #[test]
+    fn test_evaluate_presets() {
+        let (width, height) = (32, 32);
+        let rgba_data = TestPattern::AlphaRamp.generate_rgba8(width, height);
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+        let reports = evaluate_presets(image, variant);
+        let names: Vec<_> = reports.iter().map(|report| report.name).collect();
+        assert_eq!(
+            names,
+            [
+                "preview",
+                "alpha_ultrafast",
+                "alpha_very_fast",
+                "alpha_fast",
+                "alpha_basic",
+                "alpha_slow"
+            ]
+        );
+
+        let preview = &reports[0];
+        let slow = &reports[5];
+        assert!(slow.psnr > preview.psnr);
+        assert!(slow.ssim >= preview.ssim && slow.ssim <= 1.0);
+
+        // The reports describe the blocks the variant of the preset produces.
+        let mut blocks = vec![0; slow.variant.blocks_byte_size(width, height)];
+        compress_rgba8(slow.variant, image, &mut blocks);
+        let expected = preset_report(
+            "alpha_slow",
+            slow.variant,
+            0.0,
+            &rgba_data,
+            &blocks,
+            width,
+            height,
+        );
+        assert_eq!(slow.psnr, expected.psnr);
+        assert_eq!(slow.ssim, expected.ssim);
+
+        let faster = PresetReport {
+            encode_ms: slow.encode_ms - 1.0,
+            ..*slow
+        };
+        assert!(faster.dominates(slow));
+        assert!(!slow.dominates(&faster));
+        assert!(!slow.dominates(slow));
+    }
+}
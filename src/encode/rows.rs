@@ -0,0 +1,169 @@
+use super::compress_rgba8_padded;
+use crate::{CompressionVariant, EdgeFill, ImageView};
+
+/// Compresses RGBA8 data that is read row by row, without keeping the whole image in memory.
+///
+/// `read_row` is called with the index of every row of the image, in order and exactly once, and
+/// fills the given slice with the `width * 4` bytes of the row. The rows are compressed as soon
+/// as a row of blocks is complete, so besides the `blocks_buffer` only 8 rows of texels are kept
+/// in memory. This allows compressing scanned textures that are larger than the memory of the
+/// machine, for example by reading the rows from a streaming decoder or a memory mapped file.
+///
+/// If the width or height is not a multiple of 4, the missing pixels are filled as described by
+/// `edge_fill`. All other arguments behave like in [`compress_rgba8()`](super::compress_rgba8),
+/// and the blocks are identical to the blocks of
+/// [`compress_rgba8_padded()`](super::compress_rgba8_padded).
+///
+/// # Arguments
+/// * `variation` - The block compression format to use
+/// * `width` - Width of the image in pixels
+/// * `height` - Height of the image in pixels
+/// * `edge_fill` - How the blocks at the right and bottom edge are filled
+/// * `read_row` - Fills the RGBA8 data of a row
+/// * `blocks_buffer` - Destination buffer for the compressed blocks
+///
+/// # Panics
+/// * If the destination `blocks_buffer` is too small to hold the compressed data
+///
+/// # Example
+/// ```
+/// use block_compression::{encode::compress_rgba8_rows, CompressionVariant, EdgeFill};
+///
+/// let (width, height) = (30, 30);
+/// let variant = CompressionVariant::BC1;
+/// let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(width, height)];
+///
+/// compress_rgba8_rows(
+///     variant,
+///     width,
+///     height,
+///     EdgeFill::ClampToEdge,
+///     |y, row| row.fill(y as u8), // Your decoder
+///     &mut blocks_buffer,
+/// );
+/// ```
+pub fn compress_rgba8_rows(
+    variation: CompressionVariant,
+    width: u32,
+    height: u32,
+    edge_fill: EdgeFill,
+    read_row: impl FnMut(u32, &mut [u8]),
+    blocks_buffer: &mut [u8],
+) {
+    compress_rows(
+        variation,
+        width,
+        height,
+        edge_fill,
+        read_row,
+        compress_rgba8_padded,
+        blocks_buffer,
+    );
+}
+
+/// Compresses the rows of `read_row` one row of blocks at a time with `compress`.
+pub(crate) fn compress_rows<T: Copy + Default>(
+    variation: CompressionVariant,
+    width: u32,
+    height: u32,
+    edge_fill: EdgeFill,
+    mut read_row: impl FnMut(u32, &mut [T]),
+    compress: fn(CompressionVariant, ImageView<'_, T>, EdgeFill, &mut [u8]),
+    blocks_buffer: &mut [u8],
+) {
+    let required_size = variation.blocks_byte_size(width, height);
+
+    assert!(
+        blocks_buffer.len() >= required_size,
+        "blocks_buffer size ({}) is too small to hold compressed blocks. Required size: {}",
+        blocks_buffer.len(),
+        required_size
+    );
+
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let row_len = width as usize * 4;
+    let block_row_size = variation.blocks_byte_size(width, 4);
+    let mut band = vec![T::default(); row_len * 4];
+    let mut previous_band = vec![T::default(); row_len * 4];
+
+    for (block_y, block_row) in
+        (0..height.div_ceil(4)).zip(blocks_buffer.chunks_exact_mut(block_row_size))
+    {
+        let band_y = block_y * 4;
+        let mut band_height = 0;
+
+        for y in band_y..band_y + 4 {
+            let row = (y - band_y) as usize * row_len;
+
+            if y < height {
+                read_row(y, &mut band[row..row + row_len]);
+            } else if let EdgeFill::ClampToEdge | EdgeFill::Mirror = edge_fill {
+                // The rows below the image repeat rows that were already read. Mirroring can
+                // reach up to three rows above the last row of blocks.
+                let source_y = edge_fill.coordinate(y, height);
+                if source_y >= band_y {
+                    let source = (source_y - band_y) as usize * row_len;
+                    band.copy_within(source..source + row_len, row);
+                } else {
+                    let source = (source_y + 4 - band_y) as usize * row_len;
+                    band[row..row + row_len]
+                        .copy_from_slice(&previous_band[source..source + row_len]);
+                }
+            } else {
+                break;
+            }
+
+            band_height += 1;
+        }
+
+        let view = ImageView::packed(&band[..band_height as usize * row_len], width, band_height);
+        compress(variation, view, edge_fill, block_row);
+
+        std::mem::swap(&mut band, &mut previous_band);
+    }
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_rgba8_rows() {
+        let variant = CompressionVariant::BC3;
+        let (width, height) = (10, 13);
+        let rgba_data: Vec<u8> = (0..width * height * 4)
+            .map(|i| (i * 37 % 251) as u8)
+            .collect();
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        for edge_fill in [
+            EdgeFill::ClampToEdge,
+            EdgeFill::Mirror,
+            EdgeFill::Zero,
+            EdgeFill::SolidColor([0, 255, 0, 128]),
+        ] {
+            let mut expected = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8_padded(variant, image, edge_fill, &mut expected);
+
+            let mut read_rows = Vec::new();
+            let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+            compress_rgba8_rows(
+                variant,
+                width,
+                height,
+                edge_fill,
+                |y, row| {
+                    read_rows.push(y);
+                    row.copy_from_slice(image.row(y));
+                },
+                &mut blocks,
+            );
+
+            assert_eq!(read_rows, (0..height).collect::<Vec<_>>());
+            assert_eq!(blocks, expected, "{edge_fill:?}");
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Analytic encoding of blocks that only contain a single color.
+//!
+//! Every endpoint pair is chosen by evaluating the exact formulas of the decoder, so no search
+//! over the pixels of a block is needed.
+
+#[cfg(feature = "bc6h")]
+use half::f16;
+
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+use super::bitstream::BlockWriter;
+use crate::CompressionVariant;
+
+/// Returns the endpoint pair with `levels` values each and at most `max_distance` apart, for
+/// which `decode` gets closest to `value`, together with the error.
+fn best_pair(
+    value: u32,
+    levels: u32,
+    max_distance: u32,
+    decode: impl Fn(u32, u32) -> u32,
+) -> (u32, u32, u32) {
+    let mut best = (0, 0, u32::MAX);
+    for e0 in 0..levels {
+        let e1_range = e0.saturating_sub(max_distance)..levels.min(e0 + max_distance + 1);
+        for e1 in e1_range {
+            let err = decode(e0, e1).abs_diff(value);
+            if err < best.2 {
+                best = (e0, e1, err);
+            }
+        }
+    }
+    best
+}
+
+/// Encodes a BC1 color block, which is always decoded in the four color mode by BC2 and BC3.
+#[cfg(feature = "bc15")]
+fn color_block(red: u8, green: u8, blue: u8) -> [u8; 8] {
+    // The third palette entry is 2/3 * color0 + 1/3 * color1.
+    let lerp5 = |e0: u32, e1: u32| ((2 * e0 + e1) * 351 + 61) >> 7;
+    let lerp6 = |e0: u32, e1: u32| ((2 * e0 + e1) * 2763 + 1039) >> 11;
+
+    let (r0, r1, _) = best_pair(red as u32, 32, 32, lerp5);
+    let (g0, g1, _) = best_pair(green as u32, 64, 64, lerp6);
+    let (b0, b1, _) = best_pair(blue as u32, 32, 32, lerp5);
+
+    let mut color0 = (r0 << 11 | g0 << 5 | b0) as u16;
+    let mut color1 = (r1 << 11 | g1 << 5 | b1) as u16;
+    let mut indices = 0xAAAAAAAA_u32;
+
+    if color0 < color1 {
+        // The fourth palette entry is the same color with swapped endpoints.
+        (color0, color1) = (color1, color0);
+        indices = 0xFFFFFFFF;
+    } else if color0 == color1 {
+        // BC1 uses the three color mode for equal endpoints, so the first entry is used.
+        indices = 0;
+    }
+
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+#[cfg(feature = "bc15")]
+fn join(first: [u8; 8], second: [u8; 8]) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&first);
+    block[8..].copy_from_slice(&second);
+    block
+}
+
+/// Encodes a BC4 block. Equal endpoints reproduce the value exactly.
+#[cfg(feature = "bc15")]
+const fn channel_block(value: u8) -> [u8; 8] {
+    [value, value, 0, 0, 0, 0, 0, 0]
+}
+
+/// Encodes a BC6H block in mode 11, which has a single region and unsigned 10-bit endpoints.
+#[cfg(feature = "bc6h")]
+fn bc6h_block(rgb: [f16; 3]) -> [u8; 16] {
+    const WEIGHTS: [u32; 8] = [0, 4, 9, 13, 17, 21, 26, 30];
+
+    let unquantize = |q: u32| match q {
+        0 => 0,
+        1023 => 0xFFFF,
+        _ => ((q << 16) + 0x8000) >> 10,
+    };
+
+    // The anchor index only has 3 bits. The other half of the weights is reached by swapping
+    // the endpoints.
+    let mut best = ([(0, 0); 3], 0, u32::MAX);
+    for (index, weight) in WEIGHTS.into_iter().enumerate() {
+        let mut endpoints = [(0, 0); 3];
+        let mut total_err = 0;
+
+        for (channel, value) in rgb.iter().enumerate() {
+            let decode = |e0: u32, e1: u32| {
+                let value = (unquantize(e0) * (64 - weight) + unquantize(e1) * weight + 32) >> 6;
+                (value * 31) >> 6
+            };
+            // Neighboring endpoints are enough to interpolate between two levels.
+            let (e0, e1, err) = best_pair(value.to_bits() as u32, 1024, 1, decode);
+            endpoints[channel] = (e0, e1);
+            total_err += err;
+        }
+
+        if total_err < best.2 {
+            best = (endpoints, index as u32, total_err);
+        }
+    }
+
+    let (endpoints, index, _) = best;
+    let mut writer = BlockWriter::new();
+    writer.put_bits(5, 0b00011);
+    for (e0, _) in endpoints {
+        writer.put_bits(10, e0);
+    }
+    for (_, e1) in endpoints {
+        writer.put_bits(10, e1);
+    }
+    writer.put_bits(3, index);
+    for _ in 1..16 {
+        writer.put_bits(4, index);
+    }
+    debug_assert_eq!(writer.position(), 128);
+    writer.to_bytes()
+}
+
+/// Encodes a BC7 block in mode 5, which has separate 7-bit color and 8-bit alpha endpoints.
+#[cfg(feature = "bc7")]
+fn bc7_block(rgba: [u8; 4]) -> [u8; 16] {
+    const WEIGHTS: [u32; 2] = [0, 21];
+
+    let expand = |e: u32| (e << 1) | (e >> 6);
+
+    // The anchor index only has 1 bit. The other half of the weights is reached by swapping
+    // the endpoints.
+    let mut best = ([(0, 0); 3], 0, u32::MAX);
+    for (index, weight) in WEIGHTS.into_iter().enumerate() {
+        let mut endpoints = [(0, 0); 3];
+        let mut total_err = 0;
+
+        for channel in 0..3 {
+            let decode =
+                |e0: u32, e1: u32| (expand(e0) * (64 - weight) + expand(e1) * weight + 32) >> 6;
+            let (e0, e1, err) = best_pair(rgba[channel] as u32, 128, 128, decode);
+            endpoints[channel] = (e0, e1);
+            total_err += err;
+        }
+
+        if total_err < best.2 {
+            best = (endpoints, index as u32, total_err);
+        }
+    }
+
+    let (endpoints, index, _) = best;
+    let mut writer = BlockWriter::new();
+    writer.put_bits(6, 1 << 5);
+    // No channel rotation.
+    writer.put_bits(2, 0);
+    for (e0, e1) in endpoints {
+        writer.put_bits(7, e0);
+        writer.put_bits(7, e1);
+    }
+    writer.put_bits(8, rgba[3] as u32);
+    writer.put_bits(8, rgba[3] as u32);
+    writer.put_bits(1, index);
+    for _ in 1..16 {
+        writer.put_bits(2, index);
+    }
+    // All alpha indices select the first endpoint.
+    writer.put_bits(31, 0);
+    debug_assert_eq!(writer.position(), 128);
+    writer.to_bytes()
+}
+
+/// Returns a single block of the variant that contains only the given color. Only the first
+/// [`CompressionVariant::block_byte_size()`] bytes are used.
+pub(crate) fn solid_color_block(variant: CompressionVariant, rgba: [u8; 4]) -> [u8; 16] {
+    #[allow(unused_variables)]
+    let [red, green, blue, alpha] = rgba;
+
+    match variant {
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC1 => join(color_block(red, green, blue), [0; 8]),
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC2 => {
+            let alpha = (alpha as u64 + 8) / 17 * 0x1111_1111_1111_1111;
+            join(alpha.to_le_bytes(), color_block(red, green, blue))
+        }
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC3 => join(channel_block(alpha), color_block(red, green, blue)),
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC4 => join(channel_block(red), [0; 8]),
+        #[cfg(feature = "bc15")]
+        CompressionVariant::BC5 => join(channel_block(red), channel_block(green)),
+        #[cfg(feature = "bc6h")]
+        CompressionVariant::BC6H(..) => {
+            let color = |value: u8| f16::from_f32(value as f32 / 255.0);
+            bc6h_block([color(red), color(green), color(blue)])
+        }
+        #[cfg(feature = "bc7")]
+        CompressionVariant::BC7(..) => bc7_block(rgba),
+        CompressionVariant::RGBA8 => unreachable!("uncompressed texels are not stored in blocks"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_solid_color_bc15() {
+        use crate::decode::{decode_block_bc1, decode_block_bc2, decode_block_bc3};
+
+        for value in 0..=255 {
+            let rgba = [value, 255 - value, value / 2, value];
+
+            for (variant, decode, tolerance) in [
+                (
+                    CompressionVariant::BC1,
+                    decode_block_bc1 as fn(&[u8], &mut [u8], usize),
+                    0,
+                ),
+                (CompressionVariant::BC2, decode_block_bc2, 8),
+                (CompressionVariant::BC3, decode_block_bc3, 0),
+            ] {
+                let block = solid_color_block(variant, rgba);
+                let mut decoded = [0; 64];
+                decode(&block, &mut decoded, 16);
+
+                for texel in decoded.chunks_exact(4) {
+                    for channel in 0..3 {
+                        assert!(
+                            texel[channel].abs_diff(rgba[channel]) <= 4,
+                            "{variant:?}: {texel:?} is not close to {rgba:?}"
+                        );
+                    }
+                    if variant != CompressionVariant::BC1 {
+                        assert!(texel[3].abs_diff(rgba[3]) <= tolerance);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_solid_color_bc6h() {
+        use crate::{decode::decode_block_bc6h, BC6HSettings};
+
+        for value in 0..=255 {
+            let rgba = [value, 255 - value, value / 2, 255];
+            let block = solid_color_block(CompressionVariant::BC6H(BC6HSettings::basic()), rgba);
+
+            let mut decoded = [f16::ZERO; 48];
+            decode_block_bc6h(&block, &mut decoded, 12, false);
+
+            for texel in decoded.chunks_exact(3) {
+                for channel in 0..3 {
+                    let expected = rgba[channel] as f32 / 255.0;
+                    assert!(
+                        (texel[channel].to_f32() - expected).abs() <= expected * 0.01 + 1e-4,
+                        "{texel:?} is not close to {rgba:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "bc7")]
+    #[test]
+    fn test_solid_color_bc7_is_exact() {
+        use crate::{decode::decode_block_bc7, BC7Settings};
+
+        for value in 0..=255 {
+            let rgba = [value, 255 - value, value / 2, value];
+            let block =
+                solid_color_block(CompressionVariant::BC7(BC7Settings::alpha_basic()), rgba);
+
+            let mut decoded = [0; 64];
+            decode_block_bc7(&block, &mut decoded, 16);
+
+            for texel in decoded.chunks_exact(4) {
+                assert_eq!(texel, rgba);
+            }
+        }
+    }
+}
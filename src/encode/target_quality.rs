@@ -0,0 +1,333 @@
+use super::{compress_rgba8, presets::packed_rgba8};
+use crate::{
+    decode::{compare_rgba8, decompress_blocks_as_rgba8},
+    CompressionVariant, ImageView, ImageViewMut,
+};
+
+/// Size of the square windows that are sampled. Matches the windows the SSIM is computed over.
+const SAMPLE_WINDOW: u32 = 8;
+
+/// Number of sampled windows along each side of a square image.
+const SAMPLE_GRID_SIDE: usize = 16;
+
+/// Upper bound of windows that are sampled to estimate the quality of a candidate.
+const MAX_SAMPLE_WINDOWS: usize = SAMPLE_GRID_SIDE * SAMPLE_GRID_SIDE;
+
+/// The quality that [`compress_rgba8_target_quality()`] needs to reach.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum QualityTarget {
+    /// The minimal peak signal-to-noise ratio in dB.
+    Psnr(f64),
+    /// The minimal mean structural similarity over windows of 8x8 pixels, between `0.0` and
+    /// `1.0`.
+    Ssim(f64),
+}
+
+/// Compresses raw RGBA8 data with the fastest candidate that reaches the given target quality.
+///
+/// The candidates are expected to be ordered from the fastest to the slowest preset. For every
+/// candidate a sampled subset of the image is compressed, decoded and compared against the
+/// source. The first candidate that reaches the `target` is used to compress the whole image. If
+/// no candidate reaches the target, the last (slowest) candidate is used. Easy content will
+/// therefore only pay the price of the fast presets.
+///
+/// The samples are evenly distributed windows of 8x8 pixels, the same windows the SSIM is
+/// computed over. The quality is measured on the 8-bit values of the channels the variant stores,
+/// like [`compare_compressed()`](crate::decode::compare_compressed) does. Images of up to 256
+/// windows are measured completely, so the selected candidate reaches the target on the whole
+/// image. The selection only runs on the CPU, there is no GPU counterpart.
+///
+/// Returns the candidate that was used to compress the image.
+///
+/// # Arguments
+/// * `candidates` - The block compression formats to try, ordered from fastest to slowest
+/// * `target` - The quality the sampled windows need to reach
+/// * `image` - View into the source RGBA8 pixel data
+/// * `blocks_buffer` - Destination buffer for the compressed blocks
+///
+/// # Panics
+/// * If `candidates` is empty
+/// * If the width or height of the `image` is not a multiple of 4
+/// * If the destination `blocks_buffer` is too small to hold the compressed data of a candidate
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::{compress_rgba8_target_quality, QualityTarget},
+///     BC7Settings, CompressionVariant, ImageView,
+/// };
+///
+/// let rgba_data = vec![0u8; 256 * 256 * 4];
+/// let width = 256;
+/// let height = 256;
+/// let candidates = [
+///     CompressionVariant::BC7(BC7Settings::alpha_ultrafast()),
+///     CompressionVariant::BC7(BC7Settings::alpha_fast()),
+///     CompressionVariant::BC7(BC7Settings::alpha_slow()),
+/// ];
+///
+/// let mut blocks_buffer = vec![0u8; candidates[0].blocks_byte_size(width, height)];
+///
+/// let used = compress_rgba8_target_quality(
+///     &candidates,
+///     QualityTarget::Psnr(45.0),
+///     ImageView::packed(&rgba_data, width, height),
+///     &mut blocks_buffer,
+/// );
+/// ```
+pub fn compress_rgba8_target_quality(
+    candidates: &[CompressionVariant],
+    target: QualityTarget,
+    image: ImageView<'_, u8>,
+    blocks_buffer: &mut [u8],
+) -> CompressionVariant {
+    image.assert_rgba();
+    assert!(!candidates.is_empty(), "at least one candidate is needed");
+    assert_eq!(image.height() % 4, 0);
+    assert_eq!(image.width() % 4, 0);
+
+    let samples = gather_sample_windows(image);
+
+    let selected = candidates[..candidates.len() - 1]
+        .iter()
+        .copied()
+        .find(|&candidate| reaches_target(candidate, &samples, target))
+        .unwrap_or(candidates[candidates.len() - 1]);
+
+    compress_rgba8(selected, image, blocks_buffer);
+
+    selected
+}
+
+/// Collects views of evenly distributed windows of the SSIM grid. The windows at the right and
+/// bottom edge are smaller if the size is not a multiple of the window size.
+///
+/// The samples form a grid with separate steps in both directions, so they cover all columns
+/// and rows of the image. Every sample is the window at the center of its grid cell.
+fn gather_sample_windows(image: ImageView<'_, u8>) -> Vec<ImageView<'_, u8>> {
+    let windows_x = image.width().div_ceil(SAMPLE_WINDOW) as usize;
+    let windows_y = image.height().div_ceil(SAMPLE_WINDOW) as usize;
+
+    // Starts with a square grid and hands the samples a narrow image can't use to the other
+    // direction.
+    let samples_y = windows_y.min(MAX_SAMPLE_WINDOWS / windows_x.clamp(1, SAMPLE_GRID_SIDE));
+    let samples_x = windows_x.min(MAX_SAMPLE_WINDOWS / samples_y.max(1));
+
+    (0..samples_y)
+        .flat_map(|sample_y| (0..samples_x).map(move |sample_x| (sample_x, sample_y)))
+        .map(|(sample_x, sample_y)| {
+            let x = ((2 * sample_x + 1) * windows_x / (2 * samples_x)) as u32 * SAMPLE_WINDOW;
+            let y = ((2 * sample_y + 1) * windows_y / (2 * samples_y)) as u32 * SAMPLE_WINDOW;
+
+            image.crop(
+                x,
+                y,
+                SAMPLE_WINDOW.min(image.width() - x),
+                SAMPLE_WINDOW.min(image.height() - y),
+            )
+        })
+        .collect()
+}
+
+/// Compresses and decodes every sampled window with the variant and checks whether the windows
+/// together reach the target.
+fn reaches_target(
+    variant: CompressionVariant,
+    samples: &[ImageView<'_, u8>],
+    target: QualityTarget,
+) -> bool {
+    if samples.is_empty() {
+        return true;
+    }
+
+    let channels = variant.channel_count() as usize;
+    let mut squared_error = 0.0;
+    let mut ssim = 0.0;
+
+    for window in samples {
+        let (width, height) = (window.width(), window.height());
+        let source = packed_rgba8(*window);
+
+        let mut blocks = vec![0u8; variant.blocks_byte_size(width, height)];
+        compress_rgba8(variant, *window, &mut blocks);
+
+        let mut decoded = vec![0u8; source.len()];
+        decompress_blocks_as_rgba8(
+            variant,
+            &blocks,
+            ImageViewMut::packed(&mut decoded, width, height),
+        );
+
+        // A window is a single SSIM window, so the stats hold the SSIM of the window.
+        ssim += compare_rgba8(variant, &source, &decoded, width, height).ssim;
+        for (original, decoded) in source.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+            for channel in 0..channels {
+                let diff = original[channel] as f64 - decoded[channel] as f64;
+                squared_error += diff * diff;
+            }
+        }
+    }
+
+    match target {
+        QualityTarget::Psnr(min_psnr) => {
+            let pixels: u32 = samples
+                .iter()
+                .map(|window| window.width() * window.height())
+                .sum();
+            let mse = squared_error / (pixels as usize * channels) as f64;
+            mse == 0.0 || 10.0 * (255.0 * 255.0 / mse).log10() >= min_psnr
+        }
+        QualityTarget::Ssim(min_ssim) => ssim / samples.len() as f64 >= min_ssim,
+    }
+}
+
+#[cfg(all(test, feature = "bc7"))]
+mod tests {
+    use super::*;
+    use crate::{decode::DiffStats, testgen::TestPattern, BC7Settings};
+
+    /// The stats of the whole image compressed with every candidate.
+    fn full_stats(
+        candidates: &[CompressionVariant],
+        rgba_data: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Vec<DiffStats> {
+        candidates
+            .iter()
+            .map(|&variant| {
+                let blocks = crate::test_util::compress(variant, rgba_data, width, height);
+                let decoded = crate::test_util::decode(variant, &blocks, width, height);
+                compare_rgba8(variant, rgba_data, &decoded, width, height)
+            })
+            .collect()
+    }
+
+    /// Checks that the selected candidate is the first one that reaches the target on the
+    /// whole image, for targets between the quality of the candidates.
+    fn check_selection(target: fn(f64) -> QualityTarget, metric: fn(&DiffStats) -> f64) {
+        // 5x3 windows with smaller windows at the right and bottom edge.
+        let (width, height) = (36, 20);
+        let rgba_data = TestPattern::Noise { seed: 7 }.generate_rgba8(width, height);
+        let candidates = [
+            CompressionVariant::BC7(BC7Settings::alpha_ultrafast()),
+            CompressionVariant::BC7(BC7Settings::alpha_fast()),
+            CompressionVariant::BC7(BC7Settings::alpha_slow()),
+        ];
+
+        let qualities: Vec<f64> = full_stats(&candidates, &rgba_data, width, height)
+            .iter()
+            .map(metric)
+            .collect();
+        assert!(qualities.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let targets = [
+            (qualities[0] - 0.001, 0),
+            ((qualities[0] + qualities[1]) / 2.0, 1),
+            ((qualities[1] + qualities[2]) / 2.0, 2),
+            // Unreachable targets fall back to the slowest candidate.
+            (qualities[2] + 1.0, 2),
+        ];
+        for (quality, expected) in targets {
+            let mut blocks = vec![0; candidates[0].blocks_byte_size(width, height)];
+            let selected = compress_rgba8_target_quality(
+                &candidates,
+                target(quality),
+                ImageView::packed(&rgba_data, width, height),
+                &mut blocks,
+            );
+            assert_eq!(selected, candidates[expected], "target {quality}");
+            assert!(expected == 2 || qualities[expected] >= quality);
+            assert_eq!(
+                blocks,
+                crate::test_util::compress(selected, &rgba_data, width, height)
+            );
+        }
+    }
+
+    #[test]
+    fn test_psnr_target() {
+        check_selection(QualityTarget::Psnr, |stats| stats.psnr);
+    }
+
+    #[test]
+    fn test_ssim_target() {
+        check_selection(QualityTarget::Ssim, |stats| stats.ssim);
+    }
+
+    #[test]
+    fn test_samples_cover_columns_and_rows() {
+        for (width, height) in [(2048, 2048), (1024, 1024), (4096, 64), (32, 4096)] {
+            let rgba_data = vec![0; width as usize * height as usize * 4];
+            let samples = gather_sample_windows(ImageView::packed(&rgba_data, width, height));
+            assert_eq!(samples.len(), MAX_SAMPLE_WINDOWS, "{width}x{height}");
+
+            // The offset of the view data encodes the position of the window.
+            let positions: Vec<(usize, usize)> = samples
+                .iter()
+                .map(|window| {
+                    let offset = window.data().as_ptr() as usize - rgba_data.as_ptr() as usize;
+                    (
+                        offset % (width as usize * 4) / 4,
+                        offset / (width as usize * 4),
+                    )
+                })
+                .collect();
+            let mut columns: Vec<usize> = positions.iter().map(|&(x, _)| x).collect();
+            let mut rows: Vec<usize> = positions.iter().map(|&(_, y)| y).collect();
+            columns.sort_unstable();
+            columns.dedup();
+            rows.sort_unstable();
+            rows.dedup();
+
+            let windows_x = (width / SAMPLE_WINDOW) as usize;
+            let windows_y = (height / SAMPLE_WINDOW) as usize;
+            assert_eq!(columns.len() * rows.len(), MAX_SAMPLE_WINDOWS);
+            assert!(columns.len() == windows_x || columns.len() >= SAMPLE_GRID_SIDE);
+            assert!(rows.len() == windows_y || rows.len() >= SAMPLE_GRID_SIDE);
+            // The samples spread over the whole image.
+            assert!(columns[columns.len() - 1] >= width as usize / 2);
+            assert!(rows[rows.len() - 1] >= height as usize / 2);
+        }
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_sampled_selection_reaches_target() {
+        // 4x128 windows, more than are sampled. Only every other column of windows holds noise,
+        // the rest is flat, so samples that miss these columns pick the fast candidate.
+        let (width, height) = (32, 1024);
+        let noise = TestPattern::Noise { seed: 3 }.generate_rgba8(width, height);
+        let rgba_data: Vec<u8> = noise
+            .chunks_exact(4)
+            .enumerate()
+            .flat_map(|(index, texel)| {
+                let column = index as u32 % width / SAMPLE_WINDOW;
+                if column % 2 == 1 {
+                    [texel[0], texel[1], texel[2], 255]
+                } else {
+                    [128, 128, 128, 255]
+                }
+            })
+            .collect();
+        let candidates = [
+            CompressionVariant::BC1,
+            CompressionVariant::BC7(BC7Settings::opaque_ultra_fast()),
+        ];
+
+        let psnr: Vec<f64> = full_stats(&candidates, &rgba_data, width, height)
+            .iter()
+            .map(|stats| stats.psnr)
+            .collect();
+        let target = (psnr[0] + psnr[1]) / 2.0;
+
+        let mut blocks = vec![0; candidates[1].blocks_byte_size(width, height)];
+        let selected = compress_rgba8_target_quality(
+            &candidates,
+            QualityTarget::Psnr(target),
+            ImageView::packed(&rgba_data, width, height),
+            &mut blocks,
+        );
+        assert_eq!(selected, candidates[1]);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use super::batch::{compress_batch_with_callback, BatchItem, BatchOrder};
+use crate::{CompressionVariant, EdgeFill, ImageView};
+
+/// The width and height of the payload of a virtual texture page in pixels, without its border.
+pub const PAGE_SIZE: u32 = 128;
+
+/// A page of a source texture that is compressed into a [`PageAtlas`] by [`compress_pages()`].
+#[derive(Copy, Clone, Debug)]
+pub struct VirtualPage<'a> {
+    /// The ID of the page in the page table, for example its packed virtual address.
+    pub id: u64,
+    /// View into the RGBA8 pixel data of the whole source texture.
+    pub texture: ImageView<'a, u8>,
+    /// The column of the page in the source texture. The page starts at pixel `x * PAGE_SIZE`.
+    pub x: u32,
+    /// The row of the page in the source texture. The page starts at pixel `y * PAGE_SIZE`.
+    pub y: u32,
+}
+
+/// How the pages of a [`PageAtlas`] are compressed.
+#[derive(Copy, Clone, Debug)]
+pub struct PageAtlasSettings {
+    /// The block compression format of all pages.
+    pub variant: CompressionVariant,
+    /// The number of pixels of the neighboring pages that are stored around every page, so that
+    /// filtering at the page edges doesn't need the neighboring page. `PAGE_SIZE + 2 * border`
+    /// needs to be a multiple of 4, which is the case for even borders.
+    pub border: u32,
+    /// How pixels outside of the source texture are filled. These are the borders at the edge of
+    /// the texture and the parts of pages that extend past the texture.
+    pub edge_fill: EdgeFill,
+}
+
+impl PageAtlasSettings {
+    /// Creates the settings of pages without a border.
+    pub fn new(variant: CompressionVariant) -> Self {
+        Self {
+            variant,
+            border: 0,
+            edge_fill: EdgeFill::default(),
+        }
+    }
+
+    /// Returns the width and height of a page including its border in pixels.
+    pub const fn page_size(&self) -> u32 {
+        PAGE_SIZE + 2 * self.border
+    }
+
+    /// Returns the size of the blocks of a single page in bytes.
+    pub const fn page_byte_size(&self) -> usize {
+        self.variant
+            .blocks_byte_size(self.page_size(), self.page_size())
+    }
+}
+
+/// The compressed pages of a virtual texture.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PageAtlas {
+    /// The blocks of all pages, stored one page after another in the order of the pages. The
+    /// blocks of every page are stored row by row, like the blocks of a texture of
+    /// [`PageAtlasSettings::page_size()`] pixels.
+    pub blocks: Vec<u8>,
+    /// Maps the ID of every page to the byte offset of its blocks in `blocks`.
+    pub page_table: BTreeMap<u64, usize>,
+}
+
+/// Compresses pages of various source textures into a single page atlas.
+///
+/// Virtual texturing streams fixed-size pages of BC blocks into a physical page cache on the
+/// GPU and looks them up through a page table. The returned atlas stores the blocks of every
+/// page in a layout that can be uploaded directly into a slot of such a cache, together with the
+/// offset of every page. The pages are compressed as one batch, see [`compress_batch()`] for how
+/// the work is scheduled.
+///
+/// # Panics
+/// - If `PAGE_SIZE + 2 * border` is not a multiple of 4
+/// - If a page starts outside of its source texture
+/// - If two pages have the same ID
+///
+/// # Example
+/// ```
+/// use block_compression::{
+///     encode::{compress_pages, PageAtlasSettings, VirtualPage},
+///     CompressionVariant, ImageView,
+/// };
+///
+/// let albedo = vec![0u8; 256 * 256 * 4];
+/// let texture = ImageView::packed(&albedo, 256, 256);
+///
+/// let pages: Vec<VirtualPage> = (0..4)
+///     .map(|index| VirtualPage {
+///         id: 0x100 + index as u64,
+///         texture,
+///         x: index % 2,
+///         y: index / 2,
+///     })
+///     .collect();
+///
+/// let settings = PageAtlasSettings {
+///     border: 4,
+///     ..PageAtlasSettings::new(CompressionVariant::BC1)
+/// };
+/// let atlas = compress_pages(&pages, &settings);
+///
+/// // Pages of 136x136 pixels with 34x34 blocks of 8 bytes.
+/// assert_eq!(atlas.blocks.len(), 4 * 34 * 34 * 8);
+/// assert_eq!(atlas.page_table[&0x102], 2 * 34 * 34 * 8);
+/// ```
+///
+/// [`compress_batch()`]: super::compress_batch
+pub fn compress_pages(pages: &[VirtualPage<'_>], settings: &PageAtlasSettings) -> PageAtlas {
+    let page_size = settings.page_size();
+    assert_eq!(
+        page_size % 4,
+        0,
+        "pages with a border of {} pixels are not a multiple of 4",
+        settings.border
+    );
+
+    let page_byte_size = settings.page_byte_size();
+
+    let mut page_table = BTreeMap::new();
+    for (index, page) in pages.iter().enumerate() {
+        let previous = page_table.insert(page.id, index * page_byte_size);
+        assert!(previous.is_none(), "page ID {} is not unique", page.id);
+    }
+
+    let page_data: Vec<Vec<u8>> = pages
+        .iter()
+        .map(|page| extract_page(page, settings))
+        .collect();
+
+    let items: Vec<BatchItem> = page_data
+        .iter()
+        .map(|data| {
+            BatchItem::new(
+                settings.variant,
+                ImageView::packed(data, page_size, page_size),
+            )
+        })
+        .collect();
+
+    let mut blocks = vec![0; pages.len() * page_byte_size];
+    compress_batch_with_callback(&items, BatchOrder::Completion, |index, page_blocks| {
+        let offset = index * page_byte_size;
+        blocks[offset..offset + page_byte_size].copy_from_slice(&page_blocks);
+    });
+
+    PageAtlas { blocks, page_table }
+}
+
+/// Copies the pixels of a page and its border into a tightly packed image.
+fn extract_page(page: &VirtualPage<'_>, settings: &PageAtlasSettings) -> Vec<u8> {
+    let texture = page.texture;
+    assert!(
+        page.x as u64 * (PAGE_SIZE as u64) < texture.width() as u64
+            && page.y as u64 * (PAGE_SIZE as u64) < texture.height() as u64,
+        "page ({}, {}) is outside of the texture",
+        page.x,
+        page.y
+    );
+
+    let page_size = settings.page_size() as i64;
+    let origin_x = page.x as i64 * PAGE_SIZE as i64 - settings.border as i64;
+    let origin_y = page.y as i64 * PAGE_SIZE as i64 - settings.border as i64;
+
+    let fill = match settings.edge_fill {
+        EdgeFill::SolidColor(color) => color,
+        _ => [0; 4],
+    };
+
+    let mut data = Vec::with_capacity((page_size * page_size * 4) as usize);
+    for y in origin_y..origin_y + page_size {
+        let row = edge_coordinate(settings.edge_fill, y, texture.height()).map(|y| texture.row(y));
+
+        for x in origin_x..origin_x + page_size {
+            match (row, edge_coordinate(settings.edge_fill, x, texture.width())) {
+                (Some(row), Some(x)) => {
+                    let x = x as usize * 4;
+                    data.extend_from_slice(&row[x..x + 4]);
+                }
+                _ => data.extend_from_slice(&fill),
+            }
+        }
+    }
+
+    data
+}
+
+/// Maps a coordinate that can lie outside of the texture on any side to a pixel of the texture,
+/// or returns `None` if the pixel is filled with a fixed color.
+fn edge_coordinate(edge_fill: EdgeFill, coordinate: i64, size: u32) -> Option<u32> {
+    let size = size as i64;
+    let coordinate = match edge_fill {
+        _ if (0..size).contains(&coordinate) => coordinate,
+        EdgeFill::ClampToEdge => coordinate.clamp(0, size - 1),
+        EdgeFill::Mirror => {
+            let period = coordinate.rem_euclid(2 * size);
+            if period < size {
+                period
+            } else {
+                2 * size - 1 - period
+            }
+        }
+        EdgeFill::Zero | EdgeFill::SolidColor(..) => return None,
+    };
+
+    Some(coordinate as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "bc15")]
+    use crate::encode::compress_rgba8;
+
+    #[test]
+    fn test_edge_coordinate() {
+        assert_eq!(edge_coordinate(EdgeFill::ClampToEdge, -3, 8), Some(0));
+        assert_eq!(edge_coordinate(EdgeFill::ClampToEdge, 9, 8), Some(7));
+        assert_eq!(edge_coordinate(EdgeFill::Mirror, -1, 8), Some(0));
+        assert_eq!(edge_coordinate(EdgeFill::Mirror, -3, 8), Some(2));
+        assert_eq!(edge_coordinate(EdgeFill::Mirror, 9, 8), Some(6));
+        assert_eq!(edge_coordinate(EdgeFill::Zero, 5, 8), Some(5));
+        assert_eq!(edge_coordinate(EdgeFill::Zero, -1, 8), None);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_page_atlas() {
+        let (width, height) = (256, 200);
+        let texels: Vec<u8> = (0..width * height * 4)
+            .map(|index| (index * 13 % 251) as u8)
+            .collect();
+        let texture = ImageView::packed(&texels, width, height);
+
+        let pages =
+            [(9, 1, 0), (4, 0, 1), (7, 0, 0)].map(|(id, x, y)| VirtualPage { id, texture, x, y });
+        let settings = PageAtlasSettings {
+            border: 2,
+            edge_fill: EdgeFill::SolidColor([1, 2, 3, 4]),
+            ..PageAtlasSettings::new(CompressionVariant::BC3)
+        };
+
+        // The border of the second column holds the last two columns of the first page.
+        let page = extract_page(&pages[0], &settings);
+        let page_view = ImageView::packed(&page, 132, 132);
+        assert_eq!(page_view.row(2)[..8], texture.row(0)[126 * 4..128 * 4]);
+        assert_eq!(page_view.row(0)[8..12], [1, 2, 3, 4]);
+
+        // The second row extends past the bottom of the texture.
+        let page = extract_page(&pages[1], &settings);
+        let page_view = ImageView::packed(&page, 132, 132);
+        assert_eq!(page_view.row(73)[8..12], texture.row(199)[..4]);
+        assert_eq!(page_view.row(74)[8..12], [1, 2, 3, 4]);
+
+        let atlas = compress_pages(&pages, &settings);
+        let page_byte_size = settings.page_byte_size();
+        assert_eq!(atlas.blocks.len(), 3 * page_byte_size);
+        assert_eq!(
+            atlas.page_table.into_iter().collect::<Vec<_>>(),
+            [(4, page_byte_size), (7, 2 * page_byte_size), (9, 0)]
+        );
+
+        for (index, page) in pages.iter().enumerate() {
+            let data = extract_page(page, &settings);
+            let mut blocks = vec![0; page_byte_size];
+            compress_rgba8(
+                settings.variant,
+                ImageView::packed(&data, 132, 132),
+                &mut blocks,
+            );
+
+            let offset = index * page_byte_size;
+            assert_eq!(atlas.blocks[offset..offset + page_byte_size], blocks);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Little-endian serialization of compressed blocks.
+//!
+//! All block compression formats are defined as little-endian byte streams. The encoders of this
+//! crate build their blocks from `u32` words, and the GPU compressor writes `u32` words into its
+//! destination buffer, which are little-endian on every GPU. These helpers convert between the
+//! words and the bytes explicitly, so the blocks are identical on little- and big-endian hosts.
+//! Use them instead of casting the words with `bytemuck`, which keeps the native byte order.
+//!
+//! The crate is only tested on little-endian hosts. The tests of this module check the byte
+//! layout of the blocks against fixed values, so they catch a missing conversion when they are
+//! run on a big-endian host, for example under QEMU.
+
+/// Writes `words` as little-endian bytes into the start of `bytes`.
+///
+/// # Panics
+/// - If `bytes` is smaller than `words.len() * 4`
+#[inline]
+pub fn write_words_le(words: &[u32], bytes: &mut [u8]) {
+    assert!(
+        bytes.len() >= words.len() * 4,
+        "bytes size ({}) is too small to hold {} words",
+        bytes.len(),
+        words.len()
+    );
+
+    for (chunk, &word) in bytes.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Reads little-endian words from the start of `bytes` into `words`.
+///
+/// # Panics
+/// - If `bytes` is smaller than `words.len() * 4`
+#[inline]
+pub fn read_words_le(bytes: &[u8], words: &mut [u32]) {
+    assert!(
+        bytes.len() >= words.len() * 4,
+        "bytes size ({}) is too small to read {} words",
+        bytes.len(),
+        words.len()
+    );
+
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+}
+
+/// Returns the little-endian bytes of `words`.
+pub fn words_to_bytes_le(words: &[u32]) -> Vec<u8> {
+    let mut bytes = vec![0; words.len() * 4];
+    write_words_le(words, &mut bytes);
+    bytes
+}
+
+/// Returns the words of little-endian `bytes`.
+///
+/// # Panics
+/// - If the length of `bytes` is not a multiple of 4
+pub fn bytes_to_words_le(bytes: &[u8]) -> Vec<u32> {
+    assert_eq!(bytes.len() % 4, 0, "bytes size must be a multiple of 4");
+
+    let mut words = vec![0; bytes.len() / 4];
+    read_words_le(bytes, &mut words);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_order() {
+        let words = [0x04030201, 0x08070605];
+        let bytes = words_to_bytes_le(&words);
+
+        assert_eq!(bytes, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(bytes_to_words_le(&bytes), words);
+    }
+
+    #[cfg(feature = "bc15")]
+    #[test]
+    fn test_bc1_block_layout() {
+        use crate::{encode::compress_rgba8, CompressionVariant, ImageView};
+
+        // A solid block stores its color as a little-endian RGB565 endpoint.
+        let rgba_data = [255, 0, 0, 255].repeat(16);
+        let mut blocks = [0; 8];
+        compress_rgba8(
+            CompressionVariant::BC1,
+            ImageView::packed(&rgba_data, 4, 4),
+            &mut blocks,
+        );
+
+        let endpoints = [
+            u16::from_le_bytes([blocks[0], blocks[1]]),
+            u16::from_le_bytes([blocks[2], blocks[3]]),
+        ];
+        assert!(endpoints.contains(&0xF800), "{endpoints:04X?}");
+    }
+}
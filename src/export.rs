@@ -0,0 +1,511 @@
+//! Export of compressed textures with a JSON manifest.
+//!
+//! A pragmatic interchange format for engines that sideload compressed textures outside of DDS or
+//! KTX containers. Every texture is written as a raw payload file containing the blocks of all of
+//! its mip levels, starting with the base level. A `manifest.json` next to the payloads describes
+//! them:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "textures": [
+//!     {
+//!       "name": "albedo",
+//!       "file": "albedo.bin",
+//!       "variant": "bc7",
+//!       "width": 256,
+//!       "height": 256,
+//!       "block_byte_size": 16,
+//!       "mips": [
+//!         { "width": 256, "height": 256, "offset": 0, "size": 65536, "hash": "fnv1a64:..." }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The hash is the 64-bit FNV-1a hash of the blocks of the mip level as a hexadecimal string.
+//!
+//! The module also converts compressed textures back into standard image formats, to inspect
+//! the result of a compression or to round-trip textures without the compressor CLI:
+//! [`to_png()`] behind the `png` feature and [`to_exr()`] behind the `exr` feature.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(any(feature = "exr", feature = "png"))]
+use crate::{decode::decompress_blocks_as_rgba8, ImageViewMut};
+use crate::{fingerprint::fnv1a64, CompressionVariant};
+
+/// The version of the manifest format.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// A compressed texture that should be exported.
+#[derive(Copy, Clone, Debug)]
+pub struct ExportTexture<'a> {
+    /// The name of the texture. Also used as the file stem of the payload.
+    pub name: &'a str,
+    /// The block compression variant of the blocks.
+    pub variant: CompressionVariant,
+    /// The width of the base mip level in pixels.
+    pub width: u32,
+    /// The height of the base mip level in pixels.
+    pub height: u32,
+    /// The blocks of every mip level, starting with the base level.
+    pub mip_levels: &'a [&'a [u8]],
+}
+
+impl ExportTexture<'_> {
+    fn mip_dimensions(&self, mip_level: u32) -> (u32, u32) {
+        (
+            u32::max(self.width >> mip_level, 1),
+            u32::max(self.height >> mip_level, 1),
+        )
+    }
+
+    fn validate(&self) {
+        assert!(
+            !self.name.is_empty() && !self.name.contains(['/', '\\']),
+            "texture name must be a valid file stem"
+        );
+        assert!(
+            !self.mip_levels.is_empty(),
+            "texture needs at least one mip level"
+        );
+        assert!(
+            self.mip_levels.len() <= 32,
+            "texture has more mip levels than possible"
+        );
+
+        for (mip_level, blocks) in self.mip_levels.iter().enumerate() {
+            let (width, height) = self.mip_dimensions(mip_level as u32);
+            assert_eq!(
+                blocks.len(),
+                self.variant.blocks_byte_size(width, height),
+                "mip level {mip_level} of texture {} has not the expected size",
+                self.name
+            );
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}.bin", self.name)
+    }
+}
+
+/// Writes the payload of every texture and the `manifest.json` into `directory`.
+///
+/// The directory is created if it doesn't exist yet. Existing files are overwritten.
+///
+/// # Panics
+/// - If a texture has no mip levels, or a mip level has not the expected size
+/// - If the name of a texture is empty or contains a path separator
+pub fn export_textures(directory: &Path, textures: &[ExportTexture<'_>]) -> io::Result<()> {
+    textures.iter().for_each(ExportTexture::validate);
+
+    fs::create_dir_all(directory)?;
+
+    for texture in textures {
+        let mut file = BufWriter::new(File::create(directory.join(texture.file_name()))?);
+        for blocks in texture.mip_levels {
+            file.write_all(blocks)?;
+        }
+        file.flush()?;
+    }
+
+    let mut manifest = BufWriter::new(File::create(directory.join("manifest.json"))?);
+    write_manifest(&mut manifest, textures)?;
+    manifest.flush()
+}
+
+/// Writes only the JSON manifest of the given textures.
+///
+/// Useful if the payloads are stored by other means, for example inside an asset archive.
+///
+/// # Panics
+/// - For the same reasons as [`export_textures()`]
+pub fn write_manifest(writer: &mut impl Write, textures: &[ExportTexture<'_>]) -> io::Result<()> {
+    textures.iter().for_each(ExportTexture::validate);
+
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"version\": {MANIFEST_VERSION},")?;
+    writeln!(writer, "  \"textures\": [")?;
+
+    for (index, texture) in textures.iter().enumerate() {
+        writeln!(writer, "    {{")?;
+        writeln!(writer, "      \"name\": {},", json_string(texture.name))?;
+        writeln!(
+            writer,
+            "      \"file\": {},",
+            json_string(&texture.file_name())
+        )?;
+        writeln!(writer, "      \"variant\": \"{}\",", texture.variant.name())?;
+        writeln!(writer, "      \"width\": {},", texture.width)?;
+        writeln!(writer, "      \"height\": {},", texture.height)?;
+        writeln!(
+            writer,
+            "      \"block_byte_size\": {},",
+            texture.variant.block_byte_size()
+        )?;
+        writeln!(writer, "      \"mips\": [")?;
+
+        let mut offset = 0;
+        for (mip_level, blocks) in texture.mip_levels.iter().enumerate() {
+            let (width, height) = texture.mip_dimensions(mip_level as u32);
+            let separator = separator(mip_level, texture.mip_levels.len());
+            writeln!(
+                writer,
+                "        {{ \"width\": {width}, \"height\": {height}, \"offset\": {offset}, \"size\": {}, \"hash\": \"fnv1a64:{:016x}\" }}{separator}",
+                blocks.len(),
+                fnv1a64(blocks)
+            )?;
+            offset += blocks.len();
+        }
+
+        writeln!(writer, "      ]")?;
+        writeln!(writer, "    }}{}", separator(index, textures.len()))?;
+    }
+
+    writeln!(writer, "  ]")?;
+    writeln!(writer, "}}")
+}
+
+const fn separator(index: usize, count: usize) -> &'static str {
+    if index + 1 < count {
+        ","
+    } else {
+        ""
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut string = String::with_capacity(value.len() + 2);
+    string.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+    string.push('"');
+    string
+}
+
+/// Decodes the blocks and writes them as an 8-bit RGBA PNG file.
+///
+/// BC6H blocks are decoded as unsigned and clamped into the displayable range. Use [`to_exr()`]
+/// to keep their full range.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+pub fn to_png(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_png(&mut file, variant, blocks_data, width, height)?;
+    file.flush()
+}
+
+/// Decodes the blocks and writes them as an 8-bit RGBA PNG image into `writer`.
+///
+/// # Panics
+/// - For the same reasons as [`to_png()`]
+#[cfg(feature = "png")]
+#[cfg_attr(docsrs, doc(cfg(feature = "png")))]
+pub fn write_png(
+    writer: &mut impl Write,
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let rgba_data = decode_rgba8(variant, blocks_data, width, height);
+
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut png_writer = encoder.write_header()?;
+    png_writer.write_image_data(&rgba_data)?;
+    png_writer.finish()?;
+
+    Ok(())
+}
+
+/// Decodes the blocks and writes them as an uncompressed OpenEXR file with 32-bit float RGBA
+/// channels.
+///
+/// BC6H blocks are decoded as unsigned and keep their full range. All other variants are written
+/// with their normalized values from 0.0 to 1.0, without any color space conversion.
+///
+/// # Panics
+/// - The `blocks_data` has not the expected size (`variant.blocks_byte_size()`)
+#[cfg(feature = "exr")]
+#[cfg_attr(docsrs, doc(cfg(feature = "exr")))]
+pub fn to_exr(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_exr(&mut file, variant, blocks_data, width, height)?;
+    file.flush()
+}
+
+/// Decodes the blocks and writes them as an uncompressed OpenEXR image into `writer`.
+///
+/// # Panics
+/// - For the same reasons as [`to_exr()`]
+#[cfg(feature = "exr")]
+#[cfg_attr(docsrs, doc(cfg(feature = "exr")))]
+pub fn write_exr(
+    writer: &mut impl Write,
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let rgba_data = decode_rgba32f(variant, blocks_data, width, height);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&EXR_MAGIC.to_le_bytes());
+    header.extend_from_slice(&EXR_VERSION.to_le_bytes());
+
+    // The channels need to be sorted by name.
+    let mut channels = Vec::new();
+    for name in EXR_CHANNELS {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&EXR_PIXEL_TYPE_FLOAT.to_le_bytes());
+        channels.extend_from_slice(&[0; 4]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+
+    write_exr_attribute(&mut header, "channels", "chlist", &channels);
+    write_exr_attribute(&mut header, "compression", "compression", &[0]);
+    write_exr_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_exr_attribute(&mut header, "displayWindow", "box2i", &window);
+    write_exr_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    write_exr_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    write_exr_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    write_exr_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // Every scan line is stored in its own chunk, which the offset table points to.
+    let line_size = width as usize * EXR_CHANNELS.len() * 4;
+    let chunk_size = (8 + line_size) as u64;
+    let chunks_offset = header.len() as u64 + height as u64 * 8;
+
+    writer.write_all(&header)?;
+    for y in 0..height as u64 {
+        writer.write_all(&(chunks_offset + y * chunk_size).to_le_bytes())?;
+    }
+
+    let mut line = Vec::with_capacity(line_size);
+    for (y, row) in rgba_data.chunks_exact(width as usize * 4).enumerate() {
+        line.clear();
+        for channel in EXR_CHANNEL_INDICES {
+            for texel in row.chunks_exact(4) {
+                line.extend_from_slice(&texel[channel].to_le_bytes());
+            }
+        }
+
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+        writer.write_all(&line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "exr")]
+const EXR_MAGIC: u32 = 20000630;
+#[cfg(feature = "exr")]
+const EXR_VERSION: u32 = 2;
+#[cfg(feature = "exr")]
+const EXR_PIXEL_TYPE_FLOAT: i32 = 2;
+#[cfg(feature = "exr")]
+const EXR_CHANNELS: [&str; 4] = ["A", "B", "G", "R"];
+/// The index of each channel of [`EXR_CHANNELS`] in an RGBA texel.
+#[cfg(feature = "exr")]
+const EXR_CHANNEL_INDICES: [usize; 4] = [3, 2, 1, 0];
+
+#[cfg(feature = "exr")]
+fn write_exr_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(any(feature = "exr", feature = "png"))]
+fn decode_rgba8(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut rgba_data = vec![0; width as usize * height as usize * 4];
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks_data,
+        ImageViewMut::packed(&mut rgba_data, width, height),
+    );
+    rgba_data
+}
+
+#[cfg(feature = "exr")]
+fn decode_rgba32f(
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    #[cfg(feature = "bc6h")]
+    if variant.is_hdr() {
+        let mut rgba_data = vec![0.0; width as usize * height as usize * 4];
+        crate::decode::decompress_blocks_as_rgba32f(
+            variant,
+            blocks_data,
+            ImageViewMut::packed(&mut rgba_data, width, height),
+        );
+        return rgba_data;
+    }
+
+    decode_rgba8(variant, blocks_data, width, height)
+        .into_iter()
+        .map(|value| value as f32 / 255.0)
+        .collect()
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a64() {
+        assert_eq!(fnv1a64(b""), 0xCBF29CE484222325);
+        assert_eq!(fnv1a64(b"a"), 0xAF63DC4C8601EC8C);
+    }
+
+    #[test]
+    fn test_write_manifest() {
+        let base = [0u8; 32];
+        let mip = [0u8; 8];
+        let textures = [ExportTexture {
+            name: "bricks \"red\"",
+            variant: CompressionVariant::BC1,
+            width: 8,
+            height: 8,
+            mip_levels: &[&base, &mip],
+        }];
+
+        let mut manifest = Vec::new();
+        write_manifest(&mut manifest, &textures).unwrap();
+        let manifest = String::from_utf8(manifest).unwrap();
+
+        assert!(manifest.contains("\"name\": \"bricks \\\"red\\\"\","));
+        assert!(manifest.contains("\"variant\": \"bc1\","));
+        assert!(manifest.contains(
+            "{ \"width\": 4, \"height\": 4, \"offset\": 32, \"size\": 8, \"hash\": \"fnv1a64:"
+        ));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_write_png() {
+        use crate::{encode::compress_rgba8, testgen::TestPattern, ImageView};
+
+        let (width, height) = (12, 8);
+        let rgba_data = TestPattern::AlphaRamp.generate_rgba8(width, height);
+        let variant = CompressionVariant::RGBA8;
+        let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+        compress_rgba8(
+            variant,
+            ImageView::packed(&rgba_data, width, height),
+            &mut blocks,
+        );
+
+        let mut png_data = Vec::new();
+        write_png(&mut png_data, variant, &blocks, width, height).unwrap();
+
+        let mut reader = png::Decoder::new(io::Cursor::new(png_data))
+            .read_info()
+            .unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+
+        assert_eq!((info.width, info.height), (width, height));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(decoded, rgba_data);
+    }
+
+    #[cfg(feature = "exr")]
+    #[test]
+    fn test_write_exr() {
+        let (width, height) = (8, 4);
+        let variant = CompressionVariant::RGBA8;
+        let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+        blocks[4..8].copy_from_slice(&[255, 0, 51, 102]);
+
+        let mut exr_data = Vec::new();
+        write_exr(&mut exr_data, variant, &blocks, width, height).unwrap();
+
+        assert_eq!(exr_data[..4], EXR_MAGIC.to_le_bytes());
+
+        let line_size = width as usize * 4 * 4;
+        let header_size = exr_data.len() - height as usize * (8 + 8 + line_size);
+        let offset = |y: usize| {
+            let start = header_size + y * 8;
+            u64::from_le_bytes(exr_data[start..start + 8].try_into().unwrap()) as usize
+        };
+        assert_eq!(offset(1) - offset(0), 8 + line_size);
+
+        // The second texel of the first scan line, stored as planar A, B, G and R channels.
+        let line = &exr_data[offset(0)..offset(1)];
+        assert_eq!(line[..4], 0i32.to_le_bytes());
+        let value = |channel: usize| {
+            let start = 8 + (channel * width as usize + 1) * 4;
+            f32::from_le_bytes(line[start..start + 4].try_into().unwrap())
+        };
+        assert_eq!(value(0), 0.4);
+        assert_eq!(value(1), 0.2);
+        assert_eq!(value(2), 0.0);
+        assert_eq!(value(3), 1.0);
+    }
+}
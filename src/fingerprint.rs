@@ -0,0 +1,174 @@
+//! Stable fingerprints of compressed blocks.
+//!
+//! The fingerprints use the FNV-1a hash, which is simple, fast and fully specified, so the same
+//! blocks produce the same fingerprint on every machine, platform and version of this crate.
+//! The hashed data is the lowercase name of the variant (for example `"bc7"`), a single zero
+//! byte and the blocks.
+//!
+//! For validating the integrity of blocks after a transfer, the module also provides the CRC-32
+//! checksum (IEEE 802.3, as used by zlib and PNG) of single mip levels.
+
+use crate::{mip_dimension, CompressionVariant};
+
+const FNV64_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+const FNV64_PRIME: u64 = 0x100000001B3;
+const FNV128_OFFSET_BASIS: u128 = 0x6C62272E07BB014262B821756295C58D;
+const FNV128_PRIME: u128 = 0x0000000001000000000000000000013B;
+const CRC32_POLYNOMIAL: u32 = 0xEDB88320;
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// Returns the 64-bit fingerprint of compressed blocks.
+///
+/// The variant is part of the fingerprint, so the same bytes compressed as different variants
+/// don't collide. The encoder settings are not, since they only influence the blocks.
+///
+/// # Example
+/// ```
+/// use block_compression::{fingerprint, CompressionVariant};
+///
+/// let blocks = vec![0u8; CompressionVariant::BC1.blocks_byte_size(4, 4)];
+/// assert_eq!(fingerprint(CompressionVariant::BC1, &blocks), 0xA8F3BD7D5730241B);
+/// ```
+pub fn fingerprint(variant: CompressionVariant, blocks: &[u8]) -> u64 {
+    let hash = fnv1a64_update(FNV64_OFFSET_BASIS, variant.name().as_bytes());
+    let hash = fnv1a64_update(hash, &[0]);
+    fnv1a64_update(hash, blocks)
+}
+
+/// Returns the 128-bit fingerprint of compressed blocks.
+///
+/// Works like [`fingerprint()`], but has a far lower chance of collisions for very large asset
+/// libraries.
+pub fn fingerprint128(variant: CompressionVariant, blocks: &[u8]) -> u128 {
+    [variant.name().as_bytes(), &[0], blocks]
+        .iter()
+        .flat_map(|data| data.iter())
+        .fold(FNV128_OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u128).wrapping_mul(FNV128_PRIME)
+        })
+}
+
+/// Returns the CRC-32 checksum of `data`.
+///
+/// # Example
+/// ```
+/// use block_compression::crc32;
+///
+/// assert_eq!(crc32(b"123456789"), 0xCBF43926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Returns the CRC-32 checksum of every mip level of a mip chain.
+///
+/// The mip levels need to be stored one after another in `blocks`, starting with the base level,
+/// like [`CompressionVariant::mip_blocks_offset()`] describes. Streaming systems can store the
+/// checksums next to the blocks and validate every mip level after a transfer without decoding
+/// it.
+///
+/// # Panics
+/// - If `blocks` is too small to hold `mip_level_count` mip levels
+pub fn mip_checksums(
+    variant: CompressionVariant,
+    width: u32,
+    height: u32,
+    mip_level_count: u32,
+    blocks: &[u8],
+) -> Vec<u32> {
+    let required_size = variant.mip_blocks_offset(width, height, mip_level_count);
+    assert!(
+        blocks.len() >= required_size,
+        "blocks size ({}) is too small to hold {mip_level_count} mip levels. Required size: {required_size}",
+        blocks.len()
+    );
+
+    (0..mip_level_count)
+        .map(|mip_level| {
+            let offset = variant.mip_blocks_offset(width, height, mip_level);
+            let size = variant.blocks_byte_size(
+                mip_dimension(width, mip_level),
+                mip_dimension(height, mip_level),
+            );
+            crc32(&blocks[offset..offset + size])
+        })
+        .collect()
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+/// Returns the 64-bit FNV-1a hash of `data`.
+#[cfg(feature = "export")]
+pub(crate) fn fnv1a64(data: &[u8]) -> u64 {
+    fnv1a64_update(FNV64_OFFSET_BASIS, data)
+}
+
+fn fnv1a64_update(hash: u64, data: &[u8]) -> u64 {
+    data.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV64_PRIME)
+    })
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a64_update(FNV64_OFFSET_BASIS, b"a"), 0xAF63DC4C8601EC8C);
+
+        let hash = b"a".iter().fold(FNV128_OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u128).wrapping_mul(FNV128_PRIME)
+        });
+        assert_eq!(hash, 0xD228CB696F1A8CAF78912B704E4A8964);
+    }
+
+    #[test]
+    fn test_mip_checksums() {
+        let variant = CompressionVariant::BC1;
+        let mut blocks = vec![0u8; variant.mip_blocks_offset(8, 8, 4)];
+        let checksums = mip_checksums(variant, 8, 8, 4, &blocks);
+        assert_eq!(checksums[0], crc32(&[0; 32]));
+        assert_eq!(checksums[1], crc32(&[0; 8]));
+
+        let offset = variant.mip_blocks_offset(8, 8, 2);
+        blocks[offset] = 1;
+        let changed = mip_checksums(variant, 8, 8, 4, &blocks);
+        assert_eq!(changed[..2], checksums[..2]);
+        assert_ne!(changed[2], checksums[2]);
+        assert_eq!(changed[3], checksums[3]);
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_variant() {
+        let blocks = [0u8; 16];
+        assert_ne!(
+            fingerprint(CompressionVariant::BC2, &blocks),
+            fingerprint(CompressionVariant::BC3, &blocks)
+        );
+        assert_ne!(
+            fingerprint128(CompressionVariant::BC2, &blocks),
+            fingerprint128(CompressionVariant::BC3, &blocks)
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use std::{
+    sync::{mpsc, Arc},
+    time::Instant,
+};
+
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoderDescriptor, ComputePassDescriptor, Device,
+    Extent3d, Maintain, MapMode, Origin3d, Queue, TexelCopyBufferLayout, TexelCopyTextureInfo,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor,
+};
+
+use crate::{
+    encode::compress_rgba8, ByteOffset, CompressionVariant, CompressorError, GpuBlockCompressor,
+    ImageView, TaskOptions,
+};
+
+/// A single texture that is compressed by the [`HybridScheduler`].
+#[derive(Copy, Clone, Debug)]
+pub struct HybridTask<'a> {
+    /// The block compression variant to use.
+    pub variant: CompressionVariant,
+    /// View into the source RGBA8 pixel data.
+    pub image: ImageView<'a, u8>,
+    /// Offset in bytes of the compressed blocks inside the destination buffer.
+    pub blocks_offset: usize,
+}
+
+impl HybridTask<'_> {
+    fn pixel_count(&self) -> f64 {
+        self.image.width() as f64 * self.image.height() as f64
+    }
+
+    fn blocks_byte_size(&self) -> usize {
+        self.variant
+            .blocks_byte_size(self.image.width(), self.image.height())
+    }
+}
+
+/// How much the output of the [`HybridScheduler`] may vary between runs.
+///
+/// The GPU and the CPU encoders produce slightly different blocks, and neither of them makes
+/// random choices. Whether the blocks of a task change between runs therefore only depends on
+/// which side compressed the task.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum EncoderDeterminism {
+    /// Tasks are split by the throughput that was measured in the previous batches. This is the
+    /// fastest option, but the split and thus the blocks of a task can change between runs.
+    #[default]
+    Adaptive,
+    /// Tasks are split only based on their size and the throughput that was set with
+    /// [`HybridScheduler::set_throughput()`], so the output is identical between runs on the
+    /// same device and driver. The measurements of the batches are not used.
+    Reproducible,
+    /// All tasks are compressed on the CPU. The output is identical on every machine and
+    /// independent of the number of threads.
+    BitExact,
+}
+
+/// Splits batches of compression tasks between the GPU compressor and the CPU encoders.
+///
+/// Meant for big offline bakes on machines with strong CPUs. Every batch is split based on the
+/// throughput that was measured for both sides in the previous batches, so that the GPU and the
+/// CPU finish at roughly the same time. The CPU side runs on its own thread while the GPU works
+/// and uses all cores if the `rayon` feature is enabled. Both sides write into the same output
+/// layout, so the result doesn't depend on where a task was compressed, except for the small
+/// differences between the GPU and CPU encoders. Use [`HybridScheduler::set_determinism()`] if
+/// those differences matter, for example when verifying the output of a bake.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut scheduler = HybridScheduler::new(device.clone(), queue.clone());
+///
+/// let mut blocks_offset = 0;
+/// let tasks: Vec<HybridTask> = images
+///     .iter()
+///     .map(|image| {
+///         let task = HybridTask {
+///             variant,
+///             image: ImageView::packed(&image.data, image.width, image.height),
+///             blocks_offset,
+///         };
+///         blocks_offset += variant.blocks_byte_size(image.width, image.height);
+///         task
+///     })
+///     .collect();
+///
+/// let mut blocks_buffer = vec![0; blocks_offset];
+/// scheduler.compress(&tasks, &mut blocks_buffer);
+/// ```
+pub struct HybridScheduler {
+    compressor: GpuBlockCompressor,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    gpu_throughput: Option<f64>,
+    cpu_throughput: Option<f64>,
+    determinism: EncoderDeterminism,
+}
+
+impl HybridScheduler {
+    /// Creates a new scheduler that dispatches its GPU work on the given device.
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self {
+            compressor: GpuBlockCompressor::new(device.clone(), queue.clone()),
+            device,
+            queue,
+            gpu_throughput: None,
+            cpu_throughput: None,
+            determinism: EncoderDeterminism::default(),
+        }
+    }
+
+    /// Rebuilds the GPU resources of the scheduler on a new device.
+    ///
+    /// While the device is lost, all tasks are compressed on the CPU. The measured throughput
+    /// is kept.
+    pub fn recreate(&mut self, device: Arc<Device>, queue: Arc<Queue>) {
+        self.compressor.recreate(device.clone(), queue.clone());
+        self.device = device;
+        self.queue = queue;
+    }
+
+    /// Returns the measured throughput of the GPU and the CPU in pixels per second.
+    ///
+    /// A side returns `None` until it compressed its first batch. Batches are only measured with
+    /// [`EncoderDeterminism::Adaptive`].
+    pub fn throughput(&self) -> (Option<f64>, Option<f64>) {
+        (self.gpu_throughput, self.cpu_throughput)
+    }
+
+    /// Sets the throughput of the GPU and the CPU in pixels per second.
+    ///
+    /// Allows starting with the measurements of a previous run, instead of splitting the first
+    /// batch evenly.
+    pub fn set_throughput(&mut self, gpu_pixels_per_second: f64, cpu_pixels_per_second: f64) {
+        self.gpu_throughput = Some(gpu_pixels_per_second);
+        self.cpu_throughput = Some(cpu_pixels_per_second);
+    }
+
+    /// Sets how much the output may vary between runs. Defaults to
+    /// [`EncoderDeterminism::Adaptive`].
+    pub fn set_determinism(&mut self, determinism: EncoderDeterminism) {
+        self.determinism = determinism;
+    }
+
+    /// Returns how much the output may vary between runs.
+    pub fn determinism(&self) -> EncoderDeterminism {
+        self.determinism
+    }
+
+    /// Compresses all tasks and writes their blocks into `blocks_buffer`.
+    ///
+    /// Blocks until all tasks are finished. If the GPU device is lost, the tasks of the GPU are
+    /// compressed on the CPU instead, even with [`EncoderDeterminism::Reproducible`].
+    ///
+    /// # Panics
+    /// - If the width or height of an image is not a multiple of 4
+    /// - If the `blocks_buffer` is too small to hold the compressed blocks of a task at its
+    ///   offset
+    pub fn compress(&mut self, tasks: &[HybridTask<'_>], blocks_buffer: &mut [u8]) {
+        for task in tasks {
+            assert_eq!(task.image.width() % 4, 0);
+            assert_eq!(task.image.height() % 4, 0);
+            assert!(
+                blocks_buffer.len() >= task.blocks_offset + task.blocks_byte_size(),
+                "blocks_buffer size ({}) is too small to hold compressed blocks at offset {}",
+                blocks_buffer.len(),
+                task.blocks_offset,
+            );
+        }
+
+        let (gpu_tasks, cpu_tasks) = self.split(tasks);
+
+        let (gpu_results, cpu_results) = std::thread::scope(|scope| {
+            let cpu_worker = scope.spawn(|| {
+                let start = Instant::now();
+                let results: Vec<Vec<u8>> = cpu_tasks
+                    .iter()
+                    .map(|task| {
+                        let mut blocks = vec![0; task.blocks_byte_size()];
+                        compress_rgba8(task.variant, task.image, &mut blocks);
+                        blocks
+                    })
+                    .collect();
+                (results, start.elapsed().as_secs_f64())
+            });
+
+            let start = Instant::now();
+            let gpu_results = self.compress_on_gpu(&gpu_tasks);
+            let gpu_seconds = start.elapsed().as_secs_f64();
+
+            let cpu_results = cpu_worker.join().expect("CPU worker panicked");
+            ((gpu_results, gpu_seconds), cpu_results)
+        });
+
+        let (gpu_blocks, gpu_seconds) = gpu_results;
+        let (cpu_blocks, cpu_seconds) = cpu_results;
+
+        let gpu_blocks = match gpu_blocks {
+            Ok(gpu_blocks) => {
+                if self.determinism == EncoderDeterminism::Adaptive {
+                    self.gpu_throughput =
+                        update_throughput(self.gpu_throughput, &gpu_tasks, gpu_seconds);
+                }
+                gpu_blocks
+            }
+            Err(CompressorError::DeviceLost { .. }) => {
+                for task in &gpu_tasks {
+                    let offset = task.blocks_offset;
+                    let blocks = &mut blocks_buffer[offset..offset + task.blocks_byte_size()];
+                    compress_rgba8(task.variant, task.image, blocks);
+                }
+                Vec::new()
+            }
+        };
+
+        for (task, blocks) in gpu_tasks
+            .iter()
+            .zip(gpu_blocks)
+            .chain(cpu_tasks.iter().zip(cpu_blocks))
+        {
+            blocks_buffer[task.blocks_offset..task.blocks_offset + blocks.len()]
+                .copy_from_slice(&blocks);
+        }
+
+        if self.determinism == EncoderDeterminism::Adaptive {
+            self.cpu_throughput = update_throughput(self.cpu_throughput, &cpu_tasks, cpu_seconds);
+        }
+    }
+
+    fn split<'a>(&self, tasks: &[HybridTask<'a>]) -> (Vec<HybridTask<'a>>, Vec<HybridTask<'a>>) {
+        let gpu_available = self.compressor.device_lost().is_none()
+            && self.determinism != EncoderDeterminism::BitExact;
+
+        split_tasks(
+            tasks,
+            self.gpu_throughput,
+            self.cpu_throughput,
+            gpu_available,
+            self.device.limits().max_texture_dimension_2d,
+        )
+    }
+
+    fn compress_on_gpu(
+        &mut self,
+        tasks: &[HybridTask<'_>],
+    ) -> Result<Vec<Vec<u8>>, CompressorError> {
+        if tasks.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(error) = self.compressor.device_lost() {
+            return Err(error);
+        }
+
+        let total_size: usize = tasks.iter().map(HybridTask::blocks_byte_size).sum();
+
+        let blocks_buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("hybrid blocks buffer"),
+            size: total_size as u64,
+            usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("hybrid staging buffer"),
+            size: total_size as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut offset = 0;
+        for task in tasks {
+            let (width, height) = (task.image.width(), task.image.height());
+            let size = Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+
+            let texture = self.device.create_texture(&TextureDescriptor {
+                label: Some("hybrid source texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+
+            self.queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                task.image.data(),
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(task.image.stride()),
+                    rows_per_image: Some(height),
+                },
+                size,
+            );
+
+            self.compressor.add_compression_task(
+                task.variant,
+                &texture.create_view(&TextureViewDescriptor::default()),
+                width,
+                height,
+                &blocks_buffer,
+                None,
+                Some(ByteOffset(offset as u64)),
+                TaskOptions::default(),
+            );
+
+            offset += task.blocks_byte_size();
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("hybrid command encoder"),
+            });
+
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("hybrid compute pass"),
+                timestamp_writes: None,
+            });
+            self.compressor.compress(&mut pass)?;
+        }
+
+        encoder.copy_buffer_to_buffer(&blocks_buffer, 0, &staging_buffer, 0, total_size as u64);
+        self.queue.submit([encoder.finish()]);
+
+        let buffer_slice = staging_buffer.slice(..);
+        let (tx, rx) = mpsc::channel();
+        buffer_slice.map_async(MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+
+        match rx.recv() {
+            Ok(Ok(())) => {}
+            _ => match self.compressor.device_lost() {
+                Some(error) => return Err(error),
+                None => panic!("couldn't read the compressed blocks from the GPU"),
+            },
+        }
+
+        let results = {
+            let data = buffer_slice.get_mapped_range();
+            let mut offset = 0;
+            tasks
+                .iter()
+                .map(|task| {
+                    let size = task.blocks_byte_size();
+                    let blocks = data[offset..offset + size].to_vec();
+                    offset += size;
+                    blocks
+                })
+                .collect()
+        };
+        staging_buffer.unmap();
+
+        Ok(results)
+    }
+}
+
+/// Assigns the largest tasks first to the side that would finish them earlier.
+///
+/// A side that was not measured yet is assumed to be as fast as the other side. Otherwise a
+/// batch that went entirely to one side would make that side infinitely faster than the
+/// unmeasured one, and all later batches would go to it as well.
+fn split_tasks<'a>(
+    tasks: &[HybridTask<'a>],
+    gpu_throughput: Option<f64>,
+    cpu_throughput: Option<f64>,
+    gpu_available: bool,
+    max_dimension: u32,
+) -> (Vec<HybridTask<'a>>, Vec<HybridTask<'a>>) {
+    let gpu_throughput = gpu_throughput.or(cpu_throughput).unwrap_or(1.0);
+    let cpu_throughput = cpu_throughput.unwrap_or(gpu_throughput);
+
+    let mut sorted = tasks.to_vec();
+    sorted.sort_by(|a, b| b.pixel_count().total_cmp(&a.pixel_count()));
+
+    let mut gpu_tasks = Vec::new();
+    let mut cpu_tasks = Vec::new();
+    let mut gpu_time = 0.0;
+    let mut cpu_time = 0.0;
+
+    for task in sorted {
+        let fits_gpu = gpu_available
+            && task.image.width() <= max_dimension
+            && task.image.height() <= max_dimension;
+        let gpu_finish = gpu_time + task.pixel_count() / gpu_throughput;
+        let cpu_finish = cpu_time + task.pixel_count() / cpu_throughput;
+
+        if fits_gpu && gpu_finish <= cpu_finish {
+            gpu_time = gpu_finish;
+            gpu_tasks.push(task);
+        } else {
+            cpu_time = cpu_finish;
+            cpu_tasks.push(task);
+        }
+    }
+
+    (gpu_tasks, cpu_tasks)
+}
+
+/// Blends a new throughput measurement into the previous one, so a single noisy batch doesn't
+/// skew the split too much.
+fn update_throughput(previous: Option<f64>, tasks: &[HybridTask<'_>], seconds: f64) -> Option<f64> {
+    if tasks.is_empty() || seconds <= 0.0 {
+        return previous;
+    }
+
+    let pixels: f64 = tasks.iter().map(HybridTask::pixel_count).sum();
+    let measured = pixels / seconds;
+
+    Some(match previous {
+        Some(previous) => previous * 0.5 + measured * 0.5,
+        None => measured,
+    })
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    fn tasks(rgba_data: &[u8], count: usize) -> Vec<HybridTask<'_>> {
+        (0..count)
+            .map(|index| HybridTask {
+                variant: CompressionVariant::BC1,
+                image: ImageView::packed(rgba_data, 16, 16),
+                blocks_offset: index * 128,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_after_single_task_batch() {
+        let rgba_data = vec![0; 16 * 16 * 4];
+
+        // Ties go to the GPU, so the first batch leaves the CPU unmeasured.
+        let single = tasks(&rgba_data, 1);
+        let (gpu_tasks, cpu_tasks) = split_tasks(&single, None, None, true, 8192);
+        assert_eq!((gpu_tasks.len(), cpu_tasks.len()), (1, 0));
+
+        let gpu_throughput = update_throughput(None, &gpu_tasks, 256.0 / 1e8);
+        let cpu_throughput = update_throughput(None, &cpu_tasks, 0.0);
+        assert!(cpu_throughput.is_none());
+
+        // The unmeasured CPU still receives tasks, so it gets measured in the next batch.
+        let batch = tasks(&rgba_data, 8);
+        let (gpu_tasks, cpu_tasks) =
+            split_tasks(&batch, gpu_throughput, cpu_throughput, true, 8192);
+        assert_eq!((gpu_tasks.len(), cpu_tasks.len()), (4, 4));
+
+        // The same holds for an unmeasured GPU.
+        let (gpu_tasks, cpu_tasks) = split_tasks(&batch, None, Some(1e8), true, 8192);
+        assert_eq!((gpu_tasks.len(), cpu_tasks.len()), (4, 4));
+    }
+
+    #[test]
+    fn test_split_by_throughput() {
+        let rgba_data = vec![0; 16 * 16 * 4];
+        let batch = tasks(&rgba_data, 8);
+
+        let (gpu_tasks, cpu_tasks) = split_tasks(&batch, Some(3.0), Some(1.0), true, 8192);
+        assert_eq!((gpu_tasks.len(), cpu_tasks.len()), (6, 2));
+
+        // Tasks go to the CPU if the GPU is unavailable or the texture is too large for it.
+        let (gpu_tasks, _) = split_tasks(&batch, Some(3.0), Some(1.0), false, 8192);
+        assert!(gpu_tasks.is_empty());
+        let (gpu_tasks, _) = split_tasks(&batch, Some(3.0), Some(1.0), true, 8);
+        assert!(gpu_tasks.is_empty());
+    }
+}
@@ -0,0 +1,520 @@
+//! Views into image data.
+//!
+//! The CPU encoders and decoders read and write their image data through these views. A view
+//! describes the dimensions, the channel count and the row stride of the data, so padded rows or a
+//! cropped region of a larger image can be used without copying.
+
+/// Number of elements of a single RGBA pixel.
+const CHANNELS: usize = 4;
+
+/// How the pixels outside an image are filled, if its width or height is not a multiple of the
+/// block size.
+///
+/// The blocks at the right and bottom edge then extend past the image. The best choice depends on
+/// the content: clamping works well for most textures, mirroring keeps gradients continuous for
+/// tiled terrain and a solid transparent color keeps UI atlas entries from bleeding.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum EdgeFill {
+    /// Repeats the last row and column of the image.
+    #[default]
+    ClampToEdge,
+    /// Mirrors the image at its right and bottom edge.
+    Mirror,
+    /// Fills with zero in all channels, which is transparent black.
+    Zero,
+    /// Fills with a solid RGBA8 color. For floating point images the channels are mapped to
+    /// `[0, 1]`.
+    SolidColor([u8; 4]),
+}
+
+impl EdgeFill {
+    #[cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    ))]
+    pub(crate) const fn raw_mode(self) -> u32 {
+        match self {
+            Self::ClampToEdge => 0,
+            Self::Mirror => 1,
+            Self::Zero => 2,
+            Self::SolidColor(..) => 3,
+        }
+    }
+
+    #[cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    ))]
+    pub(crate) const fn raw_color(self) -> u32 {
+        match self {
+            Self::SolidColor(color) => u32::from_le_bytes(color),
+            _ => 0,
+        }
+    }
+
+    #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+    pub(crate) fn coordinate(self, coordinate: u32, size: u32) -> u32 {
+        match self {
+            _ if coordinate < size => coordinate,
+            Self::Mirror => (size * 2).saturating_sub(coordinate + 1).min(size - 1),
+            _ => size - 1,
+        }
+    }
+}
+
+/// Channel values that can be used to fill the edge of an image.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+pub(crate) trait EdgeFillValue: Copy {
+    const ZERO: Self;
+
+    fn from_unorm8(value: u8) -> Self;
+}
+
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+impl EdgeFillValue for u8 {
+    const ZERO: Self = 0;
+
+    fn from_unorm8(value: u8) -> Self {
+        value
+    }
+}
+
+#[cfg(feature = "bc6h")]
+impl EdgeFillValue for half::f16 {
+    const ZERO: Self = half::f16::ZERO;
+
+    fn from_unorm8(value: u8) -> Self {
+        half::f16::from_f32(value as f32 / 255.0)
+    }
+}
+
+fn required_len(width: u32, height: u32, channels: u32, stride: u32) -> usize {
+    if width == 0 || height == 0 {
+        0
+    } else {
+        (height as usize - 1) * stride as usize + width as usize * channels as usize
+    }
+}
+
+fn validate(len: usize, width: u32, height: u32, channels: u32, stride: u32) {
+    assert!(
+        (1..=4).contains(&channels),
+        "channel count ({}) is not between 1 and 4",
+        channels
+    );
+    assert!(
+        stride as usize >= width as usize * channels as usize,
+        "stride ({}) is smaller than a row of {} pixels",
+        stride,
+        width
+    );
+
+    let required_len = required_len(width, height, channels, stride);
+    assert!(
+        len >= required_len,
+        "image data size ({}) is too small for the image. Required size: {}",
+        len,
+        required_len
+    );
+}
+
+/// A view into image data.
+///
+/// Every pixel consists of `channels` consecutive elements of type `T`. Views created with
+/// [`ImageView::new()`] and [`ImageView::packed()`] hold RGBA data with four channels in the order
+/// red, green, blue and alpha, which all RGBA functions of the crate expect. Views with fewer
+/// channels are created with [`ImageView::with_channels()`], for example for the two channel
+/// [`compress_la8_bc5()`](crate::encode::compress_la8_bc5). Rows start `stride` elements apart.
+///
+/// # Example
+/// ```
+/// use block_compression::ImageView;
+///
+/// let rgba_data = vec![0u8; 256 * 256 * 4];
+///
+/// // A tightly packed image.
+/// let image = ImageView::packed(&rgba_data, 256, 256);
+///
+/// // The lower right quarter of the same image.
+/// let quarter = image.crop(128, 128, 128, 128);
+/// assert_eq!(quarter.stride(), 256 * 4);
+/// ```
+#[derive(Debug)]
+pub struct ImageView<'a, T> {
+    data: &'a [T],
+    width: u32,
+    height: u32,
+    channels: u32,
+    stride: u32,
+}
+
+impl<T> Clone for ImageView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ImageView<'_, T> {}
+
+impl<'a, T> ImageView<'a, T> {
+    /// Creates a new view into `data`.
+    ///
+    /// # Arguments
+    /// * `data` - The RGBA image data
+    /// * `width` - Width of the image in pixels
+    /// * `height` - Height of the image in pixels
+    /// * `stride` - Number of elements per row (for padding). Must be `width * 4` for tightly
+    ///   packed RGBA data.
+    ///
+    /// # Panics
+    /// - If `stride` is smaller than `width * 4`
+    /// - If `data` is too small to hold `height` rows
+    pub fn new(data: &'a [T], width: u32, height: u32, stride: u32) -> Self {
+        Self::with_channels(data, width, height, CHANNELS as u32, stride)
+    }
+
+    /// Creates a new view into tightly packed RGBA data.
+    ///
+    /// # Panics
+    /// - If `data` is too small to hold `width * height` pixels
+    pub fn packed(data: &'a [T], width: u32, height: u32) -> Self {
+        Self::new(data, width, height, width * CHANNELS as u32)
+    }
+
+    /// Creates a new view into `data` with `channels` elements per pixel.
+    ///
+    /// # Arguments
+    /// * `data` - The image data
+    /// * `width` - Width of the image in pixels
+    /// * `height` - Height of the image in pixels
+    /// * `channels` - Number of elements per pixel, between 1 and 4
+    /// * `stride` - Number of elements per row (for padding). Must be `width * channels` for
+    ///   tightly packed data.
+    ///
+    /// # Panics
+    /// - If `channels` is not between 1 and 4
+    /// - If `stride` is smaller than `width * channels`
+    /// - If `data` is too small to hold `height` rows
+    pub fn with_channels(
+        data: &'a [T],
+        width: u32,
+        height: u32,
+        channels: u32,
+        stride: u32,
+    ) -> Self {
+        validate(data.len(), width, height, channels, stride);
+
+        Self {
+            data,
+            width,
+            height,
+            channels,
+            stride,
+        }
+    }
+
+    /// Creates a new view into tightly packed data with `channels` elements per pixel.
+    ///
+    /// # Panics
+    /// - If `channels` is not between 1 and 4
+    /// - If `data` is too small to hold `width * height` pixels
+    pub fn packed_with_channels(data: &'a [T], width: u32, height: u32, channels: u32) -> Self {
+        Self::with_channels(data, width, height, channels, width * channels)
+    }
+
+    /// The width of the image in pixels.
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image in pixels.
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of elements of a single pixel.
+    pub const fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Panics if the view doesn't hold RGBA data, which the RGBA functions of the crate expect.
+    #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+    pub(crate) fn assert_rgba(&self) {
+        assert_eq!(
+            self.channels as usize, CHANNELS,
+            "the image needs to hold RGBA data with 4 channels"
+        );
+    }
+
+    /// The number of elements between the start of two rows.
+    pub const fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    /// The underlying data, starting with the first pixel of the first row.
+    pub const fn data(&self) -> &'a [T] {
+        self.data
+    }
+
+    /// Returns the `width * channels` elements of row `y`.
+    ///
+    /// # Panics
+    /// - If `y` is not smaller than the height
+    pub fn row(&self, y: u32) -> &'a [T] {
+        assert!(y < self.height, "row {} is out of bounds", y);
+
+        let offset = y as usize * self.stride as usize;
+        &self.data[offset..offset + self.width as usize * self.channels as usize]
+    }
+
+    /// Returns an iterator over all rows from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let view = *self;
+        (0..self.height).map(move |y| view.row(y))
+    }
+
+    /// Returns a tightly packed copy of the image, which is extended to the next multiple of 4
+    /// in both dimensions, together with its width and height.
+    #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+    pub(crate) fn padded_to_blocks(&self, edge_fill: EdgeFill) -> (Vec<T>, u32, u32)
+    where
+        T: EdgeFillValue,
+    {
+        self.assert_rgba();
+
+        let width = self.width.next_multiple_of(4);
+        let height = self.height.next_multiple_of(4);
+        let fill = match edge_fill {
+            EdgeFill::Zero => Some([T::ZERO; CHANNELS]),
+            EdgeFill::SolidColor(color) => Some(color.map(T::from_unorm8)),
+            EdgeFill::ClampToEdge | EdgeFill::Mirror => None,
+        };
+
+        let mut data = Vec::with_capacity(width as usize * height as usize * CHANNELS);
+        for y in 0..height {
+            for x in 0..width {
+                let inside = x < self.width && y < self.height;
+                match fill {
+                    Some(fill) if !inside => data.extend_from_slice(&fill),
+                    _ => {
+                        let x = edge_fill.coordinate(x, self.width) as usize;
+                        let row = self.row(edge_fill.coordinate(y, self.height));
+                        data.extend_from_slice(&row[x * CHANNELS..(x + 1) * CHANNELS]);
+                    }
+                }
+            }
+        }
+
+        (data, width, height)
+    }
+
+    /// Returns a view into a rectangular region of the image.
+    ///
+    /// # Panics
+    /// - If the region is not inside the image
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        assert!(
+            x as u64 + width as u64 <= self.width as u64
+                && y as u64 + height as u64 <= self.height as u64,
+            "cropped region is not inside the image"
+        );
+
+        let offset = y as usize * self.stride as usize + x as usize * self.channels as usize;
+        let end = offset + required_len(width, height, self.channels, self.stride);
+
+        Self {
+            data: &self.data[offset..end],
+            width,
+            height,
+            channels: self.channels,
+            stride: self.stride,
+        }
+    }
+}
+
+/// A mutable view into RGBA image data.
+///
+/// See [`ImageView`] for the layout of the data.
+#[derive(Debug)]
+pub struct ImageViewMut<'a, T> {
+    data: &'a mut [T],
+    width: u32,
+    height: u32,
+    channels: u32,
+    stride: u32,
+}
+
+impl<'a, T> ImageViewMut<'a, T> {
+    /// Creates a new mutable view into `data`.
+    ///
+    /// # Arguments
+    /// * `data` - The RGBA image data
+    /// * `width` - Width of the image in pixels
+    /// * `height` - Height of the image in pixels
+    /// * `stride` - Number of elements per row (for padding). Must be `width * 4` for tightly
+    ///   packed RGBA data.
+    ///
+    /// # Panics
+    /// - If `stride` is smaller than `width * 4`
+    /// - If `data` is too small to hold `height` rows
+    pub fn new(data: &'a mut [T], width: u32, height: u32, stride: u32) -> Self {
+        Self::with_channels(data, width, height, CHANNELS as u32, stride)
+    }
+
+    /// Creates a new mutable view into tightly packed RGBA data.
+    ///
+    /// # Panics
+    /// - If `data` is too small to hold `width * height` pixels
+    pub fn packed(data: &'a mut [T], width: u32, height: u32) -> Self {
+        Self::new(data, width, height, width * CHANNELS as u32)
+    }
+
+    /// Creates a new mutable view into `data` with `channels` elements per pixel.
+    ///
+    /// # Arguments
+    /// * `data` - The image data
+    /// * `width` - Width of the image in pixels
+    /// * `height` - Height of the image in pixels
+    /// * `channels` - Number of elements per pixel, between 1 and 4
+    /// * `stride` - Number of elements per row (for padding). Must be `width * channels` for
+    ///   tightly packed data.
+    ///
+    /// # Panics
+    /// - If `channels` is not between 1 and 4
+    /// - If `stride` is smaller than `width * channels`
+    /// - If `data` is too small to hold `height` rows
+    pub fn with_channels(
+        data: &'a mut [T],
+        width: u32,
+        height: u32,
+        channels: u32,
+        stride: u32,
+    ) -> Self {
+        validate(data.len(), width, height, channels, stride);
+
+        Self {
+            data,
+            width,
+            height,
+            channels,
+            stride,
+        }
+    }
+
+    /// Creates a new mutable view into tightly packed data with `channels` elements per pixel.
+    ///
+    /// # Panics
+    /// - If `channels` is not between 1 and 4
+    /// - If `data` is too small to hold `width * height` pixels
+    pub fn packed_with_channels(data: &'a mut [T], width: u32, height: u32, channels: u32) -> Self {
+        Self::with_channels(data, width, height, channels, width * channels)
+    }
+
+    /// The width of the image in pixels.
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image in pixels.
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of elements of a single pixel.
+    pub const fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Panics if the view doesn't hold RGBA data, which the RGBA functions of the crate expect.
+    #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+    pub(crate) fn assert_rgba(&self) {
+        assert_eq!(
+            self.channels as usize, CHANNELS,
+            "the image needs to hold RGBA data with 4 channels"
+        );
+    }
+
+    /// The number of elements between the start of two rows.
+    pub const fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    /// The underlying data, starting with the first pixel of the first row.
+    pub fn data_mut(&mut self) -> &mut [T] {
+        self.data
+    }
+
+    /// Returns the `width * channels` elements of row `y`.
+    ///
+    /// # Panics
+    /// - If `y` is not smaller than the height
+    pub fn row_mut(&mut self, y: u32) -> &mut [T] {
+        assert!(y < self.height, "row {} is out of bounds", y);
+
+        let offset = y as usize * self.stride as usize;
+        &mut self.data[offset..offset + self.width as usize * self.channels as usize]
+    }
+
+    /// Reborrows the view as an immutable view.
+    pub fn as_view(&self) -> ImageView<'_, T> {
+        ImageView {
+            data: self.data,
+            width: self.width,
+            height: self.height,
+            channels: self.channels,
+            stride: self.stride,
+        }
+    }
+
+    /// Returns a mutable view into a rectangular region of the image.
+    ///
+    /// # Panics
+    /// - If the region is not inside the image
+    pub fn crop_mut(&mut self, x: u32, y: u32, width: u32, height: u32) -> ImageViewMut<'_, T> {
+        assert!(
+            x as u64 + width as u64 <= self.width as u64
+                && y as u64 + height as u64 <= self.height as u64,
+            "cropped region is not inside the image"
+        );
+
+        let offset = y as usize * self.stride as usize + x as usize * self.channels as usize;
+        let end = offset + required_len(width, height, self.channels, self.stride);
+
+        ImageViewMut {
+            data: &mut self.data[offset..end],
+            width,
+            height,
+            channels: self.channels,
+            stride: self.stride,
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "bc15", feature = "bc6h", feature = "bc7")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_to_blocks() {
+        let data: Vec<u8> = (0..6 * 4).collect();
+        let image = ImageView::packed(&data, 3, 2);
+        let pixel = |data: &[u8], x: usize, y: usize| data[(y * 4 + x) * 4];
+
+        let (clamped, width, height) = image.padded_to_blocks(EdgeFill::ClampToEdge);
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(pixel(&clamped, 3, 0), pixel(&data[..], 2, 0));
+        assert_eq!(pixel(&clamped, 3, 3), 20);
+
+        let (mirrored, ..) = image.padded_to_blocks(EdgeFill::Mirror);
+        assert_eq!(pixel(&mirrored, 3, 0), 8);
+        assert_eq!(pixel(&mirrored, 0, 2), 12);
+        assert_eq!(pixel(&mirrored, 0, 3), 0);
+
+        let (zero, ..) = image.padded_to_blocks(EdgeFill::Zero);
+        assert_eq!(&zero[12..16], &[0; 4]);
+        assert_eq!(&zero[..12], &data[..12]);
+
+        let (solid, ..) = image.padded_to_blocks(EdgeFill::SolidColor([1, 2, 3, 4]));
+        assert_eq!(&solid[60..64], &[1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Hand-off of compressed blocks to native renderers that don't use wgpu.
+//!
+//! wgpu owns the memory of the buffers it creates and offers no way to export it, and its
+//! Vulkan and Metal backends don't even expose the native handle of a buffer. The zero-copy path
+//! therefore goes the other way around: the engine allocates the destination buffer with its
+//! native API, for example as exportable external memory that another API or process imports,
+//! and wraps it with [`import_destination_buffer()`]. The compressor then writes the blocks
+//! directly into the memory of the engine.
+//!
+//! wgpu doesn't know about the native work of the engine and doesn't synchronize with it. The
+//! engine must not read the blocks before the compression has finished, for example by waiting
+//! for [`GpuBlockCompressor::on_complete()`] or until the queue is idle.
+//!
+//! [`with_hal_buffer()`] gives access to the wgpu-hal buffer of any wgpu buffer, for example to
+//! get the `ID3D12Resource` of a buffer that wgpu created on DX12.
+//!
+//! Only available on native platforms.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wgpu::hal::api::Vulkan;
+//!
+//! // `raw_buffer` is a `vk::Buffer` of the `VkDevice` of `device` with bound memory and at least
+//! // the `STORAGE_BUFFER` and `TRANSFER_SRC` usages.
+//! let hal_buffer = unsafe { wgpu::hal::vulkan::Device::buffer_from_raw(raw_buffer) };
+//! let blocks = unsafe {
+//!     import_destination_buffer::<Vulkan>(&device, hal_buffer, size, Some("engine blocks"))
+//! };
+//!
+//! compressor.add_compression_task(
+//!     variant,
+//!     &view,
+//!     width,
+//!     height,
+//!     &blocks,
+//!     None,
+//!     None,
+//!     TaskOptions::default(),
+//! );
+//! ```
+//!
+//! [`GpuBlockCompressor::on_complete()`]: crate::GpuBlockCompressor::on_complete
+
+use wgpu::{core::hal_api::HalApi, hal::Api, Buffer, BufferDescriptor, BufferUsages, Device};
+
+/// The usages a destination buffer of the compressor needs: it is bound as a storage buffer and
+/// copied from by readbacks.
+pub const DESTINATION_BUFFER_USAGES: BufferUsages =
+    BufferUsages::STORAGE.union(BufferUsages::COPY_SRC);
+
+/// Wraps a wgpu-hal buffer of the backend `A` as a destination buffer of the compressor.
+///
+/// # Arguments
+/// * `device` - The device of the compressor
+/// * `hal_buffer` - The buffer, for example created with `buffer_from_raw()` of the device of
+///   the backend from a native handle
+/// * `size` - The size of the buffer in bytes
+/// * `label` - The label of the buffer for graphics debuggers
+///
+/// # Safety
+/// - `hal_buffer` must be created on the native device of `device`
+/// - `hal_buffer` must be at least `size` bytes large, have memory bound and support the
+///   [`DESTINATION_BUFFER_USAGES`]
+/// - The native buffer must stay alive and must not be accessed by other native work while the
+///   compressor writes into it
+pub unsafe fn import_destination_buffer<A: HalApi>(
+    device: &Device,
+    hal_buffer: <A as Api>::Buffer,
+    size: u64,
+    label: Option<&str>,
+) -> Buffer {
+    unsafe {
+        device.create_buffer_from_hal::<A>(
+            hal_buffer,
+            &BufferDescriptor {
+                label,
+                size,
+                usage: DESTINATION_BUFFER_USAGES,
+                mapped_at_creation: false,
+            },
+        )
+    }
+}
+
+/// Calls `callback` with the wgpu-hal buffer of `buffer`, or with `None` if the buffer doesn't
+/// belong to the backend `A`.
+///
+/// # Safety
+/// - The native handle of the wgpu-hal buffer must not be destroyed
+pub unsafe fn with_hal_buffer<A: HalApi, R>(
+    buffer: &Buffer,
+    callback: impl FnOnce(Option<&<A as Api>::Buffer>) -> R,
+) -> R {
+    unsafe { buffer.as_hal::<A, _, R>(callback) }
+}
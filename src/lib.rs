@@ -15,6 +15,12 @@
 //! seems to take a very long time to compile the shader. For this reason we moved them behind
 //! features, which are included in the default features.
 //!
+//! ## CPU only builds
+//!
+//! The CPU encoders and decoders don't depend on `wgpu`. The `GpuBlockCompressor` and all other
+//! types that need a device are behind the `gpu` feature, which is not part of the default
+//! features. The `wgpu` feature is an alias of `gpu` for existing dependents.
+//!
 //! ## Supported block compressions
 //!
 //! Currently supported block compressions are:
@@ -26,46 +32,283 @@
 //!  * BC5
 //!  * BC6H
 //!  * BC7
+//!
+//! Uncompressed RGBA8 outputs are supported with the same API, so pipelines don't need a
+//! separate code path for textures that are not worth compressing.
+//!
+//! ## Stable output across backends
+//!
+//! The BC1 to BC5 kernels encode the 8-bit values of a block with integer math, so they produce
+//! the same blocks on every backend and driver, which allows caching the blocks by the hash of
+//! their source. This covers the BC1 punch-through mode and the dithered alpha of BC2, but not
+//! sRGB views, normal maps generated from height maps, 16-bit precision and signed distance
+//! fields, whose math still runs in floating point. The written block errors are floating point
+//! as well.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "gpu")]
+mod allocator;
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+mod backends;
 #[cfg(all(
-    feature = "wgpu",
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod block_cache;
+#[cfg(all(
+    feature = "gpu",
     any(feature = "bc15", feature = "bc6h", feature = "bc7")
 ))]
 mod block_compressor;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub mod budget;
+#[cfg(feature = "gpu")]
+mod buffer_pool;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod capture;
+pub mod color;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod compatibility;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub mod dds;
 pub mod decode;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub mod diagnostics;
 pub mod encode;
+pub mod endian;
+#[cfg(all(
+    feature = "export",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "export")))]
+pub mod export;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod fingerprint;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod hybrid;
+mod image;
+#[cfg(all(feature = "interop", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "interop")))]
+pub mod interop;
+pub mod mip;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+mod periodic;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub mod policy;
+#[cfg(feature = "gpu")]
+mod readback;
 mod settings;
+#[cfg(all(test, any(feature = "bc15", feature = "bc6h", feature = "bc7")))]
+mod test_util;
+pub mod testgen;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub mod tiling;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+mod units;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7"),
+    not(target_arch = "wasm32")
+))]
+mod validation;
 
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-use std::hash::{Hash, Hasher};
+use mip::MipTail;
 
+#[cfg(feature = "gpu")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gpu")))]
+pub use allocator::{BufferAllocator, DeviceAllocator};
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "gpu", not(target_arch = "wasm32")))))]
+pub use backends::{for_each_backend, BackendDevice};
 #[cfg(all(
-    feature = "wgpu",
+    feature = "gpu",
     any(feature = "bc15", feature = "bc6h", feature = "bc7")
 ))]
 #[cfg_attr(
     docsrs,
     doc(cfg(all(
-        feature = "wgpu",
+        feature = "gpu",
         any(feature = "bc15", feature = "bc6h", feature = "bc7")
     )))
 )]
-pub use block_compressor::GpuBlockCompressor;
+pub use block_cache::BlockCache;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use block_compressor::{
+    CompletedTask, CompressorError, DispatchUniforms, EncodingOptions, GpuBlockCompressor,
+    MemoryStats, NormalFilter, NormalMapSettings, ResourceUsage, SharedPipelines, SourceColorSpace,
+    TaskId, TaskOptions,
+};
+#[cfg(feature = "gpu")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gpu")))]
+pub use buffer_pool::{BlockBufferPool, SlotState};
 pub use bytemuck;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use capture::{CapturedFrame, FrameCapture};
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use compatibility::{
+    is_software_adapter, SelfCheckReport, SelfCheckResult, MIN_SELF_CHECK_PSNR,
+};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use fingerprint::{crc32, fingerprint, fingerprint128, mip_checksums};
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
 pub use half;
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use hybrid::{EncoderDeterminism, HybridScheduler, HybridTask};
+pub use image::{EdgeFill, ImageView, ImageViewMut};
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7")
+    )))
+)]
+pub use periodic::PeriodicCompressor;
+#[cfg(feature = "gpu")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gpu")))]
+pub use readback::SparseReadback;
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bc6h", feature = "bc7"))))]
+pub use settings::NvttQuality;
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use settings::Preset;
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bc6h", feature = "bc7"))))]
+pub use settings::SettingsError;
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
-pub use settings::BC6HSettings;
+pub use settings::{BC6HSettings, HdrRemap, NonFiniteHandling};
 #[cfg(feature = "bc7")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
-pub use settings::BC7Settings;
+pub use settings::{BC7Settings, Mode2Search, Mode45ScalarChannel};
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub use units::{BlockCoord, BlockIndex, ByteOffset};
+#[cfg(all(
+    feature = "gpu",
+    any(feature = "bc15", feature = "bc6h", feature = "bc7"),
+    not(target_arch = "wasm32")
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(
+        feature = "gpu",
+        any(feature = "bc15", feature = "bc6h", feature = "bc7"),
+        not(target_arch = "wasm32")
+    )))
+)]
+pub use validation::GpuError;
+
+/// Returns the size of a dimension at the given mip level.
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+const fn mip_dimension(size: u32, mip_level: u32) -> u32 {
+    if mip_level >= u32::BITS || size >> mip_level == 0 {
+        1
+    } else {
+        size >> mip_level
+    }
+}
 
 /// Block compression variants supported by this crate.
-#[derive(Copy, Clone, Debug)]
+///
+/// Two variants are only equal if they also use the same settings. Use
+/// [`CompressionVariant::format_key()`] to compare or hash only the block format.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
 #[cfg_attr(
     docsrs,
@@ -100,23 +343,58 @@ pub enum CompressionVariant {
     #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
     /// BC7 compression with smooth alpha (RGBA)
     BC7(BC7Settings),
+    /// Uncompressed RGBA8 texels (RGBA)
+    ///
+    /// Copies the texels without compressing them, so pipelines can produce uncompressed outputs,
+    /// for example of tiny textures, with the same tasks and functions as the compressed ones.
+    /// The texels are stored in rows from top to bottom, and the width and height are padded to
+    /// a multiple of 4 like the blocks of all other variants. Every 4x4 "block" is 64 bytes
+    /// large, but its texels are spread over four rows of the image, so only whole block rows
+    /// can be handled on their own.
+    RGBA8,
 }
 
+/// The block format of a [`CompressionVariant`] without its encoder settings.
+///
+/// All variants with the same format produce blocks that are decoded the same way, so this is
+/// the key to use for anything that only depends on the format, like GPU pipelines.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-impl PartialEq for CompressionVariant {
-    fn eq(&self, other: &Self) -> bool {
-        std::mem::discriminant(self) == std::mem::discriminant(other)
-    }
-}
-
-#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-impl Eq for CompressionVariant {}
-
-#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
-impl Hash for CompressionVariant {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        std::mem::discriminant(self).hash(state);
-    }
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7")))
+)]
+pub enum FormatKey {
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    /// BC1
+    BC1,
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    /// BC2
+    BC2,
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    /// BC3
+    BC3,
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    /// BC4
+    BC4,
+    #[cfg(feature = "bc15")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc15")))]
+    /// BC5
+    BC5,
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    /// BC6H with any settings
+    BC6H,
+    #[cfg(feature = "bc7")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
+    /// BC7 with any settings
+    BC7,
+    /// Uncompressed RGBA8
+    RGBA8,
 }
 
 #[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
@@ -126,9 +404,15 @@ impl CompressionVariant {
     /// The width is used to calculate how many blocks are needed per row,
     /// which is then multiplied by the block size.
     /// Width is rounded up to the nearest multiple of 4.
+    ///
+    /// For [`CompressionVariant::RGBA8`], the row is a single row of texels instead of a row of
+    /// blocks, as uncompressed texture copies expect it.
     pub const fn bytes_per_row(self, width: u32) -> u32 {
         let blocks_per_row = (width + 3) / 4;
-        blocks_per_row * self.block_byte_size()
+        match self {
+            Self::RGBA8 => blocks_per_row * 16,
+            _ => blocks_per_row * self.block_byte_size(),
+        }
     }
 
     /// Returns the byte size required for storing compressed blocks for the given dimensions.
@@ -143,7 +427,308 @@ impl CompressionVariant {
         block_count * block_size
     }
 
-    const fn block_byte_size(self) -> u32 {
+    /// Returns the block format of the variant, which ignores the settings of BC6H and BC7.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC7Settings, CompressionVariant, FormatKey};
+    ///
+    /// let fast = CompressionVariant::BC7(BC7Settings::alpha_ultrafast());
+    /// let slow = CompressionVariant::BC7(BC7Settings::alpha_slow());
+    ///
+    /// assert_ne!(fast, slow);
+    /// assert_eq!(fast.format_key(), slow.format_key());
+    /// assert_eq!(fast.format_key(), FormatKey::BC7);
+    /// ```
+    pub const fn format_key(self) -> FormatKey {
+        match self {
+            #[cfg(feature = "bc15")]
+            Self::BC1 => FormatKey::BC1,
+            #[cfg(feature = "bc15")]
+            Self::BC2 => FormatKey::BC2,
+            #[cfg(feature = "bc15")]
+            Self::BC3 => FormatKey::BC3,
+            #[cfg(feature = "bc15")]
+            Self::BC4 => FormatKey::BC4,
+            #[cfg(feature = "bc15")]
+            Self::BC5 => FormatKey::BC5,
+            #[cfg(feature = "bc6h")]
+            Self::BC6H(..) => FormatKey::BC6H,
+            #[cfg(feature = "bc7")]
+            Self::BC7(..) => FormatKey::BC7,
+            Self::RGBA8 => FormatKey::RGBA8,
+        }
+    }
+
+    /// Returns the number of color channels the variant stores.
+    pub const fn channel_count(self) -> u32 {
+        match self {
+            #[cfg(feature = "bc15")]
+            Self::BC4 => 1,
+            #[cfg(feature = "bc15")]
+            Self::BC5 => 2,
+            #[cfg(feature = "bc15")]
+            Self::BC1 => 3,
+            #[cfg(feature = "bc15")]
+            Self::BC2 | Self::BC3 => 4,
+            #[cfg(feature = "bc6h")]
+            Self::BC6H(..) => 3,
+            #[cfg(feature = "bc7")]
+            Self::BC7(..) => 4,
+            Self::RGBA8 => 4,
+        }
+    }
+
+    /// Returns `true` if the variant stores an alpha channel.
+    pub const fn has_alpha(self) -> bool {
+        match self {
+            #[cfg(feature = "bc15")]
+            Self::BC2 | Self::BC3 => true,
+            #[cfg(feature = "bc7")]
+            Self::BC7(..) => true,
+            Self::RGBA8 => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the variant stores high dynamic range data.
+    pub const fn is_hdr(self) -> bool {
+        match self {
+            #[cfg(feature = "bc6h")]
+            Self::BC6H(..) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns the width and height in pixels of a single block.
+    pub const fn block_dimensions(self) -> (u32, u32) {
+        (4, 4)
+    }
+
+    /// Returns the average number of bits used to store a single pixel.
+    pub const fn bits_per_pixel(self) -> u32 {
+        let (block_width, block_height) = self.block_dimensions();
+        self.block_byte_size() * 8 / (block_width * block_height)
+    }
+
+    /// Returns the byte offset of a mip level inside the blocks of a full mip chain.
+    ///
+    /// The mip levels are expected to be stored one after the other, starting with the base
+    /// level of the given dimensions. Every level is half the size of the previous one, but at
+    /// least one pixel wide and high.
+    pub const fn mip_blocks_offset(self, width: u32, height: u32, mip_level: u32) -> usize {
+        let mut offset = 0;
+        let mut level = 0;
+        while level < mip_level {
+            offset +=
+                self.blocks_byte_size(mip_dimension(width, level), mip_dimension(height, level));
+            level += 1;
+        }
+        offset
+    }
+
+    /// Returns the size in bytes of the blocks of a full mip chain that ends as described by
+    /// `mip_tail`.
+    ///
+    /// Levels smaller than a block are padded and take the space of a whole block.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{mip::MipTail, CompressionVariant};
+    ///
+    /// let variant = CompressionVariant::BC1;
+    /// assert_eq!(variant.mip_chain_byte_size(8, 8, MipTail::StopAt4x4), 32 + 8);
+    /// assert_eq!(variant.mip_chain_byte_size(8, 8, MipTail::Full), 32 + 8 + 8 + 8);
+    /// ```
+    pub const fn mip_chain_byte_size(self, width: u32, height: u32, mip_tail: MipTail) -> usize {
+        self.mip_blocks_offset(width, height, mip_tail.level_count(width, height))
+    }
+
+    /// Returns the variant with the settings of the fast preview tier.
+    ///
+    /// BC6H and BC7 use [`BC6HSettings::preview()`] and [`BC7Settings::preview()`]. All other
+    /// variants have no settings and are returned unchanged. On the CPU,
+    /// [`encode::compress_rgba8_preview()`] also uses a faster encoder for BC1 to BC3.
+    pub const fn preview(self) -> Self {
+        match self {
+            #[cfg(feature = "bc6h")]
+            Self::BC6H(..) => Self::BC6H(BC6HSettings::preview()),
+            #[cfg(feature = "bc7")]
+            Self::BC7(..) => Self::BC7(BC7Settings::preview()),
+            #[allow(unreachable_patterns)]
+            variant => variant,
+        }
+    }
+
+    /// Returns the variant with the settings of a quality [`Preset`].
+    ///
+    /// The settings of BC6H and BC7 are replaced by [`BC6HSettings::from_preset()`] and
+    /// [`BC7Settings::from_preset()`], and BC7 keeps whether it preserves the alpha channel. All
+    /// other variants have no settings and are returned unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC7Settings, CompressionVariant, Preset};
+    ///
+    /// let variant = CompressionVariant::BC7(BC7Settings::alpha_ultrafast());
+    /// assert_eq!(
+    ///     variant.with_preset(Preset::Slow),
+    ///     CompressionVariant::BC7(BC7Settings::alpha_slow())
+    /// );
+    /// assert_eq!(CompressionVariant::BC1.with_preset(Preset::Slow), CompressionVariant::BC1);
+    /// ```
+    pub const fn with_preset(self, preset: Preset) -> Self {
+        match self {
+            #[cfg(feature = "bc6h")]
+            Self::BC6H(..) => Self::BC6H(BC6HSettings::from_preset(preset)),
+            #[cfg(feature = "bc7")]
+            Self::BC7(settings) => {
+                Self::BC7(BC7Settings::from_preset(preset, settings.channels == 4))
+            }
+            #[allow(unreachable_patterns)]
+            variant => {
+                let _ = preset;
+                variant
+            }
+        }
+    }
+
+    /// Returns the variant of a `DXGI_FORMAT` value, as stored in the DX10 header extension of
+    /// DDS files.
+    ///
+    /// The typeless, sRGB and signed formats map to the same variant as their unsigned linear
+    /// counterpart. Signed BC4, BC5 and BC6H blocks need to be decoded with the signed option of
+    /// the decoder. BC6H and BC7 use the [`BC6HSettings::basic()`] and
+    /// [`BC7Settings::alpha_basic()`] settings, which don't affect decoding. Returns `None` for
+    /// formats that are not block compressed or whose variant is not enabled.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::CompressionVariant;
+    ///
+    /// // DXGI_FORMAT_BC3_UNORM_SRGB
+    /// assert_eq!(CompressionVariant::from_dxgi(78), Some(CompressionVariant::BC3));
+    /// // DXGI_FORMAT_R8G8B8A8_UNORM
+    /// assert_eq!(CompressionVariant::from_dxgi(28), None);
+    /// ```
+    pub const fn from_dxgi(format: u32) -> Option<Self> {
+        match format {
+            #[cfg(feature = "bc15")]
+            70..=72 => Some(Self::BC1),
+            #[cfg(feature = "bc15")]
+            73..=75 => Some(Self::BC2),
+            #[cfg(feature = "bc15")]
+            76..=78 => Some(Self::BC3),
+            #[cfg(feature = "bc15")]
+            79..=81 => Some(Self::BC4),
+            #[cfg(feature = "bc15")]
+            82..=84 => Some(Self::BC5),
+            #[cfg(feature = "bc6h")]
+            94..=96 => Some(Self::BC6H(BC6HSettings::basic())),
+            #[cfg(feature = "bc7")]
+            97..=99 => Some(Self::BC7(BC7Settings::alpha_basic())),
+            _ => None,
+        }
+    }
+
+    /// Returns the variant of a `VkFormat` value, as stored in the header of KTX2 files.
+    ///
+    /// The mapping follows the same rules as [`CompressionVariant::from_dxgi()`]. Both the RGB
+    /// and RGBA formats of BC1 map to [`CompressionVariant::BC1`].
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::CompressionVariant;
+    ///
+    /// // VK_FORMAT_BC1_RGBA_SRGB_BLOCK
+    /// assert_eq!(CompressionVariant::from_vk_format(134), Some(CompressionVariant::BC1));
+    /// // VK_FORMAT_ASTC_4x4_UNORM_BLOCK
+    /// assert_eq!(CompressionVariant::from_vk_format(157), None);
+    /// ```
+    pub const fn from_vk_format(format: u32) -> Option<Self> {
+        match format {
+            #[cfg(feature = "bc15")]
+            131..=134 => Some(Self::BC1),
+            #[cfg(feature = "bc15")]
+            135 | 136 => Some(Self::BC2),
+            #[cfg(feature = "bc15")]
+            137 | 138 => Some(Self::BC3),
+            #[cfg(feature = "bc15")]
+            139 | 140 => Some(Self::BC4),
+            #[cfg(feature = "bc15")]
+            141 | 142 => Some(Self::BC5),
+            #[cfg(feature = "bc6h")]
+            143 | 144 => Some(Self::BC6H(BC6HSettings::basic())),
+            #[cfg(feature = "bc7")]
+            145 | 146 => Some(Self::BC7(BC7Settings::alpha_basic())),
+            _ => None,
+        }
+    }
+
+    /// Returns the variant of a FourCC code of the pixel format of a DDS header.
+    ///
+    /// Supports the legacy `DXT1` to `DXT5` codes and the `ATI1`, `ATI2`, `BC4U`, `BC4S`,
+    /// `BC5U` and `BC5S` codes. The premultiplied `DXT2` and `DXT4` map to BC2 and BC3. Files with
+    /// the `DX10` code store the format in the header extension, which is read with
+    /// [`CompressionVariant::from_dxgi()`]. Returns `None` for all other codes.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::CompressionVariant;
+    ///
+    /// assert_eq!(CompressionVariant::from_fourcc(*b"DXT5"), Some(CompressionVariant::BC3));
+    /// assert_eq!(CompressionVariant::from_fourcc(*b"DX10"), None);
+    /// ```
+    pub const fn from_fourcc(fourcc: [u8; 4]) -> Option<Self> {
+        match &fourcc {
+            #[cfg(feature = "bc15")]
+            b"DXT1" => Some(Self::BC1),
+            #[cfg(feature = "bc15")]
+            b"DXT2" | b"DXT3" => Some(Self::BC2),
+            #[cfg(feature = "bc15")]
+            b"DXT4" | b"DXT5" => Some(Self::BC3),
+            #[cfg(feature = "bc15")]
+            b"ATI1" | b"BC4U" | b"BC4S" => Some(Self::BC4),
+            #[cfg(feature = "bc15")]
+            b"ATI2" | b"BC5U" | b"BC5S" => Some(Self::BC5),
+            _ => None,
+        }
+    }
+
+    /// Returns the FourCC code of the variant in the pixel format of a legacy DDS header, which
+    /// old engines read instead of the DX10 header extension.
+    ///
+    /// BC1 to BC3 use their legacy names `DXT1`, `DXT3` and `DXT5`, BC4 and BC5 the `ATI1` and
+    /// `ATI2` codes. Returns `None` for BC6H, BC7 and RGBA8, which have no FourCC code.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::CompressionVariant;
+    ///
+    /// assert_eq!(CompressionVariant::BC3.fourcc(), Some(*b"DXT5"));
+    /// assert_eq!(CompressionVariant::RGBA8.fourcc(), None);
+    /// ```
+    pub const fn fourcc(self) -> Option<[u8; 4]> {
+        match self {
+            #[cfg(feature = "bc15")]
+            Self::BC1 => Some(*b"DXT1"),
+            #[cfg(feature = "bc15")]
+            Self::BC2 => Some(*b"DXT3"),
+            #[cfg(feature = "bc15")]
+            Self::BC3 => Some(*b"DXT5"),
+            #[cfg(feature = "bc15")]
+            Self::BC4 => Some(*b"ATI1"),
+            #[cfg(feature = "bc15")]
+            Self::BC5 => Some(*b"ATI2"),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Returns the byte size of a single compressed block.
+    pub const fn block_byte_size(self) -> u32 {
         match self {
             #[cfg(feature = "bc15")]
             Self::BC1 | Self::BC4 => 8,
@@ -153,10 +738,10 @@ impl CompressionVariant {
             Self::BC6H(..) => 16,
             #[cfg(feature = "bc7")]
             Self::BC7(..) => 16,
+            Self::RGBA8 => 64,
         }
     }
 
-    #[cfg(feature = "wgpu")]
     const fn name(self) -> &'static str {
         match self {
             #[cfg(feature = "bc15")]
@@ -173,10 +758,11 @@ impl CompressionVariant {
             Self::BC6H(..) => "bc6h",
             #[cfg(feature = "bc7")]
             Self::BC7(..) => "bc7",
+            Self::RGBA8 => "rgba8",
         }
     }
 
-    #[cfg(feature = "wgpu")]
+    #[cfg(feature = "gpu")]
     const fn entry_point(self) -> &'static str {
         match self {
             #[cfg(feature = "bc15")]
@@ -193,6 +779,7 @@ impl CompressionVariant {
             Self::BC6H(..) => "compress_bc6h",
             #[cfg(feature = "bc7")]
             Self::BC7(..) => "compress_bc7",
+            Self::RGBA8 => "copy_rgba8",
         }
     }
 }
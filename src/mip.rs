@@ -0,0 +1,426 @@
+//! CPU based mip map generation.
+//!
+//! The generated mip levels are meant to be compressed afterward with one of the block
+//! compression variants. How the levels are filtered is controlled by the [`BakeOptions`].
+
+use std::ops::Range;
+
+use crate::{
+    color::{linear_to_srgb, srgb_to_linear},
+    ImageView,
+};
+
+/// How the source texels are filtered when generating mip levels.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MipFilter {
+    /// The color channels are stored in sRGB space. They are converted to linear space before
+    /// filtering and back to sRGB space afterward, so mips don't darken. This is the correct
+    /// choice for albedo and other color textures.
+    #[default]
+    Srgb,
+    /// All channels are filtered as they are stored. The correct choice for linear data like
+    /// roughness, height or mask textures.
+    Linear,
+    /// The RGB channels store a unit vector mapped from `[-1, 1]` to `[0, 255]`. The vectors are
+    /// renormalized after filtering, so the mips don't get de-normalized.
+    NormalMap,
+}
+
+/// Where a mip chain ends.
+///
+/// Levels smaller than a block are padded to a full block when they are compressed, so the
+/// last levels of a full chain all take the space of a single block. Some engines expect these
+/// levels, others stop the chain as soon as a level would be smaller than a block.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MipTail {
+    /// The chain ends with the 1x1 level.
+    #[default]
+    Full,
+    /// The chain ends with the last level that is at least 4 pixels wide and high. The base
+    /// level is always part of the chain.
+    StopAt4x4,
+}
+
+impl MipTail {
+    /// Returns the number of mip levels of a chain with this tail for the given dimensions,
+    /// including the base level.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::mip::MipTail;
+    ///
+    /// assert_eq!(MipTail::Full.level_count(256, 64), 9);
+    /// assert_eq!(MipTail::StopAt4x4.level_count(256, 64), 5);
+    /// assert_eq!(MipTail::StopAt4x4.level_count(2, 2), 1);
+    /// ```
+    pub const fn level_count(self, width: u32, height: u32) -> u32 {
+        match self {
+            Self::Full => mip_level_count(width, height),
+            Self::StopAt4x4 => {
+                let min = if width < height { width } else { height };
+                if min == 0 {
+                    0
+                } else if min < 4 {
+                    1
+                } else {
+                    // Level `n` is at least 4 pixels wide and high as long as `min >> n >= 4`.
+                    u32::BITS - min.leading_zeros() - 2
+                }
+            }
+        }
+    }
+}
+
+/// Options that control how a texture is baked into its mip levels.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BakeOptions {
+    /// The filter used to generate the mip levels.
+    pub mip_filter: MipFilter,
+    /// Where the generated mip chain ends.
+    pub mip_tail: MipTail,
+}
+
+/// A single generated mip level with tightly packed RGBA8 data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MipLevel {
+    /// The width of the level in pixels.
+    pub width: u32,
+    /// The height of the level in pixels.
+    pub height: u32,
+    /// The RGBA8 data of the level.
+    pub data: Vec<u8>,
+}
+
+impl MipLevel {
+    /// Returns a view into the data of the level, which can be passed to the CPU encoders.
+    pub fn view(&self) -> ImageView<'_, u8> {
+        ImageView::packed(&self.data, self.width, self.height)
+    }
+}
+
+/// Returns the number of mip levels of a full chain for the given dimensions, including the base
+/// level.
+pub const fn mip_level_count(width: u32, height: u32) -> u32 {
+    let max = if width > height { width } else { height };
+    if max == 0 {
+        0
+    } else {
+        u32::BITS - max.leading_zeros()
+    }
+}
+
+/// Downsamples RGBA8 data to half its size by using a 2x2 box filter.
+///
+/// Odd dimensions are handled by folding the last row or column into the last texel of the
+/// level, which averages 3 source texels in that direction. The resulting size is
+/// `max(width / 2, 1)` x `max(height / 2, 1)`.
+///
+/// # Panics
+/// - If `width` or `height` is zero
+/// - If `rgba_data` has not the expected size (`width * height * 4`)
+pub fn downsample_rgba8(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &BakeOptions,
+) -> MipLevel {
+    assert!(
+        width > 0 && height > 0,
+        "can't downsample an image without pixels"
+    );
+    assert_eq!(
+        rgba_data.len(),
+        width as usize * height as usize * 4,
+        "the input slice has not the expected size"
+    );
+
+    let target_width = u32::max(width / 2, 1);
+    let target_height = u32::max(height / 2, 1);
+    let mut data = vec![0; target_width as usize * target_height as usize * 4];
+
+    let source_width = width as usize;
+
+    for y in 0..target_height as usize {
+        let source_rows = source_range(y, target_height, height);
+
+        for x in 0..target_width as usize {
+            let source_columns = source_range(x, target_width, width);
+            let texel_count = (source_rows.len() * source_columns.len()) as f32;
+
+            let mut sum = [0.0; 4];
+            for source_y in source_rows.clone() {
+                for source_x in source_columns.clone() {
+                    let offset = (source_y * source_width + source_x) * 4;
+                    let texel = decode_texel(&rgba_data[offset..offset + 4], options.mip_filter);
+                    for (sum, value) in sum.iter_mut().zip(texel.iter()) {
+                        *sum += value;
+                    }
+                }
+            }
+
+            let average = sum.map(|value| value / texel_count);
+
+            let offset = (y * target_width as usize + x) * 4;
+            encode_texel(&mut data[offset..offset + 4], average, options.mip_filter);
+        }
+    }
+
+    MipLevel {
+        width: target_width,
+        height: target_height,
+        data,
+    }
+}
+
+/// Generates all mip levels below the base level down to 1x1, or down to the last level of at
+/// least 4x4 pixels if [`BakeOptions::mip_tail`] is [`MipTail::StopAt4x4`].
+///
+/// The base level itself is not part of the returned levels. Images without pixels have no
+/// mip levels.
+///
+/// # Panics
+/// - If `rgba_data` has not the expected size (`width * height * 4`)
+///
+/// # Example
+/// ```
+/// use block_compression::mip::{generate_mip_chain_rgba8, BakeOptions, MipFilter};
+///
+/// let rgba_data = vec![255u8; 64 * 64 * 4];
+/// let options = BakeOptions {
+///     mip_filter: MipFilter::Srgb,
+///     ..Default::default()
+/// };
+///
+/// let mips = generate_mip_chain_rgba8(&rgba_data, 64, 64, &options);
+/// assert_eq!(mips.len(), 6);
+/// ```
+pub fn generate_mip_chain_rgba8(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &BakeOptions,
+) -> Vec<MipLevel> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+
+    let level_count = options
+        .mip_tail
+        .level_count(width, height)
+        .saturating_sub(1);
+    let mut levels: Vec<MipLevel> = Vec::with_capacity(level_count as usize);
+
+    for _ in 0..level_count {
+        let level = match levels.last() {
+            Some(previous) => {
+                downsample_rgba8(&previous.data, previous.width, previous.height, options)
+            }
+            None => downsample_rgba8(rgba_data, width, height, options),
+        };
+        levels.push(level);
+    }
+
+    levels
+}
+
+/// Returns the source texels of the `target` texel of a level in one direction. The last texel
+/// also covers the last source texel of an odd size.
+fn source_range(target: usize, target_size: u32, size: u32) -> Range<usize> {
+    let start = target * 2;
+    if target + 1 == target_size as usize {
+        start..size as usize
+    } else {
+        start..start + 2
+    }
+}
+
+fn decode_texel(rgba: &[u8], filter: MipFilter) -> [f32; 4] {
+    let normalized = [
+        rgba[0] as f32 / 255.0,
+        rgba[1] as f32 / 255.0,
+        rgba[2] as f32 / 255.0,
+        rgba[3] as f32 / 255.0,
+    ];
+
+    match filter {
+        MipFilter::Srgb => [
+            srgb_to_linear(normalized[0]),
+            srgb_to_linear(normalized[1]),
+            srgb_to_linear(normalized[2]),
+            normalized[3],
+        ],
+        MipFilter::Linear => normalized,
+        MipFilter::NormalMap => [
+            normalized[0] * 2.0 - 1.0,
+            normalized[1] * 2.0 - 1.0,
+            normalized[2] * 2.0 - 1.0,
+            normalized[3],
+        ],
+    }
+}
+
+fn encode_texel(rgba: &mut [u8], value: [f32; 4], filter: MipFilter) {
+    let normalized = match filter {
+        MipFilter::Srgb => [
+            linear_to_srgb(value[0]),
+            linear_to_srgb(value[1]),
+            linear_to_srgb(value[2]),
+            value[3],
+        ],
+        MipFilter::Linear => value,
+        MipFilter::NormalMap => {
+            let length = (value[0] * value[0] + value[1] * value[1] + value[2] * value[2]).sqrt();
+
+            // Fall back to a normal pointing straight up if the normals cancel out.
+            let normal = if length > f32::EPSILON {
+                [value[0] / length, value[1] / length, value[2] / length]
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+
+            [
+                normal[0] * 0.5 + 0.5,
+                normal[1] * 0.5 + 0.5,
+                normal[2] * 0.5 + 0.5,
+                value[3],
+            ]
+        }
+    };
+
+    for (target, value) in rgba.iter_mut().zip(normalized.iter()) {
+        *target = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mip_level_count() {
+        assert_eq!(mip_level_count(0, 0), 0);
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(256, 16), 9);
+        assert_eq!(mip_level_count(100, 3), 7);
+    }
+
+    #[test]
+    fn test_srgb_checkerboard_does_not_darken() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let rgba_data = [black, white, white, black].concat();
+
+        let srgb = downsample_rgba8(
+            &rgba_data,
+            2,
+            2,
+            &BakeOptions {
+                mip_filter: MipFilter::Srgb,
+                ..Default::default()
+            },
+        );
+        let linear = downsample_rgba8(
+            &rgba_data,
+            2,
+            2,
+            &BakeOptions {
+                mip_filter: MipFilter::Linear,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(srgb.data, [188, 188, 188, 255]);
+        assert_eq!(linear.data, [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn test_odd_size_covers_last_row_and_column() {
+        let options = BakeOptions {
+            mip_filter: MipFilter::Linear,
+            ..Default::default()
+        };
+
+        // Only the last column and the last row of the 3x3 image are white.
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let rgba_data = [
+            black, black, white, //
+            black, black, white, //
+            white, white, white,
+        ]
+        .concat();
+        let level = downsample_rgba8(&rgba_data, 3, 3, &options);
+        assert_eq!((level.width, level.height), (1, 1));
+        // 5 of the 9 texels are white.
+        assert_eq!(level.data, [142, 142, 142, 255]);
+
+        // The last of 5 columns only lands in the last texel.
+        let gray = [100, 100, 100, 255];
+        let rgba_data = [gray, gray, gray, gray, white].concat();
+        let level = downsample_rgba8(&rgba_data, 5, 1, &options);
+        assert_eq!(level.data, [gray, [152, 152, 152, 255]].concat());
+    }
+
+    #[test]
+    #[should_panic(expected = "without pixels")]
+    fn test_zero_size() {
+        assert!(generate_mip_chain_rgba8(&[], 0, 4, &BakeOptions::default()).is_empty());
+        downsample_rgba8(&[], 0, 4, &BakeOptions::default());
+    }
+
+    #[test]
+    fn test_normal_map_is_renormalized() {
+        // Two normals tilted to the left and right average to a short vector pointing up.
+        let left = [38, 128, 218, 255];
+        let right = [218, 128, 218, 255];
+        let rgba_data = [left, right, left, right].concat();
+
+        let level = downsample_rgba8(
+            &rgba_data,
+            2,
+            2,
+            &BakeOptions {
+                mip_filter: MipFilter::NormalMap,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(level.data, [128, 128, 255, 255]);
+    }
+
+    #[test]
+    fn test_generate_mip_chain_sizes() {
+        let rgba_data = vec![0; 16 * 4 * 4];
+        let levels = generate_mip_chain_rgba8(&rgba_data, 16, 4, &BakeOptions::default());
+
+        let sizes: Vec<(u32, u32)> = levels
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(sizes, [(8, 2), (4, 1), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn test_mip_tail_level_count() {
+        for (width, height) in [(0, 0), (1, 1), (3, 9), (4, 4), (7, 7), (8, 8), (256, 16)] {
+            let full = MipTail::Full.level_count(width, height);
+            assert_eq!(full, mip_level_count(width, height));
+
+            let stop = MipTail::StopAt4x4.level_count(width, height);
+            let expected = (0..full)
+                .take_while(|&level| {
+                    level == 0 || ((width >> level) >= 4 && (height >> level) >= 4)
+                })
+                .count() as u32;
+            assert_eq!(stop, expected, "{width}x{height}");
+        }
+
+        let rgba_data = vec![0; 32 * 8 * 4];
+        let options = BakeOptions {
+            mip_tail: MipTail::StopAt4x4,
+            ..Default::default()
+        };
+        let levels = generate_mip_chain_rgba8(&rgba_data, 32, 8, &options);
+        assert_eq!(levels.len(), 1);
+        assert_eq!((levels[0].width, levels[0].height), (16, 4));
+    }
+}
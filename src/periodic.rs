@@ -0,0 +1,238 @@
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc,
+};
+
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, Device, Maintain, TextureView};
+
+use crate::{CompressionVariant, GpuBlockCompressor, TaskId, TaskOptions};
+
+/// Compresses a render target every N frames into two alternating output buffers.
+///
+/// Engines often render impostors, billboards or reflection probes into a render target and
+/// refresh them periodically instead of every frame. The periodic compressor compresses the
+/// render target into one output buffer, while the other output keeps the previous result, so
+/// the engine always has finished blocks to copy into its textures:
+///
+/// 1. [`PeriodicCompressor::frame()`] is called once per frame. On every `interval`-th frame it
+///    adds a compression task of the render target into the output that is not the latest.
+/// 2. The frame runs [`GpuBlockCompressor::compress()`] and
+///    [`GpuBlockCompressor::after_submit()`] as usual.
+/// 3. [`PeriodicCompressor::poll()`] polls the device without blocking. Once a compression has
+///    finished, its output becomes the [latest](PeriodicCompressor::latest) one.
+///
+/// The GPU executes the work of a queue in order, so copies or draws that use the latest output
+/// and are submitted before the next compression never see partially written blocks. A capture
+/// is skipped if its output is still being compressed from an earlier capture, which only
+/// happens if the GPU lags behind by a whole interval. After the device was lost, a new periodic
+/// compressor needs to be created on the new device.
+///
+/// # Example
+///
+/// ```ignore
+/// let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+/// let mut impostors = PeriodicCompressor::new(device.clone(), variant, 512, 512, 30);
+///
+/// loop {
+///     render_impostor(&mut encoder, &impostor_view);
+///
+///     impostors.frame(&mut compressor, &impostor_view);
+///     compressor.compress_split(&mut encoder).unwrap();
+///     queue.submit([encoder.finish()]);
+///     compressor.after_submit();
+///
+///     if impostors.poll() {
+///         upload_impostor(impostors.latest().unwrap());
+///     }
+/// }
+/// ```
+pub struct PeriodicCompressor {
+    device: Arc<Device>,
+    variant: CompressionVariant,
+    width: u32,
+    height: u32,
+    interval: u32,
+    frame: u64,
+    outputs: [Buffer; 2],
+    in_flight: [bool; 2],
+    latest: Option<(usize, u64)>,
+    generation: u64,
+    sender: Sender<(usize, u64)>,
+    receiver: Receiver<(usize, u64)>,
+}
+
+impl PeriodicCompressor {
+    /// Creates a periodic compressor of a render target with the given size.
+    ///
+    /// Allocates both output buffers, which support `STORAGE` and `COPY_SRC`, so they can be
+    /// copied into block compressed textures.
+    ///
+    /// # Arguments
+    /// * `variant` - The block compression variant of the outputs
+    /// * `width` - The width of the render target in pixels
+    /// * `height` - The height of the render target in pixels
+    /// * `interval` - The number of frames between two captures. An interval of 1 captures every
+    ///   frame
+    ///
+    /// # Panics
+    /// - If `interval` is zero
+    pub fn new(
+        device: Arc<Device>,
+        variant: CompressionVariant,
+        width: u32,
+        height: u32,
+        interval: u32,
+    ) -> Self {
+        assert_ne!(interval, 0, "the capture interval needs to be at least 1");
+
+        let size = variant.blocks_byte_size(width, height) as u64;
+        let outputs = ["periodic blocks buffer 0", "periodic blocks buffer 1"].map(|label| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        });
+
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            device,
+            variant,
+            width,
+            height,
+            interval,
+            frame: 0,
+            outputs,
+            in_flight: [false; 2],
+            latest: None,
+            generation: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Returns the block compression variant of the outputs.
+    pub fn variant(&self) -> CompressionVariant {
+        self.variant
+    }
+
+    /// Returns the width of the render target in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the render target in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of frames between two captures.
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Returns both output buffers. Which one holds the latest blocks alternates between
+    /// captures.
+    pub fn outputs(&self) -> &[Buffer; 2] {
+        &self.outputs
+    }
+
+    /// Advances to the next frame and captures the render target if the frame is due.
+    ///
+    /// The first call always captures. Returns the ID of the added compression task, or `None`
+    /// if the frame was not due or the output was still in flight. The render target needs the
+    /// size given to [`PeriodicCompressor::new()`], all other requirements are the same as for
+    /// [`GpuBlockCompressor::add_compression_task()`].
+    ///
+    /// # Panics
+    /// - For the same reasons as [`GpuBlockCompressor::add_compression_task()`]
+    pub fn frame(
+        &mut self,
+        compressor: &mut GpuBlockCompressor,
+        render_target: &TextureView,
+    ) -> Option<TaskId> {
+        let frame = self.frame;
+        self.frame += 1;
+
+        if frame % self.interval as u64 != 0 {
+            return None;
+        }
+
+        let index = match self.latest {
+            Some((latest, _)) => 1 - latest,
+            None => (frame / self.interval as u64 % 2) as usize,
+        };
+        if self.in_flight[index] {
+            return None;
+        }
+
+        let id = compressor.add_compression_task(
+            self.variant,
+            render_target,
+            self.width,
+            self.height,
+            &self.outputs[index],
+            None,
+            None,
+            TaskOptions::default(),
+        );
+
+        let sender = self.sender.clone();
+        compressor.on_complete(id, move |_| {
+            // The receiver is gone if the periodic compressor was dropped in the meantime.
+            let _ = sender.send((index, frame));
+        });
+        self.in_flight[index] = true;
+
+        Some(id)
+    }
+
+    /// Polls the device without blocking and tracks the finished captures.
+    ///
+    /// Returns `true` if the latest output changed since the last call.
+    pub fn poll(&mut self) -> bool {
+        self.device.poll(Maintain::Poll);
+
+        let mut changed = false;
+        while let Ok((index, frame)) = self.receiver.try_recv() {
+            self.in_flight[index] = false;
+
+            if self
+                .latest_frame()
+                .map_or(true, |latest_frame| frame > latest_frame)
+            {
+                self.latest = Some((index, frame));
+                self.generation += 1;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Returns the output buffer with the blocks of the most recent finished capture, or `None`
+    /// if no capture has finished yet.
+    pub fn latest(&self) -> Option<&Buffer> {
+        self.latest.map(|(index, _)| &self.outputs[index])
+    }
+
+    /// Returns the frame of the most recent finished capture, counted in calls to
+    /// [`PeriodicCompressor::frame()`] starting at 0.
+    pub fn latest_frame(&self) -> Option<u64> {
+        self.latest.map(|(_, frame)| frame)
+    }
+
+    /// Returns the number of captures that became the latest output so far.
+    ///
+    /// Engines can compare it with the generation of their last upload to detect new blocks.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns `true` if a capture was added, but has not finished yet.
+    pub fn is_pending(&self) -> bool {
+        self.in_flight.contains(&true)
+    }
+}
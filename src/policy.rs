@@ -0,0 +1,119 @@
+//! Decides whether a texture is worth compressing.
+//!
+//! Block compression saves memory and bandwidth, but small textures like UI icons gain little
+//! from it: they take up a few kilobytes either way, blur their fine details and pay the padding
+//! of partial blocks. Every asset pipeline ends up with a rule like "don't compress anything
+//! smaller than 64x64". [`CompressionPolicy`] keeps that rule in one place and returns
+//! [`CompressionVariant::RGBA8`] for the textures that should stay uncompressed, so the result
+//! can be passed straight to the encoders or the GPU compressor.
+//!
+//! # Example
+//! ```
+//! use block_compression::{policy::CompressionPolicy, BC7Settings, CompressionVariant};
+//!
+//! let policy = CompressionPolicy::default();
+//! let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+//!
+//! assert_eq!(policy.recommend(variant, 32, 32), CompressionVariant::RGBA8);
+//! assert_eq!(policy.recommend(variant, 256, 256), variant);
+//!
+//! // Stricter thresholds for a pipeline with a tight memory budget.
+//! let policy = CompressionPolicy {
+//!     min_pixel_count: 16 * 16,
+//!     ..CompressionPolicy::new()
+//! };
+//! assert_eq!(policy.recommend(variant, 32, 32), variant);
+//! ```
+
+use crate::CompressionVariant;
+
+/// The thresholds below which textures are stored uncompressed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CompressionPolicy {
+    /// Textures with fewer pixels are stored uncompressed. Defaults to 64x64 pixels.
+    pub min_pixel_count: u32,
+    /// Textures whose width or height is smaller are stored uncompressed. Defaults to 4 pixels,
+    /// so lookup tables and gradients that are narrower than a block keep their exact values and
+    /// don't pay for padded blocks.
+    pub min_dimension: u32,
+}
+
+impl CompressionPolicy {
+    /// Creates a policy with the default thresholds.
+    pub const fn new() -> Self {
+        Self {
+            min_pixel_count: 64 * 64,
+            min_dimension: 4,
+        }
+    }
+
+    /// Returns `true` if a texture of the given size is worth compressing.
+    pub const fn should_compress(&self, width: u32, height: u32) -> bool {
+        let pixel_count = width as u64 * height as u64;
+
+        pixel_count >= self.min_pixel_count as u64
+            && width >= self.min_dimension
+            && height >= self.min_dimension
+    }
+
+    /// Returns the variant to store a texture of the given size with.
+    ///
+    /// Returns `variant` if the texture is worth compressing and [`CompressionVariant::RGBA8`]
+    /// otherwise. HDR variants are always kept, since RGBA8 can't store their range.
+    pub const fn recommend(
+        &self,
+        variant: CompressionVariant,
+        width: u32,
+        height: u32,
+    ) -> CompressionVariant {
+        if variant.is_hdr() || self.should_compress(width, height) {
+            variant
+        } else {
+            CompressionVariant::RGBA8
+        }
+    }
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend() {
+        let policy = CompressionPolicy::new();
+        let variant = CompressionVariant::BC1;
+
+        assert_eq!(policy.recommend(variant, 64, 64), variant);
+        assert_eq!(policy.recommend(variant, 63, 64), CompressionVariant::RGBA8);
+        assert_eq!(
+            policy.recommend(variant, 4096, 2),
+            CompressionVariant::RGBA8
+        );
+        assert_eq!(policy.recommend(variant, 0, 0), CompressionVariant::RGBA8);
+        assert_eq!(
+            policy.recommend(CompressionVariant::RGBA8, 256, 256),
+            CompressionVariant::RGBA8
+        );
+
+        let policy = CompressionPolicy {
+            min_pixel_count: 0,
+            min_dimension: 0,
+        };
+        assert_eq!(policy.recommend(variant, 1, 1), variant);
+    }
+
+    #[cfg(feature = "bc6h")]
+    #[test]
+    fn test_hdr_variants_are_kept() {
+        use crate::BC6HSettings;
+
+        let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+        assert_eq!(CompressionPolicy::new().recommend(variant, 8, 8), variant);
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, MapMode};
+
+const STATE_IDLE: u8 = 0;
+const STATE_MAPPING: u8 = 1;
+const STATE_READY: u8 = 2;
+
+/// A copy of a range of the blocks buffer into the staging buffer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct RangeCopy {
+    source: Range<u64>,
+    staging_offset: u64,
+}
+
+/// Downloads selected byte ranges of a blocks buffer instead of the whole buffer.
+///
+/// Incremental workflows like texture painting only re-compress the blocks of a dirty region.
+/// Copying the whole blocks buffer back to the CPU after every stroke wastes bus bandwidth on
+/// blocks that didn't change. The readback owns a staging buffer that is just large enough for
+/// the given ranges, which are packed one after the other in the order they were given.
+/// Adjacent ranges are copied with a single copy command.
+///
+/// The ranges are usually the [`Tile::row_byte_ranges()`] of the dirty tiles, or the
+/// [`Tile::byte_range`] of tiles that are [contiguous].
+///
+/// The lifecycle of a readback is:
+///
+/// 1. [`SparseReadback::record()`] copies the ranges of a blocks buffer into the staging buffer.
+/// 2. After the command buffer was submitted, [`SparseReadback::request_map()`] starts mapping.
+/// 3. Once the device was polled and the readback is ready, [`SparseReadback::read()`] or
+///    [`SparseReadback::scatter_into()`] hands out the data. The readback can be recorded again
+///    afterward.
+///
+/// # Example
+///
+/// Updating the CPU copy of the blocks of a texture after a tile was re-compressed:
+///
+/// ```ignore
+/// let tile = tiles(variant, width, height, 64, 64).nth(dirty_tile).unwrap();
+/// let readback = SparseReadback::new(&device, tile.row_byte_ranges());
+///
+/// let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+/// {
+///     let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+///     compressor.compress(&mut pass).unwrap();
+/// }
+/// readback.record(&blocks_buffer, &mut encoder);
+/// queue.submit([encoder.finish()]);
+///
+/// readback.request_map();
+/// device.poll(Maintain::Wait);
+/// assert!(readback.scatter_into(&mut cpu_blocks));
+/// ```
+///
+/// [`Tile::row_byte_ranges()`]: crate::tiling::Tile::row_byte_ranges
+/// [`Tile::byte_range`]: crate::tiling::Tile::byte_range
+/// [contiguous]: crate::tiling::Tile::is_contiguous
+pub struct SparseReadback {
+    staging: Buffer,
+    copies: Vec<RangeCopy>,
+    size: u64,
+    state: Arc<AtomicU8>,
+}
+
+impl SparseReadback {
+    /// Creates a readback of the given byte ranges of a blocks buffer.
+    ///
+    /// # Panics
+    /// - If a range is empty or its start or end is not a multiple of 4, which copies between
+    ///   buffers require. The ranges of all blocks and rows of texels fulfill this.
+    /// - If no range is given
+    pub fn new(device: &Device, ranges: impl IntoIterator<Item = Range<u64>>) -> Self {
+        let (copies, size) = plan_copies(ranges);
+        assert!(size != 0, "readback needs at least one range");
+
+        let staging = device.create_buffer(&BufferDescriptor {
+            label: Some("sparse readback staging buffer"),
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            staging,
+            copies,
+            size,
+            state: Arc::new(AtomicU8::new(STATE_IDLE)),
+        }
+    }
+
+    /// Returns the number of bytes that are downloaded, which is the sum of the sizes of all
+    /// ranges.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the number of copy commands [`SparseReadback::record()`] records, after adjacent
+    /// ranges were merged.
+    pub fn copy_count(&self) -> usize {
+        self.copies.len()
+    }
+
+    /// Records the copies of the ranges of `buffer` into the staging buffer.
+    ///
+    /// # Panics
+    /// - If the buffer can't be used as a copy source
+    /// - If a range ends after the end of the buffer
+    /// - If the staging buffer is still mapped or waits for its mapping
+    pub fn record(&self, buffer: &Buffer, encoder: &mut CommandEncoder) {
+        assert!(
+            buffer.usage().contains(BufferUsages::COPY_SRC),
+            "buffer needs to be a copy source for readbacks"
+        );
+        assert_eq!(
+            self.state.load(Ordering::Acquire),
+            STATE_IDLE,
+            "readback needs to be read before it can be recorded again"
+        );
+
+        for copy in &self.copies {
+            assert!(
+                copy.source.end <= buffer.size(),
+                "range {:?} is outside of the buffer of {} bytes",
+                copy.source,
+                buffer.size()
+            );
+
+            encoder.copy_buffer_to_buffer(
+                buffer,
+                copy.source.start,
+                &self.staging,
+                copy.staging_offset,
+                copy.source.end - copy.source.start,
+            );
+        }
+    }
+
+    /// Starts mapping the staging buffer.
+    ///
+    /// Must be called after the command buffer containing the copies was submitted. The device
+    /// needs to be polled for the mapping to finish.
+    ///
+    /// # Panics
+    /// - If the staging buffer is already mapped or waits for its mapping
+    pub fn request_map(&self) {
+        let transitioned = self
+            .state
+            .compare_exchange(
+                STATE_IDLE,
+                STATE_MAPPING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+        assert!(transitioned, "readback is already mapped");
+
+        let state = self.state.clone();
+        self.staging
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let next = match result {
+                    Ok(()) => STATE_READY,
+                    Err(_) => STATE_IDLE,
+                };
+                state.store(next, Ordering::Release);
+            });
+    }
+
+    /// Returns `true` once the mapping has finished and the data can be read.
+    pub fn is_ready(&self) -> bool {
+        self.state.load(Ordering::Acquire) == STATE_READY
+    }
+
+    /// Reads the downloaded ranges, packed one after the other in the order they were given, if
+    /// the mapping has finished.
+    ///
+    /// The staging buffer is unmapped afterward, even if `f` panics. Returns `None` if the
+    /// readback is not ready yet.
+    pub fn read<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        if !self.is_ready() {
+            return None;
+        }
+
+        // Declared before the mapped range, so the range is dropped before the buffer is unmapped.
+        let _unmap = UnmapGuard {
+            staging: &self.staging,
+            state: &self.state,
+        };
+        let data = self.staging.slice(..).get_mapped_range();
+
+        Some(f(&data))
+    }
+
+    /// Writes the downloaded ranges to their offsets in `blocks`, a CPU copy of the whole
+    /// blocks buffer, if the mapping has finished. All other bytes of `blocks` keep their value.
+    ///
+    /// Returns `false` if the readback is not ready yet.
+    ///
+    /// # Panics
+    /// - If a range ends after the end of `blocks`
+    pub fn scatter_into(&self, blocks: &mut [u8]) -> bool {
+        self.read(|data| scatter(&self.copies, data, blocks))
+            .is_some()
+    }
+}
+
+/// Unmaps the staging buffer of a readback when dropped, so a panic while reading doesn't leave
+/// it mapped.
+struct UnmapGuard<'a> {
+    staging: &'a Buffer,
+    state: &'a AtomicU8,
+}
+
+impl Drop for UnmapGuard<'_> {
+    fn drop(&mut self) {
+        self.staging.unmap();
+        self.state.store(STATE_IDLE, Ordering::Release);
+    }
+}
+
+/// Merges adjacent ranges and assigns them their offsets in the staging buffer. Returns the
+/// copies and the size of the staging buffer.
+fn plan_copies(ranges: impl IntoIterator<Item = Range<u64>>) -> (Vec<RangeCopy>, u64) {
+    let mut copies: Vec<RangeCopy> = Vec::new();
+    let mut size = 0;
+
+    for range in ranges {
+        assert!(range.start < range.end, "range {range:?} is empty");
+        assert!(
+            range.start % 4 == 0 && range.end % 4 == 0,
+            "range {range:?} is not aligned to 4 bytes"
+        );
+
+        match copies.last_mut() {
+            Some(last) if last.source.end == range.start => last.source.end = range.end,
+            _ => copies.push(RangeCopy {
+                source: range.clone(),
+                staging_offset: size,
+            }),
+        }
+
+        size += range.end - range.start;
+    }
+
+    (copies, size)
+}
+
+/// Copies the packed data of the ranges to their offsets in the blocks buffer.
+fn scatter(copies: &[RangeCopy], data: &[u8], blocks: &mut [u8]) {
+    for copy in copies {
+        let start = copy.staging_offset as usize;
+        let len = (copy.source.end - copy.source.start) as usize;
+
+        assert!(
+            copy.source.end <= blocks.len() as u64,
+            "range {:?} is outside of the blocks buffer of {} bytes",
+            copy.source,
+            blocks.len()
+        );
+
+        blocks[copy.source.start as usize..copy.source.end as usize]
+            .copy_from_slice(&data[start..start + len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_copies_merges_adjacent_ranges() {
+        let (copies, size) = plan_copies([0..16, 16..32, 64..72, 128..144, 144..148]);
+
+        assert_eq!(size, 60);
+        assert_eq!(
+            copies,
+            [
+                RangeCopy {
+                    source: 0..32,
+                    staging_offset: 0,
+                },
+                RangeCopy {
+                    source: 64..72,
+                    staging_offset: 32,
+                },
+                RangeCopy {
+                    source: 128..148,
+                    staging_offset: 40,
+                },
+            ]
+        );
+
+        let source: Vec<u8> = (0..148).map(|i| i as u8).collect();
+        let data: Vec<u8> = copies
+            .iter()
+            .flat_map(|copy| &source[copy.source.start as usize..copy.source.end as usize])
+            .copied()
+            .collect();
+        let mut blocks = vec![0; source.len()];
+        scatter(&copies, &data, &mut blocks);
+
+        for (offset, (&value, &expected)) in blocks.iter().zip(&source).enumerate() {
+            let copied = copies
+                .iter()
+                .any(|copy| copy.source.contains(&(offset as u64)));
+            assert_eq!(value, if copied { expected } else { 0 }, "offset {offset}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not aligned")]
+    fn test_plan_copies_rejects_unaligned_ranges() {
+        plan_copies(std::iter::once(0..6));
+    }
+}
@@ -1,10 +1,305 @@
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+use std::fmt;
+
 #[cfg(any(feature = "bc6h", feature = "bc7"))]
 use bytemuck::{Pod, Zeroable};
 
+/// Quality levels as used by NVIDIA's Texture Tools (NVTT).
+///
+/// Allows mapping existing NVTT quality configurations onto the presets of this crate with
+/// [`BC6HSettings::from_nvtt_quality()`] and [`BC7Settings::from_nvtt_quality()`].
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bc6h", feature = "bc7"))))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum NvttQuality {
+    /// `Quality_Fastest`
+    Fastest,
+    /// `Quality_Normal`
+    #[default]
+    Normal,
+    /// `Quality_Production`
+    Production,
+    /// `Quality_Highest`
+    Highest,
+}
+
+/// Quality presets that map to equivalent settings of every variant.
+///
+/// Allows configuring the quality of a pipeline without knowing which variant a texture uses,
+/// see [`CompressionVariant::with_preset()`](crate::CompressionVariant::with_preset). Variants
+/// without settings compress the same with every preset.
+///
+/// | Preset  | BC6H                      | BC7 opaque                      | BC7 alpha                      |
+/// |---------|---------------------------|---------------------------------|--------------------------------|
+/// | `Fast`  | [`BC6HSettings::fast()`]  | [`BC7Settings::opaque_fast()`]  | [`BC7Settings::alpha_fast()`]  |
+/// | `Basic` | [`BC6HSettings::basic()`] | [`BC7Settings::opaque_basic()`] | [`BC7Settings::alpha_basic()`] |
+/// | `Slow`  | [`BC6HSettings::slow()`]  | [`BC7Settings::opaque_slow()`]  | [`BC7Settings::alpha_slow()`]  |
+#[cfg(any(feature = "bc15", feature = "bc6h", feature = "bc7"))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Preset {
+    /// Fast compression for previews and iteration.
+    Fast,
+    /// Balanced compression for most assets.
+    #[default]
+    Basic,
+    /// Slow compression for final builds.
+    Slow,
+}
+
+/// Normalizes the profile names of ISPC and `intel_tex_2`, so that `"alpha_ultrafast"`,
+/// `"alpha_ultra_fast_settings"` and `"AlphaUltraFast"` all map to `"alphaultrafast"`. The
+/// `opaque` prefix of `intel_tex_2` is dropped, since ISPC names the opaque profiles without it.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn normalize_profile_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let name = name.strip_suffix("settings").unwrap_or(&name);
+    let name = name.strip_prefix("opaque").unwrap_or(name);
+    name.to_string()
+}
+
+/// Errors of [`BC6HSettings::validate()`] and [`BC7Settings::validate()`].
+///
+/// The presets and builder methods always produce valid settings. Settings that were created in
+/// other ways, for example by casting bytes with `bytemuck`, can describe searches that don't
+/// produce a block or that read outside of the partition tables of the encoders.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bc6h", feature = "bc7"))))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SettingsError {
+    /// No mode is searched for every block, so some blocks are never encoded. Modes that search
+    /// partitions are only searched with a skip threshold above zero, and the alpha presets
+    /// don't search modes 4, 5 and 7 for opaque blocks.
+    NoModes,
+    /// The number of channels is not 3 or 4.
+    Channels(u32),
+    /// A skip threshold is larger than the number of partitions of its modes.
+    SkipThreshold {
+        /// The modes the threshold belongs to.
+        modes: &'static str,
+        /// The number of partitions the threshold searches.
+        threshold: u32,
+        /// The number of partitions of the modes.
+        partitions: u32,
+    },
+    /// The channels tried on the scalar line of modes 4 and 5 are not a range of the channels
+    /// of the settings.
+    Mode45Channels {
+        /// The first channel that is tried.
+        first: u32,
+        /// The channel after the last one that is tried.
+        end: u32,
+    },
+    /// A weight or error threshold is negative or not finite.
+    InvalidValue {
+        /// The name of the setting.
+        setting: &'static str,
+    },
+    /// The handling of non-finite values is not one of [`NonFiniteHandling`].
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    NonFiniteHandling(u32),
+}
+
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoModes => write!(f, "the settings don't search a mode for every block"),
+            Self::Channels(channels) => {
+                write!(f, "the settings encode {channels} channels instead of 3 or 4")
+            }
+            Self::SkipThreshold {
+                modes,
+                threshold,
+                partitions,
+            } => write!(
+                f,
+                "the skip threshold of {modes} searches {threshold} partitions, but there are only {partitions}"
+            ),
+            Self::Mode45Channels { first, end } => write!(
+                f,
+                "the scalar channels {first}..{end} of modes 4 and 5 are not a range of the channels"
+            ),
+            Self::InvalidValue { setting } => {
+                write!(f, "the {setting} must be a finite, non-negative number")
+            }
+            #[cfg(feature = "bc6h")]
+            Self::NonFiniteHandling(raw) => write!(f, "unknown non-finite handling {raw}"),
+        }
+    }
+}
+
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+impl std::error::Error for SettingsError {}
+
+/// Checks that a skip threshold doesn't search more partitions than there are.
+#[cfg(any(feature = "bc6h", feature = "bc7"))]
+fn check_skip_threshold(
+    modes: &'static str,
+    threshold: u32,
+    partitions: u32,
+) -> Result<(), SettingsError> {
+    if threshold > partitions {
+        return Err(SettingsError::SkipThreshold {
+            modes,
+            threshold,
+            partitions,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the bits of an `f32` setting are a finite, non-negative number.
+#[cfg(feature = "bc7")]
+fn check_non_negative(setting: &'static str, bits: u32) -> Result<(), SettingsError> {
+    let value = f32::from_bits(bits);
+    if !(value.is_finite() && value >= 0.0) {
+        return Err(SettingsError::InvalidValue { setting });
+    }
+    Ok(())
+}
+
+/// How the BC6H encoders handle color channels of the source that are NaN or infinite.
+///
+/// BC6H can only store finite values. Without handling, a single non-finite texel spreads into
+/// the endpoints and corrupts its whole block. Finite values outside of the half float range of
+/// float sources on the GPU are always clamped to the largest finite half float.
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum NonFiniteHandling {
+    /// Replaces NaN with zero and infinite values with the largest finite half float of the
+    /// same sign.
+    #[default]
+    Clamp,
+    /// Replaces the channel with the average of the same channel of the direct neighbors to the
+    /// left, right, top and bottom that are inside the image and finite, or with zero if there
+    /// is no such neighbor.
+    NeighborAverage,
+    /// Treats non-finite channels as an error. The CPU encoders panic. The GPU compressor can't
+    /// fail a task, so it encodes the channels as zero and writes an infinite error for the
+    /// block if the task has an [`errors_buffer`].
+    ///
+    /// [`errors_buffer`]: crate::TaskOptions::errors_buffer
+    Reject,
+}
+
+#[cfg(feature = "bc6h")]
+impl NonFiniteHandling {
+    pub(crate) const CLAMP: u32 = 0;
+    pub(crate) const NEIGHBOR_AVERAGE: u32 = 1;
+    pub(crate) const REJECT: u32 = 2;
+
+    const fn raw(self) -> u32 {
+        match self {
+            Self::Clamp => Self::CLAMP,
+            Self::NeighborAverage => Self::NEIGHBOR_AVERAGE,
+            Self::Reject => Self::REJECT,
+        }
+    }
+}
+
+/// A linear remapping of HDR values before they are encoded as BC6H.
+///
+/// BC6H stores half floats, which overflow above 65504 and lose precision below about 6e-5.
+/// Content with a more extreme dynamic range, like physical light units, can be remapped into
+/// the range of half floats before the encoding and restored with the [inverse](HdrRemap::inverse)
+/// when the texture is sampled, for example by multiplying with the exposure in the shader. Every
+/// color channel is encoded as `value * scale + bias`. Alpha is not affected.
+///
+/// # Example
+/// ```
+/// use block_compression::HdrRemap;
+///
+/// let remap = HdrRemap::from_range(0.0, 1.0e6);
+/// assert_eq!(remap.apply(1.0e6), 1.0);
+/// assert_eq!(remap.inverse().apply(0.5), 5.0e5);
+/// ```
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HdrRemap {
+    /// The factor the values are multiplied with.
+    pub scale: f32,
+    /// The offset that is added to the scaled values.
+    pub bias: f32,
+}
+
+#[cfg(feature = "bc6h")]
+impl Default for HdrRemap {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[cfg(feature = "bc6h")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+impl HdrRemap {
+    /// The remapping that keeps all values unchanged.
+    pub const IDENTITY: Self = Self {
+        scale: 1.0,
+        bias: 0.0,
+    };
+
+    /// Creates a remapping of `value * scale + bias`.
+    ///
+    /// # Panics
+    /// - If `scale` is zero or not finite, or `bias` is not finite
+    pub fn new(scale: f32, bias: f32) -> Self {
+        let remap = Self { scale, bias };
+        remap.validate();
+        remap
+    }
+
+    /// Creates a remapping of the values between `min` and `max` into `[0, 1]`, where half
+    /// floats have their finest steps and can't overflow.
+    ///
+    /// # Panics
+    /// - If `min` or `max` is not finite, or `max` is not larger than `min`
+    pub fn from_range(min: f32, max: f32) -> Self {
+        assert!(
+            min.is_finite() && max.is_finite() && max > min,
+            "the range needs finite bounds with max larger than min"
+        );
+
+        let scale = 1.0 / (max - min);
+        Self::new(scale, -min * scale)
+    }
+
+    /// Returns the remapped value.
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.scale + self.bias
+    }
+
+    /// Returns the remapping that restores the original values.
+    pub fn inverse(&self) -> Self {
+        Self {
+            scale: 1.0 / self.scale,
+            bias: -self.bias / self.scale,
+        }
+    }
+
+    /// Returns `true` if the remapping keeps all values unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    pub(crate) fn validate(&self) {
+        assert!(
+            self.scale.is_finite() && self.scale != 0.0 && self.bias.is_finite(),
+            "the HDR remapping needs a finite, non-zero scale and a finite bias"
+        );
+    }
+}
+
 /// Encoding settings for BC6H.
 #[cfg(feature = "bc6h")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Pod, Zeroable)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Pod, Zeroable)]
 #[repr(C)]
 pub struct BC6HSettings {
     pub(crate) slow_mode: u32,
@@ -12,6 +307,8 @@ pub struct BC6HSettings {
     pub(crate) refine_iterations_1p: u32,
     pub(crate) refine_iterations_2p: u32,
     pub(crate) fast_skip_threshold: u32,
+    pub(crate) non_finite: u32,
+    pub(crate) precise_pca: u32,
 }
 
 #[cfg(feature = "bc6h")]
@@ -24,6 +321,8 @@ impl BC6HSettings {
             fast_skip_threshold: 0,
             refine_iterations_1p: 0,
             refine_iterations_2p: 0,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: false as _,
         }
     }
 
@@ -35,6 +334,8 @@ impl BC6HSettings {
             fast_skip_threshold: 2,
             refine_iterations_1p: 0,
             refine_iterations_2p: 1,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: false as _,
         }
     }
 
@@ -46,6 +347,8 @@ impl BC6HSettings {
             fast_skip_threshold: 4,
             refine_iterations_1p: 2,
             refine_iterations_2p: 2,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: false as _,
         }
     }
 
@@ -57,6 +360,8 @@ impl BC6HSettings {
             fast_skip_threshold: 10,
             refine_iterations_1p: 2,
             refine_iterations_2p: 2,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: false as _,
         }
     }
 
@@ -68,14 +373,216 @@ impl BC6HSettings {
             fast_skip_threshold: 32,
             refine_iterations_1p: 2,
             refine_iterations_2p: 2,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: true as _,
+        }
+    }
+
+    /// Fast preview settings for live previews like editor viewports.
+    ///
+    /// Uses the fast mode search without any refinement, which is the fastest configuration
+    /// of the BC6H encoder. The quality is not meant for final assets.
+    pub const fn preview() -> Self {
+        Self {
+            slow_mode: false as _,
+            fast_mode: true as _,
+            fast_skip_threshold: 0,
+            refine_iterations_1p: 0,
+            refine_iterations_2p: 0,
+            non_finite: NonFiniteHandling::CLAMP,
+            precise_pca: false as _,
+        }
+    }
+
+    /// Sets how NaN and infinite color channels of the source are handled.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC6HSettings, NonFiniteHandling};
+    ///
+    /// let settings = BC6HSettings::basic().with_non_finite_handling(NonFiniteHandling::Reject);
+    /// assert_eq!(settings.non_finite_handling(), NonFiniteHandling::Reject);
+    /// ```
+    pub const fn with_non_finite_handling(mut self, handling: NonFiniteHandling) -> Self {
+        self.non_finite = handling.raw();
+        self
+    }
+
+    /// Returns how NaN and infinite color channels of the source are handled.
+    pub const fn non_finite_handling(&self) -> NonFiniteHandling {
+        match self.non_finite {
+            NonFiniteHandling::NEIGHBOR_AVERAGE => NonFiniteHandling::NeighborAverage,
+            NonFiniteHandling::REJECT => NonFiniteHandling::Reject,
+            _ => NonFiniteHandling::Clamp,
         }
     }
+
+    /// Sets whether the principal axis of the endpoints is computed with extra precision.
+    ///
+    /// The covariance of a block is accumulated from the sums of its values and their squares,
+    /// which cancel out for blocks with large values and small differences between them, like
+    /// bright HDR highlights. The axis then points in an arbitrary direction and the endpoints
+    /// miss the colors of the block. With the precise PCA, the covariance is computed from the
+    /// differences to the mean of the block, in double precision on the CPU and with
+    /// compensated sums on the GPU. It is enabled by [`BC6HSettings::very_slow()`], which makes
+    /// it differ from the ISPC profile for these blocks.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC6HSettings;
+    ///
+    /// let settings = BC6HSettings::slow().with_precise_pca(true);
+    /// assert!(settings.precise_pca());
+    /// ```
+    pub const fn with_precise_pca(mut self, enabled: bool) -> Self {
+        self.precise_pca = enabled as _;
+        self
+    }
+
+    /// Returns whether the principal axis of the endpoints is computed with extra precision.
+    pub const fn precise_pca(&self) -> bool {
+        self.precise_pca != 0
+    }
+
+    /// Checks that the settings describe a search the encoders can run.
+    ///
+    /// The presets and builder methods always produce valid settings, so this only needs to be
+    /// called for settings that were created in other ways, for example by casting bytes that
+    /// were stored with `bytemuck`. [`GpuBlockCompressor`](crate::GpuBlockCompressor) validates
+    /// the settings of every task when it is added.
+    ///
+    /// # Errors
+    /// - [`SettingsError::SkipThreshold`] if the two-region modes search more than 32 partitions
+    /// - [`SettingsError::NonFiniteHandling`] if the handling of non-finite values is unknown
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC6HSettings;
+    ///
+    /// assert_eq!(BC6HSettings::basic().validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        check_skip_threshold("the two-region modes", self.fast_skip_threshold, 32)?;
+
+        if self.non_finite > NonFiniteHandling::REJECT {
+            return Err(SettingsError::NonFiniteHandling(self.non_finite));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the settings of an ISPC or `intel_tex_2` BC6H profile by its name.
+    ///
+    /// Only parses the name of a profile, it doesn't convert `intel_tex_2` settings values.
+    /// Accepts the ISPC profile names (`"veryfast"`, `"fast"`, `"basic"`, `"slow"`,
+    /// `"veryslow"`) as well as the names of the `intel_tex_2::bc6h` settings functions (for
+    /// example `"very_fast_settings"`). The presets of this crate are a port of the ISPC
+    /// profiles, so both produce the same encoding. Returns `None` for unknown names.
+    pub fn from_ispc_profile_name(name: &str) -> Option<Self> {
+        match normalize_profile_name(name).as_str() {
+            "veryfast" => Some(Self::very_fast()),
+            "fast" => Some(Self::fast()),
+            "basic" => Some(Self::basic()),
+            "slow" => Some(Self::slow()),
+            "veryslow" => Some(Self::very_slow()),
+            _ => None,
+        }
+    }
+
+    /// Returns the settings that correspond to a NVTT quality level.
+    ///
+    /// | NVTT         | Settings                      |
+    /// |--------------|-------------------------------|
+    /// | `Fastest`    | [`BC6HSettings::very_fast()`] |
+    /// | `Normal`     | [`BC6HSettings::basic()`]     |
+    /// | `Production` | [`BC6HSettings::slow()`]      |
+    /// | `Highest`    | [`BC6HSettings::very_slow()`] |
+    pub const fn from_nvtt_quality(quality: NvttQuality) -> Self {
+        match quality {
+            NvttQuality::Fastest => Self::very_fast(),
+            NvttQuality::Normal => Self::basic(),
+            NvttQuality::Production => Self::slow(),
+            NvttQuality::Highest => Self::very_slow(),
+        }
+    }
+
+    /// Returns the settings of a [`Preset`].
+    pub const fn from_preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Fast => Self::fast(),
+            Preset::Basic => Self::basic(),
+            Preset::Slow => Self::slow(),
+        }
+    }
+}
+
+/// How the BC7 encoder searches the partitions of mode 2.
+///
+/// Mode 2 has three subsets with 64 partitions each, which makes its full search by far the
+/// most expensive one. It only pays off for blocks with three distinct color groups, which are
+/// rare in most textures.
+#[cfg(feature = "bc7")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Mode2Search {
+    /// Tries all 64 partitions for every block.
+    Full,
+    /// Never uses mode 2.
+    Skip,
+    /// Tries mode 2 after all other modes, and only the few most promising partitions for which
+    /// an estimate of the error could beat the best error found so far. The estimate is the
+    /// error of fitting a line through the colors of every subset, which the quantized endpoints
+    /// can't beat. Most blocks skip mode 2 entirely, while the blocks that benefit from it still
+    /// use it.
+    Estimate,
+}
+
+#[cfg(feature = "bc7")]
+impl Mode2Search {
+    pub(crate) const FULL: u32 = 0;
+    pub(crate) const SKIP: u32 = 1;
+    pub(crate) const ESTIMATE: u32 = 2;
+
+    const fn raw(self) -> u32 {
+        match self {
+            Self::Full => Self::FULL,
+            Self::Skip => Self::SKIP,
+            Self::Estimate => Self::ESTIMATE,
+        }
+    }
+}
+
+/// The channel that BC7 modes 4 and 5 encode separately from the others.
+///
+/// Modes 4 and 5 store a block as a color line and a separate scalar line with its own
+/// endpoints and indices. By default the scalar line holds alpha, but a rotation can swap alpha
+/// with red, green or blue, so that one of the color channels gets the scalar treatment instead.
+/// The channel on the scalar line keeps its shape independent of the others, which protects
+/// data that isn't correlated with the rest of the block, like the multiplier of RGBM or a
+/// mask packed into a color channel. The rotation is stored in every block, so decoders always
+/// return the channels in their original order.
+#[cfg(feature = "bc7")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Mode45ScalarChannel {
+    /// Tries every channel of the settings and keeps the rotation with the smallest error.
+    #[default]
+    Any,
+    /// Only encodes red on the scalar line.
+    Red,
+    /// Only encodes green on the scalar line.
+    Green,
+    /// Only encodes blue on the scalar line.
+    Blue,
+    /// Only encodes alpha on the scalar line, which never rotates the channels. Only available
+    /// for the alpha presets.
+    Alpha,
 }
 
 #[cfg(feature = "bc7")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
 /// Encoding settings for BC7.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Pod, Zeroable)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Pod, Zeroable)]
 #[repr(C)]
 pub struct BC7Settings {
     pub(crate) refine_iterations: [u32; 8],
@@ -84,11 +591,26 @@ pub struct BC7Settings {
     pub(crate) fast_skip_threshold_mode1: u32,
     pub(crate) fast_skip_threshold_mode3: u32,
     pub(crate) fast_skip_threshold_mode7: u32,
+    /// The first channel that is tried as the scalar channel of modes 4 and 5.
     pub(crate) mode45_channel0: u32,
+    /// The channel after the last one that is tried as the scalar channel of modes 4 and 5.
+    pub(crate) mode45_channel1: u32,
     pub(crate) refine_iterations_channel: u32,
     pub(crate) channels: u32,
+    /// The bits of the `f32` alpha weight, so the settings stay `Eq` and `Hash`.
+    pub(crate) alpha_weight: u32,
+    pub(crate) linear_error: u32,
+    pub(crate) precise_pca: u32,
+    /// Set to 1 if the search of a block starts with the mode of a neighboring block.
+    pub(crate) neighbor_seeding: u32,
+    /// The bits of the `f32` error up to which the mode of the neighbor is kept.
+    pub(crate) neighbor_max_error: u32,
 }
 
+/// The bits of an alpha weight of 1.0.
+#[cfg(feature = "bc7")]
+const DEFAULT_ALPHA_WEIGHT: u32 = 0x3F80_0000;
+
 #[cfg(feature = "bc7")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bc7")))]
 impl BC7Settings {
@@ -96,12 +618,18 @@ impl BC7Settings {
     pub const fn opaque_ultra_fast() -> Self {
         Self {
             channels: 3,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, false as _, false as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 3,
             fast_skip_threshold_mode3: 1,
             fast_skip_threshold_mode7: 0,
             mode45_channel0: 0,
+            mode45_channel1: 3,
             refine_iterations_channel: 0,
             refine_iterations: [2, 2, 2, 1, 2, 2, 1, 0],
         }
@@ -111,12 +639,18 @@ impl BC7Settings {
     pub const fn opaque_very_fast() -> Self {
         Self {
             channels: 3,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, true as _, false as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 3,
             fast_skip_threshold_mode3: 1,
             fast_skip_threshold_mode7: 0,
             mode45_channel0: 0,
+            mode45_channel1: 3,
             refine_iterations_channel: 0,
             refine_iterations: [2, 2, 2, 1, 2, 2, 1, 0],
         }
@@ -126,12 +660,18 @@ impl BC7Settings {
     pub const fn opaque_fast() -> Self {
         Self {
             channels: 3,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, true as _, false as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 12,
             fast_skip_threshold_mode3: 4,
             fast_skip_threshold_mode7: 0,
             mode45_channel0: 0,
+            mode45_channel1: 3,
             refine_iterations_channel: 0,
             refine_iterations: [2, 2, 2, 1, 2, 2, 2, 0],
         }
@@ -141,12 +681,18 @@ impl BC7Settings {
     pub const fn opaque_basic() -> Self {
         Self {
             channels: 3,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [true as _, true as _, true as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 12,
             fast_skip_threshold_mode3: 8,
             fast_skip_threshold_mode7: 0,
             mode45_channel0: 0,
+            mode45_channel1: 3,
             refine_iterations_channel: 2,
             refine_iterations: [2, 2, 2, 2, 2, 2, 2, 0],
         }
@@ -156,12 +702,18 @@ impl BC7Settings {
     pub const fn opaque_slow() -> Self {
         Self {
             channels: 3,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: true as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [true as _, true as _, true as _, true as _],
             skip_mode2: false as _,
             fast_skip_threshold_mode1: 64,
             fast_skip_threshold_mode3: 64,
             fast_skip_threshold_mode7: 0,
             mode45_channel0: 0,
+            mode45_channel1: 3,
             refine_iterations_channel: 4,
             refine_iterations: [4, 4, 4, 4, 4, 4, 4, 0],
         }
@@ -171,12 +723,18 @@ impl BC7Settings {
     pub const fn alpha_ultrafast() -> Self {
         Self {
             channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, false as _, true as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 0,
             fast_skip_threshold_mode3: 0,
             fast_skip_threshold_mode7: 4,
             mode45_channel0: 3,
+            mode45_channel1: 4,
             refine_iterations_channel: 1,
             refine_iterations: [2, 1, 2, 1, 1, 1, 2, 2],
         }
@@ -186,12 +744,18 @@ impl BC7Settings {
     pub const fn alpha_very_fast() -> Self {
         Self {
             channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, true as _, true as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 0,
             fast_skip_threshold_mode3: 0,
             fast_skip_threshold_mode7: 4,
             mode45_channel0: 3,
+            mode45_channel1: 4,
             refine_iterations_channel: 2,
             refine_iterations: [2, 1, 2, 1, 2, 2, 2, 2],
         }
@@ -201,12 +765,18 @@ impl BC7Settings {
     pub const fn alpha_fast() -> Self {
         Self {
             channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [false as _, true as _, true as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 4,
             fast_skip_threshold_mode3: 4,
             fast_skip_threshold_mode7: 8,
             mode45_channel0: 3,
+            mode45_channel1: 4,
             refine_iterations_channel: 2,
             refine_iterations: [2, 1, 2, 1, 2, 2, 2, 2],
         }
@@ -216,12 +786,18 @@ impl BC7Settings {
     pub const fn alpha_basic() -> Self {
         Self {
             channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [true as _, true as _, true as _, true as _],
             skip_mode2: true as _,
             fast_skip_threshold_mode1: 12,
             fast_skip_threshold_mode3: 8,
             fast_skip_threshold_mode7: 8,
             mode45_channel0: 0,
+            mode45_channel1: 4,
             refine_iterations_channel: 2,
             refine_iterations: [2, 2, 2, 2, 2, 2, 2, 2],
         }
@@ -231,14 +807,736 @@ impl BC7Settings {
     pub const fn alpha_slow() -> Self {
         Self {
             channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: true as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
             mode_selection: [true as _, true as _, true as _, true as _],
             skip_mode2: false as _,
             fast_skip_threshold_mode1: 64,
             fast_skip_threshold_mode3: 64,
             fast_skip_threshold_mode7: 64,
             mode45_channel0: 0,
+            mode45_channel1: 4,
             refine_iterations_channel: 4,
             refine_iterations: [4, 4, 4, 4, 4, 4, 4, 4],
         }
     }
+
+    /// Fast preview settings for live previews like editor viewports.
+    ///
+    /// Only searches mode 6 with a single quantization pass and no refinement, which is faster
+    /// than [`BC7Settings::opaque_ultra_fast()`]. Mode 6 also encodes alpha, so the settings
+    /// work for opaque and transparent images. The quality is not meant for final assets.
+    pub const fn preview() -> Self {
+        Self {
+            channels: 4,
+            alpha_weight: DEFAULT_ALPHA_WEIGHT,
+            linear_error: false as _,
+            precise_pca: false as _,
+            neighbor_seeding: false as _,
+            neighbor_max_error: 0,
+            mode_selection: [false as _, false as _, false as _, true as _],
+            skip_mode2: true as _,
+            fast_skip_threshold_mode1: 0,
+            fast_skip_threshold_mode3: 0,
+            fast_skip_threshold_mode7: 0,
+            mode45_channel0: 0,
+            mode45_channel1: 4,
+            refine_iterations_channel: 0,
+            refine_iterations: [0; 8],
+        }
+    }
+
+    /// Sets how the partitions of mode 2 are searched.
+    ///
+    /// Mode 2 is only searched by settings that search modes 0 and 2, which are the slow
+    /// presets. All other presets ignore this setting.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC7Settings, Mode2Search};
+    ///
+    /// let settings = BC7Settings::opaque_slow().with_mode2_search(Mode2Search::Estimate);
+    /// assert_eq!(settings.mode2_search(), Mode2Search::Estimate);
+    /// ```
+    pub const fn with_mode2_search(mut self, search: Mode2Search) -> Self {
+        self.skip_mode2 = search.raw();
+        self
+    }
+
+    /// Returns how the partitions of mode 2 are searched.
+    pub const fn mode2_search(&self) -> Mode2Search {
+        match self.skip_mode2 {
+            Mode2Search::FULL => Mode2Search::Full,
+            Mode2Search::ESTIMATE => Mode2Search::Estimate,
+            _ => Mode2Search::Skip,
+        }
+    }
+
+    /// Sets how much the error of the alpha channel counts relative to the error of a color
+    /// channel.
+    ///
+    /// The default weight of 1.0 treats all channels the same. Larger weights keep the alpha
+    /// closer to the source at the cost of the color, which suits cutouts and UI elements with
+    /// sharp alpha edges. Smaller weights favor the color, which suits soft translucency. Only
+    /// has an effect for the alpha presets.
+    ///
+    /// # Panics
+    /// - If `alpha_weight` is negative or not finite
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC7Settings;
+    ///
+    /// let settings = BC7Settings::alpha_basic().with_alpha_weight(4.0);
+    /// assert_eq!(settings.alpha_weight(), 4.0);
+    /// ```
+    pub fn with_alpha_weight(mut self, alpha_weight: f32) -> Self {
+        assert!(
+            alpha_weight.is_finite() && alpha_weight >= 0.0,
+            "alpha weight must be a finite, non-negative number"
+        );
+        self.alpha_weight = alpha_weight.to_bits();
+        self
+    }
+
+    /// Returns how much the error of the alpha channel counts relative to a color channel.
+    pub fn alpha_weight(&self) -> f32 {
+        f32::from_bits(self.alpha_weight)
+    }
+
+    /// Sets whether the error of the color channels is measured in linear space.
+    ///
+    /// By default the encoder minimizes the error of the stored 8-bit values. For sRGB textures
+    /// this over-penalizes errors in dark areas, where sRGB spends more codes on smaller changes
+    /// in brightness, and under-penalizes errors in bright areas. With the linear error, the
+    /// error of every color value is scaled by the squared slope of the sRGB transfer function
+    /// at the source value, which moves the precision to where it is visible. Only use it for
+    /// sRGB textures. Block errors written by the GPU compressor are also measured in linear
+    /// space.
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC7Settings;
+    ///
+    /// let settings = BC7Settings::opaque_basic().with_linear_error(true);
+    /// assert!(settings.linear_error());
+    /// ```
+    pub const fn with_linear_error(mut self, enabled: bool) -> Self {
+        self.linear_error = enabled as _;
+        self
+    }
+
+    /// Returns whether the error of the color channels is measured in linear space.
+    pub const fn linear_error(&self) -> bool {
+        self.linear_error != 0
+    }
+
+    /// Sets whether the principal axis of the endpoints is computed with extra precision.
+    ///
+    /// The covariance of a block is accumulated from the sums of its values and their squares,
+    /// which cancel out for blocks whose values are spread over a small part of a large range.
+    /// With the precise PCA, the covariance is computed from the differences to the mean of
+    /// the block, in double precision on the CPU and with compensated sums on the GPU. It is
+    /// enabled by [`BC7Settings::opaque_slow()`] and [`BC7Settings::alpha_slow()`].
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC7Settings;
+    ///
+    /// let settings = BC7Settings::opaque_basic().with_precise_pca(true);
+    /// assert!(settings.precise_pca());
+    /// ```
+    pub const fn with_precise_pca(mut self, enabled: bool) -> Self {
+        self.precise_pca = enabled as _;
+        self
+    }
+
+    /// Returns whether the principal axis of the endpoints is computed with extra precision.
+    pub const fn precise_pca(&self) -> bool {
+        self.precise_pca != 0
+    }
+
+    /// Sets whether the search of a block starts with the mode and partition of a neighboring
+    /// block, and up to which error that is good enough to skip the full search.
+    ///
+    /// Neighboring blocks of smooth content usually end up with the same mode and partition.
+    /// With the seeding, the endpoints of a block are first fitted with the choices of the block
+    /// to its left. If the error is not larger than `max_error`, the block is kept and the mode
+    /// search is skipped. Otherwise the full search runs, which can only improve upon the
+    /// seeded block. The error is measured like for
+    /// [`compress_rgba8_with_previous()`](crate::encode::compress_rgba8_with_previous): the sum
+    /// of the weighted squared errors of the 8-bit values of a block. A `max_error` of 16 roughly
+    /// halves the encode time of smooth textures at a loss of less than 0.1 dB, larger values
+    /// trade more quality for speed.
+    ///
+    /// The CPU encoder seeds every block with the block to its left, in the order of the rows.
+    /// The GPU compressor seeds every second block of a row with the block to its left, which
+    /// another invocation of the same workgroup compressed. `None` disables the seeding, which
+    /// is the default of all presets. The seeding only applies to
+    /// [`compress_rgba8()`](crate::encode::compress_rgba8) and the GPU compressor.
+    ///
+    /// # Panics
+    /// - If `max_error` is negative or not finite
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::BC7Settings;
+    ///
+    /// let settings = BC7Settings::alpha_basic().with_neighbor_seeding(Some(16.0));
+    /// assert_eq!(settings.neighbor_seeding(), Some(16.0));
+    /// ```
+    pub fn with_neighbor_seeding(mut self, max_error: Option<f32>) -> Self {
+        match max_error {
+            Some(max_error) => {
+                assert!(
+                    max_error.is_finite() && max_error >= 0.0,
+                    "the maximal error must be a finite, non-negative number"
+                );
+                self.neighbor_seeding = true as _;
+                self.neighbor_max_error = max_error.to_bits();
+            }
+            None => {
+                self.neighbor_seeding = false as _;
+                self.neighbor_max_error = 0;
+            }
+        }
+        self
+    }
+
+    /// Returns the error up to which the mode of a neighboring block is kept, or `None` if the
+    /// search doesn't start with the mode of a neighbor.
+    pub fn neighbor_seeding(&self) -> Option<f32> {
+        (self.neighbor_seeding != 0).then(|| f32::from_bits(self.neighbor_max_error))
+    }
+
+    /// Sets which channel modes 4 and 5 encode on their separate scalar line.
+    ///
+    /// The opaque presets and the slower alpha presets try every channel. The faster alpha
+    /// presets only try alpha. Restricting the search to a single channel is faster and keeps
+    /// the precision of that channel in the blocks of modes 4 and 5, for example for the
+    /// multiplier of RGBM data in alpha. Only has an effect for settings that search modes 4
+    /// and 5.
+    ///
+    /// # Panics
+    /// - If `channel` is [`Mode45ScalarChannel::Alpha`] for the opaque presets, which don't
+    ///   encode alpha
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC7Settings, Mode45ScalarChannel};
+    ///
+    /// let settings =
+    ///     BC7Settings::alpha_basic().with_mode45_scalar_channel(Mode45ScalarChannel::Alpha);
+    /// assert_eq!(settings.mode45_scalar_channel(), Mode45ScalarChannel::Alpha);
+    /// ```
+    pub const fn with_mode45_scalar_channel(mut self, channel: Mode45ScalarChannel) -> Self {
+        let (channel0, channel1) = match channel {
+            Mode45ScalarChannel::Any => (0, self.channels),
+            Mode45ScalarChannel::Red => (0, 1),
+            Mode45ScalarChannel::Green => (1, 2),
+            Mode45ScalarChannel::Blue => (2, 3),
+            Mode45ScalarChannel::Alpha => (3, 4),
+        };
+        assert!(
+            channel1 <= self.channels,
+            "the opaque presets can't encode alpha on the scalar line"
+        );
+        self.mode45_channel0 = channel0;
+        self.mode45_channel1 = channel1;
+        self
+    }
+
+    /// Returns which channel modes 4 and 5 encode on their separate scalar line.
+    pub const fn mode45_scalar_channel(&self) -> Mode45ScalarChannel {
+        match (self.mode45_channel0, self.mode45_channel1) {
+            (0, 1) => Mode45ScalarChannel::Red,
+            (1, 2) => Mode45ScalarChannel::Green,
+            (2, 3) => Mode45ScalarChannel::Blue,
+            (3, 4) => Mode45ScalarChannel::Alpha,
+            _ => Mode45ScalarChannel::Any,
+        }
+    }
+
+    /// Checks that the settings describe a search the encoders can run.
+    ///
+    /// The presets and builder methods always produce valid settings, so this only needs to be
+    /// called for settings that were created in other ways, for example by casting bytes that
+    /// were stored with `bytemuck`. [`GpuBlockCompressor`](crate::GpuBlockCompressor) validates
+    /// the settings of every task when it is added.
+    ///
+    /// # Errors
+    /// - [`SettingsError::Channels`] if the settings don't encode 3 or 4 channels
+    /// - [`SettingsError::SkipThreshold`] if modes 1, 3 or 7 search more than 64 partitions
+    /// - [`SettingsError::Mode45Channels`] if the scalar channels of modes 4 and 5 are not a
+    ///   range of the encoded channels
+    /// - [`SettingsError::InvalidValue`] if the alpha weight or the error of the neighbor
+    ///   seeding is negative or not finite
+    /// - [`SettingsError::NoModes`] if some blocks are not searched by any mode, for example
+    ///   because all modes are disabled or the skip thresholds of the only enabled modes are zero
+    ///
+    /// # Example
+    /// ```
+    /// use block_compression::{BC7Settings, SettingsError};
+    /// use bytemuck::Zeroable;
+    ///
+    /// assert_eq!(BC7Settings::alpha_basic().validate(), Ok(()));
+    /// assert_eq!(
+    ///     BC7Settings::zeroed().validate(),
+    ///     Err(SettingsError::Channels(0))
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !matches!(self.channels, 3 | 4) {
+            return Err(SettingsError::Channels(self.channels));
+        }
+
+        check_skip_threshold("mode 1", self.fast_skip_threshold_mode1, 64)?;
+        check_skip_threshold("mode 3", self.fast_skip_threshold_mode3, 64)?;
+        check_skip_threshold("mode 7", self.fast_skip_threshold_mode7, 64)?;
+
+        if self.mode45_channel0 >= self.mode45_channel1 || self.mode45_channel1 > self.channels {
+            return Err(SettingsError::Mode45Channels {
+                first: self.mode45_channel0,
+                end: self.mode45_channel1,
+            });
+        }
+
+        check_non_negative("alpha weight", self.alpha_weight)?;
+        if self.neighbor_seeding != 0 {
+            check_non_negative(
+                "maximal error of the neighbor seeding",
+                self.neighbor_max_error,
+            )?;
+        }
+
+        let [modes02, modes137, modes45, mode6] = self.mode_selection.map(|enabled| enabled != 0);
+        let modes13 = self.fast_skip_threshold_mode1 != 0 || self.fast_skip_threshold_mode3 != 0;
+        let mode7 = self.fast_skip_threshold_mode7 != 0;
+
+        // The alpha presets skip modes 4, 5 and 7 for opaque blocks.
+        let every_block = modes02 || (modes137 && modes13) || mode6;
+        let alpha_modes = (modes137 && mode7) || modes45;
+        if !(every_block || self.channels == 3 && alpha_modes) {
+            return Err(SettingsError::NoModes);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the settings of an ISPC or `intel_tex_2` BC7 profile by its name.
+    ///
+    /// Only parses the name of a profile, it doesn't convert `intel_tex_2` settings values.
+    /// Accepts the ISPC profile names (`"ultrafast"`, `"veryfast"`, `"fast"`, `"basic"`,
+    /// `"slow"` and their `"alpha_"` variants) as well as the names of the `intel_tex_2::bc7`
+    /// settings functions (for example `"opaque_ultra_fast_settings"`). The presets of this crate
+    /// are a port of the ISPC profiles, so both produce the same encoding. Returns `None` for
+    /// unknown names.
+    pub fn from_ispc_profile_name(name: &str) -> Option<Self> {
+        match normalize_profile_name(name).as_str() {
+            "ultrafast" => Some(Self::opaque_ultra_fast()),
+            "veryfast" => Some(Self::opaque_very_fast()),
+            "fast" => Some(Self::opaque_fast()),
+            "basic" => Some(Self::opaque_basic()),
+            "slow" => Some(Self::opaque_slow()),
+            "alphaultrafast" => Some(Self::alpha_ultrafast()),
+            "alphaveryfast" => Some(Self::alpha_very_fast()),
+            "alphafast" => Some(Self::alpha_fast()),
+            "alphabasic" => Some(Self::alpha_basic()),
+            "alphaslow" => Some(Self::alpha_slow()),
+            _ => None,
+        }
+    }
+
+    /// Returns the settings that correspond to a NVTT quality level.
+    ///
+    /// | NVTT         | Opaque                                | Alpha                               |
+    /// |--------------|---------------------------------------|-------------------------------------|
+    /// | `Fastest`    | [`BC7Settings::opaque_ultra_fast()`]  | [`BC7Settings::alpha_ultrafast()`]  |
+    /// | `Normal`     | [`BC7Settings::opaque_fast()`]        | [`BC7Settings::alpha_fast()`]       |
+    /// | `Production` | [`BC7Settings::opaque_basic()`]       | [`BC7Settings::alpha_basic()`]      |
+    /// | `Highest`    | [`BC7Settings::opaque_slow()`]        | [`BC7Settings::alpha_slow()`]       |
+    ///
+    /// # Arguments
+    /// * `quality` - The NVTT quality level
+    /// * `alpha` - Whether the texture has an alpha channel that needs to be preserved
+    pub const fn from_nvtt_quality(quality: NvttQuality, alpha: bool) -> Self {
+        match (quality, alpha) {
+            (NvttQuality::Fastest, false) => Self::opaque_ultra_fast(),
+            (NvttQuality::Normal, false) => Self::opaque_fast(),
+            (NvttQuality::Production, false) => Self::opaque_basic(),
+            (NvttQuality::Highest, false) => Self::opaque_slow(),
+            (NvttQuality::Fastest, true) => Self::alpha_ultrafast(),
+            (NvttQuality::Normal, true) => Self::alpha_fast(),
+            (NvttQuality::Production, true) => Self::alpha_basic(),
+            (NvttQuality::Highest, true) => Self::alpha_slow(),
+        }
+    }
+
+    /// Returns the settings of a [`Preset`].
+    ///
+    /// # Arguments
+    /// * `preset` - The quality preset
+    /// * `alpha` - Whether the texture has an alpha channel that needs to be preserved
+    pub const fn from_preset(preset: Preset, alpha: bool) -> Self {
+        match (preset, alpha) {
+            (Preset::Fast, false) => Self::opaque_fast(),
+            (Preset::Basic, false) => Self::opaque_basic(),
+            (Preset::Slow, false) => Self::opaque_slow(),
+            (Preset::Fast, true) => Self::alpha_fast(),
+            (Preset::Basic, true) => Self::alpha_basic(),
+            (Preset::Slow, true) => Self::alpha_slow(),
+        }
+    }
+}
+
+/// A field of a settings struct that the `Settings` struct of its shader mirrors.
+#[cfg(all(feature = "gpu", any(feature = "bc6h", feature = "bc7")))]
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct FieldLayout {
+    pub(crate) name: &'static str,
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+}
+
+#[cfg(all(feature = "gpu", any(feature = "bc6h", feature = "bc7")))]
+const fn field_size<S, F>(_field: fn(&S) -> &F) -> usize {
+    size_of::<F>()
+}
+
+/// Lists the fields of a settings struct in the order of the `Settings` struct of its shader.
+///
+/// The settings are copied as they are into storage buffers, where WGSL packs the 4-byte
+/// scalars and arrays of the shader structs without padding. The layout is asserted at compile
+/// time to be packed the same way, so a field that is added to only one side or in a different
+/// order fails the build.
+#[cfg(all(feature = "gpu", any(feature = "bc6h", feature = "bc7")))]
+macro_rules! shader_layout {
+    ($settings:ty { $($field:ident),* $(,)? }) => {{
+        const FIELDS: &[FieldLayout] = &[$(FieldLayout {
+            name: stringify!($field),
+            offset: std::mem::offset_of!($settings, $field),
+            size: field_size(|settings: &$settings| &settings.$field),
+        }),*];
+
+        const _: () = {
+            let mut offset = 0;
+            let mut index = 0;
+            while index < FIELDS.len() {
+                assert!(FIELDS[index].offset == offset, "settings field out of order");
+                assert!(FIELDS[index].size % 4 == 0, "settings field is not a 4-byte type");
+                offset += FIELDS[index].size;
+                index += 1;
+            }
+            assert!(offset == size_of::<$settings>(), "settings field missing");
+        };
+
+        FIELDS
+    }};
+}
+
+/// The layout of the `Settings` struct of the BC6H shader.
+#[cfg(all(feature = "gpu", feature = "bc6h"))]
+pub(crate) const BC6H_SETTINGS_LAYOUT: &[FieldLayout] = shader_layout!(BC6HSettings {
+    slow_mode,
+    fast_mode,
+    refine_iterations_1p,
+    refine_iterations_2p,
+    fast_skip_threshold,
+    non_finite,
+    precise_pca,
+});
+
+/// The layout of the `Settings` struct of the BC7 shader.
+#[cfg(all(feature = "gpu", feature = "bc7"))]
+pub(crate) const BC7_SETTINGS_LAYOUT: &[FieldLayout] = shader_layout!(BC7Settings {
+    refine_iterations,
+    mode_selection,
+    skip_mode2,
+    fast_skip_threshold_mode1,
+    fast_skip_threshold_mode3,
+    fast_skip_threshold_mode7,
+    mode45_channel0,
+    mode45_channel1,
+    refine_iterations_channel,
+    channels,
+    alpha_weight,
+    linear_error,
+    precise_pca,
+    neighbor_seeding,
+    neighbor_max_error,
+});
+
+#[cfg(all(test, feature = "bc6h", feature = "bc7"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ispc_profile_names() {
+        assert_eq!(
+            BC7Settings::from_ispc_profile_name("ultrafast"),
+            Some(BC7Settings::opaque_ultra_fast())
+        );
+        assert_eq!(
+            BC7Settings::from_ispc_profile_name("opaque_ultra_fast_settings"),
+            Some(BC7Settings::opaque_ultra_fast())
+        );
+        assert_eq!(
+            BC7Settings::from_ispc_profile_name("alpha_basic"),
+            Some(BC7Settings::alpha_basic())
+        );
+        assert_eq!(
+            BC6HSettings::from_ispc_profile_name("very_slow_settings"),
+            Some(BC6HSettings::very_slow())
+        );
+        assert_eq!(BC6HSettings::from_ispc_profile_name("ultrafast"), None);
+        assert_eq!(BC7Settings::from_ispc_profile_name("veryslow"), None);
+    }
+
+    #[test]
+    fn test_hdr_remap_inverse() {
+        let remap = HdrRemap::from_range(-2.0, 6.0);
+        assert_eq!(remap.apply(-2.0), 0.0);
+        assert_eq!(remap.apply(6.0), 1.0);
+
+        let inverse = remap.inverse();
+        for value in [-2.0, 0.0, 1.5, 6.0] {
+            assert!((inverse.apply(remap.apply(value)) - value).abs() < 1e-6);
+        }
+        assert!(HdrRemap::default().is_identity());
+    }
+
+    #[test]
+    fn test_mode45_scalar_channel() {
+        assert_eq!(
+            BC7Settings::opaque_basic().mode45_scalar_channel(),
+            Mode45ScalarChannel::Any
+        );
+        assert_eq!(
+            BC7Settings::alpha_fast().mode45_scalar_channel(),
+            Mode45ScalarChannel::Alpha
+        );
+
+        for channel in [
+            Mode45ScalarChannel::Any,
+            Mode45ScalarChannel::Red,
+            Mode45ScalarChannel::Green,
+            Mode45ScalarChannel::Blue,
+            Mode45ScalarChannel::Alpha,
+        ] {
+            let settings = BC7Settings::alpha_fast().with_mode45_scalar_channel(channel);
+            assert_eq!(settings.mode45_scalar_channel(), channel);
+        }
+    }
+
+    #[cfg(feature = "reflection")]
+    #[test]
+    fn test_shader_settings_layout() {
+        use naga::{front::wgsl, TypeInner};
+
+        for (source, layout, size) in [
+            (
+                include_str!("shader/bc6h.wgsl"),
+                BC6H_SETTINGS_LAYOUT,
+                size_of::<BC6HSettings>(),
+            ),
+            (
+                include_str!("shader/bc7.wgsl"),
+                BC7_SETTINGS_LAYOUT,
+                size_of::<BC7Settings>(),
+            ),
+        ] {
+            let module = wgsl::parse_str(source).unwrap();
+            let (members, span) = module
+                .types
+                .iter()
+                .find_map(|(_, ty)| match &ty.inner {
+                    TypeInner::Struct { members, span }
+                        if ty.name.as_deref() == Some("Settings") =>
+                    {
+                        Some((members, *span))
+                    }
+                    _ => None,
+                })
+                .expect("shader has no settings struct");
+
+            assert_eq!(span as usize, size);
+            assert_eq!(members.len(), layout.len());
+            for (member, field) in members.iter().zip(layout) {
+                assert_eq!(member.name.as_deref(), Some(field.name));
+                assert_eq!(member.offset as usize, field.offset, "{}", field.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        for settings in [
+            BC7Settings::opaque_ultra_fast(),
+            BC7Settings::opaque_slow(),
+            BC7Settings::alpha_ultrafast(),
+            BC7Settings::alpha_slow(),
+            BC7Settings::preview(),
+            BC7Settings::alpha_fast().with_neighbor_seeding(Some(16.0)),
+        ] {
+            assert_eq!(settings.validate(), Ok(()));
+        }
+        for settings in [BC6HSettings::preview(), BC6HSettings::very_slow()] {
+            assert_eq!(settings.validate(), Ok(()));
+        }
+
+        let only_mode7 = BC7Settings {
+            mode_selection: [0, 1, 0, 0],
+            fast_skip_threshold_mode1: 0,
+            fast_skip_threshold_mode3: 0,
+            fast_skip_threshold_mode7: 4,
+            ..BC7Settings::alpha_basic()
+        };
+        // Opaque blocks of the alpha settings would never be encoded.
+        assert_eq!(only_mode7.validate(), Err(SettingsError::NoModes));
+        assert_eq!(
+            BC7Settings {
+                channels: 3,
+                mode45_channel1: 3,
+                ..only_mode7
+            }
+            .validate(),
+            Ok(())
+        );
+
+        assert_eq!(
+            BC7Settings {
+                mode_selection: [0; 4],
+                ..BC7Settings::opaque_basic()
+            }
+            .validate(),
+            Err(SettingsError::NoModes)
+        );
+        assert_eq!(
+            BC7Settings {
+                fast_skip_threshold_mode3: 65,
+                ..BC7Settings::opaque_basic()
+            }
+            .validate(),
+            Err(SettingsError::SkipThreshold {
+                modes: "mode 3",
+                threshold: 65,
+                partitions: 64,
+            })
+        );
+        assert_eq!(
+            BC7Settings {
+                mode45_channel0: 3,
+                mode45_channel1: 4,
+                ..BC7Settings::opaque_basic()
+            }
+            .validate(),
+            Err(SettingsError::Mode45Channels { first: 3, end: 4 })
+        );
+        assert_eq!(
+            BC7Settings {
+                alpha_weight: f32::NAN.to_bits(),
+                ..BC7Settings::alpha_basic()
+            }
+            .validate(),
+            Err(SettingsError::InvalidValue {
+                setting: "alpha weight"
+            })
+        );
+
+        assert_eq!(
+            BC6HSettings {
+                fast_skip_threshold: 33,
+                ..BC6HSettings::basic()
+            }
+            .validate(),
+            Err(SettingsError::SkipThreshold {
+                modes: "the two-region modes",
+                threshold: 33,
+                partitions: 32,
+            })
+        );
+        assert_eq!(
+            BC6HSettings {
+                non_finite: 3,
+                ..BC6HSettings::basic()
+            }
+            .validate(),
+            Err(SettingsError::NonFiniteHandling(3))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mode45_scalar_channel_alpha_of_opaque() {
+        let _ = BC7Settings::opaque_basic().with_mode45_scalar_channel(Mode45ScalarChannel::Alpha);
+    }
+
+    #[test]
+    fn test_preset_psnr_targets() {
+        use crate::{
+            test_util::{compress_hdr, decode_hdr, hdr_squared_error, round_trip_psnr},
+            testgen::TestPattern,
+            CompressionVariant,
+        };
+
+        let (width, height) = (32, 32);
+
+        let psnr_ldr = |pattern: TestPattern, variant: CompressionVariant| {
+            let rgba_data = pattern.generate_rgba8(width, height);
+            round_trip_psnr(variant, &rgba_data, width, height)
+        };
+
+        // The color channels of the HDR pattern reach 4.0, which is the peak of the PSNR.
+        let psnr_hdr = |pattern: TestPattern, variant: CompressionVariant| {
+            let rgba_data = pattern.generate_rgba16f(width, height, 4.0);
+            let blocks = compress_hdr(variant, &rgba_data, width, height);
+            let decoded = decode_hdr(variant, &blocks, width, height);
+
+            let mse = hdr_squared_error(&rgba_data, &decoded) / (width * height * 3) as f64;
+            10.0 * (4.0 * 4.0 / mse.max(1e-12)).log10()
+        };
+
+        // The minimal PSNR of the opaque gradient, the alpha ramp and the HDR gradient.
+        let targets = [
+            (Preset::Fast, [40.0, 38.0, 35.0]),
+            (Preset::Basic, [40.0, 40.0, 35.0]),
+            (Preset::Slow, [41.0, 40.0, 35.0]),
+        ];
+
+        let mut previous = [0.0; 3];
+        for (preset, min_psnr) in targets {
+            let psnr = [
+                psnr_ldr(
+                    TestPattern::Gradient,
+                    CompressionVariant::BC7(BC7Settings::opaque_basic()).with_preset(preset),
+                ),
+                psnr_ldr(
+                    TestPattern::AlphaRamp,
+                    CompressionVariant::BC7(BC7Settings::alpha_basic()).with_preset(preset),
+                ),
+                psnr_hdr(
+                    TestPattern::Gradient,
+                    CompressionVariant::BC6H(BC6HSettings::basic()).with_preset(preset),
+                ),
+            ];
+
+            for index in 0..3 {
+                assert!(
+                    psnr[index] >= min_psnr[index],
+                    "{preset:?}: case {index} has a PSNR of {:.2} dB, expected at least {} dB",
+                    psnr[index],
+                    min_psnr[index]
+                );
+                // Slower presets may lose a little on single images, but not more.
+                assert!(
+                    psnr[index] >= previous[index] - 0.25,
+                    "{preset:?}: case {index} is worse than the faster preset"
+                );
+            }
+            previous = psnr;
+        }
+    }
 }
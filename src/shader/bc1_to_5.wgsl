@@ -20,22 +20,189 @@ struct Uniforms {
     height: u32,
     texture_y_offset: u32,
     blocks_offset: u32,
+    errors_offset: u32,
+    write_errors: u32,
+    edge_fill: u32,
+    fill_color: u32,
+    normal_filter: u32,
+    normal_strength: f32,
+    normal_flip_y: u32,
+    dither_alpha: u32,
+    sdf: u32,
+    sdf_pivot: f32,
+    hdr_scale: f32,
+    hdr_bias: f32,
+    source_srgb: u32,
+    punch_through_alpha: u32,
+    texture_index: u32,
 }
 
+const EDGE_FILL_CLAMP_TO_EDGE: u32 = 0u;
+const EDGE_FILL_MIRROR: u32 = 1u;
+const EDGE_FILL_ZERO: u32 = 2u;
+const EDGE_FILL_SOLID_COLOR: u32 = 3u;
+
+const NORMAL_FILTER_SOBEL: u32 = 0u;
+const NORMAL_FILTER_SCHARR: u32 = 1u;
+
+// Must match the constants of the CPU encoder.
+const PUNCH_THROUGH_THRESHOLD: f32 = 128.0;
+const SDF_PIVOT_WEIGHT: f32 = 32.0;
+const SDF_EDGE_WIDTH: f32 = 24.0;
+const SDF_SEARCH_RADIUS: i32 = 4;
+
 @group(0) @binding(0) var source_texture: texture_2d<f32>;
 @group(0) @binding(1) var<storage, read_write> block_buffer: array<u32>;
 @group(0) @binding(2) var<uniform> uniforms: Uniforms;
+@group(0) @binding(3) var secondary_texture: texture_2d<f32>;
+@group(0) @binding(4) var<storage, read_write> error_buffer: array<f32>;
+
+// The bounds checking variant of the shader replaces these two declarations and binds a
+// violation counter. It's selected with the `bounds_checks` feature.
+const BOUNDS_CHECKS: bool = false;
+fn report_bounds_violation() {}
+
+// Returns true if the block lies inside the task and all elements up to `end` are inside the
+// bound buffer of `length` elements. Violations are counted and the write needs to be skipped.
+fn in_bounds(xx: u32, yy: u32, end: u32, length: u32) -> bool {
+    if (!BOUNDS_CHECKS) {
+        return true;
+    }
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx < block_width && yy < block_height && end <= length) {
+        return true;
+    }
+
+    report_bounds_violation();
+    return false;
+}
 
 fn sq(x: f32) -> f32 {
     return x * x;
 }
 
-fn rsqrt(x: f32) -> f32 {
-    return 1.0 / sqrt(x);
+
+fn edge_coordinate(coordinate: u32, size: u32) -> u32 {
+    if (coordinate < size) {
+        return coordinate;
+    }
+    if (uniforms.edge_fill == EDGE_FILL_MIRROR) {
+        return u32(max(i32(size * 2u) - 1 - i32(coordinate), 0));
+    }
+    return size - 1u;
+}
+
+// Encodes the colors of sRGB views, which the hardware decoded to linear, back to sRGB.
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn load_texel(pixel_x: u32, pixel_y: u32) -> vec4<f32> {
+    let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+    if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+        return vec4<f32>(0.0);
+    }
+    if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+        return unpack4x8unorm(uniforms.fill_color);
+    }
+
+    let x = edge_coordinate(pixel_x, uniforms.width);
+    let y = edge_coordinate(pixel_y, uniforms.height) + uniforms.texture_y_offset;
+    let texel = textureLoad(source_texture, vec2<u32>(x, y), 0);
+
+    if (uniforms.source_srgb != 0u) {
+        return vec4<f32>(linear_to_srgb(texel.rgb), texel.a);
+    }
+    return texel;
+}
+
+fn load_secondary_red(pixel_x: u32, pixel_y: u32, fill_channel: u32) -> f32 {
+    let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+    if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+        return 0.0;
+    }
+    if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+        return unpack4x8unorm(uniforms.fill_color)[fill_channel];
+    }
+
+    let x = edge_coordinate(pixel_x, uniforms.width);
+    let y = edge_coordinate(pixel_y, uniforms.height) + uniforms.texture_y_offset;
+    return textureLoad(secondary_texture, vec2<u32>(x, y), 0).r;
+}
+
+fn load_height(pixel_x: i32, pixel_y: i32) -> f32 {
+    let x = u32(clamp(pixel_x, 0, i32(uniforms.width) - 1));
+    let y = u32(clamp(pixel_y, 0, i32(uniforms.height) - 1)) + uniforms.texture_y_offset;
+    return textureLoad(source_texture, vec2<u32>(x, y), 0).r;
+}
+
+// Derives the X and Y component of the normal from the height gradient around a pixel,
+// mapped to [0, 1].
+fn height_normal(pixel_x: u32, pixel_y: u32) -> vec2<f32> {
+    let x = i32(edge_coordinate(pixel_x, uniforms.width));
+    let y = i32(edge_coordinate(pixel_y, uniforms.height));
+
+    var h: array<f32, 9>;
+    for (var dy = 0; dy < 3; dy++) {
+        for (var dx = 0; dx < 3; dx++) {
+            h[dy * 3 + dx] = load_height(x + dx - 1, y + dy - 1);
+        }
+    }
+
+    // Both kernels are normalized to return the height difference between neighboring pixels.
+    var corner = 1.0 / 8.0;
+    var center = 2.0 / 8.0;
+    if (uniforms.normal_filter == NORMAL_FILTER_SCHARR) {
+        corner = 3.0 / 32.0;
+        center = 10.0 / 32.0;
+    }
+
+    let gx = corner * (h[2] + h[8] - h[0] - h[6]) + center * (h[5] - h[3]);
+    let gy = corner * (h[6] + h[8] - h[0] - h[2]) + center * (h[7] - h[1]);
+
+    // The rows of the texture go down, so the gradient is flipped for a Y axis pointing up.
+    let y_sign = select(1.0, -1.0, uniforms.normal_flip_y != 0u);
+    let normal = normalize(vec3<f32>(
+        -gx * uniforms.normal_strength,
+        gy * uniforms.normal_strength * y_sign,
+        1.0,
+    ));
+
+    return normal.xy * 0.5 + 0.5;
+}
+
+fn load_block_normals(normals: ptr<function, array<vec2<f32>, 16>>, xx: u32, yy: u32) {
+    for (var y = 0u; y < 4u; y++) {
+        for (var x = 0u; x < 4u; x++) {
+            let pixel_x = xx * 4u + x;
+            let pixel_y = yy * 4u + y;
+            let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+            var normal: vec2<f32>;
+            if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+                normal = vec2<f32>(0.0);
+            } else if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+                normal = unpack4x8unorm(uniforms.fill_color).rg;
+            } else {
+                normal = height_normal(pixel_x, pixel_y);
+            }
+
+            (*normals)[y * 4u + x] = normal;
+        }
+    }
 }
 
-fn rcp(x: f32) -> f32 {
-    return 1.0 / x;
+fn load_block_normal_channel_8bit(block: ptr<function, array<f32, 64>>, normals: ptr<function, array<vec2<f32>, 16>>, channel: u32) {
+    for (var i = 0u; i < 16u; i++) {
+        (*block)[48u + i] = round((*normals)[i][channel] * 255.0);
+    }
 }
 
 fn load_block_interleaved_rgba(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) {
@@ -43,12 +210,12 @@ fn load_block_interleaved_rgba(block: ptr<function, array<f32, 64>>, xx: u32, yy
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
             let pixel_y = yy * 4u + y;
-            let rgba = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0);
+            let rgba = load_texel(pixel_x, pixel_y);
 
-            (*block)[16u * 0u + y * 4u + x] = rgba.r * 255.0;
-            (*block)[16u * 1u + y * 4u + x] = rgba.g * 255.0;
-            (*block)[16u * 2u + y * 4u + x] = rgba.b * 255.0;
-            (*block)[16u * 3u + y * 4u + x] = rgba.a * 255.0;
+            (*block)[16u * 0u + y * 4u + x] = round(rgba.r * 255.0);
+            (*block)[16u * 1u + y * 4u + x] = round(rgba.g * 255.0);
+            (*block)[16u * 2u + y * 4u + x] = round(rgba.b * 255.0);
+            (*block)[16u * 3u + y * 4u + x] = round(rgba.a * 255.0);
         }
     }
 }
@@ -58,9 +225,9 @@ fn load_block_r_8bit(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) {
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
             let pixel_y = yy * 4u + y;
-            let red = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0).r;
+            let red = load_texel(pixel_x, pixel_y).r;
 
-            (*block)[48u + y * 4u + x] = red * 255.0;
+            (*block)[48u + y * 4u + x] = round(red * 255.0);
         }
     }
 }
@@ -70,24 +237,44 @@ fn load_block_g_8bit(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) {
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
             let pixel_y = yy * 4u + y;
-            let green = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0).g;
+            let green = load_texel(pixel_x, pixel_y).g;
 
-            (*block)[48u + y * 4u + x] = green  * 255.0;
+            (*block)[48u + y * 4u + x] = round(green * 255.0);
         }
     }
 }
 
+fn load_block_secondary_r_8bit(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32, fill_channel: u32) {
+    for (var y = 0u; y < 4u; y++) {
+        for (var x = 0u; x < 4u; x++) {
+            let pixel_x = xx * 4u + x;
+            let pixel_y = yy * 4u + y;
+            let red = load_secondary_red(pixel_x, pixel_y, fill_channel);
+
+            (*block)[48u + y * 4u + x] = round(red * 255.0);
+        }
+    }
+}
+
+// Thresholds of an ordered 4x4 dither in 1/16 steps. Must match `BAYER_4X4` of the CPU encoder.
+const BAYER_4X4 = array<u32, 16>(0u, 8u, 2u, 10u, 12u, 4u, 14u, 6u, 3u, 11u, 1u, 9u, 15u, 7u, 13u, 5u);
+
 fn load_block_alpha_4bit(xx: u32, yy: u32) -> vec2<u32> {
     var alpha_bits: vec2<u32>;
 
     for (var y = 0u; y < 4u; y++) {
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
-            let pixel_y = yy * 4u + y + uniforms.texture_y_offset;;
-            let alpha = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0).a;
-
-            // Convert alpha to 4 bits (0-15)
-            let alpha4 = u32(alpha * 15.0);
+            let pixel_y = yy * 4u + y;
+            let alpha = u32(round(load_texel(pixel_x, pixel_y).a * 255.0));
+
+            // Convert alpha to 4 bits (0-15), which is alpha * 15 / 255 rounded down. The dither
+            // adds a threshold of (BAYER_4X4 + 0.5) / 16 before rounding.
+            var alpha4 = alpha / 17u;
+            if (uniforms.dither_alpha != 0u) {
+                let threshold = 2u * BAYER_4X4[y * 4u + x] + 1u;
+                alpha4 = min((32u * alpha + 17u * threshold) / 544u, 15u);
+            }
             let bit_position = y * 16u + x * 4u;
 
             if (bit_position < 32u) {
@@ -104,6 +291,10 @@ fn load_block_alpha_4bit(xx: u32, yy: u32) -> vec2<u32> {
 fn store_data_2(block_width: u32, xx: u32, yy: u32, data: vec2<u32>) {
     let offset = uniforms.blocks_offset + (yy * block_width * 2u + xx * 2u);
 
+    if (!in_bounds(xx, yy, offset + 2u, arrayLength(&block_buffer))) {
+        return;
+    }
+
     block_buffer[offset + 0] = data[0];
     block_buffer[offset + 1] = data[1];
 }
@@ -111,244 +302,223 @@ fn store_data_2(block_width: u32, xx: u32, yy: u32, data: vec2<u32>) {
 fn store_data_4(block_width: u32, xx: u32, yy: u32, data: vec4<u32>) {
     let offset = uniforms.blocks_offset + (yy * block_width * 4u + xx * 4u);
 
+    if (!in_bounds(xx, yy, offset + 4u, arrayLength(&block_buffer))) {
+        return;
+    }
+
     block_buffer[offset + 0] = data[0];
     block_buffer[offset + 1] = data[1];
     block_buffer[offset + 2] = data[2];
     block_buffer[offset + 3] = data[3];
 }
 
-fn compute_covar_dc(
-    covar: ptr<function, array<f32, 6>>,
-    dc: ptr<function, vec3<f32>>,
-    block: ptr<function, array<f32, 64>>,
-) {
-    for (var p = 0u; p < 3u; p++) {
-        var acc = 0.0;
-        for (var k = 0u; k < 16u; k++) {
-            acc += (*block)[k + p * 16u];
-        }
-        (*dc)[p] = acc / 16.0;
+fn store_error(block_width: u32, xx: u32, yy: u32, error: f32) {
+    let offset = uniforms.errors_offset + yy * block_width + xx;
+
+    if (!in_bounds(xx, yy, offset + 1u, arrayLength(&error_buffer))) {
+        return;
     }
 
-    var covar0 = 0.0;
-    var covar1 = 0.0;
-    var covar2 = 0.0;
-    var covar3 = 0.0;
-    var covar4 = 0.0;
-    var covar5 = 0.0;
+    error_buffer[offset] = error;
+}
+
+// The BC1 colors and the BC3 alpha are encoded with integer math on the 8-bit values of the
+// block. Floating point results differ between backends, for example when a driver fuses a
+// multiply and an add, while these encoders produce the same blocks on every backend.
+
+fn block_color(block: ptr<function, array<f32, 64>>, k: u32) -> vec3<i32> {
+    return vec3<i32>(i32((*block)[k]), i32((*block)[k + 16u]), i32((*block)[k + 32u]));
+}
+
+// Returns the sum of the colors of the pixels in `mask` and their covariance, scaled by the square
+// of the number of pixels.
+fn compute_covar_sum(
+    covar: ptr<function, array<i32, 6>>,
+    block: ptr<function, array<f32, 64>>,
+    mask: u32,
+) -> vec3<i32> {
+    var sum = vec3<i32>(0);
+    var products: array<i32, 6>;
 
     for (var k = 0u; k < 16u; k++) {
-        let rgb0 = (*block)[k + 0u * 16u] - (*dc)[0];
-        let rgb1 = (*block)[k + 1u * 16u] - (*dc)[1];
-        let rgb2 = (*block)[k + 2u * 16u] - (*dc)[2];
+        if ((mask & (1u << k)) == 0u) {
+            continue;
+        }
 
-        covar0 += rgb0 * rgb0;
-        covar1 += rgb0 * rgb1;
-        covar2 += rgb0 * rgb2;
-        covar3 += rgb1 * rgb1;
-        covar4 += rgb1 * rgb2;
-        covar5 += rgb2 * rgb2;
+        let c = block_color(block, k);
+        sum += c;
+        products[0] += c.r * c.r;
+        products[1] += c.r * c.g;
+        products[2] += c.r * c.b;
+        products[3] += c.g * c.g;
+        products[4] += c.g * c.b;
+        products[5] += c.b * c.b;
     }
 
-    (*covar)[0] = covar0;
-    (*covar)[1] = covar1;
-    (*covar)[2] = covar2;
-    (*covar)[3] = covar3;
-    (*covar)[4] = covar4;
-    (*covar)[5] = covar5;
-}
+    let count = i32(countOneBits(mask));
+    (*covar)[0] = count * products[0] - sum.r * sum.r;
+    (*covar)[1] = count * products[1] - sum.r * sum.g;
+    (*covar)[2] = count * products[2] - sum.r * sum.b;
+    (*covar)[3] = count * products[3] - sum.g * sum.g;
+    (*covar)[4] = count * products[4] - sum.g * sum.b;
+    (*covar)[5] = count * products[5] - sum.b * sum.b;
 
-fn ssymv(result: ptr<function, vec3<f32>>, covar: ptr<function, array<f32, 6>>, a_vector: ptr<function, vec3<f32>>) {
-    (*result)[0] = (*covar)[0] * (*a_vector)[0] + (*covar)[1] * (*a_vector)[1] + (*covar)[2] * (*a_vector)[2];
-    (*result)[1] = (*covar)[1] * (*a_vector)[0] + (*covar)[3] * (*a_vector)[1] + (*covar)[4] * (*a_vector)[2];
-    (*result)[2] = (*covar)[2] * (*a_vector)[0] + (*covar)[4] * (*a_vector)[1] + (*covar)[5] * (*a_vector)[2];
+    return sum;
 }
 
-fn compute_axis3(axis: ptr<function, vec3<f32>>, covar: ptr<function, array<f32, 6>>, powerIterations: i32) {
-    var a_vector = vec3<f32>(1.0, 1.0, 1.0);
-
-    for (var i = 0; i < powerIterations; i++) {
-        ssymv(axis, covar, &a_vector);
+// Finds the principal axis of the covariance with a power iteration in fixed point. The largest
+// component of the axis is scaled to 255.
+fn compute_axis3(covar: ptr<function, array<i32, 6>>, power_iterations: i32) -> vec3<i32> {
+    // Scales the covariance down, so its products with the axis fit into 32 bits.
+    var c: array<i32, 6>;
+    for (var i = 0u; i < 6u; i++) {
+        c[i] = (*covar)[i] / 8;
+    }
 
-        for (var p = 0u; p < 3u; p++) {
-            a_vector[p] = (*axis)[p];
+    var axis = vec3<i32>(255);
+    for (var i = 0; i < power_iterations; i++) {
+        let product = vec3<i32>(
+            c[0] * axis.x + c[1] * axis.y + c[2] * axis.z,
+            c[1] * axis.x + c[3] * axis.y + c[4] * axis.z,
+            c[2] * axis.x + c[4] * axis.y + c[5] * axis.z,
+        );
+
+        let largest = max(max(abs(product.x), abs(product.y)), abs(product.z));
+        if (largest == 0) {
+            break;
         }
 
-        if (i % 2 == 1) {
-            var norm_sq = 0.0;
-            for (var p = 0u; p < 3u; p++) {
-                norm_sq += (*axis)[p] * (*axis)[p];
-            }
-
-            let rnorm = rsqrt(norm_sq);
-            for (var p = 0u; p < 3u; p++) {
-                a_vector[p] *= rnorm;
-            }
+        // Drops the low bits of large products, so the scaling to 255 doesn't overflow.
+        var divisor = 1;
+        while (largest / divisor >= (1 << 22)) {
+            divisor *= 2;
         }
+        axis = product / divisor * 255 / (largest / divisor);
     }
 
-    for (var p = 0u; p < 3u; p++) {
-        (*axis)[p] = a_vector[p];
+    return axis;
+}
+
+// Returns the integer square root of a value below 2^18.
+fn isqrt(value: i32) -> i32 {
+    var root = 0;
+    for (var bit = 256; bit > 0; bit /= 2) {
+        if ((root + bit) * (root + bit) <= value) {
+            root += bit;
+        }
     }
+    return root;
 }
 
+// Projects the pixels in `mask` on the axis through their mean and returns the colors at the
+// smallest and the largest projection.
 fn pick_endpoints(
-    c0: ptr<function, vec3<f32>>,
-    c1: ptr<function, vec3<f32>>,
     block: ptr<function, array<f32, 64>>,
-    axis: ptr<function, vec3<f32>>,
-    dc: ptr<function, vec3<f32>>
-) {
-    var min_dot = 256.0 * 256.0;
-    var max_dot = 0.0;
+    mask: u32,
+    sum: vec3<i32>,
+    axis: vec3<i32>,
+) -> array<vec3<i32>, 2> {
+    // The projections are scaled by the number of pixels and the length of the axis.
+    let count = i32(countOneBits(mask));
+    var min_dot = 0x7FFFFFFF;
+    var max_dot = -0x7FFFFFFF;
 
-    for (var y = 0u; y < 4u; y++) {
-        for (var x = 0u; x < 4u; x++) {
-            var dot = 0.0;
-            for (var p = 0u; p < 3u; p++) {
-                dot += ((*block)[p * 16u + y * 4u + x] - (*dc)[p]) * (*axis)[p];
-            }
-
-            min_dot = min(min_dot, dot);
-            max_dot = max(max_dot, dot);
+    for (var k = 0u; k < 16u; k++) {
+        if ((mask & (1u << k)) == 0u) {
+            continue;
         }
-    }
 
-    if (max_dot - min_dot < 1.0) {
-        min_dot -= 0.5;
-        max_dot += 0.5;
+        let projection = dot(count * block_color(block, k) - sum, axis);
+        min_dot = min(min_dot, projection);
+        max_dot = max(max_dot, projection);
     }
 
-    var norm_sq = 0.0;
-    for (var p = 0u; p < 3u; p++) {
-        norm_sq += (*axis)[p] * (*axis)[p];
+    // Keeps the endpoints of flat blocks at least one level apart.
+    let norm_sq = dot(axis, axis);
+    let level = count * isqrt(norm_sq);
+    if (max_dot - min_dot < level) {
+        min_dot -= level / 2;
+        max_dot += level / 2;
     }
 
-    let rnorm_sq = rcp(norm_sq);
-    for (var p = 0u; p < 3u; p++) {
-        (*c0)[p] = clamp((*dc)[p] + min_dot * rnorm_sq * (*axis)[p], 0.0, 255.0);
-        (*c1)[p] = clamp((*dc)[p] + max_dot * rnorm_sq * (*axis)[p], 0.0, 255.0);
-    }
+    let scale = count * norm_sq;
+    var endpoints: array<vec3<i32>, 2>;
+    endpoints[0] = clamp((sum * norm_sq + min_dot * axis) / scale, vec3<i32>(0), vec3<i32>(255));
+    endpoints[1] = clamp((sum * norm_sq + max_dot * axis) / scale, vec3<i32>(0), vec3<i32>(255));
+    return endpoints;
 }
 
-fn dec_rgb565(c: ptr<function, vec3<f32>>, p: i32) {
+fn dec_rgb565(p: i32) -> vec3<i32> {
     let b5 = (p >> 0) & 31;
     let g6 = (p >> 5) & 63;
     let r5 = (p >> 11) & 31;
 
-    (*c)[0] = f32((r5 << 3) + (r5 >> 2));
-    (*c)[1] = f32((g6 << 2) + (g6 >> 4));
-    (*c)[2] = f32((b5 << 3) + (b5 >> 2));
+    return vec3<i32>((r5 << 3) + (r5 >> 2), (g6 << 2) + (g6 >> 4), (b5 << 3) + (b5 >> 2));
 }
 
-fn enc_rgb565(c: ptr<function, vec3<f32>>) -> i32 {
-    let r = i32((*c)[0]);
-    let g = i32((*c)[1]);
-    let b = i32((*c)[2]);
-
-    let r5 = (r * 31 + 128 + ((r * 31) >> 8)) >> 8;
-    let g6 = (g * 63 + 128 + ((g * 63) >> 8)) >> 8;
-    let b5 = (b * 31 + 128 + ((b * 31) >> 8)) >> 8;
+fn enc_rgb565(c: vec3<i32>) -> i32 {
+    let r5 = (c.r * 31 + 128 + ((c.r * 31) >> 8)) >> 8;
+    let g6 = (c.g * 63 + 128 + ((c.g * 63) >> 8)) >> 8;
+    let b5 = (c.b * 31 + 128 + ((c.b * 31) >> 8)) >> 8;
 
     return (r5 << 11) + (g6 << 5) + b5;
 }
 
 fn fast_quant(block: ptr<function, array<f32, 64>>, p0: i32, p1: i32) -> u32 {
-    var c0: vec3<f32>;
-    var c1: vec3<f32>;
-    dec_rgb565(&c0, p0);
-    dec_rgb565(&c1, p1);
-
-    var dir: vec3<f32>;
-    for (var p = 0u; p < 3u; p++) {
-        dir[p] = c1[p] - c0[p];
-    }
-
-    var sq_norm = 0.0;
-    for (var p = 0u; p < 3u; p++) {
-        sq_norm += sq(dir[p]);
-    }
-
-    let rsq_norm = rcp(sq_norm);
-
-    for (var p = 0u; p < 3u; p++) {
-        dir[p] *= rsq_norm * 3.0;
-    }
+    let c0 = dec_rgb565(p0);
+    let dir = dec_rgb565(p1) - c0;
+    let sq_norm = dot(dir, dir);
 
-    var bias = 0.5;
-    for (var p = 0u; p < 3u; p++) {
-        bias -= c0[p] * dir[p];
+    if (sq_norm == 0) {
+        return 0u;
     }
 
     var bits = 0u;
-    var scaler = 1u;
     for (var k = 0u; k < 16u; k++) {
-        var dot = 0.0;
-        for (var p = 0u; p < 3u; p++) {
-            dot += (*block)[k + p * 16u] * dir[p];
-        }
-
-        let q = clamp(i32(dot + bias), 0, 3);
-        bits += u32(q) * scaler;
-        scaler *= 4u;
+        // Rounds 3 * projection / sq_norm to the closest index.
+        let projection = dot(block_color(block, k) - c0, dir);
+        let q = clamp((6 * projection + sq_norm) / (2 * sq_norm), 0, 3);
+        bits |= u32(q) << (k * 2u);
     }
 
     return bits;
 }
 
-fn bc1_refine(pe: ptr<function, vec2<i32>>, block: ptr<function, array<f32, 64>>, bits: u32, dc: ptr<function, vec3<f32>>) {
-    var c0: vec3<f32>;
-    var c1: vec3<f32>;
+fn bc1_refine(pe: ptr<function, vec2<i32>>, block: ptr<function, array<f32, 64>>, bits: u32, sum: vec3<i32>) {
+    var c0: vec3<i32>;
+    var c1: vec3<i32>;
 
     if ((bits ^ (bits * 4u)) < 4u) {
-        for (var p = 0u; p < 3u; p++) {
-            c0[p] = (*dc)[p];
-            c1[p] = (*dc)[p];
-        }
+        c0 = sum / 16;
+        c1 = sum / 16;
     } else {
-        var atb1: vec3<f32>;
-        var sum_q = 0.0;
-        var sum_qq = 0.0;
+        var atb1 = vec3<i32>(0);
+        var sum_q = 0;
+        var sum_qq = 0;
         var shifted_bits = bits;
 
         for (var k = 0u; k < 16u; k++) {
-            let q = f32(shifted_bits & 3u);
+            let q = i32(shifted_bits & 3u);
             shifted_bits = shifted_bits >> 2u;
 
-            let x = 3.0 - q;
-
             sum_q += q;
             sum_qq += q * q;
-
-            for (var p = 0u; p < 3u; p++) {
-                atb1[p] += x * (*block)[k + p * 16u];
-            }
+            atb1 += (3 - q) * block_color(block, k);
         }
 
-        var sum: vec3<f32>;
-        var atb2: vec3<f32>;
-
-        for (var p = 0u; p < 3u; p++) {
-            sum[p] = (*dc)[p] * 16.0;
-            atb2[p] = 3.0 * sum[p] - atb1[p];
-        }
+        let atb2 = 3 * sum - atb1;
 
-        let cxx = 16.0 * sq(3.0) - 2.0 * 3.0 * sum_q + sum_qq;
+        let cxx = 16 * 9 - 2 * 3 * sum_q + sum_qq;
         let cyy = sum_qq;
-        let cxy = 3.0 * sum_q - sum_qq;
-        let scale = 3.0 * rcp(cxx * cyy - cxy * cxy);
+        let cxy = 3 * sum_q - sum_qq;
+        let det = cxx * cyy - cxy * cxy;
 
-        for (var p = 0u; p < 3u; p++) {
-            c0[p] = (atb1[p] * cyy - atb2[p] * cxy) * scale;
-            c1[p] = (atb2[p] * cxx - atb1[p] * cxy) * scale;
-
-            c0[p] = clamp(c0[p], 0.0, 255.0);
-            c1[p] = clamp(c1[p], 0.0, 255.0);
-        }
+        c0 = clamp(3 * (atb1 * cyy - atb2 * cxy) / det, vec3<i32>(0), vec3<i32>(255));
+        c1 = clamp(3 * (atb2 * cxx - atb1 * cxy) / det, vec3<i32>(0), vec3<i32>(255));
     }
 
-    (*pe)[0] = enc_rgb565(&c0);
-    (*pe)[1] = enc_rgb565(&c1);
+    (*pe)[0] = enc_rgb565(c0);
+    (*pe)[1] = enc_rgb565(c1);
 }
 
 fn fix_qbits(qbits: u32) -> u32 {
@@ -364,25 +534,14 @@ fn compress_block_bc1_core(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
     let power_iterations = 4;
     let refine_iterations = 1;
 
-    var covar: array<f32, 6>;
-    var dc: vec3<f32>;
-    compute_covar_dc(&covar, &dc, block);
-
-    const eps = 0.001;
-    covar[0] += eps;
-    covar[3] += eps;
-    covar[5] += eps;
-
-    var axis: vec3<f32>;
-    compute_axis3(&axis, &covar, power_iterations);
-
-    var c0: vec3<f32>;
-    var c1: vec3<f32>;
-    pick_endpoints(&c0, &c1, block, &axis, &dc);
+    var covar: array<i32, 6>;
+    let sum = compute_covar_sum(&covar, block, 0xFFFFu);
+    let axis = compute_axis3(&covar, power_iterations);
+    let endpoints = pick_endpoints(block, 0xFFFFu, sum, axis);
 
     var p: vec2<i32>;
-    p[0] = enc_rgb565(&c0);
-    p[1] = enc_rgb565(&c1);
+    p[0] = enc_rgb565(endpoints[0]);
+    p[1] = enc_rgb565(endpoints[1]);
     if (p[0] < p[1]) {
         let temp = p[0];
         p[0] = p[1];
@@ -394,7 +553,7 @@ fn compress_block_bc1_core(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
     data[1] = fast_quant(block, p[0], p[1]);
 
     for (var i = 0; i < refine_iterations; i++) {
-        bc1_refine(&p, block, data[1], &dc);
+        bc1_refine(&p, block, data[1], sum);
         if (p[0] < p[1]) {
             let temp = p[0];
             p[0] = p[1];
@@ -408,28 +567,142 @@ fn compress_block_bc1_core(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
     return data;
 }
 
+// Encodes the endpoints in the order that selects the punch-through mode.
+fn three_color_endpoints(c0: vec3<i32>, c1: vec3<i32>) -> vec2<i32> {
+    let p0 = enc_rgb565(c0);
+    let p1 = enc_rgb565(c1);
+    return vec2<i32>(min(p0, p1), max(p0, p1));
+}
+
+// Returns the indices of the pixels in the punch-through mode. Opaque pixels are projected on the
+// line between the endpoints, all other pixels get the transparent index 3.
+fn three_color_quant(block: ptr<function, array<f32, 64>>, p: vec2<i32>, mask: u32) -> u32 {
+    let c0 = dec_rgb565(p[0]);
+    let dir = dec_rgb565(p[1]) - c0;
+    let sq_norm = dot(dir, dir);
+
+    // The levels 0, 1 and 2 lie at the first endpoint, the middle and the second endpoint.
+    const LEVEL_INDEX = array<u32, 3>(0u, 2u, 1u);
+
+    var bits = 0u;
+    for (var k = 0u; k < 16u; k++) {
+        var index = 3u;
+        if ((mask & (1u << k)) != 0u) {
+            // Rounds 2 * projection / sq_norm to the closest level.
+            var level = 0;
+            if (sq_norm > 0) {
+                let projection = dot(block_color(block, k) - c0, dir);
+                level = clamp((4 * projection + sq_norm) / (2 * sq_norm), 0, 2);
+            }
+
+            index = LEVEL_INDEX[level];
+        }
+
+        bits |= index << (k * 2u);
+    }
+
+    return bits;
+}
+
+// Fits the endpoints to the opaque pixels with a least squares solve for the given indices. The
+// weights of the endpoints are doubled, so the middle level has integer weights.
+fn three_color_refine(
+    block: ptr<function, array<f32, 64>>,
+    bits: u32,
+    mask: u32,
+    sum: vec3<i32>,
+) -> array<vec3<i32>, 2> {
+    var aa = 0;
+    var ab = 0;
+    var bb = 0;
+    var ax = vec3<i32>(0);
+    var bx = vec3<i32>(0);
+
+    for (var k = 0u; k < 16u; k++) {
+        if ((mask & (1u << k)) == 0u) {
+            continue;
+        }
+
+        var weight = 1;
+        let q = (bits >> (k * 2u)) & 3u;
+        if (q == 0u) {
+            weight = 0;
+        } else if (q == 1u) {
+            weight = 2;
+        }
+
+        aa += (2 - weight) * (2 - weight);
+        ab += (2 - weight) * weight;
+        bb += weight * weight;
+
+        let c = block_color(block, k);
+        ax += (2 - weight) * c;
+        bx += weight * c;
+    }
+
+    var endpoints: array<vec3<i32>, 2>;
+    let det = aa * bb - ab * ab;
+    if (det == 0) {
+        let dc = sum / i32(countOneBits(mask));
+        endpoints[0] = dc;
+        endpoints[1] = dc;
+        return endpoints;
+    }
+
+    endpoints[0] = clamp(2 * (ax * bb - bx * ab) / det, vec3<i32>(0), vec3<i32>(255));
+    endpoints[1] = clamp(2 * (bx * aa - ax * ab) / det, vec3<i32>(0), vec3<i32>(255));
+    return endpoints;
+}
+
+// Fits the opaque pixels to the three colors of the punch-through mode and marks all other
+// pixels as transparent.
+fn compress_block_bc1_three_color(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
+    var mask = 0u;
+    for (var k = 0u; k < 16u; k++) {
+        if ((*block)[48u + k] >= PUNCH_THROUGH_THRESHOLD) {
+            mask |= 1u << k;
+        }
+    }
+
+    if (mask == 0u) {
+        // Equal endpoints select the punch-through mode and every pixel is transparent.
+        return vec2<u32>(0u, 0xFFFFFFFFu);
+    }
+
+    var covar: array<i32, 6>;
+    let sum = compute_covar_sum(&covar, block, mask);
+    let axis = compute_axis3(&covar, 4);
+    var endpoints = pick_endpoints(block, mask, sum, axis);
+
+    var p = three_color_endpoints(endpoints[0], endpoints[1]);
+    var bits = three_color_quant(block, p, mask);
+
+    endpoints = three_color_refine(block, bits, mask, sum);
+    p = three_color_endpoints(endpoints[0], endpoints[1]);
+    bits = three_color_quant(block, p, mask);
+
+    return vec2<u32>((u32(p[1]) << 16u) | u32(p[0]), bits);
+}
+
 fn compress_block_bc3_alpha(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
-    var ep = vec2<f32>(255.0, 0.0);
+    var ep = vec2<i32>(255, 0);
 
     // Find min/max endpoints using block[48] to block[63] for alpha
     for (var k: u32 = 0u; k < 16u; k++) {
-        ep[0] = min(ep[0], (*block)[48 + k]);
-        ep[1] = max(ep[1], (*block)[48 + k]);
+        ep[0] = min(ep[0], i32((*block)[48 + k]));
+        ep[1] = max(ep[1], i32((*block)[48 + k]));
     }
 
     // Prevent division by zero
-    if (ep[0] == ep[1]) {
-        ep[1] = ep[0] + 0.1;
-    }
+    let range = max(ep[1] - ep[0], 1);
 
     var qblock: vec2<u32>;
-    let scale = 7.0 / (ep[1] - ep[0]);
-
     for (var k: u32 = 0u; k < 16u; k++) {
-        let v = (*block)[48u + k];
-        let proj = (v - ep[0]) * scale + 0.5;
+        // Rounds 7 * (v - ep[0]) / range to the closest index.
+        let v = i32((*block)[48u + k]);
+        let proj = (14 * (v - ep[0]) + range) / (2 * range);
 
-        var q = clamp(i32(proj), 0, 7);
+        var q = clamp(proj, 0, 7);
         q = 7 - q;
 
         if (q > 0) {
@@ -443,7 +716,7 @@ fn compress_block_bc3_alpha(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
     }
 
     var data: vec2<u32>;
-    data[0] = (clamp(u32(ep[0]), 0u, 255u) << 8u) | clamp(u32(ep[1]), 0u, 255u);
+    data[0] = (u32(ep[0]) << 8u) | u32(ep[1]);
     data[0] |= qblock[0] << 16u;
     data[1] = qblock[0] >> 16u;
     data[1] |= qblock[1] << 8u;
@@ -451,6 +724,296 @@ fn compress_block_bc3_alpha(block: ptr<function, array<f32, 64>>) -> vec2<u32> {
     return data;
 }
 
+struct Bc4Fit {
+    error: f32,
+    e0: u32,
+    e1: u32,
+    qblock: vec2<u32>,
+}
+
+// Loads a channel with its full 16-bit precision. The values are scaled to the 8-bit range of the
+// endpoints, but keep their fractional part.
+fn load_block_channel_16bit(values: ptr<function, array<f32, 16>>, xx: u32, yy: u32, channel: u32) {
+    for (var y = 0u; y < 4u; y++) {
+        for (var x = 0u; x < 4u; x++) {
+            let pixel_x = xx * 4u + x;
+            let pixel_y = yy * 4u + y;
+            let value = u32(round(load_texel(pixel_x, pixel_y)[channel] * 65535.0));
+
+            (*values)[y * 4u + x] = f32(value) / 257.0;
+        }
+    }
+}
+
+// Returns the weights of the first and second endpoint for an index of the eight value mode.
+fn bc4_weights(index: u32) -> vec2<f32> {
+    if (index == 0u) {
+        return vec2<f32>(1.0, 0.0);
+    }
+    if (index == 1u) {
+        return vec2<f32>(0.0, 1.0);
+    }
+
+    let weight = f32(index - 1u) / 7.0;
+    return vec2<f32>(1.0 - weight, weight);
+}
+
+// Assigns every value to the closest palette entry of the two 8-bit endpoints.
+fn bc4_fit(values: ptr<function, array<f32, 16>>, weights: ptr<function, array<f32, 16>>, e0: u32, e1: u32) -> Bc4Fit {
+    var fit: Bc4Fit;
+    fit.e0 = e0;
+    fit.e1 = e1;
+
+    for (var k = 0u; k < 16u; k++) {
+        var best_error = 3.40282347e38;
+        var best_index = 0u;
+
+        for (var index = 0u; index < 8u; index++) {
+            let weights = bc4_weights(index);
+            let entry = weights.x * f32(e0) + weights.y * f32(e1);
+            let error = sq((*values)[k] - entry);
+
+            if (error < best_error) {
+                best_error = error;
+                best_index = index;
+            }
+        }
+
+        fit.error += (*weights)[k] * best_error;
+        fit.qblock[k / 8u] |= best_index << ((k % 8u) * 3u);
+    }
+
+    return fit;
+}
+
+// Tries the 8-bit endpoints up to `radius` levels around the unquantized endpoints. The first
+// endpoint needs to be the larger one, or the block would be decoded in the six value mode.
+fn bc4_try_endpoints(values: ptr<function, array<f32, 16>>, weights: ptr<function, array<f32, 16>>, best: ptr<function, Bc4Fit>, high: f32, low: f32, radius: i32) {
+    for (var i = 1 - radius; i <= radius; i++) {
+        for (var j = 1 - radius; j <= radius; j++) {
+            let e0 = u32(clamp(floor(high) + f32(i), 0.0, 255.0));
+            let e1 = u32(clamp(floor(low) + f32(j), 0.0, 255.0));
+
+            if (e0 < e1) {
+                continue;
+            }
+
+            let fit = bc4_fit(values, weights, e0, e1);
+            if (fit.error < (*best).error) {
+                *best = fit;
+            }
+        }
+    }
+}
+
+// Compresses a channel of 16-bit values. The endpoints are optimized before they are quantized to
+// 8 bits, so the precision of the source is only lost in the final rounding.
+fn compress_block_bc4_16bit(values: ptr<function, array<f32, 16>>) -> Bc4Fit {
+    var weights: array<f32, 16>;
+    for (var k = 0u; k < 16u; k++) {
+        weights[k] = 1.0;
+    }
+
+    return compress_block_bc4_weighted(values, &weights, 1);
+}
+
+// Returns how much the error of a value counts in the signed distance field mode.
+fn sdf_weight(value: f32) -> f32 {
+    let falloff = max(1.0 - abs(value - uniforms.sdf_pivot) / SDF_EDGE_WIDTH, 0.0);
+    return 1.0 + (SDF_PIVOT_WEIGHT - 1.0) * falloff;
+}
+
+// Compresses a signed distance field, with the error of the values close to the pivot weighted
+// higher than the error of all other values.
+fn compress_block_bc4_sdf(values: ptr<function, array<f32, 16>>) -> Bc4Fit {
+    var weights: array<f32, 16>;
+    for (var k = 0u; k < 16u; k++) {
+        weights[k] = sdf_weight((*values)[k]);
+    }
+
+    return compress_block_bc4_weighted(values, &weights, SDF_SEARCH_RADIUS);
+}
+
+// Fits the endpoints to minimize the weighted squared error of the values.
+fn compress_block_bc4_weighted(values: ptr<function, array<f32, 16>>, weights: ptr<function, array<f32, 16>>, radius: i32) -> Bc4Fit {
+    var high = 0.0;
+    var low = 255.0;
+    for (var k = 0u; k < 16u; k++) {
+        high = max(high, (*values)[k]);
+        low = min(low, (*values)[k]);
+    }
+
+    var best: Bc4Fit;
+    best.error = 3.40282347e38;
+    bc4_try_endpoints(values, weights, &best, high, low, radius);
+
+    // Weighted least squares fit of the endpoints for the indices of the best candidate.
+    var aa = 0.0;
+    var ab = 0.0;
+    var bb = 0.0;
+    var av = 0.0;
+    var bv = 0.0;
+    for (var k = 0u; k < 16u; k++) {
+        let index = (best.qblock[k / 8u] >> ((k % 8u) * 3u)) & 7u;
+        let endpoint_weights = bc4_weights(index);
+        let weight = (*weights)[k];
+        let value = (*values)[k];
+
+        aa += weight * endpoint_weights.x * endpoint_weights.x;
+        ab += weight * endpoint_weights.x * endpoint_weights.y;
+        bb += weight * endpoint_weights.y * endpoint_weights.y;
+        av += weight * endpoint_weights.x * value;
+        bv += weight * endpoint_weights.y * value;
+    }
+
+    let det = aa * bb - ab * ab;
+    if (abs(det) > 1e-6) {
+        let e0 = (av * bb - bv * ab) / det;
+        let e1 = (bv * aa - av * ab) / det;
+        bc4_try_endpoints(values, weights, &best, max(e0, e1), min(e0, e1), radius);
+    }
+
+    return best;
+}
+
+fn pack_bc4(fit: Bc4Fit) -> vec2<u32> {
+    var data: vec2<u32>;
+    data[0] = fit.e0 | (fit.e1 << 8u) | (fit.qblock[0] << 16u);
+    data[1] = (fit.qblock[0] >> 16u) | (fit.qblock[1] << 8u);
+    return data;
+}
+
+fn expand_rgb565(c: u32) -> vec3<u32> {
+    let r5 = (c >> 11u) & 31u;
+    let g6 = (c >> 5u) & 63u;
+    let b5 = c & 31u;
+
+    return vec3<u32>(r5, g6, b5);
+}
+
+fn bc1_palette(data: vec2<u32>, opaque_mode: bool) -> array<vec3<f32>, 4> {
+    let c0 = data[0] & 0xFFFFu;
+    let c1 = data[0] >> 16u;
+    let e0 = expand_rgb565(c0);
+    let e1 = expand_rgb565(c1);
+
+    // Uses the same integer interpolation as the CPU decoder.
+    var palette: array<vec3<f32>, 4>;
+    palette[0] = vec3<f32>((e0 * vec3<u32>(527u, 259u, 527u) + vec3<u32>(23u, 33u, 23u)) >> vec3<u32>(6u));
+    palette[1] = vec3<f32>((e1 * vec3<u32>(527u, 259u, 527u) + vec3<u32>(23u, 33u, 23u)) >> vec3<u32>(6u));
+
+    if (c0 > c1 || opaque_mode) {
+        let scale = vec3<u32>(351u, 2763u, 351u);
+        let bias = vec3<u32>(61u, 1039u, 61u);
+        let shift = vec3<u32>(7u, 11u, 7u);
+        palette[2] = vec3<f32>(((2u * e0 + e1) * scale + bias) >> shift);
+        palette[3] = vec3<f32>(((e0 + 2u * e1) * scale + bias) >> shift);
+    } else {
+        let scale = vec3<u32>(1053u, 4145u, 1053u);
+        let bias = vec3<u32>(125u, 1019u, 125u);
+        let shift = vec3<u32>(8u, 11u, 8u);
+        palette[2] = vec3<f32>(((e0 + e1) * scale + bias) >> shift);
+        palette[3] = vec3<f32>(0.0);
+    }
+
+    return palette;
+}
+
+fn block_error_color(block: ptr<function, array<f32, 64>>, data: vec2<u32>, opaque_mode: bool) -> f32 {
+    var palette = bc1_palette(data, opaque_mode);
+
+    var err = 0.0;
+    for (var k = 0u; k < 16u; k++) {
+        let q = (data[1] >> (k * 2u)) & 3u;
+        for (var p = 0u; p < 3u; p++) {
+            err += sq((*block)[k + p * 16u] - palette[q][p]);
+        }
+    }
+
+    return err;
+}
+
+// Returns the error of a BC1 block, whose pixels are transparent if they use the index 3 in the
+// punch-through mode. The error of the color is weighted by the alpha of the source, since the
+// color of invisible pixels doesn't matter.
+fn block_error_bc1_alpha_weighted(block: ptr<function, array<f32, 64>>, data: vec2<u32>) -> f32 {
+    return f32(block_error_bc1_alpha_weighted_255(block, data)) / 255.0;
+}
+
+// Returns 255 times the error of `block_error_bc1_alpha_weighted()` as an integer, so the modes
+// are compared the same way on every backend.
+fn block_error_bc1_alpha_weighted_255(block: ptr<function, array<f32, 64>>, data: vec2<u32>) -> i32 {
+    var palette = bc1_palette(data, false);
+    let punch_through = (data[0] & 0xFFFFu) <= (data[0] >> 16u);
+
+    var err = 0;
+    for (var k = 0u; k < 16u; k++) {
+        let q = (data[1] >> (k * 2u)) & 3u;
+        let alpha = i32((*block)[48u + k]);
+
+        let color_diff = block_color(block, k) - vec3<i32>(palette[q]);
+        let color_err = dot(color_diff, color_diff);
+
+        var decoded_alpha = 255;
+        if (punch_through && q == 3u) {
+            decoded_alpha = 0;
+        }
+
+        err += color_err * alpha + 255 * (alpha - decoded_alpha) * (alpha - decoded_alpha);
+    }
+
+    return err;
+}
+
+fn block_error_alpha_4bit(block: ptr<function, array<f32, 64>>, alpha_bits: vec2<u32>) -> f32 {
+    var err = 0.0;
+    for (var k = 0u; k < 16u; k++) {
+        let alpha4 = (alpha_bits[k / 8u] >> ((k % 8u) * 4u)) & 15u;
+        err += sq((*block)[48u + k] - f32(alpha4 * 17u));
+    }
+
+    return err;
+}
+
+fn block_error_alpha(block: ptr<function, array<f32, 64>>, data: vec2<u32>) -> f32 {
+    let a0 = data[0] & 0xFFu;
+    let a1 = (data[0] >> 8u) & 0xFFu;
+
+    var palette: array<f32, 8>;
+    palette[0] = f32(a0);
+    palette[1] = f32(a1);
+
+    if (a0 > a1) {
+        for (var i = 1u; i < 7u; i++) {
+            palette[i + 1u] = f32(((7u - i) * a0 + i * a1) / 7u);
+        }
+    } else {
+        for (var i = 1u; i < 5u; i++) {
+            palette[i + 1u] = f32(((5u - i) * a0 + i * a1) / 5u);
+        }
+        palette[6] = 0.0;
+        palette[7] = 255.0;
+    }
+
+    var err = 0.0;
+    for (var k = 0u; k < 16u; k++) {
+        let bit = 16u + k * 3u;
+
+        var q: u32;
+        if (bit >= 32u) {
+            q = (data[1] >> (bit - 32u)) & 7u;
+        } else if (bit + 3u <= 32u) {
+            q = (data[0] >> bit) & 7u;
+        } else {
+            q = ((data[0] >> bit) | (data[1] << (32u - bit))) & 7u;
+        }
+
+        err += sq((*block)[48u + k] - palette[q]);
+    }
+
+    return err;
+}
+
 @compute
 @workgroup_size(8, 8)
 fn compress_bc1(@builtin(global_invocation_id) global_id: vec3<u32>) {
@@ -469,11 +1032,35 @@ fn compress_bc1(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     load_block_interleaved_rgba(&block, xx, yy);
 
-    let color_result = compress_block_bc1_core(&block);
+    var color_result = compress_block_bc1_core(&block);
+
+    if (uniforms.punch_through_alpha != 0u) {
+        var min_alpha = 255.0;
+        for (var k = 0u; k < 16u; k++) {
+            min_alpha = min(min_alpha, block[48u + k]);
+        }
+
+        // Blocks with transparent pixels use the mode with the lower error in all channels.
+        if (min_alpha < PUNCH_THROUGH_THRESHOLD) {
+            let punch_through = compress_block_bc1_three_color(&block);
+            if (block_error_bc1_alpha_weighted_255(&block, punch_through) < block_error_bc1_alpha_weighted_255(&block, color_result)) {
+                color_result = punch_through;
+            }
+        }
+    }
+
     compressed_data[0] = color_result[0];
     compressed_data[1] = color_result[1];
 
     store_data_2(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        if (uniforms.punch_through_alpha != 0u) {
+            store_error(block_width, xx, yy, block_error_bc1_alpha_weighted(&block, color_result));
+        } else {
+            store_error(block_width, xx, yy, block_error_color(&block, color_result, false));
+        }
+    }
 }
 
 @compute
@@ -503,6 +1090,10 @@ fn compress_bc2(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compressed_data[3] = color_result[1];
 
     store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, block_error_alpha_4bit(&block, alpha_result) + block_error_color(&block, color_result, true));
+    }
 }
 
 @compute
@@ -532,6 +1123,10 @@ fn compress_bc3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compressed_data[3] = color_result[1];
 
     store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, block_error_alpha(&block, alpha_result) + block_error_color(&block, color_result, true));
+    }
 }
 
 @compute
@@ -552,11 +1147,24 @@ fn compress_bc4(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     load_block_r_8bit(&block, xx, yy);
 
-    let color_result = compress_block_bc3_alpha(&block);
+    var color_result: vec2<u32>;
+    if (uniforms.sdf != 0u) {
+        var values: array<f32, 16>;
+        for (var k = 0u; k < 16u; k++) {
+            values[k] = block[48u + k];
+        }
+        color_result = pack_bc4(compress_block_bc4_sdf(&values));
+    } else {
+        color_result = compress_block_bc3_alpha(&block);
+    }
     compressed_data[0] = color_result[0];
     compressed_data[1] = color_result[1];
 
     store_data_2(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, block_error_alpha(&block, color_result));
+    }
 }
 
 @compute
@@ -581,6 +1189,11 @@ fn compress_bc5(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compressed_data[0] = red_result[0];
     compressed_data[1] = red_result[1];
 
+    var err = 0.0;
+    if (uniforms.write_errors != 0u) {
+        err = block_error_alpha(&block, red_result);
+    }
+
     load_block_g_8bit(&block, xx, yy);
 
     let green_result = compress_block_bc3_alpha(&block);
@@ -588,4 +1201,192 @@ fn compress_bc5(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compressed_data[3] = green_result[1];
 
     store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, err + block_error_alpha(&block, green_result));
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn compress_bc3_separate_alpha(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    var block: array<f32, 64>;
+    var compressed_data: vec4<u32>;
+
+    load_block_interleaved_rgba(&block, xx, yy);
+    load_block_secondary_r_8bit(&block, xx, yy, 3u);
+
+    let alpha_result = compress_block_bc3_alpha(&block);
+    compressed_data[0] = alpha_result[0];
+    compressed_data[1] = alpha_result[1];
+
+    let color_result = compress_block_bc1_core(&block);
+    compressed_data[2] = color_result[0];
+    compressed_data[3] = color_result[1];
+
+    store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, block_error_alpha(&block, alpha_result) + block_error_color(&block, color_result, true));
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn compress_bc5_interleaved(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    var block: array<f32, 64>;
+    var compressed_data: vec4<u32>;
+
+    load_block_r_8bit(&block, xx, yy);
+
+    let red_result = compress_block_bc3_alpha(&block);
+    compressed_data[0] = red_result[0];
+    compressed_data[1] = red_result[1];
+
+    var err = 0.0;
+    if (uniforms.write_errors != 0u) {
+        err = block_error_alpha(&block, red_result);
+    }
+
+    load_block_secondary_r_8bit(&block, xx, yy, 1u);
+
+    let green_result = compress_block_bc3_alpha(&block);
+    compressed_data[2] = green_result[0];
+    compressed_data[3] = green_result[1];
+
+    store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, err + block_error_alpha(&block, green_result));
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn compress_bc5_from_height(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    var block: array<f32, 64>;
+    var normals: array<vec2<f32>, 16>;
+    var compressed_data: vec4<u32>;
+
+    load_block_normals(&normals, xx, yy);
+    load_block_normal_channel_8bit(&block, &normals, 0u);
+
+    let red_result = compress_block_bc3_alpha(&block);
+    compressed_data[0] = red_result[0];
+    compressed_data[1] = red_result[1];
+
+    var err = 0.0;
+    if (uniforms.write_errors != 0u) {
+        err = block_error_alpha(&block, red_result);
+    }
+
+    load_block_normal_channel_8bit(&block, &normals, 1u);
+
+    let green_result = compress_block_bc3_alpha(&block);
+    compressed_data[2] = green_result[0];
+    compressed_data[3] = green_result[1];
+
+    store_data_4(block_width, xx, yy, compressed_data);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, err + block_error_alpha(&block, green_result));
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn compress_bc4_16bit(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    var values: array<f32, 16>;
+
+    load_block_channel_16bit(&values, xx, yy, 0u);
+
+    var red_fit: Bc4Fit;
+    if (uniforms.sdf != 0u) {
+        red_fit = compress_block_bc4_sdf(&values);
+
+        // The reported error is the unweighted one, like for all other blocks.
+        var weights: array<f32, 16>;
+        for (var k = 0u; k < 16u; k++) {
+            weights[k] = 1.0;
+        }
+        red_fit.error = bc4_fit(&values, &weights, red_fit.e0, red_fit.e1).error;
+    } else {
+        red_fit = compress_block_bc4_16bit(&values);
+    }
+
+    store_data_2(block_width, xx, yy, pack_bc4(red_fit));
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, red_fit.error);
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn compress_bc5_16bit(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    var values: array<f32, 16>;
+
+    load_block_channel_16bit(&values, xx, yy, 0u);
+    let red_fit = compress_block_bc4_16bit(&values);
+
+    load_block_channel_16bit(&values, xx, yy, 1u);
+    let green_fit = compress_block_bc4_16bit(&values);
+
+    let red_result = pack_bc4(red_fit);
+    let green_result = pack_bc4(green_fit);
+    store_data_4(block_width, xx, yy, vec4<u32>(red_result[0], red_result[1], green_result[0], green_result[1]));
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, red_fit.error + green_fit.error);
+    }
 }
@@ -20,16 +20,46 @@ struct Uniforms {
     height: u32,
     texture_y_offset: u32,
     blocks_offset: u32,
+    errors_offset: u32,
+    write_errors: u32,
+    edge_fill: u32,
+    fill_color: u32,
+    normal_filter: u32,
+    normal_strength: f32,
+    normal_flip_y: u32,
+    dither_alpha: u32,
+    sdf: u32,
+    sdf_pivot: f32,
+    hdr_scale: f32,
+    hdr_bias: f32,
+    source_srgb: u32,
+    punch_through_alpha: u32,
+    texture_index: u32,
 }
 
+const EDGE_FILL_CLAMP_TO_EDGE: u32 = 0u;
+const EDGE_FILL_MIRROR: u32 = 1u;
+const EDGE_FILL_ZERO: u32 = 2u;
+const EDGE_FILL_SOLID_COLOR: u32 = 3u;
+
+// Mirrors `BC6HSettings`, which is copied as it is. `BC6H_SETTINGS_LAYOUT` lists the fields in
+// this order and fails the build if the Rust layout differs.
 struct Settings {
     slow_mode: u32,
     fast_mode: u32,
     refine_iterations_1p: u32,
     refine_iterations_2p: u32,
     fast_skip_threshold: u32,
+    non_finite: u32,
+    precise_pca: u32,
 }
 
+const NON_FINITE_CLAMP: u32 = 0u;
+const NON_FINITE_NEIGHBOR_AVERAGE: u32 = 1u;
+const NON_FINITE_REJECT: u32 = 2u;
+
+const F16_MAX: f32 = 65504.0;
+
 struct State {
     data: array<u32, 5>,
     best_err: f32,
@@ -47,6 +77,30 @@ struct State {
 @group(0) @binding(1) var<storage, read_write> block_buffer: array<u32>;
 @group(0) @binding(2) var<uniform> uniforms: Uniforms;
 @group(0) @binding(3) var<storage, read> settings: Settings;
+@group(0) @binding(4) var<storage, read_write> error_buffer: array<f32>;
+
+// The bounds checking variant of the shader replaces these two declarations and binds a
+// violation counter. It's selected with the `bounds_checks` feature.
+const BOUNDS_CHECKS: bool = false;
+fn report_bounds_violation() {}
+
+// Returns true if the block lies inside the task and all elements up to `end` are inside the
+// bound buffer of `length` elements. Violations are counted and the write needs to be skipped.
+fn in_bounds(xx: u32, yy: u32, end: u32, length: u32) -> bool {
+    if (!BOUNDS_CHECKS) {
+        return true;
+    }
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx < block_width && yy < block_height && end <= length) {
+        return true;
+    }
+
+    report_bounds_violation();
+    return false;
+}
 
 fn sq(x: f32) -> f32 {
     return x * x;
@@ -69,30 +123,142 @@ fn f32_to_f16_bits(f: f32) -> u32 {
     return (sign << 15) | (exp << 10) | frac;
 }
 
-fn load_block_interleaved_16bit(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) {
+
+fn edge_coordinate(coordinate: u32, size: u32) -> u32 {
+    if (coordinate < size) {
+        return coordinate;
+    }
+    if (uniforms.edge_fill == EDGE_FILL_MIRROR) {
+        return u32(max(i32(size * 2u) - 1 - i32(coordinate), 0));
+    }
+    return size - 1u;
+}
+
+fn load_texel(pixel_x: u32, pixel_y: u32) -> vec4<f32> {
+    let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+    if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+        return vec4<f32>(0.0);
+    }
+    if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+        return unpack4x8unorm(uniforms.fill_color);
+    }
+
+    let x = edge_coordinate(pixel_x, uniforms.width);
+    let y = edge_coordinate(pixel_y, uniforms.height) + uniforms.texture_y_offset;
+    return textureLoad(source_texture, vec2<u32>(x, y), 0);
+}
+
+// Checks the exponent bits, since comparisons with NaN may be optimized away.
+fn is_finite(value: f32) -> bool {
+    return (bitcast<u32>(value) & 0x7F800000u) != 0x7F800000u;
+}
+
+fn neighbor_average(pixel_x: u32, pixel_y: u32, channel: u32) -> f32 {
+    var neighbors = array<vec2<i32>, 4>(vec2(-1, 0), vec2(1, 0), vec2(0, -1), vec2(0, 1));
+
+    var sum = 0.0;
+    var count = 0u;
+    for (var i = 0u; i < 4u; i++) {
+        let x = i32(pixel_x) + neighbors[i].x;
+        let y = i32(pixel_y) + neighbors[i].y;
+        if (x < 0 || y < 0 || u32(x) >= uniforms.width || u32(y) >= uniforms.height) {
+            continue;
+        }
+
+        let value = load_texel(u32(x), u32(y))[channel];
+        if (is_finite(value)) {
+            sum += value;
+            count += 1u;
+        }
+    }
+
+    if (count == 0u) {
+        return 0.0;
+    }
+    return sum / f32(count);
+}
+
+fn remap_hdr(value: f32) -> f32 {
+    return value * uniforms.hdr_scale + uniforms.hdr_bias;
+}
+
+// Remaps the value, replaces NaN and infinite values as configured and clamps the value to the
+// half float range. Sets `rejected` if the value is not finite and the settings reject such
+// values.
+fn sanitize_channel(value: f32, pixel_x: u32, pixel_y: u32, channel: u32, rejected: ptr<function, bool>) -> f32 {
+    var sanitized = remap_hdr(value);
+
+    if (!is_finite(value)) {
+        // The sign of infinite values is the one after the remapping.
+        let bits = bitcast<u32>(sanitized);
+        let is_nan = (bitcast<u32>(value) & 0x007FFFFFu) != 0u;
+
+        switch (settings.non_finite) {
+            case NON_FINITE_NEIGHBOR_AVERAGE: {
+                sanitized = remap_hdr(neighbor_average(pixel_x, pixel_y, channel));
+            }
+            case NON_FINITE_REJECT: {
+                sanitized = 0.0;
+                *rejected = true;
+            }
+            default: {
+                if (is_nan) {
+                    sanitized = 0.0;
+                } else {
+                    sanitized = select(F16_MAX, -F16_MAX, (bits >> 31u) != 0u);
+                }
+            }
+        }
+    }
+
+    return clamp(sanitized, -F16_MAX, F16_MAX);
+}
+
+// Returns true if the block contains a non-finite value that was rejected.
+fn load_block_interleaved_16bit(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) -> bool {
+    var rejected = false;
+
     for (var y = 0u; y < 4u; y++) {
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
-            let pixel_y = yy * 4u + y + uniforms.texture_y_offset;;
-            let rgba = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0);
+            let pixel_y = yy * 4u + y;
+            let rgba = load_texel(pixel_x, pixel_y);
 
-            (*block)[16u * 0u + y * 4u + x] = f32(f32_to_f16_bits(rgba.r) & 0xFFFF);
-            (*block)[16u * 1u + y * 4u + x] = f32(f32_to_f16_bits(rgba.g) & 0xFFFF);
-            (*block)[16u * 2u + y * 4u + x] = f32(f32_to_f16_bits(rgba.b) & 0xFFFF);
+            for (var channel = 0u; channel < 3u; channel++) {
+                let value = sanitize_channel(rgba[channel], pixel_x, pixel_y, channel, &rejected);
+                (*block)[16u * channel + y * 4u + x] = f32(f32_to_f16_bits(value) & 0xFFFF);
+            }
             (*block)[16u * 3u + y * 4u + x] = 0.0;
         }
     }
+
+    return rejected;
 }
 
 fn store_data(state: ptr<function, State>, block_width: u32, xx: u32, yy: u32) {
     let offset = uniforms.blocks_offset + (yy * block_width * 4u + xx * 4u);
 
+    if (!in_bounds(xx, yy, offset + 4u, arrayLength(&block_buffer))) {
+        return;
+    }
+
     block_buffer[offset + 0] = (*state).data[0];
     block_buffer[offset + 1] = (*state).data[1];
     block_buffer[offset + 2] = (*state).data[2];
     block_buffer[offset + 3] = (*state).data[3];
 }
 
+fn store_error(block_width: u32, xx: u32, yy: u32, error: f32) {
+    let offset = uniforms.errors_offset + yy * block_width + xx;
+
+    if (!in_bounds(xx, yy, offset + 1u, arrayLength(&error_buffer))) {
+        return;
+    }
+
+    error_buffer[offset] = error;
+}
+
 fn get_unquant_value(bits: u32, index: i32) -> i32 {
     switch (bits) {
         case 2u: {
@@ -496,11 +662,82 @@ fn compute_covar_dc_masked(covar: ptr<function, array<f32, 10>>, dc: ptr<functio
     covar_from_stats(covar, stats, channels);
 }
 
+// Computes the covariance like `compute_covar_dc_masked`, but from the differences to the mean,
+// which don't cancel out for large values with small differences. The mean is accumulated with
+// compensated sums, and the sums of the differences compensate for its remaining rounding error.
+fn compute_covar_dc_masked_precise(covar: ptr<function, array<f32, 10>>, dc: ptr<function, vec4<f32>>, block: ptr<function, array<f32, 64>>, mask: u32, channels: u32) {
+    var count = 0.0;
+    var sum = vec4<f32>(0.0);
+    var compensation = vec4<f32>(0.0);
+    for (var k = 0u; k < 16u; k++) {
+        if (((mask >> k) & 1u) == 0u) {
+            continue;
+        }
+
+        count += 1.0;
+        for (var p = 0u; p < channels; p++) {
+            let value = (*block)[k + p * 16u] - compensation[p];
+            let total = sum[p] + value;
+            compensation[p] = (total - sum[p]) - value;
+            sum[p] = total;
+        }
+    }
+
+    for (var p = 0u; p < channels; p++) {
+        (*dc)[p] = sum[p] / count;
+    }
+
+    var sums = vec4<f32>(0.0);
+    var products: array<f32, 10>;
+    for (var k = 0u; k < 16u; k++) {
+        if (((mask >> k) & 1u) == 0u) {
+            continue;
+        }
+
+        var diff = vec4<f32>(0.0);
+        for (var p = 0u; p < channels; p++) {
+            diff[p] = (*block)[k + p * 16u] - (*dc)[p];
+        }
+        sums += diff;
+
+        products[0] += diff[0] * diff[0];
+        products[1] += diff[0] * diff[1];
+        products[2] += diff[0] * diff[2];
+        products[3] += diff[0] * diff[3];
+        products[4] += diff[1] * diff[1];
+        products[5] += diff[1] * diff[2];
+        products[6] += diff[1] * diff[3];
+        products[7] += diff[2] * diff[2];
+        products[8] += diff[2] * diff[3];
+        products[9] += diff[3] * diff[3];
+    }
+
+    (*covar)[0] = products[0] - sums[0] * sums[0] / count;
+    (*covar)[1] = products[1] - sums[0] * sums[1] / count;
+    (*covar)[2] = products[2] - sums[0] * sums[2] / count;
+
+    (*covar)[4] = products[4] - sums[1] * sums[1] / count;
+    (*covar)[5] = products[5] - sums[1] * sums[2] / count;
+
+    (*covar)[7] = products[7] - sums[2] * sums[2] / count;
+
+    if (channels == 4u) {
+        (*covar)[3] = products[3] - sums[0] * sums[3] / count;
+        (*covar)[6] = products[6] - sums[1] * sums[3] / count;
+        (*covar)[8] = products[8] - sums[2] * sums[3] / count;
+        (*covar)[9] = products[9] - sums[3] * sums[3] / count;
+    }
+}
+
 fn block_pca_axis(axis: ptr<function, vec4<f32>>, dc: ptr<function, vec4<f32>>, block: ptr<function, array<f32, 64>>, mask: u32, channels: u32) {
     const power_iterations = 8u; // 4 not enough for HQ
 
     var covar: array<f32, 10>;
-    compute_covar_dc_masked(&covar, dc, block, mask, channels);
+    if (settings.precise_pca != 0u) {
+        compute_covar_dc_masked_precise(&covar, dc, block, mask, channels);
+    } else {
+        compute_covar_dc_masked(&covar, dc, block, mask, channels);
+    }
 
     const inv_var = 1.0 / (256.0 * 256.0);
     for (var k = 0u; k < 10u; k++) {
@@ -1413,7 +1650,7 @@ fn compress_bc6h(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     var block: array<f32, 64>;
 
-    load_block_interleaved_16bit(&block, xx, yy);
+    let rejected = load_block_interleaved_16bit(&block, xx, yy);
 
     var state: State;
     state.best_err = 3.40282347e38;
@@ -1421,4 +1658,8 @@ fn compress_bc6h(@builtin(global_invocation_id) global_id: vec3<u32>) {
     compress_bc6h_core(&state, &block);
 
     store_data(&state, block_width, xx, yy);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, select(state.best_err, bitcast<f32>(0x7F800000u), rejected));
+    }
 }
@@ -20,8 +20,35 @@ struct Uniforms {
     height: u32,
     texture_y_offset: u32,
     blocks_offset: u32,
+    errors_offset: u32,
+    write_errors: u32,
+    edge_fill: u32,
+    fill_color: u32,
+    normal_filter: u32,
+    normal_strength: f32,
+    normal_flip_y: u32,
+    dither_alpha: u32,
+    sdf: u32,
+    sdf_pivot: f32,
+    hdr_scale: f32,
+    hdr_bias: f32,
+    source_srgb: u32,
+    punch_through_alpha: u32,
+    texture_index: u32,
 }
 
+const EDGE_FILL_CLAMP_TO_EDGE: u32 = 0u;
+const EDGE_FILL_MIRROR: u32 = 1u;
+const EDGE_FILL_ZERO: u32 = 2u;
+const EDGE_FILL_SOLID_COLOR: u32 = 3u;
+
+const MODE2_SEARCH_FULL: u32 = 0u;
+const MODE2_SEARCH_SKIP: u32 = 1u;
+const MODE2_SEARCH_ESTIMATE: u32 = 2u;
+const MODE2_ESTIMATE_PARTITIONS: u32 = 8u;
+
+// Mirrors `BC7Settings`, which is copied as it is. `BC7_SETTINGS_LAYOUT` lists the fields in
+// this order and fails the build if the Rust layout differs.
 struct Settings {
     refine_iterations: array<u32, 8>,
     mode_selection: array<u32, 4>,
@@ -30,14 +57,22 @@ struct Settings {
     fast_skip_threshold_mode3: u32,
     fast_skip_threshold_mode7: u32,
     mode45_channel0: u32,
+    mode45_channel1: u32,
     refine_iterations_channel: u32,
     channels: u32,
+    alpha_weight: f32,
+    linear_error: u32,
+    precise_pca: u32,
+    neighbor_seeding: u32,
+    neighbor_max_error: f32,
 }
 
 struct State {
     data: array<u32, 5>,
     best_err: f32,
     opaque_err: f32,
+    // The alpha of all texels of the block is 255.
+    opaque: bool,
 }
 
 struct Mode45Parameters {
@@ -53,6 +88,30 @@ struct Mode45Parameters {
 @group(0) @binding(1) var<storage, read_write> block_buffer: array<u32>;
 @group(0) @binding(2) var<uniform> uniforms: Uniforms;
 @group(0) @binding(3) var<storage, read> settings: Settings;
+@group(0) @binding(4) var<storage, read_write> error_buffer: array<f32>;
+
+// The bounds checking variant of the shader replaces these two declarations and binds a
+// violation counter. It's selected with the `bounds_checks` feature.
+const BOUNDS_CHECKS: bool = false;
+fn report_bounds_violation() {}
+
+// Returns true if the block lies inside the task and all elements up to `end` are inside the
+// bound buffer of `length` elements. Violations are counted and the write needs to be skipped.
+fn in_bounds(xx: u32, yy: u32, end: u32, length: u32) -> bool {
+    if (!BOUNDS_CHECKS) {
+        return true;
+    }
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx < block_width && yy < block_height && end <= length) {
+        return true;
+    }
+
+    report_bounds_violation();
+    return false;
+}
 
 fn sq(x: f32) -> f32 {
     return x * x;
@@ -62,12 +121,50 @@ fn rsqrt(x: f32) -> f32 {
     return 1.0 / sqrt(x);
 }
 
+
+fn edge_coordinate(coordinate: u32, size: u32) -> u32 {
+    if (coordinate < size) {
+        return coordinate;
+    }
+    if (uniforms.edge_fill == EDGE_FILL_MIRROR) {
+        return u32(max(i32(size * 2u) - 1 - i32(coordinate), 0));
+    }
+    return size - 1u;
+}
+
+// Encodes the colors of sRGB views, which the hardware decoded to linear, back to sRGB.
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn load_texel(pixel_x: u32, pixel_y: u32) -> vec4<f32> {
+    let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+    if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+        return vec4<f32>(0.0);
+    }
+    if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+        return unpack4x8unorm(uniforms.fill_color);
+    }
+
+    let x = edge_coordinate(pixel_x, uniforms.width);
+    let y = edge_coordinate(pixel_y, uniforms.height) + uniforms.texture_y_offset;
+    let texel = textureLoad(source_texture, vec2<u32>(x, y), 0);
+
+    if (uniforms.source_srgb != 0u) {
+        return vec4<f32>(linear_to_srgb(texel.rgb), texel.a);
+    }
+    return texel;
+}
+
 fn load_block_interleaved_rgba(block: ptr<function, array<f32, 64>>, xx: u32, yy: u32) {
     for (var y = 0u; y < 4u; y++) {
         for (var x = 0u; x < 4u; x++) {
             let pixel_x = xx * 4u + x;
-            let pixel_y = yy * 4u + y + uniforms.texture_y_offset;
-            let rgba = textureLoad(source_texture, vec2<u32>(pixel_x, pixel_y), 0);
+            let pixel_y = yy * 4u + y;
+            let rgba = load_texel(pixel_x, pixel_y);
 
             (*block)[16u * 0u + y * 4u + x] = rgba.r * 255.0;
             (*block)[16u * 1u + y * 4u + x] = rgba.g * 255.0;
@@ -80,12 +177,26 @@ fn load_block_interleaved_rgba(block: ptr<function, array<f32, 64>>, xx: u32, yy
 fn store_data(state: ptr<function, State>, block_width: u32, xx: u32, yy: u32) {
     let offset = uniforms.blocks_offset + (yy * block_width * 4u + xx * 4u);
 
+    if (!in_bounds(xx, yy, offset + 4u, arrayLength(&block_buffer))) {
+        return;
+    }
+
     block_buffer[offset + 0] = (*state).data[0];
     block_buffer[offset + 1] = (*state).data[1];
     block_buffer[offset + 2] = (*state).data[2];
     block_buffer[offset + 3] = (*state).data[3];
 }
 
+fn store_error(block_width: u32, xx: u32, yy: u32, error: f32) {
+    let offset = uniforms.errors_offset + yy * block_width + xx;
+
+    if (!in_bounds(xx, yy, offset + 1u, arrayLength(&error_buffer))) {
+        return;
+    }
+
+    error_buffer[offset] = error;
+}
+
 fn get_unquant_value(bits: u32, index: i32) -> i32 {
     switch (bits) {
         case 2u: {
@@ -322,11 +433,82 @@ fn compute_covar_dc_masked(covar: ptr<function, array<f32, 10>>, dc: ptr<functio
     covar_from_stats(covar, stats, channels);
 }
 
+// Computes the covariance like `compute_covar_dc_masked`, but from the differences to the mean,
+// which don't cancel out for large values with small differences. The mean is accumulated with
+// compensated sums, and the sums of the differences compensate for its remaining rounding error.
+fn compute_covar_dc_masked_precise(covar: ptr<function, array<f32, 10>>, dc: ptr<function, vec4<f32>>, block: ptr<function, array<f32, 64>>, mask: u32, channels: u32) {
+    var count = 0.0;
+    var sum = vec4<f32>(0.0);
+    var compensation = vec4<f32>(0.0);
+    for (var k = 0u; k < 16u; k++) {
+        if (((mask >> k) & 1u) == 0u) {
+            continue;
+        }
+
+        count += 1.0;
+        for (var p = 0u; p < channels; p++) {
+            let value = (*block)[k + p * 16u] - compensation[p];
+            let total = sum[p] + value;
+            compensation[p] = (total - sum[p]) - value;
+            sum[p] = total;
+        }
+    }
+
+    for (var p = 0u; p < channels; p++) {
+        (*dc)[p] = sum[p] / count;
+    }
+
+    var sums = vec4<f32>(0.0);
+    var products: array<f32, 10>;
+    for (var k = 0u; k < 16u; k++) {
+        if (((mask >> k) & 1u) == 0u) {
+            continue;
+        }
+
+        var diff = vec4<f32>(0.0);
+        for (var p = 0u; p < channels; p++) {
+            diff[p] = (*block)[k + p * 16u] - (*dc)[p];
+        }
+        sums += diff;
+
+        products[0] += diff[0] * diff[0];
+        products[1] += diff[0] * diff[1];
+        products[2] += diff[0] * diff[2];
+        products[3] += diff[0] * diff[3];
+        products[4] += diff[1] * diff[1];
+        products[5] += diff[1] * diff[2];
+        products[6] += diff[1] * diff[3];
+        products[7] += diff[2] * diff[2];
+        products[8] += diff[2] * diff[3];
+        products[9] += diff[3] * diff[3];
+    }
+
+    (*covar)[0] = products[0] - sums[0] * sums[0] / count;
+    (*covar)[1] = products[1] - sums[0] * sums[1] / count;
+    (*covar)[2] = products[2] - sums[0] * sums[2] / count;
+
+    (*covar)[4] = products[4] - sums[1] * sums[1] / count;
+    (*covar)[5] = products[5] - sums[1] * sums[2] / count;
+
+    (*covar)[7] = products[7] - sums[2] * sums[2] / count;
+
+    if (channels == 4u) {
+        (*covar)[3] = products[3] - sums[0] * sums[3] / count;
+        (*covar)[6] = products[6] - sums[1] * sums[3] / count;
+        (*covar)[8] = products[8] - sums[2] * sums[3] / count;
+        (*covar)[9] = products[9] - sums[3] * sums[3] / count;
+    }
+}
+
 fn block_pca_axis(axis: ptr<function, vec4<f32>>, dc: ptr<function, vec4<f32>>, block: ptr<function, array<f32, 64>>, mask: u32, channels: u32) {
     const power_iterations = 8u; // 4 not enough for HQ
 
     var covar: array<f32, 10>;
-    compute_covar_dc_masked(&covar, dc, block, mask, channels);
+    if (settings.precise_pca != 0u) {
+        compute_covar_dc_masked_precise(&covar, dc, block, mask, channels);
+    } else {
+        compute_covar_dc_masked(&covar, dc, block, mask, channels);
+    }
 
     const inv_var = 1.0 / (256.0 * 256.0);
     for (var k = 0u; k < 10u; k++) {
@@ -363,6 +545,17 @@ fn block_pca_bound_split(block: ptr<function, array<f32, 64>>, mask: u32, full_s
     return sqrt(bound) * 256.0;
 }
 
+// Estimates the smallest squared error of fitting a line segment through the masked pixels.
+fn block_pca_residual(block: ptr<function, array<f32, 64>>, mask: u32, channels: u32) -> f32 {
+    var stats: array<f32, 15>;
+    compute_stats_masked(&stats, block, mask, channels);
+
+    var covar: array<f32, 10>;
+    covar_from_stats(&covar, stats, channels);
+
+    return get_pca_bound(covar, channels) * 256.0 * 256.0;
+}
+
 fn unpack_to_byte(v: i32, bits: u32) -> i32 {
     let vv = v << (8u - bits);
     return vv + (vv >> bits);
@@ -495,7 +688,56 @@ fn ep_quant_dequant(qep: ptr<function, array<i32, 24>>, ep: ptr<function, array<
     ep_dequant(ep, qep, mode);
 }
 
-fn block_quant(qblock: ptr<function, vec2<u32>>, block: ptr<function, array<f32, 64>>, bits: u32, ep: ptr<function, array<f32, 24>>, pattern: u32, channels: u32) -> f32 {
+fn channel_weights() -> vec4<f32> {
+    return vec4<f32>(1.0, 1.0, 1.0, settings.alpha_weight);
+}
+
+// The squared slope of the sRGB transfer function at every 8-bit value. Must match
+// `LINEAR_ERROR_WEIGHTS` of the CPU encoder.
+const LINEAR_ERROR_WEIGHTS = array<f32, 256>(
+    0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664, 0.005990664,
+    0.005990664, 0.005990664, 0.005990664, 0.006698007, 0.007474674, 0.008306961, 0.009196571, 0.010145195,
+    0.011154513, 0.012226191, 0.013361886, 0.014563243, 0.015831897, 0.017169474, 0.018577589, 0.02005785,
+    0.021611853, 0.023241188, 0.024947437, 0.026732171, 0.028596954, 0.030543348, 0.0325729, 0.034687147,
+    0.036887635, 0.039175887, 0.041553423, 0.044021767, 0.04658242, 0.04923689, 0.051986672, 0.05483326,
+    0.05777814, 0.06082279, 0.06396868, 0.06721729, 0.070570074, 0.0740285, 0.07759402, 0.08126808,
+    0.08505212, 0.08894759, 0.09295591, 0.097078525, 0.10131686, 0.10567233, 0.11014635, 0.11474034,
+    0.11945571, 0.12429386, 0.12925617, 0.13434409, 0.13955897, 0.1449022, 0.15037519, 0.15597929,
+    0.16171591, 0.1675864, 0.17359215, 0.17973451, 0.18601486, 0.19243455, 0.19899493, 0.20569739,
+    0.21254323, 0.21953383, 0.22667053, 0.23395467, 0.24138759, 0.24897061, 0.2567051, 0.26459235,
+    0.2726337, 0.28083047, 0.289184, 0.29769558, 0.30636656, 0.3151982, 0.3241919, 0.33334887,
+    0.34267044, 0.35215798, 0.36181268, 0.37163594, 0.381629, 0.39179313, 0.40212968, 0.41263992,
+    0.42332512, 0.43418655, 0.4452255, 0.45644328, 0.46784112, 0.47942033, 0.49118218, 0.5031279,
+    0.5152588, 0.5275761, 0.5400811, 0.552775, 0.5656592, 0.5787348, 0.59200317, 0.6054655,
+    0.61912304, 0.632977, 0.64702874, 0.66127944, 0.6757303, 0.6903826, 0.7052376, 0.7202965,
+    0.7355606, 0.75103104, 0.76670915, 0.78259605, 0.798693, 0.8150013, 0.8315221, 0.84825665,
+    0.8652062, 0.88237184, 0.89975494, 0.91735667, 0.9351782, 0.9532208, 0.9714856, 0.9899739,
+    1.0086868, 1.0276257, 1.0467916, 1.0661857, 1.0858094, 1.1056637, 1.1257498, 1.1460692,
+    1.1666226, 1.1874117, 1.2084373, 1.2297007, 1.2512032, 1.2729459, 1.29493, 1.3171567,
+    1.3396271, 1.3623426, 1.3853041, 1.4085128, 1.4319701, 1.4556772, 1.4796349, 1.5038446,
+    1.5283076, 1.5530249, 1.5779977, 1.6032271, 1.6287144, 1.6544608, 1.6804672, 1.7067351,
+    1.7332655, 1.7600595, 1.7871182, 1.814443, 1.8420349, 1.869895, 1.8980247, 1.9264249,
+    1.9550967, 1.9840416, 2.0132604, 2.0427544, 2.0725248, 2.1025724, 2.1328988, 2.163505,
+    2.1943922, 2.2255611, 2.2570136, 2.2887502, 2.3207722, 2.3530807, 2.385677, 2.4185622,
+    2.4517374, 2.4852037, 2.5189621, 2.553014, 2.5873606, 2.6220026, 2.6569414, 2.692178,
+    2.7277138, 2.7635496, 2.7996864, 2.8361259, 2.8728688, 2.9099164, 2.9472694, 2.9849296,
+    3.0228975, 3.0611746, 3.099762, 3.1386604, 3.1778712, 3.2173958, 3.2572348, 3.2973897,
+    3.3378613, 3.378651, 3.4197595, 3.4611883, 3.5029383, 3.5450106, 3.5874064, 3.630127,
+    3.673173, 3.7165458, 3.7602465, 3.804276, 3.8486357, 3.8933265, 3.9383497, 3.983706,
+    4.029397, 4.0754232, 4.121786, 4.1684866, 4.215526, 4.2629056, 4.3106256, 4.358688,
+    4.4070935, 4.455843, 4.504938, 4.5543795, 4.604168, 4.6543055, 4.7047925, 4.75563,
+    4.8068194, 4.8583617, 4.910258, 4.962509, 5.0151167, 5.068081, 5.1214037, 5.175086,
+);
+
+fn linear_channels() -> u32 {
+    return select(0u, 7u, settings.linear_error != 0u);
+}
+
+fn linear_error_weight(value: f32) -> f32 {
+    return LINEAR_ERROR_WEIGHTS[u32(clamp(value, 0.0, 255.0))];
+}
+
+fn block_quant(qblock: ptr<function, vec2<u32>>, block: ptr<function, array<f32, 64>>, bits: u32, ep: ptr<function, array<f32, 24>>, pattern: u32, channels: u32, weights: vec4<f32>, linear_channels: u32) -> f32 {
     var total_err = 0.0;
     let levels = 1u << bits;
 
@@ -531,8 +773,12 @@ fn block_quant(qblock: ptr<function, vec2<u32>>, block: ptr<function, array<f32,
             let ep_b = (*ep)[8u * j + 4u + p];
             let dec_v0 = f32(((64 - w0) * i32(ep_a) + w0 * i32(ep_b) + 32) / 64);
             let dec_v1 = f32(((64 - w1) * i32(ep_a) + w1 * i32(ep_b) + 32) / 64);
-            err0 += sq(dec_v0 - (*block)[k + p * 16u]);
-            err1 += sq(dec_v1 - (*block)[k + p * 16u]);
+            var weight = weights[p];
+            if ((linear_channels & (1u << p)) != 0u) {
+                weight *= linear_error_weight((*block)[k + p * 16u]);
+            }
+            err0 += weight * sq(dec_v0 - (*block)[k + p * 16u]);
+            err1 += weight * sq(dec_v1 - (*block)[k + p * 16u]);
         }
 
         var best_err = err1;
@@ -596,7 +842,7 @@ fn block_segment(ep: ptr<function, array<f32, 24>>, offset: u32, block: ptr<func
     }
 }
 
-fn opt_channel(qblock: ptr<function, vec2<u32>>, qep: ptr<function, vec2<i32>>, channel_block: ptr<function, array<f32, 16>>, bits: u32, epbits: u32) -> f32 {
+fn opt_channel(qblock: ptr<function, vec2<u32>>, qep: ptr<function, vec2<i32>>, channel_block: ptr<function, array<f32, 16>>, bits: u32, epbits: u32, linear: bool) -> f32 {
     var ep: vec2<f32> = vec2<f32>(255.0, 0.0);
 
     for (var k = 0u; k < 16u; k++) {
@@ -605,14 +851,14 @@ fn opt_channel(qblock: ptr<function, vec2<u32>>, qep: ptr<function, vec2<i32>>,
     }
 
     channel_quant_dequant(qep, &ep, epbits);
-    var err = channel_opt_quant(qblock, channel_block, bits, &ep);
+    var err = channel_opt_quant(qblock, channel_block, bits, &ep, linear);
 
     // Refine
     let refine_iterations = settings.refine_iterations_channel;
     for (var i = 0u; i < refine_iterations; i++) {
         channel_opt_endpoints(&ep, channel_block, bits, *qblock);
         channel_quant_dequant(qep, &ep, epbits);
-        err = channel_opt_quant(qblock, channel_block, bits, &ep);
+        err = channel_opt_quant(qblock, channel_block, bits, &ep, linear);
     }
 
     return err;
@@ -628,7 +874,7 @@ fn channel_quant_dequant(qep: ptr<function, vec2<i32>>, ep: ptr<function, vec2<f
     }
 }
 
-fn channel_opt_quant(qblock: ptr<function, vec2<u32>>, channel_block: ptr<function, array<f32, 16>>, bits: u32, ep: ptr<function, vec2<f32>>) -> f32 {
+fn channel_opt_quant(qblock: ptr<function, vec2<u32>>, channel_block: ptr<function, array<f32, 16>>, bits: u32, ep: ptr<function, vec2<f32>>, linear: bool) -> f32 {
     let levels = i32(1u << bits);
 
     (*qblock)[0] = 0u;
@@ -649,8 +895,9 @@ fn channel_opt_quant(qblock: ptr<function, vec2<u32>>, channel_block: ptr<functi
 
         let dec_v0 = f32(((64 - w0) * i32((*ep)[0]) + w0 * i32((*ep)[1]) + 32) / 64);
         let dec_v1 = f32(((64 - w1) * i32((*ep)[0]) + w1 * i32((*ep)[1]) + 32) / 64);
-        err0 += sq(dec_v0 - (*channel_block)[k]);
-        err1 += sq(dec_v1 - (*channel_block)[k]);
+        let weight = select(1.0, linear_error_weight((*channel_block)[k]), linear);
+        err0 += weight * sq(dec_v0 - (*channel_block)[k]);
+        err1 += weight * sq(dec_v1 - (*channel_block)[k]);
 
         let best_err = select(err1, err0, err0 < err1);
         let best_q = select(q1_clamped, q1_clamped - 1, err0 < err1);
@@ -1054,7 +1301,7 @@ fn bc7_enc_mode01237_part_fast(qep: ptr<function, array<i32, 24>>, qblock: ptr<f
 
     ep_quant_dequant(qep, &ep, mode, channels);
 
-    return block_quant(qblock, block, bits, &ep, pattern, channels);
+    return block_quant(qblock, block, bits, &ep, pattern, channels, channel_weights(), linear_channels());
 }
 
 fn bc7_enc_mode01237(state: ptr<function, State>, block: ptr<function, array<f32, 64>>, mode: u32, part_list: array<i32, 64>, part_count: u32) {
@@ -1106,7 +1353,7 @@ fn bc7_enc_mode01237(state: ptr<function, State>, block: ptr<function, array<f32
         ep_quant_dequant(&qep, &ep, mode, channels);
 
         let pattern = get_pattern(best_part_id);
-        let err = block_quant(&qblock, block, bits, &ep, pattern, channels);
+        let err = block_quant(&qblock, block, bits, &ep, pattern, channels, channel_weights(), linear_channels());
 
         if (err < best_err) {
             for (var i = 0u; i < 8u * pairs; i++) {
@@ -1138,11 +1385,34 @@ fn bc7_enc_mode02(state: ptr<function, State>, block: ptr<function, array<f32, 6
 
     bc7_enc_mode01237(state, block, 0u, part_list, 16u);
 
-    if (settings.skip_mode2 == 0) {
+    if (settings.skip_mode2 == MODE2_SEARCH_FULL) {
         bc7_enc_mode01237(state, block, 2u, part_list, 64u);
     }
 }
 
+// Only searches the mode 2 partitions whose estimated error can beat the best error.
+fn bc7_enc_mode2_estimate(state: ptr<function, State>, block: ptr<function, array<f32, 64>>) {
+    var part_list: array<i32, 64>;
+    var part_count = 0u;
+
+    for (var part = 0; part < 64; part++) {
+        var bound = (*state).opaque_err;
+        for (var j = 0u; j < 3u; j++) {
+            let mask = get_pattern_mask(part + 64, j);
+            bound += block_pca_residual(block, mask, 3u);
+        }
+
+        if (bound < (*state).best_err) {
+            part_list[part_count] = part + i32(bound) * 64;
+            part_count += 1u;
+        }
+    }
+
+    let search_count = min(part_count, MODE2_ESTIMATE_PARTITIONS);
+    partial_sort_list(&part_list, i32(part_count), i32(search_count));
+    bc7_enc_mode01237(state, block, 2u, part_list, search_count);
+}
+
 fn bc7_enc_mode13(state: ptr<function, State>, block: ptr<function, array<f32, 64>>) {
     if (settings.fast_skip_threshold_mode1 == 0u && settings.fast_skip_threshold_mode3 == 0u) {
         return;
@@ -1205,15 +1475,26 @@ fn bc7_enc_mode45_candidate(best_candidate: ptr<function, Mode45Parameters>, bes
     var qep: array<i32, 24>;
     ep_quant_dequant(&qep, &ep, mode, 3u);
 
+    // The rotated channel takes the place of alpha, so it also takes its weight. Alpha is
+    // not sRGB encoded, so its error is never measured in linear space.
+    var weights = channel_weights();
+    var linear = linear_channels();
+    if (rotation < 3u) {
+        weights[rotation] = settings.alpha_weight;
+        weights[3] = 1.0;
+        linear &= ~(1u << rotation);
+    }
+    let channel_linear = linear != linear_channels();
+
     var qblock: vec2<u32>;
-    var err = block_quant(&qblock, &candidate_block, bits, &ep, 0u, 3u);
+    var err = block_quant(&qblock, &candidate_block, bits, &ep, 0u, 3u, weights, linear);
 
     // Refine
     let refine_iterations = settings.refine_iterations[mode];
     for (var i = 0u; i < refine_iterations; i++) {
         opt_endpoints(&ep, 0u, &candidate_block, bits, qblock, 0xFFFFFFFFu, 3u);
         ep_quant_dequant(&qep, &ep, mode, 3u);
-        err = block_quant(&qblock, &candidate_block, bits, &ep, 0u, 3u);
+        err = block_quant(&qblock, &candidate_block, bits, &ep, 0u, 3u, weights, linear);
     }
 
     var channel_data: array<f32, 16>;
@@ -1225,7 +1506,7 @@ fn bc7_enc_mode45_candidate(best_candidate: ptr<function, Mode45Parameters>, bes
     var aqep: vec2<i32>;
     var aqblock: vec2<u32>;
 
-    err += opt_channel(&aqblock, &aqep, &channel_data, abits, aepbits);
+    err += channel_weights()[rotation] * opt_channel(&aqblock, &aqep, &channel_data, abits, aepbits, channel_linear);
 
     if (err < *best_err) {
         for (var i = 0u; i < 8u; i++) {
@@ -1247,7 +1528,8 @@ fn bc7_enc_mode45(state: ptr<function, State>, block: ptr<function, array<f32, 6
     var best_err = (*state).best_err;
 
     let channel0 = settings.mode45_channel0;
-    for (var p = channel0; p < settings.channels; p++) {
+    let channel1 = settings.mode45_channel1;
+    for (var p = channel0; p < channel1; p++) {
         bc7_enc_mode45_candidate(&best_candidate, &best_err, block, 4u, p, 0u);
         bc7_enc_mode45_candidate(&best_candidate, &best_err, block, 4u, p, 1u);
     }
@@ -1258,7 +1540,7 @@ fn bc7_enc_mode45(state: ptr<function, State>, block: ptr<function, array<f32, 6
         bc7_code_mode45(state, &best_candidate, 4u);
     }
 
-    for (var p = channel0; p < settings.channels; p++) {
+    for (var p = channel0; p < channel1; p++) {
         bc7_enc_mode45_candidate(&best_candidate, &best_err, block, 5u, p, 0u);
     }
 
@@ -1286,13 +1568,13 @@ fn bc7_enc_mode6(state: ptr<function, State>, block: ptr<function, array<f32, 64
     ep_quant_dequant(&qep, &ep, mode, settings.channels);
 
     var qblock: vec2<u32>;
-    var err = block_quant(&qblock, block, bits, &ep, 0u, settings.channels);
+    var err = block_quant(&qblock, block, bits, &ep, 0u, settings.channels, channel_weights(), linear_channels());
 
     let refine_iterations = settings.refine_iterations[mode];
     for (var i = 0u; i < refine_iterations; i++) {
         opt_endpoints(&ep, 0u, block, bits, qblock, 0xFFFFFFFFu, settings.channels);
         ep_quant_dequant(&qep, &ep, mode, settings.channels);
-        err = block_quant(&qblock, block, bits, &ep, 0u, settings.channels);
+        err = block_quant(&qblock, block, bits, &ep, 0u, settings.channels, channel_weights(), linear_channels());
     }
 
     if (err < (*state).best_err) {
@@ -1321,56 +1603,150 @@ fn bc7_enc_mode7(state: ptr<function, State>, block: ptr<function, array<f32, 64
     bc7_enc_mode01237(state, block, 7u, part_list, settings.fast_skip_threshold_mode7);
 }
 
+// Fits the block with the mode and partition or rotation of the block that starts with the word
+// `seed`.
+fn bc7_enc_seed(state: ptr<function, State>, block: ptr<function, array<f32, 64>>, seed: u32, alpha_modes: bool) {
+    let mode = firstTrailingBit(seed & 0xFFu);
+
+    switch (mode) {
+        case 0u, 1u, 2u, 3u, 7u: {
+            if (mode == 7u && !alpha_modes) {
+                return;
+            }
+
+            var shift = mode + 1u;
+            var bits = 6u;
+            if (mode == 0u) {
+                bits = 4u;
+            } else if (mode == 7u) {
+                shift = 8u;
+            }
+
+            var part_list: array<i32, 64>;
+            part_list[0] = i32((seed >> shift) & ((1u << bits) - 1u));
+            bc7_enc_mode01237(state, block, mode, part_list, 1u);
+        }
+        case 4u, 5u: {
+            if (!alpha_modes) {
+                return;
+            }
+
+            // The rotation is stored as the channel that is swapped with alpha plus one.
+            let rotation = ((seed >> (mode + 1u)) + 3u) & 3u;
+            var swap = 0u;
+            if (mode == 4u) {
+                swap = (seed >> 7u) & 1u;
+            }
+
+            var candidate: Mode45Parameters;
+            var best_err = (*state).best_err;
+            bc7_enc_mode45_candidate(&candidate, &best_err, block, mode, rotation, swap);
+
+            if (best_err < (*state).best_err) {
+                (*state).best_err = best_err;
+                bc7_code_mode45(state, &candidate, mode);
+            }
+        }
+        case 6u: {
+            bc7_enc_mode6(state, block);
+        }
+        default: {}
+    }
+}
+
+// Starts the search with the mode of `seed`, the first word of the block to the left. The full
+// search is skipped if the seeded block is good enough.
+fn compress_block_bc7_core_seeded(state: ptr<function, State>, block: ptr<function, array<f32, 64>>, seed: u32) {
+    bc7_enc_seed(state, block, seed, settings.channels == 3u || !(*state).opaque);
+    if ((*state).best_err <= settings.neighbor_max_error) {
+        return;
+    }
+
+    compress_block_bc7_core(state, block);
+}
+
 fn compress_block_bc7_core(state: ptr<function, State>, block: ptr<function, array<f32, 64>>) {
+    // Opaque blocks of an alpha preset skip the modes that spend bits on separate alpha.
+    let alpha_modes = settings.channels == 3u || !(*state).opaque;
+
     if (settings.mode_selection[0] != 0u) {
         bc7_enc_mode02(state, block);
     }
     if (settings.mode_selection[1] != 0u) {
         bc7_enc_mode13(state,block);
-        bc7_enc_mode7(state, block);
+        if (alpha_modes) {
+            bc7_enc_mode7(state, block);
+        }
     }
-    if (settings.mode_selection[2] != 0u) {
+    if (settings.mode_selection[2] != 0u && alpha_modes) {
         bc7_enc_mode45(state, block);
     }
     if (settings.mode_selection[3] != 0u) {
         bc7_enc_mode6(state, block);
     }
+    if (settings.mode_selection[0] != 0u && settings.skip_mode2 == MODE2_SEARCH_ESTIMATE) {
+        bc7_enc_mode2_estimate(state, block);
+    }
 }
 
-fn compute_opaque_err(block: ptr<function, array<f32, 64>>) -> f32 {
+fn compute_opaque_err(state: ptr<function, State>, block: ptr<function, array<f32, 64>>) {
     if (settings.channels == 3u) {
-        return 0.0;
+        (*state).opaque_err = 0.0;
     } else {
         var err = 0.0;
         for (var k = 0u; k < 16u; k++) {
             err += sq((*block)[48u + k] - 255.0);
         }
-        return err;
+        (*state).opaque = err == 0.0;
+        (*state).opaque_err = settings.alpha_weight * err;
     }
 }
 
+// The first words of the blocks of the workgroup, which seed the blocks to their right.
+var<workgroup> seed_words: array<u32, 64>;
+
 @compute
 @workgroup_size(8, 8)
-fn compress_bc7(@builtin(global_invocation_id) global_id: vec3<u32>) {
+fn compress_bc7(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(local_invocation_id) local_id: vec3<u32>) {
     let xx = global_id.x;
     let yy = global_id.y;
 
     let block_width = (uniforms.width + 3u) / 4u;
     let block_height = (uniforms.height + 3u) / 4u;
 
-    if (xx >= block_width || yy >= block_height) {
-        return;
-    }
+    // All invocations need to reach the barrier, so the ones outside of the image only skip the
+    // work.
+    let in_image = xx < block_width && yy < block_height;
+    let seeded = settings.neighbor_seeding != 0u && (local_id.x & 1u) == 1u;
+    let local_index = local_id.y * 8u + local_id.x;
 
     var block: array<f32, 64>;
-
-    load_block_interleaved_rgba(&block, xx, yy);
-
     var state: State;
     state.best_err = 3.40282347e38;
-    state.opaque_err = compute_opaque_err(&block);
 
-    compress_block_bc7_core(&state, &block);
+    if (in_image) {
+        load_block_interleaved_rgba(&block, xx, yy);
+        compute_opaque_err(&state, &block);
+
+        if (!seeded) {
+            compress_block_bc7_core(&state, &block);
+            seed_words[local_index] = state.data[0];
+        }
+    }
+
+    workgroupBarrier();
+
+    if (!in_image) {
+        return;
+    }
+
+    if (seeded) {
+        compress_block_bc7_core_seeded(&state, &block, seed_words[local_index - 1u]);
+    }
 
     store_data(&state, block_width, xx, yy);
+
+    if (uniforms.write_errors != 0u) {
+        store_error(block_width, xx, yy, state.best_err);
+    }
 }
@@ -0,0 +1,150 @@
+// Copyright (c) 2025, Nils Hasenbanck
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of
+// the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT,
+// TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// Copies the texels of the source texture into rows of uncompressed RGBA8 texels. Every invocation
+// handles a 4x4 block, so the tasks are dispatched and split into bands like the compressed ones.
+
+struct Uniforms {
+    width: u32,
+    height: u32,
+    texture_y_offset: u32,
+    blocks_offset: u32,
+    errors_offset: u32,
+    write_errors: u32,
+    edge_fill: u32,
+    fill_color: u32,
+    normal_filter: u32,
+    normal_strength: f32,
+    normal_flip_y: u32,
+    dither_alpha: u32,
+    sdf: u32,
+    sdf_pivot: f32,
+    hdr_scale: f32,
+    hdr_bias: f32,
+    source_srgb: u32,
+    punch_through_alpha: u32,
+    texture_index: u32,
+}
+
+const EDGE_FILL_CLAMP_TO_EDGE: u32 = 0u;
+const EDGE_FILL_MIRROR: u32 = 1u;
+const EDGE_FILL_ZERO: u32 = 2u;
+const EDGE_FILL_SOLID_COLOR: u32 = 3u;
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> block_buffer: array<u32>;
+@group(0) @binding(2) var<uniform> uniforms: Uniforms;
+@group(0) @binding(4) var<storage, read_write> error_buffer: array<f32>;
+
+// The bounds checking variant of the shader replaces these two declarations and binds a
+// violation counter. It's selected with the `bounds_checks` feature.
+const BOUNDS_CHECKS: bool = false;
+fn report_bounds_violation() {}
+
+// Returns true if the block lies inside the task and all elements up to `end` are inside the
+// bound buffer of `length` elements. Violations are counted and the write needs to be skipped.
+fn in_bounds(xx: u32, yy: u32, end: u32, length: u32) -> bool {
+    if (!BOUNDS_CHECKS) {
+        return true;
+    }
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx < block_width && yy < block_height && end <= length) {
+        return true;
+    }
+
+    report_bounds_violation();
+    return false;
+}
+
+fn edge_coordinate(coordinate: u32, size: u32) -> u32 {
+    if (coordinate < size) {
+        return coordinate;
+    }
+    if (uniforms.edge_fill == EDGE_FILL_MIRROR) {
+        return u32(max(i32(size * 2u) - 1 - i32(coordinate), 0));
+    }
+    return size - 1u;
+}
+
+// Encodes the colors of sRGB views, which the hardware decoded to linear, back to sRGB.
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn load_texel(pixel_x: u32, pixel_y: u32) -> vec4<f32> {
+    let inside = pixel_x < uniforms.width && pixel_y < uniforms.height;
+
+    if (!inside && uniforms.edge_fill == EDGE_FILL_ZERO) {
+        return vec4<f32>(0.0);
+    }
+    if (!inside && uniforms.edge_fill == EDGE_FILL_SOLID_COLOR) {
+        return unpack4x8unorm(uniforms.fill_color);
+    }
+
+    let x = edge_coordinate(pixel_x, uniforms.width);
+    let y = edge_coordinate(pixel_y, uniforms.height) + uniforms.texture_y_offset;
+    let texel = textureLoad(source_texture, vec2<u32>(x, y), 0);
+
+    if (uniforms.source_srgb != 0u) {
+        return vec4<f32>(linear_to_srgb(texel.rgb), texel.a);
+    }
+    return texel;
+}
+
+@compute
+@workgroup_size(8, 8)
+fn copy_rgba8(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let xx = global_id.x;
+    let yy = global_id.y;
+
+    let block_width = (uniforms.width + 3u) / 4u;
+    let block_height = (uniforms.height + 3u) / 4u;
+
+    if (xx >= block_width || yy >= block_height) {
+        return;
+    }
+
+    // The rows of the output are padded to whole blocks.
+    let row_pitch = block_width * 4u;
+
+    for (var y = 0u; y < 4u; y++) {
+        let offset = uniforms.blocks_offset + (yy * 4u + y) * row_pitch + xx * 4u;
+
+        if (!in_bounds(xx, yy, offset + 4u, arrayLength(&block_buffer))) {
+            return;
+        }
+
+        for (var x = 0u; x < 4u; x++) {
+            block_buffer[offset + x] = pack4x8unorm(load_texel(xx * 4u + x, yy * 4u + y));
+        }
+    }
+
+    // The texels are copied without any loss.
+    if (uniforms.write_errors != 0u) {
+        let offset = uniforms.errors_offset + yy * block_width + xx;
+
+        if (!in_bounds(xx, yy, offset + 1u, arrayLength(&error_buffer))) {
+            return;
+        }
+
+        error_buffer[offset] = 0.0;
+    }
+}
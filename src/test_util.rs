@@ -0,0 +1,142 @@
+//! Round trip helpers shared by the unit tests of the encoders.
+
+#[cfg(feature = "bc6h")]
+use half::f16;
+
+#[cfg(any(feature = "bc15", feature = "bc7"))]
+use crate::encode::compress_rgba8;
+#[cfg(feature = "bc6h")]
+use crate::{decode::decompress_blocks_as_rgba32f, encode::compress_rgba16};
+use crate::{decode::decompress_blocks_as_rgba8, CompressionVariant, ImageView, ImageViewMut};
+
+/// Compresses tightly packed RGBA8 data.
+#[cfg(any(feature = "bc15", feature = "bc7"))]
+pub(crate) fn compress(
+    variant: CompressionVariant,
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+    compress_rgba8(
+        variant,
+        ImageView::packed(rgba_data, width, height),
+        &mut blocks,
+    );
+    blocks
+}
+
+/// Decodes blocks into tightly packed RGBA8 data.
+pub(crate) fn decode(
+    variant: CompressionVariant,
+    blocks: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut decoded = vec![0; width as usize * height as usize * 4];
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks,
+        ImageViewMut::packed(&mut decoded, width, height),
+    );
+    decoded
+}
+
+/// Returns the sum of the squared differences of all channels.
+#[cfg(any(feature = "bc15", feature = "bc7"))]
+pub(crate) fn squared_error(source: &[u8], decoded: &[u8]) -> f64 {
+    source
+        .iter()
+        .zip(decoded)
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum()
+}
+
+/// Returns the sum of the squared differences of all channels for every 4x4 block, in row-major
+/// block order.
+#[cfg(all(feature = "bc15", feature = "bc7"))]
+pub(crate) fn block_squared_errors(
+    source: &[u8],
+    decoded: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<f64> {
+    let (block_width, block_height) = (width as usize / 4, height as usize / 4);
+    let mut errors = vec![0.0; block_width * block_height];
+
+    for (index, (a, b)) in source
+        .chunks_exact(4)
+        .zip(decoded.chunks_exact(4))
+        .enumerate()
+    {
+        let (x, y) = (index % width as usize, index / width as usize);
+        errors[(y / 4) * block_width + x / 4] += squared_error(a, b);
+    }
+
+    errors
+}
+
+/// Returns the PSNR of all channels in dB. Identical data has a finite PSNR of 138 dB.
+#[cfg(feature = "bc7")]
+pub(crate) fn psnr(source: &[u8], decoded: &[u8]) -> f64 {
+    let mse = squared_error(source, decoded) / source.len() as f64;
+    10.0 * (255.0 * 255.0 / mse.max(1e-9)).log10()
+}
+
+/// Compresses and decodes tightly packed RGBA8 data and returns the PSNR of the result.
+#[cfg(feature = "bc7")]
+pub(crate) fn round_trip_psnr(
+    variant: CompressionVariant,
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+) -> f64 {
+    let blocks = compress(variant, rgba_data, width, height);
+    psnr(rgba_data, &decode(variant, &blocks, width, height))
+}
+
+/// Compresses tightly packed RGBA16F data.
+#[cfg(feature = "bc6h")]
+pub(crate) fn compress_hdr(
+    variant: CompressionVariant,
+    rgba_data: &[f16],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut blocks = vec![0; variant.blocks_byte_size(width, height)];
+    compress_rgba16(
+        variant,
+        ImageView::packed(rgba_data, width, height),
+        &mut blocks,
+    );
+    blocks
+}
+
+/// Decodes blocks into tightly packed RGBA32F data.
+#[cfg(feature = "bc6h")]
+pub(crate) fn decode_hdr(
+    variant: CompressionVariant,
+    blocks: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    let mut decoded = vec![0.0; width as usize * height as usize * 4];
+    decompress_blocks_as_rgba32f(
+        variant,
+        blocks,
+        ImageViewMut::packed(&mut decoded, width, height),
+    );
+    decoded
+}
+
+/// Returns the sum of the squared differences of the color channels. Alpha is ignored, since
+/// BC6H doesn't store it.
+#[cfg(feature = "bc6h")]
+pub(crate) fn hdr_squared_error(source: &[f16], decoded: &[f32]) -> f64 {
+    source
+        .chunks_exact(4)
+        .zip(decoded.chunks_exact(4))
+        .flat_map(|(a, b)| (0..3).map(move |channel| (a[channel], b[channel])))
+        .map(|(a, b)| (a.to_f64() - b as f64).powi(2))
+        .sum()
+}
@@ -0,0 +1,176 @@
+//! Deterministic synthetic test patterns.
+//!
+//! The patterns cover typical cases of block compression, like smooth gradients, hard edges,
+//! noise and varying alpha. They are generated on the fly, so integrations can be benchmarked and
+//! tested across all variants without shipping large image assets. The same pattern, size and
+//! seed always produce the same data.
+//!
+//! # Example
+//! ```
+//! use block_compression::{
+//!     encode::compress_rgba8, testgen::TestPattern, CompressionVariant, ImageView,
+//! };
+//!
+//! let variant = CompressionVariant::BC1;
+//! let mut blocks_buffer = vec![0u8; variant.blocks_byte_size(64, 64)];
+//!
+//! for pattern in TestPattern::all(42) {
+//!     let rgba_data = pattern.generate_rgba8(64, 64);
+//!     compress_rgba8(variant, ImageView::packed(&rgba_data, 64, 64), &mut blocks_buffer);
+//! }
+//! ```
+
+#[cfg(feature = "bc6h")]
+use half::f16;
+
+/// A synthetic test pattern.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TestPattern {
+    /// Red increases from left to right, green from top to bottom and blue along the diagonal.
+    /// Alpha is opaque.
+    Gradient,
+    /// Opaque black and white squares with a side length of `cell_size` pixels.
+    Checkerboard {
+        /// The side length of a square in pixels. A size of zero is treated as one.
+        cell_size: u32,
+    },
+    /// Uniformly distributed random values in all four channels.
+    Noise {
+        /// The seed of the random number generator.
+        seed: u64,
+    },
+    /// A color gradient with alpha increasing from fully transparent on the left to fully opaque
+    /// on the right.
+    AlphaRamp,
+}
+
+impl TestPattern {
+    /// Returns every pattern once, using the given seed for the noise pattern.
+    pub const fn all(seed: u64) -> [Self; 4] {
+        [
+            Self::Gradient,
+            Self::Checkerboard { cell_size: 4 },
+            Self::Noise { seed },
+            Self::AlphaRamp,
+        ]
+    }
+
+    /// Generates tightly packed RGBA8 data of the pattern.
+    pub fn generate_rgba8(&self, width: u32, height: u32) -> Vec<u8> {
+        let mut rgba_data = Vec::with_capacity(width as usize * height as usize * 4);
+        let mut rng = SplitMix64::new(match *self {
+            Self::Noise { seed } => seed,
+            _ => 0,
+        });
+
+        for y in 0..height {
+            for x in 0..width {
+                let texel = match *self {
+                    Self::Gradient => {
+                        let (red, green, blue) = gradient(x, y, width, height);
+                        [red, green, blue, 255]
+                    }
+                    Self::Checkerboard { cell_size } => {
+                        let cell_size = cell_size.max(1);
+                        let value = if (x / cell_size + y / cell_size) % 2 == 0 {
+                            0
+                        } else {
+                            255
+                        };
+                        [value, value, value, 255]
+                    }
+                    Self::Noise { .. } => rng.next_u64().to_le_bytes()[..4].try_into().unwrap(),
+                    Self::AlphaRamp => {
+                        let (red, green, blue) = gradient(x, y, width, height);
+                        [red, green, blue, ramp(x, width)]
+                    }
+                };
+
+                rgba_data.extend_from_slice(&texel);
+            }
+        }
+
+        rgba_data
+    }
+
+    /// Generates tightly packed RGBA16F data of the pattern.
+    ///
+    /// The color channels of the RGBA8 pattern are scaled to the range `[0, scale]`, which allows
+    /// generating HDR data for BC6H. Alpha is mapped to `[0, 1]`.
+    #[cfg(feature = "bc6h")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bc6h")))]
+    pub fn generate_rgba16f(&self, width: u32, height: u32, scale: f32) -> Vec<f16> {
+        self.generate_rgba8(width, height)
+            .chunks_exact(4)
+            .flat_map(|texel| {
+                let color = |value: u8| f16::from_f32(value as f32 / 255.0 * scale);
+                [
+                    color(texel[0]),
+                    color(texel[1]),
+                    color(texel[2]),
+                    f16::from_f32(texel[3] as f32 / 255.0),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn ramp(position: u32, size: u32) -> u8 {
+    if size <= 1 {
+        return 0;
+    }
+    (position as u64 * 255 / (size as u64 - 1)) as u8
+}
+
+fn gradient(x: u32, y: u32, width: u32, height: u32) -> (u8, u8, u8) {
+    let red = ramp(x, width);
+    let green = ramp(y, height);
+    let blue = ((red as u32 + green as u32) / 2) as u8;
+    (red, green, blue)
+}
+
+/// A small and fast PRNG, so the generated noise is the same on all platforms.
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_are_deterministic() {
+        for pattern in TestPattern::all(7) {
+            let rgba_data = pattern.generate_rgba8(16, 8);
+            assert_eq!(rgba_data.len(), 16 * 8 * 4);
+            assert_eq!(rgba_data, pattern.generate_rgba8(16, 8));
+        }
+
+        let first = TestPattern::Noise { seed: 1 }.generate_rgba8(8, 8);
+        let second = TestPattern::Noise { seed: 2 }.generate_rgba8(8, 8);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_split_mix_64_sequence() {
+        // The first outputs of the reference implementation for seed 0.
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220A8397B1DCDAF);
+        assert_eq!(rng.next_u64(), 0x6E789E6AA1B965F4);
+        assert_eq!(rng.next_u64(), 0x06C45D188009454F);
+    }
+}
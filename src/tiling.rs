@@ -0,0 +1,284 @@
+//! Helpers to split a texture into tiles for partial uploads, compressions and readbacks.
+//!
+//! Large textures are often not compressed in one go. Streaming systems upload and compress
+//! them tile by tile, and editors only re-compress the tiles that changed. [`tiles()`] iterates
+//! the tiles of a texture together with the blocks they cover and where these blocks are in the
+//! blocks buffer of the whole texture, which is laid out in rows of blocks like the output of
+//! all compressors of this crate.
+//!
+//! Tiles that span the whole width of the texture are contiguous in the blocks buffer. They are
+//! the bands the GPU compressor works with: passing [`Tile::rect`]'s `y` as `texture_y_offset` and
+//! [`Tile::blocks_offset()`] as `blocks_offset` to
+//! [`GpuBlockCompressor::add_compression_task()`] compresses the tile in place. Narrower tiles
+//! are scattered over several block rows, which [`Tile::row_byte_ranges()`] returns.
+//!
+//! The texels of [`CompressionVariant::RGBA8`] are stored in rows of texels instead of blocks, so
+//! the byte ranges of its tiles cover rows of texels.
+//!
+//! # Example
+//! ```
+//! use block_compression::{tiling::tiles, CompressionVariant};
+//!
+//! let tiles: Vec<_> = tiles(CompressionVariant::BC1, 1000, 600, 512, 512).collect();
+//!
+//! assert_eq!(tiles.len(), 4);
+//! assert_eq!(tiles[3].rect.width, 488);
+//! assert_eq!(tiles[3].rect.height, 88);
+//! assert_eq!(tiles[3].row_byte_ranges().count(), 22);
+//! ```
+//!
+//! [`GpuBlockCompressor::add_compression_task()`]: crate::GpuBlockCompressor::add_compression_task
+
+use std::ops::Range;
+
+use crate::{ByteOffset, CompressionVariant};
+
+/// A rectangle of pixels or blocks.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rect {
+    /// The left edge.
+    pub x: u32,
+    /// The top edge.
+    pub y: u32,
+    /// The width.
+    pub width: u32,
+    /// The height.
+    pub height: u32,
+}
+
+/// A tile of a texture, returned by [`tiles()`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Tile {
+    /// The pixels of the tile. Tiles at the right and bottom edge are clipped to the texture.
+    pub rect: Rect,
+    /// The blocks of the tile, in blocks of the texture.
+    pub blocks: Rect,
+    /// The bytes in the blocks buffer of the texture from the first block of the tile to the
+    /// end of its last block. Unless the tile is [contiguous](Tile::is_contiguous), the range
+    /// also covers blocks of the tiles to its left and right.
+    pub byte_range: Range<u64>,
+    layout: RowLayout,
+}
+
+/// How the blocks of a texture are stored in rows of its blocks buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct RowLayout {
+    /// The distance in bytes between two stored rows.
+    row_pitch: u64,
+    /// The number of stored rows of every block row. Only uncompressed texels spread a block
+    /// over several rows.
+    rows_per_block: u32,
+    /// The bytes a block takes up in each of its stored rows.
+    block_row_size: u64,
+}
+
+impl Tile {
+    /// Returns `true` if the blocks of the tile are stored in one piece in the blocks buffer of
+    /// the texture, which is the case for tiles that span the whole width of the texture or
+    /// only cover a single block row of a compressed variant.
+    pub fn is_contiguous(&self) -> bool {
+        let layout = self.layout;
+        self.blocks.height * layout.rows_per_block <= 1
+            || self.blocks.width as u64 * layout.block_row_size == layout.row_pitch
+    }
+
+    /// Returns the offset of the first block of the tile in the blocks buffer of the texture.
+    pub fn blocks_offset(&self) -> ByteOffset {
+        ByteOffset(self.byte_range.start)
+    }
+
+    /// Returns the number of blocks of the tile.
+    pub fn block_count(&self) -> u32 {
+        self.blocks.width * self.blocks.height
+    }
+
+    /// Returns the bytes of every block row of the tile in the blocks buffer of the texture,
+    /// from top to bottom. For [`CompressionVariant::RGBA8`], these are the rows of texels.
+    ///
+    /// The blocks of a tile that was compressed on its own are stored in the same order, so
+    /// its block rows can be copied one after the other into these ranges.
+    pub fn row_byte_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        let layout = self.layout;
+        let row_size = self.blocks.width as u64 * layout.block_row_size;
+        let row_count = self.blocks.height as u64 * layout.rows_per_block as u64;
+        (0..row_count).map(move |row| {
+            let start = self.byte_range.start + row * layout.row_pitch;
+            start..start + row_size
+        })
+    }
+}
+
+/// Iterates the tiles of a texture in rows from top to bottom and left to right.
+///
+/// Every tile is `tile_width` by `tile_height` pixels large, except for the tiles at the right
+/// and bottom edge, which are clipped to the texture. A texture without pixels has no tiles.
+///
+/// # Panics
+/// - If `tile_width` or `tile_height` is zero or not a multiple of 4, since tiles need to start
+///   at a block boundary
+pub fn tiles(
+    variant: CompressionVariant,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+) -> Tiles {
+    assert!(
+        tile_width != 0 && tile_width % 4 == 0,
+        "the tile width needs to be a non-zero multiple of 4"
+    );
+    assert!(
+        tile_height != 0 && tile_height % 4 == 0,
+        "the tile height needs to be a non-zero multiple of 4"
+    );
+
+    let rows_per_block = match variant {
+        CompressionVariant::RGBA8 => 4,
+        _ => 1,
+    };
+
+    Tiles {
+        width,
+        height,
+        tile_width,
+        tile_height,
+        columns: width.div_ceil(tile_width),
+        rows: height.div_ceil(tile_height),
+        next: 0,
+        layout: RowLayout {
+            row_pitch: variant.blocks_byte_size(width, 4) as u64 / rows_per_block as u64,
+            rows_per_block,
+            block_row_size: (variant.block_byte_size() / rows_per_block) as u64,
+        },
+    }
+}
+
+/// The iterator of [`tiles()`].
+#[derive(Clone, Debug)]
+pub struct Tiles {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    rows: u32,
+    next: u32,
+    layout: RowLayout,
+}
+
+impl Iterator for Tiles {
+    type Item = Tile;
+
+    fn next(&mut self) -> Option<Tile> {
+        if self.next >= self.columns * self.rows {
+            return None;
+        }
+
+        let (column, row) = (self.next % self.columns, self.next / self.columns);
+        self.next += 1;
+
+        let x = column * self.tile_width;
+        let y = row * self.tile_height;
+        let rect = Rect {
+            x,
+            y,
+            width: self.tile_width.min(self.width - x),
+            height: self.tile_height.min(self.height - y),
+        };
+        let blocks = Rect {
+            x: x / 4,
+            y: y / 4,
+            width: rect.width.div_ceil(4),
+            height: rect.height.div_ceil(4),
+        };
+
+        let layout = self.layout;
+        let first_row = (blocks.y * layout.rows_per_block) as u64;
+        let last_row = ((blocks.y + blocks.height) * layout.rows_per_block - 1) as u64;
+        let start = first_row * layout.row_pitch + blocks.x as u64 * layout.block_row_size;
+        let end =
+            last_row * layout.row_pitch + (blocks.x + blocks.width) as u64 * layout.block_row_size;
+
+        Some(Tile {
+            rect,
+            blocks,
+            byte_range: start..end,
+            layout,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.columns * self.rows).saturating_sub(self.next) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Tiles {}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+    use crate::{
+        encode::compress_rgba8_padded, testgen::TestPattern, BlockCoord, EdgeFill, ImageView,
+    };
+
+    #[test]
+    fn test_tiles_cover_texture() {
+        for variant in [CompressionVariant::BC1, CompressionVariant::RGBA8] {
+            check_tiles_cover_texture(variant);
+        }
+    }
+
+    fn check_tiles_cover_texture(variant: CompressionVariant) {
+        let (width, height) = (70, 37);
+        let rgba_data = TestPattern::AlphaRamp.generate_rgba8(width, height);
+        let image = ImageView::packed(&rgba_data, width, height);
+
+        let mut expected = vec![0; variant.blocks_byte_size(width, height)];
+        compress_rgba8_padded(variant, image, EdgeFill::ClampToEdge, &mut expected);
+
+        for (tile_width, tile_height) in [(16, 8), (72, 12), (4, 40)] {
+            let tiles = tiles(variant, width, height, tile_width, tile_height);
+            assert_eq!(
+                tiles.len(),
+                (width.div_ceil(tile_width) * height.div_ceil(tile_height)) as usize
+            );
+
+            let mut blocks = vec![0; expected.len()];
+            for tile in tiles {
+                assert_eq!(
+                    tile.blocks_offset(),
+                    BlockCoord::new(tile.blocks.x, tile.blocks.y).byte_offset(variant, width)
+                );
+
+                let Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = tile.rect;
+                let mut tile_blocks = vec![0; variant.blocks_byte_size(width, height)];
+                compress_rgba8_padded(
+                    variant,
+                    image.crop(x, y, width, height),
+                    EdgeFill::ClampToEdge,
+                    &mut tile_blocks,
+                );
+
+                let single_row = height <= 4 && variant != CompressionVariant::RGBA8;
+                assert_eq!(tile.is_contiguous(), tile_width >= 72 || single_row);
+                if tile.is_contiguous() {
+                    let range = tile.byte_range.start as usize..tile.byte_range.end as usize;
+                    assert_eq!(range.len(), tile_blocks.len());
+                }
+
+                let row_size = tile_blocks.len() / tile.row_byte_ranges().count();
+                for (range, row) in tile.row_byte_ranges().zip(tile_blocks.chunks(row_size)) {
+                    blocks[range.start as usize..range.end as usize].copy_from_slice(row);
+                }
+            }
+
+            assert_eq!(blocks, expected, "{variant:?} {tile_width}x{tile_height}");
+        }
+    }
+}
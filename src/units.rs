@@ -0,0 +1,211 @@
+//! Typed units for the positions of blocks and the offsets into blocks buffers.
+//!
+//! Pixel coordinates, block coordinates and byte offsets are all plain integers, and mixing
+//! them up is a common bug: a pixel row passed as a block row lands four times too far down, and
+//! a block index passed as a byte offset overwrites the first blocks of the buffer. The types of
+//! this module name their unit, and the conversions between them take the block size of the
+//! [`CompressionVariant`] into account.
+//!
+//! The byte offsets follow the layout of the blocks buffers of this crate: rows of blocks, and
+//! rows of texels for [`CompressionVariant::RGBA8`].
+//!
+//! # Example
+//! ```
+//! use block_compression::{BlockCoord, BlockIndex, ByteOffset, CompressionVariant};
+//!
+//! let variant = CompressionVariant::BC1;
+//! let block = BlockCoord::from_pixel(100, 36);
+//!
+//! assert_eq!(block, BlockCoord::new(25, 9));
+//! assert_eq!(block.index(256), BlockIndex(9 * 64 + 25));
+//! assert_eq!(block.byte_offset(variant, 256), ByteOffset((9 * 64 + 25) * 8));
+//! assert_eq!(ByteOffset(4808).block(variant, 256), Some(block));
+//! ```
+
+use crate::CompressionVariant;
+
+/// The position of a block in the grid of blocks of a texture, in blocks instead of pixels.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BlockCoord {
+    /// The column of the block.
+    pub x: u32,
+    /// The row of the block.
+    pub y: u32,
+}
+
+impl BlockCoord {
+    /// Creates a block coordinate from a column and row of blocks.
+    pub const fn new(x: u32, y: u32) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns the block that contains the pixel.
+    pub const fn from_pixel(x: u32, y: u32) -> Self {
+        Self { x: x / 4, y: y / 4 }
+    }
+
+    /// Returns the pixel in the top left corner of the block.
+    pub const fn to_pixel(self) -> (u32, u32) {
+        (self.x * 4, self.y * 4)
+    }
+
+    /// Returns the index of the block in the rows of blocks of a texture that is `width`
+    /// pixels wide.
+    pub const fn index(self, width: u32) -> BlockIndex {
+        BlockIndex(self.y as u64 * block_columns(width) + self.x as u64)
+    }
+
+    /// Returns the offset of the block in the blocks buffer of a texture that is `width` pixels
+    /// wide. For [`CompressionVariant::RGBA8`], this is the offset of its first row of texels.
+    pub const fn byte_offset(self, variant: CompressionVariant, width: u32) -> ByteOffset {
+        let layout = RowLayout::new(variant, width);
+        ByteOffset(
+            self.y as u64 * layout.rows_per_block * layout.row_pitch
+                + self.x as u64 * layout.block_row_size,
+        )
+    }
+}
+
+/// The index of a block in the rows of blocks of a texture.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BlockIndex(pub u64);
+
+impl BlockIndex {
+    /// Returns the position of the block in a texture that is `width` pixels wide.
+    pub const fn coord(self, width: u32) -> BlockCoord {
+        let columns = block_columns(width);
+        BlockCoord {
+            x: (self.0 % columns) as u32,
+            y: (self.0 / columns) as u32,
+        }
+    }
+
+    /// Returns the offset of the block in the blocks buffer of a texture that is `width` pixels
+    /// wide. For [`CompressionVariant::RGBA8`], this is the offset of its first row of texels.
+    pub const fn byte_offset(self, variant: CompressionVariant, width: u32) -> ByteOffset {
+        self.coord(width).byte_offset(variant, width)
+    }
+}
+
+impl From<u64> for BlockIndex {
+    fn from(index: u64) -> Self {
+        Self(index)
+    }
+}
+
+/// An offset in bytes into a blocks buffer.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteOffset(pub u64);
+
+impl ByteOffset {
+    /// Returns the block that starts at the offset in the blocks buffer of a texture that is
+    /// `width` pixels wide, or `None` if no block starts at the offset.
+    pub const fn block(self, variant: CompressionVariant, width: u32) -> Option<BlockCoord> {
+        let layout = RowLayout::new(variant, width);
+        let block_row_pitch = layout.rows_per_block * layout.row_pitch;
+        let offset_in_row = self.0 % block_row_pitch;
+
+        if offset_in_row >= layout.row_pitch || offset_in_row % layout.block_row_size != 0 {
+            return None;
+        }
+
+        Some(BlockCoord {
+            x: (offset_in_row / layout.block_row_size) as u32,
+            y: (self.0 / block_row_pitch) as u32,
+        })
+    }
+}
+
+impl From<u64> for ByteOffset {
+    fn from(offset: u64) -> Self {
+        Self(offset)
+    }
+}
+
+impl From<ByteOffset> for u64 {
+    fn from(offset: ByteOffset) -> Self {
+        offset.0
+    }
+}
+
+/// Returns the number of block columns of a texture that is `width` pixels wide.
+const fn block_columns(width: u32) -> u64 {
+    let columns = width.div_ceil(4) as u64;
+    if columns == 0 {
+        1
+    } else {
+        columns
+    }
+}
+
+/// How the blocks of a texture are stored in rows of its blocks buffer.
+struct RowLayout {
+    /// The distance in bytes between two stored rows.
+    row_pitch: u64,
+    /// The number of stored rows of every block row.
+    rows_per_block: u64,
+    /// The bytes a block takes up in each of its stored rows.
+    block_row_size: u64,
+}
+
+impl RowLayout {
+    const fn new(variant: CompressionVariant, width: u32) -> Self {
+        let rows_per_block = match variant {
+            CompressionVariant::RGBA8 => 4,
+            _ => 1,
+        };
+        let block_row_size = (variant.block_byte_size() / rows_per_block) as u64;
+
+        Self {
+            row_pitch: block_columns(width) * block_row_size,
+            rows_per_block: rows_per_block as u64,
+            block_row_size,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "bc15"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_roundtrip() {
+        let width = 70;
+        for variant in [
+            CompressionVariant::BC1,
+            CompressionVariant::BC3,
+            CompressionVariant::RGBA8,
+        ] {
+            let mut previous = None;
+            for y in 0..4 {
+                for x in 0..18 {
+                    let block = BlockCoord::new(x, y);
+                    let index = block.index(width);
+                    let offset = block.byte_offset(variant, width);
+
+                    assert_eq!(index.coord(width), block);
+                    assert_eq!(index.byte_offset(variant, width), offset);
+                    assert_eq!(offset.block(variant, width), Some(block));
+                    assert!(previous < Some(offset), "{variant:?} {block:?}");
+                    previous = Some(offset);
+                }
+            }
+
+            // The offsets match the layout of the encoders.
+            let first_of_row = BlockCoord::new(0, 1).byte_offset(variant, width);
+            assert_eq!(
+                first_of_row.0,
+                variant.blocks_byte_size(width, 4) as u64,
+                "{variant:?}"
+            );
+        }
+
+        assert_eq!(BlockCoord::from_pixel(7, 12).to_pixel(), (4, 12));
+        assert_eq!(ByteOffset(12).block(CompressionVariant::BC1, width), None);
+        // The second texel row of the first RGBA8 block.
+        assert_eq!(
+            ByteOffset(18 * 16).block(CompressionVariant::RGBA8, width),
+            None
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+use wgpu::{Device, Error, ErrorFilter};
+
+use crate::{CompressorError, GpuBlockCompressor};
+
+/// Errors of [`GpuBlockCompressor::compress_validated()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GpuError {
+    /// The compressor couldn't record its work, see [`CompressorError`].
+    Compressor(CompressorError),
+    /// The recorded work failed the validation of wgpu, for example because a destination
+    /// buffer is too small or misses a usage.
+    Validation {
+        /// The description of wgpu, which names the labels of the affected resources.
+        description: String,
+    },
+    /// wgpu or the driver ran out of memory.
+    OutOfMemory {
+        /// The description of wgpu.
+        description: String,
+    },
+    /// An internal error of wgpu or the driver.
+    Internal {
+        /// The description of wgpu.
+        description: String,
+    },
+}
+
+impl GpuError {
+    fn from_wgpu(error: Error) -> Self {
+        match error {
+            Error::Validation { description, .. } => Self::Validation { description },
+            Error::OutOfMemory { source } => Self::OutOfMemory {
+                description: source.to_string(),
+            },
+            Error::Internal { description, .. } => Self::Internal { description },
+        }
+    }
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compressor(error) => error.fmt(f),
+            Self::Validation { description } => {
+                write!(f, "validation error while compressing: {description}")
+            }
+            Self::OutOfMemory { description } => {
+                write!(f, "out of memory while compressing: {description}")
+            }
+            Self::Internal { description } => {
+                write!(f, "internal error while compressing: {description}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Compressor(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<CompressorError> for GpuError {
+    fn from(error: CompressorError) -> Self {
+        Self::Compressor(error)
+    }
+}
+
+impl GpuBlockCompressor {
+    /// Records the work of the compressor inside error scopes and returns the GPU errors it
+    /// raised instead of passing them to the uncaptured error handler of the device.
+    ///
+    /// `pass_builder` begins the compute passes and calls [`GpuBlockCompressor::compress()`],
+    /// [`GpuBlockCompressor::compress_chunk()`] or [`GpuBlockCompressor::compress_split()`].
+    /// wgpu validates the commands of a compute pass when the pass ends, so the passes need to
+    /// end before `pass_builder` returns. Errors are attributed to the compressor instead of
+    /// panicking in a global handler, so a tool can report a broken texture or destination
+    /// buffer and carry on with the next one.
+    ///
+    /// Error scopes capture all errors of the device, so errors of other threads that use the
+    /// device at the same time are returned as well. Only available on native platforms, where
+    /// wgpu resolves error scopes right away.
+    ///
+    /// # Arguments
+    /// * `pass_builder` - Records the compute passes of the compressor
+    ///
+    /// # Errors
+    /// - [`GpuError::Compressor`] if `pass_builder` returned an error
+    /// - [`GpuError::Validation`], [`GpuError::OutOfMemory`] or [`GpuError::Internal`] if wgpu
+    ///   raised an error. The command encoder of the passes is invalid and must not be submitted.
+    ///   The completion callbacks of the tasks recorded by `pass_builder` are dropped without
+    ///   being called.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    ///
+    /// let result = compressor.compress_validated(|compressor| {
+    ///     let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+    ///     compressor.compress(&mut pass)
+    /// });
+    ///
+    /// match result {
+    ///     Ok(()) => queue.submit([encoder.finish()]),
+    ///     Err(error) => eprintln!("skipping texture: {error}"),
+    /// }
+    /// ```
+    pub fn compress_validated<T>(
+        &mut self,
+        pass_builder: impl FnOnce(&mut Self) -> Result<T, CompressorError>,
+    ) -> Result<T, GpuError> {
+        let device = self.device().clone();
+        let pending_completions = self.pending_completions();
+
+        device.push_error_scope(ErrorFilter::Internal);
+        device.push_error_scope(ErrorFilter::OutOfMemory);
+        device.push_error_scope(ErrorFilter::Validation);
+
+        let result = pass_builder(self);
+
+        // All scopes need to be popped, even if an earlier one captured an error.
+        let errors = [
+            pop_error_scope(&device),
+            pop_error_scope(&device),
+            pop_error_scope(&device),
+        ];
+
+        let value = result?;
+        match errors.into_iter().flatten().next() {
+            Some(error) => {
+                self.truncate_completions(pending_completions);
+                Err(GpuError::from_wgpu(error))
+            }
+            None => Ok(value),
+        }
+    }
+}
+
+struct NoopWake;
+
+impl Wake for NoopWake {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Pops an error scope of a native device, which resolves on the first poll.
+fn pop_error_scope(device: &Device) -> Option<Error> {
+    let waker = Waker::from(Arc::new(NoopWake));
+    let mut future = pin!(device.pop_error_scope());
+    match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(error) => error,
+        Poll::Pending => unreachable!("error scopes of native devices resolve immediately"),
+    }
+}
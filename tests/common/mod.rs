@@ -1,6 +1,8 @@
 use std::sync::{Arc, LazyLock};
 
-use block_compression::CompressionVariant;
+use block_compression::{
+    bytemuck::cast_slice, color::rgba8_srgb_to_linear_f16, CompressionVariant,
+};
 use half::f16;
 use image::ImageReader;
 use pollster::block_on;
@@ -13,34 +15,28 @@ use wgpu::{
     TextureDimension, TextureFormat, TextureUsages,
 };
 
-#[inline]
-pub fn srgb_to_linear(srgb: u8) -> f64 {
-    let v = (srgb as f64) / 255.0;
-    if v <= 0.04045 {
-        v / 12.92
-    } else {
-        ((v + 0.055) / 1.055).powf(2.4)
-    }
-}
-
 pub const BRICK_FILE_PATH: &str = "tests/images/brick.png";
 pub const MARBLE_FILE_PATH: &str = "tests/images/marble.png";
 
+pub fn create_instance() -> Instance {
+    Instance::new(&InstanceDescriptor {
+        backends: Backends::from_env().unwrap_or_default(),
+        flags: InstanceFlags::from_build_config().with_env(),
+        backend_options: BackendOptions {
+            gl: GlBackendOptions {
+                gles_minor_version: Gles3MinorVersion::Version1,
+            },
+            dx12: Dx12BackendOptions {
+                shader_compiler: Dx12Compiler::StaticDxc,
+            }
+            .with_env(),
+        },
+    })
+}
+
 pub fn create_wgpu_resources() -> (Arc<Device>, Arc<Queue>) {
     static CACHE: LazyLock<(Arc<Device>, Arc<Queue>)> = LazyLock::new(|| {
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::from_env().unwrap_or_default(),
-            flags: InstanceFlags::from_build_config().with_env(),
-            backend_options: BackendOptions {
-                gl: GlBackendOptions {
-                    gles_minor_version: Gles3MinorVersion::Version1,
-                },
-                dx12: Dx12BackendOptions {
-                    shader_compiler: Dx12Compiler::StaticDxc,
-                }
-                .with_env(),
-            },
-        });
+        let instance = create_instance();
 
         let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: PowerPreference::HighPerformance,
@@ -99,10 +95,8 @@ pub fn read_image_and_create_texture(
     let height = rgba_image.height();
 
     let texture = if matches!(variant, CompressionVariant::BC6H(..)) {
-        let rgba_f16_data: Vec<u8> = rgba_image
-            .iter()
-            .flat_map(|color| f16::from_f64(srgb_to_linear(*color)).to_ne_bytes())
-            .collect();
+        let mut rgba_f16_data = vec![f16::ZERO; rgba_image.len()];
+        rgba8_srgb_to_linear_f16(&rgba_image, &mut rgba_f16_data);
 
         device.create_texture_with_data(
             queue,
@@ -121,7 +115,7 @@ pub fn read_image_and_create_texture(
                 view_formats: &[],
             },
             TextureDataOrder::LayerMajor,
-            rgba_f16_data.as_slice(),
+            cast_slice(&rgba_f16_data),
         )
     } else {
         device.create_texture_with_data(
@@ -1,12 +1,13 @@
 use block_compression::{
-    decode::decompress_blocks_as_rgba8, encode::compress_rgba8, BC6HSettings, BC7Settings,
-    CompressionVariant, GpuBlockCompressor,
+    color::srgb8_to_linear, decode::decompress_blocks_as_rgba8, encode::compress_rgba8,
+    BC6HSettings, BC7Settings, CompressionVariant, GpuBlockCompressor, ImageView, ImageViewMut,
+    TaskOptions,
 };
 use wgpu::{CommandEncoderDescriptor, ComputePassDescriptor, TextureViewDescriptor};
 
 use self::common::{
     create_blocks_buffer, create_wgpu_resources, download_blocks_data,
-    read_image_and_create_texture, srgb_to_linear, BRICK_FILE_PATH, MARBLE_FILE_PATH,
+    read_image_and_create_texture, BRICK_FILE_PATH, MARBLE_FILE_PATH,
 };
 
 mod common;
@@ -57,13 +58,13 @@ pub fn calculate_image_metrics_rgba8(
     for index in (0..original.len()).step_by(4) {
         for channel in 0..channels as usize {
             let orig = if channel < 3 {
-                srgb_to_linear(original[index + channel])
+                srgb8_to_linear(original[index + channel]) as f64
             } else {
                 (original[index + channel] as f64) / 255.0
             };
 
             let comp = if channel < 3 {
-                srgb_to_linear(compressed[index + channel])
+                srgb8_to_linear(compressed[index + channel]) as f64
             } else {
                 (compressed[index + channel] as f64) / 255.0
             };
@@ -145,7 +146,7 @@ fn compress_image_reference(
 ) -> Vec<u8> {
     let output_size = variant.blocks_byte_size(width, height);
     let mut blocks = vec![0; output_size];
-    compress_rgba8(variant, data, &mut blocks, width, height, width * 4);
+    compress_rgba8(variant, ImageView::packed(data, width, height), &mut blocks);
     blocks
 }
 
@@ -167,6 +168,7 @@ fn compress_image(image_path: &str, variant: CompressionVariant) -> (u32, u32, V
         &blocks,
         None,
         None,
+        TaskOptions::default(),
     );
 
     let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
@@ -179,7 +181,7 @@ fn compress_image(image_path: &str, variant: CompressionVariant) -> (u32, u32, V
             timestamp_writes: None,
         });
 
-        block_compressor.compress(&mut pass);
+        block_compressor.compress(&mut pass).unwrap();
     }
 
     queue.submit([encoder.finish()]);
@@ -205,7 +207,11 @@ fn calculate_psnr(
     let size = width * height * 4;
 
     let mut decompressed_data = vec![0; size as usize];
-    decompress_blocks_as_rgba8(variant, width, height, blocks_data, &mut decompressed_data);
+    decompress_blocks_as_rgba8(
+        variant,
+        blocks_data,
+        ImageViewMut::packed(&mut decompressed_data, width, height),
+    );
 
     calculate_image_metrics_rgba8(original_data, &decompressed_data, width, height, channels)
 }
@@ -1,9 +1,23 @@
 use block_compression::*;
-use wgpu::{CommandEncoderDescriptor, ComputePassDescriptor, TextureViewDescriptor};
+use pollster::block_on;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc, Arc,
+};
+
+use wgpu::{
+    util::{DeviceExt, TextureDataOrder},
+    Backends, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferDescriptor,
+    BufferUsages, Color, CommandEncoderDescriptor, ComputePassDescriptor,
+    ComputePipelineDescriptor, Device, DeviceDescriptor, Extent3d, Features, Limits, LoadOp,
+    Maintain, Operations, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RequestAdapterOptions, ShaderModuleDescriptor, ShaderSource, StoreOp, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
+};
 
 use crate::common::{
-    create_blocks_buffer, create_wgpu_resources, download_blocks_data,
-    read_image_and_create_texture, BRICK_FILE_PATH, MARBLE_FILE_PATH,
+    create_blocks_buffer, create_instance, create_wgpu_resources, download_blocks_data,
+    error_handler, read_image_and_create_texture, BRICK_FILE_PATH, MARBLE_FILE_PATH,
 };
 
 mod common;
@@ -38,6 +52,7 @@ fn test_multi_task_compression(variant: CompressionVariant) {
         &blocks,
         None,
         None,
+        TaskOptions::default(),
     );
     block_compressor.add_compression_task(
         variant,
@@ -46,7 +61,8 @@ fn test_multi_task_compression(variant: CompressionVariant) {
         brick_half_height,
         &blocks,
         Some(brick_half_height),
-        Some(bricks_half_size as u32),
+        Some(ByteOffset(bricks_half_size as u64)),
+        TaskOptions::default(),
     );
 
     block_compressor.add_compression_task(
@@ -56,7 +72,11 @@ fn test_multi_task_compression(variant: CompressionVariant) {
         marble_half_height,
         &blocks,
         None,
-        Some((bricks_half_size * 2) as u32),
+        Some(ByteOffset((bricks_half_size * 2) as u64)),
+        TaskOptions {
+            label: Some("marble top half"),
+            ..Default::default()
+        },
     );
     block_compressor.add_compression_task(
         variant,
@@ -65,7 +85,8 @@ fn test_multi_task_compression(variant: CompressionVariant) {
         marble_half_height,
         &blocks,
         Some(marble_half_height),
-        Some((bricks_half_size * 2 + marble_half_size) as u32),
+        Some(ByteOffset((bricks_half_size * 2 + marble_half_size) as u64)),
+        TaskOptions::default(),
     );
 
     let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
@@ -78,7 +99,7 @@ fn test_multi_task_compression(variant: CompressionVariant) {
             timestamp_writes: None,
         });
 
-        block_compressor.compress(&mut pass);
+        block_compressor.compress(&mut pass).unwrap();
     }
 
     queue.submit([encoder.finish()]);
@@ -103,6 +124,9 @@ fn test_multi_task_compression(variant: CompressionVariant) {
     assert!(brick_second_half_not_empty, "Brick second half is empty");
     assert!(marble_first_half_not_empty, "Marble first half is empty");
     assert!(marble_second_half_not_empty, "Marble second half is empty");
+
+    #[cfg(feature = "bounds_checks")]
+    assert_eq!(block_compressor.bounds_violations(), 0);
 }
 
 #[test]
@@ -130,6 +154,108 @@ fn multi_task_compression_bc5() {
     test_multi_task_compression(CompressionVariant::BC5);
 }
 
+/// Compresses the lower half of the brick texture once through `texture_y_offset` and once from
+/// a texture that only holds the lower half. Both need to produce the same blocks.
+fn test_texture_y_offset(variant: CompressionVariant) {
+    use wgpu::{
+        util::{DeviceExt, TextureDataOrder},
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    };
+
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, rgba_data) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let width = brick_texture.width();
+    let half_height = (brick_texture.height() / 2) & !3;
+
+    let row_size = width as usize * 4;
+    let lower_half =
+        &rgba_data[half_height as usize * row_size..][..half_height as usize * row_size];
+    let lower_texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("lower half"),
+            size: Extent3d {
+                width,
+                height: half_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        lower_half,
+    );
+
+    let half_size = variant.blocks_byte_size(width, half_height);
+    let blocks = create_blocks_buffer(&device, (half_size * 2) as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &brick_texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        half_height,
+        &blocks,
+        Some(half_height),
+        None,
+        TaskOptions::default(),
+    );
+    block_compressor.add_compression_task(
+        variant,
+        &lower_texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        half_height,
+        &blocks,
+        None,
+        Some(ByteOffset(half_size as u64)),
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("compute pass"),
+            timestamp_writes: None,
+        });
+
+        block_compressor.compress(&mut pass).unwrap();
+    }
+
+    queue.submit([encoder.finish()]);
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+    assert_eq!(blocks_data[..half_size], blocks_data[half_size..]);
+}
+
+#[test]
+fn texture_y_offset_bc1() {
+    test_texture_y_offset(CompressionVariant::BC1);
+}
+
+#[test]
+fn texture_y_offset_bc3() {
+    test_texture_y_offset(CompressionVariant::BC3);
+}
+
+#[test]
+fn texture_y_offset_bc4() {
+    test_texture_y_offset(CompressionVariant::BC4);
+}
+
+#[test]
+fn texture_y_offset_bc5() {
+    test_texture_y_offset(CompressionVariant::BC5);
+}
+
 #[test]
 fn multi_task_compression_bc6h() {
     test_multi_task_compression(CompressionVariant::BC6H(BC6HSettings::very_fast()));
@@ -139,3 +265,2760 @@ fn multi_task_compression_bc6h() {
 fn multi_task_compression_bc7() {
     test_multi_task_compression(CompressionVariant::BC7(BC7Settings::opaque_ultra_fast()));
 }
+
+#[test]
+fn multi_task_completion_callbacks() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    let brick_half_height = (brick_texture.height() / 2) & !3;
+    let bricks_half_size = variant.blocks_byte_size(brick_texture.width(), brick_half_height);
+
+    let blocks = create_blocks_buffer(&device, (bricks_half_size * 2) as u64);
+
+    let first = block_compressor.add_compression_task(
+        variant,
+        &brick_view,
+        brick_texture.width(),
+        brick_half_height,
+        &blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+    let second = block_compressor.add_compression_task(
+        variant,
+        &brick_view,
+        brick_texture.width(),
+        brick_half_height,
+        &blocks,
+        Some(brick_half_height),
+        Some(ByteOffset(bricks_half_size as u64)),
+        TaskOptions::default(),
+    );
+
+    let (tx, rx) = mpsc::channel();
+
+    let first_tx = tx.clone();
+    block_compressor.on_complete(first, move |task| {
+        assert!(task.mapped_blocks().is_none());
+        assert!(task.checksum().is_none());
+        first_tx.send((task.id(), None)).unwrap();
+    });
+    block_compressor.on_complete_with_readback(second, move |task| {
+        let data = task.mapped_blocks().map(|blocks| blocks.to_vec());
+        assert_eq!(task.checksum(), data.as_deref().map(crc32));
+        tx.send((task.id(), data)).unwrap();
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("compute pass"),
+            timestamp_writes: None,
+        });
+
+        block_compressor.compress(&mut pass).unwrap();
+    }
+
+    block_compressor.record_readbacks(&mut encoder);
+    queue.submit([encoder.finish()]);
+    block_compressor.after_submit();
+
+    device.poll(Maintain::Wait);
+
+    let mut completed: Vec<_> = rx.try_iter().collect();
+    completed.sort_by_key(|(id, _)| *id == second);
+
+    assert_eq!(completed.len(), 2, "Not all callbacks were called");
+    assert_eq!(completed[0].0, first);
+    assert_eq!(completed[1].0, second);
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+    let readback = completed[1].1.as_ref().expect("Readback is missing");
+    assert_eq!(readback.as_slice(), &blocks_data[bricks_half_size..]);
+}
+
+#[test]
+fn readbacks_share_one_staging_buffer() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    let brick_half_height = (brick_texture.height() / 2) & !3;
+    let bricks_half_size = variant.blocks_byte_size(brick_texture.width(), brick_half_height);
+
+    let blocks = create_blocks_buffer(&device, (bricks_half_size * 2) as u64);
+
+    let (tx, rx) = mpsc::channel();
+    for half in 0..2 {
+        let id = block_compressor.add_compression_task(
+            variant,
+            &brick_view,
+            brick_texture.width(),
+            brick_half_height,
+            &blocks,
+            Some(half * brick_half_height),
+            Some(ByteOffset((half as usize * bricks_half_size) as u64)),
+            TaskOptions::default(),
+        );
+
+        let tx = tx.clone();
+        block_compressor.on_complete_with_readback(id, move |task| {
+            let data = task.mapped_blocks().map(|blocks| blocks.to_vec());
+            tx.send((half, data)).unwrap();
+        });
+    }
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("compute pass"),
+            timestamp_writes: None,
+        });
+
+        block_compressor.compress(&mut pass).unwrap();
+    }
+
+    // A missing readback panics before any completion is handed over.
+    let missing_readback = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        block_compressor.after_submit();
+    }));
+    assert!(missing_readback.is_err());
+
+    block_compressor.record_readbacks(&mut encoder);
+    queue.submit([encoder.finish()]);
+    block_compressor.after_submit();
+
+    device.poll(Maintain::Wait);
+
+    assert_eq!(block_compressor.memory_stats().staging_buffers_requested, 1);
+
+    let mut completed: Vec<_> = rx.try_iter().collect();
+    completed.sort_by_key(|(half, _)| *half);
+    assert_eq!(completed.len(), 2, "Not all callbacks were called");
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+    for (half, readback) in completed {
+        let readback = readback.expect("Readback is missing");
+        let start = half as usize * bricks_half_size;
+        assert_eq!(
+            readback.as_slice(),
+            &blocks_data[start..start + bricks_half_size]
+        );
+    }
+}
+
+#[test]
+fn multi_task_compression_split_passes() {
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+    block_compressor.set_max_tasks_per_pass(1);
+    assert_eq!(block_compressor.max_tasks_per_pass(), 1);
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    let brick_quarter_height = (brick_texture.height() / 4) & !3;
+    let bricks_quarter_size = variant.blocks_byte_size(brick_texture.width(), brick_quarter_height);
+
+    let blocks = create_blocks_buffer(&device, (bricks_quarter_size * 3) as u64);
+
+    for quarter in 0..3 {
+        block_compressor.add_compression_task(
+            variant,
+            &brick_view,
+            brick_texture.width(),
+            brick_quarter_height,
+            &blocks,
+            Some(brick_quarter_height * quarter),
+            Some(ByteOffset((bricks_quarter_size * quarter as usize) as u64)),
+            TaskOptions::default(),
+        );
+    }
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    block_compressor.compress_split(&mut encoder).unwrap();
+
+    queue.submit([encoder.finish()]);
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+
+    for (quarter, blocks) in blocks_data.chunks(bricks_quarter_size).enumerate() {
+        assert!(
+            !blocks.iter().all(|&data| data == 0),
+            "Quarter {quarter} is empty"
+        );
+    }
+}
+
+#[test]
+fn multi_task_compression_chunks() {
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    let brick_quarter_height = (brick_texture.height() / 4) & !3;
+    let bricks_quarter_size = variant.blocks_byte_size(brick_texture.width(), brick_quarter_height);
+
+    let chunked_blocks = create_blocks_buffer(&device, (bricks_quarter_size * 4) as u64);
+    let reference_blocks = create_blocks_buffer(&device, (bricks_quarter_size * 4) as u64);
+
+    let add_tasks = |block_compressor: &mut GpuBlockCompressor, blocks: &Buffer| {
+        for quarter in 0..4 {
+            block_compressor.add_compression_task(
+                variant,
+                &brick_view,
+                brick_texture.width(),
+                brick_quarter_height,
+                blocks,
+                Some(brick_quarter_height * quarter),
+                Some(ByteOffset((bricks_quarter_size * quarter as usize) as u64)),
+                TaskOptions::default(),
+            );
+        }
+    };
+
+    // Every chunk is submitted separately, like when spreading the batch over several frames.
+    add_tasks(&mut block_compressor, &chunked_blocks);
+    let mut remaining_counts = Vec::new();
+    loop {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        let remaining = {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("chunk"),
+                timestamp_writes: None,
+            });
+            block_compressor.compress_chunk(&mut pass, 3).unwrap()
+        };
+        queue.submit([encoder.finish()]);
+
+        remaining_counts.push(remaining);
+        if remaining == 0 {
+            break;
+        }
+    }
+    assert_eq!(remaining_counts, [1, 0]);
+
+    add_tasks(&mut block_compressor, &reference_blocks);
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    assert_eq!(
+        download_blocks_data(&device, &queue, chunked_blocks),
+        download_blocks_data(&device, &queue, reference_blocks)
+    );
+}
+
+#[derive(Default)]
+struct CountingAllocator {
+    created: AtomicUsize,
+    released: AtomicUsize,
+}
+
+impl BufferAllocator for CountingAllocator {
+    fn create_buffer(&self, device: &Device, descriptor: &BufferDescriptor<'_>) -> Buffer {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        device.create_buffer(descriptor)
+    }
+
+    fn release_buffer(&self, buffer: Buffer) {
+        self.released.fetch_add(1, Ordering::Relaxed);
+        drop(buffer);
+    }
+}
+
+#[test]
+fn custom_allocator_supplies_internal_buffers() {
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (device, queue) = create_wgpu_resources();
+    let allocator = Arc::new(CountingAllocator::default());
+    let mut block_compressor =
+        GpuBlockCompressor::with_allocator(device.clone(), queue.clone(), allocator.clone());
+    let initial_buffers = allocator.created.load(Ordering::Relaxed);
+    assert_ne!(initial_buffers, 0);
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    // More tasks than the initial uniforms and settings buffers hold, so both need to grow.
+    let task_count = 20;
+    let row_size = variant.blocks_byte_size(brick_texture.width(), 4);
+    let blocks = create_blocks_buffer(&device, (row_size * task_count) as u64);
+
+    let mut last = None;
+    for row in 0..task_count {
+        last = Some(block_compressor.add_compression_task(
+            variant,
+            &brick_view,
+            brick_texture.width(),
+            4,
+            &blocks,
+            Some(row as u32 * 4),
+            Some(ByteOffset((row_size * row) as u64)),
+            TaskOptions::default(),
+        ));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    block_compressor.on_complete_with_readback(last.unwrap(), move |task| {
+        tx.send(task.mapped_blocks().map(|blocks| blocks.to_vec()))
+            .unwrap();
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("compute pass"),
+            timestamp_writes: None,
+        });
+        block_compressor.compress(&mut pass).unwrap();
+    }
+    block_compressor.record_readbacks(&mut encoder);
+    queue.submit([encoder.finish()]);
+    block_compressor.after_submit();
+    device.poll(Maintain::Wait);
+
+    let readback = rx.try_recv().unwrap().expect("Readback is missing");
+
+    // The grown uniforms and BC7 settings buffers plus the staging buffer.
+    assert_eq!(
+        allocator.created.load(Ordering::Relaxed),
+        initial_buffers + 3
+    );
+    assert_eq!(allocator.released.load(Ordering::Relaxed), 3);
+
+    drop(block_compressor);
+    assert_eq!(
+        allocator.released.load(Ordering::Relaxed),
+        allocator.created.load(Ordering::Relaxed)
+    );
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+    assert_eq!(
+        readback.as_slice(),
+        &blocks_data[row_size * (task_count - 1)..]
+    );
+}
+
+#[test]
+fn normal_map_compression_from_height() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // The height rises from left to right.
+    let (width, height) = (16, 16);
+    let height_data: Vec<u8> = (0..width * height)
+        .map(|i| (i % width * 16) as u8)
+        .collect();
+    let height_texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("height map"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &height_data,
+    );
+
+    let variant = CompressionVariant::BC5;
+    let blocks = create_blocks_buffer(&device, variant.blocks_byte_size(width, height) as u64);
+
+    block_compressor.add_normal_map_compression_task(
+        &height_texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        NormalMapSettings {
+            strength: 4.0,
+            ..Default::default()
+        },
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    block_compressor.compress_split(&mut encoder).unwrap();
+
+    queue.submit([encoder.finish()]);
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+
+    let mut normals = vec![0; (width * height * 4) as usize];
+    decode::decompress_blocks_as_rgba8(
+        variant,
+        &blocks_data,
+        ImageViewMut::packed(&mut normals, width, height),
+    );
+
+    // Away from the border, the normals tilt to the left and not up or down.
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let texel = &normals[((y * width + x) * 4) as usize..][..2];
+            assert!(
+                (90..=104).contains(&texel[0]),
+                "Normal X at {x}x{y} is {}",
+                texel[0]
+            );
+            assert!(
+                (124..=132).contains(&texel[1]),
+                "Normal Y at {x}x{y} is {}",
+                texel[1]
+            );
+        }
+    }
+}
+
+#[test]
+fn bc4_16bit_source_precision() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // A shallow ramp, whose values mostly fall between two 8-bit steps.
+    let (width, height) = (64, 64);
+    let source: Vec<f32> = (0..width * height)
+        .map(|i| 0.25 + (i % width + i / width) as f32 / 3000.0)
+        .collect();
+    let source_data: Vec<u8> = source
+        .iter()
+        .flat_map(|&value| half::f16::from_f32(value).to_le_bytes())
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("16-bit height map"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R16Float,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &source_data,
+    );
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let variant = CompressionVariant::BC4;
+    let size = variant.blocks_byte_size(width, height) as u64;
+    let regular_blocks = create_blocks_buffer(&device, size);
+    let precise_blocks = create_blocks_buffer(&device, size);
+
+    block_compressor.add_compression_task(
+        variant,
+        &view,
+        width,
+        height,
+        &regular_blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+    block_compressor.add_16bit_compression_task(
+        variant,
+        &view,
+        width,
+        height,
+        &precise_blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    block_compressor.compress_split(&mut encoder).unwrap();
+
+    queue.submit([encoder.finish()]);
+
+    let error = |blocks: Buffer| {
+        let blocks_data = download_blocks_data(&device, &queue, blocks);
+
+        let mut decoded = vec![0; (width * height * 4) as usize];
+        decode::decompress_blocks_as_rgba8(
+            variant,
+            &blocks_data,
+            ImageViewMut::packed(&mut decoded, width, height),
+        );
+
+        source
+            .iter()
+            .zip(decoded.chunks_exact(4))
+            .map(|(&value, texel)| (value * 255.0 - texel[0] as f32).powi(2) as f64)
+            .sum::<f64>()
+    };
+
+    let regular_error = error(regular_blocks);
+    let precise_error = error(precise_blocks);
+
+    assert!(
+        precise_error < regular_error,
+        "16-bit error ({precise_error}) is not lower than the 8-bit error ({regular_error})"
+    );
+}
+
+#[test]
+fn interleaved_compression_matches_packed_source() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (64, 32);
+    let color = testgen::TestPattern::Gradient.generate_rgba8(width, height);
+    let secondary: Vec<u8> = testgen::TestPattern::Noise { seed: 7 }
+        .generate_rgba8(width, height)
+        .chunks_exact(4)
+        .map(|texel| texel[0])
+        .collect();
+
+    let create_texture = |format: TextureFormat, data: &[u8]| {
+        let texture = device.create_texture_with_data(
+            &queue,
+            &TextureDescriptor {
+                label: Some("interleaved source"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            data,
+        );
+        texture.create_view(&TextureViewDescriptor::default())
+    };
+
+    let color_view = create_texture(TextureFormat::Rgba8Unorm, &color);
+    let secondary_view = create_texture(TextureFormat::R8Unorm, &secondary);
+
+    // The channel of the packed texture the secondary texture is read into.
+    for (variant, secondary_channel) in [(CompressionVariant::BC3, 3), (CompressionVariant::BC5, 1)]
+    {
+        let mut packed = color.clone();
+        for (texel, &value) in packed.chunks_exact_mut(4).zip(&secondary) {
+            texel[secondary_channel] = value;
+        }
+        let packed_view = create_texture(TextureFormat::Rgba8Unorm, &packed);
+
+        let size = variant.blocks_byte_size(width, height) as u64;
+        let interleaved_blocks = create_blocks_buffer(&device, size);
+        let packed_blocks = create_blocks_buffer(&device, size);
+
+        block_compressor.add_interleaved_compression_task(
+            variant,
+            &color_view,
+            &secondary_view,
+            width,
+            height,
+            &interleaved_blocks,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+        block_compressor.add_compression_task(
+            variant,
+            &packed_view,
+            width,
+            height,
+            &packed_blocks,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+
+        block_compressor.compress_split(&mut encoder).unwrap();
+
+        queue.submit([encoder.finish()]);
+
+        assert_eq!(
+            download_blocks_data(&device, &queue, interleaved_blocks),
+            download_blocks_data(&device, &queue, packed_blocks),
+            "{variant:?}: interleaved blocks differ from the blocks of the packed source"
+        );
+    }
+}
+
+#[test]
+fn bc6h_rejected_non_finite_blocks_report_errors() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (8, 8);
+    let mut texels: Vec<f32> = (0..width * height * 4)
+        .map(|index| index as f32 / 256.0)
+        .collect();
+    // The red channel of a pixel in the block at (1, 1).
+    texels[(6 * width + 5) as usize * 4] = f32::NAN;
+    let texel_data: Vec<u8> = texels
+        .iter()
+        .flat_map(|&value| half::f16::from_f32(value).to_le_bytes())
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("hdr source"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba16Float,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &texel_data,
+    );
+
+    let settings = BC6HSettings::very_fast().with_non_finite_handling(NonFiniteHandling::Reject);
+    let variant = CompressionVariant::BC6H(settings);
+    let blocks = create_blocks_buffer(&device, variant.blocks_byte_size(width, height) as u64);
+    let errors = create_blocks_buffer(&device, 4 * 4);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            errors_buffer: Some(&errors),
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let errors_data = download_blocks_data(&device, &queue, errors);
+    let errors: &[f32] = bytemuck::cast_slice(&errors_data);
+    assert_eq!(errors[3], f32::INFINITY);
+    assert!(errors[..3].iter().all(|error| error.is_finite()));
+}
+
+#[test]
+fn resource_usages_declare_task_bindings() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (32, 16);
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("source"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let variant = CompressionVariant::BC1;
+    let size = variant.blocks_byte_size(width, height) as u64;
+    let blocks = create_blocks_buffer(&device, 512 + size);
+    let errors = create_blocks_buffer(&device, 32 * 4);
+
+    let task = block_compressor.add_compression_task(
+        variant,
+        &view,
+        width,
+        height,
+        &blocks,
+        None,
+        Some(ByteOffset(512)),
+        TaskOptions {
+            errors_buffer: Some(&errors),
+            ..Default::default()
+        },
+    );
+
+    let usages = block_compressor.resource_usages(task).unwrap();
+    assert_eq!(usages.len(), 3);
+    assert!(matches!(&usages[0], ResourceUsage::ReadTexture(read) if *read == view));
+    assert!(matches!(
+        &usages[1],
+        ResourceUsage::WriteBuffer { buffer, range } if *buffer == blocks && *range == (512..512 + size)
+    ));
+    assert!(matches!(
+        &usages[2],
+        ResourceUsage::WriteBuffer { buffer, range } if *buffer == errors && *range == (0..32 * 4)
+    ));
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    // Dispatched tasks are no longer known.
+    assert!(block_compressor.resource_usages(task).is_none());
+}
+
+#[test]
+fn msaa_resolve_compression() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (16, 16);
+    let multisampled_texture = device.create_texture(&TextureDescriptor {
+        label: Some("multisampled render target"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 4,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("clear"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: &multisampled_texture.create_view(&TextureViewDescriptor::default()),
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(Color {
+                    r: 1.0,
+                    g: 0.5,
+                    b: 0.0,
+                    a: 1.0,
+                }),
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let blocks = create_blocks_buffer(&device, variant.blocks_byte_size(width, height) as u64);
+
+    block_compressor.add_msaa_resolve_compression_task(
+        &mut encoder,
+        variant,
+        &multisampled_texture,
+        &blocks,
+        None,
+        TaskOptions::default(),
+    );
+
+    block_compressor.compress_split(&mut encoder).unwrap();
+
+    queue.submit([encoder.finish()]);
+
+    let blocks_data = download_blocks_data(&device, &queue, blocks);
+
+    let mut resolved = vec![0; (width * height * 4) as usize];
+    decode::decompress_blocks_as_rgba8(
+        variant,
+        &blocks_data,
+        ImageViewMut::packed(&mut resolved, width, height),
+    );
+
+    for texel in resolved.chunks_exact(4) {
+        assert_eq!(texel[0], 255);
+        assert!((126..=129).contains(&texel[1]), "Green is {}", texel[1]);
+        assert_eq!(texel[2], 0);
+    }
+}
+
+#[test]
+fn frame_capture_delivers_blocks() {
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+    let mut capture = FrameCapture::new(device.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+    let (width, height) = (brick_texture.width(), brick_texture.height());
+
+    let (sender, receiver) = mpsc::channel();
+    capture.capture(
+        &mut block_compressor,
+        &brick_view,
+        width,
+        height,
+        variant,
+        move |frame| sender.send(frame).unwrap(),
+    );
+    assert_eq!(capture.pending(), 1);
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+
+    block_compressor.compress_split(&mut encoder).unwrap();
+    block_compressor.record_readbacks(&mut encoder);
+
+    queue.submit([encoder.finish()]);
+    block_compressor.after_submit();
+
+    device.poll(Maintain::Wait);
+    assert_eq!(capture.deliver(), 1);
+    assert_eq!(capture.pending(), 0);
+
+    let frame = receiver.try_recv().expect("Capture was not delivered");
+    assert_eq!((frame.width(), frame.height()), (width, height));
+
+    let blocks = frame.blocks().expect("Blocks were not mapped");
+    assert_eq!(blocks.len(), variant.blocks_byte_size(width, height));
+    assert!(!blocks.iter().all(|&data| data == 0));
+}
+
+#[test]
+fn split_dispatches_match_single_dispatch() {
+    let (device, queue) = create_wgpu_resources();
+
+    let variant = CompressionVariant::BC3;
+    let (texture, _) = read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    // A partial last block row, which is mirrored from the rows above it.
+    let (width, height) = (texture.width(), texture.height() - 2);
+    let size = variant.blocks_byte_size(width, height);
+
+    let compress = |max_blocks_per_dispatch: Option<u32>| {
+        let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+        if let Some(max_blocks_per_dispatch) = max_blocks_per_dispatch {
+            block_compressor.set_max_blocks_per_dispatch(max_blocks_per_dispatch);
+        }
+
+        let blocks = create_blocks_buffer(&device, size as u64);
+        block_compressor.add_compression_task(
+            variant,
+            &view,
+            width,
+            height,
+            &blocks,
+            None,
+            None,
+            TaskOptions {
+                encoding: EncodingOptions {
+                    edge_fill: EdgeFill::Mirror,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        block_compressor.compress_split(&mut encoder).unwrap();
+        queue.submit([encoder.finish()]);
+
+        download_blocks_data(&device, &queue, blocks)
+    };
+
+    let single = compress(None);
+    let split = compress(Some(width.div_ceil(4) * 3 + 1));
+    assert_eq!(single, split);
+}
+
+#[test]
+fn steady_state_frames_do_not_reallocate() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (texture, _) = read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let (width, height) = (texture.width(), texture.height());
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64 * 3);
+
+    let compress_frame = |block_compressor: &mut GpuBlockCompressor| {
+        for index in 0..3 {
+            let offset = (size * index) as u64;
+            block_compressor.add_compression_task(
+                variant,
+                &view,
+                width,
+                height,
+                &blocks,
+                None,
+                Some(ByteOffset(offset)),
+                TaskOptions::default(),
+            );
+        }
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        block_compressor.compress_split(&mut encoder).unwrap();
+        queue.submit([encoder.finish()]);
+    };
+
+    // The first frame sizes the internal buffers for the batch.
+    compress_frame(&mut block_compressor);
+    block_compressor.reset_memory_stats();
+    compress_frame(&mut block_compressor);
+
+    let stats = block_compressor.memory_stats();
+    assert_eq!(stats.compress_calls, 1);
+    assert_eq!(stats.bind_groups_created, 3);
+    assert_eq!(stats.buffers_reallocated, 0);
+    assert_eq!(stats.staging_buffers_requested, 0);
+    assert!(stats.bytes_uploaded > 0);
+}
+
+#[test]
+fn texture_array_batches_share_bind_groups() {
+    let instance = create_instance();
+    let adapter = block_on(instance.request_adapter(&RequestAdapterOptions::default()))
+        .expect("Failed to find an appropriate adapter");
+
+    let features = Features::TEXTURE_BINDING_ARRAY | Features::PARTIALLY_BOUND_BINDING_ARRAY;
+    if !adapter.features().contains(features) {
+        return;
+    }
+
+    let (array_device, array_queue) = block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: Some("texture array device"),
+            required_features: features,
+            required_limits: Limits {
+                max_sampled_textures_per_shader_stage:
+                    adapter.limits().max_sampled_textures_per_shader_stage,
+                ..Limits::default()
+            },
+            ..Default::default()
+        },
+        None,
+    ))
+    .expect("Failed to create device");
+    array_device.on_uncaptured_error(Box::new(error_handler));
+
+    let variant = CompressionVariant::BC1;
+    let compress = |device: Arc<Device>, queue: Arc<Queue>| {
+        let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+        let textures = [
+            BRICK_FILE_PATH,
+            MARBLE_FILE_PATH,
+            BRICK_FILE_PATH,
+            MARBLE_FILE_PATH,
+        ]
+        .map(|path| read_image_and_create_texture(&device, &queue, path, variant).0);
+        let sizes = textures
+            .each_ref()
+            .map(|texture| variant.blocks_byte_size(texture.width(), texture.height()) as u64);
+        let blocks = create_blocks_buffer(&device, sizes.iter().sum());
+
+        let mut offset = 0;
+        for (texture, size) in textures.iter().zip(sizes) {
+            block_compressor.add_compression_task(
+                variant,
+                &texture.create_view(&TextureViewDescriptor::default()),
+                texture.width(),
+                texture.height(),
+                &blocks,
+                None,
+                Some(ByteOffset(offset)),
+                TaskOptions::default(),
+            );
+            offset += size;
+        }
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+            block_compressor.compress(&mut pass).unwrap();
+        }
+        queue.submit([encoder.finish()]);
+
+        let stats = block_compressor.memory_stats();
+        let texture_array_len = block_compressor.texture_array_len();
+        (
+            download_blocks_data(&device, &queue, blocks),
+            stats.bind_groups_created,
+            texture_array_len,
+        )
+    };
+
+    let (array_blocks, array_bind_groups, texture_array_len) =
+        compress(Arc::new(array_device), Arc::new(array_queue));
+    let (device, queue) = create_wgpu_resources();
+    let (blocks, bind_groups, _) = compress(device, queue);
+
+    assert!(texture_array_len >= 2);
+    assert_eq!(bind_groups, 4);
+    assert_eq!(array_bind_groups, 4usize.div_ceil(texture_array_len) as u64);
+    assert_eq!(array_blocks, blocks);
+}
+
+#[test]
+fn block_cache_skips_unchanged_tasks() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+    let cache = BlockCache::new(1 << 20);
+    block_compressor.set_block_cache(Some(cache.clone()));
+
+    let variant = CompressionVariant::BC1;
+    let (texture, _) = read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    let (width, height) = (texture.width(), texture.height());
+
+    let mut compress = || {
+        let blocks = device.create_buffer(&BufferDescriptor {
+            label: Some("blocks buffer"),
+            size: variant.blocks_byte_size(width, height) as u64,
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let completed = Arc::new(AtomicUsize::new(0));
+        let task = block_compressor.add_compression_task(
+            variant,
+            &view,
+            width,
+            height,
+            &blocks,
+            None,
+            None,
+            TaskOptions {
+                content_hash: Some(0xB41C),
+                ..Default::default()
+            },
+        );
+        let counter = completed.clone();
+        block_compressor.on_complete(task, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+            block_compressor.compress(&mut pass).unwrap();
+        }
+        block_compressor.record_readbacks(&mut encoder);
+        queue.submit([encoder.finish()]);
+        block_compressor.after_submit();
+        device.poll(Maintain::Wait);
+
+        assert_eq!(completed.load(Ordering::Relaxed), 1);
+        (
+            download_blocks_data(&device, &queue, blocks),
+            block_compressor.memory_stats().bind_groups_created,
+        )
+    };
+
+    let (compressed, bind_groups) = compress();
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 1, 1));
+
+    let (cached, cached_bind_groups) = compress();
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    assert_eq!(cached_bind_groups, bind_groups);
+    assert_eq!(cached, compressed);
+}
+
+#[test]
+fn validation_errors_are_returned() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let variant = CompressionVariant::BC1;
+    let (texture, _) = read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let (width, height) = (texture.width(), texture.height());
+    let size = variant.blocks_byte_size(width, height) as u64;
+
+    let mut compress = |blocks: &Buffer| {
+        let completed = Arc::new(AtomicUsize::new(0));
+        let id = block_compressor.add_compression_task(
+            variant,
+            &view,
+            width,
+            height,
+            blocks,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+        let counter = completed.clone();
+        block_compressor.on_complete(id, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        let result = block_compressor.compress_validated(|compressor| {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("validated pass"),
+                timestamp_writes: None,
+            });
+            compressor.compress(&mut pass)
+        });
+
+        if result.is_ok() {
+            queue.submit([encoder.finish()]);
+        }
+        block_compressor.after_submit();
+        device.poll(Maintain::Wait);
+
+        (result, completed.load(Ordering::SeqCst))
+    };
+
+    // A destroyed destination buffer fails validation instead of reaching the error handler.
+    let destroyed = create_blocks_buffer(&device, size);
+    destroyed.destroy();
+    let (result, completed) = compress(&destroyed);
+    assert!(
+        matches!(result, Err(GpuError::Validation { .. })),
+        "{result:?}"
+    );
+    assert_eq!(completed, 0);
+
+    // The compressor recovers for the next texture.
+    let (result, completed) = compress(&create_blocks_buffer(&device, size));
+    assert_eq!(result, Ok(()));
+    assert_eq!(completed, 1);
+}
+
+#[cfg(all(feature = "interop", not(target_vendor = "apple")))]
+#[test]
+fn imported_vulkan_buffer_receives_blocks() {
+    use wgpu::hal::{api::Vulkan, BufferUses, Device as _, MemoryFlags};
+
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (32, 32);
+    let rgba_data = testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+
+    let variant = CompressionVariant::RGBA8;
+    let size = variant.blocks_byte_size(width, height) as u64;
+
+    // Stands in for a buffer that an engine allocated with the native API.
+    let hal_buffer = unsafe {
+        device.as_hal::<Vulkan, _, _>(|hal_device| {
+            hal_device.map(|hal_device| {
+                hal_device
+                    .create_buffer(&wgpu::hal::BufferDescriptor {
+                        label: Some("native blocks"),
+                        size,
+                        usage: BufferUses::STORAGE_READ_WRITE | BufferUses::COPY_SRC,
+                        memory_flags: MemoryFlags::empty(),
+                    })
+                    .unwrap()
+            })
+        })
+    };
+    let Some(hal_buffer) = hal_buffer else {
+        // Only devices of Vulkan adapters have a Vulkan device.
+        return;
+    };
+
+    let blocks = unsafe {
+        interop::import_destination_buffer::<Vulkan>(&device, hal_buffer, size, Some("blocks"))
+    };
+    assert!(unsafe { interop::with_hal_buffer::<Vulkan, _>(&blocks, |buffer| buffer.is_some()) });
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("source"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    assert_eq!(download_blocks_data(&device, &queue, blocks), rgba_data);
+}
+
+#[test]
+fn self_check_passes_with_software_limits() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+    block_compressor.set_software_compatibility(true);
+
+    let report = block_compressor.self_check().unwrap();
+    assert!(!report.results.is_empty());
+    assert!(report.settings_mismatches.is_empty(), "{report:?}");
+    assert!(
+        report.passed(),
+        "self-check failed: {:?}",
+        report.failures().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn periodic_compression_alternates_outputs() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+
+    let mut periodic = PeriodicCompressor::new(
+        device.clone(),
+        variant,
+        brick_texture.width(),
+        brick_texture.height(),
+        2,
+    );
+
+    let mut captures = Vec::new();
+    let mut latest = Vec::new();
+    for _ in 0..4 {
+        captures.push(periodic.frame(&mut block_compressor, &brick_view).is_some());
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        block_compressor.compress_split(&mut encoder).unwrap();
+        queue.submit([encoder.finish()]);
+        block_compressor.after_submit();
+
+        device.poll(Maintain::Wait);
+        periodic.poll();
+        latest.push(periodic.latest_frame());
+    }
+
+    assert_eq!(captures, [true, false, true, false]);
+    assert_eq!(latest, [Some(0), Some(0), Some(2), Some(2)]);
+    assert_eq!(periodic.generation(), 2);
+    assert!(!periodic.is_pending());
+
+    let [first, second] = periodic.outputs().clone();
+    let first = download_blocks_data(&device, &queue, first);
+    let second = download_blocks_data(&device, &queue, second);
+    assert!(!first.iter().all(|&data| data == 0));
+    assert_eq!(first, second);
+}
+
+#[test]
+fn hybrid_output_is_reproducible() {
+    let variant = CompressionVariant::BC7(BC7Settings::opaque_ultra_fast());
+    let (device, queue) = create_wgpu_resources();
+
+    let images: Vec<(Vec<u8>, u32, u32)> = [(256, 256), (128, 64), (64, 64), (32, 128)]
+        .into_iter()
+        .enumerate()
+        .map(|(seed, (width, height))| {
+            let pattern = testgen::TestPattern::all(seed as u64)[seed % 4];
+            (pattern.generate_rgba8(width, height), width, height)
+        })
+        .collect();
+
+    let mut blocks_offset = 0;
+    let tasks: Vec<HybridTask> = images
+        .iter()
+        .map(|(data, width, height)| {
+            let task = HybridTask {
+                variant,
+                image: ImageView::packed(data, *width, *height),
+                blocks_offset,
+            };
+            blocks_offset += variant.blocks_byte_size(*width, *height);
+            task
+        })
+        .collect();
+
+    let run = |determinism: EncoderDeterminism| {
+        let mut scheduler = HybridScheduler::new(device.clone(), queue.clone());
+        scheduler.set_determinism(determinism);
+        scheduler.set_throughput(2.0, 1.0);
+
+        let mut runs = Vec::new();
+        for _ in 0..3 {
+            let mut blocks_buffer = vec![0; blocks_offset];
+            scheduler.compress(&tasks, &mut blocks_buffer);
+            runs.push(blocks_buffer);
+        }
+        assert_eq!(scheduler.throughput(), (Some(2.0), Some(1.0)));
+        runs
+    };
+
+    for blocks in run(EncoderDeterminism::Reproducible).windows(2) {
+        assert_eq!(blocks[0], blocks[1]);
+    }
+
+    let mut cpu_blocks = vec![0; blocks_offset];
+    for task in &tasks {
+        let size = task
+            .variant
+            .blocks_byte_size(task.image.width(), task.image.height());
+        let offset = task.blocks_offset;
+        encode::compress_rgba8(
+            task.variant,
+            task.image,
+            &mut cpu_blocks[offset..offset + size],
+        );
+    }
+
+    for blocks in run(EncoderDeterminism::BitExact) {
+        assert_eq!(blocks, cpu_blocks);
+    }
+}
+
+#[test]
+fn bc2_dithered_alpha_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (64, 16);
+    let rgba_data: Vec<u8> = (0..width * height)
+        .flat_map(|index| [128, 64, 32, (index % width * 2 + index / width) as u8])
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("alpha ramp"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let variant = CompressionVariant::BC2;
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                dither_alpha: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+    let mut cpu_blocks = vec![0; size];
+    encode::compress_rgba8_bc2_dithered(
+        ImageView::packed(&rgba_data, width, height),
+        &mut cpu_blocks,
+    );
+
+    // The dither never lands exactly on a quantization step, so the alpha is bit exact.
+    for (gpu, cpu) in gpu_blocks.chunks_exact(16).zip(cpu_blocks.chunks_exact(16)) {
+        assert_eq!(gpu[..8], cpu[..8]);
+    }
+}
+
+#[test]
+fn bc1_punch_through_alpha_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (32, 32);
+    let rgba_data: Vec<u8> = (0..width * height)
+        .flat_map(|index| {
+            let (x, y) = (index % width, index / width);
+            let alpha = if x < 8 || (x + y) % 3 == 0 { 255 } else { 0 };
+            [(x * 8) as u8, (y * 8) as u8, 96, alpha]
+        })
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("leaves"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let variant = CompressionVariant::BC1;
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                punch_through_alpha: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+    let mut cpu_blocks = vec![0; size];
+    encode::compress_rgba8_bc1_punch_through(
+        ImageView::packed(&rgba_data, width, height),
+        &mut cpu_blocks,
+    );
+
+    // Both select the same mode, so the transparent pixels are identical.
+    let decode = |blocks: &[u8]| {
+        let mut decoded = vec![0; rgba_data.len()];
+        decode::decompress_blocks_as_rgba8(
+            variant,
+            blocks,
+            ImageViewMut::packed(&mut decoded, width, height),
+        );
+        decoded
+    };
+    let (gpu, cpu) = (decode(&gpu_blocks), decode(&cpu_blocks));
+    for ((gpu, cpu), source) in gpu
+        .chunks_exact(4)
+        .zip(cpu.chunks_exact(4))
+        .zip(rgba_data.chunks_exact(4))
+    {
+        assert_eq!(gpu[3], cpu[3]);
+        assert_eq!(gpu[3], source[3]);
+    }
+}
+
+#[test]
+fn bc4_sdf_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // The signed distance field of a circle, with the edge at 128.
+    let (width, height) = (64, 64);
+    let rgba_data: Vec<u8> = (0..width * height)
+        .flat_map(|index| {
+            let (x, y) = ((index % width) as f32 - 31.5, (index / width) as f32 - 31.5);
+            let distance = 20.0 - (x * x + y * y).sqrt();
+            [(128.0 + distance * 12.0).clamp(0.0, 255.0) as u8, 0, 0, 255]
+        })
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("circle sdf"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let variant = CompressionVariant::BC4;
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                sdf_pivot: Some(128),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+    let mut cpu_blocks = vec![0; size];
+    encode::compress_rgba8_bc4_sdf(
+        ImageView::packed(&rgba_data, width, height),
+        128,
+        &mut cpu_blocks,
+    );
+
+    let psnr = psnr(
+        &decode_rgba8(variant, &gpu_blocks, width, height),
+        &decode_rgba8(variant, &cpu_blocks, width, height),
+    );
+    assert!(
+        psnr >= MIN_BACKEND_PSNR,
+        "SDF blocks differ from the CPU encoder ({psnr:.2} dB)"
+    );
+}
+
+#[test]
+fn bc7_mode45_scalar_channel_restricts_rotation() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // Smooth color gradients with an unrelated alpha, which favor modes 4 and 5.
+    let (width, height) = (64, 64);
+    let rgba_data: Vec<u8> = (0..width * height)
+        .flat_map(|index| {
+            let (x, y) = (index % width, index / width);
+            [
+                (x * 4) as u8,
+                (y * 4) as u8,
+                ((x + y) * 2) as u8,
+                (x * y) as u8,
+            ]
+        })
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("gradients"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let settings =
+        BC7Settings::alpha_basic().with_mode45_scalar_channel(Mode45ScalarChannel::Green);
+    let variant = CompressionVariant::BC7(settings);
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+    for block in gpu_blocks.chunks_exact(16) {
+        let mode = block[0].trailing_zeros();
+        if mode == 4 || mode == 5 {
+            // Rotation 2 swaps alpha and green.
+            assert_eq!((block[0] >> (mode + 1)) & 3, 2);
+        }
+    }
+}
+
+#[test]
+fn bc7_neighbor_seeding_keeps_seeds() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (72, 40);
+    let rgba_data = testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("alpha ramp"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    // Every seed is kept, so the odd block columns repeat the mode of the block to their left.
+    let settings = BC7Settings::alpha_basic().with_neighbor_seeding(Some(f32::MAX));
+    let variant = CompressionVariant::BC7(settings);
+    let size = variant.blocks_byte_size(width, height);
+    let seeded_blocks = create_blocks_buffer(&device, size as u64);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    for (settings, blocks) in [
+        (settings, &seeded_blocks),
+        (BC7Settings::alpha_basic(), &blocks),
+    ] {
+        block_compressor.add_compression_task(
+            CompressionVariant::BC7(settings),
+            &view,
+            width,
+            height,
+            blocks,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+    }
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let seeded_blocks = download_blocks_data(&device, &queue, seeded_blocks);
+    let blocks = download_blocks_data(&device, &queue, blocks);
+
+    for row in seeded_blocks.chunks_exact(16 * width as usize / 4) {
+        for pair in row.chunks_exact(32) {
+            assert_eq!(pair[0].trailing_zeros(), pair[16].trailing_zeros());
+        }
+    }
+
+    // The unseeded blocks of the even columns are searched as before.
+    for (seeded, unseeded) in seeded_blocks.chunks_exact(32).zip(blocks.chunks_exact(32)) {
+        assert_eq!(seeded[..16], unseeded[..16]);
+    }
+}
+
+#[test]
+fn rgba8_passthrough_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // Not a multiple of 4, so the rows are padded. The small dispatch limit splits the task.
+    let (width, height) = (45, 30);
+    let rgba_data = testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("passthrough source"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let variant = CompressionVariant::RGBA8;
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.set_max_blocks_per_dispatch(24);
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                edge_fill: EdgeFill::Mirror,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_texels = download_blocks_data(&device, &queue, blocks);
+
+    let mut cpu_texels = vec![0; size];
+    encode::compress_rgba8_padded(
+        variant,
+        ImageView::packed(&rgba_data, width, height),
+        EdgeFill::Mirror,
+        &mut cpu_texels,
+    );
+
+    assert_eq!(gpu_texels, cpu_texels);
+}
+
+#[test]
+fn srgb_views_are_encoded_back_to_srgb() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (width, height) = (32, 32);
+    let rgba_data = testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+
+    // The texture has no non-sRGB view format, so only sRGB views can be created.
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("srgb source"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &rgba_data,
+    );
+
+    let variant = CompressionVariant::RGBA8;
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                source_color_space: SourceColorSpace::Srgb,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let texels = download_blocks_data(&device, &queue, blocks);
+
+    // The conversion to linear and back can round differently than the stored values.
+    for (index, (texel, expected)) in texels.iter().zip(&rgba_data).enumerate() {
+        assert!(
+            texel.abs_diff(*expected) <= 1,
+            "texel {} differs: {texel} instead of {expected}",
+            index / 4
+        );
+    }
+}
+
+#[test]
+fn bc6h_hdr_remap_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    // A gradient up to 60000, which is remapped into [0, 1].
+    let (width, height) = (32, 32);
+    let rgba_data: Vec<f32> = (0..width * height)
+        .flat_map(|index| {
+            let value = 60000.0 * index as f32 / (width * height) as f32;
+            [value, value * 0.5, 60000.0 - value, 1.0]
+        })
+        .collect();
+    let half_data: Vec<half::f16> = rgba_data.iter().copied().map(half::f16::from_f32).collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("hdr gradient"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba16Float,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        bytemuck::cast_slice(&half_data),
+    );
+
+    let variant = CompressionVariant::BC6H(BC6HSettings::basic());
+    let remap = HdrRemap::from_range(0.0, 60000.0);
+    let size = variant.blocks_byte_size(width, height);
+    let blocks = create_blocks_buffer(&device, size as u64);
+
+    let id = block_compressor.add_compression_task(
+        variant,
+        &texture.create_view(&TextureViewDescriptor::default()),
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions {
+            encoding: EncodingOptions {
+                hdr_remap: remap,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (sender, receiver) = mpsc::channel();
+    block_compressor.on_complete(id, move |task| {
+        sender.send(task.hdr_restore()).unwrap();
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+    block_compressor.after_submit();
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+    assert_eq!(receiver.recv().unwrap(), remap.inverse());
+
+    let mut cpu_blocks = vec![0; size];
+    let restore = encode::compress_rgba32f_remapped(
+        variant,
+        ImageView::packed(&rgba_data, width, height),
+        remap,
+        &mut cpu_blocks,
+    );
+    assert_eq!(restore, remap.inverse());
+
+    let psnr = psnr(
+        &decode_rgba8(variant, &gpu_blocks, width, height),
+        &decode_rgba8(variant, &cpu_blocks, width, height),
+    );
+    assert!(
+        psnr >= MIN_BACKEND_PSNR,
+        "remapped HDR blocks differ from the CPU encoder ({psnr:.2} dB)"
+    );
+}
+
+#[test]
+fn mip_level_tasks_compute_level_sizes() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let variant = CompressionVariant::BC1;
+    let (width, height, mip_level_count) = (64, 48, 4);
+    let levels: Vec<Vec<u8>> = (0..mip_level_count)
+        .map(|mip_level| {
+            testgen::TestPattern::AlphaRamp.generate_rgba8(width >> mip_level, height >> mip_level)
+        })
+        .collect();
+
+    let texture = device.create_texture_with_data(
+        &queue,
+        &TextureDescriptor {
+            label: Some("mip chain"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &levels.concat(),
+    );
+
+    let level_sizes: Vec<usize> = (0..mip_level_count)
+        .map(|mip_level| variant.blocks_byte_size(width >> mip_level, height >> mip_level))
+        .collect();
+    let blocks = create_blocks_buffer(&device, level_sizes.iter().sum::<usize>() as u64);
+
+    let mut offset = 0;
+    for (mip_level, size) in level_sizes.iter().enumerate() {
+        block_compressor.add_mip_compression_task(
+            variant,
+            &texture,
+            mip_level as u32,
+            &blocks,
+            Some(ByteOffset(offset as u64)),
+            TaskOptions::default(),
+        );
+        offset += size;
+    }
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    queue.submit([encoder.finish()]);
+
+    let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+    let mut offset = 0;
+    for (mip_level, (level, size)) in levels.iter().zip(level_sizes).enumerate() {
+        let (level_width, level_height) = (width >> mip_level, height >> mip_level);
+        let mut cpu_blocks = vec![0; size];
+        encode::compress_rgba8_padded(
+            variant,
+            ImageView::packed(level, level_width, level_height),
+            EdgeFill::ClampToEdge,
+            &mut cpu_blocks,
+        );
+
+        let (padded_width, padded_height) = (
+            level_width.next_multiple_of(4),
+            level_height.next_multiple_of(4),
+        );
+        let psnr = psnr(
+            &decode_rgba8(
+                variant,
+                &gpu_blocks[offset..offset + size],
+                padded_width,
+                padded_height,
+            ),
+            &decode_rgba8(variant, &cpu_blocks, padded_width, padded_height),
+        );
+        assert!(
+            psnr >= MIN_BACKEND_PSNR,
+            "mip level {mip_level} differs from the CPU encoder ({psnr:.2} dB)"
+        );
+
+        offset += size;
+    }
+}
+
+#[test]
+fn sparse_readback_matches_full_download() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+    let (width, height) = (brick_texture.width(), brick_texture.height());
+
+    let blocks = create_blocks_buffer(&device, variant.blocks_byte_size(width, height) as u64);
+    block_compressor.add_compression_task(
+        variant,
+        &brick_view,
+        width,
+        height,
+        &blocks,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+
+    // A dirty tile in the middle of the texture and the first row of blocks.
+    let tile = tiling::tiles(variant, width, height, 64, 32)
+        .nth(5)
+        .unwrap();
+    let ranges: Vec<_> = std::iter::once(0..variant.blocks_byte_size(width, 4) as u64)
+        .chain(tile.row_byte_ranges())
+        .collect();
+    let readback = SparseReadback::new(&device, ranges.iter().cloned());
+    assert_eq!(
+        readback.size(),
+        ranges
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum::<u64>()
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    block_compressor.compress_split(&mut encoder).unwrap();
+    readback.record(&blocks, &mut encoder);
+    queue.submit([encoder.finish()]);
+
+    readback.request_map();
+    device.poll(Maintain::Wait);
+    assert!(readback.is_ready());
+
+    let expected = download_blocks_data(&device, &queue, blocks);
+    let mut sparse = vec![0; expected.len()];
+    assert!(readback.scatter_into(&mut sparse));
+    assert!(!readback.is_ready());
+
+    for range in ranges {
+        let range = range.start as usize..range.end as usize;
+        assert_eq!(sparse[range.clone()], expected[range]);
+    }
+}
+
+#[test]
+fn shared_pipelines_compress_on_threads() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let shared = Arc::new(SharedPipelines::new(device.clone()));
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let (width, height) = (brick_texture.width(), brick_texture.height());
+    let size = variant.blocks_byte_size(width, height) as u64;
+
+    let outputs: Vec<Vec<u8>> = std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mut block_compressor =
+                    GpuBlockCompressor::with_shared_pipelines(shared.clone(), queue.clone());
+                assert!(Arc::ptr_eq(block_compressor.shared_pipelines(), &shared));
+
+                let (device, queue, brick_texture) = (&device, &queue, &brick_texture);
+                scope.spawn(move || {
+                    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+                    let blocks = create_blocks_buffer(device, size);
+                    block_compressor.add_compression_task(
+                        variant,
+                        &brick_view,
+                        width,
+                        height,
+                        &blocks,
+                        None,
+                        None,
+                        TaskOptions::default(),
+                    );
+
+                    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("command encoder"),
+                    });
+                    block_compressor.compress_split(&mut encoder).unwrap();
+                    queue.submit([encoder.finish()]);
+
+                    download_blocks_data(device, queue, blocks)
+                })
+            })
+            .collect();
+
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect()
+    });
+
+    assert!(outputs.iter().all(|output| output == &outputs[0]));
+    assert!(!outputs[0].iter().all(|&data| data == 0));
+}
+
+#[test]
+fn record_single_matches_compress() {
+    let variant = CompressionVariant::BC1;
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let (brick_texture, _) =
+        read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+    let brick_view = brick_texture.create_view(&TextureViewDescriptor::default());
+    let (width, height) = (brick_texture.width(), brick_texture.height());
+    let size = variant.blocks_byte_size(width, height) as u64;
+
+    // The uniforms are placed at the second aligned slot of the buffer of the integrator.
+    let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+    let mut uniforms_data = vec![0; alignment + DispatchUniforms::SIZE];
+    uniforms_data[alignment..].copy_from_slice(&DispatchUniforms::new(width, height).to_bytes());
+    let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("integrator uniforms"),
+        contents: &uniforms_data,
+        usage: BufferUsages::UNIFORM,
+    });
+    let errors = create_blocks_buffer(&device, 4);
+    let blocks = create_blocks_buffer(&device, size);
+
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("integrator bind group"),
+        layout: block_compressor
+            .shared_pipelines()
+            .bind_group_layout(variant),
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&brick_view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: blocks.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &uniforms,
+                    offset: 0,
+                    size: std::num::NonZeroU64::new(DispatchUniforms::SIZE as u64),
+                }),
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: errors.as_entire_binding(),
+            },
+        ],
+    });
+
+    let expected = create_blocks_buffer(&device, size);
+    block_compressor.add_compression_task(
+        variant,
+        &brick_view,
+        width,
+        height,
+        &expected,
+        None,
+        None,
+        TaskOptions::default(),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+        let [workgroup_width, workgroup_height, _] =
+            diagnostics::dispatch_report(variant, width, height).workgroup_count;
+        block_compressor.record_single(
+            &mut pass,
+            variant,
+            &bind_group,
+            alignment as u32,
+            0,
+            [workgroup_width, workgroup_height],
+        );
+        block_compressor.compress(&mut pass).unwrap();
+    }
+    queue.submit([encoder.finish()]);
+
+    let blocks = download_blocks_data(&device, &queue, blocks);
+    assert_eq!(blocks, download_blocks_data(&device, &queue, expected));
+    assert!(!blocks.iter().all(|&data| data == 0));
+}
+
+#[test]
+fn preset_evaluation_matches_cpu() {
+    let (device, queue) = create_wgpu_resources();
+    let mut block_compressor = GpuBlockCompressor::new(device, queue);
+
+    let (width, height) = (64, 64);
+    let rgba_data = testgen::TestPattern::AlphaRamp.generate_rgba8(width, height);
+    let image = ImageView::packed(&rgba_data, width, height);
+    let variant = CompressionVariant::BC7(BC7Settings::alpha_basic());
+
+    let gpu_reports = block_compressor.evaluate_presets(image, variant).unwrap();
+    let cpu_reports = encode::evaluate_presets(image, variant);
+
+    assert_eq!(gpu_reports.len(), cpu_reports.len());
+    for (gpu, cpu) in gpu_reports.iter().zip(&cpu_reports) {
+        assert_eq!(gpu.variant, cpu.variant);
+        assert!(
+            (gpu.psnr - cpu.psnr).abs() < 1.0,
+            "{}: {:.2} dB on the GPU and {:.2} dB on the CPU",
+            gpu.name,
+            gpu.psnr,
+            cpu.psnr,
+        );
+    }
+}
+
+/// The minimal PSNR between the decoded GPU and CPU results. Both sides run the same algorithms,
+/// so only small differences of the floating point math are expected.
+const MIN_BACKEND_PSNR: f64 = 40.0;
+
+fn decode_rgba8(variant: CompressionVariant, blocks: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut decoded = vec![0; (width * height * 4) as usize];
+    decode::decompress_blocks_as_rgba8(
+        variant,
+        blocks,
+        ImageViewMut::packed(&mut decoded, width, height),
+    );
+    decoded
+}
+
+fn psnr(a: &[u8], b: &[u8]) -> f64 {
+    let mse = a
+        .iter()
+        .zip(b)
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum::<f64>()
+        / a.len() as f64;
+
+    10.0 * (255.0 * 255.0 / mse.max(1e-9)).log10()
+}
+
+#[test]
+fn all_backends_match_cpu() {
+    let variants = [
+        CompressionVariant::BC1,
+        CompressionVariant::BC3,
+        CompressionVariant::BC4,
+        CompressionVariant::BC5,
+        CompressionVariant::BC7(BC7Settings::opaque_ultra_fast()),
+        CompressionVariant::BC7(BC7Settings::opaque_ultra_fast().with_linear_error(true)),
+    ];
+
+    let instance = create_instance();
+    let count = block_on(for_each_backend(&instance, Backends::all(), |backend| {
+        let (device, queue) = (backend.device, backend.queue);
+        device.on_uncaptured_error(Box::new(error_handler));
+
+        let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+        for variant in variants {
+            let (texture, rgba_data) =
+                read_image_and_create_texture(&device, &queue, BRICK_FILE_PATH, variant);
+            let (width, height) = (texture.width(), texture.height());
+            let size = variant.blocks_byte_size(width, height);
+
+            let blocks = create_blocks_buffer(&device, size as u64);
+            block_compressor.add_compression_task(
+                variant,
+                &texture.create_view(&TextureViewDescriptor::default()),
+                width,
+                height,
+                &blocks,
+                None,
+                None,
+                TaskOptions::default(),
+            );
+
+            let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("command encoder"),
+            });
+            block_compressor.compress_split(&mut encoder).unwrap();
+            queue.submit([encoder.finish()]);
+
+            let gpu_blocks = download_blocks_data(&device, &queue, blocks);
+
+            let mut cpu_blocks = vec![0; size];
+            encode::compress_rgba8(
+                variant,
+                ImageView::packed(&rgba_data, width, height),
+                &mut cpu_blocks,
+            );
+
+            let psnr = psnr(
+                &decode_rgba8(variant, &gpu_blocks, width, height),
+                &decode_rgba8(variant, &cpu_blocks, width, height),
+            );
+
+            assert!(
+                psnr >= MIN_BACKEND_PSNR,
+                "{} ({:?}): {variant:?} differs from the CPU encoder ({psnr:.2} dB)",
+                backend.info.name,
+                backend.info.backend,
+            );
+        }
+    }));
+
+    assert!(count > 0, "no adapter was found");
+}
+
+#[test]
+fn bc1_to_5_blocks_match_across_backends() {
+    const BRICK_ALPHA_FILE_PATH: &str = "tests/images/brick-alpha.png";
+
+    // The variant, the image and whether the punch-through mode or the alpha dithering is used.
+    let tasks = [
+        (CompressionVariant::BC1, BRICK_FILE_PATH, false),
+        (CompressionVariant::BC1, BRICK_ALPHA_FILE_PATH, true),
+        (CompressionVariant::BC2, BRICK_ALPHA_FILE_PATH, true),
+        (CompressionVariant::BC3, BRICK_ALPHA_FILE_PATH, false),
+        (CompressionVariant::BC4, MARBLE_FILE_PATH, false),
+        (CompressionVariant::BC5, MARBLE_FILE_PATH, false),
+    ];
+
+    let instance = create_instance();
+    let mut outputs = Vec::new();
+    let count = block_on(for_each_backend(&instance, Backends::all(), |backend| {
+        let (device, queue) = (backend.device, backend.queue);
+        device.on_uncaptured_error(Box::new(error_handler));
+
+        let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+        let blocks: Vec<Vec<u8>> = tasks
+            .iter()
+            .map(|&(variant, path, option)| {
+                let (texture, _) = read_image_and_create_texture(&device, &queue, path, variant);
+                let (width, height) = (texture.width(), texture.height());
+                let size = variant.blocks_byte_size(width, height);
+
+                let blocks = create_blocks_buffer(&device, size as u64);
+                block_compressor.add_compression_task(
+                    variant,
+                    &texture.create_view(&TextureViewDescriptor::default()),
+                    width,
+                    height,
+                    &blocks,
+                    None,
+                    None,
+                    TaskOptions {
+                        encoding: EncodingOptions {
+                            punch_through_alpha: option,
+                            dither_alpha: option,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                );
+
+                let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("command encoder"),
+                });
+                block_compressor.compress_split(&mut encoder).unwrap();
+                queue.submit([encoder.finish()]);
+
+                download_blocks_data(&device, &queue, blocks)
+            })
+            .collect();
+
+        outputs.push((backend.info, blocks));
+    }));
+
+    assert!(count > 0, "no adapter was found");
+
+    let (reference_info, reference) = &outputs[0];
+    for (info, blocks) in &outputs[1..] {
+        for ((variant, path, _), (blocks, reference)) in
+            tasks.iter().zip(blocks.iter().zip(reference))
+        {
+            assert!(
+                blocks == reference,
+                "{} ({:?}) and {} ({:?}) differ for {variant:?} of {path}",
+                info.name,
+                info.backend,
+                reference_info.name,
+                reference_info.backend,
+            );
+        }
+    }
+}
+
+/// Reads the texels of a BC texture back with a compute pass, so the texture units of the GPU
+/// decode the blocks.
+const HARDWARE_DECODE_SHADER: &str = r#"
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> texels: array<vec4<f32>>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(source);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    texels[id.y * size.x + id.x] = textureLoad(source, id.xy, 0);
+}
+"#;
+
+/// The largest difference between the hardware and the CPU decoders, relative to values above
+/// one. The D3D11 spec allows BC1 to BC3 colors to be interpolated with a lower precision, which
+/// some vendors do. BC6H decodes to half floats on both sides.
+const HARDWARE_DECODE_TOLERANCE: f32 = 3.0 / 255.0;
+
+/// Creates a device that can sample BC textures, or returns `None` if the adapter doesn't
+/// support `TEXTURE_COMPRESSION_BC`.
+fn create_bc_texture_device() -> Option<(Arc<Device>, Arc<Queue>)> {
+    let instance = create_instance();
+    let adapter = block_on(instance.request_adapter(&RequestAdapterOptions::default()))
+        .expect("Failed to find an appropriate adapter");
+
+    if !adapter
+        .features()
+        .contains(Features::TEXTURE_COMPRESSION_BC)
+    {
+        return None;
+    }
+
+    let (device, queue) = block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: Some("BC texture device"),
+            required_features: Features::TEXTURE_COMPRESSION_BC,
+            ..Default::default()
+        },
+        None,
+    ))
+    .expect("Failed to create device");
+    device.on_uncaptured_error(Box::new(error_handler));
+
+    Some((Arc::new(device), Arc::new(queue)))
+}
+
+fn sample_bc_texture(
+    device: &Device,
+    queue: &Queue,
+    variant: CompressionVariant,
+    blocks_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    let format = match variant {
+        CompressionVariant::BC1 => TextureFormat::Bc1RgbaUnorm,
+        CompressionVariant::BC2 => TextureFormat::Bc2RgbaUnorm,
+        CompressionVariant::BC3 => TextureFormat::Bc3RgbaUnorm,
+        CompressionVariant::BC4 => TextureFormat::Bc4RUnorm,
+        CompressionVariant::BC5 => TextureFormat::Bc5RgUnorm,
+        CompressionVariant::BC6H(..) => TextureFormat::Bc6hRgbUfloat,
+        CompressionVariant::BC7(..) => TextureFormat::Bc7RgbaUnorm,
+        CompressionVariant::RGBA8 => panic!("RGBA8 is not block compressed"),
+    };
+
+    let texture = device.create_texture_with_data(
+        queue,
+        &TextureDescriptor {
+            label: Some("BC texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        blocks_data,
+    );
+
+    let texels = device.create_buffer(&BufferDescriptor {
+        label: Some("decoded texels"),
+        size: width as u64 * height as u64 * 16,
+        usage: BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("hardware decode"),
+        source: ShaderSource::Wgsl(HARDWARE_DECODE_SHADER.into()),
+    });
+    let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: Some("hardware decode"),
+        layout: None,
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: None,
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("hardware decode"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(
+                    &texture.create_view(&TextureViewDescriptor::default()),
+                ),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: texels.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("command encoder"),
+    });
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("hardware decode"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(width.div_ceil(8), height.div_ceil(8), 1);
+    }
+    queue.submit([encoder.finish()]);
+
+    bytemuck::pod_collect_to_vec(&download_blocks_data(device, queue, texels))
+}
+
+#[test]
+fn hardware_decode_matches_cpu_decoders() {
+    let Some((device, queue)) = create_bc_texture_device() else {
+        // Only adapters that can sample BC textures decode the blocks in hardware.
+        return;
+    };
+    let mut block_compressor = GpuBlockCompressor::new(device.clone(), queue.clone());
+
+    let variants = [
+        CompressionVariant::BC1,
+        CompressionVariant::BC2,
+        CompressionVariant::BC3,
+        CompressionVariant::BC4,
+        CompressionVariant::BC5,
+        CompressionVariant::BC6H(BC6HSettings::very_fast()),
+        CompressionVariant::BC7(BC7Settings::alpha_very_fast()),
+    ];
+
+    for variant in variants {
+        let (texture, _) =
+            read_image_and_create_texture(&device, &queue, MARBLE_FILE_PATH, variant);
+        let (width, height) = (texture.width(), texture.height());
+        let size = variant.blocks_byte_size(width, height);
+
+        let blocks = create_blocks_buffer(&device, size as u64);
+        block_compressor.add_compression_task(
+            variant,
+            &texture.create_view(&TextureViewDescriptor::default()),
+            width,
+            height,
+            &blocks,
+            None,
+            None,
+            TaskOptions::default(),
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        block_compressor.compress_split(&mut encoder).unwrap();
+        queue.submit([encoder.finish()]);
+
+        let blocks_data = download_blocks_data(&device, &queue, blocks);
+        let hardware = sample_bc_texture(&device, &queue, variant, &blocks_data, width, height);
+
+        let mut expected = vec![0.0; (width * height * 4) as usize];
+        if let CompressionVariant::BC6H(..) = variant {
+            decode::decompress_blocks_as_rgba32f(
+                variant,
+                &blocks_data,
+                ImageViewMut::packed(&mut expected, width, height),
+            );
+        } else {
+            let mut decoded = vec![0; expected.len()];
+            decode::decompress_blocks_as_rgba8_with_options(
+                variant,
+                &blocks_data,
+                ImageViewMut::packed(&mut decoded, width, height),
+                &decode::DecodeOptions {
+                    d3d11_exact: true,
+                    ..Default::default()
+                },
+            );
+            for (expected, decoded) in expected.iter_mut().zip(decoded) {
+                *expected = decoded as f32 / 255.0;
+            }
+        }
+
+        // The hardware fills the channels the variant doesn't store.
+        let channels = match variant {
+            CompressionVariant::BC4 => 1,
+            CompressionVariant::BC5 => 2,
+            CompressionVariant::BC6H(..) => 3,
+            _ => 4,
+        };
+
+        for (index, (&hardware, &expected)) in hardware.iter().zip(&expected).enumerate() {
+            if index % 4 >= channels {
+                continue;
+            }
+
+            let texel = index as u32 / 4;
+            assert!(
+                (hardware - expected).abs() <= HARDWARE_DECODE_TOLERANCE * expected.abs().max(1.0),
+                "{variant:?}: channel {} of texel ({}, {}) is {hardware} in hardware and \
+                 {expected} on the CPU",
+                index % 4,
+                texel % width,
+                texel / width,
+            );
+        }
+    }
+}